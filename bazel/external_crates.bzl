@@ -171,6 +171,7 @@ def external_crates_repository(name):
             "rand_chacha": crate.spec(version = "=0.2.2"),
             "rand_core": crate.spec(version = "=0.5.1"),
             "regex": crate.spec(version = "=1.5.6"),
+            "reqwest": crate.spec(version = "=0.11.8"),
             "rustversion": crate.spec(version = "=1.0.2"),
             "scoped_threadpool": crate.spec(version = "=0.1.0"),
             "serde_bytes": crate.spec(version = "=0.11.6"),
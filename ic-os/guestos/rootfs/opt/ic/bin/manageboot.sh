@@ -112,6 +112,12 @@ Usage:
       has been confirmed previously already (safe to call as many times
       as you like, will not iniate I/O if nothing to be written).
 
+    rollback
+      Reverts a newly installed upgrade that has not been confirmed yet by
+      rebooting immediately, so that the bootloader falls back to the
+      previous installation. Fails if the current system has already been
+      confirmed.
+
     current
       Output currently booted system (A or B) on stdout and exit.
 
@@ -180,7 +186,9 @@ case "${ACTION}" in
 
     upgrade-commit) ;&
 
-    confirm)
+    confirm) ;&
+
+    rollback)
         # Re-execute script as root (unless root already) for operations that
         # require privilege.
         if [ $(id -u) != 0 ]; then
@@ -239,6 +247,16 @@ case "${ACTION}" in
             write_grubenv "${GRUBENV_FILE}"
         fi
         ;;
+    rollback)
+        if [ "${boot_cycle}" != "failsafe_check" ]; then
+            echo "Cannot roll back: present system is already committed as stable." >&2
+            exit 1
+        fi
+
+        # The bootloader reverts to the other system on next boot.
+        sync
+        reboot
+        ;;
     current)
         echo "${CURRENT_ALTERNATIVE}"
         ;;
@@ -27,6 +27,7 @@ use ic_config::embedders::Config as EmbeddersConfig;
 use ic_embedders::wasm_utils::instrumentation::InstrumentationOutput;
 use ic_embedders::wasm_utils::validation::WasmValidationDetails;
 use ic_embedders::{
+    compilation_cache::CompilationCache,
     wasm_executor::WasmStateChanges,
     wasm_utils::{
         decoding::decode_wasm,
@@ -40,6 +41,7 @@ use ic_logger::replica_logger::no_op_logger;
 use ic_replicated_state::page_map::PageMapSerialization;
 use ic_replicated_state::{EmbedderCache, Memory, PageMap};
 use ic_types::CanisterId;
+use ic_wasm_types::SerializedModule;
 
 use crate::dts::{DeterministicTimeSlicingHandler, PausedExecution};
 
@@ -242,13 +244,22 @@ struct CanisterWasm {
     compilate: Arc<EmbedderCache>,
 }
 
+/// The outputs of compiling a Wasm binary in addition to the `CanisterWasm`.
+struct CompilationOutput {
+    instrumentation_output: InstrumentationOutput,
+    wasm_validation_details: WasmValidationDetails,
+    /// Set if the module was compiled because it was not in the read-only
+    /// compilation cache.
+    serialized_module: Option<SerializedModule>,
+}
+
 impl CanisterWasm {
     /// Validates and compiles the given Wasm binary.
     pub fn compile(
         config: &ic_config::embedders::Config,
         embedder: &Arc<WasmtimeEmbedder>,
         wasm_src: Vec<u8>,
-    ) -> HypervisorResult<(Self, InstrumentationOutput, WasmValidationDetails)> {
+    ) -> HypervisorResult<(Self, CompilationOutput)> {
         let wasm = decode_wasm(Arc::new(wasm_src))?;
         let wasm_validation_details = validate_wasm_binary(&wasm, config)?;
         let instrumentation_output = instrument(
@@ -256,13 +267,17 @@ impl CanisterWasm {
             &InstructionCostTable::new(),
             config.cost_to_compile_wasm_instruction,
         )?;
-        let compilate = embedder.compile(&instrumentation_output.binary)?;
+        let (compilate, serialized_module) =
+            embedder.compile_and_serialize(&instrumentation_output.binary)?;
         let compilate = Arc::new(compilate);
 
         Ok((
             Self { compilate },
-            instrumentation_output,
-            wasm_validation_details,
+            CompilationOutput {
+                instrumentation_output,
+                wasm_validation_details,
+                serialized_module,
+            },
         ))
     }
 }
@@ -291,7 +306,14 @@ impl SandboxManager {
     /// to relay e.g. syscalls and completions.
     pub fn new(controller: Arc<dyn ControllerService>, config: EmbeddersConfig) -> Self {
         let log = ic_logger::replica_logger::no_op_logger();
-        let embedder = Arc::new(WasmtimeEmbedder::new(config.clone(), log));
+        let mut embedder = WasmtimeEmbedder::new(config.clone(), log.clone());
+        if let Some(dir) = &config.compilation_cache_dir {
+            // Only the replica adds entries to the cache. Newly compiled
+            // modules are returned to it instead.
+            let compilation_cache = CompilationCache::open_read_only(dir.clone(), log);
+            embedder = embedder.with_compilation_cache(Arc::new(compilation_cache));
+        }
+        let embedder = Arc::new(embedder);
         SandboxManager {
             repr: Mutex::new(SandboxManagerInt {
                 canister_wasms: HashMap::new(),
@@ -315,18 +337,14 @@ impl SandboxManager {
         &self,
         wasm_id: WasmId,
         wasm_src: Vec<u8>,
-    ) -> HypervisorResult<(
-        Arc<CanisterWasm>,
-        InstrumentationOutput,
-        WasmValidationDetails,
-    )> {
+    ) -> HypervisorResult<(Arc<CanisterWasm>, CompilationOutput)> {
         let mut guard = self.repr.lock().unwrap();
         assert!(
             !guard.canister_wasms.contains_key(&wasm_id),
             "Failed to open wasm session {}: id is already in use",
             wasm_id,
         );
-        let (wasm, instrumentation_output, wasm_validation_details) =
+        let (wasm, compilation_output) =
             CanisterWasm::compile(&self.config, &self.embedder, wasm_src)?;
         // Return as much memory as possible because compiling seems to use up
         // some extra memory that can be returned.
@@ -340,19 +358,19 @@ impl SandboxManager {
         guard
             .canister_wasms
             .insert(wasm_id, Arc::clone(&canister_wasm));
-        Ok((
-            canister_wasm,
-            instrumentation_output,
-            wasm_validation_details,
-        ))
+        Ok((canister_wasm, compilation_output))
     }
 
     /// Compiles the given Wasm binary and registers it under the given id.
-    /// The function may fail if the Wasm binary is invalid.
-    pub fn open_wasm(&self, wasm_id: WasmId, wasm_src: Vec<u8>) -> HypervisorResult<()> {
-        let (_wasm, _instrumentation_output, _validation_details) =
-            self.open_wasm_internal(wasm_id, wasm_src)?;
-        Ok(())
+    /// The function may fail if the Wasm binary is invalid. Returns the
+    /// serialized module if it was not found in the compilation cache.
+    pub fn open_wasm(
+        &self,
+        wasm_id: WasmId,
+        wasm_src: Vec<u8>,
+    ) -> HypervisorResult<Option<SerializedModule>> {
+        let (_wasm, compilation_output) = self.open_wasm_internal(wasm_id, wasm_src)?;
+        Ok(compilation_output.serialized_module)
     }
 
     /// Closes previously opened wasm instance, by id.
@@ -483,8 +501,14 @@ impl SandboxManager {
         canister_id: CanisterId,
     ) -> HypervisorResult<CreateExecutionStateSuccessReply> {
         // Validate, instrument, and compile the binary.
-        let (canister_wasm, instrumentation_output, wasm_validation_details) =
-            self.open_wasm_internal(wasm_id, wasm_source)?;
+        let (
+            canister_wasm,
+            CompilationOutput {
+                instrumentation_output,
+                wasm_validation_details,
+                serialized_module,
+            },
+        ) = self.open_wasm_internal(wasm_id, wasm_source)?;
         let compilation_cost = instrumentation_output.compilation_cost;
         let embedder_cache = Arc::clone(&canister_wasm.compilate);
         let embedder = Arc::clone(&self.embedder);
@@ -517,6 +541,7 @@ impl SandboxManager {
             exported_functions,
             wasm_metadata: wasm_validation_details.wasm_metadata,
             compilation_cost,
+            serialized_module,
         })
     }
 }
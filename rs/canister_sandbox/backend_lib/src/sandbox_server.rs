@@ -34,7 +34,7 @@ impl SandboxService for SandboxServer {
 
     fn open_wasm(&self, req: OpenWasmRequest) -> rpc::Call<OpenWasmReply> {
         let result = self.manager.open_wasm(req.wasm_id, req.wasm_src);
-        rpc::Call::new_resolved(Ok(OpenWasmReply(result.map(|_| ()))))
+        rpc::Call::new_resolved(Ok(OpenWasmReply(result)))
     }
 
     fn close_wasm(&self, req: CloseWasmRequest) -> rpc::Call<CloseWasmReply> {
@@ -203,6 +203,7 @@ mod tests {
                 Cycles::from(0),
                 PrincipalId::try_from([0].as_ref()).unwrap(),
                 CallContextId::from(0),
+                None,
             ),
             globals,
             canister_current_memory_usage: NumBytes::from(0),
@@ -226,6 +227,7 @@ mod tests {
                 incoming_payload.to_vec(),
                 PrincipalId::try_from([0].as_ref()).unwrap(),
                 None,
+                None,
             ),
             globals,
             canister_current_memory_usage: NumBytes::from(0),
@@ -14,6 +14,7 @@ use ic_replicated_state::{
     Global, NumWasmPages,
 };
 use ic_types::{methods::WasmMethod, CanisterId, NumInstructions};
+use ic_wasm_types::SerializedModule;
 use serde::{Deserialize, Serialize};
 
 use super::{
@@ -54,9 +55,10 @@ pub struct OpenWasmRequest {
     pub wasm_src: Vec<u8>,
 }
 
-/// Reply to an `OpenWasmRequest`.
+/// Reply to an `OpenWasmRequest`. Holds the compiled module if it was not
+/// found in the compilation cache, so that the replica can add it.
 #[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct OpenWasmReply(pub HypervisorResult<()>);
+pub struct OpenWasmReply(pub HypervisorResult<Option<SerializedModule>>);
 
 /// Request to close the indicated wasm object.
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -238,6 +240,8 @@ pub struct CreateExecutionStateSuccessReply {
     pub exported_functions: BTreeSet<WasmMethod>,
     pub wasm_metadata: WasmMetadata,
     pub compilation_cost: NumInstructions,
+    /// The compiled module, if it was not found in the compilation cache.
+    pub serialized_module: Option<SerializedModule>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
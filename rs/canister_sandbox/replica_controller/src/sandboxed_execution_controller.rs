@@ -6,6 +6,7 @@ use ic_canister_sandbox_common::protocol::structs::SandboxExecInput;
 use ic_canister_sandbox_common::sandbox_service::SandboxService;
 use ic_canister_sandbox_common::{protocol, rpc};
 use ic_config::embedders::Config as EmbeddersConfig;
+use ic_embedders::compilation_cache::CompilationCache;
 use ic_embedders::wasm_executor::get_wasm_reserved_pages;
 use ic_embedders::WasmExecutionInput;
 use ic_interfaces::execution_environment::{HypervisorResult, InstanceStats, WasmExecutionOutput};
@@ -18,7 +19,7 @@ use ic_replicated_state::canister_state::execution_state::{
 use ic_replicated_state::{EmbedderCache, ExecutionState, ExportedFunctions, Memory, PageMap};
 use ic_system_api::sandbox_safe_system_state::SystemStateChanges;
 use ic_types::{CanisterId, NumInstructions};
-use ic_wasm_types::CanisterModule;
+use ic_wasm_types::{CanisterModule, SerializedModule};
 use prometheus::{Histogram, HistogramVec, IntGauge};
 use std::collections::{HashMap, VecDeque};
 use std::convert::TryInto;
@@ -323,6 +324,10 @@ pub struct SandboxedExecutionController {
     compile_count_for_testing: AtomicU64,
     metrics: Arc<SandboxedExecutionMetrics>,
     launcher_service: Box<dyn LauncherService>,
+    /// The cache to which the modules compiled by sandbox processes are
+    /// added, as the sandboxes only read from it.
+    compilation_cache: Option<Arc<CompilationCache>>,
+    embedder_config: EmbeddersConfig,
 }
 
 impl SandboxedExecutionController {
@@ -332,6 +337,7 @@ impl SandboxedExecutionController {
         logger: ReplicaLogger,
         metrics_registry: &MetricsRegistry,
         embedder_config: &EmbeddersConfig,
+        compilation_cache: Option<Arc<CompilationCache>>,
     ) -> std::io::Result<Self> {
         let launcher_exec_argv = create_launcher_argv().expect("No sandbox_launcher binary found");
         let sandbox_exec_argv =
@@ -378,6 +384,8 @@ impl SandboxedExecutionController {
             sandbox_exec_argv,
             metrics,
             launcher_service,
+            compilation_cache,
+            embedder_config: embedder_config.clone(),
         })
     }
 
@@ -548,7 +556,7 @@ impl SandboxedExecutionController {
 
         // Ensure that Wasm is compiled.
         let (wasm_id, compile_count) =
-            match open_wasm(&sandbox_process, &*execution_state.wasm_binary) {
+            match self.open_wasm(&sandbox_process, &*execution_state.wasm_binary) {
                 Ok((wasm_id, compile_count)) => (wasm_id, compile_count),
                 Err(err) => {
                     return (
@@ -741,6 +749,7 @@ impl SandboxedExecutionController {
             .sync()
             .unwrap()
             .0?;
+        self.cache_compiled_module(wasm_binary.binary.as_slice(), reply.serialized_module);
 
         cache_opened_wasm(
             &mut *wasm_binary.embedder_cache.lock().unwrap(),
@@ -772,6 +781,54 @@ impl SandboxedExecutionController {
     pub fn compile_count_for_testing(&self) -> u64 {
         self.compile_count_for_testing.load(Ordering::Relaxed)
     }
+
+    // Get compiled wasm object in sandbox. Ask cache first, upload + compile if
+    // needed.
+    fn open_wasm(
+        &self,
+        sandbox_process: &Arc<SandboxProcess>,
+        wasm_binary: &WasmBinary,
+    ) -> HypervisorResult<(WasmId, u64)> {
+        let mut embedder_cache = wasm_binary.embedder_cache.lock().unwrap();
+        if let Some(cache) = embedder_cache.as_ref() {
+            if let Some(opened_wasm) = cache.downcast::<OpenedWasm>() {
+                if let Some(cached_sandbox_process) = opened_wasm.sandbox_process.upgrade() {
+                    assert!(Arc::ptr_eq(&cached_sandbox_process, sandbox_process));
+                    return Ok((opened_wasm.wasm_id, 0));
+                }
+            }
+        }
+        let wasm_id = WasmId::new();
+        sandbox_process
+            .history
+            .record(format!("OpenWasm(wasm_id={})", wasm_id));
+        let serialized_module = sandbox_process
+            .sandbox_service
+            .open_wasm(protocol::sbxsvc::OpenWasmRequest {
+                wasm_id,
+                wasm_src: wasm_binary.binary.as_slice().to_vec(),
+            })
+            .sync()
+            .unwrap()
+            .0?;
+        self.cache_compiled_module(wasm_binary.binary.as_slice(), serialized_module);
+        cache_opened_wasm(&mut *embedder_cache, sandbox_process, wasm_id);
+        Ok((wasm_id, 1))
+    }
+
+    // Adds a module that a sandbox process compiled because it was missing
+    // from the compilation cache to the cache.
+    fn cache_compiled_module(
+        &self,
+        wasm_source: &[u8],
+        serialized_module: Option<SerializedModule>,
+    ) {
+        if let (Some(compilation_cache), Some(serialized_module)) =
+            (&self.compilation_cache, serialized_module)
+        {
+            compilation_cache.insert(wasm_source, &self.embedder_config, &serialized_module);
+        }
+    }
 }
 
 // Cache the sandbox process and wasm id of the opened wasm in the embedder
@@ -785,38 +842,6 @@ fn cache_opened_wasm(
     *embedder_cache = Some(EmbedderCache::new(opened_wasm));
 }
 
-// Get compiled wasm object in sandbox. Ask cache first, upload + compile if
-// needed.
-fn open_wasm(
-    sandbox_process: &Arc<SandboxProcess>,
-    wasm_binary: &WasmBinary,
-) -> HypervisorResult<(WasmId, u64)> {
-    let mut embedder_cache = wasm_binary.embedder_cache.lock().unwrap();
-    if let Some(cache) = embedder_cache.as_ref() {
-        if let Some(opened_wasm) = cache.downcast::<OpenedWasm>() {
-            if let Some(cached_sandbox_process) = opened_wasm.sandbox_process.upgrade() {
-                assert!(Arc::ptr_eq(&cached_sandbox_process, sandbox_process));
-                return Ok((opened_wasm.wasm_id, 0));
-            }
-        }
-    }
-    let wasm_id = WasmId::new();
-    sandbox_process
-        .history
-        .record(format!("OpenWasm(wasm_id={})", wasm_id));
-    sandbox_process
-        .sandbox_service
-        .open_wasm(protocol::sbxsvc::OpenWasmRequest {
-            wasm_id,
-            wasm_src: wasm_binary.binary.as_slice().to_vec(),
-        })
-        .sync()
-        .unwrap()
-        .0?;
-    cache_opened_wasm(&mut *embedder_cache, sandbox_process, wasm_id);
-    Ok((wasm_id, 1))
-}
-
 // Returns the id of the remote memory after making sure that the remote memory
 // is in sync with the local memory.
 fn open_remote_memory(
@@ -955,6 +980,7 @@ mod tests {
     use super::*;
     use ic_config::logger::Config as LoggerConfig;
     use ic_logger::{new_replica_logger, replica_logger::no_op_logger};
+    use ic_test_utilities::metrics::fetch_int_counter;
     use ic_test_utilities::types::ids::canister_test_id;
     use libc::kill;
     use slog::{o, Drain};
@@ -1001,6 +1027,7 @@ mod tests {
             logger,
             &MetricsRegistry::new(),
             &EmbeddersConfig::default(),
+            None,
         )
         .unwrap();
 
@@ -1037,6 +1064,52 @@ mod tests {
             canister_id, sandbox_pid
         )));
     }
+
+    #[test]
+    fn modules_compiled_by_a_sandbox_are_cached_for_other_sandboxes() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let metrics_registry = MetricsRegistry::new();
+        let mut embedder_config = EmbeddersConfig::default();
+        embedder_config.compilation_cache_dir = Some(tempdir.path().to_path_buf());
+        let compilation_cache = CompilationCache::new(
+            tempdir.path().to_path_buf(),
+            embedder_config.compilation_cache_capacity,
+            &metrics_registry,
+            no_op_logger(),
+        )
+        .unwrap();
+        let controller = SandboxedExecutionController::new(
+            no_op_logger(),
+            &metrics_registry,
+            &embedder_config,
+            Some(Arc::new(compilation_cache)),
+        )
+        .unwrap();
+        let num_entries = || fs::read_dir(tempdir.path()).unwrap().count();
+        let num_misses = || {
+            fetch_int_counter(
+                &metrics_registry,
+                "execution_wasm_compilation_cache_misses_total",
+            )
+        };
+
+        let wat = r#"(module (func (export "canister_update test")))"#;
+        let wasm_source = wabt::wat2wasm(wat).unwrap();
+        // The sandbox of the first canister compiles the module and the
+        // controller adds it to the cache.
+        controller
+            .create_execution_state(wasm_source.clone(), PathBuf::new(), canister_test_id(0))
+            .unwrap();
+        assert_eq!(num_entries(), 1);
+        assert_eq!(num_misses(), Some(1));
+
+        // The sandbox of the second canister loads it from the cache.
+        controller
+            .create_execution_state(wasm_source, PathBuf::new(), canister_test_id(1))
+            .unwrap();
+        assert_eq!(num_entries(), 1);
+        assert_eq!(num_misses(), Some(1));
+    }
 }
 
 /// Service responsible for printing the history of a canister's activity when
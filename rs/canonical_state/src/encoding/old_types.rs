@@ -57,6 +57,7 @@ impl TryFrom<RequestV3> for ic_types::messages::Request {
             payment: request.payment.cycles.try_into()?,
             method_name: request.method_name,
             method_payload: request.method_payload,
+            deadline: None,
         })
     }
 }
@@ -102,6 +103,7 @@ impl TryFrom<ResponseV3> for ic_types::messages::Response {
             originator_reply_callback: response.originator_reply_callback.into(),
             refund: response.refund.cycles.try_into()?,
             response_payload: response.response_payload.try_into()?,
+            deadline: None,
         })
     }
 }
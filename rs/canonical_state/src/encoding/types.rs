@@ -62,6 +62,8 @@ pub struct Request {
     pub method_payload: Bytes,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cycles_payment: Option<Cycles>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<u64>,
 }
 
 /// Canonical representation of `ic_types::messages::Response`.
@@ -77,6 +79,8 @@ pub struct Response {
     pub response_payload: Payload,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cycles_refund: Option<Cycles>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<u64>,
 }
 
 /// Canonical representation of `ic_types::funds::Cycles`.
@@ -243,6 +247,7 @@ impl From<(&ic_types::messages::Request, CertificationVersion)> for Request {
             method_name: request.method_name.clone(),
             method_payload: request.method_payload.clone(),
             cycles_payment: None,
+            deadline: encode_deadline(request.deadline, certification_version),
         }
     }
 }
@@ -264,6 +269,9 @@ impl TryFrom<Request> for ic_types::messages::Request {
             payment,
             method_name: request.method_name,
             method_payload: request.method_payload,
+            deadline: request
+                .deadline
+                .map(ic_types::Time::from_nanos_since_unix_epoch),
         })
     }
 }
@@ -283,6 +291,7 @@ impl From<(&ic_types::messages::Response, CertificationVersion)> for Response {
             refund: funds,
             response_payload: (&response.response_payload, certification_version).into(),
             cycles_refund: None,
+            deadline: encode_deadline(response.deadline, certification_version),
         }
     }
 }
@@ -303,10 +312,26 @@ impl TryFrom<Response> for ic_types::messages::Response {
             originator_reply_callback: response.originator_reply_callback.into(),
             refund,
             response_payload: response.response_payload.try_into()?,
+            deadline: response
+                .deadline
+                .map(ic_types::Time::from_nanos_since_unix_epoch),
         })
     }
 }
 
+/// Encodes the deadline of a best-effort message, starting with
+/// `CertificationVersion::V10`.
+fn encode_deadline(
+    deadline: Option<ic_types::Time>,
+    certification_version: CertificationVersion,
+) -> Option<u64> {
+    if certification_version >= CertificationVersion::V10 {
+        deadline.map(|deadline| deadline.as_nanos_since_unix_epoch())
+    } else {
+        None
+    }
+}
+
 impl From<(&ic_types::funds::Cycles, CertificationVersion)> for Cycles {
     fn from(
         (cycles, _certification_version): (&ic_types::funds::Cycles, CertificationVersion),
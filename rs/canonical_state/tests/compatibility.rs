@@ -59,7 +59,7 @@ pub(crate) fn arb_valid_versioned_stream_header(
         // 8.
         (
             arb_stream_header(/* sig_min_size */ 0, sig_max_size),
-            Just(CertificationVersion::V8..=CertificationVersion::V9)
+            Just(CertificationVersion::V8..=MAX_SUPPORTED_CERTIFICATION_VERSION)
         ),
     ]
 }
@@ -97,7 +97,7 @@ lazy_static! {
         ),
         #[allow(clippy::redundant_closure)]
         VersionedEncoding::new(
-            CertificationVersion::V0..=CertificationVersion::V9,
+            CertificationVersion::V0..=MAX_SUPPORTED_CERTIFICATION_VERSION,
             "StreamHeader",
             |v| StreamHeaderV8::proxy_encode(v),
             |v| StreamHeaderV8::proxy_decode(v),
@@ -174,10 +174,18 @@ proptest! {
 /// Produces a `RequestOrResponse` valid at all certification versions in the range.
 pub(crate) fn arb_valid_versioned_message(
 ) -> impl Strategy<Value = (RequestOrResponse, RangeInclusive<CertificationVersion>)> {
-    prop_oneof![(
-        arbitrary::request_or_response(),
-        Just(CertificationVersion::V0..=MAX_SUPPORTED_CERTIFICATION_VERSION)
-    ),]
+    prop_oneof![
+        (
+            arbitrary::request_or_response(),
+            Just(CertificationVersion::V0..=MAX_SUPPORTED_CERTIFICATION_VERSION)
+        ),
+        // Best-effort messages may only be encoded starting with certification
+        // version 10.
+        (
+            arbitrary::best_effort_request_or_response(),
+            Just(CertificationVersion::V10..=MAX_SUPPORTED_CERTIFICATION_VERSION)
+        ),
+    ]
 }
 
 lazy_static! {
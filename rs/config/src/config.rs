@@ -20,6 +20,7 @@ use crate::{
     registration::Config as RegistrationConfig,
     registry_client::Config as RegistryClientConfig,
     state_manager::Config as StateManagerConfig,
+    tracing::Config as TracingConfig,
     transport::TransportConfig,
 };
 use ic_types::malicious_behaviour::MaliciousBehaviour;
@@ -52,6 +53,7 @@ pub struct Config {
     pub registration: RegistrationConfig,
     pub nns_registry_replicator: NnsRegistryReplicatorConfig,
     pub adapters_config: AdaptersConfig,
+    pub tracing: TracingConfig,
 }
 
 /// Mirrors the Config struct except that fields are made optional. This is
@@ -76,6 +78,7 @@ pub struct ConfigOptional {
     pub registration: Option<RegistrationConfig>,
     pub nns_registry_replicator: Option<NnsRegistryReplicatorConfig>,
     pub adapters_config: Option<AdaptersConfig>,
+    pub tracing: Option<TracingConfig>,
 }
 
 impl Config {
@@ -105,6 +108,7 @@ impl Config {
             registration: RegistrationConfig::default(),
             nns_registry_replicator: NnsRegistryReplicatorConfig::default(),
             adapters_config: AdaptersConfig::default(),
+            tracing: TracingConfig::default(),
         }
     }
 
@@ -163,6 +167,7 @@ impl Config {
                 .nns_registry_replicator
                 .unwrap_or(default.nns_registry_replicator),
             adapters_config: cfg.adapters_config.unwrap_or(default.adapters_config),
+            tracing: cfg.tracing.unwrap_or(default.tracing),
         })
     }
 
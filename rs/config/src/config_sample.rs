@@ -370,6 +370,23 @@ pub const SAMPLE_CONFIG: &str = r#"
         // The canister http adapter socket file is: /ic-os/guestos/rootfs/systemd/system/ic-canister-http-adapter.socket
         canister_http_uds_path: "/run/ic-node/canister-http-adapter/socket",
     },
+    // =================================================
+    // Configuration of the tracing of message lifecycle.
+    // =================================================
+    tracing: {
+        // The fraction of ingress messages and block heights to trace, between 0.0 and 1.0.
+        // Tracing is disabled if the rate is 0.0.
+        sampling_rate: 0.0,
+
+        // Where to export spans to.
+        //
+        // Alternatives:
+        // - EXAMPLE: exporter: { otlp: "http://127.0.0.1:4318/v1/traces" },
+        //   Send spans to the OTLP/HTTP endpoint of an OpenTelemetry collector.
+        // - EXAMPLE: exporter: { jsonl_file: "/path/to/file" },
+        //   Append spans to the specified file, one JSON object per line.
+        exporter: { otlp: "http://127.0.0.1:4318/v1/traces" },
+    },
 }
 "#;
 
@@ -1,5 +1,6 @@
-use ic_types::NumInstructions;
+use ic_types::{NumInstructions, MAX_WASM64_MEMORY_IN_BYTES};
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
 use crate::flag_status::FlagStatus;
 use ic_base_types::NumBytes;
@@ -19,6 +20,9 @@ pub(crate) const MAX_CUSTOM_SECTIONS: usize = 16;
 pub(crate) const MAX_CUSTOM_SECTIONS_SIZE: NumBytes = NumBytes::new(1048576);
 /// The number of threads to use for query execution.
 pub(crate) const QUERY_EXECUTION_THREADS: usize = 2;
+/// The maximum total size of the compiled Wasm modules kept in the on-disk
+/// compilation cache.
+pub(crate) const COMPILATION_CACHE_CAPACITY: NumBytes = NumBytes::new(10 * 1024 * 1024 * 1024);
 
 /// In terms of execution time, compiling 1 WASM instructions takes as much time
 /// as actually executing 3_000 instructions. Only public for use in tests.
@@ -29,6 +33,8 @@ const DEFAULT_COST_TO_COMPILE_WASM_INSTRUCTION: NumInstructions = NumInstruction
 pub struct FeatureFlags {
     pub api_cycles_u128_flag: FlagStatus,
     pub rate_limiting_of_debug_prints: FlagStatus,
+    /// Allows canisters to install modules whose memory has 64-bit addresses.
+    pub wasm64: FlagStatus,
 }
 
 impl Default for FeatureFlags {
@@ -36,6 +42,7 @@ impl Default for FeatureFlags {
         Self {
             api_cycles_u128_flag: FlagStatus::Enabled,
             rate_limiting_of_debug_prints: FlagStatus::Enabled,
+            wasm64: FlagStatus::Disabled,
         }
     }
 }
@@ -63,6 +70,16 @@ pub struct Config {
 
     /// Flags to enable or disable features that are still experimental.
     pub feature_flags: FeatureFlags,
+
+    /// Directory of the on-disk cache of compiled Wasm modules, which
+    /// survives replica restarts. The cache is disabled if not set.
+    pub compilation_cache_dir: Option<PathBuf>,
+
+    /// Maximum total size of the compiled Wasm modules in the on-disk cache.
+    pub compilation_cache_capacity: NumBytes,
+
+    /// Maximum size of a Wasm memory with 64-bit addresses.
+    pub max_wasm64_memory_size: NumBytes,
 }
 
 impl Config {
@@ -76,6 +93,9 @@ impl Config {
             max_custom_sections_size: MAX_CUSTOM_SECTIONS_SIZE,
             cost_to_compile_wasm_instruction: DEFAULT_COST_TO_COMPILE_WASM_INSTRUCTION,
             feature_flags: FeatureFlags::default(),
+            compilation_cache_dir: None,
+            compilation_cache_capacity: COMPILATION_CACHE_CAPACITY,
+            max_wasm64_memory_size: NumBytes::new(MAX_WASM64_MEMORY_IN_BYTES),
         }
     }
 }
@@ -1,5 +1,6 @@
 use crate::{
-    embedders::QUERY_EXECUTION_THREADS, flag_status::FlagStatus,
+    embedders::{COMPILATION_CACHE_CAPACITY, QUERY_EXECUTION_THREADS},
+    flag_status::FlagStatus,
     subnet_config::MAX_INSTRUCTIONS_PER_MESSAGE,
 };
 use ic_base_types::NumSeconds;
@@ -7,6 +8,7 @@ use ic_types::{
     Cycles, NumBytes, NumInstructions, MAX_STABLE_MEMORY_IN_BYTES, MAX_WASM_MEMORY_IN_BYTES,
 };
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
 const GB: u64 = 1024 * 1024 * 1024;
 
@@ -103,6 +105,18 @@ pub struct Config {
     /// If this flag is enabled, then message execution of canisters will be
     /// rate limited based on the number of executed instructions per round.
     pub rate_limiting_of_instructions: FlagStatus,
+
+    /// Directory of the on-disk cache of compiled Wasm modules. Compiled
+    /// modules are reused across replica restarts if set. Only the replica
+    /// writes to the directory, the canister sandbox processes only read it.
+    pub compilation_cache_dir: Option<PathBuf>,
+
+    /// Maximum total size of the compiled Wasm modules in the on-disk cache.
+    pub compilation_cache_capacity: NumBytes,
+
+    /// If this flag is enabled, then canisters can install modules whose
+    /// memory has 64-bit addresses.
+    pub wasm64: FlagStatus,
 }
 
 impl Default for Config {
@@ -127,6 +141,9 @@ impl Default for Config {
             rate_limiting_of_debug_prints: FlagStatus::Enabled,
             rate_limiting_of_heap_delta: FlagStatus::Enabled,
             rate_limiting_of_instructions: FlagStatus::Enabled,
+            compilation_cache_dir: None,
+            compilation_cache_capacity: COMPILATION_CACHE_CAPACITY,
+            wasm64: FlagStatus::Disabled,
         }
     }
 }
@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// The default endpoint of a local OpenTelemetry collector accepting
+/// OTLP/HTTP requests.
+const DEFAULT_OTLP_ENDPOINT: &str = "http://127.0.0.1:4318/v1/traces";
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Exporter {
+    /// Send spans to the OTLP/HTTP endpoint at the specified URL.
+    Otlp(String),
+    /// Append spans to the given file, one JSON object per line.
+    JsonlFile(PathBuf),
+}
+
+impl Default for Exporter {
+    fn default() -> Self {
+        Exporter::Otlp(DEFAULT_OTLP_ENDPOINT.to_string())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// The fraction of ingress messages and block heights that are traced,
+    /// between 0.0 and 1.0. Tracing is disabled if the rate is 0.0.
+    ///
+    /// The sampling decision only depends on the message ID or height, so all
+    /// components (and all replicas) trace the same messages.
+    pub sampling_rate: f64,
+    pub exporter: Exporter,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            sampling_rate: 0.0,
+            exporter: Exporter::default(),
+        }
+    }
+}
@@ -27,7 +27,7 @@ use ic_types::{
     crypto::threshold_sig::ni_dkg::{
         NiDkgId, NiDkgTag, NiDkgTargetSubnet::Remote, NiDkgTranscript,
     },
-    messages::{CallbackId, Response},
+    messages::{CallbackId, MessageId, Response},
     CountBytes, ReplicaVersion,
 };
 use std::collections::BTreeMap;
@@ -174,7 +174,34 @@ pub fn deliver_batches(
                     batch_height,
                     block_hash
                 );
+                let tracer = ic_tracing::tracer();
+                let mut span = tracer
+                    .height_span("consensus.deliver_batch", h)
+                    .with_attribute("block_hash", &block_hash)
+                    .with_attribute("ingress_count", ingress_count);
+                // The inclusion spans of the messages last until their batch
+                // has been handed to message routing.
+                let inclusion_spans: Vec<_> = if tracer.is_enabled() {
+                    ingress_ids
+                        .iter()
+                        .map(|ingress_id| {
+                            tracer
+                                .message_span(
+                                    "consensus.block_inclusion",
+                                    &MessageId::from(ingress_id),
+                                )
+                                .with_attribute("height", h)
+                                .with_attribute("block_hash", &block_hash)
+                        })
+                        .collect()
+                } else {
+                    Vec::new()
+                };
                 let result = message_routing.deliver_batch(batch);
+                drop(inclusion_spans);
+                if let Err(err) = &result {
+                    span.set_attribute("error", format!("{:?}", err));
+                }
                 if let Some(f) = result_processor {
                     f(
                         &result,
@@ -311,6 +338,7 @@ pub fn generate_execution_responses_for_expired_canister_http_responses(
                         message: "Canister http request timed out".to_string(),
                     },
                 ),
+                deadline: None,
             });
         }
     }
@@ -340,6 +368,7 @@ pub fn generate_execution_responses_for_canister_http_responses(
                         ic_types::messages::Payload::Reject((canister_http_reject).into())
                     }
                 },
+                deadline: None,
             }
         })
         .collect()
@@ -430,6 +459,7 @@ pub fn generate_responses_to_setup_initial_dkg_calls(
                 originator_reply_callback: *callback_id,
                 refund: Cycles::zero(),
                 response_payload,
+                deadline: None,
             });
         }
     }
@@ -467,6 +497,7 @@ pub fn generate_responses_to_sign_with_ecdsa_calls(
                     }
                     .encode(),
                 ),
+                deadline: None,
             });
         }
     }
@@ -502,6 +533,7 @@ fn generate_responses_to_initial_dealings_calls(
                     }
                     .encode(),
                 ),
+                deadline: None,
             });
         }
     }
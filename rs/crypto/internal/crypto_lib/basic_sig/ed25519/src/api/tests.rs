@@ -373,3 +373,115 @@ mod verify_public_key {
         assert!(!verify_public_key(&pubkey_with_composite_order));
     }
 }
+
+mod verify_batch {
+    use crate::types::{PublicKeyBytes, SecretKeyBytes, SignatureBytes};
+    use crate::{sign, verify, verify_batch};
+    use ic_crypto_internal_test_vectors::ed25519::{crypto_lib_testvec, Ed25519TestVector};
+    use ic_crypto_secrets_containers::SecretArray;
+    use strum::IntoEnumIterator;
+
+    const SEED: [u8; 32] = [42; 32];
+
+    fn test_vectors() -> Vec<(SignatureBytes, Vec<u8>, PublicKeyBytes)> {
+        Ed25519TestVector::iter()
+            .map(|test_vec| {
+                let (_, pk, msg, sig) = crypto_lib_testvec(test_vec);
+                (SignatureBytes(sig), msg, PublicKeyBytes(pk))
+            })
+            .collect()
+    }
+
+    fn verify_all(signatures: &[(SignatureBytes, Vec<u8>, PublicKeyBytes)]) -> bool {
+        let signatures: Vec<_> = signatures
+            .iter()
+            .map(|(sig, msg, pk)| (sig, msg.as_slice(), pk))
+            .collect();
+        verify_batch(&signatures, SEED).is_ok()
+    }
+
+    #[test]
+    fn should_verify_empty_batch() {
+        assert!(verify_batch(&[], SEED).is_ok());
+    }
+
+    #[test]
+    fn should_verify_batch_of_test_vectors() {
+        assert!(verify_all(&test_vectors()));
+    }
+
+    #[test]
+    fn should_verify_batch_with_repeated_public_key() {
+        let (sk, pk, _, _) = crypto_lib_testvec(Ed25519TestVector::RFC8032_ED25519_SHA_ABC);
+        let sk = SecretKeyBytes(SecretArray::new_and_dont_zeroize_argument(&sk));
+        let signatures: Vec<_> = (0..10u8)
+            .map(|i| (sign(&[i], &sk).unwrap(), vec![i], PublicKeyBytes(pk)))
+            .collect();
+        assert!(verify_all(&signatures));
+    }
+
+    #[test]
+    fn should_fail_to_verify_batch_with_one_invalid_signature() {
+        for i in 0..test_vectors().len() {
+            let mut signatures = test_vectors();
+            signatures[i].0 .0[0] ^= 1;
+            assert!(!verify_all(&signatures));
+        }
+    }
+
+    #[test]
+    fn should_fail_to_verify_batch_with_wrong_message() {
+        let mut signatures = test_vectors();
+        signatures[0].1.push(0);
+        assert!(!verify_all(&signatures));
+    }
+
+    #[test]
+    fn should_fail_to_verify_batch_with_non_canonical_s() {
+        let mut signatures = test_vectors();
+        // s + l is not reduced, but represents the same scalar as s.
+        let l: [u8; 32] = [
+            0xed, 0xd3, 0xf5, 0x5c, 0x1a, 0x63, 0x12, 0x58, 0xd6, 0x9c, 0xf7, 0xa2, 0xde, 0xf9,
+            0xde, 0x14, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x10,
+        ];
+        let sig = &mut signatures[0].0 .0;
+        let mut carry = 0u16;
+        for i in 0..32 {
+            let sum = sig[32 + i] as u16 + l[i] as u16 + carry;
+            sig[32 + i] = sum as u8;
+            carry = sum >> 8;
+        }
+        assert!(!verify_all(&signatures));
+    }
+
+    #[test]
+    fn should_fail_to_verify_batch_with_small_order_components() {
+        use curve25519_dalek::constants::{ED25519_BASEPOINT_POINT, EIGHT_TORSION};
+        use curve25519_dalek::scalar::Scalar;
+        use sha2::{Digest, Sha512};
+
+        // A signature that passes individual verification although both the
+        // public key and R have a small-order component: R = r * B + j * T
+        // satisfies the (cofactorless) equation iff k + j = 0 mod 8.
+        let a = Scalar::from_bytes_mod_order([7; 32]);
+        let torsion = EIGHT_TORSION[1];
+        let pk = PublicKeyBytes((a * ED25519_BASEPOINT_POINT + torsion).compress().0);
+        let msg = b"message".to_vec();
+        let sig = (1..)
+            .map(|i: u64| {
+                let r = Scalar::from(i);
+                let r_point = r * ED25519_BASEPOINT_POINT + Scalar::from(i % 8) * torsion;
+                let r_bytes = r_point.compress().0;
+                let k = Scalar::from_hash(Sha512::new().chain(&r_bytes).chain(&pk.0).chain(&msg));
+                let mut sig = [0; 64];
+                sig[..32].copy_from_slice(&r_bytes);
+                sig[32..].copy_from_slice((r + k * a).as_bytes());
+                SignatureBytes(sig)
+            })
+            .find(|sig| verify(sig, &msg, &pk).is_ok())
+            .unwrap();
+
+        // The caller falls back to individual verification.
+        assert!(!verify_all(&[(sig, msg, pk)]));
+    }
+}
@@ -17,9 +17,10 @@ use ic_crypto_tls_interfaces::{
     TlsServerHandshakeError, TlsStream,
 };
 use ic_interfaces::crypto::{
-    BasicSigVerifier, BasicSigVerifierByPublicKey, CanisterSigVerifier, IDkgProtocol, KeyManager,
-    MultiSigVerifier, Signable, ThresholdEcdsaSigVerifier, ThresholdEcdsaSigner,
-    ThresholdSigVerifier, ThresholdSigVerifierByPublicKey,
+    BasicSigBatchVerifierByPublicKey, BasicSigVerifier, BasicSigVerifierByPublicKey,
+    CanisterSigVerifier, IDkgProtocol, KeyManager, MultiSigVerifier, Signable,
+    ThresholdEcdsaSigVerifier, ThresholdEcdsaSigner, ThresholdSigVerifier,
+    ThresholdSigVerifierByPublicKey,
 };
 use ic_interfaces::registry::RegistryClient;
 use ic_logger::replica_logger::no_op_logger;
@@ -586,6 +587,18 @@ impl<C: CryptoServiceProvider, T: Signable> BasicSigVerifierByPublicKey<T>
     }
 }
 
+impl<C: CryptoServiceProvider, T: Signable> BasicSigBatchVerifierByPublicKey<T>
+    for TempCryptoComponentGeneric<C>
+{
+    fn verify_basic_sig_batch_by_public_key(
+        &self,
+        signatures: &[(&BasicSigOf<T>, &T, &UserPublicKey)],
+    ) -> CryptoResult<()> {
+        self.crypto_component
+            .verify_basic_sig_batch_by_public_key(signatures)
+    }
+}
+
 impl<C: CryptoServiceProvider, T: Signable> CanisterSigVerifier<T>
     for TempCryptoComponentGeneric<C>
 {
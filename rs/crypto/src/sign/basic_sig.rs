@@ -1,6 +1,8 @@
 use super::*;
+use ic_crypto_internal_basic_sig_ed25519 as ed25519;
 use ic_crypto_internal_csp::api::CspSigner;
 use ic_crypto_internal_csp::types::SigConverter;
+use ic_crypto_sha::{DomainSeparationContext, Sha256};
 
 #[cfg(test)]
 mod tests;
@@ -69,3 +71,49 @@ impl BasicSignVerifierByPublicKeyInternal {
         )
     }
 }
+
+pub struct BasicSigBatchVerifierByPublicKeyInternal {}
+
+impl BasicSigBatchVerifierByPublicKeyInternal {
+    /// Verifies all Ed25519 signatures in a single batch, and all other
+    /// signatures individually.
+    pub fn verify_basic_sig_batch_by_public_key<C: CspSigner, S: Signable>(
+        csp_signer: &C,
+        signatures: &[(&BasicSigOf<S>, &S, &UserPublicKey)],
+    ) -> CryptoResult<()> {
+        let mut ed25519_signatures = Vec::new();
+        for (signature, signed_bytes, public_key) in signatures {
+            if public_key.algorithm_id == AlgorithmId::Ed25519 {
+                ed25519_signatures.push((
+                    ed25519::types::SignatureBytes::try_from(&signature.get_ref().0)?,
+                    signed_bytes.as_signed_bytes(),
+                    ed25519::types::PublicKeyBytes::try_from(&public_key.key)?,
+                ));
+            } else {
+                BasicSignVerifierByPublicKeyInternal::verify_basic_sig_by_public_key(
+                    csp_signer,
+                    signature,
+                    *signed_bytes,
+                    public_key,
+                )?;
+            }
+        }
+
+        // The coefficients of the batch verification are derived from the
+        // signatures, so that all replicas verifying the same batch agree.
+        let mut hasher = Sha256::new_with_context(&DomainSeparationContext::new(
+            "ic-ed25519-batch-verification-seed",
+        ));
+        let batch: Vec<_> = ed25519_signatures
+            .iter()
+            .map(|(signature, signed_bytes, public_key)| {
+                hasher.write(&signature.0);
+                hasher.write(&public_key.0);
+                hasher.write(&(signed_bytes.len() as u64).to_be_bytes());
+                hasher.write(signed_bytes);
+                (signature, signed_bytes.as_slice(), public_key)
+            })
+            .collect();
+        ed25519::verify_batch(&batch, hasher.finish())
+    }
+}
@@ -0,0 +1,96 @@
+use ic_crypto_sha::{DomainSeparationContext, Sha256};
+use ic_interfaces::crypto::Signable;
+use ic_types::crypto::UserPublicKey;
+use ic_types::messages::Delegation;
+use ic_types::RegistryVersion;
+use lru::LruCache;
+use parking_lot::Mutex;
+
+#[cfg(test)]
+mod tests;
+
+/// The number of delegations a `VerifiedDelegationCache` holds by default.
+const DEFAULT_CAPACITY: usize = 10_000;
+
+/// Remembers delegations whose signatures were successfully verified.
+///
+/// The requests of a session typically carry the same chain of delegations,
+/// and verifying a delegation (in particular if it carries a canister
+/// signature) can be more expensive than verifying the request signature
+/// itself. With this cache, each delegation of a chain is verified only once.
+///
+/// Entries are keyed by a hash of the delegation, its signature, the public
+/// key it was verified with and the registry version, so an entry only covers
+/// exactly the same verification. Failed verifications are not cached. The
+/// expiry of delegations is not covered by the cache and has to be checked by
+/// the caller.
+pub struct VerifiedDelegationCache {
+    verified: Mutex<LruCache<[u8; 32], ()>>,
+}
+
+impl VerifiedDelegationCache {
+    /// Creates a cache that holds up to `capacity` delegations, evicting the
+    /// least recently used ones.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            verified: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    /// Returns `Ok(())` if the verification of `signature` on `delegation`
+    /// with `public_key` at `registry_version` succeeded before. Otherwise
+    /// returns the result of `verify`, remembering it if it is successful.
+    pub fn get_or_verify<E>(
+        &self,
+        delegation: &Delegation,
+        signature: &[u8],
+        public_key: &UserPublicKey,
+        registry_version: RegistryVersion,
+        verify: impl FnOnce() -> Result<(), E>,
+    ) -> Result<(), E> {
+        let key = cache_key(delegation, signature, public_key, registry_version);
+        if self.verified.lock().get(&key).is_some() {
+            return Ok(());
+        }
+        verify()?;
+        self.verified.lock().put(key, ());
+        Ok(())
+    }
+
+    /// Returns the number of delegations in the cache.
+    pub fn len(&self) -> usize {
+        self.verified.lock().len()
+    }
+
+    /// Returns `true` if the cache does not contain any delegations.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for VerifiedDelegationCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+fn cache_key(
+    delegation: &Delegation,
+    signature: &[u8],
+    public_key: &UserPublicKey,
+    registry_version: RegistryVersion,
+) -> [u8; 32] {
+    let mut hasher =
+        Sha256::new_with_context(&DomainSeparationContext::new("ic-verified-delegation"));
+    for bytes in &[
+        delegation.as_signed_bytes().as_slice(),
+        signature,
+        public_key.key.as_slice(),
+    ] {
+        hasher.write(&(bytes.len() as u64).to_be_bytes());
+        hasher.write(bytes);
+    }
+    hasher.write(&[public_key.algorithm_id as u8]);
+    hasher.write(&registry_version.get().to_be_bytes());
+    hasher.finish()
+}
@@ -0,0 +1,136 @@
+#![allow(clippy::unwrap_used)]
+use super::*;
+use ic_types::crypto::AlgorithmId;
+use ic_types::time::UNIX_EPOCH;
+use std::cell::Cell;
+
+fn delegation(pubkey: u8) -> Delegation {
+    Delegation::new(vec![pubkey; 32], UNIX_EPOCH)
+}
+
+fn public_key(key: &[u8]) -> UserPublicKey {
+    UserPublicKey {
+        key: key.to_vec(),
+        algorithm_id: AlgorithmId::Ed25519,
+    }
+}
+
+#[test]
+fn should_verify_only_once() {
+    let cache = VerifiedDelegationCache::default();
+    let verifications = Cell::new(0);
+    let verify = || {
+        verifications.set(verifications.get() + 1);
+        Ok::<(), ()>(())
+    };
+
+    for _ in 0..3 {
+        let result = cache.get_or_verify(
+            &delegation(1),
+            b"signature",
+            &public_key(b"public key"),
+            RegistryVersion::from(1),
+            verify,
+        );
+        assert_eq!(result, Ok(()));
+    }
+    assert_eq!(verifications.get(), 1);
+    assert_eq!(cache.len(), 1);
+}
+
+#[test]
+fn should_not_cache_failed_verification() {
+    let cache = VerifiedDelegationCache::default();
+    let verify = || Err("invalid signature");
+
+    for _ in 0..2 {
+        let result = cache.get_or_verify(
+            &delegation(1),
+            b"signature",
+            &public_key(b"public key"),
+            RegistryVersion::from(1),
+            verify,
+        );
+        assert_eq!(result, Err("invalid signature"));
+    }
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn should_only_cover_identical_verification() {
+    let cache = VerifiedDelegationCache::default();
+    cache
+        .get_or_verify(
+            &delegation(1),
+            b"signature",
+            &public_key(b"public key"),
+            RegistryVersion::from(1),
+            || Ok::<(), ()>(()),
+        )
+        .unwrap();
+
+    let is_cached =
+        |delegation: &Delegation, signature: &[u8], public_key: &UserPublicKey, version: u64| {
+            cache
+                .get_or_verify(
+                    delegation,
+                    signature,
+                    public_key,
+                    RegistryVersion::from(version),
+                    || Err(()),
+                )
+                .is_ok()
+        };
+    let pk = public_key(b"public key");
+    assert!(is_cached(&delegation(1), b"signature", &pk, 1));
+    assert!(!is_cached(&delegation(2), b"signature", &pk, 1));
+    assert!(!is_cached(&delegation(1), b"signaturf", &pk, 1));
+    assert!(!is_cached(
+        &delegation(1),
+        b"signature",
+        &public_key(b"public kez"),
+        1
+    ));
+    assert!(!is_cached(&delegation(1), b"signature", &pk, 2));
+    let other_algorithm = UserPublicKey {
+        key: pk.key.clone(),
+        algorithm_id: AlgorithmId::EcdsaP256,
+    };
+    assert!(!is_cached(
+        &delegation(1),
+        b"signature",
+        &other_algorithm,
+        1
+    ));
+    // The concatenation of the inputs alone does not determine the entry.
+    assert!(!is_cached(
+        &delegation(1),
+        b"signaturep",
+        &public_key(b"ublic key"),
+        1
+    ));
+}
+
+#[test]
+fn should_evict_least_recently_used_delegation() {
+    let cache = VerifiedDelegationCache::new(2);
+    let pk = public_key(b"public key");
+    let get_or_verify = |pubkey, result: Result<(), ()>| {
+        cache.get_or_verify(
+            &delegation(pubkey),
+            b"signature",
+            &pk,
+            RegistryVersion::from(1),
+            || result,
+        )
+    };
+    get_or_verify(1, Ok(())).unwrap();
+    get_or_verify(2, Ok(())).unwrap();
+    get_or_verify(1, Ok(())).unwrap();
+    get_or_verify(3, Ok(())).unwrap();
+
+    assert_eq!(cache.len(), 2);
+    assert!(get_or_verify(1, Err(())).is_ok());
+    assert!(get_or_verify(2, Err(())).is_err());
+    assert!(get_or_verify(3, Err(())).is_ok());
+}
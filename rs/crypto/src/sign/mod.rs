@@ -1,17 +1,21 @@
 use super::*;
 
 use crate::sign::basic_sig::BasicSigVerifierInternal;
-use crate::sign::basic_sig::{BasicSignVerifierByPublicKeyInternal, BasicSignerInternal};
+use crate::sign::basic_sig::{
+    BasicSigBatchVerifierByPublicKeyInternal, BasicSignVerifierByPublicKeyInternal,
+    BasicSignerInternal,
+};
 use crate::sign::multi_sig::MultiSigVerifierInternal;
 use crate::sign::multi_sig::MultiSignerInternal;
 use crate::sign::threshold_sig::{ThresholdSigVerifierInternal, ThresholdSignerInternal};
 pub use canister_threshold_sig::ecdsa::{derive_tecdsa_public_key, get_tecdsa_master_public_key};
+pub use delegation_cache::VerifiedDelegationCache;
 use ic_crypto_internal_csp::types::{CspPublicKey, CspSignature};
 use ic_crypto_internal_csp::CryptoServiceProvider;
 use ic_interfaces::crypto::{
-    BasicSigVerifier, BasicSigVerifierByPublicKey, BasicSigner, CanisterSigVerifier,
-    MultiSigVerifier, MultiSigner, Signable, ThresholdEcdsaSigVerifier, ThresholdEcdsaSigner,
-    ThresholdSigVerifier, ThresholdSigVerifierByPublicKey, ThresholdSigner,
+    BasicSigBatchVerifierByPublicKey, BasicSigVerifier, BasicSigVerifierByPublicKey, BasicSigner,
+    CanisterSigVerifier, MultiSigVerifier, MultiSigner, Signable, ThresholdEcdsaSigVerifier,
+    ThresholdEcdsaSigner, ThresholdSigVerifier, ThresholdSigVerifierByPublicKey, ThresholdSigner,
 };
 use ic_logger::{debug, new_logger};
 use ic_types::crypto::canister_threshold_sig::error::{
@@ -38,6 +42,7 @@ pub use threshold_sig::ThresholdSigDataStoreImpl;
 mod basic_sig;
 mod canister_sig;
 mod canister_threshold_sig;
+mod delegation_cache;
 mod multi_sig;
 mod threshold_sig;
 
@@ -142,6 +147,30 @@ impl<C: CryptoServiceProvider, S: Signable> BasicSigVerifierByPublicKey<S>
     }
 }
 
+impl<C: CryptoServiceProvider, S: Signable> BasicSigBatchVerifierByPublicKey<S>
+    for CryptoComponentFatClient<C>
+{
+    fn verify_basic_sig_batch_by_public_key(
+        &self,
+        signatures: &[(&BasicSigOf<S>, &S, &UserPublicKey)],
+    ) -> CryptoResult<()> {
+        let logger = new_logger!(&self.logger;
+            crypto.trait_name => "BasicSigBatchVerifierByPublicKey",
+            crypto.method_name => "verify_basic_sig_batch_by_public_key",
+        );
+        debug!(logger; crypto.description => "start",);
+        let result = BasicSigBatchVerifierByPublicKeyInternal::verify_basic_sig_batch_by_public_key(
+            &self.csp, signatures,
+        );
+        debug!(logger;
+            crypto.description => "end",
+            crypto.is_ok => result.is_ok(),
+            crypto.error => log_err(result.as_ref().err()),
+        );
+        result
+    }
+}
+
 impl<C: CryptoServiceProvider, H: Signable> MultiSigner<H> for CryptoComponentFatClient<C> {
     fn sign_multi(
         &self,
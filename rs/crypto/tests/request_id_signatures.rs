@@ -5,7 +5,9 @@ use ic_crypto::{
     CryptoComponent, KeyBytesContentType,
 };
 use ic_crypto_internal_test_vectors::test_data;
-use ic_interfaces::crypto::{BasicSigVerifierByPublicKey, SignableMock, DOMAIN_IC_REQUEST};
+use ic_interfaces::crypto::{
+    BasicSigBatchVerifierByPublicKey, BasicSigVerifierByPublicKey, SignableMock, DOMAIN_IC_REQUEST,
+};
 use ic_logger::replica_logger::no_op_logger;
 use ic_registry_client_fake::FakeRegistryClient;
 use ic_registry_proto_data_provider::ProtoRegistryDataProvider;
@@ -175,6 +177,51 @@ fn should_verify_request_id_ecdsa_signature() {
     });
 }
 
+#[test]
+fn should_verify_batch_of_request_id_signatures() {
+    let request_ids: Vec<_> = (0..10).map(|i| MessageId::from([i; 32])).collect();
+    let mut signatures: Vec<_> = request_ids
+        .iter()
+        .map(ed25519_signature_and_public_key)
+        .collect();
+    // Non-Ed25519 signatures are verified individually as part of the batch.
+    signatures.push(ecdsa_signature_and_public_key(
+        Nid::X9_62_PRIME256V1,
+        &request_ids[0],
+    ));
+    let mut batch: Vec<_> = signatures
+        .iter()
+        .zip(request_ids.iter().chain(std::iter::once(&request_ids[0])))
+        .map(|((signature, public_key), request_id)| (signature, request_id, public_key))
+        .collect();
+    CryptoConfig::run_with_temp_config(|config| {
+        let crypto = crypto_component(&config);
+        assert!(crypto.verify_basic_sig_batch_by_public_key(&[]).is_ok());
+        assert!(crypto.verify_basic_sig_batch_by_public_key(&batch).is_ok());
+
+        // A signature on a different request ID invalidates the batch.
+        batch[3].1 = &request_ids[4];
+        assert!(crypto
+            .verify_basic_sig_batch_by_public_key(&batch)
+            .unwrap_err()
+            .is_signature_verification_error());
+    })
+}
+
+#[test]
+fn should_fail_batch_verification_with_malformed_ed25519_pk() {
+    let request_id = MessageId::from([42; 32]);
+    let (signature, mut public_key) = ed25519_signature_and_public_key(&request_id);
+    public_key.key.pop();
+    CryptoConfig::run_with_temp_config(|config| {
+        let crypto = crypto_component(&config);
+        assert!(crypto
+            .verify_basic_sig_batch_by_public_key(&[(&signature, &request_id, &public_key)])
+            .unwrap_err()
+            .is_malformed_public_key());
+    })
+}
+
 #[test]
 fn should_correctly_parse_der_encoded_ecdsa_p256_pk() {
     let pk_der = hex::decode(test_data::ECDSA_P256_PK_DER_HEX).unwrap();
@@ -147,7 +147,7 @@ fn get_state_hash(
 }
 
 fn install_canister(
-    wasm: &str,
+    wasm: &[u8],
     message_routing: &MessageRoutingImpl,
     ingress_history_reader: &dyn IngressHistoryReader,
     mut nonce: u64,
@@ -169,7 +169,6 @@ fn install_canister(
         Err(err) => panic!("{}", err),
     };
 
-    let wasm = wat2wasm(wasm).unwrap();
     let signed_ingress = SignedIngressBuilder::new()
         .canister_id(IC_00)
         .expiry_time(UNIX_EPOCH + Duration::from_secs(60))
@@ -179,7 +178,7 @@ fn install_canister(
             InstallCodeArgs::new(
                 CanisterInstallMode::try_from("install".to_string()).unwrap(),
                 canister_id,
-                wasm,
+                wasm.to_vec(),
                 vec![],
                 None,
                 None,
@@ -234,6 +233,13 @@ const WASM: &str = r#"(module
 "#;
 
 pub fn determinism_test(msgs: Vec<&str>) {
+    determinism_test_with_wasm(&wat2wasm(WASM).unwrap(), msgs)
+}
+
+/// Installs the given Wasm module on several canisters, executes the given
+/// update methods on all of them, and checks that the resulting state hash is
+/// the same in every iteration.
+pub fn determinism_test_with_wasm(wasm: &[u8], msgs: Vec<&str>) {
     let rt = tokio::runtime::Runtime::new().unwrap();
     let _enter_guard = rt.enter();
     let mut hashes = vec![];
@@ -247,7 +253,7 @@ pub fn determinism_test(msgs: Vec<&str>) {
             * num_canisters_per_core)
             .map(|_index| {
                 let (canister, inner_nonce) = install_canister(
-                    WASM,
+                    wasm,
                     &message_routing,
                     ingress_history_reader.as_ref(),
                     nonce,
@@ -1,4 +1,4 @@
-use ic_determinism_test::determinism_test;
+use ic_determinism_test::{determinism_test, determinism_test_with_wasm};
 
 #[test]
 fn test_process_batches_deterministically() {
@@ -6,3 +6,34 @@ fn test_process_batches_deterministically() {
         "dirty1", "dirty2", "dirty1", "dirty2", "dirty1", "dirty2",
     ]);
 }
+
+// Vector floating-point operations that produce NaNs, whose bit patterns are
+// not specified by Wasm unless NaN canonicalization is enabled.
+const SIMD_WAT: &str = r#"(module
+(import "ic0" "msg_reply" (func $msg_reply))
+
+(func $simd_nans
+  (v128.store (i32.const 0)
+    (f32x4.div (v128.const f32x4 0 -0 inf -inf) (v128.const f32x4 0 0 inf inf)))
+  (v128.store (i32.const 16)
+    (f64x2.sqrt (v128.const f64x2 -1 -inf)))
+  (v128.store (i32.const 32)
+    (f32x4.mul (v128.load (i32.const 0)) (v128.const f32x4 -nan:0x200000 1 2 3)))
+  (v128.store (i32.const 48)
+    (f64x2.add (v128.load (i32.const 16)) (v128.const f64x2 nan:0x4000000000000 -nan)))
+  (v128.store (i32.const 64)
+    (f32x4.demote_f64x2_zero (v128.load (i32.const 48))))
+  (call $msg_reply)
+)
+
+(memory $memory 1)
+(export "canister_update simd_nans" (func $simd_nans))
+(export "memory" (memory $memory)))
+"#;
+
+#[test]
+fn test_process_simd_batches_deterministically() {
+    // The bundled `wabt` predates the final encoding of the SIMD instructions.
+    let wasm = wat::parse_str(SIMD_WAT).unwrap();
+    determinism_test_with_wasm(&wasm, vec!["simd_nans", "simd_nans"]);
+}
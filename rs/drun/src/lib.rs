@@ -1,6 +1,7 @@
 //! Standalone interface for testing application canisters.
 
 use crate::message::{msg_stream_from_file, Message};
+use crate::script::{load_script, MessageRoutingTarget, Script, ScriptFormat, ScriptRunner};
 use hex::encode;
 use ic_config::{subnet_config::SubnetConfigs, Config};
 use ic_cycles_account_manager::CyclesAccountManager;
@@ -33,9 +34,9 @@ use ic_test_utilities_registry::{
 use ic_types::{
     batch::{Batch, BatchPayload, IngressPayload},
     ingress::{IngressState, IngressStatus, WasmResult},
-    messages::{MessageId, SignedIngress},
+    messages::{MessageId, SignedIngress, UserQuery},
     replica_config::ReplicaConfig,
-    time::UNIX_EPOCH,
+    time::{Time, UNIX_EPOCH},
     CanisterId, NodeId, PrincipalId, Randomness, RegistryVersion, SubnetId,
 };
 use slog::{Drain, Logger};
@@ -45,6 +46,7 @@ use std::sync::{Arc, Mutex};
 use std::{thread::sleep, time::Duration};
 
 mod message;
+mod script;
 
 // drun will panic if it takes more than this many batches
 // until a response for a message is received
@@ -59,6 +61,13 @@ pub struct DrunOptions {
     pub log_file: Option<PathBuf>,
 }
 
+/// The inputs `drun` can process: a stream of messages in the line-based
+/// format or a structured script.
+enum Input<I> {
+    Messages(I),
+    Script(Script),
+}
+
 /// Deliver a single message to the Message Routing layer
 fn deliver_message(
     msg: SignedIngress,
@@ -68,10 +77,16 @@ fn deliver_message(
 ) {
     let message_id = msg.id();
 
-    let _ = execute_ingress_message(message_routing, msg, &message_id, ingress_hist_reader);
+    let _ = execute_ingress_message(
+        message_routing,
+        msg,
+        &message_id,
+        ingress_hist_reader,
+        UNIX_EPOCH,
+    );
     // print result after waiting, to not interleave the result
     // with debug.print messages from subsequent calls. revise after DFN-1269.
-    wait_extra_batches(message_routing, extra_batches);
+    wait_extra_batches(message_routing, extra_batches, UNIX_EPOCH);
     print_ingress_result(&message_id, ingress_hist_reader);
 }
 
@@ -165,7 +180,10 @@ pub fn run_drun(uo: DrunOptions) -> Result<(), String> {
         subnet_id,
     };
 
-    let mut msg_stream = msg_stream_from_file(&msg_filename)?;
+    let input = match ScriptFormat::from_filename(&msg_filename) {
+        Some(format) => Input::Script(load_script(&msg_filename, format)?),
+        None => Input::Messages(msg_stream_from_file(&msg_filename)?),
+    };
     let log = match log_file {
         Some(log_file) => setup_logger(log_file),
         None => slog::Logger::root(slog::Discard, slog::o!()),
@@ -231,6 +249,22 @@ pub fn run_drun(uo: DrunOptions) -> Result<(), String> {
         Arc::clone(&registry) as _,
     );
 
+    let mut msg_stream = match input {
+        Input::Messages(msg_stream) => msg_stream,
+        Input::Script(script) => {
+            let query = |q: UserQuery| {
+                query_handler.query(q, state_manager.get_latest_state().take(), Vec::new())
+            };
+            let target = MessageRoutingTarget {
+                message_routing: &message_routing,
+                ingress_history: ingress_hist_reader.as_ref(),
+                query: &query,
+                extra_batches,
+            };
+            return ScriptRunner::new(&target).run(script);
+        }
+    };
+
     msg_stream.try_for_each(|parse_result| {
         parse_result.map(|msg| match msg {
             Message::Install(msg) => {
@@ -309,7 +343,11 @@ fn print_wasm_result(wasm_result: WasmResult) {
     }
 }
 
-fn build_batch(message_routing: &dyn MessageRouting, msgs: Vec<SignedIngress>) -> Batch {
+fn build_batch(
+    message_routing: &dyn MessageRouting,
+    msgs: Vec<SignedIngress>,
+    time: Time,
+) -> Batch {
     Batch {
         batch_number: message_routing.expected_batch_height(),
         requires_full_state_hash: !msgs.is_empty(),
@@ -320,7 +358,7 @@ fn build_batch(message_routing: &dyn MessageRouting, msgs: Vec<SignedIngress>) -
         randomness: Randomness::from([0; 32]),
         ecdsa_subnet_public_key: None,
         registry_version: RegistryVersion::from(1),
-        time: UNIX_EPOCH,
+        time,
         consensus_responses: vec![],
     }
 }
@@ -333,8 +371,9 @@ fn execute_ingress_message(
     msg: SignedIngress,
     msg_id: &MessageId,
     ingress_history: &dyn IngressHistoryReader,
+    time: Time,
 ) -> Result<WasmResult, UserError> {
-    let mut batch = build_batch(message_routing, vec![msg]);
+    let mut batch = build_batch(message_routing, vec![msg], time);
     for _ in 0..MAX_BATCHES_UNTIL_RESPONSE {
         // In the first batch we try to send the ingress message itself. If it fails, we
         // repeat with the same batch.
@@ -345,7 +384,7 @@ fn execute_ingress_message(
         // potential inter-canister messages that the ingress message may have
         // triggered.
         if message_routing.deliver_batch(batch.clone()).is_ok() {
-            batch = build_batch(message_routing, vec![])
+            batch = build_batch(message_routing, vec![], time)
         }
         sleep(WAIT_PER_BATCH);
 
@@ -391,10 +430,10 @@ fn execute_ingress_message(
 ///
 /// This is a temporary measure until DFN-1269 is resolved. In that ticket, we
 /// will actually try to wait until all messages have been executed.
-fn wait_extra_batches(message_routing: &dyn MessageRouting, extra_batches: u64) {
+fn wait_extra_batches(message_routing: &dyn MessageRouting, extra_batches: u64, time: Time) {
     for _ in 0..extra_batches {
         loop {
-            let batch = build_batch(message_routing, vec![]);
+            let batch = build_batch(message_routing, vec![], time);
             let ok = message_routing.deliver_batch(batch).is_ok();
             sleep(WAIT_PER_BATCH);
             if ok {
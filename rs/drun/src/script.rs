@@ -0,0 +1,890 @@
+//! Structured `drun` scripts.
+//!
+//! Besides the line-based format of the `message` module, `drun` accepts
+//! scripts encoded as JSON or CBOR. Scripts bind the IDs of created canisters
+//! to variables, encode arguments from textual Candid, check the results of
+//! calls against expectations and produce a JSON report of every step.
+use super::{execute_ingress_message, wait_extra_batches, CanisterId};
+use crate::message::parse_canister_id;
+
+use candid::IDLArgs;
+use hex::encode;
+use ic_error_types::{RejectCode, UserError};
+use ic_ic00_types::{
+    self as ic00, CanisterIdRecord, CanisterInstallMode, InstallCodeArgs, Payload,
+    ProvisionalCreateCanisterWithCyclesArgs, ProvisionalTopUpCanisterArgs,
+};
+use ic_interfaces::{execution_environment::IngressHistoryReader, messaging::MessageRouting};
+use ic_test_utilities::types::messages::SignedIngressBuilder;
+use ic_types::{
+    ingress::WasmResult,
+    messages::{SignedIngress, UserQuery},
+    time::{current_time_and_expiry_time, Time, UNIX_EPOCH},
+    PrincipalId, UserId,
+};
+use serde::{Deserialize, Serialize};
+
+use std::{collections::BTreeMap, fs, path::Path, path::PathBuf, time::Duration};
+
+/// The encodings of structured scripts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum ScriptFormat {
+    Json,
+    Cbor,
+}
+
+impl ScriptFormat {
+    /// Returns the format of the script in `filename` based on its extension,
+    /// or `None` if the file uses the line-based format.
+    pub(crate) fn from_filename(filename: &str) -> Option<Self> {
+        match Path::new(filename).extension().and_then(|ext| ext.to_str()) {
+            Some("json") => Some(ScriptFormat::Json),
+            Some("cbor") => Some(ScriptFormat::Cbor),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Script {
+    steps: Vec<Step>,
+}
+
+/// A single step of a script. Canister IDs, senders and Candid arguments may
+/// refer to the canister bound to `name` with `${name}`.
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum Step {
+    /// Creates a canister, optionally binding its ID to the variable `bind`.
+    Create {
+        bind: Option<String>,
+        cycles: Option<u64>,
+    },
+    /// Installs the Wasm module in the file `wasm` on `canister`.
+    Install {
+        canister: String,
+        wasm: PathBuf,
+        #[serde(default)]
+        mode: CanisterInstallMode,
+        arg: Option<Argument>,
+    },
+    /// Executes an update call as an ingress message.
+    Ingress {
+        canister: String,
+        method: String,
+        arg: Option<Argument>,
+        sender: Option<String>,
+        expect: Option<Expectation>,
+    },
+    /// Executes a query call.
+    Query {
+        canister: String,
+        method: String,
+        arg: Option<Argument>,
+        sender: Option<String>,
+        expect: Option<Expectation>,
+    },
+    /// Advances the time of the next batches by the specified number of
+    /// nanoseconds.
+    AdvanceTime { nanos: u64 },
+    /// Adds the specified number of cycles to the balance of `canister`.
+    TopUp { canister: String, cycles: u64 },
+}
+
+/// The argument of a call.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+enum Argument {
+    /// Textual Candid values, e.g. `(42 : nat, "hello")`.
+    Candid(String),
+    /// Hex-encoded bytes, optionally prefixed with `0x`.
+    Hex(String),
+    /// Text encoded as UTF-8.
+    Text(String),
+}
+
+/// The expected result of a call.
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+enum Expectation {
+    /// The call is replied with the specified argument. Candid replies match
+    /// if they decode to the same values, so integer literals should be
+    /// annotated with their type, e.g. `(42 : nat)`.
+    Reply(Argument),
+    /// The call is rejected with the specified reject code.
+    Reject(u64),
+}
+
+/// The JSON report of a script run.
+#[derive(Debug, Serialize)]
+struct Report {
+    passed: bool,
+    steps: Vec<StepReport>,
+}
+
+#[derive(Debug, Serialize)]
+struct StepReport {
+    step: usize,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    canister_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<CallResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    passed: bool,
+}
+
+/// The result of a call, as reported.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+enum CallResult {
+    Reply {
+        hex: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        candid: Option<String>,
+    },
+    Reject {
+        code: u64,
+        message: String,
+    },
+}
+
+impl From<&Result<WasmResult, UserError>> for CallResult {
+    fn from(result: &Result<WasmResult, UserError>) -> Self {
+        match result {
+            Ok(WasmResult::Reply(bytes)) => CallResult::Reply {
+                hex: encode(bytes),
+                candid: IDLArgs::from_bytes(bytes).ok().map(|args| args.to_string()),
+            },
+            Ok(WasmResult::Reject(message)) => CallResult::Reject {
+                code: RejectCode::CanisterReject as u64,
+                message: message.clone(),
+            },
+            Err(err) => CallResult::Reject {
+                code: err.reject_code() as u64,
+                message: err.to_string(),
+            },
+        }
+    }
+}
+
+pub(crate) fn load_script(filename: &str, format: ScriptFormat) -> Result<Script, String> {
+    let bytes = fs::read(filename)
+        .map_err(|e| format!("Could not read script file: {} - Error: {}", filename, e))?;
+    match format {
+        ScriptFormat::Json => serde_json::from_slice(&bytes).map_err(|e| e.to_string()),
+        ScriptFormat::Cbor => serde_cbor::from_slice(&bytes).map_err(|e| e.to_string()),
+    }
+    .map_err(|e| format!("Failed to parse script {}: {}", filename, e))
+}
+
+/// The replica that the calls of a script are executed on.
+pub(crate) trait ScriptTarget {
+    /// Executes the ingress message `msg` in batches with the given `time`
+    /// and returns its result.
+    fn execute_ingress(&self, msg: SignedIngress, time: Time) -> Result<WasmResult, UserError>;
+
+    /// Executes `query` on the latest state.
+    fn query(&self, query: UserQuery) -> Result<WasmResult, UserError>;
+}
+
+/// Executes calls by delivering batches to the Message Routing layer.
+pub(crate) struct MessageRoutingTarget<'a> {
+    pub(crate) message_routing: &'a dyn MessageRouting,
+    pub(crate) ingress_history: &'a dyn IngressHistoryReader,
+    pub(crate) query: &'a dyn Fn(UserQuery) -> Result<WasmResult, UserError>,
+    pub(crate) extra_batches: u64,
+}
+
+impl ScriptTarget for MessageRoutingTarget<'_> {
+    fn execute_ingress(&self, msg: SignedIngress, time: Time) -> Result<WasmResult, UserError> {
+        let msg_id = msg.id();
+        let result = execute_ingress_message(
+            self.message_routing,
+            msg,
+            &msg_id,
+            self.ingress_history,
+            time,
+        );
+        wait_extra_batches(self.message_routing, self.extra_batches, time);
+        result
+    }
+
+    fn query(&self, query: UserQuery) -> Result<WasmResult, UserError> {
+        (self.query)(query)
+    }
+}
+
+/// Executes the steps of a script against a `ScriptTarget`.
+pub(crate) struct ScriptRunner<'a> {
+    target: &'a dyn ScriptTarget,
+    time: Time,
+    nonce: u64,
+    variables: BTreeMap<String, CanisterId>,
+}
+
+impl<'a> ScriptRunner<'a> {
+    pub(crate) fn new(target: &'a dyn ScriptTarget) -> Self {
+        Self {
+            target,
+            time: UNIX_EPOCH,
+            nonce: 0,
+            variables: BTreeMap::new(),
+        }
+    }
+
+    /// Runs all steps of `script` and prints the report to stdout.
+    ///
+    /// A step that cannot be executed aborts the script. Returns an error if
+    /// any step did not pass.
+    pub(crate) fn run(self, script: Script) -> Result<(), String> {
+        let report = self.execute(script);
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?
+        );
+        let failed = report.steps.iter().filter(|step| !step.passed).count();
+        if failed == 0 {
+            Ok(())
+        } else {
+            Err(format!("{} step(s) of the script failed", failed))
+        }
+    }
+
+    /// Runs all steps of `script` and returns the report.
+    fn execute(mut self, script: Script) -> Report {
+        let mut steps = Vec::new();
+        for (i, step) in script.steps.into_iter().enumerate() {
+            let report = self.run_step(i, step);
+            let aborted = report.result.is_none() && !report.passed;
+            steps.push(report);
+            if aborted {
+                break;
+            }
+        }
+
+        Report {
+            passed: steps.iter().all(|step| step.passed),
+            steps,
+        }
+    }
+
+    fn run_step(&mut self, i: usize, step: Step) -> StepReport {
+        let mut report = StepReport {
+            step: i,
+            kind: step.kind(),
+            canister_id: None,
+            result: None,
+            error: None,
+            passed: true,
+        };
+
+        let outcome = match step {
+            Step::Create { bind, cycles } => self.create(bind, cycles, &mut report),
+            Step::Install {
+                canister,
+                wasm,
+                mode,
+                arg,
+            } => self.install(&canister, &wasm, mode, arg, &mut report),
+            Step::Ingress {
+                canister,
+                method,
+                arg,
+                sender,
+                expect,
+            } => self.call(&canister, method, arg, sender, expect, false, &mut report),
+            Step::Query {
+                canister,
+                method,
+                arg,
+                sender,
+                expect,
+            } => self.call(&canister, method, arg, sender, expect, true, &mut report),
+            Step::AdvanceTime { nanos } => {
+                self.time = self.time + Duration::from_nanos(nanos);
+                Ok(())
+            }
+            Step::TopUp { canister, cycles } => self.top_up(&canister, cycles, &mut report),
+        };
+
+        if let Err(err) = outcome {
+            report.passed = false;
+            report.error = Some(err);
+        }
+        report
+    }
+
+    fn create(
+        &mut self,
+        bind: Option<String>,
+        cycles: Option<u64>,
+        report: &mut StepReport,
+    ) -> Result<(), String> {
+        let result = self.execute_ingress(
+            None,
+            ic00::IC_00,
+            ic00::Method::ProvisionalCreateCanisterWithCycles.to_string(),
+            ProvisionalCreateCanisterWithCyclesArgs::new(cycles.map(u128::from)).encode(),
+        );
+        report.result = Some(CallResult::from(&result));
+        let canister_id = match result {
+            Ok(WasmResult::Reply(bytes)) => CanisterIdRecord::decode(&bytes)
+                .map_err(|e| format!("Failed to decode the canister ID: {}", e))?
+                .get_canister_id(),
+            _ => return Err("Failed to create a canister".to_string()),
+        };
+        report.canister_id = Some(canister_id.to_string());
+        if let Some(name) = bind {
+            self.variables.insert(name, canister_id);
+        }
+        Ok(())
+    }
+
+    fn install(
+        &mut self,
+        canister: &str,
+        wasm: &Path,
+        mode: CanisterInstallMode,
+        arg: Option<Argument>,
+        report: &mut StepReport,
+    ) -> Result<(), String> {
+        let canister_id = self.canister_id(canister)?;
+        report.canister_id = Some(canister_id.to_string());
+        let wasm_module = fs::read(wasm).map_err(|e| {
+            format!(
+                "Could not open wasm file: {} - Error: {}",
+                wasm.display(),
+                e
+            )
+        })?;
+        let arg = self.encode_argument(arg)?;
+
+        let result = self.execute_ingress(
+            None,
+            ic00::IC_00,
+            ic00::Method::InstallCode.to_string(),
+            InstallCodeArgs::new(
+                mode,
+                canister_id,
+                wasm_module,
+                arg,
+                None,
+                Some(8 * 1024 * 1024 * 1024), // drun users dont care about memory limits
+                None,
+            )
+            .encode(),
+        );
+        report.result = Some(CallResult::from(&result));
+        match result {
+            Ok(WasmResult::Reply(_)) => Ok(()),
+            _ => Err(format!(
+                "Failed to install code on canister {}",
+                canister_id
+            )),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn call(
+        &mut self,
+        canister: &str,
+        method: String,
+        arg: Option<Argument>,
+        sender: Option<String>,
+        expect: Option<Expectation>,
+        is_query: bool,
+        report: &mut StepReport,
+    ) -> Result<(), String> {
+        let canister_id = self.canister_id(canister)?;
+        report.canister_id = Some(canister_id.to_string());
+        let sender = sender
+            .map(|sender| self.principal_id(&sender).map(UserId::from))
+            .transpose()?;
+        let arg = self.encode_argument(arg)?;
+
+        let result = if is_query {
+            self.nonce += 1;
+            self.target.query(UserQuery {
+                source: sender.unwrap_or_else(|| UserId::from(PrincipalId::new_anonymous())),
+                receiver: canister_id,
+                method_name: method,
+                method_payload: arg,
+                ingress_expiry: current_time_and_expiry_time().1.as_nanos_since_unix_epoch(),
+                nonce: Some(self.nonce.to_le_bytes().to_vec()),
+            })
+        } else {
+            self.execute_ingress(sender, canister_id, method, arg)
+        };
+        report.result = Some(CallResult::from(&result));
+        match expect {
+            Some(expectation) => self.check(&expectation, &result),
+            None => Ok(()),
+        }
+    }
+
+    fn top_up(
+        &mut self,
+        canister: &str,
+        cycles: u64,
+        report: &mut StepReport,
+    ) -> Result<(), String> {
+        let canister_id = self.canister_id(canister)?;
+        report.canister_id = Some(canister_id.to_string());
+        let result = self.execute_ingress(
+            None,
+            ic00::IC_00,
+            ic00::Method::ProvisionalTopUpCanister.to_string(),
+            ProvisionalTopUpCanisterArgs::new(canister_id, u128::from(cycles)).encode(),
+        );
+        report.result = Some(CallResult::from(&result));
+        match result {
+            Ok(WasmResult::Reply(_)) => Ok(()),
+            _ => Err(format!("Failed to top up canister {}", canister_id)),
+        }
+    }
+
+    fn execute_ingress(
+        &mut self,
+        sender: Option<UserId>,
+        canister_id: CanisterId,
+        method: String,
+        payload: Vec<u8>,
+    ) -> Result<WasmResult, UserError> {
+        self.nonce += 1;
+        let mut builder = SignedIngressBuilder::new()
+            .canister_id(canister_id)
+            .method_name(method)
+            .method_payload(payload)
+            .nonce(self.nonce);
+        if let Some(sender) = sender {
+            builder = builder.sender(sender);
+        }
+        self.target.execute_ingress(builder.build(), self.time)
+    }
+
+    /// Checks that `result` matches `expectation`.
+    fn check(
+        &self,
+        expectation: &Expectation,
+        result: &Result<WasmResult, UserError>,
+    ) -> Result<(), String> {
+        match (expectation, CallResult::from(result)) {
+            (Expectation::Reply(expected), CallResult::Reply { .. }) => {
+                let actual = match result {
+                    Ok(WasmResult::Reply(bytes)) => bytes,
+                    _ => unreachable!("only replies are reported as such"),
+                };
+                let expected_bytes = self.encode_argument(Some(expected.clone()))?;
+                if &expected_bytes == actual || self.candid_matches(expected, actual)? {
+                    Ok(())
+                } else {
+                    Err(format!("Expected reply 0x{}", encode(expected_bytes)))
+                }
+            }
+            (Expectation::Reply(_), CallResult::Reject { code, message }) => Err(format!(
+                "Expected a reply, got reject {}: {}",
+                code, message
+            )),
+            (Expectation::Reject(expected), CallResult::Reject { code, .. }) => {
+                if *expected == code {
+                    Ok(())
+                } else {
+                    Err(format!("Expected reject code {}, got {}", expected, code))
+                }
+            }
+            (Expectation::Reject(expected), CallResult::Reply { hex, .. }) => Err(format!(
+                "Expected reject code {}, got reply 0x{}",
+                expected, hex
+            )),
+        }
+    }
+
+    /// Returns true if `expected` is a Candid argument with the same values
+    /// as the Candid encoded `actual` bytes.
+    fn candid_matches(&self, expected: &Argument, actual: &[u8]) -> Result<bool, String> {
+        match expected {
+            Argument::Candid(text) => {
+                let expected = self.parse_candid(text)?;
+                Ok(IDLArgs::from_bytes(actual)
+                    .map(|actual| actual.args == expected.args)
+                    .unwrap_or(false))
+            }
+            Argument::Hex(_) | Argument::Text(_) => Ok(false),
+        }
+    }
+
+    fn encode_argument(&self, arg: Option<Argument>) -> Result<Vec<u8>, String> {
+        match arg {
+            None => Ok(vec![]),
+            Some(Argument::Candid(text)) => self
+                .parse_candid(&text)?
+                .to_bytes()
+                .map_err(|e| format!("Failed to encode Candid {}: {}", text, e)),
+            Some(Argument::Hex(hex)) => hex::decode(hex.trim_start_matches("0x"))
+                .map_err(|e| format!("Illegal hex character sequence {}: {}", hex, e)),
+            Some(Argument::Text(text)) => Ok(text.into_bytes()),
+        }
+    }
+
+    fn parse_candid(&self, text: &str) -> Result<IDLArgs, String> {
+        let text = self.substitute(text)?;
+        text.parse::<IDLArgs>()
+            .map_err(|e| format!("Failed to parse Candid {}: {}", text, e))
+    }
+
+    fn canister_id(&self, canister: &str) -> Result<CanisterId, String> {
+        parse_canister_id(&self.substitute(canister)?)
+    }
+
+    fn principal_id(&self, principal: &str) -> Result<PrincipalId, String> {
+        use std::str::FromStr;
+        let principal = self.substitute(principal)?;
+        PrincipalId::from_str(&principal)
+            .map_err(|e| format!("Failed to convert {} to principal id with {}", principal, e))
+    }
+
+    /// Replaces all occurrences of `${name}` in `text` with the textual
+    /// representation of the canister ID bound to `name`.
+    fn substitute(&self, text: &str) -> Result<String, String> {
+        let mut result = String::new();
+        let mut rest = text;
+        while let Some(start) = rest.find("${") {
+            let end = rest[start..]
+                .find('}')
+                .ok_or_else(|| format!("Unterminated variable in {}", text))?;
+            let name = &rest[start + 2..start + end];
+            let canister_id = self
+                .variables
+                .get(name)
+                .ok_or_else(|| format!("Unbound variable {}", name))?;
+            result.push_str(&rest[..start]);
+            result.push_str(&canister_id.to_string());
+            rest = &rest[start + end + 1..];
+        }
+        result.push_str(rest);
+        Ok(result)
+    }
+}
+
+impl Step {
+    fn kind(&self) -> &'static str {
+        match self {
+            Step::Create { .. } => "create",
+            Step::Install { .. } => "install",
+            Step::Ingress { .. } => "ingress",
+            Step::Query { .. } => "query",
+            Step::AdvanceTime { .. } => "advance_time",
+            Step::TopUp { .. } => "top_up",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_state_machine_tests::StateMachine;
+    use std::time::SystemTime;
+
+    /// A canister that echoes the argument of `echo` (and the query
+    /// `echo_query`) and traps in `fail`.
+    const ECHO_WAT: &str = r#"
+        (module
+          (import "ic0" "msg_arg_data_size" (func $msg_arg_data_size (result i32)))
+          (import "ic0" "msg_arg_data_copy" (func $msg_arg_data_copy (param i32 i32 i32)))
+          (import "ic0" "msg_reply_data_append" (func $msg_reply_data_append (param i32 i32)))
+          (import "ic0" "msg_reply" (func $msg_reply))
+          (import "ic0" "trap" (func $trap (param i32 i32)))
+          (func $echo
+            (call $msg_arg_data_copy (i32.const 0) (i32.const 0) (call $msg_arg_data_size))
+            (call $msg_reply_data_append (i32.const 0) (call $msg_arg_data_size))
+            (call $msg_reply))
+          (func $fail
+            (call $trap (i32.const 0) (i32.const 0)))
+          (memory 1)
+          (export "canister_update echo" (func $echo))
+          (export "canister_query echo_query" (func $echo))
+          (export "canister_update fail" (func $fail)))"#;
+
+    impl ScriptTarget for StateMachine {
+        fn execute_ingress(&self, msg: SignedIngress, time: Time) -> Result<WasmResult, UserError> {
+            self.set_time(
+                SystemTime::UNIX_EPOCH + Duration::from_nanos(time.as_nanos_since_unix_epoch()),
+            );
+            let msg_id = msg.id();
+            self.send_signed_ingress(msg);
+            self.await_ingress(msg_id, 100)
+        }
+
+        fn query(&self, query: UserQuery) -> Result<WasmResult, UserError> {
+            self.query_as(
+                query.source.get(),
+                query.receiver,
+                query.method_name,
+                query.method_payload,
+            )
+        }
+    }
+
+    /// Runs the JSON script `script` on a fresh state machine. `${wasm}` in
+    /// the script is replaced with the path of the echo canister's module.
+    fn run_script(script: &str) -> Report {
+        let dir = tempfile::tempdir().unwrap();
+        let wasm = dir.path().join("echo.wasm");
+        fs::write(&wasm, wabt::wat2wasm(ECHO_WAT).unwrap()).unwrap();
+        let script: Script =
+            serde_json::from_str(&script.replace("${wasm}", wasm.to_str().unwrap())).unwrap();
+
+        let env = StateMachine::new();
+        ScriptRunner::new(&env).execute(script)
+    }
+
+    fn errors(report: &Report) -> Vec<Option<&str>> {
+        report
+            .steps
+            .iter()
+            .map(|step| step.error.as_deref())
+            .collect()
+    }
+
+    #[test]
+    fn test_run_passing_script() {
+        let report = run_script(
+            r#"{
+                "steps": [
+                    {"type": "create", "bind": "echo", "cycles": 1000000000000},
+                    {"type": "install", "canister": "${echo}", "wasm": "${wasm}"},
+                    {"type": "ingress", "canister": "${echo}", "method": "echo",
+                     "arg": {"text": "hello"}, "expect": {"reply": {"hex": "68656c6c6f"}}},
+                    {"type": "ingress", "canister": "${echo}", "method": "echo",
+                     "arg": {"candid": "(42 : nat, principal \"${echo}\")"},
+                     "expect": {"reply": {"candid": "(42 : nat, principal \"${echo}\")"}}},
+                    {"type": "query", "canister": "${echo}", "method": "echo_query",
+                     "arg": {"hex": "0x0102"}, "expect": {"reply": {"hex": "0102"}}},
+                    {"type": "ingress", "canister": "${echo}", "method": "fail",
+                     "expect": {"reject": 5}},
+                    {"type": "advance_time", "nanos": 1000000000},
+                    {"type": "top_up", "canister": "${echo}", "cycles": 500},
+                    {"type": "ingress", "canister": "${echo}", "method": "echo"}
+                ]
+            }"#,
+        );
+
+        assert_eq!(errors(&report), vec![None; 9]);
+        assert!(report.passed);
+        let canister_id = report.steps[0].canister_id.clone();
+        assert!(canister_id.is_some());
+        assert!(report.steps[1..]
+            .iter()
+            .filter(|step| step.kind != "advance_time")
+            .all(|step| step.canister_id == canister_id));
+        assert_eq!(
+            report.steps[2].result,
+            Some(CallResult::Reply {
+                hex: "68656c6c6f".to_string(),
+                candid: None,
+            })
+        );
+        assert!(matches!(
+            report.steps[5].result,
+            Some(CallResult::Reject { code: 5, .. })
+        ));
+    }
+
+    #[test]
+    fn test_run_script_with_failed_expectations() {
+        let report = run_script(
+            r#"{
+                "steps": [
+                    {"type": "create", "bind": "echo", "cycles": 1000000000000},
+                    {"type": "install", "canister": "${echo}", "wasm": "${wasm}"},
+                    {"type": "ingress", "canister": "${echo}", "method": "echo",
+                     "arg": {"text": "hello"}, "expect": {"reply": {"text": "bye"}}},
+                    {"type": "ingress", "canister": "${echo}", "method": "echo",
+                     "arg": {"text": "hello"}, "expect": {"reject": 4}},
+                    {"type": "ingress", "canister": "${echo}", "method": "fail",
+                     "expect": {"reply": {"text": "hello"}}},
+                    {"type": "ingress", "canister": "${echo}", "method": "fail",
+                     "expect": {"reject": 4}},
+                    {"type": "query", "canister": "${echo}", "method": "echo_query",
+                     "arg": {"candid": "(1 : nat)"}, "expect": {"reply": {"candid": "(2 : nat)"}}},
+                    {"type": "ingress", "canister": "${echo}", "method": "echo",
+                     "arg": {"text": "still running"}}
+                ]
+            }"#,
+        );
+
+        assert!(!report.passed);
+        let errors = errors(&report);
+        assert_eq!(errors.len(), 8, "failed expectations do not abort");
+        assert_eq!(&errors[..2], &[None, None]);
+        assert_eq!(errors[2], Some("Expected reply 0x627965"));
+        assert_eq!(
+            errors[3],
+            Some("Expected reject code 4, got reply 0x68656c6c6f")
+        );
+        assert!(errors[4]
+            .unwrap()
+            .starts_with("Expected a reply, got reject 5: "));
+        assert_eq!(errors[5], Some("Expected reject code 4, got 5"));
+        assert!(errors[6].unwrap().starts_with("Expected reply 0x"));
+        assert_eq!(errors[7], None);
+        // The results of failed calls are still reported.
+        assert!(report.steps[2..7].iter().all(|step| step.result.is_some()));
+        assert!(report
+            .steps
+            .iter()
+            .map(|step| step.passed)
+            .eq(vec![true, true, false, false, false, false, false, true]));
+    }
+
+    #[test]
+    fn test_run_script_aborts_on_errors() {
+        // A step that cannot be executed aborts the script.
+        let report = run_script(
+            r#"{
+                "steps": [
+                    {"type": "create", "bind": "echo", "cycles": 1000000000000},
+                    {"type": "ingress", "canister": "${unknown}", "method": "echo"},
+                    {"type": "ingress", "canister": "${echo}", "method": "echo"}
+                ]
+            }"#,
+        );
+        assert!(!report.passed);
+        assert_eq!(
+            errors(&report),
+            vec![None, Some("Unbound variable unknown")]
+        );
+        assert_eq!(report.steps[1].result, None);
+
+        let report = run_script(
+            r#"{
+                "steps": [
+                    {"type": "create", "bind": "echo", "cycles": 1000000000000},
+                    {"type": "install", "canister": "${echo}", "wasm": "missing.wasm"},
+                    {"type": "ingress", "canister": "${echo}", "method": "echo"}
+                ]
+            }"#,
+        );
+        assert!(!report.passed);
+        assert_eq!(report.steps.len(), 2);
+        assert!(report.steps[1]
+            .error
+            .as_ref()
+            .unwrap()
+            .starts_with("Could not open wasm file: missing.wasm"));
+
+        // Calls that are rejected without an expectation pass, while failed
+        // installations fail without aborting the script. Arguments that
+        // cannot be encoded abort it.
+        let report = run_script(
+            r#"{
+                "steps": [
+                    {"type": "create", "bind": "echo", "cycles": 1000000000000},
+                    {"type": "ingress", "canister": "${echo}", "method": "echo"},
+                    {"type": "install", "canister": "${echo}", "wasm": "${wasm}"},
+                    {"type": "install", "canister": "${echo}", "wasm": "${wasm}"},
+                    {"type": "ingress", "canister": "${echo}", "method": "echo"},
+                    {"type": "ingress", "canister": "${echo}", "method": "echo",
+                     "arg": {"candid": "(42"}},
+                    {"type": "ingress", "canister": "${echo}", "method": "echo"}
+                ]
+            }"#,
+        );
+        assert!(!report.passed);
+        let errors = errors(&report);
+        assert_eq!(errors.len(), 6);
+        assert_eq!(&errors[..3], &[None, None, None]);
+        assert!(matches!(
+            report.steps[1].result,
+            Some(CallResult::Reject { .. })
+        ));
+        assert!(errors[3]
+            .unwrap()
+            .starts_with("Failed to install code on canister "));
+        assert!(report.steps[3].result.is_some());
+        assert_eq!(errors[4], None);
+        assert!(errors[5].unwrap().starts_with("Failed to parse Candid (42"));
+        assert_eq!(report.steps[5].result, None);
+    }
+
+    #[test]
+    fn test_parse_json_script() {
+        let script: Script = serde_json::from_str(
+            r#"{
+                "steps": [
+                    {"type": "create", "bind": "ledger", "cycles": 1000},
+                    {"type": "install", "canister": "${ledger}", "wasm": "ledger.wasm",
+                     "mode": "reinstall", "arg": {"candid": "()"}},
+                    {"type": "ingress", "canister": "${ledger}", "method": "write",
+                     "arg": {"hex": "0x0102"}, "expect": {"reply": {"text": "ok"}}},
+                    {"type": "query", "canister": "${ledger}", "method": "read",
+                     "expect": {"reject": 4}},
+                    {"type": "advance_time", "nanos": 1000000000},
+                    {"type": "top_up", "canister": "${ledger}", "cycles": 500}
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            script.steps,
+            vec![
+                Step::Create {
+                    bind: Some("ledger".to_string()),
+                    cycles: Some(1000),
+                },
+                Step::Install {
+                    canister: "${ledger}".to_string(),
+                    wasm: PathBuf::from("ledger.wasm"),
+                    mode: CanisterInstallMode::Reinstall,
+                    arg: Some(Argument::Candid("()".to_string())),
+                },
+                Step::Ingress {
+                    canister: "${ledger}".to_string(),
+                    method: "write".to_string(),
+                    arg: Some(Argument::Hex("0x0102".to_string())),
+                    sender: None,
+                    expect: Some(Expectation::Reply(Argument::Text("ok".to_string()))),
+                },
+                Step::Query {
+                    canister: "${ledger}".to_string(),
+                    method: "read".to_string(),
+                    arg: None,
+                    sender: None,
+                    expect: Some(Expectation::Reject(4)),
+                },
+                Step::AdvanceTime {
+                    nanos: 1_000_000_000
+                },
+                Step::TopUp {
+                    canister: "${ledger}".to_string(),
+                    cycles: 500,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_script_with_unknown_step_fails() {
+        assert!(serde_json::from_str::<Script>(r#"{"steps": [{"type": "delete"}]}"#).is_err());
+        assert!(
+            serde_json::from_str::<Script>(r#"{"steps": [{"type": "create", "foo": 1}]}"#).is_err()
+        );
+    }
+
+    #[test]
+    fn test_script_format_from_filename() {
+        assert_eq!(
+            ScriptFormat::from_filename("test.json"),
+            Some(ScriptFormat::Json)
+        );
+        assert_eq!(
+            ScriptFormat::from_filename("dir/test.cbor"),
+            Some(ScriptFormat::Cbor)
+        );
+        assert_eq!(ScriptFormat::from_filename("test.txt"), None);
+    }
+}
@@ -0,0 +1,595 @@
+//! A content-addressed cache of compiled Wasm modules on disk.
+//!
+//! Compiling canister modules with Wasmtime is expensive and the in-memory
+//! `EmbedderCache` of a canister is lost whenever the replica restarts or a
+//! state is loaded from a checkpoint. This cache stores the serialized
+//! compilation result on disk so that it can be reused by later replica
+//! processes as well as by the canister sandbox processes.
+//!
+//! An entry is keyed by the hash of the (instrumented) Wasm binary, the
+//! Wasmtime settings and the parts of the embedder configuration affecting
+//! compilation, and the Wasmtime version. Each entry starts with a header
+//! holding the SHA-256 hash of the serialized module and the time it took to
+//! compile it, so that corrupted entries are detected and the saved
+//! compilation time can be reported. When the total size of the entries
+//! exceeds the capacity of the cache, the least recently used entries are
+//! removed.
+//!
+//! Only the replica writes to the cache. The sandbox processes open it
+//! read-only: they load the entries written by the replica, but never add,
+//! remove or touch entries. A sandbox that compiles a module missing from the
+//! cache returns the serialized module to the replica, which adds it to the
+//! cache under a key it derives from the canister's Wasm module itself. A
+//! sandbox therefore cannot write entries for modules it was not asked to
+//! compile.
+
+use std::ffi::CString;
+use std::fs;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use ic_config::embedders::Config as EmbeddersConfig;
+use ic_crypto_sha::Sha256;
+use ic_interfaces::execution_environment::{HypervisorError, HypervisorResult};
+use ic_logger::{warn, ReplicaLogger};
+use ic_metrics::MetricsRegistry;
+use ic_types::NumBytes;
+use ic_wasm_types::{BinaryEncodedWasm, SerializedModule, WasmEngineError};
+use prometheus::{Counter, IntCounter, IntGauge};
+
+use crate::wasm_utils::decoding::decode_wasm;
+use crate::wasm_utils::instrumentation::{instrument, InstructionCostTable};
+use crate::wasm_utils::validation::{validate_wasm_binary, DETERMINISTIC_SETTINGS};
+use crate::wasmtime_embedder::STATIC_MEMORY_MAXIMUM_SIZE;
+
+/// The version of Wasmtime used to compile the cached modules. Must match the
+/// version in `Cargo.toml`. Wasmtime refuses to deserialize modules compiled
+/// by a different version, so a mismatch only results in cache misses.
+const WASMTIME_VERSION: &str = "0.35.3";
+
+/// The version of the format of cache entries. Must be incremented whenever
+/// the format of the entries or the compilation settings not covered by
+/// `entry_path` change.
+const ENTRY_FORMAT_VERSION: u32 = 2;
+
+/// The size of the header of an entry: the SHA-256 hash of the serialized
+/// module followed by the compilation duration in nanoseconds.
+const HEADER_SIZE: usize = 32 + 8;
+
+/// The suffix of the files holding cache entries.
+const ENTRY_SUFFIX: &str = "module";
+
+struct CompilationCacheMetrics {
+    hits: IntCounter,
+    misses: IntCounter,
+    errors: IntCounter,
+    compilation_time_saved: Counter,
+    size: IntGauge,
+}
+
+impl CompilationCacheMetrics {
+    fn new(metrics_registry: &MetricsRegistry) -> Self {
+        Self {
+            hits: metrics_registry.int_counter(
+                "execution_wasm_compilation_cache_hits_total",
+                "The number of compiled Wasm modules loaded from the on-disk cache",
+            ),
+            misses: metrics_registry.int_counter(
+                "execution_wasm_compilation_cache_misses_total",
+                "The number of Wasm modules that were not found in the on-disk cache",
+            ),
+            errors: metrics_registry.int_counter(
+                "execution_wasm_compilation_cache_errors_total",
+                "The number of corrupted or unreadable entries of the on-disk cache",
+            ),
+            compilation_time_saved: metrics_registry.register(
+                Counter::new(
+                    "execution_wasm_compilation_cache_time_saved_seconds_total",
+                    "The Wasm compilation time saved by loading modules from the on-disk cache",
+                )
+                .unwrap(),
+            ),
+            size: metrics_registry.int_gauge(
+                "execution_wasm_compilation_cache_size_bytes",
+                "The total size of the entries of the on-disk cache",
+            ),
+        }
+    }
+}
+
+/// An on-disk cache of compiled Wasm modules.
+pub struct CompilationCache {
+    dir: PathBuf,
+    capacity: NumBytes,
+    // Not set for read-only caches, whose metrics would not be exported.
+    metrics: Option<CompilationCacheMetrics>,
+    read_only: bool,
+    // Serializes the eviction within this process. Concurrent evictions by
+    // other processes are harmless, as removing an entry twice is ignored.
+    eviction_lock: Mutex<()>,
+    log: ReplicaLogger,
+}
+
+impl CompilationCache {
+    /// Creates a cache storing its entries in the given directory, which is
+    /// created if it does not exist.
+    pub fn new(
+        dir: PathBuf,
+        capacity: NumBytes,
+        metrics_registry: &MetricsRegistry,
+        log: ReplicaLogger,
+    ) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        let cache = Self {
+            dir,
+            capacity,
+            metrics: Some(CompilationCacheMetrics::new(metrics_registry)),
+            read_only: false,
+            eviction_lock: Mutex::new(()),
+            log,
+        };
+        cache.evict();
+        Ok(cache)
+    }
+
+    /// Opens the cache in the given directory for reading only. Modules that
+    /// are not in the cache are compiled and returned serialized instead of
+    /// being added, and corrupted entries are ignored instead of removed. Used
+    /// by the sandbox processes.
+    pub fn open_read_only(dir: PathBuf, log: ReplicaLogger) -> Self {
+        Self {
+            dir,
+            capacity: NumBytes::new(0),
+            metrics: None,
+            read_only: true,
+            eviction_lock: Mutex::new(()),
+            log,
+        }
+    }
+
+    /// Returns the compiled module of the given Wasm binary from the cache or
+    /// compiles it using the given engine. A newly compiled module is added
+    /// to the cache, or returned serialized if the cache is read-only.
+    pub(crate) fn get_or_compile(
+        &self,
+        engine: &wasmtime::Engine,
+        wasm_binary: &BinaryEncodedWasm,
+        config: &EmbeddersConfig,
+    ) -> HypervisorResult<(wasmtime::Module, Option<SerializedModule>)> {
+        let path = self.entry_path(wasm_binary, config);
+        if let Some(module) = self.load(engine, &path) {
+            return Ok((module, None));
+        }
+        self.observe(|metrics| metrics.misses.inc());
+
+        let timer = Instant::now();
+        let module = compile_module(engine, wasm_binary)?;
+        let compilation_time = timer.elapsed();
+        let serialized_module = match module.serialize() {
+            Ok(bytes) => SerializedModule {
+                bytes,
+                compilation_time,
+            },
+            Err(err) => {
+                self.observe(|metrics| metrics.errors.inc());
+                warn!(
+                    self.log,
+                    "Failed to serialize compiled Wasm module: {}", err
+                );
+                return Ok((module, None));
+            }
+        };
+        if self.read_only {
+            return Ok((module, Some(serialized_module)));
+        }
+        self.store(&path, &serialized_module.bytes, compilation_time);
+        Ok((module, None))
+    }
+
+    /// Adds a module that a sandbox process compiled from the given canister
+    /// Wasm module to the cache. The key of the entry is derived from
+    /// `wasm_source` rather than taken from the sandbox, by validating and
+    /// instrumenting it the same way as the sandbox did before compiling.
+    pub fn insert(
+        &self,
+        wasm_source: &[u8],
+        config: &EmbeddersConfig,
+        serialized_module: &SerializedModule,
+    ) {
+        if self.read_only {
+            return;
+        }
+        // The sandbox compiled the module because it was not in the cache.
+        self.observe(|metrics| metrics.misses.inc());
+        let instrumented = decode_wasm(Arc::new(wasm_source.to_vec()))
+            .and_then(|wasm| {
+                validate_wasm_binary(&wasm, config)?;
+                Ok(wasm)
+            })
+            .map_err(HypervisorError::from)
+            .and_then(|wasm| {
+                instrument(
+                    &wasm,
+                    &InstructionCostTable::new(),
+                    config.cost_to_compile_wasm_instruction,
+                )
+                .map_err(HypervisorError::from)
+            });
+        match instrumented {
+            Ok(output) => {
+                let path = self.entry_path(&output.binary, config);
+                self.store(
+                    &path,
+                    &serialized_module.bytes,
+                    serialized_module.compilation_time,
+                );
+            }
+            Err(err) => {
+                self.observe(|metrics| metrics.errors.inc());
+                warn!(
+                    self.log,
+                    "Not caching a module compiled by a sandbox: {}", err
+                );
+            }
+        }
+    }
+
+    // Updates the metrics, unless the cache has none.
+    fn observe(&self, f: impl FnOnce(&CompilationCacheMetrics)) {
+        if let Some(metrics) = &self.metrics {
+            f(metrics);
+        }
+    }
+
+    fn entry_path(&self, wasm_binary: &BinaryEncodedWasm, config: &EmbeddersConfig) -> PathBuf {
+        let mut hasher = Sha256::new();
+        hasher.write(WASMTIME_VERSION.as_bytes());
+        hasher.write(&ENTRY_FORMAT_VERSION.to_le_bytes());
+        hasher.write(format!("{:?}", DETERMINISTIC_SETTINGS).as_bytes());
+        hasher.write(&STATIC_MEMORY_MAXIMUM_SIZE.to_le_bytes());
+        hasher.write(&(config.max_wasm_stack_size as u64).to_le_bytes());
+        hasher.write(&config.max_wasm64_memory_size.get().to_le_bytes());
+        hasher.write(format!("{:?}", config.feature_flags).as_bytes());
+        hasher.write(wasm_binary.as_slice());
+        self.dir
+            .join(format!("{}.{}", hex::encode(hasher.finish()), ENTRY_SUFFIX))
+    }
+
+    // Loads the module stored in the given entry. Corrupted entries and
+    // entries that cannot be deserialized are removed, unless the cache is
+    // read-only.
+    fn load(&self, engine: &wasmtime::Engine, path: &Path) -> Option<wasmtime::Module> {
+        let entry = match fs::read(path) {
+            Ok(entry) => entry,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return None,
+            Err(err) => {
+                self.observe(|metrics| metrics.errors.inc());
+                warn!(self.log, "Failed to read {}: {}", path.display(), err);
+                return None;
+            }
+        };
+        let (compilation_time, serialized_module) = match decode_entry(&entry) {
+            Some(decoded) => decoded,
+            None => {
+                self.observe(|metrics| metrics.errors.inc());
+                warn!(self.log, "Ignoring corrupted entry {}", path.display());
+                self.remove(path);
+                return None;
+            }
+        };
+        // SAFETY: Only the replica writes entries, holding modules produced
+        // using `wasmtime::Module::serialize` by itself or by the sandbox that
+        // was asked to compile the corresponding Wasm module. The integrity
+        // check above ensures that the entry was not truncated or otherwise
+        // modified. Wasmtime additionally checks that the module was compiled
+        // by the same version and with compatible settings.
+        match unsafe { wasmtime::Module::deserialize(engine, serialized_module) } {
+            Ok(module) => {
+                self.observe(|metrics| {
+                    metrics.hits.inc();
+                    metrics
+                        .compilation_time_saved
+                        .inc_by(compilation_time.as_secs_f64());
+                });
+                if !self.read_only {
+                    touch(path);
+                }
+                Some(module)
+            }
+            Err(err) => {
+                self.observe(|metrics| metrics.errors.inc());
+                warn!(
+                    self.log,
+                    "Ignoring incompatible entry {}: {}",
+                    path.display(),
+                    err
+                );
+                self.remove(path);
+                None
+            }
+        }
+    }
+
+    // Removes the given entry, unless the cache is read-only.
+    fn remove(&self, path: &Path) {
+        if !self.read_only {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    fn store(&self, path: &Path, serialized_module: &[u8], compilation_time: Duration) {
+        // Write to a temporary file first and rename it, so that readers never
+        // observe partially written entries.
+        let tmp_path = path.with_extension(format!("{}.tmp", std::process::id()));
+        let result = fs::write(&tmp_path, encode_entry(serialized_module, compilation_time))
+            .and_then(|()| fs::rename(&tmp_path, path));
+        if let Err(err) = result {
+            self.observe(|metrics| metrics.errors.inc());
+            warn!(self.log, "Failed to store {}: {}", path.display(), err);
+            let _ = fs::remove_file(&tmp_path);
+            return;
+        }
+        self.evict();
+    }
+
+    // Removes the least recently used entries until the total size of the
+    // entries is within the capacity.
+    fn evict(&self) {
+        let _guard = self.eviction_lock.lock().unwrap();
+        let mut entries: Vec<_> = match fs::read_dir(&self.dir) {
+            Ok(dir) => dir
+                .filter_map(|entry| {
+                    let entry = entry.ok()?;
+                    let path = entry.path();
+                    if path.extension()? != ENTRY_SUFFIX {
+                        return None;
+                    }
+                    let metadata = entry.metadata().ok()?;
+                    Some((metadata.modified().ok()?, metadata.len(), path))
+                })
+                .collect(),
+            Err(err) => {
+                self.observe(|metrics| metrics.errors.inc());
+                warn!(self.log, "Failed to read {}: {}", self.dir.display(), err);
+                return;
+            }
+        };
+        entries.sort();
+        let mut size: u64 = entries.iter().map(|(_, len, _)| len).sum();
+        for (_, len, path) in entries {
+            if size <= self.capacity.get() {
+                break;
+            }
+            if fs::remove_file(&path).is_ok() {
+                size -= len;
+            }
+        }
+        self.observe(|metrics| metrics.size.set(size as i64));
+    }
+}
+
+/// Compiles the given Wasm binary using the given engine.
+pub(crate) fn compile_module(
+    engine: &wasmtime::Engine,
+    wasm_binary: &BinaryEncodedWasm,
+) -> HypervisorResult<wasmtime::Module> {
+    wasmtime::Module::new(engine, wasm_binary.as_slice())
+        .map_err(|_| HypervisorError::WasmEngineError(WasmEngineError::FailedToInstantiateModule))
+}
+
+fn encode_entry(serialized_module: &[u8], compilation_time: Duration) -> Vec<u8> {
+    let mut entry = Vec::with_capacity(HEADER_SIZE + serialized_module.len());
+    entry.extend_from_slice(&Sha256::hash(serialized_module));
+    entry.extend_from_slice(&(compilation_time.as_nanos() as u64).to_le_bytes());
+    entry.extend_from_slice(serialized_module);
+    entry
+}
+
+// Returns the compilation time and the serialized module of the given entry
+// if its integrity check succeeds.
+fn decode_entry(entry: &[u8]) -> Option<(Duration, &[u8])> {
+    if entry.len() < HEADER_SIZE {
+        return None;
+    }
+    let (header, serialized_module) = entry.split_at(HEADER_SIZE);
+    let (hash, compilation_time) = header.split_at(32);
+    if hash != Sha256::hash(serialized_module) {
+        return None;
+    }
+    let mut nanos = [0; 8];
+    nanos.copy_from_slice(compilation_time);
+    Some((
+        Duration::from_nanos(u64::from_le_bytes(nanos)),
+        serialized_module,
+    ))
+}
+
+// Sets the modification time of the given file to the current time to mark
+// the entry as recently used.
+fn touch(path: &Path) {
+    if let Ok(path) = CString::new(path.as_os_str().as_bytes()) {
+        // SAFETY: `path` is a valid NUL-terminated string and a null `times`
+        // argument sets the access and modification time to the current time.
+        unsafe {
+            libc::utimes(path.as_ptr(), std::ptr::null());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WasmtimeEmbedder;
+    use ic_config::flag_status::FlagStatus;
+    use ic_logger::replica_logger::no_op_logger;
+    use std::sync::Arc;
+
+    fn wasm(wat: &str) -> BinaryEncodedWasm {
+        BinaryEncodedWasm::new(wabt::wat2wasm(wat).unwrap())
+    }
+
+    fn embedder_with_cache(
+        dir: &Path,
+        capacity: NumBytes,
+    ) -> (WasmtimeEmbedder, Arc<CompilationCache>) {
+        let cache = Arc::new(
+            CompilationCache::new(
+                dir.to_path_buf(),
+                capacity,
+                &MetricsRegistry::new(),
+                no_op_logger(),
+            )
+            .unwrap(),
+        );
+        let embedder = WasmtimeEmbedder::new(EmbeddersConfig::default(), no_op_logger())
+            .with_compilation_cache(Arc::clone(&cache));
+        (embedder, cache)
+    }
+
+    fn metrics(cache: &CompilationCache) -> &CompilationCacheMetrics {
+        cache.metrics.as_ref().unwrap()
+    }
+
+    fn entries(dir: &Path) -> Vec<PathBuf> {
+        fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect()
+    }
+
+    #[test]
+    fn compiled_module_is_reused_after_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let binary = wasm(r#"(module (func (export "canister_update test")))"#);
+
+        let (embedder, cache) = embedder_with_cache(dir.path(), NumBytes::new(u64::MAX));
+        embedder.compile(&binary).unwrap();
+        assert_eq!(metrics(&cache).misses.get(), 1);
+        assert_eq!(entries(dir.path()).len(), 1);
+
+        // A new embedder and cache simulate a restart of the replica.
+        let (embedder, cache) = embedder_with_cache(dir.path(), NumBytes::new(u64::MAX));
+        embedder.compile(&binary).unwrap();
+        assert_eq!(metrics(&cache).hits.get(), 1);
+        assert_eq!(metrics(&cache).misses.get(), 0);
+    }
+
+    #[test]
+    fn corrupted_entry_is_removed_and_recompiled() {
+        let dir = tempfile::tempdir().unwrap();
+        let binary = wasm(r#"(module (func (export "canister_update test")))"#);
+
+        let (embedder, cache) = embedder_with_cache(dir.path(), NumBytes::new(u64::MAX));
+        embedder.compile(&binary).unwrap();
+        let path = entries(dir.path()).pop().unwrap();
+        let mut entry = fs::read(&path).unwrap();
+        let last = entry.len() - 1;
+        entry[last] ^= 1;
+        fs::write(&path, entry).unwrap();
+
+        embedder.compile(&binary).unwrap();
+        assert_eq!(metrics(&cache).hits.get(), 0);
+        assert_eq!(metrics(&cache).misses.get(), 2);
+        assert_eq!(metrics(&cache).errors.get(), 1);
+        // The corrupted entry was replaced by a valid one.
+        assert!(decode_entry(&fs::read(&path).unwrap()).is_some());
+    }
+
+    #[test]
+    fn least_recently_used_entries_are_evicted() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = wasm(r#"(module (func (export "canister_update first")))"#);
+        let second = wasm(r#"(module (func (export "canister_update second")))"#);
+
+        let (embedder, _) = embedder_with_cache(dir.path(), NumBytes::new(u64::MAX));
+        embedder.compile(&first).unwrap();
+        let size = fs::metadata(entries(dir.path()).pop().unwrap())
+            .unwrap()
+            .len();
+
+        // The capacity only fits a single entry.
+        let (embedder, cache) = embedder_with_cache(dir.path(), NumBytes::new(size + size / 2));
+        embedder.compile(&second).unwrap();
+        assert_eq!(
+            entries(dir.path()),
+            vec![cache.entry_path(&second, &EmbeddersConfig::default())]
+        );
+    }
+
+    #[test]
+    fn read_only_cache_loads_entries_but_never_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let cached = wasm(r#"(module (func (export "canister_update cached")))"#);
+        let uncached = wasm(r#"(module (func (export "canister_update uncached")))"#);
+
+        let (embedder, _) = embedder_with_cache(dir.path(), NumBytes::new(u64::MAX));
+        embedder.compile(&cached).unwrap();
+        let path = entries(dir.path()).pop().unwrap();
+
+        let read_only = Arc::new(CompilationCache::open_read_only(
+            dir.path().to_path_buf(),
+            no_op_logger(),
+        ));
+        let embedder = WasmtimeEmbedder::new(EmbeddersConfig::default(), no_op_logger())
+            .with_compilation_cache(Arc::clone(&read_only));
+        embedder.compile(&cached).unwrap();
+        embedder.compile(&uncached).unwrap();
+        assert_eq!(entries(dir.path()), vec![path.clone()]);
+
+        // Corrupted entries are ignored, but not removed.
+        fs::write(&path, b"corrupted").unwrap();
+        embedder.compile(&cached).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"corrupted");
+    }
+
+    #[test]
+    fn modules_compiled_with_a_read_only_cache_are_inserted_by_the_writer() {
+        let dir = tempfile::tempdir().unwrap();
+        let wasm_source =
+            wabt::wat2wasm(r#"(module (func (export "canister_update test")))"#).unwrap();
+        let (_, cache) = embedder_with_cache(dir.path(), NumBytes::new(u64::MAX));
+        let config = EmbeddersConfig::default();
+
+        let read_only = Arc::new(CompilationCache::open_read_only(
+            dir.path().to_path_buf(),
+            no_op_logger(),
+        ));
+        let embedder = WasmtimeEmbedder::new(config.clone(), no_op_logger())
+            .with_compilation_cache(Arc::clone(&read_only));
+        let instrumented = instrument(
+            &decode_wasm(Arc::new(wasm_source.clone())).unwrap(),
+            &InstructionCostTable::new(),
+            config.cost_to_compile_wasm_instruction,
+        )
+        .unwrap()
+        .binary;
+        let (_, serialized_module) = embedder.compile_and_serialize(&instrumented).unwrap();
+        assert!(entries(dir.path()).is_empty());
+
+        cache.insert(&wasm_source, &config, &serialized_module.unwrap());
+        assert_eq!(
+            entries(dir.path()),
+            vec![cache.entry_path(&instrumented, &config)]
+        );
+        // The read-only cache now finds the module.
+        let (_, serialized_module) = embedder.compile_and_serialize(&instrumented).unwrap();
+        assert!(serialized_module.is_none());
+    }
+
+    #[test]
+    fn entries_depend_on_the_feature_flags() {
+        let dir = tempfile::tempdir().unwrap();
+        let binary = wasm(r#"(module (func (export "canister_update test")))"#);
+        let (_, cache) = embedder_with_cache(dir.path(), NumBytes::new(u64::MAX));
+
+        let mut config = EmbeddersConfig::default();
+        config.feature_flags.wasm64 = FlagStatus::Enabled;
+        assert_ne!(
+            cache.entry_path(&binary, &EmbeddersConfig::default()),
+            cache.entry_path(&binary, &config)
+        );
+    }
+}
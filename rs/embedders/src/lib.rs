@@ -1,3 +1,4 @@
+pub mod compilation_cache;
 mod signal_handler;
 pub mod wasm_executor;
 pub mod wasm_utils;
@@ -412,7 +412,7 @@ impl WasmStateChanges {
     }
 }
 
-/// The returns the number guard pages reserved at the end of 4GiB Wasm address
+/// The returns the number guard pages reserved at the end of the Wasm address
 /// space. Message execution fails with an out-of-memory error if it attempts to
 /// use the reserved pages.
 /// Currently the pages are reserved only for canisters compiled with a Motoko
@@ -507,8 +507,7 @@ pub fn process(
         .take_execution_result(run_result.as_ref().err());
 
     let wasm_heap_size_after = instance.heap_size();
-    let wasm_heap_limit =
-        NumWasmPages::from(wasmtime_environ::WASM32_MAX_PAGES as usize) - wasm_reserved_pages;
+    let wasm_heap_limit = instance.max_heap_size() - wasm_reserved_pages;
 
     if wasm_heap_size_after > wasm_heap_limit {
         wasm_result = Err(HypervisorError::WasmReservedPages);
@@ -1,5 +1,7 @@
 pub mod decoding;
 pub mod errors;
 pub mod instrumentation;
+mod memory64;
+mod simd;
 pub mod validation;
 mod wasm_module_builder;
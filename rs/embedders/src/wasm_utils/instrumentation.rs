@@ -28,6 +28,9 @@
 //! (import "__" "update_available_memory" (func (;1;) ((param i32 i32) (result i32))))
 //! ```
 //!
+//! If the memory of the module has 64-bit addresses, the parameters and the
+//! result of `update_available_memory` are `i64` values instead.
+//!
 //! It then inserts (and exports) a global mutable counter:
 //! ```wasm
 //! (global (;0;) (mut i64) (i64.const 0))
@@ -87,20 +90,28 @@
 //!
 //! Before every bulk memory operation, a call is made to the function which
 //! will decrement the instruction counter by the "size" argument of the bulk
-//! memory instruction.
+//! memory instruction. If the memory of the module has 64-bit addresses, the
+//! function takes an `i64` argument, and the `i32` sizes of the bulk table
+//! instructions and `memory.init` are extended before the call and wrapped
+//! again after it.
 //!
 //! Note that we omit checking for the counter overflow at the non-reentrant
 //! blocks to optimize for performance. The maximal overflow in that case is
 //! bound by the length of the longest execution path consisting of
 //! non-reentrant basic blocks.
 
-use super::{errors::into_parity_wasm_error, wasm_module_builder::WasmModuleBuilder};
+use super::{
+    errors::into_parity_wasm_error,
+    memory64::{extract_memory64, restore_memory64},
+    simd::{extract_simd_instructions, simd_instruction_mnemonic},
+    wasm_module_builder::WasmModuleBuilder,
+};
 use ic_replicated_state::canister_state::WASM_PAGE_SIZE_IN_BYTES;
 use ic_replicated_state::NumWasmPages;
 use ic_sys::{PageBytes, PageIndex, PAGE_SIZE};
 use ic_types::methods::WasmMethod;
 use ic_types::NumInstructions;
-use ic_wasm_types::{BinaryEncodedWasm, WasmInstrumentationError};
+use ic_wasm_types::{BinaryEncodedWasm, ParityWasmError, WasmInstrumentationError};
 
 use parity_wasm::builder;
 use parity_wasm::elements::{
@@ -151,7 +162,10 @@ impl InstructionCostTable {
     // Returns the cost of a Wasm instruction from the cost table or the default
     // cost if the instruction is not in the cost table.
     fn cost(&self, i: &Instruction) -> u64 {
-        let mnemonic = instruction_to_mnemonic(i);
+        let mnemonic = match simd_instruction_mnemonic(i) {
+            Some(mnemonic) => mnemonic.to_string(),
+            None => instruction_to_mnemonic(i),
+        };
         *self
             .instruction_cost
             .get(&mnemonic)
@@ -176,6 +190,30 @@ impl Default for InstructionCostTable {
             0,
         );
 
+        // The following SIMD instructions have no direct equivalent on x86-64
+        // and are lowered to longer instruction sequences, so they are more
+        // expensive than the others.
+        for mnemonic in [
+            "i8x16.shl",
+            "i8x16.shr_s",
+            "i8x16.shr_u",
+            "i8x16.popcnt",
+            "i64x2.mul",
+            "i64x2.shr_s",
+            "f32x4.min",
+            "f32x4.max",
+            "f64x2.min",
+            "f64x2.max",
+            "i32x4.trunc_sat_f32x4_s",
+            "i32x4.trunc_sat_f32x4_u",
+            "i32x4.trunc_sat_f64x2_s_zero",
+            "i32x4.trunc_sat_f64x2_u_zero",
+            "f32x4.convert_i32x4_u",
+            "f64x2.convert_low_i32x4_u",
+        ] {
+            instruction_cost.insert(mnemonic.to_string(), 4);
+        }
+
         Self {
             default_cost: 1,
             instruction_cost,
@@ -195,7 +233,7 @@ impl Default for InstructionCostTable {
 // added as the last two imports, we'd need to increment only non imported
 // functions, since imported functions precede all others in the function index
 // space, but this would be error-prone).
-fn inject_helper_functions(module: Module) -> Module {
+fn inject_helper_functions(module: Module, memory64: bool) -> Module {
     let mut builder = builder::from_module(module);
     let import_sig = builder.push_signature(builder::signature().build_sig());
 
@@ -210,9 +248,9 @@ fn inject_helper_functions(module: Module) -> Module {
 
     let import_sig = builder.push_signature(
         builder::signature()
-            .with_param(ValueType::I32)
-            .with_param(ValueType::I32)
-            .with_result(ValueType::I32)
+            .with_param(address_type(memory64))
+            .with_param(address_type(memory64))
+            .with_result(address_type(memory64))
             .build_sig(),
     );
     builder.push_import(
@@ -269,6 +307,16 @@ fn inject_helper_functions(module: Module) -> Module {
     module
 }
 
+// Returns the type of the addresses of a memory, which is also the type of the
+// arguments and result of `memory.grow`.
+fn address_type(memory64: bool) -> ValueType {
+    if memory64 {
+        ValueType::I64
+    } else {
+        ValueType::I32
+    }
+}
+
 /// Vector of heap data chunks with their offsets.
 pub struct Segments(Vec<(usize, Vec<u8>)>);
 
@@ -382,6 +430,8 @@ pub struct ExportModuleData {
     pub instructions_counter_ix: u32,
     pub decr_instruction_counter_fn: u32,
     pub start_fn_ix: Option<u32>,
+    /// True if the memory of the module has 64-bit addresses.
+    pub memory64: bool,
 }
 
 /// Takes a Wasm binary and inserts the instructions metering and memory grow
@@ -394,10 +444,16 @@ pub fn instrument(
     instruction_cost_table: &InstructionCostTable,
     cost_to_compile_wasm_instruction: NumInstructions,
 ) -> Result<InstrumentationOutput, WasmInstrumentationError> {
-    let module = parity_wasm::deserialize_buffer::<Module>(wasm.as_slice()).map_err(|err| {
+    let (wasm, simd_instructions) = extract_simd_instructions(wasm.as_slice()).map_err(|err| {
+        WasmInstrumentationError::ParityDeserializeError(ParityWasmError::new(err.to_string()))
+    })?;
+    let (wasm, memory64) = extract_memory64(&wasm).map_err(|err| {
+        WasmInstrumentationError::ParityDeserializeError(ParityWasmError::new(err.to_string()))
+    })?;
+    let module = parity_wasm::deserialize_buffer::<Module>(&wasm).map_err(|err| {
         WasmInstrumentationError::ParityDeserializeError(into_parity_wasm_error(err))
     })?;
-    let mut module = inject_helper_functions(module);
+    let mut module = inject_helper_functions(module, memory64);
     module = export_table(module);
     module = export_memory(module);
     module = export_mutable_globals(module);
@@ -409,6 +465,7 @@ pub fn instrument(
         instructions_counter_ix: num_globals,
         decr_instruction_counter_fn: num_functions,
         start_fn_ix: module.start_section(),
+        memory64,
     };
 
     if export_module_data.start_fn_ix.is_some() {
@@ -446,7 +503,7 @@ pub fn instrument(
         if !func_types.is_empty() {
             let func_bodies = module.code_section_mut().unwrap().bodies_mut();
             for (func_ix, func_type) in func_types.into_iter().enumerate() {
-                inject_update_available_memory(&mut func_bodies[func_ix], &func_type);
+                inject_update_available_memory(&mut func_bodies[func_ix], &func_type, memory64);
             }
         }
     }
@@ -500,7 +557,12 @@ pub fn instrument(
             })
             .unwrap_or(0)) as u64;
 
-    let result = parity_wasm::serialize(module).map_err(|err| {
+    if memory64 {
+        restore_memory64(&mut module).map_err(|err| {
+            WasmInstrumentationError::ParitySerializeError(into_parity_wasm_error(err))
+        })?;
+    }
+    let result = simd_instructions.serialize(module).map_err(|err| {
         WasmInstrumentationError::ParitySerializeError(into_parity_wasm_error(err))
     })?;
     Ok(InstrumentationOutput {
@@ -522,35 +584,45 @@ pub fn export_additional_symbols(
 ) -> Result<Module, WasmInstrumentationError> {
     let mut mbuilder = WasmModuleBuilder::new(builder::from_module(module));
 
+    // The amount is an `i64` already if the memory has 64-bit addresses.
+    let get_amount: &[Instruction] = if export_module_data.memory64 {
+        &[Instruction::GetLocal(0)]
+    } else {
+        &[Instruction::GetLocal(0), Instruction::I64ExtendUI32]
+    };
+    let mut instructions = vec![
+        // Call out_of_instructions if count is already negative.
+        Instruction::GetGlobal(export_module_data.instructions_counter_ix),
+    ];
+    instructions.extend_from_slice(get_amount);
+    instructions.extend_from_slice(&[
+        Instruction::I64LtS,
+        Instruction::If(BlockType::NoResult),
+        Instruction::Call(export_module_data.out_of_instructions_fn),
+        Instruction::End,
+        // Subtract the parameter amount from the instruction counter
+        Instruction::GetGlobal(export_module_data.instructions_counter_ix),
+    ]);
+    instructions.extend_from_slice(get_amount);
+    instructions.extend_from_slice(&[
+        Instruction::I64Sub,
+        Instruction::SetGlobal(export_module_data.instructions_counter_ix),
+        // Return the original param so this function doesn't alter the stack
+        Instruction::GetLocal(0),
+        Instruction::End,
+    ]);
+
     // push function to decrement the instruction counter
     mbuilder.push_function(
         builder::function()
             .with_signature(
                 builder::signature()
-                    .with_param(ValueType::I32) // amount to decrement by
-                    .with_result(ValueType::I32) // argument is returned so stack remains unchanged
+                    .with_param(address_type(export_module_data.memory64)) // amount to decrement by
+                    .with_result(address_type(export_module_data.memory64)) // argument is returned so stack remains unchanged
                     .build_sig(),
             )
             .body()
-            .with_instructions(Instructions::new(vec![
-                // Call out_of_instructions if count is already negative.
-                Instruction::GetGlobal(export_module_data.instructions_counter_ix),
-                Instruction::GetLocal(0),
-                Instruction::I64ExtendUI32,
-                Instruction::I64LtS,
-                Instruction::If(BlockType::NoResult),
-                Instruction::Call(export_module_data.out_of_instructions_fn),
-                Instruction::End,
-                // Subtract the parameter amount from the instruction counter
-                Instruction::GetGlobal(export_module_data.instructions_counter_ix),
-                Instruction::GetLocal(0),
-                Instruction::I64ExtendUI32,
-                Instruction::I64Sub,
-                Instruction::SetGlobal(export_module_data.instructions_counter_ix),
-                // Return the original param so this function doesn't alter the stack
-                Instruction::GetLocal(0),
-                Instruction::End,
-            ]))
+            .with_instructions(Instructions::new(instructions))
             .build()
             .build(),
     );
@@ -678,9 +750,20 @@ fn inject_metering(
                 }
             }
             InjectionPointCostDetail::DynamicCost => {
-                elems.extend_from_slice(&[Instruction::Call(
-                    export_data_module.decr_instruction_counter_fn,
-                )]);
+                let call = Instruction::Call(export_data_module.decr_instruction_counter_fn);
+                if export_data_module.memory64
+                    && !has_address_sized_length(&orig_elems[point.position])
+                {
+                    // The function takes an `i64` if the memory has 64-bit
+                    // addresses, but the size of this instruction is an `i32`.
+                    elems.extend_from_slice(&[
+                        Instruction::I64ExtendUI32,
+                        call,
+                        Instruction::I32WrapI64,
+                    ]);
+                } else {
+                    elems.push(call);
+                }
             }
         }
         last_injection_position = point.position;
@@ -689,11 +772,25 @@ fn inject_metering(
     *code.elements_mut() = elems;
 }
 
+// Returns true if the size argument of the given bulk memory instruction has
+// the type of the memory addresses rather than `i32`.
+fn has_address_sized_length(instruction: &Instruction) -> bool {
+    matches!(
+        instruction,
+        Instruction::Bulk(BulkInstruction::MemoryFill)
+            | Instruction::Bulk(BulkInstruction::MemoryCopy)
+    )
+}
+
 // Scans through a function and adds instrumentation after each `memory.grow`
 // instruction to make sure that there's enough available memory left to support
 // the requested extra memory. If no `memory.grow` instructions are present then
 // the function's code remains unchanged.
-fn inject_update_available_memory(func_body: &mut FuncBody, func_type: &FunctionType) {
+fn inject_update_available_memory(
+    func_body: &mut FuncBody,
+    func_type: &FunctionType,
+    memory64: bool,
+) {
     let mut injection_points: Vec<usize> = Vec::new();
     {
         let code = func_body.code();
@@ -711,7 +808,9 @@ fn inject_update_available_memory(func_body: &mut FuncBody, func_type: &Function
         // We inject a local to cache the argument to `memory.grow`.
         let n_locals: u32 = func_body.locals().iter().map(Local::count).sum();
         let memory_local_ix = func_type.params().len() as u32 + n_locals;
-        func_body.locals_mut().push(Local::new(1, ValueType::I32));
+        func_body
+            .locals_mut()
+            .push(Local::new(1, address_type(memory64)));
         let code = func_body.code_mut();
         let orig_elems = code.elements_mut();
         let mut elems: Vec<Instruction> = Vec::new();
@@ -814,6 +913,10 @@ fn get_data(sections: &mut Vec<Section>) -> Vec<(usize, Vec<u8>)> {
                                     Instruction::I32Const(val),
                                     Instruction::End
                                ] => ((*val) as u32) as usize, // Convert via `u32` to avoid 64-bit sign-extension.
+                                [
+                                    Instruction::I64Const(val),
+                                    Instruction::End
+                               ] => (*val) as u64 as usize,
                                 _ => panic!(
                                     "complex initialization expressions for data segments are not supported!"
                                     ),
@@ -0,0 +1,206 @@
+//! Support for memories with 64-bit addresses (the memory64 proposal) in
+//! `parity_wasm`.
+//!
+//! `parity_wasm`, which is used to validate and instrument canister modules,
+//! cannot decode the limits of a memory whose addresses are 64 bits wide. The
+//! validation and instrumentation do not depend on the width of the
+//! addresses, so before a module is parsed with `parity_wasm`, the limits of
+//! its 64-bit memory are rewritten as the limits of a 32-bit memory, see
+//! [`extract_memory64`]. After the module has been transformed, the memory
+//! section is encoded with 64-bit limits again, see [`restore_memory64`].
+
+use parity_wasm::elements::{
+    Error, MemoryType, Module, Section, Serialize, Uint8, VarUint32, VarUint64,
+};
+use std::convert::TryFrom;
+use wasmparser::{
+    BinaryReader, BinaryReaderError, ImportSectionEntryType, ImportSectionReader,
+    MemorySectionReader,
+};
+
+/// The id of the import section.
+const IMPORT_SECTION_ID: u8 = 2;
+
+/// The id of the memory section.
+const MEMORY_SECTION_ID: u8 = 5;
+
+/// The limits flag indicating that the maximum is present.
+const FLAG_HAS_MAX: u8 = 0x01;
+
+/// The limits flag indicating that the addresses of the memory are 64 bits
+/// wide.
+const FLAG_MEMORY64: u8 = 0x04;
+
+/// Errors that can occur when rewriting the memory of a module.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Memory64Error {
+    /// The module could not be decoded.
+    Decoding(String),
+    /// The module has a 64-bit memory that cannot be supported.
+    InvalidMemory(String),
+}
+
+impl std::fmt::Display for Memory64Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Decoding(err) | Self::InvalidMemory(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl From<BinaryReaderError> for Memory64Error {
+    fn from(err: BinaryReaderError) -> Self {
+        Self::Decoding(err.to_string())
+    }
+}
+
+/// Rewrites the limits of the 64-bit memories defined in the given module as
+/// the limits of 32-bit memories that can be parsed by `parity_wasm`.
+///
+/// Returns the rewritten module and whether it defines a 64-bit memory. Fails
+/// if a 64-bit memory is imported or if its limits do not fit into 32 bits.
+pub(crate) fn extract_memory64(wasm: &[u8]) -> Result<(Vec<u8>, bool), Memory64Error> {
+    let mut memory64 = false;
+    let mut reader = BinaryReader::new(wasm);
+    // The magic number and the version are copied as they are.
+    let mut output = reader.read_bytes(8)?.to_vec();
+    while !reader.eof() {
+        let section_start = reader.original_position();
+        let id = reader.read_u8()? as u8;
+        let size = reader.read_var_u32()? as usize;
+        let payload_start = reader.original_position();
+        let payload = reader.read_bytes(size)?;
+        match id {
+            IMPORT_SECTION_ID => check_imported_memories(payload, payload_start)?,
+            MEMORY_SECTION_ID => {
+                let mut memories = MemorySectionReader::new(payload, payload_start)?;
+                let mut section = Vec::new();
+                write_u32(memories.get_count(), &mut section);
+                for _ in 0..memories.get_count() {
+                    let memory = memories.read()?;
+                    memory64 |= memory.memory64;
+                    let initial = to_u32(memory.initial)?;
+                    let maximum = memory.maximum.map(to_u32).transpose()?;
+                    MemoryType::new(initial, maximum)
+                        .serialize(&mut section)
+                        .expect("Writing to a vector cannot fail");
+                }
+                output.push(id);
+                write_u32(section.len() as u32, &mut output);
+                output.extend_from_slice(&section);
+                continue;
+            }
+            _ => {}
+        }
+        output.extend_from_slice(&wasm[section_start..payload_start + size]);
+    }
+    Ok((output, memory64))
+}
+
+/// Encodes the limits of the memories defined in the given module as the
+/// limits of 64-bit memories.
+///
+/// This reverts [`extract_memory64`] and must only be called if the module
+/// originally defined a 64-bit memory.
+pub(crate) fn restore_memory64(module: &mut Module) -> Result<(), Error> {
+    for section in module.sections_mut().iter_mut() {
+        if let Section::Memory(memory_section) = section {
+            let mut memories = Vec::new();
+            VarUint32::from(memory_section.entries().len()).serialize(&mut memories)?;
+            for memory in memory_section.entries() {
+                let limits = memory.limits();
+                let flags = match limits.maximum() {
+                    Some(_) => FLAG_MEMORY64 | FLAG_HAS_MAX,
+                    None => FLAG_MEMORY64,
+                };
+                Uint8::from(flags).serialize(&mut memories)?;
+                VarUint64::from(limits.initial() as u64).serialize(&mut memories)?;
+                if let Some(maximum) = limits.maximum() {
+                    VarUint64::from(maximum as u64).serialize(&mut memories)?;
+                }
+            }
+            // The payload of an unparsed section includes its size.
+            let mut payload = Vec::new();
+            VarUint32::from(memories.len()).serialize(&mut payload)?;
+            payload.extend_from_slice(&memories);
+            *section = Section::Unparsed {
+                id: MEMORY_SECTION_ID,
+                payload,
+            };
+        }
+    }
+    Ok(())
+}
+
+// Fails if the given import section imports a 64-bit memory.
+fn check_imported_memories(payload: &[u8], payload_start: usize) -> Result<(), Memory64Error> {
+    let mut imports = ImportSectionReader::new(payload, payload_start)?;
+    for _ in 0..imports.get_count() {
+        if let ImportSectionEntryType::Memory(memory) = imports.read()?.ty {
+            if memory.memory64 {
+                return Err(Memory64Error::InvalidMemory(
+                    "64-bit memories cannot be imported.".to_string(),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn to_u32(pages: u64) -> Result<u32, Memory64Error> {
+    u32::try_from(pages).map_err(|_| {
+        Memory64Error::InvalidMemory(format!(
+            "The memory limit of {} pages does not fit into 32 bits.",
+            pages
+        ))
+    })
+}
+
+fn write_u32(value: u32, output: &mut Vec<u8>) {
+    VarUint32::from(value)
+        .serialize(output)
+        .expect("Writing to a vector cannot fail")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WAT: &str = r#"(module
+        (import "ic0" "msg_reply" (func $msg_reply))
+        (func $f
+            (drop (memory.grow (i64.const 1)))
+            (i64.store offset=8 (i64.const 16) (memory.size))
+            (call $msg_reply))
+        (memory $memory i64 1 1000)
+        (data (i64.const 32) "hello")
+        (export "canister_update f" (func $f))
+        (export "memory" (memory $memory)))"#;
+
+    #[test]
+    fn memory64_limits_are_restored() {
+        let wasm = wat::parse_str(WAT).unwrap();
+        let (rewritten, memory64) = extract_memory64(&wasm).unwrap();
+        assert!(memory64);
+        let mut module = parity_wasm::deserialize_buffer::<Module>(&rewritten).unwrap();
+        let limits = module.memory_section().unwrap().entries()[0].limits();
+        assert_eq!((limits.initial(), limits.maximum()), (1, Some(1000)));
+        restore_memory64(&mut module).unwrap();
+        assert_eq!(parity_wasm::serialize(module).unwrap(), wasm);
+    }
+
+    #[test]
+    fn memory32_is_unchanged() {
+        let wasm = wat::parse_str("(module (memory 1 2))").unwrap();
+        assert_eq!(extract_memory64(&wasm).unwrap(), (wasm, false));
+    }
+
+    #[test]
+    fn imported_memory64_is_rejected() {
+        let wasm = wat::parse_str(r#"(module (import "env" "memory" (memory i64 1)))"#).unwrap();
+        assert!(matches!(
+            extract_memory64(&wasm),
+            Err(Memory64Error::InvalidMemory(_))
+        ));
+    }
+}
@@ -0,0 +1,560 @@
+//! Support for the standardized Wasm SIMD instructions in `parity_wasm`.
+//!
+//! `parity_wasm`, which is used to validate and instrument canister modules,
+//! only implements a pre-standard draft of the SIMD proposal whose binary
+//! encoding is incompatible with the one produced by current compilers and
+//! expected by Wasmtime. SIMD instructions never affect the control flow, so
+//! the validation and instrumentation do not need to understand them.
+//! Therefore, before a module is parsed with `parity_wasm`, every SIMD
+//! instruction in its code section is replaced by a (draft) `v128.const`
+//! placeholder whose immediate refers to the original instruction, see
+//! [`extract_simd_instructions`]. After the module has been transformed, the
+//! placeholders are replaced by the original instructions again when the
+//! module is serialized, see [`SimdInstructions::serialize`].
+
+use parity_wasm::elements::{
+    Error, FuncBody, Instruction, Module, Section, Serialize, SimdInstruction, VarUint32,
+};
+use wasmparser::{BinaryReader, BinaryReaderError, CodeSectionReader};
+
+/// The prefix byte of all SIMD instructions.
+const SIMD_PREFIX: u8 = 0xfd;
+
+/// The opcode of `v128.const` in the draft encoding of `parity_wasm`.
+const DRAFT_V128_CONST: u8 = 0x02;
+
+/// The id of the code section.
+const CODE_SECTION_ID: u8 = 10;
+
+/// Errors that can occur when extracting the SIMD instructions of a module.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum SimdError {
+    /// The module could not be decoded.
+    Decoding(String),
+    /// The module uses a SIMD instruction whose result is not deterministic,
+    /// such as the instructions of the relaxed SIMD proposal.
+    NonDeterministicInstruction { offset: usize, opcode: u32 },
+}
+
+impl std::fmt::Display for SimdError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Decoding(err) => write!(f, "{}", err),
+            Self::NonDeterministicInstruction { offset, opcode } => write!(
+                f,
+                "the SIMD instruction {:#x} at offset {:#x} is not deterministic",
+                opcode, offset
+            ),
+        }
+    }
+}
+
+impl From<BinaryReaderError> for SimdError {
+    fn from(err: BinaryReaderError) -> Self {
+        Self::Decoding(err.to_string())
+    }
+}
+
+/// The SIMD instructions extracted from the code section of a module.
+#[derive(Debug, Default)]
+pub(crate) struct SimdInstructions {
+    // The encoding of each instruction, including the SIMD prefix.
+    instructions: Vec<Vec<u8>>,
+}
+
+impl SimdInstructions {
+    /// Returns the encoding of the SIMD instruction referred to by the given
+    /// placeholder, or `None` if it is not a placeholder.
+    fn original(&self, instruction: &Instruction) -> Result<Option<&[u8]>, Error> {
+        match placeholder_immediate(instruction) {
+            Some((index, _)) => self
+                .instructions
+                .get(index as usize)
+                .map(|encoding| Some(encoding.as_slice()))
+                .ok_or(Error::Other("Unknown SIMD instruction placeholder")),
+            None => Ok(None),
+        }
+    }
+
+    /// Serializes the given module, replacing the placeholders in its code
+    /// section by the original SIMD instructions.
+    pub(crate) fn serialize(&self, mut module: Module) -> Result<Vec<u8>, Error> {
+        if !self.instructions.is_empty() {
+            for section in module.sections_mut().iter_mut() {
+                if let Section::Code(code_section) = section {
+                    let payload = self.encode_code_section(code_section.bodies())?;
+                    *section = Section::Unparsed {
+                        id: CODE_SECTION_ID,
+                        payload,
+                    };
+                }
+            }
+        }
+        parity_wasm::serialize(module)
+    }
+
+    // Encodes the given function bodies like `parity_wasm` does, except for
+    // the placeholders. The result includes the size of the section.
+    fn encode_code_section(&self, bodies: &[FuncBody]) -> Result<Vec<u8>, Error> {
+        let mut section = Vec::new();
+        VarUint32::from(bodies.len()).serialize(&mut section)?;
+        for body in bodies {
+            let mut encoded_body = Vec::new();
+            VarUint32::from(body.locals().len()).serialize(&mut encoded_body)?;
+            for local in body.locals() {
+                local.serialize(&mut encoded_body)?;
+            }
+            for instruction in body.code().elements() {
+                match self.original(instruction)? {
+                    Some(encoding) => encoded_body.extend_from_slice(encoding),
+                    None => instruction.clone().serialize(&mut encoded_body)?,
+                }
+            }
+            VarUint32::from(encoded_body.len()).serialize(&mut section)?;
+            section.extend_from_slice(&encoded_body);
+        }
+        let mut payload = Vec::new();
+        VarUint32::from(section.len()).serialize(&mut payload)?;
+        payload.extend_from_slice(&section);
+        Ok(payload)
+    }
+}
+
+/// Replaces the SIMD instructions in the code section of the given module by
+/// placeholders that can be parsed by `parity_wasm`.
+///
+/// Returns the rewritten module together with the extracted instructions,
+/// which are needed to restore the placeholders. Fails if the module uses a
+/// non-deterministic SIMD instruction.
+pub(crate) fn extract_simd_instructions(
+    wasm: &[u8],
+) -> Result<(Vec<u8>, SimdInstructions), SimdError> {
+    let mut simd_instructions = SimdInstructions::default();
+    let mut reader = BinaryReader::new(wasm);
+    // The magic number and the version are copied as they are.
+    let mut output = reader.read_bytes(8)?.to_vec();
+    while !reader.eof() {
+        let section_start = reader.original_position();
+        let id = reader.read_u8()? as u8;
+        let size = reader.read_var_u32()? as usize;
+        let payload_start = reader.original_position();
+        let payload = reader.read_bytes(size)?;
+        if id == CODE_SECTION_ID {
+            let code_section =
+                extract_from_code_section(payload, payload_start, &mut simd_instructions)?;
+            output.push(id);
+            write_u32(code_section.len(), &mut output);
+            output.extend_from_slice(&code_section);
+        } else {
+            output.extend_from_slice(&wasm[section_start..payload_start + size]);
+        }
+    }
+    Ok((output, simd_instructions))
+}
+
+// Returns the given code section with the SIMD instructions replaced by
+// placeholders. The result does not include the size of the section.
+fn extract_from_code_section(
+    payload: &[u8],
+    payload_start: usize,
+    simd_instructions: &mut SimdInstructions,
+) -> Result<Vec<u8>, SimdError> {
+    let mut reader = CodeSectionReader::new(payload, payload_start)?;
+    let mut section = Vec::with_capacity(payload.len());
+    write_u32(reader.get_count() as usize, &mut section);
+    for _ in 0..reader.get_count() {
+        let body = reader.read()?;
+        let mut operators = body.get_operators_reader()?;
+        let body_start = body.range().start;
+        // The locals are copied as they are.
+        let mut encoded_body = payload
+            [body_start - payload_start..operators.original_position() - payload_start]
+            .to_vec();
+        while !operators.eof() {
+            let (_, start) = operators.read_with_offset()?;
+            let encoding =
+                &payload[start - payload_start..operators.original_position() - payload_start];
+            if encoding[0] != SIMD_PREFIX {
+                encoded_body.extend_from_slice(encoding);
+                continue;
+            }
+            let opcode = BinaryReader::new(&encoding[1..]).read_var_u32()?;
+            if simd_mnemonic(opcode).is_none() {
+                return Err(SimdError::NonDeterministicInstruction {
+                    offset: start,
+                    opcode,
+                });
+            }
+            let index = simd_instructions.instructions.len() as u32;
+            simd_instructions.instructions.push(encoding.to_vec());
+            encoded_body.extend_from_slice(&[SIMD_PREFIX, DRAFT_V128_CONST]);
+            encoded_body.extend_from_slice(&index.to_le_bytes());
+            encoded_body.extend_from_slice(&opcode.to_le_bytes());
+            encoded_body.extend_from_slice(&[0; 8]);
+        }
+        write_u32(encoded_body.len(), &mut section);
+        section.extend_from_slice(&encoded_body);
+    }
+    Ok(section)
+}
+
+// Returns the index of the referred instruction and its opcode if the given
+// instruction is a placeholder.
+fn placeholder_immediate(instruction: &Instruction) -> Option<(u32, u32)> {
+    match instruction {
+        Instruction::Simd(SimdInstruction::V128Const(immediate)) => {
+            let mut index = [0; 4];
+            let mut opcode = [0; 4];
+            index.copy_from_slice(&immediate[0..4]);
+            opcode.copy_from_slice(&immediate[4..8]);
+            Some((u32::from_le_bytes(index), u32::from_le_bytes(opcode)))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the mnemonic of the given instruction if it is a SIMD instruction
+/// placeholder.
+pub(crate) fn simd_instruction_mnemonic(instruction: &Instruction) -> Option<&'static str> {
+    placeholder_immediate(instruction).and_then(|(_, opcode)| simd_mnemonic(opcode))
+}
+
+fn write_u32(value: usize, output: &mut Vec<u8>) {
+    VarUint32::from(value)
+        .serialize(output)
+        .expect("Writing to a vector cannot fail")
+}
+
+// Returns the mnemonic of the standardized SIMD instruction with the given
+// opcode, or `None` if the opcode does not belong to a deterministic
+// instruction.
+//
+// Note that the instructions of the relaxed SIMD proposal are intentionally
+// missing, because their results depend on the hardware.
+fn simd_mnemonic(opcode: u32) -> Option<&'static str> {
+    let mnemonic = match opcode {
+        0x00 => "v128.load",
+        0x01 => "v128.load8x8_s",
+        0x02 => "v128.load8x8_u",
+        0x03 => "v128.load16x4_s",
+        0x04 => "v128.load16x4_u",
+        0x05 => "v128.load32x2_s",
+        0x06 => "v128.load32x2_u",
+        0x07 => "v128.load8_splat",
+        0x08 => "v128.load16_splat",
+        0x09 => "v128.load32_splat",
+        0x0a => "v128.load64_splat",
+        0x0b => "v128.store",
+        0x0c => "v128.const",
+        0x0d => "i8x16.shuffle",
+        0x0e => "i8x16.swizzle",
+        0x0f => "i8x16.splat",
+        0x10 => "i16x8.splat",
+        0x11 => "i32x4.splat",
+        0x12 => "i64x2.splat",
+        0x13 => "f32x4.splat",
+        0x14 => "f64x2.splat",
+        0x15 => "i8x16.extract_lane_s",
+        0x16 => "i8x16.extract_lane_u",
+        0x17 => "i8x16.replace_lane",
+        0x18 => "i16x8.extract_lane_s",
+        0x19 => "i16x8.extract_lane_u",
+        0x1a => "i16x8.replace_lane",
+        0x1b => "i32x4.extract_lane",
+        0x1c => "i32x4.replace_lane",
+        0x1d => "i64x2.extract_lane",
+        0x1e => "i64x2.replace_lane",
+        0x1f => "f32x4.extract_lane",
+        0x20 => "f32x4.replace_lane",
+        0x21 => "f64x2.extract_lane",
+        0x22 => "f64x2.replace_lane",
+        0x23 => "i8x16.eq",
+        0x24 => "i8x16.ne",
+        0x25 => "i8x16.lt_s",
+        0x26 => "i8x16.lt_u",
+        0x27 => "i8x16.gt_s",
+        0x28 => "i8x16.gt_u",
+        0x29 => "i8x16.le_s",
+        0x2a => "i8x16.le_u",
+        0x2b => "i8x16.ge_s",
+        0x2c => "i8x16.ge_u",
+        0x2d => "i16x8.eq",
+        0x2e => "i16x8.ne",
+        0x2f => "i16x8.lt_s",
+        0x30 => "i16x8.lt_u",
+        0x31 => "i16x8.gt_s",
+        0x32 => "i16x8.gt_u",
+        0x33 => "i16x8.le_s",
+        0x34 => "i16x8.le_u",
+        0x35 => "i16x8.ge_s",
+        0x36 => "i16x8.ge_u",
+        0x37 => "i32x4.eq",
+        0x38 => "i32x4.ne",
+        0x39 => "i32x4.lt_s",
+        0x3a => "i32x4.lt_u",
+        0x3b => "i32x4.gt_s",
+        0x3c => "i32x4.gt_u",
+        0x3d => "i32x4.le_s",
+        0x3e => "i32x4.le_u",
+        0x3f => "i32x4.ge_s",
+        0x40 => "i32x4.ge_u",
+        0x41 => "f32x4.eq",
+        0x42 => "f32x4.ne",
+        0x43 => "f32x4.lt",
+        0x44 => "f32x4.gt",
+        0x45 => "f32x4.le",
+        0x46 => "f32x4.ge",
+        0x47 => "f64x2.eq",
+        0x48 => "f64x2.ne",
+        0x49 => "f64x2.lt",
+        0x4a => "f64x2.gt",
+        0x4b => "f64x2.le",
+        0x4c => "f64x2.ge",
+        0x4d => "v128.not",
+        0x4e => "v128.and",
+        0x4f => "v128.andnot",
+        0x50 => "v128.or",
+        0x51 => "v128.xor",
+        0x52 => "v128.bitselect",
+        0x53 => "v128.any_true",
+        0x54 => "v128.load8_lane",
+        0x55 => "v128.load16_lane",
+        0x56 => "v128.load32_lane",
+        0x57 => "v128.load64_lane",
+        0x58 => "v128.store8_lane",
+        0x59 => "v128.store16_lane",
+        0x5a => "v128.store32_lane",
+        0x5b => "v128.store64_lane",
+        0x5c => "v128.load32_zero",
+        0x5d => "v128.load64_zero",
+        0x5e => "f32x4.demote_f64x2_zero",
+        0x5f => "f64x2.promote_low_f32x4",
+        0x60 => "i8x16.abs",
+        0x61 => "i8x16.neg",
+        0x62 => "i8x16.popcnt",
+        0x63 => "i8x16.all_true",
+        0x64 => "i8x16.bitmask",
+        0x65 => "i8x16.narrow_i16x8_s",
+        0x66 => "i8x16.narrow_i16x8_u",
+        0x67 => "f32x4.ceil",
+        0x68 => "f32x4.floor",
+        0x69 => "f32x4.trunc",
+        0x6a => "f32x4.nearest",
+        0x6b => "i8x16.shl",
+        0x6c => "i8x16.shr_s",
+        0x6d => "i8x16.shr_u",
+        0x6e => "i8x16.add",
+        0x6f => "i8x16.add_sat_s",
+        0x70 => "i8x16.add_sat_u",
+        0x71 => "i8x16.sub",
+        0x72 => "i8x16.sub_sat_s",
+        0x73 => "i8x16.sub_sat_u",
+        0x74 => "f64x2.ceil",
+        0x75 => "f64x2.floor",
+        0x76 => "i8x16.min_s",
+        0x77 => "i8x16.min_u",
+        0x78 => "i8x16.max_s",
+        0x79 => "i8x16.max_u",
+        0x7a => "f64x2.trunc",
+        0x7b => "i8x16.avgr_u",
+        0x7c => "i16x8.extadd_pairwise_i8x16_s",
+        0x7d => "i16x8.extadd_pairwise_i8x16_u",
+        0x7e => "i32x4.extadd_pairwise_i16x8_s",
+        0x7f => "i32x4.extadd_pairwise_i16x8_u",
+        0x80 => "i16x8.abs",
+        0x81 => "i16x8.neg",
+        0x82 => "i16x8.q15mulr_sat_s",
+        0x83 => "i16x8.all_true",
+        0x84 => "i16x8.bitmask",
+        0x85 => "i16x8.narrow_i32x4_s",
+        0x86 => "i16x8.narrow_i32x4_u",
+        0x87 => "i16x8.extend_low_i8x16_s",
+        0x88 => "i16x8.extend_high_i8x16_s",
+        0x89 => "i16x8.extend_low_i8x16_u",
+        0x8a => "i16x8.extend_high_i8x16_u",
+        0x8b => "i16x8.shl",
+        0x8c => "i16x8.shr_s",
+        0x8d => "i16x8.shr_u",
+        0x8e => "i16x8.add",
+        0x8f => "i16x8.add_sat_s",
+        0x90 => "i16x8.add_sat_u",
+        0x91 => "i16x8.sub",
+        0x92 => "i16x8.sub_sat_s",
+        0x93 => "i16x8.sub_sat_u",
+        0x94 => "f64x2.nearest",
+        0x95 => "i16x8.mul",
+        0x96 => "i16x8.min_s",
+        0x97 => "i16x8.min_u",
+        0x98 => "i16x8.max_s",
+        0x99 => "i16x8.max_u",
+        0x9b => "i16x8.avgr_u",
+        0x9c => "i16x8.extmul_low_i8x16_s",
+        0x9d => "i16x8.extmul_high_i8x16_s",
+        0x9e => "i16x8.extmul_low_i8x16_u",
+        0x9f => "i16x8.extmul_high_i8x16_u",
+        0xa0 => "i32x4.abs",
+        0xa1 => "i32x4.neg",
+        0xa3 => "i32x4.all_true",
+        0xa4 => "i32x4.bitmask",
+        0xa7 => "i32x4.extend_low_i16x8_s",
+        0xa8 => "i32x4.extend_high_i16x8_s",
+        0xa9 => "i32x4.extend_low_i16x8_u",
+        0xaa => "i32x4.extend_high_i16x8_u",
+        0xab => "i32x4.shl",
+        0xac => "i32x4.shr_s",
+        0xad => "i32x4.shr_u",
+        0xae => "i32x4.add",
+        0xb1 => "i32x4.sub",
+        0xb5 => "i32x4.mul",
+        0xb6 => "i32x4.min_s",
+        0xb7 => "i32x4.min_u",
+        0xb8 => "i32x4.max_s",
+        0xb9 => "i32x4.max_u",
+        0xba => "i32x4.dot_i16x8_s",
+        0xbc => "i32x4.extmul_low_i16x8_s",
+        0xbd => "i32x4.extmul_high_i16x8_s",
+        0xbe => "i32x4.extmul_low_i16x8_u",
+        0xbf => "i32x4.extmul_high_i16x8_u",
+        0xc0 => "i64x2.abs",
+        0xc1 => "i64x2.neg",
+        0xc3 => "i64x2.all_true",
+        0xc4 => "i64x2.bitmask",
+        0xc7 => "i64x2.extend_low_i32x4_s",
+        0xc8 => "i64x2.extend_high_i32x4_s",
+        0xc9 => "i64x2.extend_low_i32x4_u",
+        0xca => "i64x2.extend_high_i32x4_u",
+        0xcb => "i64x2.shl",
+        0xcc => "i64x2.shr_s",
+        0xcd => "i64x2.shr_u",
+        0xce => "i64x2.add",
+        0xd1 => "i64x2.sub",
+        0xd5 => "i64x2.mul",
+        0xd6 => "i64x2.eq",
+        0xd7 => "i64x2.ne",
+        0xd8 => "i64x2.lt_s",
+        0xd9 => "i64x2.gt_s",
+        0xda => "i64x2.le_s",
+        0xdb => "i64x2.ge_s",
+        0xdc => "i64x2.extmul_low_i32x4_s",
+        0xdd => "i64x2.extmul_high_i32x4_s",
+        0xde => "i64x2.extmul_low_i32x4_u",
+        0xdf => "i64x2.extmul_high_i32x4_u",
+        0xe0 => "f32x4.abs",
+        0xe1 => "f32x4.neg",
+        0xe3 => "f32x4.sqrt",
+        0xe4 => "f32x4.add",
+        0xe5 => "f32x4.sub",
+        0xe6 => "f32x4.mul",
+        0xe7 => "f32x4.div",
+        0xe8 => "f32x4.min",
+        0xe9 => "f32x4.max",
+        0xea => "f32x4.pmin",
+        0xeb => "f32x4.pmax",
+        0xec => "f64x2.abs",
+        0xed => "f64x2.neg",
+        0xef => "f64x2.sqrt",
+        0xf0 => "f64x2.add",
+        0xf1 => "f64x2.sub",
+        0xf2 => "f64x2.mul",
+        0xf3 => "f64x2.div",
+        0xf4 => "f64x2.min",
+        0xf5 => "f64x2.max",
+        0xf6 => "f64x2.pmin",
+        0xf7 => "f64x2.pmax",
+        0xf8 => "i32x4.trunc_sat_f32x4_s",
+        0xf9 => "i32x4.trunc_sat_f32x4_u",
+        0xfa => "f32x4.convert_i32x4_s",
+        0xfb => "f32x4.convert_i32x4_u",
+        0xfc => "i32x4.trunc_sat_f64x2_s_zero",
+        0xfd => "i32x4.trunc_sat_f64x2_u_zero",
+        0xfe => "f64x2.convert_low_i32x4_s",
+        0xff => "f64x2.convert_low_i32x4_u",
+        _ => return None,
+    };
+    Some(mnemonic)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WAT: &str = r#"(module
+        (memory 1)
+        (func $f (param i32) (result i32)
+            (local v128)
+            (local.set 1 (f32x4.div (v128.const f32x4 0 0 0 0) (v128.const f32x4 0 0 0 0)))
+            (block
+                (loop
+                    (local.set 1
+                        (i8x16.shuffle 0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15
+                            (local.get 1) (local.get 1)))
+                    (br_if 1 (i32.eqz (local.get 0)))
+                    (local.set 0 (i32.sub (local.get 0) (i32.const 1)))
+                    (br 0)))
+            (v128.store offset=16 (i32.const 0) (f64x2.sqrt (local.get 1)))
+            (i32x4.extract_lane 3 (i32x4.add (local.get 1) (v128.load (i32.const 0)))))
+        (export "f" (func $f)))"#;
+
+    fn simd_mnemonics(module: &Module) -> Vec<&'static str> {
+        module.code_section().unwrap().bodies()[0]
+            .code()
+            .elements()
+            .iter()
+            .filter_map(simd_instruction_mnemonic)
+            .collect()
+    }
+
+    #[test]
+    fn simd_instructions_are_restored() {
+        let wasm = wat::parse_str(WAT).unwrap();
+        let (rewritten, simd_instructions) = extract_simd_instructions(&wasm).unwrap();
+        let mut module = parity_wasm::deserialize_buffer::<Module>(&rewritten).unwrap();
+        assert_eq!(
+            simd_mnemonics(&module),
+            vec![
+                "v128.const",
+                "v128.const",
+                "f32x4.div",
+                "i8x16.shuffle",
+                "f64x2.sqrt",
+                "v128.store",
+                "v128.load",
+                "i32x4.add",
+                "i32x4.extract_lane"
+            ]
+        );
+        assert_eq!(simd_instructions.serialize(module.clone()).unwrap(), wasm);
+
+        // The instructions are restored after the code was modified.
+        module.code_section_mut().unwrap().bodies_mut()[0]
+            .code_mut()
+            .elements_mut()
+            .insert(0, Instruction::Nop);
+        let modified = simd_instructions.serialize(module).unwrap();
+        let mut validator = wasmparser::Validator::new();
+        validator.wasm_features(wasmparser::WasmFeatures {
+            simd: true,
+            ..Default::default()
+        });
+        validator.validate_all(&modified).unwrap();
+        assert_eq!(modified.len(), wasm.len() + 1);
+    }
+
+    #[test]
+    fn relaxed_simd_instructions_are_rejected() {
+        let mut wasm = wat::parse_str(WAT).unwrap();
+        // Replace `f32x4.div` by the relaxed `f32x4.fma` instruction.
+        let position = wasm
+            .windows(3)
+            .position(|window| window == [SIMD_PREFIX, 0xe7, 0x01])
+            .unwrap();
+        wasm[position + 1] = 0xaf;
+        assert_eq!(
+            extract_simd_instructions(&wasm).err(),
+            Some(SimdError::NonDeterministicInstruction {
+                offset: position,
+                opcode: 0xaf
+            })
+        );
+    }
+}
@@ -2,13 +2,16 @@
 //! installed on the Internet Computer.
 
 use super::errors::into_parity_wasm_error;
+use super::memory64::{extract_memory64, Memory64Error};
+use super::simd::{extract_simd_instructions, SimdError};
 
 use ic_config::{
     embedders::{Config as EmbeddersConfig, FeatureFlags},
     flag_status::FlagStatus,
 };
-use ic_replicated_state::canister_state::execution_state::{
-    CustomSection, CustomSectionType, WasmMetadata,
+use ic_replicated_state::canister_state::{
+    execution_state::{CustomSection, CustomSectionType, WasmMetadata},
+    WASM_PAGE_SIZE_IN_BYTES,
 };
 use ic_types::NumBytes;
 use ic_wasm_types::{BinaryEncodedWasm, WasmValidationError};
@@ -46,7 +49,15 @@ const API_VERSION_IC0: &str = "ic0";
 // module.
 fn get_valid_system_apis(
     feature_flags: &FeatureFlags,
+    memory64: bool,
 ) -> HashMap<String, HashMap<String, FunctionSignature>> {
+    // Pointers into the Wasm memory and sizes of memory regions have the
+    // width of the memory's addresses.
+    let ptr = if memory64 {
+        ValueType::I64
+    } else {
+        ValueType::I32
+    };
     let mut valid_system_apis = vec![
         (
             // Public methods
@@ -55,7 +66,7 @@ fn get_valid_system_apis(
                 API_VERSION_IC0,
                 FunctionSignature {
                     param_types: vec![],
-                    return_type: vec![ValueType::I32],
+                    return_type: vec![ptr],
                 },
             )],
         ),
@@ -64,7 +75,7 @@ fn get_valid_system_apis(
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValueType::I32, ValueType::I32, ValueType::I32],
+                    param_types: vec![ptr, ptr, ptr],
                     return_type: vec![],
                 },
             )],
@@ -75,7 +86,7 @@ fn get_valid_system_apis(
                 API_VERSION_IC0,
                 FunctionSignature {
                     param_types: vec![],
-                    return_type: vec![ValueType::I32],
+                    return_type: vec![ptr],
                 },
             )],
         ),
@@ -84,7 +95,7 @@ fn get_valid_system_apis(
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValueType::I32, ValueType::I32, ValueType::I32],
+                    param_types: vec![ptr, ptr, ptr],
                     return_type: vec![],
                 },
             )],
@@ -95,7 +106,7 @@ fn get_valid_system_apis(
                 API_VERSION_IC0,
                 FunctionSignature {
                     param_types: vec![],
-                    return_type: vec![ValueType::I32],
+                    return_type: vec![ptr],
                 },
             )],
         ),
@@ -104,7 +115,7 @@ fn get_valid_system_apis(
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValueType::I32, ValueType::I32, ValueType::I32],
+                    param_types: vec![ptr, ptr, ptr],
                     return_type: vec![],
                 },
             )],
@@ -129,13 +140,23 @@ fn get_valid_system_apis(
                 },
             )],
         ),
+        (
+            "msg_deadline",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![],
+                    return_type: vec![ValueType::I64],
+                },
+            )],
+        ),
         (
             "msg_reject_msg_size",
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
                     param_types: vec![],
-                    return_type: vec![ValueType::I32],
+                    return_type: vec![ptr],
                 },
             )],
         ),
@@ -144,7 +165,7 @@ fn get_valid_system_apis(
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValueType::I32, ValueType::I32, ValueType::I32],
+                    param_types: vec![ptr, ptr, ptr],
                     return_type: vec![],
                 },
             )],
@@ -154,7 +175,7 @@ fn get_valid_system_apis(
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValueType::I32, ValueType::I32],
+                    param_types: vec![ptr, ptr],
                     return_type: vec![],
                 },
             )],
@@ -174,7 +195,7 @@ fn get_valid_system_apis(
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValueType::I32, ValueType::I32],
+                    param_types: vec![ptr, ptr],
                     return_type: vec![],
                 },
             )],
@@ -185,7 +206,7 @@ fn get_valid_system_apis(
                 API_VERSION_IC0,
                 FunctionSignature {
                     param_types: vec![],
-                    return_type: vec![ValueType::I32],
+                    return_type: vec![ptr],
                 },
             )],
         ),
@@ -194,7 +215,7 @@ fn get_valid_system_apis(
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValueType::I32, ValueType::I32, ValueType::I32],
+                    param_types: vec![ptr, ptr, ptr],
                     return_type: vec![],
                 },
             )],
@@ -205,7 +226,7 @@ fn get_valid_system_apis(
                 API_VERSION_IC0,
                 FunctionSignature {
                     param_types: vec![],
-                    return_type: vec![ValueType::I32],
+                    return_type: vec![ptr],
                 },
             )],
         ),
@@ -214,7 +235,7 @@ fn get_valid_system_apis(
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValueType::I32, ValueType::I32, ValueType::I32],
+                    param_types: vec![ptr, ptr, ptr],
                     return_type: vec![],
                 },
             )],
@@ -236,16 +257,16 @@ fn get_valid_system_apis(
                 API_VERSION_IC0,
                 FunctionSignature {
                     param_types: vec![
+                        ptr,
+                        ptr,
+                        ptr,
+                        ptr,
                         ValueType::I32,
                         ValueType::I32,
                         ValueType::I32,
                         ValueType::I32,
-                        ValueType::I32,
-                        ValueType::I32,
-                        ValueType::I32,
-                        ValueType::I32,
-                        ValueType::I32,
-                        ValueType::I32,
+                        ptr,
+                        ptr,
                     ],
                     return_type: vec![ValueType::I32],
                 },
@@ -257,10 +278,10 @@ fn get_valid_system_apis(
                 API_VERSION_IC0,
                 FunctionSignature {
                     param_types: vec![
-                        ValueType::I32,
-                        ValueType::I32,
-                        ValueType::I32,
-                        ValueType::I32,
+                        ptr,
+                        ptr,
+                        ptr,
+                        ptr,
                         ValueType::I32,
                         ValueType::I32,
                         ValueType::I32,
@@ -275,7 +296,7 @@ fn get_valid_system_apis(
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValueType::I32, ValueType::I32],
+                    param_types: vec![ptr, ptr],
                     return_type: vec![],
                 },
             )],
@@ -290,6 +311,16 @@ fn get_valid_system_apis(
                 },
             )],
         ),
+        (
+            "call_with_best_effort_response",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![ValueType::I32],
+                    return_type: vec![],
+                },
+            )],
+        ),
         (
             "call_cycles_add",
             vec![(
@@ -316,7 +347,7 @@ fn get_valid_system_apis(
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValueType::I32, ValueType::I32],
+                    param_types: vec![ptr, ptr],
                     return_type: vec![],
                 },
             )],
@@ -426,7 +457,7 @@ fn get_valid_system_apis(
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValueType::I32, ValueType::I32],
+                    param_types: vec![ptr, ptr],
                     return_type: vec![],
                 },
             )],
@@ -476,7 +507,7 @@ fn get_valid_system_apis(
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValueType::I32, ValueType::I32],
+                    param_types: vec![ptr, ptr],
                     return_type: vec![],
                 },
             )],
@@ -497,7 +528,7 @@ fn get_valid_system_apis(
                 API_VERSION_IC0,
                 FunctionSignature {
                     param_types: vec![],
-                    return_type: vec![ValueType::I32],
+                    return_type: vec![ptr],
                 },
             )],
         ),
@@ -506,7 +537,7 @@ fn get_valid_system_apis(
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValueType::I32, ValueType::I32, ValueType::I32],
+                    param_types: vec![ptr, ptr, ptr],
                     return_type: vec![],
                 },
             )],
@@ -551,7 +582,7 @@ fn get_valid_system_apis(
                 vec![(
                     API_VERSION_IC0,
                     FunctionSignature {
-                        param_types: vec![ValueType::I32],
+                        param_types: vec![ptr],
                         return_type: vec![],
                     },
                 )],
@@ -561,7 +592,7 @@ fn get_valid_system_apis(
                 vec![(
                     API_VERSION_IC0,
                     FunctionSignature {
-                        param_types: vec![ValueType::I32],
+                        param_types: vec![ptr],
                         return_type: vec![],
                     },
                 )],
@@ -571,7 +602,7 @@ fn get_valid_system_apis(
                 vec![(
                     API_VERSION_IC0,
                     FunctionSignature {
-                        param_types: vec![ValueType::I32],
+                        param_types: vec![ptr],
                         return_type: vec![],
                     },
                 )],
@@ -581,7 +612,7 @@ fn get_valid_system_apis(
                 vec![(
                     API_VERSION_IC0,
                     FunctionSignature {
-                        param_types: vec![ValueType::I64, ValueType::I64, ValueType::I32],
+                        param_types: vec![ValueType::I64, ValueType::I64, ptr],
                         return_type: vec![],
                     },
                 )],
@@ -712,18 +743,20 @@ fn set_imports_details(import_details: &mut WasmImportsDetails, import_module: &
 // Performs the following checks for the import section:
 // * If we import memory or table, we can only import from “env”.
 // * Any imported functions that appear in `valid_system_apis` have the correct
-//   signatures.
+//   signatures. The pointers and sizes passed to the System API are 64 bits
+//   wide if the memory of the module has 64-bit addresses.
 //
 // Returns information about what IC0 methods are imported via
 // `WasmImportsDetails`.
 fn validate_import_section(
     module: &Module,
     feature_flags: &FeatureFlags,
+    memory64: bool,
 ) -> Result<WasmImportsDetails, WasmValidationError> {
     let mut imports_details = WasmImportsDetails::default();
 
     if let Some(section) = module.import_section() {
-        let valid_system_apis = get_valid_system_apis(feature_flags);
+        let valid_system_apis = get_valid_system_apis(feature_flags, memory64);
         for entry in section.entries() {
             let import_module = entry.module();
             let field = entry.field();
@@ -857,7 +890,8 @@ fn validate_export_section(module: &Module) -> Result<usize, WasmValidationError
 }
 
 // Checks that offset-expressions in data sections consist of only one constant
-// expression. Required because of OP. See also:
+// expression, which is an `i64` constant if the memory has 64-bit addresses.
+// Required because of OP. See also:
 // src/hypervisor/metering_injector/mod.rs
 fn validate_data_section(module: &Module) -> Result<(), WasmValidationError> {
     fn validate_segment(s: &DataSegment) -> Result<(), WasmValidationError> {
@@ -866,7 +900,8 @@ fn validate_data_section(module: &Module) -> Result<(), WasmValidationError> {
                 "Empty offset in data segment.".to_string(),
             )),
             Some(expr) => match expr.code() {
-                [Instruction::I32Const(_), Instruction::End] => Ok(()),
+                [Instruction::I32Const(_), Instruction::End]
+                | [Instruction::I64Const(_), Instruction::End] => Ok(()),
                 _ => Err(WasmValidationError::InvalidDataSection(
                     "Invalid offset expression in data segment.".to_string(),
                 )),
@@ -885,7 +920,40 @@ fn validate_data_section(module: &Module) -> Result<(), WasmValidationError> {
         .try_for_each(validate_segment)
 }
 
-// Checks that no more than `max_globals` are defined in the module.
+// Checks that a memory with 64-bit addresses is only defined if the `wasm64`
+// feature is enabled and that it does not initially exceed the maximum size of
+// such memories.
+fn validate_memory_section(
+    module: &Module,
+    memory64: bool,
+    config: &EmbeddersConfig,
+) -> Result<(), WasmValidationError> {
+    if !memory64 {
+        return Ok(());
+    }
+    if config.feature_flags.wasm64 == FlagStatus::Disabled {
+        return Err(WasmValidationError::InvalidMemorySection(
+            "Memories with 64-bit addresses are not supported.".to_string(),
+        ));
+    }
+    let max_pages = config.max_wasm64_memory_size.get() / WASM_PAGE_SIZE_IN_BYTES as u64;
+    if let Some(section) = module.memory_section() {
+        for memory in section.entries() {
+            let initial_pages = memory.limits().initial() as u64;
+            if initial_pages > max_pages {
+                return Err(WasmValidationError::InvalidMemorySection(format!(
+                    "The memory with 64-bit addresses has {} initial pages, which exceeds the maximum of {} pages.",
+                    initial_pages, max_pages
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+// Checks that no more than `max_globals` are defined in the module and that
+// none of them has type `v128`, because the values of exported globals are
+// persisted and `v128` values cannot be represented in the canister state.
 fn validate_global_section(module: &Module, max_globals: usize) -> Result<(), WasmValidationError> {
     if let Some(section) = module.global_section() {
         let globals_defined = section.entries().len();
@@ -895,6 +963,16 @@ fn validate_global_section(module: &Module, max_globals: usize) -> Result<(), Wa
                 allowed: max_globals,
             });
         }
+        if let Some(index) = section
+            .entries()
+            .iter()
+            .position(|global| global.global_type().content_type() == ValueType::V128)
+        {
+            return Err(WasmValidationError::InvalidGlobalSection(format!(
+                "Global {} has type v128, which is not supported.",
+                index
+            )));
+        }
     }
     Ok(())
 }
@@ -1048,13 +1126,39 @@ fn validate_code_section(module: &Module) -> Result<(), WasmValidationError> {
 }
 
 /// Sets Wasmtime flags to ensure deterministic execution.
+///
+/// SIMD is enabled, because NaN canonicalization also applies to vector
+/// floating-point operations. The non-deterministic instructions of the relaxed
+/// SIMD proposal are rejected by `validate_wasm_binary`. Memories with 64-bit
+/// addresses are accepted by Wasmtime, but `validate_wasm_binary` rejects them
+/// unless the `wasm64` feature flag is enabled.
 pub fn ensure_determinism(config: &mut Config) {
+    let settings = DETERMINISTIC_SETTINGS;
     config
-        .wasm_threads(false)
-        .wasm_simd(false)
-        .cranelift_nan_canonicalization(true);
+        .wasm_threads(settings.threads)
+        .wasm_simd(settings.simd)
+        .wasm_memory64(settings.memory64)
+        .cranelift_nan_canonicalization(settings.nan_canonicalization);
+}
+
+/// The Wasmtime flags set by [ensure_determinism].
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct DeterministicSettings {
+    pub threads: bool,
+    pub simd: bool,
+    pub memory64: bool,
+    pub nan_canonicalization: bool,
 }
 
+/// The flags set by [ensure_determinism]. They are part of the key of the
+/// compilation cache, as they affect the compiled code.
+pub(crate) const DETERMINISTIC_SETTINGS: DeterministicSettings = DeterministicSettings {
+    threads: false,
+    simd: true,
+    memory64: true,
+    nan_canonicalization: true,
+};
+
 fn can_compile(wasm: &BinaryEncodedWasm) -> Result<(), WasmValidationError> {
     let mut config = wasmtime::Config::default();
     ensure_determinism(&mut config);
@@ -1105,18 +1209,31 @@ pub struct WasmValidationDetails {
 /// * Data
 /// * Global
 /// * Function
+/// * Memory
 /// * CustomSections
 ///
-/// Additionally, it ensures that the wasm binary can actually compile.
+/// Additionally, it ensures that the wasm binary can actually compile and that
+/// it only uses deterministic SIMD instructions.
 pub fn validate_wasm_binary(
     wasm: &BinaryEncodedWasm,
     config: &EmbeddersConfig,
 ) -> Result<WasmValidationDetails, WasmValidationError> {
     can_compile(wasm)?;
-    let module = parity_wasm::deserialize_buffer::<Module>(wasm.as_slice())
+    let (wasm, _) = extract_simd_instructions(wasm.as_slice()).map_err(|err| match err {
+        SimdError::Decoding(err) => WasmValidationError::DecodingError(err),
+        SimdError::NonDeterministicInstruction { .. } => {
+            WasmValidationError::NonDeterministicInstruction(err.to_string())
+        }
+    })?;
+    let (wasm, memory64) = extract_memory64(&wasm).map_err(|err| match err {
+        Memory64Error::Decoding(err) => WasmValidationError::DecodingError(err),
+        Memory64Error::InvalidMemory(err) => WasmValidationError::InvalidMemorySection(err),
+    })?;
+    let module = parity_wasm::deserialize_buffer::<Module>(&wasm)
         .map_err(|err| WasmValidationError::ParityDeserializeError(into_parity_wasm_error(err)))?;
-    let imports_details = validate_import_section(&module, &config.feature_flags)?;
+    let imports_details = validate_import_section(&module, &config.feature_flags, memory64)?;
     let reserved_exports = validate_export_section(&module)?;
+    validate_memory_section(&module, memory64, config)?;
     validate_data_section(&module)?;
     validate_global_section(&module, config.max_globals)?;
     validate_function_section(&module, config.max_functions)?;
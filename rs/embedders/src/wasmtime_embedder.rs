@@ -10,7 +10,7 @@ use std::{
 };
 
 use ic_system_api::ModificationTracking;
-use wasmtime::{unix::StoreExt, Memory, Mutability, Store, Val, ValType};
+use wasmtime::{unix::StoreExt, ExternType, Memory, Mutability, Store, Val, ValType};
 
 use host_memory::MmapMemoryCreator;
 pub use host_memory::WasmtimeMemoryCreator;
@@ -25,10 +25,11 @@ use ic_types::{
     methods::{FuncRef, WasmMethod},
     CanisterId, NumInstructions,
 };
-use ic_wasm_types::{BinaryEncodedWasm, WasmEngineError};
+use ic_wasm_types::{BinaryEncodedWasm, SerializedModule, WasmEngineError};
 use memory_tracker::{DirtyPageTracking, SigsegvMemoryTracker};
 use signal_stack::WasmtimeSignalStack;
 
+use crate::compilation_cache::{compile_module, CompilationCache};
 use crate::wasm_utils::validation::ensure_determinism;
 
 use super::InstanceRunResult;
@@ -104,6 +105,10 @@ fn trap_code_to_hypervisor_error(trap_code: wasmtime::TrapCode) -> HypervisorErr
     }
 }
 
+/// The maximum size in bytes where a linear memory is considered static.
+pub(crate) const STATIC_MEMORY_MAXIMUM_SIZE: u64 =
+    wasmtime_environ::WASM_PAGE_SIZE as u64 * wasmtime_environ::WASM32_MAX_PAGES as u64;
+
 pub struct WasmtimeEmbedder {
     log: ReplicaLogger,
     config: EmbeddersConfig,
@@ -112,6 +117,8 @@ pub struct WasmtimeEmbedder {
     // and remove it. So memories will only be in this map for the time between module
     // instatiation and creation of the corresponding `SigsegvMemoryTracker`.
     created_memories: Arc<Mutex<HashMap<MemoryStart, MemoryPageSize>>>,
+    // The on-disk cache of compiled modules, if enabled.
+    compilation_cache: Option<Arc<CompilationCache>>,
 }
 
 impl WasmtimeEmbedder {
@@ -120,16 +127,37 @@ impl WasmtimeEmbedder {
             log,
             config,
             created_memories: Arc::new(Mutex::new(HashMap::new())),
+            compilation_cache: None,
         }
     }
 
+    /// Loads compiled modules from the given on-disk cache instead of
+    /// compiling them, if they are present, and adds newly compiled modules
+    /// to it unless the cache is read-only.
+    pub fn with_compilation_cache(mut self, compilation_cache: Arc<CompilationCache>) -> Self {
+        self.compilation_cache = Some(compilation_cache);
+        self
+    }
+
     pub fn compile(&self, wasm_binary: &BinaryEncodedWasm) -> HypervisorResult<EmbedderCache> {
+        self.compile_and_serialize(wasm_binary)
+            .map(|(embedder_cache, _)| embedder_cache)
+    }
+
+    /// Like [`WasmtimeEmbedder::compile`], but also returns the serialized
+    /// module if it was compiled and could not be added to the read-only
+    /// compilation cache, so that the caller can have it added by the replica.
+    pub fn compile_and_serialize(
+        &self,
+        wasm_binary: &BinaryEncodedWasm,
+    ) -> HypervisorResult<(EmbedderCache, Option<SerializedModule>)> {
         let mut config = wasmtime::Config::default();
         ensure_determinism(&mut config);
         let raw_creator = MmapMemoryCreator {};
         let mem_creator = Arc::new(WasmtimeMemoryCreator::new(
             raw_creator,
             Arc::clone(&self.created_memories),
+            self.max_wasm64_pages().get() as u64,
         ));
         config.with_host_memory(mem_creator);
 
@@ -137,23 +165,22 @@ impl WasmtimeEmbedder {
             // maximum size in bytes where a linear memory is considered
             // static. setting this to maximum Wasm memory size will guarantee
             // the memory is always static.
-            .static_memory_maximum_size(
-                wasmtime_environ::WASM_PAGE_SIZE as u64 * wasmtime_environ::WASM32_MAX_PAGES as u64,
-            )
+            .static_memory_maximum_size(STATIC_MEMORY_MAXIMUM_SIZE)
             .max_wasm_stack(self.config.max_wasm_stack_size)
             .map_err(|_| HypervisorError::WasmEngineError(WasmEngineError::FailedToSetWasmStack))?;
 
         let engine = wasmtime::Engine::new(&config).map_err(|_| {
             HypervisorError::WasmEngineError(WasmEngineError::FailedToInitializeEngine)
         })?;
-        let module = wasmtime::Module::new(&engine, wasm_binary.as_slice()).map_err(|_| {
-            HypervisorError::WasmEngineError(WasmEngineError::FailedToInstantiateModule)
-        })?;
+        let (module, serialized_module) = match &self.compilation_cache {
+            Some(cache) => cache.get_or_compile(&engine, wasm_binary, &self.config)?,
+            None => (compile_module(&engine, wasm_binary)?, None),
+        };
         // Note that a wasmtime::Module object is cheaply clonable (just doing
         // a bit of reference counting, i.e. it is a "shallow copy"). This is
         // important because EmbedderCache is cloned frequently, and that must
         // not be an expensive operation.
-        Ok(EmbedderCache::new(module))
+        Ok((EmbedderCache::new(module), serialized_module))
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -179,12 +206,27 @@ impl WasmtimeEmbedder {
             },
         );
 
-        let linker = system_api::syscalls(
-            self.log.clone(),
-            canister_id,
-            &store,
-            self.config.feature_flags.rate_limiting_of_debug_prints,
-        );
+        let memory64 = is_memory64(module);
+        let linker = if memory64 {
+            system_api::syscalls::<S, i64>(
+                self.log.clone(),
+                canister_id,
+                &store,
+                self.config.feature_flags.rate_limiting_of_debug_prints,
+            )
+        } else {
+            system_api::syscalls::<S, i32>(
+                self.log.clone(),
+                canister_id,
+                &store,
+                self.config.feature_flags.rate_limiting_of_debug_prints,
+            )
+        };
+        let max_heap_size = if memory64 {
+            self.max_wasm64_pages()
+        } else {
+            NumWasmPages::from(wasmtime_environ::WASM32_MAX_PAGES as usize)
+        };
 
         let instance = match linker.instantiate(&mut store, module) {
             Ok(instance) => instance,
@@ -323,6 +365,7 @@ impl WasmtimeEmbedder {
             instance,
             memory_tracker,
             signal_stack,
+            max_heap_size,
             log: self.log.clone(),
             instance_stats: InstanceStats {
                 accessed_pages: 0,
@@ -331,6 +374,23 @@ impl WasmtimeEmbedder {
             store,
         })
     }
+
+    /// Returns the maximum number of pages of a 64-bit memory.
+    fn max_wasm64_pages(&self) -> NumWasmPages {
+        NumWasmPages::from(
+            (self.config.max_wasm64_memory_size.get() / wasmtime_environ::WASM_PAGE_SIZE as u64)
+                as usize,
+        )
+    }
+}
+
+/// Returns true if the memory of the given module has 64-bit addresses.
+fn is_memory64(module: &wasmtime::Module) -> bool {
+    module
+        .imports()
+        .map(|import| import.ty())
+        .chain(module.exports().map(|export| export.ty()))
+        .any(|ty| matches!(ty, ExternType::Memory(memory) if memory.is_64()))
 }
 
 struct StoreRef(*mut wasmtime::Store<()>);
@@ -396,6 +456,7 @@ pub struct WasmtimeInstance<S: SystemApi> {
     instance: wasmtime::Instance,
     memory_tracker: Option<Arc<Mutex<SigsegvMemoryTracker>>>,
     signal_stack: WasmtimeSignalStack,
+    max_heap_size: NumWasmPages,
     log: ReplicaLogger,
     instance_stats: InstanceStats,
     store: wasmtime::Store<StoreData<S>>,
@@ -552,11 +613,16 @@ impl<S: SystemApi> WasmtimeInstance<S> {
     }
 
     /// Returns the heap size.
-    /// Result is guaranteed to fit in a `u32`.
     pub fn heap_size(&mut self) -> NumWasmPages {
         NumWasmPages::from(self.memory().map_or(0, |mem| mem.size(&self.store)) as usize)
     }
 
+    /// Returns the maximum heap size, which depends on whether the memory has
+    /// 32-bit or 64-bit addresses.
+    pub fn max_heap_size(&self) -> NumWasmPages {
+        self.max_heap_size
+    }
+
     /// Returns a list of exported globals.
     pub fn get_exported_globals(&mut self) -> Vec<Global> {
         let globals: Vec<_> = self
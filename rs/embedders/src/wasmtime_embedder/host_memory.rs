@@ -29,8 +29,8 @@ fn round_up_to_os_page_size(size: usize) -> usize {
     round_up_to_page_size(size, PAGE_SIZE)
 }
 
-fn wasm_max_mem_size_in_bytes() -> usize {
-    WASM32_MAX_PAGES as usize * WASM_PAGE_SIZE as usize
+fn wasm_max_mem_size_in_bytes(max_pages: u64) -> usize {
+    max_pages as usize * WASM_PAGE_SIZE as usize
 }
 
 #[derive(Hash, PartialEq, Eq)]
@@ -52,16 +52,20 @@ where
 {
     raw_creator: C,
     created_memories: Arc<Mutex<HashMap<MemoryStart, MemoryPageSize>>>,
+    // The maximum number of pages of a 64-bit memory.
+    max_wasm64_pages: u64,
 }
 
 impl<C: ICMemoryCreator> WasmtimeMemoryCreator<C> {
     pub(crate) fn new(
         raw_creator: C,
         created_memories: Arc<Mutex<HashMap<MemoryStart, MemoryPageSize>>>,
+        max_wasm64_pages: u64,
     ) -> Self {
         Self {
             raw_creator,
             created_memories,
+            max_wasm64_pages,
         }
     }
 }
@@ -82,11 +86,21 @@ where
         // and has asserts for that in its Memory implementation
         // but let's just clip to that without panicking in case they change
         // something...
-        let min = std::cmp::min(ty.minimum(), WASM32_MAX_PAGES) as usize;
-        let max =
-            std::cmp::min(ty.maximum().unwrap_or(WASM32_MAX_PAGES), WASM32_MAX_PAGES) as usize;
+        //
+        // 64-bit memories are additionally clipped to the configured maximum.
+        // If Wasmtime does not reserve the address space of a memory, its
+        // full maximum size is reserved here, so that the memory never has to
+        // move when it grows.
+        let max_pages = if ty.is_64() {
+            self.max_wasm64_pages
+        } else {
+            WASM32_MAX_PAGES
+        };
+        let min = std::cmp::min(ty.minimum(), max_pages) as usize;
+        let max = std::cmp::min(ty.maximum().unwrap_or(max_pages), max_pages) as usize;
 
-        let mem_size = reserved_size_in_bytes.unwrap_or_else(wasm_max_mem_size_in_bytes);
+        let mem_size =
+            reserved_size_in_bytes.unwrap_or_else(|| wasm_max_mem_size_in_bytes(max_pages));
 
         let mem = self
             .raw_creator
@@ -8,9 +8,43 @@ use ic_logger::{error, info, ReplicaLogger};
 use ic_registry_subnet_type::SubnetType;
 use ic_types::{CanisterId, Cycles, NumBytes, NumInstructions};
 
-use wasmtime::{AsContextMut, Caller, Global, Linker, Store, Trap, Val};
+use wasmtime::{AsContextMut, Caller, Global, Linker, Store, Trap, Val, WasmTy};
 
 use std::convert::TryFrom;
+use std::num::TryFromIntError;
+
+/// The type of the addresses and sizes passed to the System API: `i32` for
+/// modules with a 32-bit memory and `i64` for modules with a 64-bit memory.
+pub(crate) trait WasmPointer:
+    WasmTy + Copy + Into<i64> + TryFrom<u64, Error = TryFromIntError>
+{
+    /// Returns the value as an unsigned address or size.
+    fn to_u64(self) -> u64;
+
+    /// Converts the result of a native `memory.grow`, i.e., the previous
+    /// number of pages or -1, back to the type of the memory.
+    fn from_memory_grow_result(result: i64) -> Self;
+}
+
+impl WasmPointer for i32 {
+    fn to_u64(self) -> u64 {
+        self as u32 as u64
+    }
+
+    fn from_memory_grow_result(result: i64) -> Self {
+        result as i32
+    }
+}
+
+impl WasmPointer for i64 {
+    fn to_u64(self) -> u64 {
+        self as u64
+    }
+
+    fn from_memory_grow_result(result: i64) -> Self {
+        result
+    }
+}
 
 fn process_err<S: SystemApi>(
     mut store: impl AsContextMut<Data = StoreData<S>>,
@@ -116,7 +150,7 @@ fn charge_for_system_api_call<S: SystemApi>(
     canister_id: CanisterId,
     caller: &mut Caller<'_, StoreData<S>>,
     system_api_charge: NumInstructions,
-    num_bytes: u32,
+    num_bytes: u64,
 ) -> Result<(), Trap> {
     let num_instructions_global = get_num_instructions_global(caller, log, canister_id)?;
     let current_instructions =
@@ -135,7 +169,7 @@ fn charge_for_system_api_call<S: SystemApi>(
         // Continue execution
     }
     let fee = system_api
-        .get_num_instructions_from_bytes(NumBytes::from(num_bytes as u64))
+        .get_num_instructions_from_bytes(NumBytes::from(num_bytes))
         .get() as i64
         + system_api_charge.get() as i64;
     if current_instructions < fee {
@@ -231,7 +265,9 @@ fn ic0_performance_counter_helper<S: SystemApi>(
         .map_err(|e| process_err(caller, e))
 }
 
-pub(crate) fn syscalls<S: SystemApi>(
+/// Returns a linker with the System API for a module whose memory addresses
+/// and sizes have type `P`.
+pub(crate) fn syscalls<S: SystemApi, P: WasmPointer>(
     log: ReplicaLogger,
     canister_id: CanisterId,
     store: &Store<StoreData<S>>,
@@ -283,9 +319,14 @@ pub(crate) fn syscalls<S: SystemApi>(
 
     linker
         .func_wrap("ic0", "msg_caller_copy", {
-            move |caller: Caller<'_, StoreData<S>>, dst: i32, offset: i32, size: i32| {
+            move |caller: Caller<'_, StoreData<S>>, dst: P, offset: P, size: P| {
                 with_memory_and_system_api(caller, |system_api, memory| {
-                    system_api.ic0_msg_caller_copy(dst as u32, offset as u32, size as u32, memory)
+                    system_api.ic0_msg_caller_copy(
+                        dst.to_u64(),
+                        offset.to_u64(),
+                        size.to_u64(),
+                        memory,
+                    )
                 })
             }
         })
@@ -297,7 +338,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                 with_system_api(&mut caller, |s| s.ic0_msg_caller_size())
                     .map_err(|e| process_err(caller, e))
                     .and_then(|s| {
-                        i32::try_from(s).map_err(|e| {
+                        P::try_from(s as u64).map_err(|e| {
                             wasmtime::Trap::new(format!("ic0::msg_caller_size failed: {}", e))
                         })
                     })
@@ -311,7 +352,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                 with_system_api(&mut caller, |s| s.ic0_msg_arg_data_size())
                     .map_err(|e| process_err(caller, e))
                     .and_then(|s| {
-                        i32::try_from(s).map_err(|e| {
+                        P::try_from(s as u64).map_err(|e| {
                             wasmtime::Trap::new(format!("ic0::msg_arg_data_size failed: {}", e))
                         })
                     })
@@ -322,16 +363,21 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "msg_arg_data_copy", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, dst: i32, offset: i32, size: i32| {
+            move |mut caller: Caller<'_, StoreData<S>>, dst: P, offset: P, size: P| {
                 charge_for_system_api_call(
                     &log,
                     canister_id,
                     &mut caller,
                     system_api_complexity::overhead::MSG_ARG_DATA_COPY,
-                    size as u32,
+                    size.to_u64(),
                 )?;
                 with_memory_and_system_api(caller, |system_api, mem| {
-                    system_api.ic0_msg_arg_data_copy(dst as u32, offset as u32, size as u32, mem)
+                    system_api.ic0_msg_arg_data_copy(
+                        dst.to_u64(),
+                        offset.to_u64(),
+                        size.to_u64(),
+                        mem,
+                    )
                 })
             }
         })
@@ -343,7 +389,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                 with_system_api(&mut caller, |s| s.ic0_msg_method_name_size())
                     .map_err(|e| process_err(caller, e))
                     .and_then(|s| {
-                        i32::try_from(s).map_err(|e| {
+                        P::try_from(s as u64).map_err(|e| {
                             wasmtime::Trap::new(format!("ic0::msg_metohd_name_size failed: {}", e))
                         })
                     })
@@ -354,19 +400,19 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "msg_method_name_copy", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, dst: i32, offset: i32, size: i32| {
+            move |mut caller: Caller<'_, StoreData<S>>, dst: P, offset: P, size: P| {
                 charge_for_system_api_call(
                     &log,
                     canister_id,
                     &mut caller,
                     system_api_complexity::overhead::MSG_METHOD_NAME_COPY,
-                    size as u32,
+                    size.to_u64(),
                 )?;
                 with_memory_and_system_api(caller, |system_api, memory| {
                     system_api.ic0_msg_method_name_copy(
-                        dst as u32,
-                        offset as u32,
-                        size as u32,
+                        dst.to_u64(),
+                        offset.to_u64(),
+                        size.to_u64(),
                         memory,
                     )
                 })
@@ -386,16 +432,16 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "msg_reply_data_append", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, src: i32, size: i32| {
+            move |mut caller: Caller<'_, StoreData<S>>, src: P, size: P| {
                 charge_for_system_api_call(
                     &log,
                     canister_id,
                     &mut caller,
                     system_api_complexity::overhead::MSG_REPLY_DATA_APPEND,
-                    size as u32,
+                    size.to_u64(),
                 )?;
                 with_memory_and_system_api(caller, |system_api, memory| {
-                    system_api.ic0_msg_reply_data_append(src as u32, size as u32, memory)
+                    system_api.ic0_msg_reply_data_append(src.to_u64(), size.to_u64(), memory)
                 })
             }
         })
@@ -419,19 +465,28 @@ pub(crate) fn syscalls<S: SystemApi>(
         })
         .unwrap();
 
+    linker
+        .func_wrap("ic0", "msg_deadline", {
+            move |mut caller: Caller<'_, StoreData<S>>| {
+                with_system_api(&mut caller, |s| s.ic0_msg_deadline())
+                    .map_err(|e| process_err(caller, e))
+            }
+        })
+        .unwrap();
+
     linker
         .func_wrap("ic0", "msg_reject", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, src: i32, size: i32| {
+            move |mut caller: Caller<'_, StoreData<S>>, src: P, size: P| {
                 charge_for_system_api_call(
                     &log,
                     canister_id,
                     &mut caller,
                     system_api_complexity::overhead::MSG_REJECT,
-                    size as u32,
+                    size.to_u64(),
                 )?;
                 with_memory_and_system_api(caller, |system_api, memory| {
-                    system_api.ic0_msg_reject(src as u32, size as u32, memory)
+                    system_api.ic0_msg_reject(src.to_u64(), size.to_u64(), memory)
                 })
             }
         })
@@ -443,7 +498,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                 with_system_api(&mut caller, |s| s.ic0_msg_reject_msg_size())
                     .map_err(|e| process_err(caller, e))
                     .and_then(|s| {
-                        i32::try_from(s).map_err(|e| {
+                        P::try_from(s as u64).map_err(|e| {
                             wasmtime::Trap::new(format!("ic0_msg_reject_msg_size failed: {}", e))
                         })
                     })
@@ -454,19 +509,19 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "msg_reject_msg_copy", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, dst: i32, offset: i32, size: i32| {
+            move |mut caller: Caller<'_, StoreData<S>>, dst: P, offset: P, size: P| {
                 charge_for_system_api_call(
                     &log,
                     canister_id,
                     &mut caller,
                     system_api_complexity::overhead::MSG_REJECT_MSG_COPY,
-                    size as u32,
+                    size.to_u64(),
                 )?;
                 with_memory_and_system_api(caller, |system_api, memory| {
                     system_api.ic0_msg_reject_msg_copy(
-                        dst as u32,
-                        offset as u32,
-                        size as u32,
+                        dst.to_u64(),
+                        offset.to_u64(),
+                        size.to_u64(),
                         memory,
                     )
                 })
@@ -480,7 +535,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                 with_system_api(&mut caller, |s| s.ic0_canister_self_size())
                     .map_err(|e| process_err(caller, e))
                     .and_then(|s| {
-                        i32::try_from(s).map_err(|e| {
+                        P::try_from(s as u64).map_err(|e| {
                             wasmtime::Trap::new(format!("ic0_canister_self_size failed: {}", e))
                         })
                     })
@@ -490,12 +545,12 @@ pub(crate) fn syscalls<S: SystemApi>(
 
     linker
         .func_wrap("ic0", "canister_self_copy", {
-            move |caller: Caller<'_, StoreData<S>>, dst: i32, offset: i32, size: i32| {
+            move |caller: Caller<'_, StoreData<S>>, dst: P, offset: P, size: P| {
                 with_memory_and_system_api(caller, |system_api, memory| {
                     system_api.ic0_canister_self_copy(
-                        dst as u32,
-                        offset as u32,
-                        size as u32,
+                        dst.to_u64(),
+                        offset.to_u64(),
+                        size.to_u64(),
                         memory,
                     )
                 })
@@ -509,7 +564,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                 with_system_api(&mut caller, |s| s.ic0_controller_size())
                     .map_err(|e| process_err(caller, e))
                     .and_then(|s| {
-                        i32::try_from(s).map_err(|e| {
+                        P::try_from(s as u64).map_err(|e| {
                             wasmtime::Trap::new(format!("ic0_controller_size failed: {}", e))
                         })
                     })
@@ -519,9 +574,14 @@ pub(crate) fn syscalls<S: SystemApi>(
 
     linker
         .func_wrap("ic0", "controller_copy", {
-            move |caller: Caller<'_, StoreData<S>>, dst: i32, offset: i32, size: i32| {
+            move |caller: Caller<'_, StoreData<S>>, dst: P, offset: P, size: P| {
                 with_memory_and_system_api(caller, |system_api, memory| {
-                    system_api.ic0_controller_copy(dst as u32, offset as u32, size as u32, memory)
+                    system_api.ic0_controller_copy(
+                        dst.to_u64(),
+                        offset.to_u64(),
+                        size.to_u64(),
+                        memory,
+                    )
                 })
             }
         })
@@ -530,13 +590,13 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "debug_print", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, offset: i32, length: i32| {
+            move |mut caller: Caller<'_, StoreData<S>>, offset: P, length: P| {
                 charge_for_system_api_call(
                     &log,
                     canister_id,
                     &mut caller,
                     system_api_complexity::overhead::DEBUG_PRINT,
-                    length as u32,
+                    length.to_u64(),
                 )?;
                 match (
                     caller.data().system_api.subnet_type(),
@@ -549,7 +609,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                     // debug print produces output.
                     (_, FlagStatus::Disabled) | (SubnetType::System, FlagStatus::Enabled) => {
                         with_memory_and_system_api(caller, |system_api, memory| {
-                            system_api.ic0_debug_print(offset as u32, length as u32, memory)
+                            system_api.ic0_debug_print(offset.to_u64(), length.to_u64(), memory)
                         })
                     }
                 }
@@ -560,16 +620,16 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "trap", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, offset: i32, length: i32| -> Result<(), _> {
+            move |mut caller: Caller<'_, StoreData<S>>, offset: P, length: P| -> Result<(), _> {
                 charge_for_system_api_call(
                     &log,
                     canister_id,
                     &mut caller,
                     system_api_complexity::overhead::TRAP,
-                    length as u32,
+                    length.to_u64(),
                 )?;
                 with_memory_and_system_api(caller, |system_api, memory| {
-                    system_api.ic0_trap(offset as u32, length as u32, memory)
+                    system_api.ic0_trap(offset.to_u64(), length.to_u64(), memory)
                 })
             }
         })
@@ -579,35 +639,35 @@ pub(crate) fn syscalls<S: SystemApi>(
         .func_wrap("ic0", "call_simple", {
             let log = log.clone();
             move |mut caller: Caller<'_, StoreData<S>>,
-                  callee_src: i32,
-                  callee_size: i32,
-                  name_src: i32,
-                  name_len: i32,
+                  callee_src: P,
+                  callee_size: P,
+                  name_src: P,
+                  name_len: P,
                   reply_fun: i32,
                   reply_env: i32,
                   reject_fun: i32,
                   reject_env: i32,
-                  src: i32,
-                  len: i32| {
+                  src: P,
+                  len: P| {
                 charge_for_system_api_call(
                     &log,
                     canister_id,
                     &mut caller,
                     system_api_complexity::overhead::CALL_SIMPLE,
-                    len as u32,
+                    len.to_u64(),
                 )?;
                 with_memory_and_system_api(caller, |system_api, memory| {
                     system_api.ic0_call_simple(
-                        callee_src as u32,
-                        callee_size as u32,
-                        name_src as u32,
-                        name_len as u32,
+                        callee_src.to_u64(),
+                        callee_size.to_u64(),
+                        name_src.to_u64(),
+                        name_len.to_u64(),
                         reply_fun as u32,
                         reply_env as u32,
                         reject_fun as u32,
                         reject_env as u32,
-                        src as u32,
-                        len as u32,
+                        src.to_u64(),
+                        len.to_u64(),
                         memory,
                     )
                 })
@@ -619,10 +679,10 @@ pub(crate) fn syscalls<S: SystemApi>(
         .func_wrap("ic0", "call_new", {
             let log = log.clone();
             move |mut caller: Caller<'_, StoreData<S>>,
-                  callee_src: i32,
-                  callee_size: i32,
-                  name_src: i32,
-                  name_len: i32,
+                  callee_src: P,
+                  callee_size: P,
+                  name_src: P,
+                  name_len: P,
                   reply_fun: i32,
                   reply_env: i32,
                   reject_fun: i32,
@@ -640,10 +700,10 @@ pub(crate) fn syscalls<S: SystemApi>(
                 )?;
                 with_memory_and_system_api(caller, |system_api, memory| {
                     system_api.ic0_call_new(
-                        callee_src as u32,
-                        callee_size as u32,
-                        name_src as u32,
-                        name_len as u32,
+                        callee_src.to_u64(),
+                        callee_size.to_u64(),
+                        name_src.to_u64(),
+                        name_len.to_u64(),
                         reply_fun as u32,
                         reply_env as u32,
                         reject_fun as u32,
@@ -658,16 +718,16 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("ic0", "call_data_append", {
             let log = log.clone();
-            move |mut caller: Caller<'_, StoreData<S>>, src: i32, size: i32| {
+            move |mut caller: Caller<'_, StoreData<S>>, src: P, size: P| {
                 charge_for_system_api_call(
                     &log,
                     canister_id,
                     &mut caller,
                     system_api_complexity::overhead::CALL_DATA_APPEND,
-                    size as u32,
+                    size.to_u64(),
                 )?;
                 with_memory_and_system_api(caller, |system_api, memory| {
-                    system_api.ic0_call_data_append(src as u32, size as u32, memory)
+                    system_api.ic0_call_data_append(src.to_u64(), size.to_u64(), memory)
                 })
             }
         })
@@ -684,6 +744,17 @@ pub(crate) fn syscalls<S: SystemApi>(
         })
         .unwrap();
 
+    linker
+        .func_wrap("ic0", "call_with_best_effort_response", {
+            move |mut caller: Caller<'_, StoreData<S>>, timeout_seconds: i32| {
+                with_system_api(&mut caller, |s| {
+                    s.ic0_call_with_best_effort_response(timeout_seconds as u32)
+                })
+                .map_err(|e| process_err(caller, e))
+            }
+        })
+        .unwrap();
+
     linker
         .func_wrap("ic0", "call_cycles_add", {
             move |mut caller: Caller<'_, StoreData<S>>, amount: i64| {
@@ -760,7 +831,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                     canister_id,
                     &mut caller,
                     system_api_complexity::overhead::STABLE_READ,
-                    size as u32,
+                    size as u32 as u64,
                 )?;
                 with_memory_and_system_api(caller, |system_api, memory| {
                     system_api.ic0_stable_read(dst as u32, offset as u32, size as u32, memory)
@@ -778,7 +849,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                     canister_id,
                     &mut caller,
                     system_api_complexity::overhead::STABLE_WRITE,
-                    size as u32,
+                    size as u32 as u64,
                 )?;
                 with_memory_and_system_api(caller, |system_api, memory| {
                     system_api.ic0_stable_write(offset as u32, src as u32, size as u32, memory)
@@ -821,7 +892,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                     canister_id,
                     &mut caller,
                     system_api_complexity::overhead::STABLE64_READ,
-                    size as u32,
+                    size as u64,
                 )?;
                 with_memory_and_system_api(caller, |system_api, memory| {
                     system_api.ic0_stable64_read(dst as u64, offset as u64, size as u64, memory)
@@ -839,7 +910,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                     canister_id,
                     &mut caller,
                     system_api_complexity::overhead::STABLE64_WRITE,
-                    size as u32,
+                    size as u64,
                 )?;
                 with_memory_and_system_api(caller, |system_api, memory| {
                     system_api.ic0_stable64_write(offset as u64, src as u64, size as u64, memory)
@@ -890,9 +961,9 @@ pub(crate) fn syscalls<S: SystemApi>(
 
     linker
         .func_wrap("ic0", "canister_cycle_balance128", {
-            move |caller: Caller<'_, StoreData<S>>, dst: u32| {
+            move |caller: Caller<'_, StoreData<S>>, dst: P| {
                 with_memory_and_system_api(caller, |system_api, memory| {
-                    system_api.ic0_canister_cycles_balance128(dst, memory)
+                    system_api.ic0_canister_cycles_balance128(dst.to_u64(), memory)
                 })
             }
         })
@@ -914,9 +985,9 @@ pub(crate) fn syscalls<S: SystemApi>(
 
     linker
         .func_wrap("ic0", "msg_cycles_available128", {
-            move |caller: Caller<'_, StoreData<S>>, dst: u32| {
+            move |caller: Caller<'_, StoreData<S>>, dst: P| {
                 with_memory_and_system_api(caller, |system_api, memory| {
-                    system_api.ic0_msg_cycles_available128(dst, memory)
+                    system_api.ic0_msg_cycles_available128(dst.to_u64(), memory)
                 })
             }
         })
@@ -938,9 +1009,9 @@ pub(crate) fn syscalls<S: SystemApi>(
 
     linker
         .func_wrap("ic0", "msg_cycles_refunded128", {
-            move |caller: Caller<'_, StoreData<S>>, dst: u32| {
+            move |caller: Caller<'_, StoreData<S>>, dst: P| {
                 with_memory_and_system_api(caller, |system_api, memory| {
-                    system_api.ic0_msg_cycles_refunded128(dst, memory)
+                    system_api.ic0_msg_cycles_refunded128(dst.to_u64(), memory)
                 })
             }
         })
@@ -957,11 +1028,11 @@ pub(crate) fn syscalls<S: SystemApi>(
 
     linker
         .func_wrap("ic0", "msg_cycles_accept128", {
-            move |caller: Caller<'_, StoreData<S>>, amount_high: i64, amount_low: i64, dst: u32| {
+            move |caller: Caller<'_, StoreData<S>>, amount_high: i64, amount_low: i64, dst: P| {
                 with_memory_and_system_api(caller, |system_api, memory| {
                     system_api.ic0_msg_cycles_accept128(
                         Cycles::from_parts(amount_high as u64, amount_low as u64),
-                        dst,
+                        dst.to_u64(),
                         memory,
                     )
                 })
@@ -1011,11 +1082,15 @@ pub(crate) fn syscalls<S: SystemApi>(
     linker
         .func_wrap("__", "update_available_memory", {
             move |mut caller: Caller<'_, StoreData<S>>,
-                  native_memory_grow_res: i32,
-                  additional_pages: i32| {
+                  native_memory_grow_res: P,
+                  additional_pages: P| {
                 with_system_api(&mut caller, |s| {
-                    s.update_available_memory(native_memory_grow_res, additional_pages as u32)
+                    s.update_available_memory(
+                        native_memory_grow_res.into(),
+                        additional_pages.to_u64(),
+                    )
                 })
+                .map(P::from_memory_grow_result)
                 .map_err(|e| process_err(caller, e))
             }
         })
@@ -1032,9 +1107,9 @@ pub(crate) fn syscalls<S: SystemApi>(
 
     linker
         .func_wrap("ic0", "certified_data_set", {
-            move |caller: Caller<'_, StoreData<S>>, src: u32, size: u32| {
+            move |caller: Caller<'_, StoreData<S>>, src: P, size: P| {
                 with_memory_and_system_api(caller, |system_api, memory| {
-                    system_api.ic0_certified_data_set(src, size, memory)
+                    system_api.ic0_certified_data_set(src.to_u64(), size.to_u64(), memory)
                 })
             }
         })
@@ -1054,15 +1129,25 @@ pub(crate) fn syscalls<S: SystemApi>(
             move |mut caller: Caller<'_, StoreData<S>>| {
                 with_system_api(&mut caller, |s| s.ic0_data_certificate_size())
                     .map_err(|e| process_err(caller, e))
+                    .and_then(|s| {
+                        P::try_from(s as u64).map_err(|e| {
+                            wasmtime::Trap::new(format!("ic0_data_certificate_size failed: {}", e))
+                        })
+                    })
             }
         })
         .unwrap();
 
     linker
         .func_wrap("ic0", "data_certificate_copy", {
-            move |caller: Caller<'_, StoreData<S>>, dst: u32, offset: u32, size: u32| {
+            move |caller: Caller<'_, StoreData<S>>, dst: P, offset: P, size: P| {
                 with_memory_and_system_api(caller, |system_api, memory| {
-                    system_api.ic0_data_certificate_copy(dst, offset, size, memory)
+                    system_api.ic0_data_certificate_copy(
+                        dst.to_u64(),
+                        offset.to_u64(),
+                        size.to_u64(),
+                        memory,
+                    )
                 })
             }
         })
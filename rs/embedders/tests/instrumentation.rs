@@ -3,7 +3,7 @@ use ic_embedders::wasm_utils::{
     instrumentation::{
         export_additional_symbols, instrument, ExportModuleData, InstructionCostTable, Segments,
     },
-    validation::RESERVED_SYMBOLS,
+    validation::{ensure_determinism, RESERVED_SYMBOLS},
 };
 use ic_sys::{PageIndex, PAGE_SIZE};
 use ic_wasm_types::BinaryEncodedWasm;
@@ -180,6 +180,76 @@ fn test_get_data() {
     assert_eq!(0, output.data.as_slice().len())
 }
 
+#[test]
+fn can_instrument_simd_instructions() {
+    // The bundled `wabt` predates the final encoding of the SIMD instructions.
+    let wasm = wat::parse_str(
+        r#"(module
+            (memory 1)
+            (func $f (result i32)
+                (v128.store (i32.const 0)
+                    (i8x16.popcnt (v128.load (i32.const 16))))
+                (i32x4.extract_lane 0 (v128.load (i32.const 0))))
+            (export "canister_query f" (func $f)))"#,
+    )
+    .unwrap();
+    let output = instrument(
+        &BinaryEncodedWasm::new(wasm),
+        &InstructionCostTable::new(),
+        EmbeddersConfig::default().cost_to_compile_wasm_instruction,
+    )
+    .unwrap();
+    let mut config = wasmtime::Config::default();
+    ensure_determinism(&mut config);
+    let engine = wasmtime::Engine::new(&config).unwrap();
+    wasmtime::Module::validate(&engine, output.binary.as_slice()).unwrap();
+    // The instrumented module still contains the original SIMD instructions.
+    let binary = output.binary.as_slice();
+    assert!(binary.windows(2).any(|window| window == [0xfd, 0x62]));
+    assert!(binary.windows(3).any(|window| window == [0xfd, 0x1b, 0x00]));
+}
+
+#[test]
+fn can_instrument_memory64() {
+    let wasm = wat::parse_str(
+        r#"(module
+            (memory i64 1)
+            (table 1 funcref)
+            (func $f
+                (drop (memory.grow (i64.const 1)))
+                (memory.fill (i64.const 0) (i32.const 1) (i64.const 65536))
+                (memory.copy (i64.const 65536) (i64.const 0) (i64.const 16))
+                (table.copy (i32.const 0) (i32.const 0) (i32.const 1)))
+            (data (i64.const 2) "a tree")
+            (export "canister_update f" (func $f)))"#,
+    )
+    .unwrap();
+    let output = instrument(
+        &BinaryEncodedWasm::new(wasm),
+        &InstructionCostTable::new(),
+        EmbeddersConfig::default().cost_to_compile_wasm_instruction,
+    )
+    .unwrap();
+    assert_eq!(output.limits, (1, None));
+    assert_eq!((2, b"a tree".to_vec()), output.data.as_slice()[0]);
+    let mut config = wasmtime::Config::default();
+    ensure_determinism(&mut config);
+    let engine = wasmtime::Engine::new(&config).unwrap();
+    let module = wasmtime::Module::new(&engine, output.binary.as_slice()).unwrap();
+    // `update_available_memory`, which is the second import, takes and returns
+    // addresses of the 64-bit memory.
+    let update_available_memory = module.imports().nth(1).unwrap();
+    let ty = update_available_memory.ty().func().unwrap().clone();
+    assert_eq!(
+        ty.params().collect::<Vec<_>>(),
+        vec![wasmtime::ValType::I64, wasmtime::ValType::I64]
+    );
+    assert_eq!(
+        ty.results().collect::<Vec<_>>(),
+        vec![wasmtime::ValType::I64]
+    );
+}
+
 #[test]
 fn test_chunks_to_pages() {
     let segs = Segments::from(vec![
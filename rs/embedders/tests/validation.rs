@@ -1,5 +1,5 @@
 use assert_matches::assert_matches;
-use ic_config::embedders::Config as EmbeddersConfig;
+use ic_config::{embedders::Config as EmbeddersConfig, flag_status::FlagStatus};
 use ic_embedders::wasm_utils::validation::{
     extract_custom_section_name, validate_custom_section, validate_wasm_binary, WasmImportsDetails,
     WasmValidationDetails, RESERVED_SYMBOLS,
@@ -710,3 +710,109 @@ fn can_validate_performance_counter_import() {
         Ok(WasmValidationDetails::default())
     );
 }
+
+// The bundled `wabt` predates the final encoding of the SIMD instructions, so
+// the SIMD tests use the `wat` crate.
+const SIMD_WAT: &str = r#"(module
+    (memory 1)
+    (func $f
+        (v128.store (i32.const 0)
+            (f32x4.div (v128.const f32x4 0 0 0 0) (v128.load (i32.const 16))))
+        (drop (i32x4.extract_lane 0 (v128.load (i32.const 0)))))
+    (export "canister_query f" (func $f)))"#;
+
+#[test]
+fn can_validate_module_with_simd_instructions() {
+    let wasm = BinaryEncodedWasm::new(wat::parse_str(SIMD_WAT).unwrap());
+    assert_eq!(
+        validate_wasm_binary(&wasm, &EmbeddersConfig::default()),
+        Ok(WasmValidationDetails::default())
+    );
+}
+
+#[test]
+fn can_reject_module_with_relaxed_simd_instructions() {
+    let mut wasm = wat::parse_str(SIMD_WAT).unwrap();
+    // Replace `f32x4.div` by the relaxed `f32x4.fma` instruction.
+    let position = wasm
+        .windows(3)
+        .position(|window| window == [0xfd, 0xe7, 0x01])
+        .unwrap();
+    wasm[position + 1] = 0xaf;
+    assert_matches!(
+        validate_wasm_binary(&BinaryEncodedWasm::new(wasm), &EmbeddersConfig::default()),
+        Err(WasmValidationError::NonDeterministicInstruction(_))
+    );
+}
+
+#[test]
+fn can_reject_module_with_v128_global() {
+    let wasm = BinaryEncodedWasm::new(
+        wat::parse_str(r#"(module (global (mut v128) (v128.const i64x2 0 0)))"#).unwrap(),
+    );
+    assert_matches!(
+        validate_wasm_binary(&wasm, &EmbeddersConfig::default()),
+        Err(WasmValidationError::InvalidGlobalSection(_))
+    );
+}
+
+const MEMORY64_WAT: &str = r#"(module
+    (import "ic0" "msg_arg_data_copy" (func $msg_arg_data_copy (param i64 i64 i64)))
+    (import "ic0" "msg_arg_data_size" (func $msg_arg_data_size (result i64)))
+    (func $f
+        (call $msg_arg_data_copy (i64.const 4294967296) (i64.const 0) (call $msg_arg_data_size))
+        (drop (memory.grow (i64.const 1))))
+    (memory $memory i64 65537)
+    (data (i64.const 16) "hello")
+    (export "canister_update f" (func $f)))"#;
+
+fn wasm64_config() -> EmbeddersConfig {
+    let mut config = EmbeddersConfig::default();
+    config.feature_flags.wasm64 = FlagStatus::Enabled;
+    config
+}
+
+#[test]
+fn can_reject_module_with_memory64_if_disabled() {
+    let wasm = BinaryEncodedWasm::new(wat::parse_str(MEMORY64_WAT).unwrap());
+    assert_matches!(
+        validate_wasm_binary(&wasm, &EmbeddersConfig::default()),
+        Err(WasmValidationError::InvalidMemorySection(_))
+    );
+}
+
+#[test]
+fn can_validate_module_with_memory64() {
+    let wasm = BinaryEncodedWasm::new(wat::parse_str(MEMORY64_WAT).unwrap());
+    assert_eq!(
+        validate_wasm_binary(&wasm, &wasm64_config()),
+        Ok(WasmValidationDetails::default())
+    );
+}
+
+#[test]
+fn can_reject_module_with_memory64_and_32_bit_system_api_pointers() {
+    let wasm = BinaryEncodedWasm::new(
+        wat::parse_str(
+            r#"(module
+                (import "ic0" "msg_reply_data_append" (func (param i32 i32)))
+                (memory i64 1))"#,
+        )
+        .unwrap(),
+    );
+    assert_matches!(
+        validate_wasm_binary(&wasm, &wasm64_config()),
+        Err(WasmValidationError::InvalidFunctionSignature(_))
+    );
+}
+
+#[test]
+fn can_reject_module_with_too_large_memory64() {
+    let mut config = wasm64_config();
+    config.max_wasm64_memory_size = NumBytes::new(4 * 1024 * 1024 * 1024);
+    let wasm = BinaryEncodedWasm::new(wat::parse_str(MEMORY64_WAT).unwrap());
+    assert_matches!(
+        validate_wasm_binary(&wasm, &config),
+        Err(WasmValidationError::InvalidMemorySection(_))
+    );
+}
@@ -283,6 +283,7 @@ mod test {
                 0.into(),
                 PrincipalId::new_user_test_id(0),
                 0.into(),
+                None,
             ))
             .build();
 
@@ -328,6 +329,7 @@ mod test {
                 0.into(),
                 PrincipalId::new_user_test_id(0),
                 0.into(),
+                None,
             ))
             .with_num_instructions((expected_cpu_complexity - 1).into())
             .with_subnet_type(subnet_type)
@@ -358,6 +360,7 @@ mod test {
                 0.into(),
                 PrincipalId::new_user_test_id(0),
                 0.into(),
+                None,
             ))
             .with_num_instructions((expected_cpu_complexity - 1).into())
             .with_subnet_type(subnet_type)
@@ -100,7 +100,7 @@ where
 
     // Create call context and callback
     let call_origin =
-        CallOrigin::CanisterUpdate(canister_test_id(REMOTE_CANISTER_ID), CallbackId::new(0));
+        CallOrigin::CanisterUpdate(canister_test_id(REMOTE_CANISTER_ID), CallbackId::new(0), None);
     let call_context_id = canister_state
         .system_state
         .call_context_manager_mut()
@@ -114,6 +114,7 @@ where
         WasmClosure::new(0, 1),
         WasmClosure::new(0, 1),
         None,
+        None,
     );
 
     // Create an Ingress message
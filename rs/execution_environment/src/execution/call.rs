@@ -46,6 +46,7 @@ fn early_error_to_result(
                 originator_reply_callback: request.sender_reply_callback,
                 refund: request.payment,
                 response_payload: Payload::from(Err(user_error)),
+                deadline: request.deadline,
             };
             ExecResult::ResponseResult(response)
         }
@@ -198,6 +199,7 @@ fn execute_update_method(
         incoming_cycles,
         *req.sender(),
         call_context_id,
+        req.deadline(),
     );
 
     let (output, output_execution_state, output_system_state) = hypervisor.execute(
@@ -252,8 +254,13 @@ fn execute_query_method(
     let method = WasmMethod::Query(req.method_name().to_string());
     let memory_usage = canister.memory_usage(hypervisor.subnet_type());
 
-    let api_type =
-        ApiType::replicated_query(time, req.method_payload().to_vec(), *req.sender(), None);
+    let api_type = ApiType::replicated_query(
+        time,
+        req.method_payload().to_vec(),
+        *req.sender(),
+        None,
+        req.deadline(),
+    );
 
     // As we are executing the query in the replicated mode, we do
     // not want to commit updates, i.e. we must return the
@@ -163,7 +163,7 @@ pub fn execute_response(
 
         let func_ref = match call_origin {
             CallOrigin::Ingress(_, _)
-            | CallOrigin::CanisterUpdate(_, _)
+            | CallOrigin::CanisterUpdate(_, _, _)
             | CallOrigin::Heartbeat => FuncRef::UpdateClosure(closure),
             CallOrigin::CanisterQuery(_, _) | CallOrigin::Query(_) => {
                 FuncRef::QueryClosure(closure)
@@ -176,6 +176,7 @@ pub fn execute_response(
                 payload.to_vec(),
                 refunded_cycles,
                 call_context_id,
+                callback.deadline,
                 call_context.has_responded(),
             ),
             Payload::Reject(context) => ApiType::reject_callback(
@@ -183,6 +184,7 @@ pub fn execute_response(
                 context,
                 refunded_cycles,
                 call_context_id,
+                callback.deadline,
                 call_context.has_responded(),
             ),
         };
@@ -348,7 +350,7 @@ fn maybe_execute_cleanup(
         Some(cleanup_closure) => {
             let func_ref = match call_origin {
                 CallOrigin::Ingress(_, _)
-                | CallOrigin::CanisterUpdate(_, _)
+                | CallOrigin::CanisterUpdate(_, _, _)
                 | CallOrigin::Heartbeat => FuncRef::UpdateClosure(cleanup_closure),
                 CallOrigin::CanisterQuery(_, _) | CallOrigin::Query(_) => {
                     FuncRef::QueryClosure(cleanup_closure)
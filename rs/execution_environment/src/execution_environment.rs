@@ -238,6 +238,7 @@ impl ExecutionEnvironment for ExecutionEnvironmentImpl {
                             originator_reply_callback: request.sender_reply_callback,
                             refund: request.payment,
                             response_payload: response.response_payload,
+                            deadline: request.deadline,
                         });
                         (state, instructions_limit)
                     }
@@ -602,6 +603,7 @@ impl ExecutionEnvironment for ExecutionEnvironmentImpl {
                                 code: ic_error_types::RejectCode::CanisterReject,
                                 message: reject_message,
                             }),
+                            deadline: request.deadline,
                         });
                         return (state, instructions_limit);
                     }
@@ -840,6 +842,15 @@ impl ExecutionEnvironment for ExecutionEnvironmentImpl {
             }
         };
 
+        let _span = match &req {
+            RequestOrIngress::Ingress(ingress) => Some(
+                ic_tracing::tracer()
+                    .message_span("execution.execute_ingress", &ingress.message_id)
+                    .with_attribute("canister_id", canister.canister_id())
+                    .with_attribute("method_name", &ingress.method_name),
+            ),
+            RequestOrIngress::Request(_) => None,
+        };
         execute_call(
             canister,
             req,
@@ -1467,6 +1478,7 @@ impl ExecutionEnvironmentImpl {
                     originator_reply_callback: req.sender_reply_callback,
                     refund,
                     response_payload: payload,
+                    deadline: req.deadline,
                 };
 
                 state.push_subnet_output_response(response);
@@ -1545,6 +1557,7 @@ impl ExecutionEnvironmentImpl {
                             code: RejectCode::CanisterReject,
                             message: format!("Canister {}'s stop request cancelled", canister_id),
                         }),
+                        deadline: None,
                     };
                     state.push_subnet_output_response(response);
                 }
@@ -134,6 +134,10 @@ impl IngressHistoryWriter for IngressHistoryWriterImpl {
 
     fn set_status(&self, state: &mut Self::State, message_id: MessageId, status: IngressStatus) {
         let time = state.time();
+        let _span = ic_tracing::tracer()
+            .message_span("execution.set_status", &message_id)
+            .with_attribute("status", status.as_str())
+            .with_attribute("batch_time", time);
         let current_status = state.get_ingress_status(&message_id);
 
         // Guard against an invalid state transition
@@ -4,14 +4,17 @@ use ic_canister_sandbox_replica_controller::sandboxed_execution_controller::Sand
 use ic_config::flag_status::FlagStatus;
 use ic_config::{embedders::Config as EmbeddersConfig, execution_environment::Config};
 use ic_cycles_account_manager::CyclesAccountManager;
-use ic_embedders::{wasm_executor::WasmExecutor, WasmExecutionInput, WasmtimeEmbedder};
+use ic_embedders::{
+    compilation_cache::CompilationCache, wasm_executor::WasmExecutor, WasmExecutionInput,
+    WasmtimeEmbedder,
+};
 use ic_error_types::{ErrorCode, UserError};
 use ic_ic00_types::CanisterStatusType;
 use ic_interfaces::execution_environment::{
     ExecutionParameters, HypervisorError, HypervisorResult, WasmExecutionOutput,
 };
 use ic_interfaces::messages::RequestOrIngress;
-use ic_logger::{debug, fatal, ReplicaLogger};
+use ic_logger::{debug, fatal, warn, ReplicaLogger};
 use ic_metrics::{buckets::exponential_buckets, MetricsRegistry};
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::NetworkTopology;
@@ -191,6 +194,7 @@ impl Hypervisor {
             incoming_cycles,
             *request.sender(),
             call_context_id,
+            request.deadline(),
         );
         let (output, output_execution_state, output_system_state) = self.execute(
             api_type,
@@ -271,8 +275,13 @@ impl Hypervisor {
 
         match query_execution_type {
             QueryExecutionType::Replicated => {
-                let api_type =
-                    ApiType::replicated_query(time, payload.to_vec(), caller, data_certificate);
+                let api_type = ApiType::replicated_query(
+                    time,
+                    payload.to_vec(),
+                    caller,
+                    data_certificate,
+                    None,
+                );
                 // As we are executing the query in the replicated mode, we do
                 // not want to commit updates, i.e. we must return the
                 // unmodified version of the canister. Hence, execute on clones
@@ -419,6 +428,7 @@ impl Hypervisor {
                 payload.to_vec(),
                 incoming_cycles,
                 callback.call_context_id,
+                callback.deadline,
                 call_responded,
             ),
             Payload::Reject(context) => ApiType::reject_callback(
@@ -426,13 +436,14 @@ impl Hypervisor {
                 context,
                 incoming_cycles,
                 callback.call_context_id,
+                callback.deadline,
                 call_responded,
             ),
         };
 
         let func_ref = match call_origin {
             CallOrigin::Ingress(_, _)
-            | CallOrigin::CanisterUpdate(_, _)
+            | CallOrigin::CanisterUpdate(_, _, _)
             | CallOrigin::Heartbeat => FuncRef::UpdateClosure(closure),
             CallOrigin::CanisterQuery(_, _) | CallOrigin::Query(_) => {
                 FuncRef::QueryClosure(closure)
@@ -476,7 +487,7 @@ impl Hypervisor {
                     Some(cleanup_closure) => {
                         let func_ref = match call_origin {
                             CallOrigin::Ingress(_, _)
-                            | CallOrigin::CanisterUpdate(_, _)
+                            | CallOrigin::CanisterUpdate(_, _, _)
                             | CallOrigin::Heartbeat => FuncRef::UpdateClosure(cleanup_closure),
                             CallOrigin::CanisterQuery(_, _) | CallOrigin::Query(_) => {
                                 FuncRef::QueryClosure(cleanup_closure)
@@ -944,16 +955,51 @@ impl Hypervisor {
         embedder_config.query_execution_threads = config.query_execution_threads;
         embedder_config.feature_flags.rate_limiting_of_debug_prints =
             config.rate_limiting_of_debug_prints;
+        embedder_config.feature_flags.wasm64 = config.wasm64;
+        embedder_config.compilation_cache_dir = config.compilation_cache_dir.clone();
+        embedder_config.compilation_cache_capacity = config.compilation_cache_capacity;
+
+        let compilation_cache = match &embedder_config.compilation_cache_dir {
+            Some(dir) => match CompilationCache::new(
+                dir.clone(),
+                embedder_config.compilation_cache_capacity,
+                metrics_registry,
+                log.clone(),
+            ) {
+                Ok(compilation_cache) => Some(Arc::new(compilation_cache)),
+                Err(err) => {
+                    warn!(
+                        log,
+                        "Failed to create the Wasm compilation cache in {}, compiled modules are \
+                        not cached: {}",
+                        dir.display(),
+                        err
+                    );
+                    None
+                }
+            },
+            None => None,
+        };
 
+        // Sandbox processes only read from the cache and return the modules
+        // they compile, which the controller adds to the cache.
         let sandbox_executor = match config.canister_sandboxing_flag {
             FlagStatus::Enabled => Some(Arc::new(
-                SandboxedExecutionController::new(log.clone(), metrics_registry, &embedder_config)
-                    .expect("Failed to start sandboxed execution controller"),
+                SandboxedExecutionController::new(
+                    log.clone(),
+                    metrics_registry,
+                    &embedder_config,
+                    compilation_cache.clone(),
+                )
+                .expect("Failed to start sandboxed execution controller"),
             )),
             FlagStatus::Disabled => None,
         };
 
-        let wasm_embedder = WasmtimeEmbedder::new(embedder_config.clone(), log.clone());
+        let mut wasm_embedder = WasmtimeEmbedder::new(embedder_config.clone(), log.clone());
+        if let Some(compilation_cache) = compilation_cache {
+            wasm_embedder = wasm_embedder.with_compilation_cache(compilation_cache);
+        }
         let wasm_executor = WasmExecutor::new(
             wasm_embedder,
             metrics_registry,
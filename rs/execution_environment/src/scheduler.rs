@@ -895,6 +895,12 @@ impl Scheduler for SchedulerImpl {
                 self.purge_expired_ingress_messages(&mut state);
             }
 
+            // Reject all best-effort callbacks whose deadline has expired.
+            let timed_out_callbacks = state.time_out_callbacks(state.time());
+            self.metrics
+                .timed_out_callbacks_count
+                .inc_by(timed_out_callbacks as u64);
+
             // See documentation around definition of `heap_delta_estimate` for an
             // explanation.
             if state.metadata.heap_delta_estimate >= self.config.subnet_heap_delta_capacity {
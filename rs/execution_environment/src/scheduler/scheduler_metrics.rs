@@ -30,6 +30,7 @@ pub(super) struct SchedulerMetrics {
     pub(super) instructions_consumed_per_round: Histogram,
     pub(super) executable_canisters_per_round: Histogram,
     pub(super) expired_ingress_messages_count: IntCounter,
+    pub(super) timed_out_callbacks_count: IntCounter,
     pub(super) ingress_history_length: IntGauge,
     pub(super) msg_execution_duration: Histogram,
     pub(super) registered_canisters: IntGaugeVec,
@@ -156,6 +157,11 @@ impl SchedulerMetrics {
                 "Total number of ingress messages that expired before \
                       reaching a terminal state.",
             ),
+            timed_out_callbacks_count: metrics_registry.int_counter(
+                "scheduler_timed_out_callbacks_count",
+                "Total number of best-effort callbacks that were rejected \
+                      because their deadline expired.",
+            ),
             ingress_history_length: metrics_registry.int_gauge(
                 "replicated_state_ingress_history_length",
                 "Total number of entries kept in the ingress history.",
@@ -884,6 +884,7 @@ fn test_message_limit_from_message_overhead() {
                     originator_reply_callback: sender_reply_callback,
                     refund: Cycles::from(0u64),
                     response_payload: Payload::Data(vec![]),
+                    deadline: None,
                 })
             }
 
@@ -915,6 +916,7 @@ fn test_message_limit_from_message_overhead() {
                             on_reply: WasmClosure::new(0, 0),
                             on_reject: WasmClosure::new(0, 0),
                             on_cleanup: None,
+                            deadline: None,
                         });
                     canister
                         .push_output_request(
@@ -3567,6 +3569,7 @@ fn canisters_reject_open_call_contexts_when_forcibly_uninstalled() {
                                 .with_call_origin(CallOrigin::CanisterUpdate(
                                     canister_test_id(0),
                                     CallbackId::from(0),
+                                    None,
                                 ))
                                 .with_responded(false)
                                 .build(),
@@ -3797,6 +3800,7 @@ fn long_open_call_context_is_recorded() {
                                 .with_call_origin(CallOrigin::CanisterUpdate(
                                     canister_test_id(0),
                                     CallbackId::from(0),
+                                    None,
                                 ))
                                 .with_responded(false)
                                 .with_time(context_creation_time)
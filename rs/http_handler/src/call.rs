@@ -196,10 +196,12 @@ impl Service<Vec<u8>> for CallService {
         let malicious_flags = self.malicious_flags.clone();
 
         Box::pin(async move {
+            let mut span = ic_tracing::tracer().message_span("http_handler.call", &message_id);
             if let Err(http_err) = validator_executor
                 .validate_signed_ingress(&msg, registry_version, &malicious_flags)
                 .await
             {
+                span.set_attribute("status", http_err.status);
                 let res = make_plaintext_response(http_err.status, http_err.message);
                 return Ok(res);
             }
@@ -212,10 +214,14 @@ impl Service<Vec<u8>> for CallService {
                 .await
             {
                 Err(err) => {
-                    return Ok(map_box_error_to_response(err));
+                    let res = map_box_error_to_response(err);
+                    span.set_attribute("status", res.status());
+                    return Ok(res);
                 }
                 Ok(Err(err)) => {
-                    return Ok(make_response(err));
+                    let res = make_response(err);
+                    span.set_attribute("status", res.status());
+                    return Ok(res);
                 }
                 Ok(Ok(())) => (),
             }
@@ -239,6 +245,7 @@ impl Service<Vec<u8>> for CallService {
                     make_accepted_response()
                 }
             };
+            span.set_attribute("status", response.status());
             Ok(response)
         })
     }
@@ -1,6 +1,7 @@
 // The valiadator executor provides non blocking access to the crypto services needed in the http handler.
 use crate::{common::validation_error_to_http_error, HttpError};
 use http::StatusCode;
+use ic_crypto::VerifiedDelegationCache;
 use ic_interfaces::crypto::IngressSigVerifier;
 use ic_logger::{debug, ReplicaLogger};
 use ic_types::{
@@ -9,7 +10,9 @@ use ic_types::{
     time::current_time,
     RegistryVersion,
 };
-use ic_validator::{get_authorized_canisters, validate_request, CanisterIdSet};
+use ic_validator::{
+    get_authorized_canisters, validate_requests, CanisterIdSet, RequestValidationError,
+};
 use std::sync::{Arc, Mutex};
 use threadpool::ThreadPool;
 use tokio::sync::oneshot;
@@ -17,9 +20,19 @@ use tokio::sync::oneshot;
 // Number of threads used for the ingress validator executor.
 const VALIDATOR_EXECUTOR_THREADS: usize = 1;
 
+/// A signed ingress message waiting for validation.
+struct PendingIngress {
+    request: SignedIngress,
+    registry_version: RegistryVersion,
+    malicious_flags: MaliciousFlags,
+    tx: oneshot::Sender<Result<(), RequestValidationError>>,
+}
+
 #[derive(Clone)]
 pub(crate) struct ValidatorExecutor {
     validator: Arc<dyn IngressSigVerifier + Send + Sync>,
+    delegation_cache: Arc<VerifiedDelegationCache>,
+    pending_ingress: Arc<Mutex<Vec<PendingIngress>>>,
     threadpool: Arc<Mutex<ThreadPool>>,
     logger: ReplicaLogger,
 }
@@ -31,11 +44,15 @@ impl ValidatorExecutor {
     ) -> Self {
         ValidatorExecutor {
             validator,
+            delegation_cache: Arc::new(VerifiedDelegationCache::default()),
+            pending_ingress: Arc::new(Mutex::new(Vec::new())),
             threadpool: Arc::new(Mutex::new(ThreadPool::new(VALIDATOR_EXECUTOR_THREADS))),
             logger,
         }
     }
 
+    /// Validates the `request`. The signed ingress messages that arrive while
+    /// the executor is busy are validated together in a batch.
     pub async fn validate_signed_ingress(
         &self,
         request: &SignedIngress,
@@ -44,17 +61,19 @@ impl ValidatorExecutor {
     ) -> Result<(), HttpError> {
         let (tx, rx) = oneshot::channel();
 
-        let r = request.clone();
-        let mf = malicious_flags.clone();
+        self.pending_ingress.lock().unwrap().push(PendingIngress {
+            request: request.clone(),
+            registry_version,
+            malicious_flags: malicious_flags.clone(),
+            tx,
+        });
         let validator = self.validator.clone();
+        let delegation_cache = self.delegation_cache.clone();
+        let pending_ingress = self.pending_ingress.clone();
         self.threadpool.lock().unwrap().execute(move || {
-            let _ = tx.send(validate_request(
-                r.as_ref(),
-                validator.as_ref(),
-                current_time(),
-                registry_version,
-                &mf,
-            ));
+            // The messages may have been validated by an earlier job already.
+            let pending = std::mem::take(&mut *pending_ingress.lock().unwrap());
+            validate_pending_ingress(pending, validator.as_ref(), &delegation_cache);
         });
         rx.await
             .map_err(|recv_err| HttpError {
@@ -102,9 +121,45 @@ impl ValidatorExecutor {
     }
 }
 
+/// Validates the `pending` messages in batches of messages with the same
+/// registry version and malicious flags, and sends back the results.
+fn validate_pending_ingress(
+    mut pending: Vec<PendingIngress>,
+    validator: &dyn IngressSigVerifier,
+    delegation_cache: &VerifiedDelegationCache,
+) {
+    let current_time = current_time();
+    while !pending.is_empty() {
+        let registry_version = pending[0].registry_version;
+        let malicious_flags = pending[0].malicious_flags.clone();
+        let (batch, rest): (Vec<_>, Vec<_>) = pending.into_iter().partition(|ingress| {
+            ingress.registry_version == registry_version
+                && ingress.malicious_flags == malicious_flags
+        });
+        pending = rest;
+
+        let requests: Vec<_> = batch
+            .iter()
+            .map(|ingress| ingress.request.as_ref())
+            .collect();
+        let results = validate_requests(
+            &requests,
+            validator,
+            delegation_cache,
+            current_time,
+            registry_version,
+            &malicious_flags,
+        );
+        for (ingress, result) in batch.into_iter().zip(results) {
+            let _ = ingress.tx.send(result);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{validate_request, validation_error_to_http_error, ValidatorExecutor};
+    use super::{validation_error_to_http_error, ValidatorExecutor};
+    use http::StatusCode;
     use ic_logger::replica_logger::no_op_logger;
     use ic_test_utilities::{
         crypto::temp_crypto_component_with_fake_registry,
@@ -122,7 +177,7 @@ mod tests {
         },
         time::current_time_and_expiry_time,
     };
-    use ic_validator::get_authorized_canisters;
+    use ic_validator::{get_authorized_canisters, validate_request};
     use std::convert::TryFrom;
     use std::sync::Arc;
 
@@ -203,4 +258,41 @@ mod tests {
             ))
         )
     }
+
+    #[tokio::test]
+    async fn async_validate_concurrent_signed_ingress() {
+        let valid_request = |nonce| {
+            SignedIngressBuilder::new()
+                .canister_id(canister_test_id(420))
+                .nonce(nonce)
+                .sign_for_randomly_generated_sender()
+                .build()
+        };
+        // The signature does not cover the changed nonce.
+        let invalid_request = SignedIngressBuilder::new()
+            .canister_id(canister_test_id(420))
+            .nonce(2)
+            .sign_for_randomly_generated_sender()
+            .nonce(3)
+            .build();
+        let requests = vec![valid_request(1), invalid_request, valid_request(4)];
+        let sig_verifier = Arc::new(temp_crypto_component_with_fake_registry(node_test_id(0)));
+        let validator = ValidatorExecutor::new(sig_verifier, no_op_logger());
+
+        let results = futures::future::join_all(requests.iter().map(|request| {
+            validator.validate_signed_ingress(
+                request,
+                RegistryVersion::from(0),
+                &MaliciousFlags::default(),
+            )
+        }))
+        .await;
+
+        assert_eq!(results[0], Ok(()));
+        assert_eq!(
+            results[1].as_ref().unwrap_err().status,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(results[2], Ok(()));
+    }
 }
@@ -16,7 +16,7 @@ use ic_types::{
     time::current_time,
     CountBytes,
 };
-use ic_validator::validate_request;
+use ic_validator::validate_requests;
 
 impl IngressHandler for IngressManager {
     #[allow(clippy::cognitive_complexity)]
@@ -53,72 +53,102 @@ impl IngressHandler for IngressManager {
         // looks at the unvalidated ingress messages and
         // 1. either discards them
         // 2. or moves them to validated.
-        let unvalidated_artifacts = pool
+        let unvalidated_artifacts: Vec<_> = pool
             .unvalidated()
-            .get_all_by_expiry_range(expiry_range.clone());
-        change_set.extend(unvalidated_artifacts.map(|artifact| {
-            let ingress_object = &artifact.message;
-            let ingress_message = &ingress_object.signed_ingress;
-            let max_ingress_bytes_per_message =
-                ingress_message_settings.max_ingress_bytes_per_message;
-            // If the message is too large, consider the ingress message invalid
-            let size = ingress_object.count_bytes();
-            if size > max_ingress_bytes_per_message {
-                warn!(
-                    self.log,
-                    "ingress_message_remove_unvalidated";
-                    ingress_message.message_id => format!("{}", ingress_object.message_id),
-                    ingress_message.reason => "message_too_large",
-                    ingress_message.size => size as u64,
-                );
-                return RemoveFromUnvalidated(IngressMessageId::from(ingress_object));
-            }
-
-            // Check status of the ingress message against IngressHistoryReader,
-            // If Unknown, consider the ingress message valid
-            let status = get_status(&ingress_object.message_id);
-            if status != IngressStatus::Unknown {
-                debug!(
-                    self.log,
-                    "ingress_message_remove_unvalidated";
-                    ingress_message.message_id => format!("{}", ingress_object.message_id),
-                    ingress_message.reason => format!("unexpected_status_{}", status.as_str()),
-                );
-                return RemoveFromUnvalidated(IngressMessageId::from(ingress_object));
-            }
+            .get_all_by_expiry_range(expiry_range.clone())
+            .collect();
+        let max_ingress_bytes_per_message = ingress_message_settings.max_ingress_bytes_per_message;
+        // Removes the messages that are too large or known already, leaving
+        // `None` for the messages whose signatures still need to be checked.
+        let precheck: Vec<_> = unvalidated_artifacts
+            .iter()
+            .map(|artifact| {
+                let ingress_object = &artifact.message;
+                // If the message is too large, consider the ingress message invalid
+                let size = ingress_object.count_bytes();
+                if size > max_ingress_bytes_per_message {
+                    warn!(
+                        self.log,
+                        "ingress_message_remove_unvalidated";
+                        ingress_message.message_id => format!("{}", ingress_object.message_id),
+                        ingress_message.reason => "message_too_large",
+                        ingress_message.size => size as u64,
+                    );
+                    return Some(RemoveFromUnvalidated(IngressMessageId::from(
+                        ingress_object,
+                    )));
+                }
+
+                // Check status of the ingress message against IngressHistoryReader,
+                // If Unknown, consider the ingress message valid
+                let status = get_status(&ingress_object.message_id);
+                if status != IngressStatus::Unknown {
+                    debug!(
+                        self.log,
+                        "ingress_message_remove_unvalidated";
+                        ingress_message.message_id => format!("{}", ingress_object.message_id),
+                        ingress_message.reason => format!("unexpected_status_{}", status.as_str()),
+                    );
+                    return Some(RemoveFromUnvalidated(IngressMessageId::from(
+                        ingress_object,
+                    )));
+                }
+                None
+            })
+            .collect();
+
+        // Check the signatures of the remaining messages in one batch.
+        let requests: Vec<_> = unvalidated_artifacts
+            .iter()
+            .zip(precheck.iter())
+            .filter(|(_, change_action)| change_action.is_none())
+            .map(|(artifact, _)| artifact.message.signed_ingress.as_ref())
+            .collect();
+        let mut validation_results = validate_requests(
+            &requests,
+            self.ingress_signature_crypto.as_ref(),
+            &self.delegation_cache,
+            current_time,
+            registry_version,
+            &self.malicious_flags,
+        )
+        .into_iter();
+
+        change_set.extend(unvalidated_artifacts.iter().zip(precheck).map(
+            |(artifact, change_action)| {
+                if let Some(change_action) = change_action {
+                    return change_action;
+                }
+                let ingress_object = &artifact.message;
+                let ingress_message = &ingress_object.signed_ingress;
+
+                // Remove from unvalidated if the signatures can't be verified,
+                // add to validated otherwise.
+                if let Some(Err(err)) = validation_results.next() {
+                    debug!(
+                        self.log,
+                        "ingress_message_remove_unvalidated";
+                        ingress_message.message_id => format!("{}", ingress_object.message_id),
+                        ingress_message.reason => format!("auth_failure: {}", err),
+                    );
+                    return RemoveFromUnvalidated(IngressMessageId::from(ingress_object));
+                }
 
-            // Check signatures, remove from unvalidated if they can't be
-            // verified, add to validated otherwise.
-            if let Err(err) = validate_request(
-                ingress_message.as_ref(),
-                self.ingress_signature_crypto.as_ref(),
-                current_time,
-                registry_version,
-                &self.malicious_flags,
-            ) {
                 debug!(
                     self.log,
-                    "ingress_message_remove_unvalidated";
+                    "ingress_message_insert_validated";
                     ingress_message.message_id => format!("{}", ingress_object.message_id),
-                    ingress_message.reason => format!("auth_failure: {}", err),
                 );
-                return RemoveFromUnvalidated(IngressMessageId::from(ingress_object));
-            }
-
-            debug!(
-                self.log,
-                "ingress_message_insert_validated";
-                ingress_message.message_id => format!("{}", ingress_object.message_id),
-            );
-            let integrity_hash = ic_crypto::crypto_hash(ingress_message.binary()).get();
-            MoveToValidated((
-                IngressMessageId::from(ingress_object),
-                artifact.peer_id,
-                size,
-                IngressMessageAttribute::new(ingress_message),
-                integrity_hash,
-            ))
-        }));
+                let integrity_hash = ic_crypto::crypto_hash(ingress_message.binary()).get();
+                MoveToValidated((
+                    IngressMessageId::from(ingress_object),
+                    artifact.peer_id,
+                    ingress_object.count_bytes(),
+                    IngressMessageAttribute::new(ingress_message),
+                    integrity_hash,
+                ))
+            },
+        ));
 
         // Check validated messages and remove if they are not required anymore (i.e.
         // IngressHistoryReader returns status other than Unknown).
@@ -26,8 +26,19 @@ use ic_types::{
     messages::{MessageId, SignedIngress},
     CanisterId, CountBytes, Cycles, Height, NumBytes, Time,
 };
-use ic_validator::{validate_request, RequestValidationError};
-use std::{collections::BTreeMap, sync::Arc};
+use ic_validator::{validate_requests, RequestValidationError};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    ops::RangeInclusive,
+    sync::Arc,
+    time::SystemTime,
+};
+
+/// The candidate messages collected from the pool for a payload are limited
+/// to this many blocks' worth of messages and bytes. Having more candidates
+/// than fit into a block leaves room for candidates that turn out to be
+/// invalid.
+const CANDIDATE_BLOCKS: usize = 2;
 
 impl<'a> IngressSelector for IngressManager {
     fn get_ingress_payload(
@@ -37,6 +48,7 @@ impl<'a> IngressSelector for IngressManager {
         byte_limit: NumBytes,
     ) -> IngressPayload {
         let _timer = self.metrics.ingress_selector_get_payload_time.start_timer();
+        let start_time = SystemTime::now();
         let certified_height = context.certified_height;
         let past_ingress_set = match IngressSetChain::new(context.time, past_ingress, || {
             IngressHistorySet::new(self.ingress_hist_reader.as_ref(), certified_height)
@@ -75,46 +87,94 @@ impl<'a> IngressSelector for IngressManager {
             .get_ingress_message_settings(context.registry_version)
             .expect("Couldn't fetch ingress message parameters from the registry.");
 
-        // Select valid ingress messages and stop once the total size
-        // becomes greater than byte_limit.
+        let (mut canister_queues, canister_order) =
+            self.collect_candidates(expiry_range, &past_ingress_set, &settings, byte_limit);
+
+        // The signatures of the candidates are checked in one batch before
+        // selecting, so that invalid messages neither count against the quota
+        // and cycles of their canister nor leave space in the payload unused.
+        let requests: Vec<_> = canister_order
+            .iter()
+            .flat_map(|canister_id| canister_queues[canister_id].messages.iter())
+            .map(|msg| msg.as_ref())
+            .collect();
+        let mut validation_results = validate_requests(
+            &requests,
+            self.ingress_signature_crypto.as_ref(),
+            &self.delegation_cache,
+            context.time,
+            context.registry_version,
+            &self.malicious_flags,
+        )
+        .into_iter();
+        for canister_id in canister_order.iter() {
+            let queue = canister_queues.get_mut(canister_id).unwrap();
+            queue
+                .messages
+                .retain(|_| validation_results.next().unwrap().is_ok());
+        }
+
+        // Select valid ingress messages round-robin across canisters, in two
+        // phases: first every canister is limited to its quota, then the
+        // remaining space is shared among the canisters that still have
+        // messages.
+        let quota = CanisterQuota::new(&settings, byte_limit, canister_queues.len());
         let mut accumulated_size = 0;
         let mut cycles_needed: BTreeMap<CanisterId, Cycles> = BTreeMap::new();
-        let mut num_messages = 0;
+        let mut messages_in_payload = Vec::new();
+        let mut canisters_at_quota = 0;
+        'phases: for &enforce_quota in &[true, false] {
+            let mut active: VecDeque<CanisterId> = canister_order
+                .iter()
+                .filter(|canister_id| !canister_queues[canister_id].messages.is_empty())
+                .cloned()
+                .collect();
+            while let Some(canister_id) = active.pop_front() {
+                let queue = canister_queues.get_mut(&canister_id).unwrap();
+                if enforce_quota && quota.is_reached_by(queue) {
+                    canisters_at_quota += 1;
+                    continue;
+                }
+                let msg = match queue.messages.pop_front() {
+                    Some(msg) => msg,
+                    None => continue,
+                };
 
-        let mut messages_in_payload = self.ingress_pool.select_validated(
-            expiry_range,
-            Box::new(move |ingress_obj| {
-                let result = self.validate_ingress(
-                    IngressMessageId::from(ingress_obj),
-                    &ingress_obj.signed_ingress,
-                    &state,
-                    context,
-                    &settings,
-                    &past_ingress_set,
-                    num_messages,
-                    &mut cycles_needed,
-                );
-                match result {
-                    Ok(()) => {
-                        num_messages += 1;
-                        // Calculate the size and abort once we have hit the limit
-                        accumulated_size += ingress_obj.signed_ingress.count_bytes();
-                        if accumulated_size > byte_limit.get() as usize {
-                            return SelectResult::Abort;
+                // Skip messages that do not fit into the remaining space;
+                // smaller messages of this or other canisters still might.
+                let size = msg.count_bytes();
+                if accumulated_size + size <= byte_limit.get() as usize {
+                    let result = self.validate_ingress(
+                        IngressMessageId::from(&msg),
+                        &msg,
+                        &state,
+                        &settings,
+                        &past_ingress_set,
+                        messages_in_payload.len(),
+                        &mut cycles_needed,
+                    );
+                    match result {
+                        Ok(()) => {
+                            accumulated_size += size;
+                            queue.selected_messages += 1;
+                            queue.selected_bytes += size;
+                            messages_in_payload.push(msg);
                         }
-
-                        SelectResult::Selected(ingress_obj.signed_ingress.clone())
+                        Err(ValidationError::Permanent(
+                            IngressPermanentError::IngressPayloadTooBig(_, _),
+                        )) => break 'phases,
+                        Err(ValidationError::Permanent(
+                            IngressPermanentError::IngressPayloadTooManyMessages(_, _),
+                        )) => break 'phases,
+                        _ => (),
                     }
-                    Err(ValidationError::Permanent(
-                        IngressPermanentError::IngressPayloadTooBig(_, _),
-                    )) => SelectResult::Abort,
-                    Err(ValidationError::Permanent(
-                        IngressPermanentError::IngressPayloadTooManyMessages(_, _),
-                    )) => SelectResult::Abort,
-                    _ => SelectResult::Skip,
                 }
-            }),
-        );
+
+                if !queue.messages.is_empty() {
+                    active.push_back(canister_id);
+                }
+            }
+        }
 
         // NOTE: Since the `Vec<SignedIngress>` is deserialized and slightly smaller than the
         // serialized `IngressPayload`, we need to check the size of the latter.
@@ -142,12 +202,26 @@ impl<'a> IngressSelector for IngressManager {
 
         let payload_size = payload.count_bytes();
         debug_assert!(payload_size <= byte_limit.get() as usize);
+        self.observe_selection(&messages_in_payload, canisters_at_quota);
 
         // A last step is to validate the payload we just created. It will be
         // an error if this fails, in which case we log the error, and return
         // an empty payload instead.
         match self.validate_ingress_payload(&payload, past_ingress, context) {
-            Ok(()) => payload,
+            Ok(()) => {
+                let tracer = ic_tracing::tracer();
+                if tracer.is_enabled() {
+                    for ingress_id in payload.message_ids() {
+                        // The selection span covers building and validating
+                        // the payload, which is only known once it is done.
+                        let _span = tracer
+                            .message_span("ingress_manager.select", &MessageId::from(&ingress_id))
+                            .with_start_time(start_time)
+                            .with_attribute("certified_height", context.certified_height);
+                    }
+                }
+                payload
+            }
             Err(err) => {
                 error!(self.log, "Created an invalid IngressPayload: {:?}", err);
                 IngressPayload::default()
@@ -213,26 +287,51 @@ impl<'a> IngressSelector for IngressManager {
             ));
         }
 
-        // Tracks the sum of cycles needed per canister.
-        let mut cycles_needed: BTreeMap<CanisterId, Cycles> = BTreeMap::new();
+        // Deserialize the messages up to the first one that fails to, and
+        // check their signatures in one batch.
+        let mut messages = Vec::with_capacity(payload.message_count());
+        let mut payload_error = None;
         for i in 0..payload.message_count() {
-            let (ingress_id, ingress) = payload
-                .get(i)
-                .map_err(IngressPermanentError::IngressPayloadError)?;
+            match payload.get(i) {
+                Ok(message) => messages.push(message),
+                Err(err) => {
+                    payload_error = Some(IngressPermanentError::IngressPayloadError(err));
+                    break;
+                }
+            }
+        }
+        let requests: Vec<_> = messages
+            .iter()
+            .map(|(_, ingress)| ingress.as_ref())
+            .collect();
+        let validation_results = validate_requests(
+            &requests,
+            self.ingress_signature_crypto.as_ref(),
+            &self.delegation_cache,
+            context.time,
+            context.registry_version,
+            &self.malicious_flags,
+        );
 
+        // Tracks the sum of cycles needed per canister.
+        let mut cycles_needed: BTreeMap<CanisterId, Cycles> = BTreeMap::new();
+        for ((ingress_id, ingress), validation_result) in messages.iter().zip(validation_results) {
             self.validate_ingress(
                 ingress_id.clone(),
-                &ingress,
+                ingress,
                 &state,
-                context,
                 &settings,
                 &past_ingress,
                 0, // message count is checked above.
                 &mut cycles_needed,
             )?;
+            validation_result.map_err(|err| request_validation_error(ingress_id, err))?;
         }
 
-        Ok(())
+        match payload_error {
+            Some(err) => Err(ValidationError::Permanent(err)),
+            None => Ok(()),
+        }
     }
 
     fn filter_past_payloads(
@@ -296,14 +395,121 @@ impl<'a> IngressSelector for IngressManager {
     }
 }
 
+/// The candidate messages of a single canister, and the messages and bytes
+/// selected from them so far.
+#[derive(Default)]
+struct CanisterQueue {
+    messages: VecDeque<SignedIngress>,
+    candidate_bytes: usize,
+    selected_messages: usize,
+    selected_bytes: usize,
+}
+
+/// The share of a block payload that a single canister is guaranteed before
+/// the remaining space is handed out to canisters with more messages.
+struct CanisterQuota {
+    messages: usize,
+    bytes: usize,
+}
+
+impl CanisterQuota {
+    /// Splits the limits of a block evenly among `num_canisters` canisters.
+    /// Every canister may include at least one message of maximum size.
+    fn new(settings: &IngressMessageSettings, byte_limit: NumBytes, num_canisters: usize) -> Self {
+        let num_canisters = num_canisters.max(1);
+        Self {
+            messages: (settings.max_ingress_messages_per_block / num_canisters).max(1),
+            bytes: (byte_limit.get() as usize / num_canisters)
+                .max(settings.max_ingress_bytes_per_message),
+        }
+    }
+
+    fn is_reached_by(&self, queue: &CanisterQueue) -> bool {
+        queue.selected_messages >= self.messages || queue.selected_bytes >= self.bytes
+    }
+}
+
 impl IngressManager {
+    /// Collects the candidate messages for a payload per destination canister.
+    /// Messages of the same canister keep their pool order, and canisters are
+    /// returned in the order of their first message in the pool.
+    ///
+    /// A canister never gets more messages than fit into a block, and the pool
+    /// is only traversed until [`CANDIDATE_BLOCKS`] blocks' worth of candidates
+    /// have been collected, so a flooded pool does not blow up the candidate
+    /// set, however many canisters the messages are spread over. Messages that
+    /// are already in `past_ingress_set` or too large to ever be included are
+    /// no candidates and do not count towards these limits.
+    fn collect_candidates(
+        &self,
+        expiry_range: RangeInclusive<Time>,
+        past_ingress_set: &dyn IngressSetQuery,
+        settings: &IngressMessageSettings,
+        byte_limit: NumBytes,
+    ) -> (BTreeMap<CanisterId, CanisterQueue>, Vec<CanisterId>) {
+        let max_candidates = CANDIDATE_BLOCKS * settings.max_ingress_messages_per_block;
+        let max_candidate_bytes = CANDIDATE_BLOCKS * byte_limit.get() as usize;
+        let mut canister_queues: BTreeMap<CanisterId, CanisterQueue> = BTreeMap::new();
+        let mut canister_order = Vec::new();
+        let mut num_candidates = 0;
+        let mut candidate_bytes = 0;
+        self.ingress_pool.select_validated(
+            expiry_range,
+            Box::new(|ingress_obj| {
+                if num_candidates >= max_candidates || candidate_bytes >= max_candidate_bytes {
+                    return SelectResult::Abort;
+                }
+                let msg = &ingress_obj.signed_ingress;
+                let size = msg.count_bytes();
+                if size > settings.max_ingress_bytes_per_message
+                    || past_ingress_set.contains(&IngressMessageId::from(msg))
+                {
+                    return SelectResult::Skip;
+                }
+                let canister_id = msg.canister_id();
+                let queue = canister_queues.entry(canister_id).or_insert_with(|| {
+                    canister_order.push(canister_id);
+                    CanisterQueue::default()
+                });
+                if queue.messages.len() < settings.max_ingress_messages_per_block
+                    && queue.candidate_bytes < byte_limit.get() as usize
+                {
+                    queue.candidate_bytes += size;
+                    queue.messages.push_back(msg.clone());
+                    num_candidates += 1;
+                    candidate_bytes += size;
+                }
+                SelectResult::Skip
+            }),
+        );
+        (canister_queues, canister_order)
+    }
+
+    /// Records how the messages of a payload are distributed over canisters.
+    fn observe_selection(&self, messages_in_payload: &[SignedIngress], canisters_at_quota: usize) {
+        let mut messages_per_canister: BTreeMap<CanisterId, usize> = BTreeMap::new();
+        for msg in messages_in_payload {
+            *messages_per_canister.entry(msg.canister_id()).or_default() += 1;
+        }
+        self.metrics
+            .ingress_selector_canisters_per_payload
+            .observe(messages_per_canister.len() as f64);
+        for count in messages_per_canister.values() {
+            self.metrics
+                .ingress_selector_messages_per_canister
+                .observe(*count as f64);
+        }
+        self.metrics
+            .ingress_selector_canister_quota_reached
+            .inc_by(canisters_at_quota as u64);
+    }
+
     #[allow(clippy::too_many_arguments)]
     fn validate_ingress(
         &self,
         ingress_id: IngressMessageId,
         signed_ingress: &SignedIngress,
         state: &ReplicatedState,
-        context: &ValidationContext,
         settings: &IngressMessageSettings,
         past_ingress_set: &IngressSetChain<IngressHistorySet>,
         num_messages: usize,
@@ -376,30 +582,26 @@ impl IngressManager {
             }
         };
 
-        // Do not include the message if it is considered invalid with
-        // respect to the given context (expiry & registry_version).
-        if let Err(err) = validate_request(
-            signed_ingress.as_ref(),
-            self.ingress_signature_crypto.as_ref(),
-            context.time,
-            context.registry_version,
-            &self.malicious_flags,
-        ) {
-            let message_id = MessageId::from(&ingress_id);
-            return Err(ValidationError::Permanent(match err {
-                RequestValidationError::InvalidIngressExpiry(msg)
-                | RequestValidationError::InvalidDelegationExpiry(msg) => {
-                    IngressPermanentError::IngressExpired(message_id, msg)
-                }
-                err => {
-                    IngressPermanentError::IngressValidationError(message_id, format!("{}", err))
-                }
-            }));
-        }
         Ok(())
     }
 }
 
+/// Converts the error of validating the request of the message `ingress_id`
+/// with respect to the validation context (expiry & registry_version).
+fn request_validation_error(
+    ingress_id: &IngressMessageId,
+    err: RequestValidationError,
+) -> IngressPayloadValidationError {
+    let message_id = MessageId::from(ingress_id);
+    ValidationError::Permanent(match err {
+        RequestValidationError::InvalidIngressExpiry(msg)
+        | RequestValidationError::InvalidDelegationExpiry(msg) => {
+            IngressPermanentError::IngressExpired(message_id, msg)
+        }
+        err => IngressPermanentError::IngressValidationError(message_id, format!("{}", err)),
+    })
+}
+
 /// An IngressSetQuery implementation based on IngressHistoryReader.
 struct IngressHistorySet {
     get_status: Box<dyn Fn(&MessageId) -> IngressStatus>,
@@ -489,6 +691,7 @@ mod tests {
     use super::*;
     use crate::tests::{access_ingress_pool, setup, setup_registry, setup_with_params};
     use assert_matches::assert_matches;
+    use ic_artifact_pool::ingress_pool::IngressPoolImpl;
     use ic_crypto::crypto_hash;
     use ic_ic00_types::{CanisterIdRecord, Payload, IC_00};
     use ic_interfaces::{
@@ -517,7 +720,7 @@ mod tests {
         time::current_time_and_expiry_time,
         Height, RegistryVersion,
     };
-    use std::{collections::HashSet, convert::TryInto, time::Duration};
+    use std::{collections::HashSet, convert::TryInto, sync::RwLock, time::Duration};
 
     const MAX_SIZE: usize = 1000;
     const MAX_SIZE_AS_NUM_BYTES: NumBytes = NumBytes::new(MAX_SIZE as u64);
@@ -1820,4 +2023,361 @@ mod tests {
             },
         );
     }
+
+    /// Adds `msgs` to the validated section of the ingress pool.
+    fn add_validated_messages(ingress_pool: &Arc<RwLock<IngressPoolImpl>>, msgs: &[SignedIngress]) {
+        let time_source = FastForwardTimeSource::new();
+        access_ingress_pool(ingress_pool, |mut ingress_pool| {
+            for m in msgs {
+                let message_id = IngressMessageId::from(m);
+                let attribute = IngressMessageAttribute::new(m);
+                ingress_pool.insert(UnvalidatedArtifact {
+                    message: m.clone(),
+                    peer_id: node_test_id(0),
+                    timestamp: time_source.get_relative_time(),
+                });
+                ingress_pool.apply_changeset(vec![ChangeAction::MoveToValidated((
+                    message_id,
+                    node_test_id(0),
+                    m.count_bytes(),
+                    attribute,
+                    crypto_hash(m.binary()).get(),
+                ))]);
+            }
+        });
+    }
+
+    fn messages_per_canister(payload: IngressPayload) -> BTreeMap<CanisterId, usize> {
+        let msgs: Vec<SignedIngress> = payload.try_into().unwrap();
+        let mut counts = BTreeMap::new();
+        for msg in msgs {
+            *counts.entry(msg.canister_id()).or_default() += 1;
+        }
+        counts
+    }
+
+    fn state_with_canisters(num_canisters: u64) -> ReplicatedState {
+        let mut state = ReplicatedStateBuilder::default();
+        for i in 0..num_canisters {
+            state = state.with_canister(
+                CanisterStateBuilder::default()
+                    .with_canister_id(canister_test_id(i))
+                    .build(),
+            );
+        }
+        state.build()
+    }
+
+    #[tokio::test]
+    // A canister flooding the pool must not starve the other canisters.
+    async fn test_get_payload_noisy_canister_does_not_starve_others() {
+        setup_with_params(
+            None,
+            None,
+            None,
+            Some(state_with_canisters(3)),
+            |ingress_manager, ingress_pool| {
+                let time = mock_time();
+                let message = |canister: u64, nonce: u64, expiry: Time| {
+                    SignedIngressBuilder::new()
+                        .canister_id(canister_test_id(canister))
+                        .nonce(nonce)
+                        .expiry_time(expiry)
+                        .build()
+                };
+                // The messages of the noisy canister expire first, so they
+                // come first in the pool.
+                let noisy: Vec<_> = (0..20)
+                    .map(|i| message(0, i, time + MAX_INGRESS_TTL - Duration::from_secs(1)))
+                    .collect();
+                let quiet = vec![
+                    message(1, 100, time + MAX_INGRESS_TTL),
+                    message(1, 101, time + MAX_INGRESS_TTL),
+                    message(2, 102, time + MAX_INGRESS_TTL),
+                ];
+                add_validated_messages(&ingress_pool, &noisy);
+                add_validated_messages(&ingress_pool, &quiet);
+
+                let validation_context = ValidationContext {
+                    time,
+                    registry_version: RegistryVersion::from(1),
+                    certified_height: Height::from(0),
+                };
+                // There is only space for 7 messages.
+                let byte_limit = NumBytes::new(7 * noisy[0].count_bytes() as u64 + 1);
+                let payload = ingress_manager.get_ingress_payload(
+                    &HashSet::new(),
+                    &validation_context,
+                    byte_limit,
+                );
+                assert_matches!(
+                    ingress_manager.validate_ingress_payload(
+                        &payload,
+                        &HashSet::new(),
+                        &validation_context
+                    ),
+                    Ok(())
+                );
+
+                let counts = messages_per_canister(payload);
+                assert_eq!(counts[&canister_test_id(0)], 4);
+                assert_eq!(counts[&canister_test_id(1)], 2);
+                assert_eq!(counts[&canister_test_id(2)], 1);
+            },
+        )
+    }
+
+    #[tokio::test]
+    // Space left over by canisters below their quota is given to canisters
+    // that have more messages.
+    async fn test_get_payload_canister_exceeds_quota_with_spare_space() {
+        let subnet_id = subnet_test_id(0);
+        let msg_size = SignedIngressBuilder::new()
+            .canister_id(canister_test_id(0))
+            .nonce(0)
+            .expiry_time(mock_time() + MAX_INGRESS_TTL)
+            .build()
+            .count_bytes();
+        let registry = setup_registry(subnet_id, msg_size);
+        setup_with_params(
+            None,
+            Some((registry, subnet_id)),
+            None,
+            Some(state_with_canisters(2)),
+            |ingress_manager, ingress_pool| {
+                let msgs: Vec<_> = (0..10)
+                    .map(|i| (0, i))
+                    .chain(std::iter::once((1, 10)))
+                    .map(|(canister, nonce)| {
+                        SignedIngressBuilder::new()
+                            .canister_id(canister_test_id(canister))
+                            .nonce(nonce)
+                            .expiry_time(mock_time() + MAX_INGRESS_TTL)
+                            .build()
+                    })
+                    .collect();
+                add_validated_messages(&ingress_pool, &msgs);
+
+                let validation_context = ValidationContext {
+                    time: mock_time(),
+                    registry_version: RegistryVersion::from(1),
+                    certified_height: Height::from(0),
+                };
+                // The quota of each canister is 3 messages, but canister 1
+                // only has a single one.
+                let byte_limit = NumBytes::new(6 * msg_size as u64 + 1);
+                let payload = ingress_manager.get_ingress_payload(
+                    &HashSet::new(),
+                    &validation_context,
+                    byte_limit,
+                );
+
+                let counts = messages_per_canister(payload);
+                assert_eq!(counts[&canister_test_id(0)], 5);
+                assert_eq!(counts[&canister_test_id(1)], 1);
+            },
+        )
+    }
+
+    #[tokio::test]
+    // Messages with invalid signatures neither use up the quota of their
+    // canister nor leave space in the payload unused.
+    async fn test_get_payload_invalid_signatures_do_not_count_against_quota() {
+        setup_with_params(
+            None,
+            None,
+            None,
+            Some(state_with_canisters(2)),
+            |ingress_manager, ingress_pool| {
+                let expiry = mock_time() + MAX_INGRESS_TTL;
+                // Changing the nonce after signing invalidates the signature.
+                // The invalid message expires first, so it comes first in the
+                // pool.
+                let invalid = SignedIngressBuilder::new()
+                    .canister_id(canister_test_id(0))
+                    .expiry_time(expiry - Duration::from_secs(1))
+                    .sign_for_randomly_generated_sender()
+                    .nonce(0)
+                    .build();
+                let valid: Vec<_> = (0..3)
+                    .flat_map(|i| vec![(0, 10 + i), (1, 20 + i)])
+                    .map(|(canister, nonce)| {
+                        SignedIngressBuilder::new()
+                            .canister_id(canister_test_id(canister))
+                            .nonce(nonce)
+                            .expiry_time(expiry)
+                            .build()
+                    })
+                    .collect();
+                add_validated_messages(&ingress_pool, &[invalid.clone()]);
+                add_validated_messages(&ingress_pool, &valid);
+
+                let validation_context = ValidationContext {
+                    time: mock_time(),
+                    registry_version: RegistryVersion::from(1),
+                    certified_height: Height::from(0),
+                };
+                // There is only space for 4 valid messages, i.e. a quota of
+                // 2 messages per canister.
+                let byte_limit = NumBytes::new(4 * valid[0].count_bytes() as u64 + 1);
+                let payload = ingress_manager.get_ingress_payload(
+                    &HashSet::new(),
+                    &validation_context,
+                    byte_limit,
+                );
+                assert_matches!(
+                    ingress_manager.validate_ingress_payload(
+                        &payload,
+                        &HashSet::new(),
+                        &validation_context
+                    ),
+                    Ok(())
+                );
+
+                let msgs: Vec<SignedIngress> = payload.clone().try_into().unwrap();
+                assert!(!msgs.contains(&invalid));
+                let counts = messages_per_canister(payload);
+                assert_eq!(counts[&canister_test_id(0)], 2);
+                assert_eq!(counts[&canister_test_id(1)], 2);
+            },
+        )
+    }
+
+    #[tokio::test]
+    // Many canisters flooding the pool do not blow up the candidate set.
+    async fn test_collect_candidates_is_capped_across_canisters() {
+        setup_with_params(
+            None,
+            None,
+            None,
+            Some(state_with_canisters(10)),
+            |ingress_manager, ingress_pool| {
+                let msgs: Vec<_> = (0..10)
+                    .flat_map(|canister| (0..3).map(move |nonce| (canister, nonce)))
+                    .map(|(canister, nonce)| {
+                        SignedIngressBuilder::new()
+                            .canister_id(canister_test_id(canister))
+                            .nonce(nonce)
+                            .expiry_time(mock_time() + MAX_INGRESS_TTL)
+                            .build()
+                    })
+                    .collect();
+                add_validated_messages(&ingress_pool, &msgs);
+                let msg_size = msgs[0].count_bytes();
+                let expiry_range = mock_time()..=mock_time() + MAX_INGRESS_TTL;
+                let num_candidates = |queues: &BTreeMap<CanisterId, CanisterQueue>| {
+                    queues.values().map(|q| q.messages.len()).sum::<usize>()
+                };
+
+                // At most two blocks' worth of messages are collected.
+                let settings = IngressMessageSettings {
+                    max_ingress_bytes_per_message: msg_size,
+                    max_ingress_messages_per_block: 5,
+                };
+                let (queues, order) = ingress_manager.collect_candidates(
+                    expiry_range.clone(),
+                    &HashSet::new(),
+                    &settings,
+                    NumBytes::new(100 * msg_size as u64),
+                );
+                assert_eq!(num_candidates(&queues), 10);
+                assert_eq!(order.len(), queues.len());
+
+                // At most two blocks' worth of bytes are collected.
+                let settings = IngressMessageSettings {
+                    max_ingress_bytes_per_message: msg_size,
+                    max_ingress_messages_per_block: 100,
+                };
+                let (queues, _) = ingress_manager.collect_candidates(
+                    expiry_range,
+                    &HashSet::new(),
+                    &settings,
+                    NumBytes::new(3 * msg_size as u64),
+                );
+                assert_eq!(num_candidates(&queues), 6);
+            },
+        )
+    }
+
+    #[tokio::test]
+    // Messages that were already included or can never be included do not
+    // count against the candidate limits.
+    async fn test_collect_candidates_skips_included_and_oversized_messages() {
+        setup_with_params(
+            None,
+            None,
+            None,
+            Some(state_with_canisters(2)),
+            |ingress_manager, ingress_pool| {
+                // Three blocks' worth of already included messages, expiring
+                // before (and thus traversed before) the fresh ones.
+                let included: Vec<_> = (0..15)
+                    .map(|nonce| {
+                        SignedIngressBuilder::new()
+                            .canister_id(canister_test_id(0))
+                            .nonce(nonce)
+                            .expiry_time(mock_time() + Duration::from_secs(10))
+                            .build()
+                    })
+                    .collect();
+                let fresh: Vec<_> = (0..2)
+                    .flat_map(|canister| (0..3).map(move |nonce| (canister, nonce)))
+                    .map(|(canister, nonce)| {
+                        SignedIngressBuilder::new()
+                            .canister_id(canister_test_id(canister))
+                            .nonce(100 + nonce)
+                            .expiry_time(mock_time() + MAX_INGRESS_TTL)
+                            .build()
+                    })
+                    .collect();
+                let oversized = SignedIngressBuilder::new()
+                    .canister_id(canister_test_id(1))
+                    .method_payload(vec![0; 1000])
+                    .expiry_time(mock_time() + Duration::from_secs(20))
+                    .build();
+                add_validated_messages(&ingress_pool, &included);
+                add_validated_messages(&ingress_pool, &[oversized]);
+                add_validated_messages(&ingress_pool, &fresh);
+                let past_ingress: HashSet<_> =
+                    included.iter().map(IngressMessageId::from).collect();
+
+                let msg_size = fresh[0].count_bytes();
+                let settings = IngressMessageSettings {
+                    max_ingress_bytes_per_message: msg_size,
+                    max_ingress_messages_per_block: 5,
+                };
+                let (queues, order) = ingress_manager.collect_candidates(
+                    mock_time()..=mock_time() + MAX_INGRESS_TTL,
+                    &past_ingress,
+                    &settings,
+                    NumBytes::new(100 * msg_size as u64),
+                );
+                assert_eq!(order.len(), 2);
+                let candidates: HashSet<_> = queues
+                    .values()
+                    .flat_map(|queue| queue.messages.iter())
+                    .map(IngressMessageId::from)
+                    .collect();
+                let expected: HashSet<_> = fresh.iter().map(IngressMessageId::from).collect();
+                assert_eq!(candidates, expected);
+            },
+        )
+    }
+
+    #[test]
+    fn test_canister_quota() {
+        let settings = IngressMessageSettings {
+            max_ingress_bytes_per_message: 100,
+            max_ingress_messages_per_block: 10,
+        };
+        let quota = CanisterQuota::new(&settings, NumBytes::new(1000), 4);
+        assert_eq!((quota.messages, quota.bytes), (2, 250));
+
+        // Every canister may include at least one message of maximum size.
+        let quota = CanisterQuota::new(&settings, NumBytes::new(1000), 20);
+        assert_eq!((quota.messages, quota.bytes), (1, 100));
+
+        let quota = CanisterQuota::new(&settings, NumBytes::new(1000), 0);
+        assert_eq!((quota.messages, quota.bytes), (10, 1000));
+    }
 }
@@ -5,6 +5,7 @@
 mod ingress_handler;
 mod ingress_selector;
 
+use ic_crypto::VerifiedDelegationCache;
 use ic_cycles_account_manager::CyclesAccountManager;
 use ic_interfaces::{
     consensus_pool::ConsensusPoolCache,
@@ -26,7 +27,7 @@ use ic_types::{
     time::{Time, UNIX_EPOCH},
     Height, RegistryVersion, SubnetId,
 };
-use prometheus::{Histogram, IntGauge};
+use prometheus::{Histogram, IntCounter, IntGauge};
 use std::{
     collections::{BTreeMap, HashSet},
     ops::RangeInclusive,
@@ -71,6 +72,9 @@ struct IngressManagerMetrics {
     ingress_selector_get_payload_time: Histogram,
     ingress_selector_validate_payload_time: Histogram,
     ingress_payload_cache_size: IntGauge,
+    ingress_selector_canisters_per_payload: Histogram,
+    ingress_selector_messages_per_canister: Histogram,
+    ingress_selector_canister_quota_reached: IntCounter,
 }
 
 impl IngressManagerMetrics {
@@ -95,6 +99,20 @@ impl IngressManagerMetrics {
                 "ingress_payload_cache_size",
                 "The number of HashSets in payload builder's ingress payload cache.",
             ),
+            ingress_selector_canisters_per_payload: metrics_registry.histogram(
+                "ingress_selector_canisters_per_payload",
+                "The number of distinct destination canisters of the messages in an ingress payload.",
+                decimal_buckets(0, 3),
+            ),
+            ingress_selector_messages_per_canister: metrics_registry.histogram(
+                "ingress_selector_messages_per_canister",
+                "The number of messages selected for a single destination canister in an ingress payload.",
+                decimal_buckets(0, 3),
+            ),
+            ingress_selector_canister_quota_reached: metrics_registry.int_counter(
+                "ingress_selector_canister_quota_reached_total",
+                "The number of times a canister reached its quota while building an ingress payload.",
+            ),
         }
     }
 }
@@ -109,6 +127,9 @@ pub struct IngressManager {
     ingress_pool: IngressPoolSelectWrapper,
     registry_client: Arc<dyn RegistryClient>,
     ingress_signature_crypto: Arc<dyn IngressSigVerifier + Send + Sync>,
+    /// Delegations that were verified before, shared by validation and
+    /// payload building.
+    delegation_cache: VerifiedDelegationCache,
     metrics: IngressManagerMetrics,
     subnet_id: SubnetId,
     log: ReplicaLogger,
@@ -144,6 +165,7 @@ impl IngressManager {
             ingress_pool: IngressPoolSelectWrapper::new(&ingress_pool),
             registry_client,
             ingress_signature_crypto,
+            delegation_cache: VerifiedDelegationCache::default(),
             metrics: IngressManagerMetrics::new(metrics_registry),
             subnet_id,
             log,
@@ -19,6 +19,7 @@ pub use sign::threshold_sig::ni_dkg::{LoadTranscriptResult, NiDkgAlgorithm};
 
 mod sign;
 
+pub use sign::BasicSigBatchVerifierByPublicKey;
 pub use sign::BasicSigVerifier;
 pub use sign::BasicSigVerifierByPublicKey;
 pub use sign::BasicSigner;
@@ -1,7 +1,7 @@
 //! Messages used in various components.
 use ic_types::{
     messages::{Ingress, Request, Response, StopCanisterContext},
-    Cycles, PrincipalId,
+    Cycles, PrincipalId, Time,
 };
 use std::convert::TryFrom;
 
@@ -58,6 +58,14 @@ impl RequestOrIngress {
         }
     }
 
+    /// Returns the deadline of a best-effort request, `None` otherwise.
+    pub fn deadline(&self) -> Option<Time> {
+        match self {
+            RequestOrIngress::Request(msg) => msg.deadline,
+            RequestOrIngress::Ingress(_) => None,
+        }
+    }
+
     /// Extracts the cycles received with this message.
     pub fn take_cycles(&mut self) -> Cycles {
         match self {
@@ -753,12 +753,13 @@ mod checksum {
             access_kind: AccessKind,
         ) {
             self.index += 1;
-            self.value += self.index
-                * (access_addr as usize - base_addr)
-                * match access_kind {
-                    AccessKind::Read => 1,
-                    AccessKind::Write => 1 << 32,
-                };
+            // Offsets into a 64-bit Wasm memory can exceed 32 bits, so the
+            // checksum wraps around instead of overflowing.
+            let offset = (access_addr as usize - base_addr).wrapping_mul(match access_kind {
+                AccessKind::Read => 1,
+                AccessKind::Write => 1 << 32,
+            });
+            self.value = self.value.wrapping_add(self.index.wrapping_mul(offset));
         }
     }
 
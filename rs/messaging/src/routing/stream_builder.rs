@@ -2,7 +2,7 @@ use crate::message_routing::LatencyMetrics;
 use ic_base_types::NumBytes;
 use ic_constants::SYSTEM_SUBNET_STREAM_MSG_LIMIT;
 use ic_error_types::RejectCode;
-use ic_logger::{error, warn, ReplicaLogger};
+use ic_logger::{debug, error, warn, ReplicaLogger};
 use ic_metrics::{buckets::decimal_buckets, MetricsRegistry};
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::replicated_state::PeekableOutputIterator;
@@ -75,6 +75,7 @@ const LABEL_VALUE_STATUS_SUCCESS: &str = "success";
 const LABEL_VALUE_STATUS_CANISTER_NOT_FOUND: &str = "canister_not_found";
 const LABEL_VALUE_STATUS_PAYLOAD_TOO_LARGE: &str = "payload_too_large";
 const LABEL_VALUE_STATUS_INVALID_CYCLE_TRANSFER: &str = "invalid_cycle_transfer";
+const LABEL_VALUE_STATUS_BEST_EFFORT_DROPPED: &str = "best_effort_dropped";
 
 const CRITICAL_ERROR_INFINITE_LOOP: &str = "mr_stream_builder_infinite_loop";
 const CRITICAL_ERROR_PAYLOAD_TOO_LARGE: &str = "mr_stream_builder_payload_too_large";
@@ -197,6 +198,7 @@ impl StreamBuilderImpl {
                         code: reject_code,
                         message: reject_message,
                     }),
+                    deadline: req.deadline,
                 }
                 .into(),
                 // Arbitrary large amounts, pushing a response always returns memory.
@@ -307,13 +309,15 @@ impl StreamBuilderImpl {
         let mut requests_to_reject = Vec::new();
         let mut oversized_requests = Vec::new();
         let mut invalid_cycle_transfer_requests = Vec::new();
+        let mut dropped_best_effort_requests = Vec::new();
 
         let mut output_iter = state.output_into_iter();
         let mut last_output_size = usize::MAX;
 
         // Route all messages into the appropriate stream or generate reject Responses
         // when unable to (no route to canister). When a stream's byte size reaches or
-        // exceeds `target_stream_size_bytes`, any matching queues are skipped.
+        // exceeds `target_stream_size_bytes`, best-effort messages at the front of
+        // matching queues are dropped and the queues are otherwise skipped.
         while let Some((queue_id, queue_index, msg)) = output_iter.peek() {
             // Safeguard to guarantee that iteration always terminates. Will always loop at
             // least once, if messages are available.
@@ -343,6 +347,30 @@ impl StreamBuilderImpl {
                             .get(&dst_net_id)
                             .unwrap_or(&SubnetType::Application),
                     ) {
+                        if msg.deadline().is_some() {
+                            // Stream full, drop the best-effort message. A dropped
+                            // request is rejected right away, refunding its cycles to
+                            // the sender; the caller of a dropped response gets a
+                            // `SYS_UNKNOWN` reject once the deadline expires.
+                            let msg =
+                                validated_next(&mut output_iter, (queue_id, queue_index, msg));
+                            debug!(
+                                self.log,
+                                "Stream to subnet {} is full, dropping best-effort message from {} to {}",
+                                dst_net_id,
+                                msg.sender(),
+                                msg.receiver()
+                            );
+                            self.observe_message_status(
+                                &msg,
+                                LABEL_VALUE_STATUS_BEST_EFFORT_DROPPED,
+                            );
+                            if let RequestOrResponse::Request(req) = msg {
+                                dropped_best_effort_requests.push(req);
+                            }
+                            continue;
+                        }
+
                         // Stream full, skip all other messages to this destination.
                         output_iter.exclude_queue();
                         continue;
@@ -507,6 +535,15 @@ impl StreamBuilderImpl {
             );
         }
 
+        for req in dropped_best_effort_requests {
+            self.reject_local_request(
+                &mut state,
+                req,
+                RejectCode::SysTransient,
+                "Best-effort request dropped: outgoing stream is full".to_string(),
+            );
+        }
+
         // Export the total number of enqueued messages and byte size, per stream.
         streams
             .iter()
@@ -112,6 +112,7 @@ fn reject_local_request() {
                         code: RejectCode::SysFatal,
                         message: reject_message.to_string(),
                     }),
+                    deadline: None,
                 }
                 .into(),
                 (u64::MAX / 2).into(),
@@ -177,6 +178,7 @@ fn reject_local_request_for_subnet() {
                         code: RejectCode::SysFatal,
                         message: reject_message.to_string(),
                     }),
+                    deadline: None,
                 }
                 .into(),
                 (u64::MAX / 2).into(),
@@ -538,6 +540,69 @@ fn build_streams_impl_respects_message_limit() {
     build_streams_impl_respects_limits(4, 1_000_000, 4);
 }
 
+/// Tests that best-effort messages are dropped (rather than left in the output
+/// queues) when the destination stream is at its limit; and that dropped
+/// requests are rejected, refunding their cycles.
+#[test]
+fn build_streams_impl_at_limit_drops_best_effort_messages() {
+    with_test_replica_logger(|log| {
+        let (stream_builder, mut provided_state, metrics_registry) = new_fixture(&log);
+        provided_state.metadata.network_topology.routing_table = Arc::new(RoutingTable::try_from(
+            btreemap! {
+                CanisterIdRange{ start: CanisterId::from(0), end: CanisterId::from(0xfff) } => REMOTE_SUBNET,
+            },
+        ).unwrap());
+
+        // An empty stream, so that the stream builder considers it full.
+        let mut streams = provided_state.take_streams();
+        streams.get_mut_or_insert(REMOTE_SUBNET);
+        provided_state.put_streams(streams);
+
+        // Best-effort requests only.
+        let deadline = provided_state.time() + std::time::Duration::from_secs(10);
+        let msgs: Vec<Request> =
+            generate_messages_for_test(/* senders = */ 2, /* receivers = */ 2)
+                .into_iter()
+                .map(|req| Request {
+                    deadline: Some(deadline),
+                    ..req
+                })
+                .collect();
+        let msg_count = msgs.len() as u64;
+        provided_state.put_canister_states(canister_states_with_outputs(msgs.clone()));
+
+        // All messages are consumed from the output queues, none is routed.
+        let mut expected_state = consume_output_queues(&provided_state);
+
+        // Each request is rejected, with its payment refunded.
+        for msg in msgs {
+            assert!(msg.payment > Cycles::zero());
+            stream_builder.reject_local_request(
+                &mut expected_state,
+                msg,
+                RejectCode::SysTransient,
+                "Best-effort request dropped: outgoing stream is full".to_string(),
+            );
+        }
+
+        // Act.
+        let result_state = stream_builder.build_streams_impl(provided_state, 0, usize::MAX);
+
+        assert_eq!(result_state, expected_state);
+        assert_routed_messages_eq(
+            metric_vec(&[(
+                &[
+                    (LABEL_TYPE, LABEL_VALUE_TYPE_REQUEST),
+                    (LABEL_STATUS, LABEL_VALUE_STATUS_BEST_EFFORT_DROPPED),
+                ],
+                msg_count,
+            )]),
+            &metrics_registry,
+        );
+        assert_eq!(0, fetch_routed_payload_count(&metrics_registry));
+    });
+}
+
 // Tests that messages addressed to canisters not mapped to a known subnet
 // result in reject Responses.
 #[test]
@@ -696,6 +761,7 @@ fn build_streams_with_oversized_payloads() {
             payment: Cycles::new(1),
             method_name: method_name.clone(),
             method_payload: oversized_request_payload.clone(),
+            deadline: None,
         };
         assert!(local_request.payload_size_bytes() > MAX_INTER_CANISTER_PAYLOAD_IN_BYTES);
 
@@ -707,6 +773,7 @@ fn build_streams_with_oversized_payloads() {
             payment: Cycles::new(2),
             method_name,
             method_payload: oversized_request_payload,
+            deadline: None,
         };
         assert!(remote_request.payload_size_bytes() > MAX_INTER_CANISTER_PAYLOAD_IN_BYTES);
         let remote_request_reject = Response {
@@ -721,6 +788,7 @@ fn build_streams_with_oversized_payloads() {
                     local_canister
                 ),
             )),
+            deadline: None,
         };
 
         // Oversized response: will be replaced with a reject response.
@@ -730,6 +798,7 @@ fn build_streams_with_oversized_payloads() {
             originator_reply_callback: CallbackId::from(3),
             refund: Cycles::new(3),
             response_payload: Payload::Data(oversized_response_payload),
+            deadline: None,
         };
         assert!(data_response.payload_size_bytes() > MAX_INTER_CANISTER_PAYLOAD_IN_BYTES);
         let data_response_reject = Response {
@@ -744,6 +813,7 @@ fn build_streams_with_oversized_payloads() {
                     local_canister
                 ),
             )),
+            deadline: None,
         };
 
         // Oversized reject response: will be replaced with a reject response.
@@ -756,6 +826,7 @@ fn build_streams_with_oversized_payloads() {
                 RejectCode::SysTransient,
                 oversized_error_message,
             )),
+            deadline: None,
         };
         assert!(reject_response.payload_size_bytes() > MAX_INTER_CANISTER_PAYLOAD_IN_BYTES);
         let reject_response_reject = Response {
@@ -767,6 +838,7 @@ fn build_streams_with_oversized_payloads() {
                 RejectCode::SysTransient,
                 "x".repeat(5 * 1024) + "..." + &"x".repeat(2 * 1024),
             )),
+            deadline: None,
         };
 
         let (stream_builder, mut provided_state, metrics_registry) = new_fixture(&log);
@@ -1108,6 +1180,7 @@ fn build_streams_sending_cycles_from_app_to_verified_app_subnets() {
             payment: Cycles::new(1),
             method_name,
             method_payload: vec![],
+            deadline: None,
         };
 
         // Reject for sending cycles from app to verified app subnets.
@@ -1120,6 +1193,7 @@ fn build_streams_sending_cycles_from_app_to_verified_app_subnets() {
                 RejectCode::CanisterError,
                 format!("Canister {} violated contract: Canisters on Application subnets cannot send cycles to canister {} on a Verified Application subnet", local_canister, remote_canister),
             )),
+            deadline: None,
         };
 
         let (stream_builder, mut provided_state, metrics_registry) = new_fixture(&log);
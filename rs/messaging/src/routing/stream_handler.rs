@@ -849,6 +849,7 @@ fn generate_reject_response(msg: RequestOrResponse, context: RejectContext) -> R
             originator_reply_callback: msg.sender_reply_callback,
             refund: msg.payment,
             response_payload: Payload::Reject(context),
+            deadline: msg.deadline,
         }
         .into()
     } else {
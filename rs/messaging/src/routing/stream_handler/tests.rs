@@ -1251,6 +1251,7 @@ fn generate_reject_response_queue_full() {
                 RejectCode::SysTransient,
                 err.to_string(),
             )),
+            deadline: None,
         }
         .into(),
     );
@@ -1290,6 +1291,7 @@ fn generate_reject_response_canister_not_found() {
                 RejectCode::DestinationInvalid,
                 err.to_string(),
             )),
+            deadline: None,
         }
         .into(),
     );
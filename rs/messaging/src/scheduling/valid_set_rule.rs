@@ -139,6 +139,9 @@ impl ValidSetRuleImpl {
     fn induct_message(&self, state: &mut ReplicatedState, msg: SignedIngressContent) {
         trace!(self.log, "induct_message");
         let message_id = msg.id();
+        let mut span = ic_tracing::tracer()
+            .message_span("message_routing.induct_message", &message_id)
+            .with_attribute("canister_id", msg.canister_id());
         let source = msg.sender();
         let receiver = msg.canister_id();
         let payload_bytes = msg.arg().len();
@@ -197,6 +200,7 @@ impl ValidSetRuleImpl {
                 err.to_label_value()
             }
         };
+        span.set_attribute("status", status);
         self.observe_inducted_ingress_status(status);
         self.observe_unreliable_induct_ingress_message_duration(status, ingress_expiry);
     }
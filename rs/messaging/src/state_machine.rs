@@ -71,7 +71,9 @@ impl StateMachine for StateMachineImpl {
         subnet_features: SubnetFeatures,
         registry_settings: &RegistryExecutionSettings,
     ) -> ReplicatedState {
+        let tracer = ic_tracing::tracer();
         let phase_timer = Timer::start();
+        let induction_span = tracer.height_span("message_routing.induction", batch.batch_number);
 
         let mut metadata = state.system_metadata().clone();
         metadata.batch_time = batch.time;
@@ -90,6 +92,7 @@ impl StateMachine for StateMachineImpl {
         }
         state_with_messages.consensus_queue = batch.consensus_responses;
         self.observe_phase_duration(PHASE_INDUCTION, &phase_timer);
+        drop(induction_span);
 
         let execution_round_type = if batch.requires_full_state_hash {
             ExecutionRoundType::CheckpointRound
@@ -98,6 +101,7 @@ impl StateMachine for StateMachineImpl {
         };
 
         let phase_timer = Timer::start();
+        let execution_span = tracer.height_span("execution.execute_round", batch.batch_number);
         // Process messages from the induction pool through the Scheduler.
         let state_after_execution = self.scheduler.execute_round(
             state_with_messages,
@@ -108,8 +112,11 @@ impl StateMachine for StateMachineImpl {
             registry_settings,
         );
         self.observe_phase_duration(PHASE_EXECUTION, &phase_timer);
+        drop(execution_span);
 
         let phase_timer = Timer::start();
+        let _stream_builder_span =
+            tracer.height_span("message_routing.build_streams", batch.batch_number);
         // Postprocess the state and consolidate the Streams.
         let state_after_stream_builder = self.stream_builder.build_streams(state_after_execution);
         self.observe_phase_duration(PHASE_MESSAGE_ROUTING, &phase_timer);
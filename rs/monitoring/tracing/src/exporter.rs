@@ -0,0 +1,265 @@
+//! Exports finished spans from a background thread.
+
+use crate::SpanData;
+use ic_config::tracing::Exporter;
+use ic_logger::{warn, ReplicaLogger};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::mpsc::{sync_channel, Receiver, RecvTimeoutError, SyncSender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The number of finished spans that may be queued for export. Further spans
+/// are dropped until the exporter catches up.
+const QUEUE_CAPACITY: usize = 10_000;
+
+/// The maximum number of spans exported at once.
+const MAX_BATCH_SIZE: usize = 512;
+
+/// The maximum time a finished span waits for its batch to be exported.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The maximum time an export request to an OTLP collector may take, so that
+/// an unresponsive collector cannot stall the exporter indefinitely.
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Identifies the replica that recorded the spans.
+pub(crate) struct Resource {
+    pub(crate) node_id: String,
+    pub(crate) subnet_id: String,
+}
+
+/// A destination of finished spans.
+trait Sink: Send {
+    fn export(&mut self, spans: Vec<SpanData>) -> Result<(), String>;
+}
+
+/// Spawns the exporter thread and returns the sender that spans are handed
+/// to. The thread exits once all senders are dropped.
+pub(crate) fn spawn(
+    config: &Exporter,
+    resource: Resource,
+    log: ReplicaLogger,
+) -> Result<SyncSender<SpanData>, String> {
+    let sink: Box<dyn Sink> = match config {
+        Exporter::Otlp(endpoint) => Box::new(OtlpSink::new(endpoint.clone(), resource)?),
+        Exporter::JsonlFile(path) => Box::new(JsonlSink::new(path, resource)?),
+    };
+    let (sender, receiver) = sync_channel(QUEUE_CAPACITY);
+    thread::Builder::new()
+        .name("tracing_exporter".to_string())
+        .spawn(move || run(receiver, sink, log))
+        .map_err(|e| format!("Failed to spawn the tracing exporter: {}", e))?;
+    Ok(sender)
+}
+
+fn run(receiver: Receiver<SpanData>, mut sink: Box<dyn Sink>, log: ReplicaLogger) {
+    let mut batch = Vec::new();
+    let mut last_flush = Instant::now();
+    loop {
+        let disconnected = match receiver.recv_timeout(FLUSH_INTERVAL) {
+            Ok(span) => {
+                batch.push(span);
+                false
+            }
+            Err(RecvTimeoutError::Timeout) => false,
+            Err(RecvTimeoutError::Disconnected) => true,
+        };
+        let flush =
+            disconnected || batch.len() >= MAX_BATCH_SIZE || last_flush.elapsed() >= FLUSH_INTERVAL;
+        if flush && !batch.is_empty() {
+            if let Err(err) = sink.export(std::mem::take(&mut batch)) {
+                warn!(every_n_seconds => 30, log, "Failed to export spans: {}", err);
+            }
+        }
+        if flush {
+            last_flush = Instant::now();
+        }
+        if disconnected {
+            return;
+        }
+    }
+}
+
+/// A span as written to a JSONL file.
+#[derive(Serialize)]
+struct JsonlSpan<'a> {
+    node_id: &'a str,
+    subnet_id: &'a str,
+    #[serde(flatten)]
+    span: &'a SpanData,
+}
+
+/// Appends spans to a file, one JSON object per line.
+struct JsonlSink {
+    writer: BufWriter<File>,
+    resource: Resource,
+}
+
+impl JsonlSink {
+    fn new(path: &Path, resource: Resource) -> Result<Self, String> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        Ok(Self {
+            writer: BufWriter::new(file),
+            resource,
+        })
+    }
+}
+
+impl Sink for JsonlSink {
+    fn export(&mut self, spans: Vec<SpanData>) -> Result<(), String> {
+        for span in spans.iter() {
+            let line = JsonlSpan {
+                node_id: &self.resource.node_id,
+                subnet_id: &self.resource.subnet_id,
+                span,
+            };
+            serde_json::to_writer(&mut self.writer, &line).map_err(|e| e.to_string())?;
+            self.writer.write_all(b"\n").map_err(|e| e.to_string())?;
+        }
+        self.writer.flush().map_err(|e| e.to_string())
+    }
+}
+
+/// Sends spans to an OpenTelemetry collector using the JSON encoding of
+/// OTLP/HTTP.
+struct OtlpSink {
+    endpoint: String,
+    resource: Value,
+    client: reqwest::Client,
+    runtime: tokio::runtime::Runtime,
+}
+
+impl OtlpSink {
+    fn new(endpoint: String, resource: Resource) -> Result<Self, String> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| format!("Failed to create the tracing runtime: {}", e))?;
+        let client = reqwest::Client::builder()
+            .timeout(EXPORT_TIMEOUT)
+            .build()
+            .map_err(|e| format!("Failed to create the tracing HTTP client: {}", e))?;
+        Ok(Self {
+            endpoint,
+            resource: json!({
+                "attributes": [
+                    otlp_attribute("service.name", "replica"),
+                    otlp_attribute("ic.node_id", &resource.node_id),
+                    otlp_attribute("ic.subnet_id", &resource.subnet_id),
+                ]
+            }),
+            client,
+            runtime,
+        })
+    }
+
+    fn request_body(&self, spans: &[SpanData]) -> Value {
+        let spans: Vec<_> = spans
+            .iter()
+            .map(|span| {
+                json!({
+                    "traceId": span.trace_id,
+                    "spanId": span.span_id,
+                    "name": span.name,
+                    // SPAN_KIND_INTERNAL
+                    "kind": 1,
+                    "startTimeUnixNano": span.start_time_unix_nano.to_string(),
+                    "endTimeUnixNano": span.end_time_unix_nano.to_string(),
+                    "attributes": span
+                        .attributes
+                        .iter()
+                        .map(|(key, value)| otlp_attribute(key, value))
+                        .collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+        json!({
+            "resourceSpans": [{
+                "resource": self.resource,
+                "scopeSpans": [{
+                    "scope": { "name": "ic-tracing" },
+                    "spans": spans,
+                }],
+            }]
+        })
+    }
+}
+
+impl Sink for OtlpSink {
+    fn export(&mut self, spans: Vec<SpanData>) -> Result<(), String> {
+        let body = self.request_body(&spans).to_string();
+        let request = self
+            .client
+            .post(&self.endpoint)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body)
+            .send();
+        let response = self
+            .runtime
+            .block_on(request)
+            .map_err(|e| format!("Request to {} failed: {}", self.endpoint, e))?;
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(format!(
+                "Request to {} failed with status {}",
+                self.endpoint,
+                response.status()
+            ))
+        }
+    }
+}
+
+fn otlp_attribute(key: &str, value: &str) -> Value {
+    json!({ "key": key, "value": { "stringValue": value } })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn otlp_request_body_has_expected_layout() {
+        let sink = OtlpSink::new(
+            "http://127.0.0.1:4318/v1/traces".to_string(),
+            Resource {
+                node_id: "node".to_string(),
+                subnet_id: "subnet".to_string(),
+            },
+        )
+        .unwrap();
+        let mut attributes = BTreeMap::new();
+        attributes.insert("height", "7".to_string());
+        let body = sink.request_body(&[SpanData {
+            trace_id: "00".repeat(16),
+            span_id: "01".repeat(8),
+            name: "consensus.deliver_batch",
+            start_time_unix_nano: 1,
+            end_time_unix_nano: 2,
+            attributes,
+        }]);
+
+        let resource_spans = &body["resourceSpans"][0];
+        assert_eq!(
+            resource_spans["resource"]["attributes"][1],
+            json!({ "key": "ic.node_id", "value": { "stringValue": "node" } })
+        );
+        let span = &resource_spans["scopeSpans"][0]["spans"][0];
+        assert_eq!(span["name"], "consensus.deliver_batch");
+        assert_eq!(span["spanId"], "0101010101010101");
+        assert_eq!(span["startTimeUnixNano"], "1");
+        assert_eq!(
+            span["attributes"][0],
+            json!({ "key": "height", "value": { "stringValue": "7" } })
+        );
+    }
+}
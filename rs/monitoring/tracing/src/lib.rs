@@ -0,0 +1,371 @@
+//! Opt-in tracing of the lifecycle of ingress messages across replica
+//! components.
+//!
+//! Components record [`Span`]s keyed either by the ID of the message they
+//! process or by the height of the block (batch) they process. The trace ID
+//! of a span is derived from its key, so the spans that the HTTP handler, the
+//! ingress manager, consensus, message routing and execution record for the
+//! same message end up in the same trace without having to pass a context
+//! between components. The sampling decision is derived from the key as well,
+//! so either all components trace a message or none does.
+//!
+//! The process-wide tracer is installed with [`init`] and retrieved with
+//! [`tracer`]. Until [`init`] is called, or if the sampling rate is zero, the
+//! tracer is disabled and spans are no-ops.
+
+mod exporter;
+
+use ic_config::tracing::Config;
+use ic_logger::ReplicaLogger;
+use ic_types::{messages::MessageId, Height, NodeId, SubnetId};
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::sync::mpsc::SyncSender;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+static TRACER: OnceCell<Tracer> = OnceCell::new();
+
+/// Installs the process-wide tracer.
+///
+/// Returns an error if the exporter cannot be set up or if the tracer has
+/// already been installed.
+pub fn init(
+    config: &Config,
+    node_id: NodeId,
+    subnet_id: SubnetId,
+    log: ReplicaLogger,
+) -> Result<(), String> {
+    let tracer = Tracer::new(config, node_id, subnet_id, log)?;
+    TRACER
+        .set(tracer)
+        .map_err(|_| "The tracer has already been installed".to_string())
+}
+
+/// Returns the process-wide tracer, or a disabled tracer if [`init`] has not
+/// been called.
+pub fn tracer() -> &'static Tracer {
+    static DISABLED: Tracer = Tracer { inner: None };
+    TRACER.get().unwrap_or(&DISABLED)
+}
+
+/// Creates spans and hands finished spans to the exporter.
+#[derive(Clone)]
+pub struct Tracer {
+    inner: Option<TracerInner>,
+}
+
+#[derive(Clone)]
+struct TracerInner {
+    /// Keys below this threshold are sampled. Kept as a `u128` so that a
+    /// sampling rate of 1.0 samples all keys.
+    sampling_threshold: u128,
+    sender: SyncSender<SpanData>,
+}
+
+impl Tracer {
+    /// Creates a tracer that exports spans as configured in `config`.
+    pub fn new(
+        config: &Config,
+        node_id: NodeId,
+        subnet_id: SubnetId,
+        log: ReplicaLogger,
+    ) -> Result<Self, String> {
+        if config.sampling_rate <= 0.0 {
+            return Ok(Self::disabled());
+        }
+        let resource = exporter::Resource {
+            node_id: node_id.to_string(),
+            subnet_id: subnet_id.to_string(),
+        };
+        let sender = exporter::spawn(&config.exporter, resource, log)?;
+        Ok(Self {
+            inner: Some(TracerInner {
+                sampling_threshold: sampling_threshold(config.sampling_rate),
+                sender,
+            }),
+        })
+    }
+
+    /// Creates a tracer that does not record any spans.
+    pub fn disabled() -> Self {
+        Self { inner: None }
+    }
+
+    /// Returns `true` if the tracer may record spans. Components can use this
+    /// to skip work that is only needed for tracing.
+    pub fn is_enabled(&self) -> bool {
+        self.inner.is_some()
+    }
+
+    /// Starts a span in the trace of the ingress message `message_id`.
+    pub fn message_span(&self, name: &'static str, message_id: &MessageId) -> Span {
+        let bytes = message_id.as_bytes();
+        let mut trace_id = [0; 16];
+        trace_id.copy_from_slice(&bytes[..16]);
+        let mut key = [0; 8];
+        key.copy_from_slice(&bytes[..8]);
+        self.span(name, trace_id, u64::from_be_bytes(key))
+            .with_attribute("message_id", message_id)
+    }
+
+    /// Starts a span in the trace of the block at `height`.
+    pub fn height_span(&self, name: &'static str, height: Height) -> Span {
+        let key = mix(height.get());
+        let mut trace_id = [0; 16];
+        trace_id[..8].copy_from_slice(&key.to_be_bytes());
+        trace_id[8..].copy_from_slice(&height.get().to_be_bytes());
+        self.span(name, trace_id, key)
+            .with_attribute("height", height)
+    }
+
+    fn span(&self, name: &'static str, trace_id: [u8; 16], key: u64) -> Span {
+        let recording = self.inner.as_ref().and_then(|inner| {
+            if u128::from(key) >= inner.sampling_threshold {
+                return None;
+            }
+            Some(Recording {
+                data: SpanData {
+                    trace_id: hex::encode(trace_id),
+                    span_id: hex::encode(rand::random::<[u8; 8]>()),
+                    name,
+                    start_time_unix_nano: now_nanos(),
+                    end_time_unix_nano: 0,
+                    attributes: BTreeMap::new(),
+                },
+                sender: inner.sender.clone(),
+            })
+        });
+        Span { recording }
+    }
+}
+
+/// A unit of work done by a component. The span ends when it is dropped.
+#[must_use = "a span ends as soon as it is dropped"]
+pub struct Span {
+    recording: Option<Recording>,
+}
+
+struct Recording {
+    data: SpanData,
+    sender: SyncSender<SpanData>,
+}
+
+impl Span {
+    /// Returns `true` if the span is sampled, i.e. it will be exported.
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Sets the attribute `key` to `value`. The value is only formatted if
+    /// the span is recording.
+    pub fn set_attribute(&mut self, key: &'static str, value: impl Display) {
+        if let Some(recording) = &mut self.recording {
+            recording.data.attributes.insert(key, value.to_string());
+        }
+    }
+
+    /// Same as [`Span::set_attribute`], for use when creating spans.
+    pub fn with_attribute(mut self, key: &'static str, value: impl Display) -> Self {
+        self.set_attribute(key, value);
+        self
+    }
+
+    /// Backdates the start of the span to `start_time`, for work that began
+    /// before it was known which messages it concerns.
+    pub fn with_start_time(mut self, start_time: SystemTime) -> Self {
+        if let Some(recording) = &mut self.recording {
+            recording.data.start_time_unix_nano = unix_nanos(start_time);
+        }
+        self
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        if let Some(mut recording) = self.recording.take() {
+            recording.data.end_time_unix_nano = now_nanos();
+            // Drop the span rather than block the component if the exporter
+            // falls behind.
+            let _ = recording.sender.try_send(recording.data);
+        }
+    }
+}
+
+/// A finished span, as handed to the exporter.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+struct SpanData {
+    trace_id: String,
+    span_id: String,
+    name: &'static str,
+    start_time_unix_nano: u64,
+    end_time_unix_nano: u64,
+    attributes: BTreeMap<&'static str, String>,
+}
+
+fn sampling_threshold(sampling_rate: f64) -> u128 {
+    (sampling_rate.min(1.0) * 2f64.powi(64)) as u128
+}
+
+/// Spreads consecutive heights uniformly over the `u64` range, so that
+/// sampling by height does not trace runs of consecutive blocks only
+/// (the finalizer of SplitMix64).
+fn mix(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+fn now_nanos() -> u64 {
+    unix_nanos(SystemTime::now())
+}
+
+fn unix_nanos(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_config::tracing::Exporter;
+    use ic_logger::replica_logger::no_op_logger;
+    use ic_types::PrincipalId;
+    use std::io::BufRead;
+
+    fn message_id(byte: u8) -> MessageId {
+        MessageId::from([byte; 32])
+    }
+
+    fn tracer(config: &Config) -> Tracer {
+        let node_id = NodeId::from(PrincipalId::new_node_test_id(1));
+        let subnet_id = SubnetId::from(PrincipalId::new_subnet_test_id(2));
+        Tracer::new(config, node_id, subnet_id, no_op_logger()).unwrap()
+    }
+
+    #[test]
+    fn disabled_tracer_does_not_record() {
+        let tracer = Tracer::disabled();
+        assert!(!tracer.message_span("test", &message_id(0)).is_recording());
+        assert!(!tracer.height_span("test", Height::from(0)).is_recording());
+    }
+
+    #[test]
+    fn zero_sampling_rate_disables_tracing() {
+        let tracer = tracer(&Config::default());
+        assert!(!tracer.is_enabled());
+    }
+
+    #[test]
+    fn sampling_is_derived_from_key() {
+        let threshold = sampling_threshold(0.5);
+        assert_eq!(threshold, 1 << 63);
+        assert_eq!(sampling_threshold(1.0), 1 << 64);
+        assert_eq!(sampling_threshold(2.0), 1 << 64);
+        assert_eq!(sampling_threshold(0.0), 0);
+
+        let dir = tempfile::tempdir().unwrap();
+        let tracer = tracer(&Config {
+            sampling_rate: 0.5,
+            exporter: Exporter::JsonlFile(dir.path().join("spans.jsonl")),
+        });
+        // The first 8 bytes of the message ID decide whether it is sampled.
+        assert!(tracer
+            .message_span("test", &message_id(0x7f))
+            .is_recording());
+        assert!(!tracer
+            .message_span("test", &message_id(0x80))
+            .is_recording());
+    }
+
+    #[test]
+    fn spans_share_trace_id_of_their_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("spans.jsonl");
+        let tracer = tracer(&Config {
+            sampling_rate: 1.0,
+            exporter: Exporter::JsonlFile(path.clone()),
+        });
+
+        let first = tracer.message_span("first", &message_id(1));
+        let second = tracer
+            .message_span("second", &message_id(1))
+            .with_attribute("status", "ok");
+        let block = tracer.height_span("block", Height::from(7));
+        let spans: Vec<_> = vec![first, second, block]
+            .into_iter()
+            .map(|mut span| span.recording.take().unwrap().data)
+            .collect();
+
+        assert_eq!(spans[0].trace_id, hex::encode([1; 16]));
+        assert_eq!(spans[0].trace_id, spans[1].trace_id);
+        assert_ne!(spans[0].span_id, spans[1].span_id);
+        assert_eq!(spans[1].attributes["status"], "ok");
+        assert_eq!(spans[1].attributes["message_id"], message_id(1).to_string());
+        assert_eq!(spans[2].attributes["height"], "7");
+        assert!(spans[2]
+            .trace_id
+            .ends_with(&hex::encode(7u64.to_be_bytes())));
+    }
+
+    #[test]
+    fn span_can_be_backdated() {
+        let dir = tempfile::tempdir().unwrap();
+        let tracer = tracer(&Config {
+            sampling_rate: 1.0,
+            exporter: Exporter::JsonlFile(dir.path().join("spans.jsonl")),
+        });
+
+        let start_time = UNIX_EPOCH + std::time::Duration::from_secs(1);
+        let mut span = tracer
+            .message_span("test", &message_id(1))
+            .with_start_time(start_time);
+        let data = span.recording.take().unwrap().data;
+        assert_eq!(data.start_time_unix_nano, 1_000_000_000);
+
+        // Backdating a span that is not recording is a no-op.
+        assert!(!Tracer::disabled()
+            .message_span("test", &message_id(1))
+            .with_start_time(start_time)
+            .is_recording());
+    }
+
+    #[test]
+    fn jsonl_exporter_writes_one_span_per_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("spans.jsonl");
+        let tracer = tracer(&Config {
+            sampling_rate: 1.0,
+            exporter: Exporter::JsonlFile(path.clone()),
+        });
+
+        for i in 0..3 {
+            let _span = tracer.height_span("block", Height::from(i));
+        }
+        drop(tracer);
+
+        // The exporter flushes the remaining spans once the tracer is gone.
+        let mut lines = Vec::new();
+        for _ in 0..100 {
+            let file = std::fs::File::open(&path).unwrap();
+            lines = std::io::BufReader::new(file)
+                .lines()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+            if lines.len() == 3 {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+        assert_eq!(lines.len(), 3);
+        for line in lines {
+            let span: serde_json::Value = serde_json::from_str(&line).unwrap();
+            assert_eq!(span["name"], "block");
+            assert!(span["node_id"].is_string());
+            assert!(span["end_time_unix_nano"].as_u64() >= span["start_time_unix_nano"].as_u64());
+        }
+    }
+}
@@ -0,0 +1,176 @@
+//! The subset of the interface of the exchange rate canister that the cycles
+//! minting canister uses to fetch the ICP/XDR conversion rate.
+
+use crate::IcpXdrConversionRate;
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+use std::convert::{TryFrom, TryInto};
+
+/// The number of cycles attached to each call to the exchange rate canister.
+pub const EXCHANGE_RATE_CANISTER_FEE: u64 = 10_000_000_000;
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug, PartialEq, Eq)]
+pub enum AssetClass {
+    Cryptocurrency,
+    FiatCurrency,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug, PartialEq, Eq)]
+pub struct Asset {
+    pub symbol: String,
+    pub class: AssetClass,
+}
+
+impl Asset {
+    pub fn icp() -> Self {
+        Self {
+            symbol: "ICP".to_string(),
+            class: AssetClass::Cryptocurrency,
+        }
+    }
+
+    pub fn xdr() -> Self {
+        Self {
+            symbol: "XDR".to_string(),
+            class: AssetClass::FiatCurrency,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug, PartialEq, Eq)]
+pub struct GetExchangeRateRequest {
+    pub base_asset: Asset,
+    pub quote_asset: Asset,
+    /// The UNIX epoch time in seconds for which the rate is requested. If not
+    /// set, the most recent rate is returned.
+    pub timestamp: Option<u64>,
+}
+
+impl GetExchangeRateRequest {
+    /// Requests the most recent ICP/XDR rate.
+    pub fn icp_xdr() -> Self {
+        Self {
+            base_asset: Asset::icp(),
+            quote_asset: Asset::xdr(),
+            timestamp: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug, PartialEq, Eq)]
+pub struct ExchangeRateMetadata {
+    /// The number of decimal places of `ExchangeRate::rate`.
+    pub decimals: u32,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug, PartialEq, Eq)]
+pub struct ExchangeRate {
+    pub base_asset: Asset,
+    pub quote_asset: Asset,
+    /// The UNIX epoch time in seconds the rate was determined for.
+    pub timestamp: u64,
+    /// How many units of the quote asset one unit of the base asset is worth,
+    /// as a fixed point number with `metadata.decimals` decimal places.
+    pub rate: u64,
+    pub metadata: ExchangeRateMetadata,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug, PartialEq, Eq)]
+pub struct OtherError {
+    pub code: u32,
+    pub description: String,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug, PartialEq, Eq)]
+pub enum ExchangeRateError {
+    AnonymousPrincipalNotAllowed,
+    Pending,
+    CryptoBaseAssetNotFound,
+    CryptoQuoteAssetNotFound,
+    StablecoinRateNotFound,
+    StablecoinRateTooFewRates,
+    StablecoinRateZeroRate,
+    ForexInvalidTimestamp,
+    ForexBaseAssetNotFound,
+    ForexQuoteAssetNotFound,
+    ForexAssetsNotFound,
+    RateLimited,
+    NotEnoughCycles,
+    FailedToAcceptCycles,
+    InconsistentRatesReceived,
+    Other(OtherError),
+}
+
+pub type GetExchangeRateResult = Result<ExchangeRate, ExchangeRateError>;
+
+impl TryFrom<ExchangeRate> for IcpXdrConversionRate {
+    type Error = String;
+
+    fn try_from(rate: ExchangeRate) -> Result<Self, Self::Error> {
+        if rate.base_asset != Asset::icp() || rate.quote_asset != Asset::xdr() {
+            return Err(format!(
+                "Expected an ICP/XDR rate, got a {}/{} rate",
+                rate.base_asset.symbol, rate.quote_asset.symbol
+            ));
+        }
+        let decimals = 10u128
+            .checked_pow(rate.metadata.decimals)
+            .ok_or_else(|| format!("Invalid number of decimals: {}", rate.metadata.decimals))?;
+        let xdr_permyriad_per_icp: u64 = (rate.rate as u128 * 10_000 / decimals)
+            .try_into()
+            .map_err(|_| format!("The ICP/XDR rate {} is too large", rate.rate))?;
+        if xdr_permyriad_per_icp == 0 {
+            return Err(format!(
+                "The ICP/XDR rate {} with {} decimals is too small",
+                rate.rate, rate.metadata.decimals
+            ));
+        }
+        Ok(IcpXdrConversionRate {
+            timestamp_seconds: rate.timestamp,
+            xdr_permyriad_per_icp,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn icp_xdr_rate(rate: u64, decimals: u32) -> ExchangeRate {
+        ExchangeRate {
+            base_asset: Asset::icp(),
+            quote_asset: Asset::xdr(),
+            timestamp: 1_650_000_000,
+            rate,
+            metadata: ExchangeRateMetadata { decimals },
+        }
+    }
+
+    #[test]
+    fn test_convert_exchange_rate() {
+        assert_eq!(
+            IcpXdrConversionRate::try_from(icp_xdr_rate(5_123_456_789, 9)),
+            Ok(IcpXdrConversionRate {
+                timestamp_seconds: 1_650_000_000,
+                xdr_permyriad_per_icp: 51_234,
+            })
+        );
+        assert_eq!(
+            IcpXdrConversionRate::try_from(icp_xdr_rate(5, 0)),
+            Ok(IcpXdrConversionRate {
+                timestamp_seconds: 1_650_000_000,
+                xdr_permyriad_per_icp: 50_000,
+            })
+        );
+    }
+
+    #[test]
+    fn test_convert_invalid_exchange_rate() {
+        assert!(IcpXdrConversionRate::try_from(icp_xdr_rate(1, 9)).is_err());
+        assert!(IcpXdrConversionRate::try_from(icp_xdr_rate(u64::MAX, 0)).is_err());
+        assert!(IcpXdrConversionRate::try_from(icp_xdr_rate(1, 100)).is_err());
+        let mut rate = icp_xdr_rate(5_000_000_000, 9);
+        rate.quote_asset.symbol = "USD".to_string();
+        assert!(IcpXdrConversionRate::try_from(rate).is_err());
+    }
+}
@@ -6,6 +6,8 @@ use ledger_canister::{
 };
 use serde::{Deserialize, Serialize};
 
+pub mod exchange_rate_canister;
+
 pub const DEFAULT_CYCLES_PER_XDR: u128 = 1_000_000_000_000u128; // 1T cycles = 1 XDR
 
 pub const CREATE_CANISTER_REFUND_FEE: Tokens = Tokens::from_e8s(DEFAULT_TRANSFER_FEE.get_e8s() * 4);
@@ -17,6 +19,28 @@ pub struct CyclesCanisterInitPayload {
     pub governance_canister_id: CanisterId,
     pub minting_account_id: Option<AccountIdentifier>,
     pub last_purged_notification: Option<BlockHeight>,
+    /// The canister from which the ICP/XDR conversion rate is fetched
+    /// periodically. If not set, the rate is only updated by proposals.
+    pub exchange_rate_canister_id: Option<CanisterId>,
+}
+
+/// The argument of an upgrade of the cycles minting canister. The canister can
+/// also be upgraded without an argument, which keeps its configuration.
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug, PartialEq, Eq)]
+pub struct CyclesCanisterUpgradeArgs {
+    /// If set, changes the canister from which the ICP/XDR conversion rate is
+    /// fetched periodically.
+    pub exchange_rate_canister: Option<ExchangeRateCanister>,
+}
+
+/// Whether the ICP/XDR conversion rate is fetched from an exchange rate
+/// canister.
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug, PartialEq, Eq)]
+pub enum ExchangeRateCanister {
+    /// Fetch the rate from this canister.
+    Set(CanisterId),
+    /// Only update the rate by proposals.
+    Unset,
 }
 
 /// Argument taken by top up notification endpoint
@@ -31,6 +55,40 @@ pub struct NotifyTopUp {
 pub struct NotifyCreateCanister {
     pub block_index: BlockHeight,
     pub controller: PrincipalId,
+    /// Where to create the canister. If not set, the canister is created on a
+    /// random subnet that the controller is authorized to use.
+    pub subnet_selection: Option<SubnetSelection>,
+    /// The settings of the new canister. If no controllers are set, the
+    /// `controller` is the only controller of the new canister.
+    pub settings: Option<CanisterSettings>,
+}
+
+/// Selects the subnet on which a canister is created.
+#[derive(Serialize, Deserialize, CandidType, Clone, Hash, Debug, PartialEq, Eq)]
+pub enum SubnetSelection {
+    /// Create the canister on this subnet. The subnet must be authorized for
+    /// the controller or be assigned a subnet type.
+    Subnet { subnet: SubnetId },
+    /// Create the canister on a random subnet matching the filter.
+    Filter(SubnetFilter),
+}
+
+/// Filters the subnets a canister may be created on.
+#[derive(Serialize, Deserialize, CandidType, Clone, Hash, Debug, PartialEq, Eq)]
+pub struct SubnetFilter {
+    /// Only consider the subnets of this type. If not set, the subnets that
+    /// the controller is authorized to use are considered.
+    pub subnet_type: Option<String>,
+}
+
+/// The settings of a canister created by the cycles minting canister. Unset
+/// settings take the default value of the management canister.
+#[derive(Serialize, Deserialize, CandidType, Clone, Hash, Debug, Default, PartialEq, Eq)]
+pub struct CanisterSettings {
+    pub controllers: Option<Vec<PrincipalId>>,
+    pub compute_allocation: Option<candid::Nat>,
+    pub memory_allocation: Option<candid::Nat>,
+    pub freezing_threshold: Option<candid::Nat>,
 }
 
 /// Error for notify endpoints
@@ -175,6 +233,39 @@ pub struct RemoveSubnetFromAuthorizedSubnetListArgs {
     pub subnet: SubnetId,
 }
 
+/// Argument taken by the update_subnet_type endpoint
+#[derive(Serialize, Deserialize, CandidType, Clone, Hash, Debug, PartialEq, Eq)]
+pub enum UpdateSubnetTypeArgs {
+    /// Adds a new subnet type, without any subnets.
+    Add(String),
+    /// Removes a subnet type, which must not have any subnets assigned.
+    Remove(String),
+}
+
+/// A list of subnets along with the subnet type they are (un)assigned.
+#[derive(Serialize, Deserialize, CandidType, Clone, Hash, Debug, PartialEq, Eq)]
+pub struct SubnetListWithType {
+    pub subnets: Vec<SubnetId>,
+    pub subnet_type: String,
+}
+
+/// Argument taken by the change_subnet_type_assignment endpoint
+#[derive(Serialize, Deserialize, CandidType, Clone, Hash, Debug, PartialEq, Eq)]
+pub enum ChangeSubnetTypeAssignmentArgs {
+    /// Assigns the subnets the subnet type. A subnet can have at most one type
+    /// and must not be one of the default subnets.
+    Add(SubnetListWithType),
+    /// Removes the subnets from the subnet type.
+    Remove(SubnetListWithType),
+}
+
+/// The response of the get_subnet_types_to_subnets endpoint, which lists the
+/// subnet types along with the subnets assigned to each of them.
+#[derive(Serialize, Deserialize, CandidType, Clone, Hash, Debug, Default, PartialEq, Eq)]
+pub struct SubnetTypesToSubnetsResponse {
+    pub data: Vec<(String, Vec<SubnetId>)>,
+}
+
 #[derive(Serialize, Deserialize, CandidType, Clone, PartialEq, Eq, Debug, Default)]
 pub struct IcpXdrConversionRate {
     /// The time for which the market data was queried, expressed in UNIX epoch
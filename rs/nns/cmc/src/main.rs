@@ -1,13 +1,16 @@
-use std::collections::{btree_map::Entry, BTreeMap};
-use std::convert::TryInto;
+use std::collections::{btree_map::Entry, BTreeMap, BTreeSet};
+use std::convert::{TryFrom, TryInto};
 use std::sync::RwLock;
 use std::time::{Duration, UNIX_EPOCH};
 
 use candid::{candid_method, CandidType, Encode};
+use cycles_minting_canister::exchange_rate_canister::{
+    GetExchangeRateRequest, GetExchangeRateResult, EXCHANGE_RATE_CANISTER_FEE,
+};
 use cycles_minting_canister::*;
 use dfn_candid::{candid_one, CandidOne};
 use dfn_core::{
-    api::{call_with_cleanup, caller, set_certified_data},
+    api::{call_with_cleanup, call_with_funds_and_cleanup, caller, set_certified_data, Funds},
     over, over_async, over_init, stable, BytesS,
 };
 use dfn_protobuf::protobuf;
@@ -37,6 +40,15 @@ const NUM_DAYS_FOR_ICP_XDR_AVERAGE: usize = 30;
 pub const LABEL_ICP_XDR_CONVERSION_RATE: &[u8] = b"ICP_XDR_CONVERSION_RATE";
 pub const LABEL_AVERAGE_ICP_XDR_CONVERSION_RATE: &[u8] = b"AVERAGE_ICP_XDR_CONVERSION_RATE";
 
+/// How often the ICP/XDR rate is fetched from the exchange rate canister.
+const EXCHANGE_RATE_FETCH_INTERVAL_SECONDS: u64 = 5 * 60;
+/// The maximum number of rates fetched from the exchange rate canister to
+/// store, which covers a day.
+const MAX_ICP_XDR_RATE_SAMPLES: usize = 288;
+/// The number of the most recently fetched rates whose median is used as the
+/// ICP/XDR rate.
+const NUM_ICP_XDR_RATE_SAMPLES_FOR_MEDIAN: usize = 5;
+
 /// The maximum number of notification statuses to store.
 const MAX_NOTIFY_HISTORY: usize = 1_000_000;
 /// The maximum number of old notification statuses we purge in one go.
@@ -65,6 +77,11 @@ struct State {
 
     default_subnets: Vec<SubnetId>,
 
+    /// The subnets that users can select to create canisters on by their
+    /// type, indexed by the type. A subnet has at most one type and is not
+    /// one of the default subnets.
+    subnet_types_to_subnets: Option<BTreeMap<String, BTreeSet<SubnetId>>>,
+
     /// How many XDR 1 ICP is worth, along with a timestamp.
     icp_xdr_conversion_rate: Option<IcpXdrConversionRate>,
 
@@ -77,6 +94,21 @@ struct State {
     /// The recent ICP/XDR rates used to compute the average rate.
     recent_icp_xdr_rates: Option<Vec<IcpXdrConversionRate>>,
 
+    /// The canister from which the ICP/XDR rate is fetched every
+    /// `EXCHANGE_RATE_FETCH_INTERVAL_SECONDS`. If not set, the rate is only
+    /// updated by proposals.
+    exchange_rate_canister_id: Option<CanisterId>,
+
+    /// The ICP/XDR rates fetched from the exchange rate canister, oldest
+    /// first.
+    icp_xdr_rate_samples: Option<Vec<IcpXdrConversionRate>>,
+
+    /// When the ICP/XDR rate was last fetched, in seconds since the UNIX epoch.
+    last_exchange_rate_fetch_seconds: Option<u64>,
+
+    /// How many times fetching the ICP/XDR rate failed.
+    exchange_rate_fetch_failures: Option<u64>,
+
     /// How many cycles 1 XDR is worth.
     cycles_per_xdr: Cycles,
 
@@ -103,12 +135,17 @@ impl State {
             minting_account_id: None,
             authorized_subnets: BTreeMap::new(),
             default_subnets: vec![],
+            subnet_types_to_subnets: Some(BTreeMap::new()),
             icp_xdr_conversion_rate: None,
             average_icp_xdr_conversion_rate: None,
             recent_icp_xdr_rates: Some(vec![
                 IcpXdrConversionRate::default();
                 NUM_DAYS_FOR_ICP_XDR_AVERAGE
             ]),
+            exchange_rate_canister_id: None,
+            icp_xdr_rate_samples: Some(vec![]),
+            last_exchange_rate_fetch_seconds: None,
+            exchange_rate_fetch_failures: Some(0),
             cycles_per_xdr: DEFAULT_CYCLES_PER_XDR.into(),
             cycles_limit: 50_000_000_000_000_000u128.into(), // == 50 Pcycles/hour
             limiter: limiter::Limiter::new(resolution, max_age),
@@ -118,13 +155,39 @@ impl State {
         }
     }
 
+    /// Returns the type of `subnet`, if it has one.
+    fn subnet_type_of(&self, subnet: &SubnetId) -> Option<&String> {
+        self.subnet_types_to_subnets
+            .as_ref()
+            .unwrap()
+            .iter()
+            .find(|(_, subnets)| subnets.contains(subnet))
+            .map(|(subnet_type, _)| subnet_type)
+    }
+
+    /// Returns the subnets on which `controller_id` is authorized to create
+    /// canisters without selecting a subnet.
+    fn authorized_subnets_for(&self, controller_id: &PrincipalId) -> &Vec<SubnetId> {
+        self.authorized_subnets
+            .get(controller_id)
+            .unwrap_or(&self.default_subnets)
+    }
+
     fn encode(&self) -> Vec<u8> {
         candid::encode_one(&self).unwrap()
     }
 
     fn decode(bytes: &[u8]) -> Result<Self, String> {
-        candid::decode_one(bytes)
-            .map_err(|err| format!("Decoding cycles minting canister state failed: {}", err))
+        let mut state: Self = candid::decode_one(bytes)
+            .map_err(|err| format!("Decoding cycles minting canister state failed: {}", err))?;
+        // The state of older versions does not have subnet types and fetched
+        // ICP/XDR rates.
+        state
+            .subnet_types_to_subnets
+            .get_or_insert_with(BTreeMap::new);
+        state.icp_xdr_rate_samples.get_or_insert_with(Vec::new);
+        state.exchange_rate_fetch_failures.get_or_insert(0);
+        Ok(state)
     }
 
     // Keep the size of blocks_notified map not larger than max_history.
@@ -187,6 +250,7 @@ fn init(args: CyclesCanisterInitPayload) {
     state.governance_canister_id = args.governance_canister_id;
     state.minting_account_id = args.minting_account_id;
     state.last_purged_notification = args.last_purged_notification;
+    state.exchange_rate_canister_id = args.exchange_rate_canister_id;
 }
 
 ic_nervous_system_common_build_metadata::define_get_build_metadata_candid_method! {}
@@ -196,7 +260,13 @@ fn set_authorized_subnetwork_list_() {
     over(
         candid_one,
         |SetAuthorizedSubnetworkListArgs { who, subnets }| {
-            set_authorized_subnetwork_list(who, subnets)
+            let mut state = STATE.write().unwrap();
+            if CanisterId::new(caller()) != Ok(state.governance_canister_id) {
+                panic!("Only the governance canister can set authorized subnetwork lists.");
+            }
+            if let Err(err) = set_authorized_subnetwork_list(&mut state, who, subnets) {
+                panic!("{}", err);
+            }
         },
     )
 }
@@ -204,13 +274,26 @@ fn set_authorized_subnetwork_list_() {
 /// Set the list of subnets in which a principal is allowed to create
 /// canisters. If `subnets` is empty, remove the mapping for a
 /// principal. If `who` is None, set the default list of subnets.
-fn set_authorized_subnetwork_list(who: Option<PrincipalId>, subnets: Vec<SubnetId>) {
-    let mut state = STATE.write().unwrap();
-
-    let governance_canister_id = state.governance_canister_id;
-
-    if CanisterId::new(caller()) != Ok(governance_canister_id) {
-        panic!("Only the governance canister can set authorized subnetwork lists.");
+/// Subnets that have a subnet type can't be in any of these lists.
+fn set_authorized_subnetwork_list(
+    state: &mut State,
+    who: Option<PrincipalId>,
+    subnets: Vec<SubnetId>,
+) -> Result<(), String> {
+    if let Some(subnet) = subnets
+        .iter()
+        .find(|subnet| state.subnet_type_of(subnet).is_some())
+    {
+        return Err(match who {
+            Some(who) => format!(
+                "Subnet {} has a subnet type and cannot be authorized for {}.",
+                subnet, who
+            ),
+            None => format!(
+                "Subnet {} has a subnet type and cannot be a default subnet.",
+                subnet
+            ),
+        });
     }
 
     if let Some(who) = who {
@@ -225,6 +308,172 @@ fn set_authorized_subnetwork_list(who: Option<PrincipalId>, subnets: Vec<SubnetI
         print("[cycles] setting default subnet list");
         state.default_subnets = subnets;
     }
+    Ok(())
+}
+
+#[export_name = "canister_update update_subnet_type"]
+fn update_subnet_type_() {
+    over(candid_one, |args: UpdateSubnetTypeArgs| {
+        let mut state = STATE.write().unwrap();
+        if CanisterId::new(caller()) != Ok(state.governance_canister_id) {
+            panic!("Only the governance canister can update subnet types.");
+        }
+        if let Err(err) = update_subnet_type(&mut state, args) {
+            panic!("{}", err);
+        }
+    })
+}
+
+/// Adds or removes a subnet type. Only types without any subnets can be
+/// removed.
+fn update_subnet_type(state: &mut State, args: UpdateSubnetTypeArgs) -> Result<(), String> {
+    let subnet_types_to_subnets = state.subnet_types_to_subnets.as_mut().unwrap();
+    match args {
+        UpdateSubnetTypeArgs::Add(subnet_type) => {
+            if subnet_type.is_empty() {
+                return Err("The subnet type must not be empty.".to_string());
+            }
+            if subnet_types_to_subnets.contains_key(&subnet_type) {
+                return Err(format!("Subnet type {} already exists.", subnet_type));
+            }
+            print(format!("[cycles] adding subnet type {}", subnet_type));
+            subnet_types_to_subnets.insert(subnet_type, BTreeSet::new());
+        }
+        UpdateSubnetTypeArgs::Remove(subnet_type) => {
+            match subnet_types_to_subnets.get(&subnet_type) {
+                None => return Err(format!("Subnet type {} does not exist.", subnet_type)),
+                Some(subnets) if !subnets.is_empty() => {
+                    return Err(format!(
+                        "Subnet type {} cannot be removed while subnets are assigned to it.",
+                        subnet_type
+                    ))
+                }
+                Some(_) => (),
+            }
+            print(format!("[cycles] removing subnet type {}", subnet_type));
+            subnet_types_to_subnets.remove(&subnet_type);
+        }
+    }
+    Ok(())
+}
+
+#[export_name = "canister_update change_subnet_type_assignment"]
+fn change_subnet_type_assignment_() {
+    over(candid_one, |args: ChangeSubnetTypeAssignmentArgs| {
+        let mut state = STATE.write().unwrap();
+        if CanisterId::new(caller()) != Ok(state.governance_canister_id) {
+            panic!("Only the governance canister can change subnet type assignments.");
+        }
+        if let Err(err) = change_subnet_type_assignment(&mut state, args) {
+            panic!("{}", err);
+        }
+    })
+}
+
+/// Assigns subnets a subnet type, or removes them from it. Either all
+/// subnets are (un)assigned, or none if one of them can't be.
+fn change_subnet_type_assignment(
+    state: &mut State,
+    args: ChangeSubnetTypeAssignmentArgs,
+) -> Result<(), String> {
+    match args {
+        ChangeSubnetTypeAssignmentArgs::Add(SubnetListWithType {
+            subnets,
+            subnet_type,
+        }) => {
+            if !state
+                .subnet_types_to_subnets
+                .as_ref()
+                .unwrap()
+                .contains_key(&subnet_type)
+            {
+                return Err(format!("Subnet type {} does not exist.", subnet_type));
+            }
+            for subnet in &subnets {
+                if state.default_subnets.contains(subnet) {
+                    return Err(format!(
+                        "Subnet {} is a default subnet and cannot have a subnet type.",
+                        subnet
+                    ));
+                }
+                if let Some((who, _)) = state
+                    .authorized_subnets
+                    .iter()
+                    .find(|(_, authorized_subnets)| authorized_subnets.contains(subnet))
+                {
+                    return Err(format!(
+                        "Subnet {} is authorized for {} and cannot have a subnet type.",
+                        subnet, who
+                    ));
+                }
+                if let Some(current_type) = state.subnet_type_of(subnet) {
+                    return Err(format!(
+                        "Subnet {} is already assigned subnet type {}.",
+                        subnet, current_type
+                    ));
+                }
+            }
+            print(format!(
+                "[cycles] assigning subnet type {} to subnets {:?}",
+                subnet_type, subnets
+            ));
+            state
+                .subnet_types_to_subnets
+                .as_mut()
+                .unwrap()
+                .get_mut(&subnet_type)
+                .unwrap()
+                .extend(subnets);
+        }
+        ChangeSubnetTypeAssignmentArgs::Remove(SubnetListWithType {
+            subnets,
+            subnet_type,
+        }) => {
+            let assigned_subnets = state
+                .subnet_types_to_subnets
+                .as_mut()
+                .unwrap()
+                .get_mut(&subnet_type)
+                .ok_or_else(|| format!("Subnet type {} does not exist.", subnet_type))?;
+            if let Some(subnet) = subnets
+                .iter()
+                .find(|subnet| !assigned_subnets.contains(subnet))
+            {
+                return Err(format!(
+                    "Subnet {} is not assigned subnet type {}.",
+                    subnet, subnet_type
+                ));
+            }
+            print(format!(
+                "[cycles] removing subnet type {} from subnets {:?}",
+                subnet_type, subnets
+            ));
+            for subnet in &subnets {
+                assigned_subnets.remove(subnet);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[candid_method(query, rename = "get_subnet_types_to_subnets")]
+fn get_subnet_types_to_subnets() -> SubnetTypesToSubnetsResponse {
+    let state = STATE.read().unwrap();
+    SubnetTypesToSubnetsResponse {
+        data: state
+            .subnet_types_to_subnets
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|(subnet_type, subnets)| (subnet_type.clone(), subnets.iter().cloned().collect()))
+            .collect(),
+    }
+}
+
+/// Returns the subnet types along with the subnets assigned to each of them.
+#[export_name = "canister_query get_subnet_types_to_subnets"]
+fn get_subnet_types_to_subnets_() {
+    over(candid_one, |_: ()| get_subnet_types_to_subnets())
 }
 
 /// Constructs a hash tree that can be used to certify requests for the
@@ -456,6 +705,133 @@ fn set_icp_xdr_conversion_rate(
     Ok(())
 }
 
+#[candid_method(query, rename = "get_icp_xdr_rate_samples")]
+fn get_icp_xdr_rate_samples() -> Vec<IcpXdrConversionRate> {
+    STATE.read().unwrap().icp_xdr_rate_samples.clone().unwrap()
+}
+
+/// Returns the ICP/XDR rates fetched from the exchange rate canister.
+#[export_name = "canister_query get_icp_xdr_rate_samples"]
+fn get_icp_xdr_rate_samples_() {
+    over(candid_one, |_: ()| get_icp_xdr_rate_samples())
+}
+
+#[export_name = "canister_heartbeat"]
+fn canister_heartbeat() {
+    let now_seconds = dfn_core::api::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let exchange_rate_canister_id =
+        start_exchange_rate_fetch(&mut STATE.write().unwrap(), now_seconds);
+
+    // canister_heartbeat must be synchronous, so we cannot .await the future
+    if let Some(exchange_rate_canister_id) = exchange_rate_canister_id {
+        dfn_core::api::futures::spawn(fetch_icp_xdr_rate(exchange_rate_canister_id));
+    }
+}
+
+/// Returns the canister to fetch the ICP/XDR rate from if the rate is due to
+/// be fetched at `now_seconds`, recording the attempt.
+fn start_exchange_rate_fetch(state: &mut State, now_seconds: u64) -> Option<CanisterId> {
+    let exchange_rate_canister_id = state.exchange_rate_canister_id?;
+    if let Some(last_fetch_seconds) = state.last_exchange_rate_fetch_seconds {
+        if now_seconds < last_fetch_seconds + EXCHANGE_RATE_FETCH_INTERVAL_SECONDS {
+            return None;
+        }
+    }
+    state.last_exchange_rate_fetch_seconds = Some(now_seconds);
+    Some(exchange_rate_canister_id)
+}
+
+/// Fetches the current ICP/XDR rate from the exchange rate canister and sets
+/// the median of the recently fetched rates as the ICP/XDR rate. If fetching
+/// fails, the current rate, which may have been set by a proposal, is kept.
+async fn fetch_icp_xdr_rate(exchange_rate_canister_id: CanisterId) {
+    let result: Result<GetExchangeRateResult, _> = call_with_funds_and_cleanup(
+        exchange_rate_canister_id,
+        "get_exchange_rate",
+        candid_one,
+        GetExchangeRateRequest::icp_xdr(),
+        Funds::new(EXCHANGE_RATE_CANISTER_FEE),
+    )
+    .await;
+
+    let rate = match result {
+        Ok(Ok(rate)) => IcpXdrConversionRate::try_from(rate),
+        Ok(Err(err)) => Err(format!("The exchange rate canister returned {:?}", err)),
+        Err((code, msg)) => Err(format!(
+            "Calling the exchange rate canister failed with code {}: {}",
+            code.unwrap_or_default(),
+            msg
+        )),
+    };
+
+    let mut state = STATE.write().unwrap();
+    let rate = match rate {
+        Ok(rate) => rate,
+        Err(err) => {
+            print(format!(
+                "[cycles] fetching the ICP/XDR rate failed, keeping the current rate: {}",
+                err
+            ));
+            *state.exchange_rate_fetch_failures.as_mut().unwrap() += 1;
+            return;
+        }
+    };
+
+    if let Some(median_rate) = add_icp_xdr_rate_sample(&mut state, rate) {
+        update_recent_icp_xdr_rates(&median_rate, &mut state);
+        if let Err(err) = set_icp_xdr_conversion_rate(median_rate, &mut state) {
+            print(format!(
+                "[cycles] setting the fetched ICP/XDR rate failed: {}",
+                err
+            ));
+        }
+    }
+}
+
+/// Records `sample` fetched from the exchange rate canister and returns the
+/// ICP/XDR rate to set: the median of the
+/// `NUM_ICP_XDR_RATE_SAMPLES_FOR_MEDIAN` most recent samples, with the
+/// timestamp of `sample`. Returns `None` if `sample` is not more recent than
+/// the last one.
+fn add_icp_xdr_rate_sample(
+    state: &mut State,
+    sample: IcpXdrConversionRate,
+) -> Option<IcpXdrConversionRate> {
+    let samples = state.icp_xdr_rate_samples.as_mut().unwrap();
+    if let Some(last_sample) = samples.last() {
+        if sample.timestamp_seconds <= last_sample.timestamp_seconds {
+            return None;
+        }
+    }
+    let timestamp_seconds = sample.timestamp_seconds;
+    samples.push(sample);
+    if samples.len() > MAX_ICP_XDR_RATE_SAMPLES {
+        samples.drain(..samples.len() - MAX_ICP_XDR_RATE_SAMPLES);
+    }
+
+    let mut recent_rates: Vec<u64> = samples
+        .iter()
+        .rev()
+        .take(NUM_ICP_XDR_RATE_SAMPLES_FOR_MEDIAN)
+        .map(|rate| rate.xdr_permyriad_per_icp)
+        .collect();
+    recent_rates.sort_unstable();
+    let middle = recent_rates.len() / 2;
+    let xdr_permyriad_per_icp = if recent_rates.len() % 2 == 0 {
+        ((recent_rates[middle - 1] as u128 + recent_rates[middle] as u128) / 2) as u64
+    } else {
+        recent_rates[middle]
+    };
+
+    Some(IcpXdrConversionRate {
+        timestamp_seconds,
+        xdr_permyriad_per_icp,
+    })
+}
+
 #[export_name = "canister_update remove_subnet_from_authorized_subnet_list"]
 fn remove_subnet_from_authorized_subnet_list_() {
     let caller = caller();
@@ -481,6 +857,14 @@ fn remove_subnet_from_authorized_subnet_list(subnet_to_remove: SubnetId) {
         .values_mut()
         .into_iter()
         .for_each(|subnet_list| subnet_list.retain(|subnet| *subnet != subnet_to_remove));
+    state
+        .subnet_types_to_subnets
+        .as_mut()
+        .unwrap()
+        .values_mut()
+        .for_each(|subnets| {
+            subnets.remove(&subnet_to_remove);
+        });
 }
 
 /// Wrapper around over_async_may_reject that requires the future to
@@ -588,11 +972,16 @@ async fn notify_top_up(
 /// * `block_height` -  The height of the block you would like to send a
 ///   notification about.
 /// * `controller` - PrincipalId of the canister controller.
+/// * `subnet_selection` - Where to create the canister, if not on a random
+///   authorized subnet.
+/// * `settings` - The settings of the new canister.
 #[candid_method(update, rename = "notify_create_canister")]
 async fn notify_create_canister(
     NotifyCreateCanister {
         block_index,
         controller,
+        subnet_selection,
+        settings,
     }: NotifyCreateCanister,
 ) -> Result<CanisterId, NotifyError> {
     let cmc_id = dfn_core::api::id();
@@ -627,7 +1016,8 @@ async fn notify_create_canister(
         }
     }
 
-    let result = process_create_canister(controller, from, amount).await;
+    let result =
+        process_create_canister(controller, from, amount, subnet_selection, settings).await;
 
     let notified: &mut Option<BTreeMap<_, _>> = &mut STATE.write().unwrap().blocks_notified;
     notified.as_mut().unwrap().insert(
@@ -797,7 +1187,7 @@ async fn transaction_notification(tn: TransactionNotification) -> Result<CyclesR
             .ok_or_else(|| "Reserving requires a principal.".to_string())?)
             .try_into()
             .map_err(|err| format!("Cannot parse subaccount: {}", err))?;
-        match process_create_canister(controller, from, tn.amount).await {
+        match process_create_canister(controller, from, tn.amount, None, None).await {
             Ok(canister_id) => (
                 Ok(CyclesResponse::CanisterCreated(canister_id)),
                 Some(NotificationStatus::NotifiedCreateCanister(Ok(canister_id))),
@@ -889,6 +1279,8 @@ async fn process_create_canister(
     controller: PrincipalId,
     from: AccountIdentifier,
     amount: Tokens,
+    subnet_selection: Option<SubnetSelection>,
+    settings: Option<CanisterSettings>,
 ) -> Result<CanisterId, NotifyError> {
     let cycles = tokens_to_cycles(amount)?;
 
@@ -902,7 +1294,7 @@ async fn process_create_canister(
     // Create the canister. If this fails, refund. Either way,
     // return a result so that the notification cannot be retried.
     // If refund fails, we allow to retry.
-    match create_canister(controller, cycles).await {
+    match create_canister(controller, cycles, subnet_selection, settings).await {
         Ok(canister_id) => {
             burn_and_log(sub, amount).await;
             Ok(canister_id)
@@ -1063,8 +1455,14 @@ async fn deposit_cycles(canister_id: CanisterId, cycles: Cycles) -> Result<(), S
     Ok(())
 }
 
-async fn create_canister(controller_id: PrincipalId, cycles: Cycles) -> Result<CanisterId, String> {
-    let subnets = get_permuted_subnets_for(&controller_id).await?;
+async fn create_canister(
+    controller_id: PrincipalId,
+    cycles: Cycles,
+    subnet_selection: Option<SubnetSelection>,
+    settings: Option<CanisterSettings>,
+) -> Result<CanisterId, String> {
+    let subnets = get_permuted_subnets_for(&controller_id, subnet_selection.as_ref()).await?;
+    let settings = canister_settings_args(controller_id, settings);
 
     let mut last_err = None;
 
@@ -1080,10 +1478,7 @@ async fn create_canister(controller_id: PrincipalId, cycles: Cycles) -> Result<C
             &Method::CreateCanister.to_string(),
             dfn_candid::candid_one,
             CreateCanisterArgs {
-                settings: Some(CanisterSettingsArgs {
-                    controller: Some(controller_id),
-                    ..CanisterSettingsArgs::default()
-                }),
+                settings: Some(settings.clone()),
             },
             dfn_core::api::Funds::new(cycles.get().try_into().unwrap()),
         )
@@ -1156,17 +1551,64 @@ fn total_supply_() {
     })
 }
 
+/// Returns the settings of a canister created for `controller_id`, which is
+/// its only controller unless `settings` specifies the controllers.
+fn canister_settings_args(
+    controller_id: PrincipalId,
+    settings: Option<CanisterSettings>,
+) -> CanisterSettingsArgs {
+    let settings = settings.unwrap_or_default();
+    CanisterSettingsArgs {
+        controller: None,
+        controllers: Some(settings.controllers.unwrap_or_else(|| vec![controller_id])),
+        compute_allocation: settings.compute_allocation,
+        memory_allocation: settings.memory_allocation,
+        freezing_threshold: settings.freezing_threshold,
+    }
+}
+
 /// Return the list of subnets in which this controller is allowed to create
-/// canisters
-async fn get_permuted_subnets_for(controller_id: &PrincipalId) -> Result<Vec<SubnetId>, String> {
-    let mut subnets = {
-        let state = STATE.read().unwrap();
-        if let Some(subnets) = state.authorized_subnets.get(controller_id) {
-            subnets.clone()
-        } else {
-            state.default_subnets.clone()
+/// canisters, restricted according to `subnet_selection`.
+fn get_subnets_for(
+    state: &State,
+    controller_id: &PrincipalId,
+    subnet_selection: Option<&SubnetSelection>,
+) -> Result<Vec<SubnetId>, String> {
+    match subnet_selection {
+        Some(SubnetSelection::Subnet { subnet }) => {
+            if state.authorized_subnets_for(controller_id).contains(subnet)
+                || state.subnet_type_of(subnet).is_some()
+            {
+                Ok(vec![*subnet])
+            } else {
+                Err(format!(
+                    "Subnet {} is not authorized for creating canisters controlled by {}.",
+                    subnet, controller_id
+                ))
+            }
         }
-    };
+        Some(SubnetSelection::Filter(SubnetFilter {
+            subnet_type: Some(subnet_type),
+        })) => state
+            .subnet_types_to_subnets
+            .as_ref()
+            .unwrap()
+            .get(subnet_type)
+            .map(|subnets| subnets.iter().cloned().collect())
+            .ok_or_else(|| format!("Subnet type {} does not exist.", subnet_type)),
+        Some(SubnetSelection::Filter(SubnetFilter { subnet_type: None })) | None => {
+            Ok(state.authorized_subnets_for(controller_id).clone())
+        }
+    }
+}
+
+/// Return the list of subnets in which this controller is allowed to create
+/// canisters, restricted according to `subnet_selection`, in random order.
+async fn get_permuted_subnets_for(
+    controller_id: &PrincipalId,
+    subnet_selection: Option<&SubnetSelection>,
+) -> Result<Vec<SubnetId>, String> {
+    let mut subnets = get_subnets_for(&STATE.read().unwrap(), controller_id, subnet_selection)?;
 
     let mut rng = get_rng().await?;
     subnets.shuffle(&mut rng);
@@ -1210,17 +1652,43 @@ fn pre_upgrade() {
 
 #[export_name = "canister_post_upgrade"]
 fn post_upgrade() {
-    over_init(|_: BytesS| {
+    over_init(|BytesS(args)| {
         let bytes = stable::get();
         print(format!(
             "[cycles] deserializing state after upgrade ({} bytes)",
             bytes.len(),
         ));
 
-        *STATE.write().unwrap() = State::decode(&bytes).unwrap();
+        *STATE.write().unwrap() = post_upgrade_state(&bytes, &args).unwrap();
     })
 }
 
+/// Restores the state from its serialized form `bytes` and applies the
+/// upgrade `args`, which are either empty or an optional, candid encoded
+/// `CyclesCanisterUpgradeArgs`.
+fn post_upgrade_state(bytes: &[u8], args: &[u8]) -> Result<State, String> {
+    let mut state = State::decode(bytes)?;
+    if args.is_empty() {
+        return Ok(state);
+    }
+    let args: Option<CyclesCanisterUpgradeArgs> = candid::decode_one(args)
+        .map_err(|err| format!("Decoding the upgrade args failed: {}", err))?;
+    if let Some(CyclesCanisterUpgradeArgs {
+        exchange_rate_canister: Some(exchange_rate_canister),
+    }) = args
+    {
+        state.exchange_rate_canister_id = match exchange_rate_canister {
+            ExchangeRateCanister::Set(canister_id) => Some(canister_id),
+            ExchangeRateCanister::Unset => None,
+        };
+        print(format!(
+            "[cycles] setting the exchange rate canister to {:?}",
+            state.exchange_rate_canister_id
+        ));
+    }
+    Ok(state)
+}
+
 #[export_name = "canister_query http_request"]
 fn http_request() {
     dfn_http_metrics::serve_metrics(encode_metrics);
@@ -1248,6 +1716,16 @@ fn encode_metrics(w: &mut ic_metrics_encoder::MetricsEncoder<Vec<u8>>) -> std::i
             / 10_000f64,
         "Amount of XDR corresponding to 1 ICP.",
     )?;
+    w.encode_gauge(
+        "cmc_icp_xdr_rate_samples_count",
+        state.icp_xdr_rate_samples.as_ref().unwrap().len() as f64,
+        "Number of ICP/XDR rates fetched from the exchange rate canister that are stored.",
+    )?;
+    w.encode_counter(
+        "cmc_exchange_rate_fetch_failures_total",
+        state.exchange_rate_fetch_failures.unwrap() as f64,
+        "Number of times fetching the ICP/XDR rate from the exchange rate canister failed.",
+    )?;
     w.encode_gauge(
         "cmc_cycles_per_xdr",
         state.cycles_per_xdr.get() as f64,
@@ -1278,6 +1756,12 @@ mod tests {
             vec![SubnetId::from(PrincipalId::new_subnet_test_id(3))],
         );
         state.default_subnets = vec![SubnetId::from(PrincipalId::new_subnet_test_id(123))];
+        state.subnet_types_to_subnets.as_mut().unwrap().insert(
+            "fiduciary".to_string(),
+            vec![SubnetId::from(PrincipalId::new_subnet_test_id(5))]
+                .into_iter()
+                .collect(),
+        );
         state.total_cycles_minted = 1234.into();
         state.last_purged_notification = Some(33);
         let mut blocks_notified = BTreeMap::new();
@@ -1303,6 +1787,265 @@ mod tests {
         assert_eq!(state, state2);
     }
 
+    #[test]
+    fn test_post_upgrade_sets_exchange_rate_canister() {
+        let mut state = State::default();
+        state.exchange_rate_canister_id = Some(CanisterId::from_u64(42));
+        let bytes = state.encode();
+        let upgrade = |args: &[u8]| {
+            post_upgrade_state(&bytes, args)
+                .unwrap()
+                .exchange_rate_canister_id
+        };
+        let args = |exchange_rate_canister| {
+            Encode!(&Some(CyclesCanisterUpgradeArgs {
+                exchange_rate_canister
+            }))
+            .unwrap()
+        };
+
+        // Without upgrade args, the exchange rate canister is kept.
+        assert_eq!(upgrade(&[]), Some(CanisterId::from_u64(42)));
+        assert_eq!(
+            upgrade(&Encode!(&None::<CyclesCanisterUpgradeArgs>).unwrap()),
+            Some(CanisterId::from_u64(42))
+        );
+        assert_eq!(upgrade(&args(None)), Some(CanisterId::from_u64(42)));
+
+        assert_eq!(
+            upgrade(&args(Some(ExchangeRateCanister::Set(
+                CanisterId::from_u64(43)
+            )))),
+            Some(CanisterId::from_u64(43))
+        );
+        assert_eq!(upgrade(&args(Some(ExchangeRateCanister::Unset))), None);
+        assert!(post_upgrade_state(&bytes, b"garbage").is_err());
+    }
+
+    #[test]
+    fn test_state_without_subnet_types_decodes() {
+        let mut state = State::default();
+        state.subnet_types_to_subnets = None;
+
+        let state = State::decode(&state.encode()).unwrap();
+
+        assert_eq!(state.subnet_types_to_subnets, Some(BTreeMap::new()));
+    }
+
+    #[test]
+    fn test_state_without_icp_xdr_rate_samples_decodes() {
+        let mut state = State::default();
+        state.icp_xdr_rate_samples = None;
+        state.exchange_rate_fetch_failures = None;
+
+        let state = State::decode(&state.encode()).unwrap();
+
+        assert_eq!(state.icp_xdr_rate_samples, Some(vec![]));
+        assert_eq!(state.exchange_rate_fetch_failures, Some(0));
+    }
+
+    fn subnet(id: u64) -> SubnetId {
+        SubnetId::from(PrincipalId::new_subnet_test_id(id))
+    }
+
+    fn assign(state: &mut State, subnet_type: &str, subnets: Vec<SubnetId>) -> Result<(), String> {
+        change_subnet_type_assignment(
+            state,
+            ChangeSubnetTypeAssignmentArgs::Add(SubnetListWithType {
+                subnets,
+                subnet_type: subnet_type.to_string(),
+            }),
+        )
+    }
+
+    fn unassign(
+        state: &mut State,
+        subnet_type: &str,
+        subnets: Vec<SubnetId>,
+    ) -> Result<(), String> {
+        change_subnet_type_assignment(
+            state,
+            ChangeSubnetTypeAssignmentArgs::Remove(SubnetListWithType {
+                subnets,
+                subnet_type: subnet_type.to_string(),
+            }),
+        )
+    }
+
+    #[test]
+    fn test_update_subnet_type() {
+        let mut state = State::default();
+        let add = |subnet_type: &str| UpdateSubnetTypeArgs::Add(subnet_type.to_string());
+        let remove = |subnet_type: &str| UpdateSubnetTypeArgs::Remove(subnet_type.to_string());
+
+        assert!(update_subnet_type(&mut state, add("")).is_err());
+        assert!(update_subnet_type(&mut state, remove("fiduciary")).is_err());
+        update_subnet_type(&mut state, add("fiduciary")).unwrap();
+        assert!(update_subnet_type(&mut state, add("fiduciary")).is_err());
+
+        assign(&mut state, "fiduciary", vec![subnet(1)]).unwrap();
+        assert!(update_subnet_type(&mut state, remove("fiduciary")).is_err());
+        unassign(&mut state, "fiduciary", vec![subnet(1)]).unwrap();
+        update_subnet_type(&mut state, remove("fiduciary")).unwrap();
+
+        assert_eq!(state.subnet_types_to_subnets, Some(BTreeMap::new()));
+    }
+
+    #[test]
+    fn test_change_subnet_type_assignment() {
+        let mut state = State::default();
+        state.default_subnets = vec![subnet(1)];
+        state
+            .authorized_subnets
+            .insert(PrincipalId::new_user_test_id(1), vec![subnet(5)]);
+        for subnet_type in ["fiduciary", "european"] {
+            update_subnet_type(
+                &mut state,
+                UpdateSubnetTypeArgs::Add(subnet_type.to_string()),
+            )
+            .unwrap();
+        }
+
+        // Unknown types, default subnets and subnets that already have a type
+        // can't be assigned. Nothing is assigned if one subnet can't be.
+        assert!(assign(&mut state, "unknown", vec![subnet(2)]).is_err());
+        assert!(assign(&mut state, "fiduciary", vec![subnet(2), subnet(1)]).is_err());
+        assert!(assign(&mut state, "fiduciary", vec![subnet(2), subnet(5)]).is_err());
+        assign(&mut state, "fiduciary", vec![subnet(2), subnet(3)]).unwrap();
+        assert!(assign(&mut state, "european", vec![subnet(4), subnet(3)]).is_err());
+        assert!(assign(&mut state, "fiduciary", vec![subnet(2)]).is_err());
+        assert_eq!(
+            state.subnet_type_of(&subnet(2)),
+            Some(&"fiduciary".to_string())
+        );
+        assert_eq!(state.subnet_type_of(&subnet(4)), None);
+        assert_eq!(state.subnet_type_of(&subnet(5)), None);
+
+        assert!(unassign(&mut state, "european", vec![subnet(2)]).is_err());
+        assert!(unassign(&mut state, "fiduciary", vec![subnet(2), subnet(4)]).is_err());
+        unassign(&mut state, "fiduciary", vec![subnet(2)]).unwrap();
+        assert_eq!(state.subnet_type_of(&subnet(2)), None);
+        assert_eq!(
+            state.subnet_type_of(&subnet(3)),
+            Some(&"fiduciary".to_string())
+        );
+    }
+
+    #[test]
+    fn test_set_authorized_subnetwork_list() {
+        let mut state = State::default();
+        let who = PrincipalId::new_user_test_id(1);
+        update_subnet_type(
+            &mut state,
+            UpdateSubnetTypeArgs::Add("fiduciary".to_string()),
+        )
+        .unwrap();
+        assign(&mut state, "fiduciary", vec![subnet(3)]).unwrap();
+
+        // Subnets with a subnet type can neither be default nor authorized
+        // subnets. Nothing is set if one subnet has a type.
+        assert!(
+            set_authorized_subnetwork_list(&mut state, None, vec![subnet(1), subnet(3)]).is_err()
+        );
+        assert!(
+            set_authorized_subnetwork_list(&mut state, Some(who), vec![subnet(2), subnet(3)])
+                .is_err()
+        );
+        assert!(state.default_subnets.is_empty());
+        assert!(state.authorized_subnets.is_empty());
+
+        set_authorized_subnetwork_list(&mut state, None, vec![subnet(1)]).unwrap();
+        set_authorized_subnetwork_list(&mut state, Some(who), vec![subnet(2)]).unwrap();
+        assert_eq!(state.default_subnets, vec![subnet(1)]);
+        assert_eq!(state.authorized_subnets_for(&who), &vec![subnet(2)]);
+
+        set_authorized_subnetwork_list(&mut state, Some(who), vec![]).unwrap();
+        assert!(state.authorized_subnets.is_empty());
+    }
+
+    #[test]
+    fn test_get_subnets_for() {
+        let mut state = State::default();
+        let controller = PrincipalId::new_user_test_id(1);
+        let authorized_controller = PrincipalId::new_user_test_id(2);
+        state.default_subnets = vec![subnet(1)];
+        state
+            .authorized_subnets
+            .insert(authorized_controller, vec![subnet(2)]);
+        update_subnet_type(
+            &mut state,
+            UpdateSubnetTypeArgs::Add("fiduciary".to_string()),
+        )
+        .unwrap();
+        assign(&mut state, "fiduciary", vec![subnet(3), subnet(4)]).unwrap();
+
+        let select_subnet = |subnet| SubnetSelection::Subnet { subnet };
+        let select_type = |subnet_type: Option<&str>| {
+            SubnetSelection::Filter(SubnetFilter {
+                subnet_type: subnet_type.map(|t| t.to_string()),
+            })
+        };
+
+        assert_eq!(
+            get_subnets_for(&state, &controller, None),
+            Ok(vec![subnet(1)])
+        );
+        assert_eq!(
+            get_subnets_for(&state, &authorized_controller, Some(&select_type(None))),
+            Ok(vec![subnet(2)])
+        );
+        assert_eq!(
+            get_subnets_for(&state, &controller, Some(&select_type(Some("fiduciary")))),
+            Ok(vec![subnet(3), subnet(4)])
+        );
+        assert!(get_subnets_for(&state, &controller, Some(&select_type(Some("unknown")))).is_err());
+        assert_eq!(
+            get_subnets_for(&state, &controller, Some(&select_subnet(subnet(1)))),
+            Ok(vec![subnet(1)])
+        );
+        assert_eq!(
+            get_subnets_for(&state, &controller, Some(&select_subnet(subnet(4)))),
+            Ok(vec![subnet(4)])
+        );
+        // Subnets without a type are only available to authorized controllers.
+        assert!(get_subnets_for(&state, &controller, Some(&select_subnet(subnet(2)))).is_err());
+        assert!(get_subnets_for(
+            &state,
+            &authorized_controller,
+            Some(&select_subnet(subnet(1)))
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_canister_settings_args() {
+        let controller = PrincipalId::new_user_test_id(1);
+        let other_controllers = vec![
+            PrincipalId::new_user_test_id(2),
+            PrincipalId::new_user_test_id(3),
+        ];
+
+        let settings = canister_settings_args(controller, None);
+        assert_eq!(settings.controller, None);
+        assert_eq!(settings.controllers, Some(vec![controller]));
+        assert_eq!(settings.freezing_threshold, None);
+
+        let settings = canister_settings_args(
+            controller,
+            Some(CanisterSettings {
+                controllers: Some(other_controllers.clone()),
+                freezing_threshold: Some(candid::Nat::from(86_400u64)),
+                ..CanisterSettings::default()
+            }),
+        );
+        assert_eq!(settings.controllers, Some(other_controllers));
+        assert_eq!(
+            settings.freezing_threshold,
+            Some(candid::Nat::from(86_400u64))
+        );
+        assert_eq!(settings.compute_allocation, None);
+    }
+
     #[test]
     fn test_purge_notifications() {
         fn block_index_to_cycles(block_index: BlockHeight) -> Cycles {
@@ -1477,6 +2220,90 @@ mod tests {
         assert_eq!(average_rate, computed_average_rate);
     }
 
+    #[test]
+    fn test_start_exchange_rate_fetch() {
+        let mut state = State::default();
+        assert_eq!(start_exchange_rate_fetch(&mut state, 1_000), None);
+
+        let exchange_rate_canister_id = CanisterId::from_u64(42);
+        state.exchange_rate_canister_id = Some(exchange_rate_canister_id);
+        assert_eq!(
+            start_exchange_rate_fetch(&mut state, 1_000),
+            Some(exchange_rate_canister_id)
+        );
+        assert_eq!(state.last_exchange_rate_fetch_seconds, Some(1_000));
+        // The rate is not fetched again before the interval has passed.
+        assert_eq!(
+            start_exchange_rate_fetch(&mut state, 1_000 + EXCHANGE_RATE_FETCH_INTERVAL_SECONDS - 1),
+            None
+        );
+        assert_eq!(state.last_exchange_rate_fetch_seconds, Some(1_000));
+        assert_eq!(
+            start_exchange_rate_fetch(&mut state, 1_000 + EXCHANGE_RATE_FETCH_INTERVAL_SECONDS),
+            Some(exchange_rate_canister_id)
+        );
+    }
+
+    fn rate(timestamp_seconds: u64, xdr_permyriad_per_icp: u64) -> IcpXdrConversionRate {
+        IcpXdrConversionRate {
+            timestamp_seconds,
+            xdr_permyriad_per_icp,
+        }
+    }
+
+    #[test]
+    fn test_add_icp_xdr_rate_sample() {
+        let mut state = State::default();
+
+        assert_eq!(
+            add_icp_xdr_rate_sample(&mut state, rate(100, 50_000)),
+            Some(rate(100, 50_000))
+        );
+        assert_eq!(
+            add_icp_xdr_rate_sample(&mut state, rate(200, 60_000)),
+            Some(rate(200, 55_000))
+        );
+        // An outlier barely moves the median.
+        assert_eq!(
+            add_icp_xdr_rate_sample(&mut state, rate(300, 1_000_000)),
+            Some(rate(300, 60_000))
+        );
+        assert_eq!(
+            add_icp_xdr_rate_sample(&mut state, rate(400, 40_000)),
+            Some(rate(400, 55_000))
+        );
+        assert_eq!(
+            add_icp_xdr_rate_sample(&mut state, rate(500, 45_000)),
+            Some(rate(500, 50_000))
+        );
+        // Only the most recent samples are considered.
+        assert_eq!(
+            add_icp_xdr_rate_sample(&mut state, rate(600, 70_000)),
+            Some(rate(600, 60_000))
+        );
+
+        // Samples that are not more recent than the last one are ignored.
+        assert_eq!(add_icp_xdr_rate_sample(&mut state, rate(600, 10_000)), None);
+        assert_eq!(add_icp_xdr_rate_sample(&mut state, rate(550, 10_000)), None);
+        assert_eq!(state.icp_xdr_rate_samples.as_ref().unwrap().len(), 6);
+    }
+
+    #[test]
+    fn test_icp_xdr_rate_samples_are_bounded() {
+        let mut state = State::default();
+        for i in 1..=(MAX_ICP_XDR_RATE_SAMPLES as u64 + 10) {
+            add_icp_xdr_rate_sample(&mut state, rate(i, 50_000 + i));
+        }
+
+        let samples = state.icp_xdr_rate_samples.unwrap();
+        assert_eq!(samples.len(), MAX_ICP_XDR_RATE_SAMPLES);
+        assert_eq!(samples[0].timestamp_seconds, 11);
+        assert_eq!(
+            samples.last().unwrap().timestamp_seconds,
+            MAX_ICP_XDR_RATE_SAMPLES as u64 + 10
+        );
+    }
+
     #[test]
     fn test_candid_interface_compatibility() {
         use candid::utils::{service_compatible, CandidSource};
@@ -1457,12 +1457,42 @@ pub struct ListProposalInfo {
     /// restriction is applied.
     #[prost(enumeration="ProposalStatus", repeated, tag="5")]
     pub include_status: ::prost::alloc::vec::Vec<i32>,
+    /// Include only proposals with a topic in this list. If this list is
+    /// empty, no restriction is applied. A topic that is both in this
+    /// list and in `exclude_topic` is excluded.
+    #[prost(enumeration="Topic", repeated, tag="6")]
+    pub include_topic: ::prost::alloc::vec::Vec<i32>,
+    /// Include only proposals made by a neuron in this list. If this
+    /// list is empty, no restriction is applied.
+    #[prost(message, repeated, tag="7")]
+    pub include_proposer: ::prost::alloc::vec::Vec<::ic_nns_common::pb::v1::NeuronId>,
+    /// If specified, only return proposals created at or after this
+    /// timestamp (in seconds since the Unix epoch).
+    #[prost(uint64, optional, tag="8")]
+    pub created_after_timestamp_seconds: ::core::option::Option<u64>,
+    /// If specified, only return proposals created strictly before this
+    /// timestamp (in seconds since the Unix epoch).
+    #[prost(uint64, optional, tag="9")]
+    pub created_before_timestamp_seconds: ::core::option::Option<u64>,
+    /// By default, the payloads of `ExecuteNnsFunction` proposals larger
+    /// than EXECUTE_NNS_FUNCTION_PAYLOAD_LISTING_BYTES_MAX (e.g., Wasm
+    /// modules of canister upgrades) are omitted. If true, such payloads
+    /// are included; to keep the response within bounds, fewer than
+    /// `limit` proposals may be returned in this case.
+    #[prost(bool, optional, tag="10")]
+    pub include_large_payloads: ::core::option::Option<bool>,
 }
 #[derive(candid::CandidType, candid::Deserialize)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ListProposalInfoResponse {
     #[prost(message, repeated, tag="1")]
     pub proposal_info: ::prost::alloc::vec::Vec<ProposalInfo>,
+    /// If set, there may be more proposals matching the request. They
+    /// can be retrieved by repeating the request with `before_proposal`
+    /// set to this value. If not set, all matching proposals have been
+    /// returned.
+    #[prost(message, optional, tag="2")]
+    pub next_before_proposal: ::core::option::Option<::ic_nns_common::pb::v1::ProposalId>,
 }
 /// A request to list neurons. The "requested list", i.e., the list of
 /// neuron IDs to retrieve information about, is the union of the list
@@ -1803,6 +1833,16 @@ pub enum NnsFunction {
     PrepareCanisterMigration = 28,
     /// Remove `canister_migrations` entries.
     CompleteCanisterMigration = 29,
+    /// Retire replica versions, i.e., remove them from the list of blessed
+    /// replica versions. A version can only be retired if no subnet and no
+    /// unassigned node runs it.
+    RetireReplicaVersion = 30,
+    /// Add or remove a subnet type in the cycles minting canister. Subnet types
+    /// allow users to select the kind of subnet their canisters are created on.
+    UpdateSubnetType = 31,
+    /// Assign subnets a subnet type, or remove them from it, in the cycles
+    /// minting canister.
+    ChangeSubnetTypeAssignment = 32,
 }
 /// The proposal status, with respect to decision making and execution.
 /// See also ProposalRewardStatus.
@@ -126,6 +126,11 @@ pub const MAX_NUMBER_OF_NEURONS: usize = 200_000;
 /// The maximum number results returned by the method `list_proposals`.
 pub const MAX_LIST_PROPOSAL_RESULTS: u32 = 100;
 
+/// The maximum total size of the `ExecuteNnsFunction` payloads returned by
+/// the method `list_proposals` when the caller asks for large payloads to be
+/// included. This keeps the response well below the 2 MiB message limit.
+pub const MAX_LIST_PROPOSALS_PAYLOAD_BYTES: usize = 1_000_000;
+
 /// The number of e8s per ICP;
 const E8S_PER_ICP: u64 = TOKEN_SUBDIVIDABLE_BY;
 
@@ -443,6 +448,7 @@ impl NnsFunction {
             NnsFunction::NnsRootUpgrade => (LIFELINE_CANISTER_ID, "upgrade_root"),
             NnsFunction::RecoverSubnet => (REGISTRY_CANISTER_ID, "recover_subnet"),
             NnsFunction::BlessReplicaVersion => (REGISTRY_CANISTER_ID, "bless_replica_version"),
+            NnsFunction::RetireReplicaVersion => (REGISTRY_CANISTER_ID, "retire_replica_version"),
             NnsFunction::UpdateNodeOperatorConfig => {
                 (REGISTRY_CANISTER_ID, "update_node_operator_config")
             }
@@ -459,6 +465,10 @@ impl NnsFunction {
             NnsFunction::SetAuthorizedSubnetworks => {
                 (CYCLES_MINTING_CANISTER_ID, "set_authorized_subnetwork_list")
             }
+            NnsFunction::UpdateSubnetType => (CYCLES_MINTING_CANISTER_ID, "update_subnet_type"),
+            NnsFunction::ChangeSubnetTypeAssignment => {
+                (CYCLES_MINTING_CANISTER_ID, "change_subnet_type_assignment")
+            }
             NnsFunction::SetFirewallConfig => (REGISTRY_CANISTER_ID, "set_firewall_config"),
             NnsFunction::AddFirewallRules => (REGISTRY_CANISTER_ID, "add_firewall_rules"),
             NnsFunction::RemoveFirewallRules => (REGISTRY_CANISTER_ID, "remove_firewall_rules"),
@@ -1087,6 +1097,7 @@ impl Proposal {
                             | NnsFunction::RemoveNodesFromSubnet
                             | NnsFunction::UpdateConfigOfSubnet
                             | NnsFunction::BlessReplicaVersion
+                            | NnsFunction::RetireReplicaVersion
                             | NnsFunction::UpdateSubnetReplicaVersion => Topic::SubnetManagement,
                             NnsFunction::NnsCanisterInstall
                             | NnsFunction::NnsCanisterUpgrade
@@ -1097,6 +1108,8 @@ impl Proposal {
                             NnsFunction::IcpXdrConversionRate => Topic::ExchangeRate,
                             NnsFunction::ClearProvisionalWhitelist => Topic::NetworkEconomics,
                             NnsFunction::SetAuthorizedSubnetworks => Topic::SubnetManagement,
+                            NnsFunction::UpdateSubnetType => Topic::SubnetManagement,
+                            NnsFunction::ChangeSubnetTypeAssignment => Topic::SubnetManagement,
                             NnsFunction::SetFirewallConfig => Topic::SubnetManagement,
                             NnsFunction::AddFirewallRules => Topic::SubnetManagement,
                             NnsFunction::RemoveFirewallRules => Topic::SubnetManagement,
@@ -3961,25 +3974,36 @@ impl Governance {
     ///
     /// `
     /// let mut lst = gov.list_proposals(ListProposalInfo {});
-    /// while !lst.empty() {
+    /// while let Some(next) = lst.next_before_proposal {
     ///   /* do stuff with lst */
     ///   lst = gov.list_proposals(ListProposalInfo {
-    ///     before_proposal: lst.last().and_then(|x|x.id)
+    ///     before_proposal: Some(next)
     ///   });
     /// }
     /// `
     ///
+    /// The cursor `next_before_proposal` is stable: proposals that are
+    /// created between two calls have higher IDs and thus never shift the
+    /// remaining pages.
+    ///
+    /// - Proposals can be filtered by topic (`include_topic` and
+    /// `exclude_topic`), status, reward status, proposer and creation
+    /// time. Empty filters apply no restriction.
+    ///
     /// - A proposal with resticted voting is included only if the
     /// caller is allowed to vote on the proposal.
     ///
     /// - The proposals' ballots only show votes from neurons that the
     /// caller either controls or is a registered hot key for.
     ///
-    /// - Proposals with `ExecuteNnsFunction` as action have their
-    /// `payload` cleared if larger than
-    /// EXECUTE_NNS_FUNCTION_PAYLOAD_LISTING_BYTES_MAX.  The caller can
-    /// retrieve dropped payloads by calling `get_proposal_info` for
-    /// each proposal of interest.
+    /// - Unless `include_large_payloads` is set, proposals with
+    /// `ExecuteNnsFunction` as action have their `payload` cleared if
+    /// larger than EXECUTE_NNS_FUNCTION_PAYLOAD_LISTING_BYTES_MAX. The
+    /// caller can retrieve dropped payloads by calling `get_proposal_info`
+    /// for each proposal of interest. If `include_large_payloads` is set,
+    /// the listing stops early once the returned payloads exceed
+    /// MAX_LIST_PROPOSALS_PAYLOAD_BYTES (but always returns at least one
+    /// matching proposal, if any).
     pub fn list_proposals(
         &self,
         caller: &PrincipalId,
@@ -3991,15 +4015,19 @@ impl Governance {
             .get(caller)
             .unwrap_or(&empty);
         let exclude_topic: HashSet<i32> = req.exclude_topic.iter().cloned().collect();
+        let include_topic: HashSet<i32> = req.include_topic.iter().cloned().collect();
         let include_reward_status: HashSet<i32> =
             req.include_reward_status.iter().cloned().collect();
         let include_status: HashSet<i32> = req.include_status.iter().cloned().collect();
+        let include_proposer: HashSet<u64> = req.include_proposer.iter().map(|x| x.id).collect();
         let now = self.env.now();
         let filter_all = |data: &ProposalData| -> bool {
             let topic = data.topic();
             let voting_period_seconds = self.voting_period_seconds()(topic);
             // Filter out proposals by topic.
-            if exclude_topic.contains(&(topic as i32)) {
+            if exclude_topic.contains(&(topic as i32))
+                || !(include_topic.is_empty() || include_topic.contains(&(topic as i32)))
+            {
                 return false;
             }
             // Filter out proposals by reward status.
@@ -4013,6 +4041,25 @@ impl Governance {
             if !(include_status.is_empty() || include_status.contains(&(data.status() as i32))) {
                 return false;
             }
+            // Filter out proposals by proposer.
+            if !(include_proposer.is_empty()
+                || data
+                    .proposer
+                    .as_ref()
+                    .map_or(false, |n| include_proposer.contains(&n.id)))
+            {
+                return false;
+            }
+            // Filter out proposals by creation time.
+            if req
+                .created_after_timestamp_seconds
+                .map_or(false, |t| data.proposal_timestamp_seconds < t)
+                || req
+                    .created_before_timestamp_seconds
+                    .map_or(false, |t| data.proposal_timestamp_seconds >= t)
+            {
+                return false;
+            }
             // Filter out proposals by their restricted status.
             self.proposal_is_visible_to_neurons(data, caller_neurons)
         };
@@ -4030,15 +4077,43 @@ impl Governance {
         } else {
             props.range(..)
         };
-        // Now reverse the range, filter, and restrict to 'limit'.
-        let limited_rng = rng.rev().filter(|(_, x)| filter_all(x)).take(limit);
-        //
-        let proposal_info = limited_rng
-            .map(|(_, y)| y)
-            .map(|pd| self.proposal_data_to_info(pd, caller_neurons, now, true))
-            .collect();
-        // Ignore the keys and clone to a vector.
-        ListProposalInfoResponse { proposal_info }
+        // Now reverse the range and filter. We fetch one more element
+        // than requested to know whether there is another page.
+        let mut matching = rng.rev().filter(|(_, x)| filter_all(x)).peekable();
+        let mut proposal_info = Vec::new();
+        let include_large_payloads = req.include_large_payloads.unwrap_or(false);
+        let mut payload_bytes = 0;
+        while proposal_info.len() < limit {
+            let pd = match matching.peek() {
+                Some((_, pd)) => *pd,
+                None => break,
+            };
+            if include_large_payloads {
+                let size = execute_nns_function_payload_len(pd);
+                if !proposal_info.is_empty()
+                    && payload_bytes + size > MAX_LIST_PROPOSALS_PAYLOAD_BYTES
+                {
+                    break;
+                }
+                payload_bytes += size;
+            }
+            proposal_info.push(self.proposal_data_to_info(
+                pd,
+                caller_neurons,
+                now,
+                !include_large_payloads,
+            ));
+            matching.next();
+        }
+        let next_before_proposal = if matching.peek().is_some() {
+            proposal_info.last().and_then(|x| x.id)
+        } else {
+            None
+        };
+        ListProposalInfoResponse {
+            proposal_info,
+            next_before_proposal,
+        }
     }
 
     fn ready_to_be_settled_proposal_ids(&self) -> impl Iterator<Item = ProposalId> + '_ {
@@ -6528,6 +6603,15 @@ fn get_node_provider_reward(
     }
 }
 
+/// Returns the size of the payload of `data` if it is an `ExecuteNnsFunction`
+/// proposal, and 0 otherwise.
+fn execute_nns_function_payload_len(data: &ProposalData) -> usize {
+    match data.proposal.as_ref().and_then(|p| p.action.as_ref()) {
+        Some(proposal::Action::ExecuteNnsFunction(m)) => m.payload.len(),
+        _ => 0,
+    }
+}
+
 /// Affects the perception of time by users of CanisterEnv (i.e. Governance).
 ///
 /// Specifically, the time that Governance sees is the real time + delta.
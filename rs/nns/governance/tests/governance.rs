@@ -27,7 +27,7 @@ use ic_nns_governance::pb::v1::{
 use ic_nns_governance::{
     governance::{
         subaccount_from_slice, validate_proposal_title, Environment, Governance,
-        EXECUTE_NNS_FUNCTION_PAYLOAD_LISTING_BYTES_MAX,
+        EXECUTE_NNS_FUNCTION_PAYLOAD_LISTING_BYTES_MAX, MAX_LIST_PROPOSALS_PAYLOAD_BYTES,
         MIN_DISSOLVE_DELAY_FOR_VOTE_ELIGIBILITY_SECONDS, PROPOSAL_MOTION_TEXT_BYTES_MAX,
         REWARD_DISTRIBUTION_PERIOD_SECONDS, WAIT_FOR_QUIET_DEADLINE_INCREASE_SECONDS,
     },
@@ -65,8 +65,8 @@ use ic_nns_governance::{
         AddOrRemoveNodeProvider, Ballot, BallotInfo, Empty, ExecuteNnsFunction,
         Governance as GovernanceProto, GovernanceError, KnownNeuron, KnownNeuronData, ListNeurons,
         ListNeuronsResponse, ListProposalInfo, ManageNeuron, Motion, NetworkEconomics, Neuron,
-        NeuronState, NnsFunction, NodeProvider, Proposal, ProposalData, ProposalStatus,
-        RewardEvent, RewardNodeProvider, SetDefaultFollowees, Tally, Topic, Vote,
+        NeuronState, NnsFunction, NodeProvider, Proposal, ProposalData, ProposalInfo,
+        ProposalStatus, RewardEvent, RewardNodeProvider, SetDefaultFollowees, Tally, Topic, Vote,
     },
 };
 use ledger_canister::{AccountIdentifier, Memo, Tokens};
@@ -7005,6 +7005,217 @@ fn test_list_proposals() {
     assert_eq!(0, lst.len());
 }
 
+/// Tests that `next_before_proposal` can be used to page through all
+/// proposals and is not set on the last page.
+#[test]
+fn test_list_proposals_next_before_proposal() {
+    let proto = GovernanceProto {
+        economics: Some(NetworkEconomics::with_default_values()),
+        proposals: (1..=25)
+            .map(|x| {
+                (
+                    x,
+                    ProposalData {
+                        id: Some(ProposalId { id: x }),
+                        ..Default::default()
+                    },
+                )
+            })
+            .collect::<BTreeMap<u64, ProposalData>>(),
+        ..Default::default()
+    };
+    let driver = fake::FakeDriver::default();
+    let gov = Governance::new(proto, driver.get_fake_env(), driver.get_fake_ledger());
+    let caller = &principal(1);
+
+    let mut ids = vec![];
+    let mut before_proposal = None;
+    let mut pages = 0;
+    loop {
+        let response = gov.list_proposals(
+            caller,
+            &ListProposalInfo {
+                limit: 10,
+                before_proposal,
+                ..Default::default()
+            },
+        );
+        pages += 1;
+        ids.extend(response.proposal_info.iter().map(|x| x.id.unwrap().id));
+        match response.next_before_proposal {
+            Some(next) => before_proposal = Some(next),
+            None => break,
+        }
+    }
+    assert_eq!(3, pages);
+    assert_eq!((1..=25).rev().collect::<Vec<u64>>(), ids);
+
+    // Exactly `limit` results left: the last page must not have a cursor.
+    let response = gov.list_proposals(
+        caller,
+        &ListProposalInfo {
+            limit: 5,
+            before_proposal: Some(ProposalId { id: 6 }),
+            ..Default::default()
+        },
+    );
+    assert_eq!(5, response.proposal_info.len());
+    assert_eq!(None, response.next_before_proposal);
+}
+
+/// Tests filtering proposals by topic, proposer and creation time.
+#[test]
+fn test_list_proposals_filters() {
+    let motion = Proposal {
+        title: Some("A Reasonable Title".to_string()),
+        action: Some(proposal::Action::Motion(Motion {
+            motion_text: "motion".to_string(),
+        })),
+        ..Default::default()
+    };
+    let execute_nns_function = Proposal {
+        title: Some("A Reasonable Title".to_string()),
+        action: Some(proposal::Action::ExecuteNnsFunction(ExecuteNnsFunction {
+            nns_function: NnsFunction::ClearProvisionalWhitelist as i32,
+            payload: vec![],
+        })),
+        ..Default::default()
+    };
+    // Proposals with an even ID are motions made by neuron 2, proposals
+    // with an odd ID are NNS function calls made by neuron 1. Proposal
+    // `x` is created at time `10 * x`.
+    let proto = GovernanceProto {
+        economics: Some(NetworkEconomics::with_default_values()),
+        proposals: (1..=20)
+            .map(|x| {
+                (
+                    x,
+                    ProposalData {
+                        id: Some(ProposalId { id: x }),
+                        proposer: Some(NeuronId { id: 2 - x % 2 }),
+                        proposal: Some(if x % 2 == 0 {
+                            motion.clone()
+                        } else {
+                            execute_nns_function.clone()
+                        }),
+                        proposal_timestamp_seconds: 10 * x,
+                        ..Default::default()
+                    },
+                )
+            })
+            .collect::<BTreeMap<u64, ProposalData>>(),
+        ..Default::default()
+    };
+    let driver = fake::FakeDriver::default();
+    let gov = Governance::new(proto, driver.get_fake_env(), driver.get_fake_ledger());
+    let caller = &principal(1);
+    let list = |req: ListProposalInfo| -> Vec<u64> {
+        gov.list_proposals(caller, &req)
+            .proposal_info
+            .iter()
+            .map(|x| x.id.unwrap().id)
+            .collect()
+    };
+
+    let even = (1..=20).rev().filter(|x| x % 2 == 0).collect::<Vec<u64>>();
+    let odd = (1..=20).rev().filter(|x| x % 2 == 1).collect::<Vec<u64>>();
+    assert_eq!(
+        even,
+        list(ListProposalInfo {
+            include_topic: vec![Topic::Governance as i32],
+            ..Default::default()
+        })
+    );
+    assert_eq!(
+        Vec::<u64>::new(),
+        list(ListProposalInfo {
+            include_topic: vec![Topic::Governance as i32],
+            exclude_topic: vec![Topic::Governance as i32],
+            ..Default::default()
+        })
+    );
+    assert_eq!(
+        odd,
+        list(ListProposalInfo {
+            include_proposer: vec![NeuronId { id: 1 }],
+            ..Default::default()
+        })
+    );
+    assert_eq!(
+        (5..10).rev().collect::<Vec<u64>>(),
+        list(ListProposalInfo {
+            created_after_timestamp_seconds: Some(50),
+            created_before_timestamp_seconds: Some(100),
+            ..Default::default()
+        })
+    );
+    assert_eq!(
+        vec![9, 7],
+        list(ListProposalInfo {
+            limit: 2,
+            before_proposal: Some(ProposalId { id: 11 }),
+            include_topic: vec![Topic::NetworkEconomics as i32],
+            include_proposer: vec![NeuronId { id: 1 }],
+            ..Default::default()
+        })
+    );
+}
+
+/// Tests that large payloads are only returned if requested, and that the
+/// listing then stops once the payload budget is used up.
+#[test]
+fn test_list_proposals_include_large_payloads() {
+    let payload_len = MAX_LIST_PROPOSALS_PAYLOAD_BYTES / 2 + 1;
+    let mut proto = fixture_for_proposals(ProposalId { id: 1 }, vec![42; payload_len]);
+    let mut second = proto.proposals[&1].clone();
+    second.id = Some(ProposalId { id: 2 });
+    proto.proposals.insert(2, second);
+    let driver = fake::FakeDriver::default();
+    let gov = Governance::new(proto, driver.get_fake_env(), driver.get_fake_ledger());
+    let caller = &principal(1);
+    let payload_len_of =
+        |info: &ProposalInfo| match info.proposal.as_ref().unwrap().action.as_ref().unwrap() {
+            proposal::Action::ExecuteNnsFunction(eu) => eu.payload.len(),
+            _ => panic!("Unexpected action"),
+        };
+
+    let response = gov.list_proposals(
+        caller,
+        &ListProposalInfo {
+            ..Default::default()
+        },
+    );
+    assert_eq!(2, response.proposal_info.len());
+    assert!(response
+        .proposal_info
+        .iter()
+        .all(|x| payload_len_of(x) == 0));
+    assert_eq!(None, response.next_before_proposal);
+
+    let response = gov.list_proposals(
+        caller,
+        &ListProposalInfo {
+            include_large_payloads: Some(true),
+            ..Default::default()
+        },
+    );
+    assert_eq!(1, response.proposal_info.len());
+    assert_eq!(payload_len, payload_len_of(&response.proposal_info[0]));
+    assert_eq!(Some(ProposalId { id: 2 }), response.next_before_proposal);
+
+    let response = gov.list_proposals(
+        caller,
+        &ListProposalInfo {
+            include_large_payloads: Some(true),
+            before_proposal: response.next_before_proposal,
+            ..Default::default()
+        },
+    );
+    assert_eq!(1, response.proposal_info.len());
+    assert_eq!(payload_len, payload_len_of(&response.proposal_info[0]));
+    assert_eq!(None, response.next_before_proposal);
+}
+
 // Tests the following:
 //
 // 1. A proposal with resticted voting is included only if the caller
@@ -1,5 +1,11 @@
 use canister_test::Canister;
-use cycles_minting_canister::{IcpXdrConversionRateCertifiedResponse, MEMO_TOP_UP_CANISTER};
+use cycles_minting_canister::exchange_rate_canister::{
+    Asset, ExchangeRate, ExchangeRateMetadata, GetExchangeRateResult,
+};
+use cycles_minting_canister::{
+    CyclesCanisterInitPayload, IcpXdrConversionRate, IcpXdrConversionRateCertifiedResponse,
+    MEMO_TOP_UP_CANISTER,
+};
 use dfn_candid::candid_one;
 use dfn_protobuf::protobuf;
 use ic_canister_client::Sender;
@@ -7,18 +13,22 @@ use ic_nervous_system_common_test_keys::{
     TEST_NEURON_1_OWNER_KEYPAIR, TEST_USER1_KEYPAIR, TEST_USER1_PRINCIPAL,
 };
 use ic_nns_common::types::{NeuronId, ProposalId, UpdateIcpXdrConversionRatePayload};
-use ic_nns_constants::{CYCLES_MINTING_CANISTER_ID, GOVERNANCE_CANISTER_ID};
+use ic_nns_constants::{CYCLES_MINTING_CANISTER_ID, GOVERNANCE_CANISTER_ID, LEDGER_CANISTER_ID};
 use ic_nns_governance::pb::v1::{NnsFunction, ProposalStatus};
 use ic_nns_test_utils::governance::submit_external_update_proposal;
 use ic_nns_test_utils::{
     governance::wait_for_final_state,
     ids::TEST_NEURON_1_ID,
-    itest_helpers::{local_test_on_nns_subnet, NnsCanisters, NnsInitPayloadsBuilder},
+    itest_helpers::{
+        install_rust_canister, local_test_on_nns_subnet, set_up_cycles_minting_canister,
+        NnsCanisters, NnsInitPayloadsBuilder,
+    },
 };
 use ledger_canister::{
     tokens_from_proto, AccountBalanceArgs, AccountIdentifier, BlockHeight, CyclesResponse, Memo,
     NotifyCanisterArgs, SendArgs, Subaccount, Tokens, DEFAULT_TRANSFER_FEE,
 };
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 /// Test that the CMC's `icp_xdr_conversion_rate` can be updated via Governance
 /// proposal.
@@ -166,6 +176,77 @@ fn test_cmc_mints_cycles_when_cmc_has_exchange_rate() {
     });
 }
 
+/// Test that the CMC fetches the ICP/XDR conversion rate from the exchange
+/// rate canister.
+#[test]
+fn test_cmc_fetches_icp_xdr_conversion_rate() {
+    local_test_on_nns_subnet(|runtime| async move {
+        let mut exchange_rate_canister = runtime
+            .create_canister_max_cycles_with_retries()
+            .await
+            .unwrap();
+        install_rust_canister(
+            &mut exchange_rate_canister,
+            "nns/integration_tests",
+            "exchange-rate-canister-stub",
+            &[],
+            None,
+        )
+        .await;
+
+        let timestamp_seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let exchange_rate: GetExchangeRateResult = Ok(ExchangeRate {
+            base_asset: Asset::icp(),
+            quote_asset: Asset::xdr(),
+            timestamp: timestamp_seconds,
+            rate: 5_123_456_789,
+            metadata: ExchangeRateMetadata { decimals: 9 },
+        });
+        exchange_rate_canister
+            .update_("set_exchange_rate", candid_one::<(), _>, exchange_rate)
+            .await
+            .unwrap();
+
+        let cycles_minting = set_up_cycles_minting_canister(
+            &runtime,
+            CyclesCanisterInitPayload {
+                ledger_canister_id: LEDGER_CANISTER_ID,
+                governance_canister_id: GOVERNANCE_CANISTER_ID,
+                minting_account_id: None,
+                last_purged_notification: Some(1),
+                exchange_rate_canister_id: Some(exchange_rate_canister.canister_id()),
+            },
+        )
+        .await;
+
+        // The rate is fetched in the first heartbeat.
+        let mut samples: Vec<IcpXdrConversionRate> = vec![];
+        while samples.is_empty() {
+            std::thread::sleep(Duration::from_millis(100));
+            samples = cycles_minting
+                .query_("get_icp_xdr_rate_samples", candid_one, ())
+                .await
+                .unwrap();
+        }
+        let expected_rate = IcpXdrConversionRate {
+            timestamp_seconds,
+            xdr_permyriad_per_icp: 51_234,
+        };
+        assert_eq!(samples, vec![expected_rate.clone()]);
+
+        let response: IcpXdrConversionRateCertifiedResponse = cycles_minting
+            .query_("get_icp_xdr_conversion_rate", candid_one, ())
+            .await
+            .unwrap();
+        assert_eq!(response.data, expected_rate);
+
+        Ok(())
+    });
+}
+
 /// Sends 10 ICP from `TEST_USER1_PRINCIPAL`s Ledger account to the given
 /// subaccount of the CMC, which then, depending on `memo`, either tries to
 /// create a canister (aka a "cycles wallet") or top-up the canister whose
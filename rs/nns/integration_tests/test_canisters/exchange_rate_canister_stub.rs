@@ -0,0 +1,35 @@
+//! A stub of the exchange rate canister, which returns the exchange rate that
+//! was last set with `set_exchange_rate` to every `get_exchange_rate` call.
+//!
+//! It is intended to be used in tests of the cycles minting canister fetching
+//! the ICP/XDR conversion rate.
+use cycles_minting_canister::exchange_rate_canister::{
+    ExchangeRateError, GetExchangeRateRequest, GetExchangeRateResult, EXCHANGE_RATE_CANISTER_FEE,
+};
+use dfn_candid::candid_one;
+use dfn_core::over;
+
+static mut EXCHANGE_RATE: Option<GetExchangeRateResult> = None;
+
+#[export_name = "canister_update set_exchange_rate"]
+fn set_exchange_rate() {
+    over(candid_one, |exchange_rate: GetExchangeRateResult| unsafe {
+        EXCHANGE_RATE = Some(exchange_rate);
+    })
+}
+
+#[export_name = "canister_update get_exchange_rate"]
+fn get_exchange_rate() {
+    over(
+        candid_one,
+        |_: GetExchangeRateRequest| -> GetExchangeRateResult {
+            if dfn_core::api::msg_cycles_available() < EXCHANGE_RATE_CANISTER_FEE {
+                return Err(ExchangeRateError::NotEnoughCycles);
+            }
+            dfn_core::api::msg_cycles_accept(EXCHANGE_RATE_CANISTER_FEE);
+            unsafe { EXCHANGE_RATE.clone() }.unwrap_or(Err(ExchangeRateError::Pending))
+        },
+    )
+}
+
+fn main() {}
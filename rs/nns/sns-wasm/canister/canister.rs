@@ -1,12 +1,24 @@
 use candid::candid_method;
 use dfn_candid::{candid, candid_one, CandidOne};
-use dfn_core::{over, over_init};
-use ic_sns_wasm::pb::v1::{AddWasm, AddWasmResponse, GetWasm, GetWasmResponse};
-use ic_sns_wasm::sns_wasm::SnsWasmCanister;
+use dfn_core::{over, over_async, over_init};
+use ic_base_types::SubnetId;
+use ic_nervous_system_common::stable_mem_utils::{
+    BufferedStableMemReader, BufferedStableMemWriter,
+};
+use ic_sns_wasm::canister_api::CanisterApiImpl;
+use ic_sns_wasm::pb::v1::{
+    AddWasm, AddWasmResponse, DeployNewSnsResponse, GetNextSnsVersionRequest,
+    GetNextSnsVersionResponse, GetWasm, GetWasmResponse, ListDeployedSnses,
+    ListDeployedSnsesResponse, SnsWasmCanisterInitPayload, StableCanisterState,
+};
+use ic_sns_wasm::sns_wasm::{DeployNewSns, SnsWasmCanister};
+use prost::Message;
 use std::cell::RefCell;
 
 pub const LOG_PREFIX: &str = "[SNS-WASM] ";
 
+const STABLE_MEM_BUFFER_SIZE: u32 = 100 * 1024 * 1024; // 100MiB
+
 thread_local! {
   static SNS_WASM: RefCell<SnsWasmCanister> = RefCell::new(SnsWasmCanister::new());
 }
@@ -19,8 +31,14 @@ fn canister_init() {
 /// In contrast to canister_init(), this method does not do deserialization.
 /// In addition to canister_init, this method is called by canister_post_upgrade.
 #[candid_method(init)]
-fn canister_init_(_init_payload: ()) {
+fn canister_init_(init_payload: SnsWasmCanisterInitPayload) {
     println!("{}canister_init_", LOG_PREFIX);
+    let sns_subnet_ids = init_payload
+        .sns_subnet_ids
+        .into_iter()
+        .map(SubnetId::new)
+        .collect();
+    SNS_WASM.with(|sns_wasm| sns_wasm.borrow_mut().set_sns_subnets(sns_subnet_ids));
 }
 
 /// Executes some logic before executing an upgrade, including serializing and writing the
@@ -30,6 +48,17 @@ fn canister_init_(_init_payload: ()) {
 #[export_name = "canister_pre_upgrade"]
 fn canister_pre_upgrade() {
     println!("{}Executing pre upgrade", LOG_PREFIX);
+
+    SNS_WASM.with(|sns_wasm| {
+        let mut writer = BufferedStableMemWriter::new(STABLE_MEM_BUFFER_SIZE);
+        sns_wasm
+            .borrow()
+            .to_stable_state()
+            .encode(&mut writer)
+            .expect("Error. Couldn't serialize canister pre-upgrade.");
+        writer.flush();
+    });
+
     println!("{}Completed pre upgrade", LOG_PREFIX);
 }
 
@@ -39,6 +68,20 @@ fn canister_pre_upgrade() {
 fn canister_post_upgrade() {
     dfn_core::printer::hook();
     println!("{}Executing post upgrade", LOG_PREFIX);
+
+    let reader = BufferedStableMemReader::new(STABLE_MEM_BUFFER_SIZE);
+    let state = StableCanisterState::decode(reader).expect(
+        "Couldn't upgrade canister, due to state deserialization \
+         failure during post-upgrade.",
+    );
+    let sns_wasm = SnsWasmCanister::from_stable_state(state).unwrap_or_else(|err| {
+        panic!(
+            "Couldn't upgrade canister, due to an invalid state: {}",
+            err
+        )
+    });
+    SNS_WASM.with(|c| c.replace(sns_wasm));
+
     println!("{}Completed post upgrade", LOG_PREFIX);
 }
 
@@ -62,6 +105,46 @@ fn get_wasm_(get_wasm_payload: GetWasm) -> GetWasmResponse {
     SNS_WASM.with(|sns_wasm| sns_wasm.borrow().get_wasm(get_wasm_payload))
 }
 
+#[export_name = "canister_query get_next_sns_version"]
+fn get_next_sns_version() {
+    over(candid_one, get_next_sns_version_)
+}
+
+#[candid_method(query, rename = "get_next_sns_version")]
+fn get_next_sns_version_(request: GetNextSnsVersionRequest) -> GetNextSnsVersionResponse {
+    SNS_WASM.with(|sns_wasm| sns_wasm.borrow().get_next_sns_version(request))
+}
+
+#[export_name = "canister_query get_latest_sns_version_pretty"]
+fn get_latest_sns_version_pretty() {
+    over(candid_one, get_latest_sns_version_pretty_)
+}
+
+#[candid_method(query, rename = "get_latest_sns_version_pretty")]
+fn get_latest_sns_version_pretty_(_: ()) -> Vec<(String, String)> {
+    SNS_WASM.with(|sns_wasm| sns_wasm.borrow().get_latest_sns_version_pretty())
+}
+
+#[export_name = "canister_update deploy_new_sns"]
+fn deploy_new_sns() {
+    over_async(candid_one, deploy_new_sns_)
+}
+
+#[candid_method(update, rename = "deploy_new_sns")]
+async fn deploy_new_sns_(deploy_new_sns: DeployNewSns) -> DeployNewSnsResponse {
+    SnsWasmCanister::deploy_new_sns(&SNS_WASM, &CanisterApiImpl {}, deploy_new_sns).await
+}
+
+#[export_name = "canister_query list_deployed_snses"]
+fn list_deployed_snses() {
+    over(candid_one, list_deployed_snses_)
+}
+
+#[candid_method(query, rename = "list_deployed_snses")]
+fn list_deployed_snses_(request: ListDeployedSnses) -> ListDeployedSnsesResponse {
+    SNS_WASM.with(|sns_wasm| sns_wasm.borrow().list_deployed_snses(request))
+}
+
 /// This makes this Candid service self-describing, so that for example Candid
 /// UI, but also other tools, can seamlessly integrate with it.
 /// The concrete interface (__get_candid_interface_tmp_hack) is provisional, but
@@ -1,3 +1,12 @@
+/// The payload for canister_init, which configures where SNSes are deployed.
+#[derive(candid::CandidType, candid::Deserialize)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SnsWasmCanisterInitPayload {
+    /// The subnets on which new SNSes are deployed. They can only be set when the canister is
+    /// installed, and are kept across upgrades.
+    #[prost(message, repeated, tag="1")]
+    pub sns_subnet_ids: ::prost::alloc::vec::Vec<::ic_base_types::PrincipalId>,
+}
 /// The representation of a WASM along with its target canister type
 #[derive(candid::CandidType, candid::Deserialize)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -62,6 +71,122 @@ pub struct GetWasmResponse {
     #[prost(message, optional, tag="1")]
     pub wasm: ::core::option::Option<SnsWasm>,
 }
+/// The response from deploy_new_sns, which contains the IDs of the deployed SNS canisters
+/// if the deployment succeeded, or an error otherwise.
+#[derive(candid::CandidType, candid::Deserialize)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeployNewSnsResponse {
+    /// The subnet the SNS was deployed to.
+    #[prost(message, optional, tag="1")]
+    pub subnet_id: ::core::option::Option<::ic_base_types::PrincipalId>,
+    /// The IDs of the SNS canisters.
+    #[prost(message, optional, tag="2")]
+    pub canisters: ::core::option::Option<SnsCanisterIds>,
+    /// The reason the SNS could not be deployed, if any.
+    /// If the SNS was deployed, but this canister could not remove itself as a
+    /// controller of all SNS canisters, this is set along with `canisters`.
+    #[prost(message, optional, tag="3")]
+    pub error: ::core::option::Option<SnsWasmError>,
+}
+/// The IDs of the canisters that make up an SNS.
+#[derive(candid::CandidType, candid::Deserialize)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SnsCanisterIds {
+    #[prost(message, optional, tag="1")]
+    pub governance: ::core::option::Option<::ic_base_types::PrincipalId>,
+    #[prost(message, optional, tag="2")]
+    pub root: ::core::option::Option<::ic_base_types::PrincipalId>,
+    #[prost(message, optional, tag="3")]
+    pub ledger: ::core::option::Option<::ic_base_types::PrincipalId>,
+    #[prost(message, optional, tag="4")]
+    pub sale: ::core::option::Option<::ic_base_types::PrincipalId>,
+}
+/// An error returned by an SNS-WASM endpoint.
+#[derive(candid::CandidType, candid::Deserialize)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SnsWasmError {
+    #[prost(string, tag="1")]
+    pub message: ::prost::alloc::string::String,
+}
+/// The argument for list_deployed_snses.
+#[derive(candid::CandidType, candid::Deserialize)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListDeployedSnses {
+}
+/// The response for list_deployed_snses, which lists the SNSes deployed by this canister in
+/// the order they were deployed.
+#[derive(candid::CandidType, candid::Deserialize)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListDeployedSnsesResponse {
+    #[prost(message, repeated, tag="1")]
+    pub instances: ::prost::alloc::vec::Vec<DeployedSns>,
+}
+/// An SNS deployed by this canister.
+#[derive(candid::CandidType, candid::Deserialize)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeployedSns {
+    /// The IDs of the SNS canisters.
+    #[prost(message, optional, tag="1")]
+    pub canisters: ::core::option::Option<SnsCanisterIds>,
+    /// The subnet the SNS was deployed to.
+    #[prost(message, optional, tag="2")]
+    pub subnet_id: ::core::option::Option<::ic_base_types::PrincipalId>,
+}
+/// The hashes of the WASMs that make up a version of an SNS. A hash is empty if no WASM of its
+/// canister type has been added yet.
+#[derive(candid::CandidType, candid::Deserialize)]
+#[derive(Eq, Hash)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SnsVersion {
+    #[prost(bytes="vec", tag="1")]
+    pub root_wasm_hash: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes="vec", tag="2")]
+    pub governance_wasm_hash: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes="vec", tag="3")]
+    pub ledger_wasm_hash: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes="vec", tag="4")]
+    pub sale_wasm_hash: ::prost::alloc::vec::Vec<u8>,
+}
+/// The argument for get_next_sns_version, which consists of the version an SNS is running.
+#[derive(candid::CandidType, candid::Deserialize)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetNextSnsVersionRequest {
+    #[prost(message, optional, tag="1")]
+    pub current_version: ::core::option::Option<SnsVersion>,
+}
+/// The response for get_next_sns_version, which returns the version an SNS running the
+/// requested version should upgrade to, or None if it is on the latest version or the version
+/// is not on the upgrade path.
+#[derive(candid::CandidType, candid::Deserialize)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetNextSnsVersionResponse {
+    #[prost(message, optional, tag="1")]
+    pub next_version: ::core::option::Option<SnsVersion>,
+}
+/// A step of the upgrade path, in which the WASM of exactly one canister type changes.
+#[derive(candid::CandidType, candid::Deserialize)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SnsUpgrade {
+    #[prost(message, optional, tag="1")]
+    pub current_version: ::core::option::Option<SnsVersion>,
+    #[prost(message, optional, tag="2")]
+    pub next_version: ::core::option::Option<SnsVersion>,
+}
+/// The state of the canister that is persisted in stable memory across upgrades.
+#[derive(candid::CandidType, candid::Deserialize)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StableCanisterState {
+    #[prost(message, repeated, tag="1")]
+    pub wasms: ::prost::alloc::vec::Vec<SnsWasm>,
+    #[prost(message, repeated, tag="2")]
+    pub sns_subnet_ids: ::prost::alloc::vec::Vec<::ic_base_types::PrincipalId>,
+    #[prost(message, repeated, tag="3")]
+    pub deployed_sns_list: ::prost::alloc::vec::Vec<DeployedSns>,
+    #[prost(message, repeated, tag="4")]
+    pub upgrade_path: ::prost::alloc::vec::Vec<SnsUpgrade>,
+    #[prost(message, optional, tag="5")]
+    pub latest_version: ::core::option::Option<SnsVersion>,
+}
 /// The type of canister a particular WASM is intended to be installed on
 #[derive(candid::CandidType, candid::Deserialize)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
@@ -74,4 +199,6 @@ pub enum SnsCanisterType {
     Governance = 2,
     /// The type for the ledger canister
     Ledger = 3,
+    /// The type for the sale canister
+    Sale = 4,
 }
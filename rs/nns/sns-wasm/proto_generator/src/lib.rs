@@ -1,16 +1,25 @@
 use prost_build::Config;
 use std::path::Path;
 
+pub struct ProtoPaths<'a> {
+    pub sns_wasm: &'a Path,
+    pub base_types: &'a Path,
+}
+
 /// Build protos using prost_build.
-pub fn generate_prost_files(def: &Path, out: &Path) {
-    let proto_files = [def.join("ic_sns_wasm/pb/v1/sns_wasm.proto")];
+pub fn generate_prost_files(proto: ProtoPaths<'_>, out: &Path) {
+    let proto_file = proto.sns_wasm.join("ic_sns_wasm/pb/v1/sns_wasm.proto");
 
     let mut config = Config::new();
     std::fs::create_dir_all(out).expect("failed to create output directory");
     config.out_dir(out);
 
+    // Imported stuff.
+    config.extern_path(".ic_base_types.pb.v1", "::ic-base-types");
+
     // Our specific tags for all of our protobufs
     std_ic_sns_type_attr(&mut config, "SnsCanisterType");
+    std_ic_sns_type_attr(&mut config, "SnsWasmCanisterInitPayload");
     std_ic_sns_type_attr(&mut config, "SnsWasm");
     std_ic_sns_type_attr(&mut config, "AddWasm");
     std_ic_sns_type_attr(&mut config, "AddWasmResponse");
@@ -19,8 +28,22 @@ pub fn generate_prost_files(def: &Path, out: &Path) {
     std_ic_sns_type_attr(&mut config, "AddWasmResponse.AddWasmError");
     std_ic_sns_type_attr(&mut config, "GetWasm");
     std_ic_sns_type_attr(&mut config, "GetWasmResponse");
+    std_ic_sns_type_attr(&mut config, "DeployNewSnsResponse");
+    std_ic_sns_type_attr(&mut config, "SnsCanisterIds");
+    std_ic_sns_type_attr(&mut config, "SnsWasmError");
+    std_ic_sns_type_attr(&mut config, "ListDeployedSnses");
+    std_ic_sns_type_attr(&mut config, "ListDeployedSnsesResponse");
+    std_ic_sns_type_attr(&mut config, "DeployedSns");
+    std_ic_sns_type_attr(&mut config, "SnsVersion");
+    ic_sns_type_attr(&mut config, "SnsVersion", "#[derive(Eq, Hash)]");
+    std_ic_sns_type_attr(&mut config, "GetNextSnsVersionRequest");
+    std_ic_sns_type_attr(&mut config, "GetNextSnsVersionResponse");
+    std_ic_sns_type_attr(&mut config, "SnsUpgrade");
+    std_ic_sns_type_attr(&mut config, "StableCanisterState");
 
-    config.compile_protos(&proto_files, &[def]).unwrap();
+    config
+        .compile_protos(&[proto_file], &[proto.sns_wasm, proto.base_types])
+        .unwrap();
 }
 
 /// Base level derive attributes (anything we want to apply to almost everything as a rule).
@@ -1,4 +1,4 @@
-use ic_sns_wasm_proto_generator::generate_prost_files;
+use ic_sns_wasm_proto_generator::{generate_prost_files, ProtoPaths};
 use std::path::PathBuf;
 
 fn main() {
@@ -6,6 +6,7 @@ fn main() {
         .expect("CARGO_MANIFEST_DIR env variable is not defined");
     let out = PathBuf::from(&manifest_dir).join("../gen");
     let def = PathBuf::from(&manifest_dir).join("../proto");
+    let base_types = PathBuf::from(&manifest_dir).join("../../../types/base_types/proto");
 
     match std::fs::remove_dir_all(&out) {
         Ok(_) => (),
@@ -17,5 +18,11 @@ fn main() {
         ),
     }
 
-    generate_prost_files(def.as_ref(), out.as_ref());
+    generate_prost_files(
+        ProtoPaths {
+            sns_wasm: def.as_ref(),
+            base_types: base_types.as_ref(),
+        },
+        out.as_ref(),
+    );
 }
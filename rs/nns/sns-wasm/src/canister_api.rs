@@ -0,0 +1,235 @@
+use async_trait::async_trait;
+use dfn_core::api::Funds;
+use ic_base_types::{CanisterId, PrincipalId, SubnetId};
+use ic_ic00_types::{
+    CanisterIdRecord, CanisterInstallMode, CanisterSettingsArgs, CreateCanisterArgs,
+    InstallCodeArgs, Method, UpdateSettingsArgs, IC_00,
+};
+use std::time::SystemTime;
+
+/// The interactions of the SNS-WASM canister with the rest of the Internet Computer, abstracted
+/// away so that the canister logic can be tested natively.
+#[async_trait]
+pub trait CanisterApi {
+    /// Returns the ID of this canister.
+    fn local_canister_id(&self) -> CanisterId;
+
+    /// Returns the current time in seconds since the Unix epoch.
+    fn now_seconds(&self) -> u64;
+
+    /// Returns the caller of the current message.
+    fn message_caller(&self) -> PrincipalId;
+
+    /// Accepts `cycles` from the current message, or returns an error if fewer cycles were
+    /// attached to it.
+    fn accept_message_cycles(&self, cycles: u64) -> Result<(), String>;
+
+    /// Adds `cycles` of this canister to the balance of `canister_id`.
+    async fn send_cycles_to_canister(
+        &self,
+        canister_id: CanisterId,
+        cycles: u64,
+    ) -> Result<(), String>;
+
+    /// Creates a canister on `subnet_id` that is controlled by `controller_id` and holds
+    /// `cycles`.
+    async fn create_canister(
+        &self,
+        subnet_id: SubnetId,
+        controller_id: PrincipalId,
+        cycles: u64,
+    ) -> Result<CanisterId, String>;
+
+    /// Stops and deletes `canister_id`.
+    async fn delete_canister(&self, canister_id: CanisterId) -> Result<(), String>;
+
+    /// Installs `wasm` on `canister_id`, passing `init_payload` to its canister_init.
+    async fn install_wasm(
+        &self,
+        canister_id: CanisterId,
+        wasm: Vec<u8>,
+        init_payload: Vec<u8>,
+    ) -> Result<(), String>;
+
+    /// Makes `controllers` the only controllers of `canister_id`.
+    async fn set_controllers(
+        &self,
+        canister_id: CanisterId,
+        controllers: Vec<PrincipalId>,
+    ) -> Result<(), String>;
+}
+
+/// The `CanisterApi` of the SNS-WASM canister when it runs on the Internet Computer.
+pub struct CanisterApiImpl {}
+
+#[async_trait]
+impl CanisterApi for CanisterApiImpl {
+    fn local_canister_id(&self) -> CanisterId {
+        dfn_core::api::id()
+    }
+
+    fn now_seconds(&self) -> u64 {
+        dfn_core::api::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .expect("Could not get the duration.")
+            .as_secs()
+    }
+
+    fn message_caller(&self) -> PrincipalId {
+        dfn_core::api::caller()
+    }
+
+    fn accept_message_cycles(&self, cycles: u64) -> Result<(), String> {
+        let available = dfn_core::api::msg_cycles_available();
+        if available < cycles {
+            return Err(format!(
+                "Message needs {} cycles attached, but only {} were attached.",
+                cycles, available
+            ));
+        }
+        dfn_core::api::msg_cycles_accept(cycles);
+        Ok(())
+    }
+
+    async fn send_cycles_to_canister(
+        &self,
+        canister_id: CanisterId,
+        cycles: u64,
+    ) -> Result<(), String> {
+        let result: Result<(), _> = dfn_core::api::call_with_funds_and_cleanup(
+            IC_00,
+            &Method::DepositCycles.to_string(),
+            dfn_candid::candid_multi_arity,
+            (CanisterIdRecord::from(canister_id),),
+            Funds::new(cycles),
+        )
+        .await;
+
+        result.map_err(|(code, msg)| {
+            format!(
+                "Sending {} cycles to canister {} failed with code {}: {}",
+                cycles,
+                canister_id,
+                code.unwrap_or_default(),
+                msg
+            )
+        })
+    }
+
+    async fn create_canister(
+        &self,
+        subnet_id: SubnetId,
+        controller_id: PrincipalId,
+        cycles: u64,
+    ) -> Result<CanisterId, String> {
+        let result: Result<CanisterIdRecord, _> = dfn_core::api::call_with_funds_and_cleanup(
+            subnet_id.into(),
+            &Method::CreateCanister.to_string(),
+            dfn_candid::candid_one,
+            CreateCanisterArgs {
+                settings: Some(CanisterSettingsArgs {
+                    controllers: Some(vec![controller_id]),
+                    ..CanisterSettingsArgs::default()
+                }),
+            },
+            Funds::new(cycles),
+        )
+        .await;
+
+        result
+            .map(|record| record.get_canister_id())
+            .map_err(|(code, msg)| {
+                format!(
+                    "Creating canister in subnet {} failed with code {}: {}",
+                    subnet_id,
+                    code.unwrap_or_default(),
+                    msg
+                )
+            })
+    }
+
+    async fn delete_canister(&self, canister_id: CanisterId) -> Result<(), String> {
+        for method in [Method::StopCanister, Method::DeleteCanister] {
+            let result: Result<(), _> = dfn_core::api::call_with_cleanup(
+                IC_00,
+                &method.to_string(),
+                dfn_candid::candid_multi_arity,
+                (CanisterIdRecord::from(canister_id),),
+            )
+            .await;
+            result.map_err(|(code, msg)| {
+                format!(
+                    "Calling {} on canister {} failed with code {}: {}",
+                    method,
+                    canister_id,
+                    code.unwrap_or_default(),
+                    msg
+                )
+            })?;
+        }
+        Ok(())
+    }
+
+    async fn install_wasm(
+        &self,
+        canister_id: CanisterId,
+        wasm: Vec<u8>,
+        init_payload: Vec<u8>,
+    ) -> Result<(), String> {
+        let install_args = InstallCodeArgs::new(
+            CanisterInstallMode::Install,
+            canister_id,
+            wasm,
+            init_payload,
+            None,
+            None,
+            None,
+        );
+        let result: Result<(), _> = dfn_core::api::call_with_cleanup(
+            IC_00,
+            &Method::InstallCode.to_string(),
+            dfn_candid::candid_multi_arity,
+            (install_args,),
+        )
+        .await;
+
+        result.map_err(|(code, msg)| {
+            format!(
+                "Installing the wasm on canister {} failed with code {}: {}",
+                canister_id,
+                code.unwrap_or_default(),
+                msg
+            )
+        })
+    }
+
+    async fn set_controllers(
+        &self,
+        canister_id: CanisterId,
+        controllers: Vec<PrincipalId>,
+    ) -> Result<(), String> {
+        let update_settings_args = UpdateSettingsArgs {
+            canister_id: canister_id.get(),
+            settings: CanisterSettingsArgs {
+                controllers: Some(controllers),
+                ..CanisterSettingsArgs::default()
+            },
+        };
+        let result: Result<(), _> = dfn_core::api::call_with_cleanup(
+            IC_00,
+            &Method::UpdateSettings.to_string(),
+            dfn_candid::candid_multi_arity,
+            (update_settings_args,),
+        )
+        .await;
+
+        result.map_err(|(code, msg)| {
+            format!(
+                "Setting the controllers of canister {} failed with code {}: {}",
+                canister_id,
+                code.unwrap_or_default(),
+                msg
+            )
+        })
+    }
+}
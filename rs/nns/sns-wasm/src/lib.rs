@@ -1,2 +1,3 @@
+pub mod canister_api;
 pub mod pb;
 pub mod sns_wasm;
@@ -1,4 +1,4 @@
-use crate::pb::v1::SnsWasm;
+use crate::pb::v1::{SnsCanisterType, SnsVersion, SnsWasm};
 use ic_crypto_sha::Sha256;
 use std::fmt::Write;
 
@@ -28,3 +28,51 @@ impl SnsWasm {
         hash_to_hex_string(&bytes)
     }
 }
+
+impl SnsVersion {
+    /// The canister types whose wasms make up an SNS version.
+    pub const CANISTER_TYPES: [SnsCanisterType; 4] = [
+        SnsCanisterType::Root,
+        SnsCanisterType::Governance,
+        SnsCanisterType::Ledger,
+        SnsCanisterType::Sale,
+    ];
+
+    /// Returns the wasm hash of `canister_type` in this version, which is empty if there is no
+    /// wasm for it yet.
+    pub fn wasm_hash(&self, canister_type: SnsCanisterType) -> &[u8] {
+        match canister_type {
+            SnsCanisterType::Root => &self.root_wasm_hash,
+            SnsCanisterType::Governance => &self.governance_wasm_hash,
+            SnsCanisterType::Ledger => &self.ledger_wasm_hash,
+            SnsCanisterType::Sale => &self.sale_wasm_hash,
+            SnsCanisterType::Unspecified => &[],
+        }
+    }
+
+    /// Returns a copy of this version in which the wasm of `canister_type` is replaced by the
+    /// wasm with hash `wasm_hash`.
+    pub fn with_wasm_hash(&self, canister_type: SnsCanisterType, wasm_hash: &[u8; 32]) -> Self {
+        let mut version = self.clone();
+        let hash = match canister_type {
+            SnsCanisterType::Root => &mut version.root_wasm_hash,
+            SnsCanisterType::Governance => &mut version.governance_wasm_hash,
+            SnsCanisterType::Ledger => &mut version.ledger_wasm_hash,
+            SnsCanisterType::Sale => &mut version.sale_wasm_hash,
+            SnsCanisterType::Unspecified => return version,
+        };
+        *hash = wasm_hash.to_vec();
+        version
+    }
+
+    /// Returns the canister types whose wasms differ between this version and `other`.
+    pub fn changed_canister_types(&self, other: &SnsVersion) -> Vec<SnsCanisterType> {
+        Self::CANISTER_TYPES
+            .iter()
+            .filter(|canister_type| {
+                self.wasm_hash(**canister_type) != other.wasm_hash(**canister_type)
+            })
+            .cloned()
+            .collect()
+    }
+}
@@ -1,17 +1,222 @@
+use crate::canister_api::CanisterApi;
 use crate::pb::hash_to_hex_string;
 use crate::pb::v1::add_wasm_response::{AddWasmError, AddWasmOk};
 use crate::pb::v1::{
-    add_wasm_response, AddWasm, AddWasmResponse, GetWasm, GetWasmResponse, SnsCanisterType, SnsWasm,
+    add_wasm_response, AddWasm, AddWasmResponse, DeployNewSnsResponse, DeployedSns,
+    GetNextSnsVersionRequest, GetNextSnsVersionResponse, GetWasm, GetWasmResponse,
+    ListDeployedSnses, ListDeployedSnsesResponse, SnsCanisterIds, SnsCanisterType, SnsUpgrade,
+    SnsVersion, SnsWasm, SnsWasmError, StableCanisterState,
 };
-use std::collections::BTreeMap;
+use candid::{CandidType, Deserialize, Encode};
+use ic_base_types::{CanisterId, PrincipalId, SubnetId};
+use ic_sns_init::SnsInitPayload;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
 use std::convert::TryInto;
+use std::thread::LocalKey;
 
 type SnsWasmMap = BTreeMap<[u8; 32], SnsWasm>;
 
+/// The number of cycles each SNS canister is created with.
+pub const SNS_CANISTER_CREATION_CYCLES: u64 = 500_000_000_000;
+
+/// The number of cycles that have to be attached to a call of `deploy_new_sns`, which pay for
+/// the creation of the SNS canisters.
+pub const SNS_CREATION_FEE: u64 = 4 * SNS_CANISTER_CREATION_CYCLES;
+
+/// The payload for deploy_new_sns, which describes the SNS to deploy.
+#[derive(CandidType, Clone, Debug, Default, Deserialize)]
+pub struct DeployNewSns {
+    pub sns_init_payload: Option<SnsInitPayload>,
+}
+
 /// The struct that implements the public API of the canister
 #[derive(Default)]
 pub struct SnsWasmCanister {
     wasm_storage: SnsWasmStorage,
+    /// The subnets on which new SNSes are deployed. They are fixed when the canister is
+    /// installed and kept across upgrades, as there is no method to change them.
+    sns_subnet_ids: Vec<SubnetId>,
+    /// The SNSes deployed by this canister, in the order they were deployed.
+    deployed_sns_list: Vec<DeployedSns>,
+    /// The blessed versions of an SNS and the order in which SNSes upgrade through them.
+    upgrade_path: UpgradePath,
+}
+
+/// The path along which SNSes are upgraded. Each wasm added to the canister extends the path by a
+/// version in which the wasm of its canister type is replaced, so that in each step of the path
+/// exactly one SNS canister is upgraded.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UpgradePath {
+    /// The version the latest wasms make up, which new SNSes are deployed with.
+    pub latest_version: SnsVersion,
+    /// Maps each version on the path, except for the latest, to the next version on the path.
+    pub upgrade_path: HashMap<SnsVersion, SnsVersion>,
+}
+
+impl UpgradePath {
+    /// Returns the version following the latest version if the wasm of `canister_type` is
+    /// replaced by the wasm with hash `wasm_hash`, or None if that wasm is already part of the
+    /// latest version.
+    fn next_version(
+        &self,
+        canister_type: SnsCanisterType,
+        wasm_hash: &[u8; 32],
+    ) -> Result<Option<SnsVersion>, String> {
+        let next_version = self.latest_version.with_wasm_hash(canister_type, wasm_hash);
+        if next_version == self.latest_version {
+            return Ok(None);
+        }
+        self.validate_upgrade(&self.latest_version, &next_version)?;
+        Ok(Some(next_version))
+    }
+
+    /// Makes `next_version` the latest version, following the current latest version.
+    fn add_upgrade(&mut self, next_version: SnsVersion) {
+        let current_version = std::mem::replace(&mut self.latest_version, next_version.clone());
+        self.upgrade_path.insert(current_version, next_version);
+    }
+
+    /// Validates that `next_version` can follow `current_version` on the path: the wasm of
+    /// exactly one canister type changes, and the path does not return to a version already on
+    /// it, which would make it a cycle.
+    fn validate_upgrade(
+        &self,
+        current_version: &SnsVersion,
+        next_version: &SnsVersion,
+    ) -> Result<(), String> {
+        let changed_canister_types = current_version.changed_canister_types(next_version);
+        if changed_canister_types.len() != 1 {
+            return Err(format!(
+                "An upgrade must change the wasm of exactly one canister type, but it changes \
+                {:?}.",
+                changed_canister_types
+            ));
+        }
+        if self.upgrade_path.contains_key(next_version) || next_version == &self.latest_version {
+            return Err(format!(
+                "The {:?} wasm would return the SNS to a version that is already on the \
+                upgrade path.",
+                changed_canister_types[0]
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the version that follows `current_version` on the path.
+    pub fn get_next_version(&self, current_version: &SnsVersion) -> Option<&SnsVersion> {
+        self.upgrade_path.get(current_version)
+    }
+
+    /// Returns the steps of the path in order, starting with the version without any wasms.
+    fn steps(&self) -> Vec<SnsUpgrade> {
+        let mut steps = Vec::with_capacity(self.upgrade_path.len());
+        let mut current_version = SnsVersion::default();
+        while let Some(next_version) = self.upgrade_path.get(&current_version) {
+            steps.push(SnsUpgrade {
+                current_version: Some(current_version),
+                next_version: Some(next_version.clone()),
+            });
+            current_version = next_version.clone();
+        }
+        steps
+    }
+
+    /// Rebuilds the path from its `steps` and its `latest_version`, validating each step.
+    fn from_steps(steps: Vec<SnsUpgrade>, latest_version: SnsVersion) -> Result<Self, String> {
+        let mut upgrade_path = UpgradePath::default();
+        for step in steps {
+            let current_version = step.current_version.unwrap_or_default();
+            let next_version = step.next_version.unwrap_or_default();
+            if current_version != upgrade_path.latest_version {
+                return Err(format!(
+                    "The upgrade path is not contiguous: {:?} does not follow {:?}.",
+                    current_version, upgrade_path.latest_version
+                ));
+            }
+            upgrade_path.validate_upgrade(&current_version, &next_version)?;
+            upgrade_path.add_upgrade(next_version);
+        }
+        if upgrade_path.latest_version != latest_version {
+            return Err(format!(
+                "The upgrade path ends with {:?} instead of the latest version {:?}.",
+                upgrade_path.latest_version, latest_version
+            ));
+        }
+        Ok(upgrade_path)
+    }
+}
+
+/// The IDs of the canisters of an SNS that is being deployed.
+#[derive(Clone, Copy, Debug)]
+struct DeployingSnsCanisters {
+    governance: CanisterId,
+    root: CanisterId,
+    ledger: CanisterId,
+    sale: CanisterId,
+}
+
+impl DeployingSnsCanisters {
+    fn all(&self) -> [CanisterId; 4] {
+        [self.governance, self.root, self.ledger, self.sale]
+    }
+
+    /// Returns the SNS canister that is to control each SNS canister, with root last.
+    fn sns_controllers(&self) -> [(CanisterId, PrincipalId); 4] {
+        let root = self.root.get();
+        [
+            (self.governance, root),
+            (self.ledger, root),
+            (self.sale, root),
+            (self.root, self.governance.get()),
+        ]
+    }
+}
+
+/// The encoded init payloads of the SNS canisters.
+struct InitPayloads {
+    governance: Vec<u8>,
+    root: Vec<u8>,
+    ledger: Vec<u8>,
+    sale: Vec<u8>,
+}
+
+impl InitPayloads {
+    /// Builds the init payloads of the SNS `canisters` from `sns_init_payload`, at the time
+    /// `now_seconds`.
+    fn build(
+        sns_init_payload: &SnsInitPayload,
+        canisters: DeployingSnsCanisters,
+        now_seconds: u64,
+    ) -> Result<Self, String> {
+        let sns_canister_ids = ic_sns_init::SnsCanisterIds {
+            governance: canisters.governance.get(),
+            ledger: canisters.ledger.get(),
+            root: canisters.root.get(),
+        };
+        let payloads =
+            sns_init_payload.build_canister_payloads_at(&sns_canister_ids, now_seconds)?;
+        let sale_payload = sns_init_payload.sale_init_args(&sns_canister_ids, now_seconds)?;
+
+        let encode_error = |err: candid::Error| format!("Could not encode init payload: {}", err);
+        Ok(Self {
+            governance: Encode!(&payloads.governance).map_err(encode_error)?,
+            root: Encode!(&payloads.root).map_err(encode_error)?,
+            ledger: Encode!(&payloads.ledger).map_err(encode_error)?,
+            sale: Encode!(&sale_payload).map_err(encode_error)?,
+        })
+    }
+}
+
+impl From<DeployingSnsCanisters> for SnsCanisterIds {
+    fn from(canisters: DeployingSnsCanisters) -> Self {
+        SnsCanisterIds {
+            governance: Some(canisters.governance.get()),
+            root: Some(canisters.root.get()),
+            ledger: Some(canisters.ledger.get()),
+            sale: Some(canisters.sale.get()),
+        }
+    }
 }
 
 impl SnsWasmCanister {
@@ -19,6 +224,53 @@ impl SnsWasmCanister {
         SnsWasmCanister::default()
     }
 
+    /// Sets the subnets on which new SNSes are deployed. Only called from canister_init.
+    pub fn set_sns_subnets(&mut self, subnet_ids: Vec<SubnetId>) {
+        self.sns_subnet_ids = subnet_ids;
+    }
+
+    /// Restores the canister from the state persisted in stable memory, validating the upgrade
+    /// path.
+    pub fn from_stable_state(state: StableCanisterState) -> Result<Self, String> {
+        let mut wasm_storage = SnsWasmStorage::new();
+        for wasm in state.wasms {
+            wasm_storage.insert_wasm(wasm.sha256_hash(), wasm);
+        }
+        let upgrade_path =
+            UpgradePath::from_steps(state.upgrade_path, state.latest_version.unwrap_or_default())?;
+        for canister_type in SnsVersion::CANISTER_TYPES {
+            let hash = upgrade_path.latest_version.wasm_hash(canister_type);
+            if !hash.is_empty() && wasm_storage.get_wasm(&vec_to_hash(hash.to_vec())).is_none() {
+                return Err(format!(
+                    "The {:?} wasm of the latest version is not stored.",
+                    canister_type
+                ));
+            }
+        }
+
+        Ok(SnsWasmCanister {
+            wasm_storage,
+            sns_subnet_ids: state
+                .sns_subnet_ids
+                .into_iter()
+                .map(SubnetId::new)
+                .collect(),
+            deployed_sns_list: state.deployed_sns_list,
+            upgrade_path,
+        })
+    }
+
+    /// Returns the state of the canister that is persisted in stable memory.
+    pub fn to_stable_state(&self) -> StableCanisterState {
+        StableCanisterState {
+            wasms: self.wasm_storage.wasm_map.values().cloned().collect(),
+            sns_subnet_ids: self.sns_subnet_ids.iter().map(|id| id.get()).collect(),
+            deployed_sns_list: self.deployed_sns_list.clone(),
+            upgrade_path: self.upgrade_path.steps(),
+            latest_version: Some(self.upgrade_path.latest_version.clone()),
+        }
+    }
+
     /// Returns an Option(SnsWasm) in the GetWasmResponse (a struct with wasm bytecode and the install target)
     pub fn get_wasm(&self, get_wasm_payload: GetWasm) -> GetWasmResponse {
         let hash = vec_to_hash(get_wasm_payload.hash);
@@ -28,12 +280,12 @@ impl SnsWasmCanister {
     }
 
     /// Adds a WASM to the canister's storage, validating that the expected hash matches that of the
-    /// provided WASM bytecode.
+    /// provided WASM bytecode, and extends the upgrade path by a version with the WASM.
     pub fn add_wasm(&mut self, add_wasm_payload: AddWasm) -> AddWasmResponse {
         let wasm = add_wasm_payload.wasm.expect("Wasm is required");
         let hash = vec_to_hash(add_wasm_payload.hash);
 
-        let result = match self.wasm_storage.add_wasm(wasm, &hash) {
+        let result = match self.do_add_wasm(wasm, hash) {
             Ok(_) => Some(add_wasm_response::Result::Ok(AddWasmOk {
                 hash: hash.to_vec(),
             })),
@@ -43,6 +295,325 @@ impl SnsWasmCanister {
         };
         AddWasmResponse { result }
     }
+
+    fn do_add_wasm(&mut self, wasm: SnsWasm, hash: [u8; 32]) -> Result<(), String> {
+        let canister_type = SnsWasmStorage::validate_wasm(&wasm, &hash)?;
+        let next_version = self.upgrade_path.next_version(canister_type, &hash)?;
+        self.wasm_storage.insert_wasm(hash, wasm);
+        if let Some(next_version) = next_version {
+            self.upgrade_path.add_upgrade(next_version);
+        }
+        Ok(())
+    }
+
+    /// Returns the version an SNS running `current_version` should be upgraded to next.
+    pub fn get_next_sns_version(
+        &self,
+        request: GetNextSnsVersionRequest,
+    ) -> GetNextSnsVersionResponse {
+        let current_version = request.current_version.unwrap_or_default();
+        GetNextSnsVersionResponse {
+            next_version: self
+                .upgrade_path
+                .get_next_version(&current_version)
+                .cloned(),
+        }
+    }
+
+    /// Returns the latest version as pairs of canister type and hex-encoded wasm hash, for
+    /// human consumption.
+    pub fn get_latest_sns_version_pretty(&self) -> Vec<(String, String)> {
+        SnsVersion::CANISTER_TYPES
+            .iter()
+            .map(|canister_type| {
+                let hash = self.upgrade_path.latest_version.wasm_hash(*canister_type);
+                let hash = if hash.is_empty() {
+                    String::new()
+                } else {
+                    hash_to_hex_string(&vec_to_hash(hash.to_vec()))
+                };
+                (format!("{:?}", canister_type), hash)
+            })
+            .collect()
+    }
+
+    /// Returns the SNSes deployed by this canister.
+    pub fn list_deployed_snses(
+        &self,
+        _list_sns_payload: ListDeployedSnses,
+    ) -> ListDeployedSnsesResponse {
+        ListDeployedSnsesResponse {
+            instances: self.deployed_sns_list.clone(),
+        }
+    }
+
+    /// Deploys a new SNS on an SNS subnet: creates the governance, root, ledger and sale
+    /// canisters, installs the latest wasms of this canister on them, initialized according to
+    /// the given `SnsInitPayload`, and hands control over them to the SNS. If any step fails,
+    /// the canisters created so far are deleted again, and the cycles of the `SNS_CREATION_FEE`
+    /// that were not given to them are refunded to the caller. The cycles of deleted canisters
+    /// are burned, so the fee is forfeited if the deployment fails after all SNS canisters have
+    /// been created.
+    ///
+    /// Once the SNS canisters control each other, the SNS is deployed, even if this canister
+    /// can't remove itself as a controller of all of them. The response then lists the SNS
+    /// canisters along with an error saying which canisters are still co-controlled.
+    ///
+    /// The state is only accessed through `thread_safe_sns` in between the calls to other
+    /// canisters, so that it is never borrowed across an `await`.
+    pub async fn deploy_new_sns(
+        thread_safe_sns: &'static LocalKey<RefCell<SnsWasmCanister>>,
+        canister_api: &impl CanisterApi,
+        deploy_new_sns_payload: DeployNewSns,
+    ) -> DeployNewSnsResponse {
+        match Self::do_deploy_new_sns(thread_safe_sns, canister_api, deploy_new_sns_payload).await {
+            Ok((subnet_id, canisters, hand_over_error)) => DeployNewSnsResponse {
+                subnet_id: Some(subnet_id.get()),
+                canisters: Some(canisters.into()),
+                error: hand_over_error.map(|message| SnsWasmError { message }),
+            },
+            Err(message) => DeployNewSnsResponse {
+                subnet_id: None,
+                canisters: None,
+                error: Some(SnsWasmError { message }),
+            },
+        }
+    }
+
+    async fn do_deploy_new_sns(
+        thread_safe_sns: &'static LocalKey<RefCell<SnsWasmCanister>>,
+        canister_api: &impl CanisterApi,
+        deploy_new_sns_payload: DeployNewSns,
+    ) -> Result<(SubnetId, DeployingSnsCanisters, Option<String>), String> {
+        let sns_init_payload = deploy_new_sns_payload
+            .sns_init_payload
+            .ok_or_else(|| "sns_init_payload is a required field".to_string())?;
+        sns_init_payload.validate()?;
+
+        // The init payloads depend on the IDs of the SNS canisters, so they are built with
+        // placeholder IDs first, such that an invalid payload is rejected before any canister
+        // is created.
+        let now_seconds = canister_api.now_seconds();
+        let placeholder_canisters = DeployingSnsCanisters {
+            governance: CanisterId::from_u64(0),
+            root: CanisterId::from_u64(1),
+            ledger: CanisterId::from_u64(2),
+            sale: CanisterId::from_u64(3),
+        };
+        InitPayloads::build(&sns_init_payload, placeholder_canisters, now_seconds)?;
+
+        let (subnet_id, wasms) = thread_safe_sns.with(|sns_canister| {
+            let sns_canister = sns_canister.borrow();
+            Ok::<_, String>((
+                sns_canister.get_sns_subnet_id()?,
+                sns_canister.get_latest_wasms()?,
+            ))
+        })?;
+
+        // Only canisters can attach cycles, so the caller is a canister that unspent cycles can
+        // be refunded to.
+        let caller =
+            CanisterId::new(canister_api.message_caller()).map_err(|err| err.to_string())?;
+        canister_api.accept_message_cycles(SNS_CREATION_FEE)?;
+
+        let canisters = Self::create_sns_canisters(canister_api, subnet_id, caller).await?;
+
+        let result = match InitPayloads::build(&sns_init_payload, canisters, now_seconds) {
+            Ok(payloads) => {
+                Self::install_and_wire_sns(canister_api, &payloads, &wasms, canisters).await
+            }
+            Err(message) => Err(message),
+        };
+        if let Err(message) = result {
+            return Err(Self::clean_up(canister_api, caller, &canisters.all(), message).await);
+        }
+
+        let hand_over_error = Self::remove_self_as_controller(canister_api, canisters).await;
+
+        thread_safe_sns.with(|sns_canister| {
+            sns_canister
+                .borrow_mut()
+                .deployed_sns_list
+                .push(DeployedSns {
+                    canisters: Some(canisters.into()),
+                    subnet_id: Some(subnet_id.get()),
+                })
+        });
+
+        Ok((subnet_id, canisters, hand_over_error))
+    }
+
+    /// Creates the SNS canisters, controlled by this canister. If one of them can't be created,
+    /// the ones created before are deleted again and the rest of the fee is refunded to `caller`.
+    async fn create_sns_canisters(
+        canister_api: &impl CanisterApi,
+        subnet_id: SubnetId,
+        caller: CanisterId,
+    ) -> Result<DeployingSnsCanisters, String> {
+        let mut created = Vec::new();
+        for _ in 0..4 {
+            match canister_api
+                .create_canister(
+                    subnet_id,
+                    canister_api.local_canister_id().get(),
+                    SNS_CANISTER_CREATION_CYCLES,
+                )
+                .await
+            {
+                Ok(canister_id) => created.push(canister_id),
+                Err(message) => {
+                    return Err(Self::clean_up(canister_api, caller, &created, message).await);
+                }
+            }
+        }
+
+        Ok(DeployingSnsCanisters {
+            governance: created[0],
+            root: created[1],
+            ledger: created[2],
+            sale: created[3],
+        })
+    }
+
+    /// Installs the `wasms` on the SNS `canisters` and sets their controllers, such that root
+    /// controls governance, ledger and sale, and governance controls root. This canister stays a
+    /// co-controller of all of them, so that it can still delete them if a step fails.
+    async fn install_and_wire_sns(
+        canister_api: &impl CanisterApi,
+        payloads: &InitPayloads,
+        wasms: &LatestWasms,
+        canisters: DeployingSnsCanisters,
+    ) -> Result<(), String> {
+        let installs = [
+            (
+                canisters.governance,
+                &wasms.governance,
+                &payloads.governance,
+            ),
+            (canisters.root, &wasms.root, &payloads.root),
+            (canisters.ledger, &wasms.ledger, &payloads.ledger),
+            (canisters.sale, &wasms.sale, &payloads.sale),
+        ];
+        for (canister_id, wasm, init_payload) in installs {
+            canister_api
+                .install_wasm(canister_id, wasm.wasm.clone(), init_payload.clone())
+                .await?;
+        }
+
+        let this_canister = canister_api.local_canister_id().get();
+        for (canister_id, controller) in canisters.sns_controllers() {
+            canister_api
+                .set_controllers(canister_id, vec![controller, this_canister])
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Removes this canister as a controller of the wired SNS `canisters`, handing them over to
+    /// the SNS. Root is handed over last. Returns an error message listing the canisters that
+    /// this canister still co-controls, if any.
+    async fn remove_self_as_controller(
+        canister_api: &impl CanisterApi,
+        canisters: DeployingSnsCanisters,
+    ) -> Option<String> {
+        let mut errors = Vec::new();
+        for (canister_id, controller) in canisters.sns_controllers() {
+            if let Err(err) = canister_api
+                .set_controllers(canister_id, vec![controller])
+                .await
+            {
+                errors.push(format!(
+                    "Could not remove this canister as a controller of {}: {}",
+                    canister_id, err
+                ));
+            }
+        }
+        if errors.is_empty() {
+            None
+        } else {
+            Some(format!(
+                "The SNS was deployed, but not fully handed over:\n{}",
+                errors.join("\n")
+            ))
+        }
+    }
+
+    /// Deletes `canisters` after a failed deployment and refunds the cycles of the fee that were
+    /// not given to them to `caller`. The cycles of the deleted canisters are burned along with
+    /// them, so that the whole fee is forfeited once all SNS canisters have been created.
+    /// Returns the error message of the deployment extended by the errors of the clean up, if
+    /// any.
+    async fn clean_up(
+        canister_api: &impl CanisterApi,
+        caller: CanisterId,
+        canisters: &[CanisterId],
+        message: String,
+    ) -> String {
+        let mut message = format!("Could not deploy the SNS: {}", message);
+        for canister_id in canisters {
+            if let Err(err) = canister_api.delete_canister(*canister_id).await {
+                message.push_str(&format!(
+                    "\nCould not delete canister {} while cleaning up: {}",
+                    canister_id, err
+                ));
+            }
+        }
+
+        let unspent_cycles =
+            SNS_CREATION_FEE - canisters.len() as u64 * SNS_CANISTER_CREATION_CYCLES;
+        if unspent_cycles > 0 {
+            if let Err(err) = canister_api
+                .send_cycles_to_canister(caller, unspent_cycles)
+                .await
+            {
+                message.push_str(&format!(
+                    "\nCould not refund {} cycles to {} while cleaning up: {}",
+                    unspent_cycles, caller, err
+                ));
+            }
+        }
+        message
+    }
+
+    /// Retrieves the wasms of the latest version, or returns an error if there is no wasm of one
+    /// of the SNS canisters yet.
+    fn get_latest_wasms(&self) -> Result<LatestWasms, String> {
+        let latest_wasm = |canister_type: SnsCanisterType| {
+            let hash = self.upgrade_path.latest_version.wasm_hash(canister_type);
+            if hash.is_empty() {
+                return Err(format!(
+                    "No wasm of canister type {:?} has been added yet.",
+                    canister_type
+                ));
+            }
+            self.wasm_storage
+                .get_wasm(&vec_to_hash(hash.to_vec()))
+                .cloned()
+                .ok_or_else(|| format!("The latest {:?} wasm is not stored.", canister_type))
+        };
+        Ok(LatestWasms {
+            governance: latest_wasm(SnsCanisterType::Governance)?,
+            root: latest_wasm(SnsCanisterType::Root)?,
+            ledger: latest_wasm(SnsCanisterType::Ledger)?,
+            sale: latest_wasm(SnsCanisterType::Sale)?,
+        })
+    }
+
+    /// Returns the subnet to deploy the next SNS on.
+    fn get_sns_subnet_id(&self) -> Result<SubnetId, String> {
+        self.sns_subnet_ids
+            .first()
+            .cloned()
+            .ok_or_else(|| "No SNS subnet is configured for deploying SNSes.".to_string())
+    }
+}
+
+/// The wasm of the latest version of each SNS canister.
+struct LatestWasms {
+    governance: SnsWasm,
+    root: SnsWasm,
+    ledger: SnsWasm,
+    sale: SnsWasm,
 }
 
 /// This struct is responsible for storing and retrieving the wasms held by the canister
@@ -69,6 +640,15 @@ impl SnsWasmStorage {
     /// Adds a wasm to the storage
     /// Validates that the expected hash matches the sha256 hash of the WASM.
     fn add_wasm(&mut self, wasm: SnsWasm, expected_hash: &[u8; 32]) -> Result<(), String> {
+        Self::validate_wasm(&wasm, expected_hash)?;
+        self.insert_wasm(expected_hash.to_owned(), wasm);
+
+        Ok(())
+    }
+
+    /// Validates that the wasm has a valid canister type, which is returned, and that the
+    /// expected hash matches the sha256 hash of the WASM.
+    fn validate_wasm(wasm: &SnsWasm, expected_hash: &[u8; 32]) -> Result<SnsCanisterType, String> {
         if wasm.canister_type == i32::from(SnsCanisterType::Unspecified) {
             return Err("SnsWasm::canister_type cannot be 'Unspecified' (0).".to_string());
         }
@@ -89,9 +669,15 @@ impl SnsWasmStorage {
             ));
         }
 
-        self.wasm_map.insert(expected_hash.to_owned(), wasm);
+        SnsCanisterType::from_i32(wasm.canister_type).ok_or_else(|| {
+            "Invalid value for SnsWasm::canister_type.  See documentation for valid values"
+                .to_string()
+        })
+    }
 
-        Ok(())
+    /// Stores a wasm under its hash, which must have been validated before.
+    fn insert_wasm(&mut self, hash: [u8; 32], wasm: SnsWasm) {
+        self.wasm_map.insert(hash, wasm);
     }
 
     /// Retrieves a wasm by its hash.
@@ -102,11 +688,12 @@ impl SnsWasmStorage {
 
 #[cfg(test)]
 mod test {
-    use crate::pb::hash_to_hex_string;
-    use crate::pb::v1::add_wasm_response::{AddWasmError, AddWasmOk};
-    use crate::pb::v1::{add_wasm_response, AddWasm, GetWasm, SnsCanisterType};
-    use crate::sns_wasm::{SnsWasm, SnsWasmCanister, SnsWasmStorage};
+    use super::*;
+    use async_trait::async_trait;
+    use ic_base_types::PrincipalId;
     use ic_crypto_sha::Sha256;
+    use ic_sns_init::SnsInitPayloadBuilder;
+    use std::sync::Mutex;
 
     /// Provides a small wasm
     fn smallest_valid_wasm() -> SnsWasm {
@@ -282,4 +869,591 @@ mod test {
             })
         );
     }
+
+    fn add_wasm(canister: &mut SnsWasmCanister, wasm: &SnsWasm) -> AddWasmResponse {
+        canister.add_wasm(AddWasm {
+            hash: wasm.sha256_hash().to_vec(),
+            wasm: Some(wasm.clone()),
+        })
+    }
+
+    fn version(hashes: [&SnsWasm; 4]) -> SnsVersion {
+        let [root, governance, ledger, sale] = hashes;
+        SnsVersion {
+            root_wasm_hash: root.sha256_hash().to_vec(),
+            governance_wasm_hash: governance.sha256_hash().to_vec(),
+            ledger_wasm_hash: ledger.sha256_hash().to_vec(),
+            sale_wasm_hash: sale.sha256_hash().to_vec(),
+        }
+    }
+
+    fn get_next_sns_version(
+        canister: &SnsWasmCanister,
+        current_version: SnsVersion,
+    ) -> Option<SnsVersion> {
+        canister
+            .get_next_sns_version(GetNextSnsVersionRequest {
+                current_version: Some(current_version),
+            })
+            .next_version
+    }
+
+    #[test]
+    fn test_add_wasm_extends_upgrade_path() {
+        let mut canister = new_wasm_canister();
+        let root = test_wasm(SnsCanisterType::Root);
+        let governance = test_wasm(SnsCanisterType::Governance);
+        let ledger = test_wasm(SnsCanisterType::Ledger);
+        let sale = test_wasm(SnsCanisterType::Sale);
+        for wasm in [&root, &governance, &ledger, &sale] {
+            add_wasm(&mut canister, wasm);
+        }
+        let initial_version = version([&root, &governance, &ledger, &sale]);
+
+        let new_governance = SnsWasm {
+            wasm: vec![0, 0x61, 0x73, 0x6D, 2, 0, 0, 0],
+            ..governance.clone()
+        };
+        add_wasm(&mut canister, &new_governance);
+        // Adding a wasm that is already part of the latest version does not change the path.
+        add_wasm(&mut canister, &ledger);
+        let new_version = version([&root, &new_governance, &ledger, &sale]);
+
+        assert_eq!(
+            get_next_sns_version(&canister, initial_version.clone()),
+            Some(new_version.clone())
+        );
+        assert_eq!(get_next_sns_version(&canister, new_version.clone()), None);
+        assert_eq!(
+            get_next_sns_version(&canister, SnsVersion::default()),
+            Some(SnsVersion {
+                root_wasm_hash: root.sha256_hash().to_vec(),
+                ..SnsVersion::default()
+            })
+        );
+        assert_eq!(canister.upgrade_path.latest_version, new_version);
+        assert_eq!(canister.upgrade_path.upgrade_path.len(), 5);
+        assert_eq!(
+            canister.get_latest_sns_version_pretty(),
+            vec![
+                ("Root".to_string(), root.sha256_string()),
+                ("Governance".to_string(), new_governance.sha256_string()),
+                ("Ledger".to_string(), ledger.sha256_string()),
+                ("Sale".to_string(), sale.sha256_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_latest_sns_version_pretty_without_wasms() {
+        let mut canister = new_wasm_canister();
+        add_wasm(&mut canister, &test_wasm(SnsCanisterType::Ledger));
+
+        assert_eq!(
+            canister.get_latest_sns_version_pretty(),
+            vec![
+                ("Root".to_string(), "".to_string()),
+                ("Governance".to_string(), "".to_string()),
+                (
+                    "Ledger".to_string(),
+                    test_wasm(SnsCanisterType::Ledger).sha256_string()
+                ),
+                ("Sale".to_string(), "".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_add_wasm_rejects_returning_to_previous_version() {
+        let mut canister = new_wasm_canister();
+        let governance = test_wasm(SnsCanisterType::Governance);
+        let new_governance = SnsWasm {
+            wasm: vec![0, 0x61, 0x73, 0x6D, 2, 0, 0, 0],
+            ..governance.clone()
+        };
+        add_wasm(&mut canister, &governance);
+        add_wasm(&mut canister, &new_governance);
+        let upgrade_path = canister.upgrade_path.clone();
+
+        let response = add_wasm(&mut canister, &governance);
+
+        assert_eq!(
+            response.result.unwrap(),
+            add_wasm_response::Result::Error(AddWasmError {
+                error: "The Governance wasm would return the SNS to a version that is already \
+                    on the upgrade path."
+                    .to_string()
+            })
+        );
+        assert_eq!(canister.upgrade_path, upgrade_path);
+    }
+
+    #[test]
+    fn test_stable_state_round_trip() {
+        set_up_canister(None);
+        let canister = CANISTER_WRAPPER.with(|canister| canister.replace(SnsWasmCanister::new()));
+        let state = canister.to_stable_state();
+
+        let restored = SnsWasmCanister::from_stable_state(state.clone()).unwrap();
+
+        assert_eq!(restored.upgrade_path, canister.upgrade_path);
+        assert_eq!(restored.sns_subnet_ids, vec![subnet_id()]);
+        assert_eq!(
+            restored.wasm_storage.wasm_map,
+            canister.wasm_storage.wasm_map
+        );
+        assert_eq!(restored.to_stable_state(), state);
+    }
+
+    #[test]
+    fn test_stable_state_rejects_invalid_upgrade_path() {
+        let root = test_wasm(SnsCanisterType::Root);
+        let governance = test_wasm(SnsCanisterType::Governance);
+        // Upgrades two canister types at once.
+        let state = StableCanisterState {
+            wasms: vec![root.clone(), governance.clone()],
+            upgrade_path: vec![SnsUpgrade {
+                current_version: Some(SnsVersion::default()),
+                next_version: Some(SnsVersion {
+                    root_wasm_hash: root.sha256_hash().to_vec(),
+                    governance_wasm_hash: governance.sha256_hash().to_vec(),
+                    ..SnsVersion::default()
+                }),
+            }],
+            ..StableCanisterState::default()
+        };
+
+        let result = SnsWasmCanister::from_stable_state(state);
+
+        assert_eq!(
+            result.err(),
+            Some(
+                "An upgrade must change the wasm of exactly one canister type, but it changes \
+                [Root, Governance]."
+                    .to_string()
+            )
+        );
+    }
+
+    thread_local! {
+        static CANISTER_WRAPPER: RefCell<SnsWasmCanister> = RefCell::new(SnsWasmCanister::new());
+    }
+
+    /// A `CanisterApi` that records the calls made to it, and fails the call of `fail_on`.
+    /// Of the calls to `set_controllers`, only the one with index `fail_on_set_controllers`
+    /// fails.
+    #[derive(Default)]
+    struct TestCanisterApi {
+        fail_on: Option<&'static str>,
+        fail_on_set_controllers: Option<usize>,
+        set_controllers_calls: Mutex<usize>,
+        next_canister_id: Mutex<u64>,
+        created_canisters: Mutex<Vec<(SubnetId, PrincipalId, u64)>>,
+        deleted_canisters: Mutex<Vec<CanisterId>>,
+        installed_wasms: Mutex<Vec<(CanisterId, Vec<u8>)>>,
+        controllers: Mutex<Vec<(CanisterId, Vec<PrincipalId>)>>,
+        refunds: Mutex<Vec<(CanisterId, u64)>>,
+    }
+
+    impl TestCanisterApi {
+        fn failing_on(call: &'static str) -> Self {
+            Self {
+                fail_on: Some(call),
+                ..Self::default()
+            }
+        }
+
+        fn failing_on_set_controllers(index: usize) -> Self {
+            Self {
+                fail_on_set_controllers: Some(index),
+                ..Self::default()
+            }
+        }
+
+        fn check(&self, call: &'static str) -> Result<(), String> {
+            if self.fail_on == Some(call) {
+                Err(format!("{} failed", call))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    #[async_trait]
+    impl CanisterApi for TestCanisterApi {
+        fn local_canister_id(&self) -> CanisterId {
+            CanisterId::from_u64(1000)
+        }
+
+        fn now_seconds(&self) -> u64 {
+            1_650_000_000
+        }
+
+        fn message_caller(&self) -> PrincipalId {
+            caller().get()
+        }
+
+        fn accept_message_cycles(&self, cycles: u64) -> Result<(), String> {
+            assert_eq!(cycles, SNS_CREATION_FEE);
+            self.check("accept_message_cycles")
+        }
+
+        async fn send_cycles_to_canister(
+            &self,
+            canister_id: CanisterId,
+            cycles: u64,
+        ) -> Result<(), String> {
+            self.refunds.lock().unwrap().push((canister_id, cycles));
+            Ok(())
+        }
+
+        async fn create_canister(
+            &self,
+            subnet_id: SubnetId,
+            controller_id: PrincipalId,
+            cycles: u64,
+        ) -> Result<CanisterId, String> {
+            let mut next_canister_id = self.next_canister_id.lock().unwrap();
+            if *next_canister_id == 2 {
+                self.check("create_canister")?;
+            }
+            *next_canister_id += 1;
+            self.created_canisters
+                .lock()
+                .unwrap()
+                .push((subnet_id, controller_id, cycles));
+            Ok(CanisterId::from_u64(*next_canister_id))
+        }
+
+        async fn delete_canister(&self, canister_id: CanisterId) -> Result<(), String> {
+            self.deleted_canisters.lock().unwrap().push(canister_id);
+            Ok(())
+        }
+
+        async fn install_wasm(
+            &self,
+            canister_id: CanisterId,
+            wasm: Vec<u8>,
+            _init_payload: Vec<u8>,
+        ) -> Result<(), String> {
+            self.check("install_wasm")?;
+            self.installed_wasms
+                .lock()
+                .unwrap()
+                .push((canister_id, wasm));
+            Ok(())
+        }
+
+        async fn set_controllers(
+            &self,
+            canister_id: CanisterId,
+            controllers: Vec<PrincipalId>,
+        ) -> Result<(), String> {
+            let mut calls = self.set_controllers_calls.lock().unwrap();
+            let index = *calls;
+            *calls += 1;
+            if self.fail_on_set_controllers == Some(index) {
+                return Err("set_controllers failed".to_string());
+            }
+            self.controllers
+                .lock()
+                .unwrap()
+                .push((canister_id, controllers));
+            Ok(())
+        }
+    }
+
+    fn test_wasm(canister_type: SnsCanisterType) -> SnsWasm {
+        SnsWasm {
+            wasm: vec![0, 0x61, 0x73, 0x6D, 1, 0, 0, canister_type as u8],
+            canister_type: canister_type.into(),
+        }
+    }
+
+    fn caller() -> CanisterId {
+        CanisterId::from_u64(2000)
+    }
+
+    fn subnet_id() -> SubnetId {
+        SubnetId::from(PrincipalId::new_user_test_id(1))
+    }
+
+    /// Sets up CANISTER_WRAPPER with an SNS subnet and a wasm for all SNS canister types except
+    /// for `missing_wasm`.
+    fn set_up_canister(missing_wasm: Option<SnsCanisterType>) {
+        CANISTER_WRAPPER.with(|canister| {
+            let mut canister = canister.borrow_mut();
+            *canister = SnsWasmCanister::new();
+            canister.set_sns_subnets(vec![subnet_id()]);
+            for canister_type in [
+                SnsCanisterType::Governance,
+                SnsCanisterType::Root,
+                SnsCanisterType::Ledger,
+                SnsCanisterType::Sale,
+            ] {
+                if Some(canister_type) == missing_wasm {
+                    continue;
+                }
+                let wasm = test_wasm(canister_type);
+                let response = canister.add_wasm(AddWasm {
+                    hash: wasm.sha256_hash().to_vec(),
+                    wasm: Some(wasm),
+                });
+                assert!(matches!(
+                    response.result,
+                    Some(add_wasm_response::Result::Ok(_))
+                ));
+            }
+        });
+    }
+
+    fn deploy_new_sns_payload() -> DeployNewSns {
+        DeployNewSns {
+            sns_init_payload: Some(SnsInitPayloadBuilder::new().build().unwrap()),
+        }
+    }
+
+    fn list_deployed_snses() -> Vec<DeployedSns> {
+        CANISTER_WRAPPER.with(|canister| {
+            canister
+                .borrow()
+                .list_deployed_snses(ListDeployedSnses {})
+                .instances
+        })
+    }
+
+    #[tokio::test]
+    async fn test_deploy_new_sns_creates_and_wires_canisters() {
+        set_up_canister(None);
+        let canister_api = TestCanisterApi::default();
+
+        let response = SnsWasmCanister::deploy_new_sns(
+            &CANISTER_WRAPPER,
+            &canister_api,
+            deploy_new_sns_payload(),
+        )
+        .await;
+
+        let (governance, root, ledger, sale) = (
+            CanisterId::from_u64(1),
+            CanisterId::from_u64(2),
+            CanisterId::from_u64(3),
+            CanisterId::from_u64(4),
+        );
+        let canisters = SnsCanisterIds {
+            governance: Some(governance.get()),
+            root: Some(root.get()),
+            ledger: Some(ledger.get()),
+            sale: Some(sale.get()),
+        };
+        assert_eq!(
+            response,
+            DeployNewSnsResponse {
+                subnet_id: Some(subnet_id().get()),
+                canisters: Some(canisters.clone()),
+                error: None,
+            }
+        );
+
+        let local_canister_id = canister_api.local_canister_id().get();
+        assert_eq!(
+            *canister_api.created_canisters.lock().unwrap(),
+            vec![(subnet_id(), local_canister_id, SNS_CANISTER_CREATION_CYCLES); 4]
+        );
+        assert_eq!(
+            *canister_api.installed_wasms.lock().unwrap(),
+            vec![
+                (governance, test_wasm(SnsCanisterType::Governance).wasm),
+                (root, test_wasm(SnsCanisterType::Root).wasm),
+                (ledger, test_wasm(SnsCanisterType::Ledger).wasm),
+                (sale, test_wasm(SnsCanisterType::Sale).wasm),
+            ]
+        );
+        // This canister stays a co-controller until all canisters are wired.
+        assert_eq!(
+            *canister_api.controllers.lock().unwrap(),
+            vec![
+                (governance, vec![root.get(), local_canister_id]),
+                (ledger, vec![root.get(), local_canister_id]),
+                (sale, vec![root.get(), local_canister_id]),
+                (root, vec![governance.get(), local_canister_id]),
+                (governance, vec![root.get()]),
+                (ledger, vec![root.get()]),
+                (sale, vec![root.get()]),
+                (root, vec![governance.get()]),
+            ]
+        );
+        assert!(canister_api.deleted_canisters.lock().unwrap().is_empty());
+        assert!(canister_api.refunds.lock().unwrap().is_empty());
+
+        assert_eq!(
+            list_deployed_snses(),
+            vec![DeployedSns {
+                canisters: Some(canisters),
+                subnet_id: Some(subnet_id().get()),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_deploy_new_sns_fails_on_invalid_payload() {
+        set_up_canister(None);
+        let canister_api = TestCanisterApi::default();
+
+        let invalid_sns_init_payload = SnsInitPayload {
+            min_participants: Some(0),
+            ..SnsInitPayload::default()
+        };
+        for sns_init_payload in [None, Some(invalid_sns_init_payload)] {
+            let response = SnsWasmCanister::deploy_new_sns(
+                &CANISTER_WRAPPER,
+                &canister_api,
+                DeployNewSns { sns_init_payload },
+            )
+            .await;
+
+            assert!(response.error.is_some());
+            assert!(response.canisters.is_none());
+        }
+        assert!(canister_api.created_canisters.lock().unwrap().is_empty());
+        assert!(list_deployed_snses().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_deploy_new_sns_fails_on_missing_wasm() {
+        set_up_canister(Some(SnsCanisterType::Sale));
+        let canister_api = TestCanisterApi::default();
+
+        let response = SnsWasmCanister::deploy_new_sns(
+            &CANISTER_WRAPPER,
+            &canister_api,
+            deploy_new_sns_payload(),
+        )
+        .await;
+
+        assert_eq!(
+            response.error,
+            Some(SnsWasmError {
+                message: "No wasm of canister type Sale has been added yet.".to_string()
+            })
+        );
+        assert!(canister_api.created_canisters.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_deploy_new_sns_fails_without_cycles() {
+        set_up_canister(None);
+        let canister_api = TestCanisterApi::failing_on("accept_message_cycles");
+
+        let response = SnsWasmCanister::deploy_new_sns(
+            &CANISTER_WRAPPER,
+            &canister_api,
+            deploy_new_sns_payload(),
+        )
+        .await;
+
+        assert!(response.error.is_some());
+        assert!(canister_api.created_canisters.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_deploy_new_sns_cleans_up_after_failure() {
+        // The cycles given to the deleted canisters are burned, so nothing is refunded once all
+        // four canisters have been created.
+        for (failing_call, expected_deleted, expected_refunds) in [
+            (
+                "create_canister",
+                2,
+                vec![(caller(), 2 * SNS_CANISTER_CREATION_CYCLES)],
+            ),
+            ("install_wasm", 4, vec![]),
+        ] {
+            set_up_canister(None);
+            let canister_api = TestCanisterApi::failing_on(failing_call);
+
+            let response = SnsWasmCanister::deploy_new_sns(
+                &CANISTER_WRAPPER,
+                &canister_api,
+                deploy_new_sns_payload(),
+            )
+            .await;
+
+            assert_eq!(
+                response.error,
+                Some(SnsWasmError {
+                    message: format!("Could not deploy the SNS: {} failed", failing_call)
+                })
+            );
+            assert_eq!(
+                *canister_api.deleted_canisters.lock().unwrap(),
+                (1..=expected_deleted)
+                    .map(CanisterId::from_u64)
+                    .collect::<Vec<_>>()
+            );
+            assert_eq!(*canister_api.refunds.lock().unwrap(), expected_refunds);
+            assert!(list_deployed_snses().is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_deploy_new_sns_cleans_up_after_failure_to_wire_canisters() {
+        // Whichever canister fails to be wired, this canister still controls all of them.
+        for index in 0..4 {
+            set_up_canister(None);
+            let canister_api = TestCanisterApi::failing_on_set_controllers(index);
+
+            let response = SnsWasmCanister::deploy_new_sns(
+                &CANISTER_WRAPPER,
+                &canister_api,
+                deploy_new_sns_payload(),
+            )
+            .await;
+
+            assert_eq!(
+                response.error,
+                Some(SnsWasmError {
+                    message: "Could not deploy the SNS: set_controllers failed".to_string()
+                })
+            );
+            assert!(response.canisters.is_none());
+            assert_eq!(
+                *canister_api.deleted_canisters.lock().unwrap(),
+                (1..=4).map(CanisterId::from_u64).collect::<Vec<_>>()
+            );
+            assert!(canister_api.refunds.lock().unwrap().is_empty());
+            assert!(list_deployed_snses().is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_deploy_new_sns_keeps_wired_sns_if_hand_over_fails() {
+        for index in 4..8 {
+            set_up_canister(None);
+            let canister_api = TestCanisterApi::failing_on_set_controllers(index);
+
+            let response = SnsWasmCanister::deploy_new_sns(
+                &CANISTER_WRAPPER,
+                &canister_api,
+                deploy_new_sns_payload(),
+            )
+            .await;
+
+            // The wired SNS is kept, but the error says which canister is still co-controlled.
+            let canister_id = [1, 3, 4, 2][index - 4];
+            assert_eq!(
+                response.error,
+                Some(SnsWasmError {
+                    message: format!(
+                        "The SNS was deployed, but not fully handed over:\n\
+                        Could not remove this canister as a controller of {}: \
+                        set_controllers failed",
+                        CanisterId::from_u64(canister_id)
+                    )
+                })
+            );
+            assert!(response.canisters.is_some());
+            assert!(canister_api.deleted_canisters.lock().unwrap().is_empty());
+            assert_eq!(list_deployed_snses().len(), 1);
+        }
+    }
 }
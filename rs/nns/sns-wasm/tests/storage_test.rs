@@ -3,7 +3,9 @@ use canister_test::{PrincipalId, Project, Wasm};
 use dfn_core::CanisterId;
 use ic_sns_wasm::pb::v1::add_wasm_response::AddWasmOk;
 use ic_sns_wasm::pb::v1::{
-    add_wasm_response, AddWasm, AddWasmResponse, GetWasm, GetWasmResponse, SnsCanisterType, SnsWasm,
+    add_wasm_response, AddWasm, AddWasmResponse, GetNextSnsVersionRequest,
+    GetNextSnsVersionResponse, GetWasm, GetWasmResponse, SnsCanisterType, SnsVersion, SnsWasm,
+    SnsWasmCanisterInitPayload,
 };
 use ic_state_machine_tests::{CanisterSettingsArgs, StateMachine, WasmResult};
 use std::vec::Vec;
@@ -113,7 +115,12 @@ fn test_basic_storage() {
         &[], // features
     );
     // Step 1.b: Build and install canister.
-    let sns_wasm_id = create_canister(&env, wasm, None, None);
+    let sns_wasm_id = create_canister(
+        &env,
+        wasm,
+        Some(Encode!(&SnsWasmCanisterInitPayload::default()).unwrap()),
+        None,
+    );
 
     let sns_wasm = smallest_valid_wasm();
     let expected_hash = sns_wasm.sha256_hash();
@@ -137,3 +144,84 @@ fn test_basic_storage() {
     assert!(get_wasm_response.wasm.is_some());
     assert_eq!(expected_hash, get_wasm_response.wasm.unwrap().sha256_hash());
 }
+
+fn get_latest_sns_version_pretty(
+    env: &StateMachine,
+    canister_id: CanisterId,
+) -> Vec<(String, String)> {
+    let response_bytes = query(
+        env,
+        canister_id,
+        "get_latest_sns_version_pretty",
+        Encode!(&()).unwrap(),
+        None,
+    )
+    .unwrap();
+
+    Decode!(&response_bytes, Vec<(String, String)>).unwrap()
+}
+
+fn get_next_sns_version(
+    env: &StateMachine,
+    canister_id: CanisterId,
+    current_version: SnsVersion,
+) -> GetNextSnsVersionResponse {
+    let response_bytes = query(
+        env,
+        canister_id,
+        "get_next_sns_version",
+        Encode!(&GetNextSnsVersionRequest {
+            current_version: Some(current_version)
+        })
+        .unwrap(),
+        None,
+    )
+    .unwrap();
+
+    Decode!(&response_bytes, GetNextSnsVersionResponse).unwrap()
+}
+
+#[test]
+fn test_state_is_preserved_across_upgrades() {
+    let env = StateMachine::new();
+    let wasm = Project::cargo_bin_maybe_use_path_relative_to_rs(
+        "nns/sns-wasm",
+        "sns-wasm-canister",
+        &[], // features
+    );
+    let sns_wasm_id = create_canister(
+        &env,
+        wasm.clone(),
+        Some(Encode!(&SnsWasmCanisterInitPayload::default()).unwrap()),
+        None,
+    );
+
+    let sns_wasm = smallest_valid_wasm();
+    let expected_hash = sns_wasm.sha256_hash();
+    add_wasm(&env, sns_wasm_id, sns_wasm, &expected_hash);
+    let latest_version_pretty = get_latest_sns_version_pretty(&env, sns_wasm_id);
+    assert_eq!(
+        latest_version_pretty[1],
+        (
+            "Governance".to_string(),
+            smallest_valid_wasm().sha256_string()
+        )
+    );
+
+    env.upgrade_canister(sns_wasm_id, wasm.bytes(), Encode!().unwrap())
+        .unwrap();
+
+    let get_wasm_response = get_wasm(&env, sns_wasm_id, &expected_hash);
+    assert_eq!(get_wasm_response.wasm.unwrap().sha256_hash(), expected_hash);
+    assert_eq!(
+        get_latest_sns_version_pretty(&env, sns_wasm_id),
+        latest_version_pretty
+    );
+    assert_eq!(
+        get_next_sns_version(&env, sns_wasm_id, SnsVersion::default()).next_version,
+        Some(SnsVersion {
+            governance_wasm_hash: expected_hash.to_vec(),
+            ..SnsVersion::default()
+        })
+    );
+}
@@ -47,6 +47,19 @@ pub struct OrchestratorArgs {
     #[clap(long, parse(from_os_str))]
     pub(crate) version_file: PathBuf,
 
+    /// After booting into a new replica version, the time window (in
+    /// seconds) within which the replica must become healthy, i.e., make
+    /// progress on catch-up packages. Otherwise, the node rolls back to the
+    /// previous image.
+    #[clap(long, default_value = "1800")]
+    pub(crate) upgrade_health_check_window_secs: u64,
+
+    /// The maximum number of replica process exits tolerated within the
+    /// upgrade health check window before the node rolls back to the
+    /// previous image.
+    #[clap(long, default_value = "5")]
+    pub(crate) upgrade_max_replica_exits: u64,
+
     /// Print the replica's current node ID.
     #[clap(long)]
     pub node_id: bool,
@@ -1,4 +1,4 @@
-use prometheus::{IntCounter, IntGauge};
+use prometheus::{IntCounter, IntGauge, IntGaugeVec};
 
 pub const PROMETHEUS_HTTP_PORT: u16 = 9091;
 
@@ -10,6 +10,10 @@ pub struct OrchestratorMetrics {
     pub datacenter_registry_version: IntGauge,
     pub ssh_access_registry_version: IntGauge,
     pub firewall_registry_version: IntGauge,
+    /// 1 while the health of a freshly upgraded replica is being monitored
+    pub upgrade_health_check_pending: IntGauge,
+    /// Set to 1 for the replica version whose upgrade was rolled back
+    pub upgrade_rolled_back_version: IntGaugeVec,
 }
 
 impl OrchestratorMetrics {
@@ -35,6 +39,15 @@ impl OrchestratorMetrics {
                 "firewall_registry_version",
                 "Latest registry version used for firewall configuration",
             ),
+            upgrade_health_check_pending: metrics_registry.int_gauge(
+                "orchestrator_upgrade_health_check_pending",
+                "1 if the replica is still being monitored after an upgrade, 0 otherwise",
+            ),
+            upgrade_rolled_back_version: metrics_registry.int_gauge_vec(
+                "orchestrator_upgrade_rolled_back_version",
+                "1 for the replica version that failed to become healthy and was rolled back",
+                &["version"],
+            ),
         }
     }
 }
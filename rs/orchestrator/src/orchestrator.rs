@@ -140,6 +140,15 @@ impl Orchestrator {
             logger.clone(),
         ));
 
+        let (metrics, _metrics_runtime) = Self::get_metrics(
+            metrics_addr,
+            &slog_logger,
+            &metrics_registry,
+            registry.get_registry_client(),
+            crypto.clone(),
+        );
+        let metrics = Arc::new(metrics);
+
         let upgrade = Some(
             Upgrade::new(
                 Arc::clone(&registry),
@@ -152,19 +161,14 @@ impl Orchestrator {
                 registry_replicator,
                 args.replica_binary_dir.clone(),
                 logger.clone(),
+                Arc::clone(&metrics),
+                Duration::from_secs(args.upgrade_health_check_window_secs),
+                args.upgrade_max_replica_exits,
+                registration,
             )
             .await,
         );
 
-        let (metrics, _metrics_runtime) = Self::get_metrics(
-            metrics_addr,
-            &slog_logger,
-            &metrics_registry,
-            registry.get_registry_client(),
-            crypto.clone(),
-        );
-        let metrics = Arc::new(metrics);
-
         let firewall_deprecated = Some(FirewallDeprecated::new(
             Arc::clone(&registry),
             Arc::clone(&metrics),
@@ -19,10 +19,11 @@ use ic_nns_constants::REGISTRY_CANISTER_ID;
 use ic_protobuf::registry::crypto::v1::PublicKey;
 use ic_registry_local_store::LocalStore;
 use ic_sys::utility_command::UtilityCommand;
-use ic_types::{messages::MessageId, NodeId};
+use ic_types::{messages::MessageId, NodeId, ReplicaVersion};
 use prost::Message;
 use rand::prelude::*;
 use registry_canister::mutations::do_update_node_directly::UpdateNodeDirectlyPayload;
+use registry_canister::mutations::do_update_node_rolled_back_version_directly::UpdateNodeRolledBackVersionDirectlyPayload;
 use registry_canister::mutations::node_management::do_add_node::AddNodePayload;
 use std::net::IpAddr;
 use std::sync::Arc;
@@ -199,11 +200,92 @@ impl NodeRegistration {
     }
 
     async fn try_to_register_additional_key(&self, idkg_pk: PublicKey) {
+        let agent = match self.node_agent().await {
+            Some(agent) => agent,
+            None => return,
+        };
+        let update_node_payload = UpdateNodeDirectlyPayload {
+            idkg_dealing_encryption_pk: Some(protobuf_to_vec(idkg_pk)),
+        };
+
+        if let Err(e) = agent
+            .execute_update(
+                &REGISTRY_CANISTER_ID,
+                "update_node_directly",
+                Encode!(&update_node_payload)
+                    .expect("Could not encode payload for update_node-call."),
+                generate_nonce(),
+            )
+            .await
+        {
+            warn!(
+                self.log,
+                "Error when sending register additional key request: {:?}", e
+            );
+        }
+    }
+
+    /// Publishes the replica version whose upgrade this node rolled back in
+    /// the node's registry record, or clears it if `rolled_back_version` is
+    /// `None`. Nothing is sent if the registry already has this value.
+    pub(crate) async fn publish_rolled_back_version(
+        &self,
+        rolled_back_version: Option<&ReplicaVersion>,
+    ) {
+        use ic_registry_client_helpers::node::NodeRegistry;
+        let registry_version = self.registry_client.get_latest_version();
+        let published = match self
+            .registry_client
+            .get_transport_info(self.node_id, registry_version)
+        {
+            Ok(Some(node_record)) => node_record.rolled_back_replica_version_id,
+            Ok(None) => return, // not registered (anymore), nothing to publish
+            Err(e) => {
+                warn!(self.log, "Registry error: {:?}", e);
+                return;
+            }
+        };
+        let rolled_back_replica_version_id = rolled_back_version.map(|version| version.to_string());
+        if published == rolled_back_replica_version_id.clone().unwrap_or_default() {
+            return;
+        }
+
+        let agent = match self.node_agent().await {
+            Some(agent) => agent,
+            None => return,
+        };
+        let payload = UpdateNodeRolledBackVersionDirectlyPayload {
+            rolled_back_replica_version_id,
+        };
+        match agent
+            .execute_update(
+                &REGISTRY_CANISTER_ID,
+                "update_node_rolled_back_version_directly",
+                Encode!(&payload).expect("Could not encode payload for rolled back version."),
+                generate_nonce(),
+            )
+            .await
+        {
+            Ok(_) => info!(
+                self.log,
+                "Published rolled back replica version {:?} in the registry",
+                payload.rolled_back_replica_version_id
+            ),
+            Err(e) => warn!(
+                self.log,
+                "Error when publishing rolled back replica version: {:?}", e
+            ),
+        }
+    }
+
+    /// Returns an agent for one of the NNS nodes that signs requests with
+    /// this node's signing key.
+    async fn node_agent(&self) -> Option<Agent> {
         let nns_urls = match self.collect_nns_urls().await {
             Ok(urls) => urls,
             Err(e) => {
                 warn!(self.log, "Error collecting URLs: {:?}", e);
-                return;
+                return None;
             }
         };
 
@@ -213,7 +295,7 @@ impl NodeRegistration {
             pk
         } else {
             warn!(self.log, "Missing node signing key.");
-            return; // missing signing key, can't continue
+            return None; // missing signing key, can't continue
         };
 
         let key_handler = self.key_handler.clone();
@@ -230,26 +312,7 @@ impl NodeRegistration {
             sign: Arc::new(sign_cmd),
         };
 
-        let agent = Agent::new(nns_urls[0].clone(), sender);
-        let update_node_payload = UpdateNodeDirectlyPayload {
-            idkg_dealing_encryption_pk: Some(protobuf_to_vec(idkg_pk)),
-        };
-
-        if let Err(e) = agent
-            .execute_update(
-                &REGISTRY_CANISTER_ID,
-                "update_node_directly",
-                Encode!(&update_node_payload)
-                    .expect("Could not encode payload for update_node-call."),
-                generate_nonce(),
-            )
-            .await
-        {
-            warn!(
-                self.log,
-                "Error when sending register additional key request: {:?}", e
-            );
-        }
+        Some(Agent::new(nns_urls[0].clone(), sender))
     }
 
     async fn collect_nns_urls(&self) -> Result<Vec<Url>, String> {
@@ -4,6 +4,7 @@ use nix::{
     unistd::Pid,
 };
 use slog::{debug, info, warn};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Mutex;
 use std::{io::Result, sync::Arc};
 
@@ -21,6 +22,8 @@ pub(crate) struct ReplicaProcess {
     pub(crate) pid_cell: PIDCell,
     pub(crate) log: slog::Logger,
     pub(crate) join_handle: Option<std::thread::JoinHandle<()>>,
+    /// The number of times a started replica process has exited.
+    pub(crate) exit_count: Arc<AtomicU64>,
 }
 
 impl ReplicaProcess {
@@ -30,9 +33,16 @@ impl ReplicaProcess {
             pid_cell: Default::default(),
             log: logger.clone(),
             join_handle: None,
+            exit_count: Default::default(),
         }
     }
 
+    /// Returns how many times a replica process started by this process
+    /// manager has exited so far.
+    pub fn exit_count(&self) -> u64 {
+        self.exit_count.load(Ordering::Relaxed)
+    }
+
     /// Returns true only if the replica process is running.
     pub fn is_running(&self) -> bool {
         self.get_pid().is_some()
@@ -170,6 +180,7 @@ impl ReplicaProcess {
                 self.log.clone(),
                 child,
                 self.pid_cell.clone(),
+                self.exit_count.clone(),
             )));
         }
         Ok(())
@@ -182,6 +193,7 @@ fn wait_on_exit(
     log: slog::Logger,
     mut process: std::process::Child,
     pid_cell: PIDCell,
+    exit_count: Arc<AtomicU64>,
 ) -> impl FnOnce() {
     move || {
         let exit_status = process.wait();
@@ -190,6 +202,7 @@ fn wait_on_exit(
         } else {
             info!(log, "Replica exited. Exit Status: {:?}", exit_status);
         }
+        exit_count.fetch_add(1, Ordering::Relaxed);
         let _pid = pid_cell.lock().unwrap().take();
     }
 }
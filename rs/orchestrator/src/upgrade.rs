@@ -1,7 +1,13 @@
 use crate::catch_up_package_provider::CatchUpPackageProvider;
 use crate::error::{OrchestratorError, OrchestratorResult};
+use crate::metrics::OrchestratorMetrics;
+use crate::registration::NodeRegistration;
 use crate::registry_helper::RegistryHelper;
 use crate::replica_process::ReplicaProcess;
+use crate::upgrade_health::{
+    read_rolled_back_version, rolled_back_version_path, write_rolled_back_version, UpgradeHealth,
+    UpgradeHealthCheck,
+};
 use ic_http_utils::file_downloader::FileDownloader;
 use ic_interfaces::registry::RegistryClient;
 use ic_logger::{error, info, warn, ReplicaLogger};
@@ -16,6 +22,7 @@ use std::convert::TryFrom;
 use std::path::PathBuf;
 use std::process::exit;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::process::Command;
 
 /// Provides function to continuously check the Registry to determine if this
@@ -35,6 +42,14 @@ pub(crate) struct Upgrade {
     node_id: NodeId,
     /// The replica version that is prepared by 'prepare_upgrade' to upgrade to.
     prepared_upgrade_version: Option<ReplicaVersion>,
+    metrics: Arc<OrchestratorMetrics>,
+    /// Set while the boot into a new replica version is not confirmed yet.
+    health_check: Option<UpgradeHealthCheck>,
+    /// A replica version whose upgrade was rolled back. We do not upgrade to
+    /// this version again.
+    rolled_back_version: Option<ReplicaVersion>,
+    /// Used to publish the rolled back version in the node's registry record.
+    registration: NodeRegistration,
 }
 
 impl Upgrade {
@@ -50,8 +65,24 @@ impl Upgrade {
         registry_replicator: Arc<RegistryReplicator>,
         release_content_dir: PathBuf,
         logger: ReplicaLogger,
+        metrics: Arc<OrchestratorMetrics>,
+        health_check_window: Duration,
+        max_replica_exits: u64,
+        registration: NodeRegistration,
     ) -> Self {
-        let value = Self {
+        let rolled_back_version =
+            read_rolled_back_version(&rolled_back_version_path(&release_content_dir));
+        if let Some(version) = &rolled_back_version {
+            warn!(
+                logger,
+                "The upgrade to replica version {} was rolled back", version
+            );
+            metrics
+                .upgrade_rolled_back_version
+                .with_label_values(&[version.as_ref()])
+                .set(1);
+        }
+        let mut value = Self {
             registry,
             replica_process,
             cup_provider,
@@ -63,14 +94,46 @@ impl Upgrade {
             registry_replicator,
             logger,
             prepared_upgrade_version: None,
+            metrics,
+            health_check: None,
+            rolled_back_version,
+            registration,
         };
-        value.confirm_boot().await;
+        if value.is_boot_confirmed().await {
+            value.confirm_boot().await;
+        } else {
+            info!(
+                value.logger,
+                "Booted into replica version {} for the first time; confirming the boot once \
+                the replica is healthy (timeout: {:?})",
+                value.replica_version,
+                health_check_window
+            );
+            let initial_cup_height = value
+                .cup_provider
+                .get_local_cup()
+                .map(|cup| cup.cup.content.height());
+            let initial_replica_exits = value.replica_process.lock().unwrap().exit_count();
+            value.health_check = Some(UpgradeHealthCheck::new(
+                Instant::now(),
+                health_check_window,
+                max_replica_exits,
+                initial_cup_height,
+                initial_replica_exits,
+            ));
+            value.metrics.upgrade_health_check_pending.set(1);
+        }
         value
     }
 
     /// Checks for a new release package, and if found, upgrades to this release
     /// package
     pub(crate) async fn check(&mut self) -> OrchestratorResult<Option<SubnetId>> {
+        // Keep the node's registry record in sync with the rolled back version,
+        // so that a rollback is visible outside of this node's metrics.
+        self.registration
+            .publish_rolled_back_version(self.rolled_back_version.as_ref())
+            .await;
         let latest_registry_version = self.registry.get_latest_version();
         // Determine the subnet_id using the local CUP.
         let (subnet_id, local_cup) = if let Some(cup) = self.cup_provider.get_local_cup() {
@@ -92,6 +155,9 @@ impl Upgrade {
                 // If no subnet is assigned to the node id, we're unassigned.
                 _ => {
                     self.check_for_upgrade_as_unassigned().await?;
+                    // Unassigned nodes run no replica, so there is nothing to
+                    // monitor after an upgrade.
+                    self.confirm_healthy_upgrade().await;
                     return Ok(None);
                 }
             }
@@ -147,6 +213,7 @@ impl Upgrade {
             .registry
             .get_replica_version(subnet_id, cup_registry_version)?;
         if new_replica_version != self.replica_version {
+            self.check_not_rolled_back(&new_replica_version)?;
             info!(
                 self.logger,
                 "Starting version upgrade: {} -> {}", self.replica_version, new_replica_version
@@ -165,6 +232,11 @@ impl Upgrade {
         // This will start a new replica process if none is running.
         self.ensure_replica_is_running(&self.replica_version, subnet_id)?;
 
+        // If we just upgraded, confirm the boot if the replica is healthy or
+        // roll back if it failed to become healthy.
+        self.check_upgrade_health(cup.cup.content.height(), subnet_id, latest_registry_version)
+            .await?;
+
         // This will trigger an image download if one is already scheduled but we did
         // not arrive at the corresponding CUP yet.
         self.prepare_upgrade_if_scheduled(subnet_id).await?;
@@ -234,6 +306,7 @@ impl Upgrade {
             .registry
             .get_replica_version(subnet_id, registry_version)?;
         if new_replica_version != self.replica_version {
+            self.check_not_rolled_back(&new_replica_version)?;
             info!(
                 self.logger,
                 "Version upgrade detected: {} -> {}", self.replica_version, new_replica_version
@@ -257,6 +330,7 @@ impl Upgrade {
                 if self.replica_version == replica_version {
                     return Ok(());
                 }
+                self.check_not_rolled_back(&replica_version)?;
                 info!(
                     self.logger,
                     "Replica upgrade on unassigned node detected: old version {}, new version {}",
@@ -385,9 +459,147 @@ impl Upgrade {
             })
     }
 
+    // Returns an error if an upgrade to the given version was rolled back
+    // before.
+    fn check_not_rolled_back(&self, replica_version: &ReplicaVersion) -> OrchestratorResult<()> {
+        if self.rolled_back_version.as_ref() == Some(replica_version) {
+            return Err(OrchestratorError::UpgradeError(format!(
+                "Not upgrading to replica version {}, as a previous upgrade to it was rolled back",
+                replica_version
+            )));
+        }
+        Ok(())
+    }
+
+    // Evaluates the health of the replica if the boot into the current
+    // version is not confirmed yet. Confirms the boot if the replica is
+    // healthy and rolls back to the previous image if it is unhealthy.
+    // The health window is restarted while the subnet is halted.
+    async fn check_upgrade_health(
+        &mut self,
+        cup_height: Height,
+        subnet_id: SubnetId,
+        registry_version: RegistryVersion,
+    ) -> OrchestratorResult<()> {
+        let health_check = match &mut self.health_check {
+            Some(health_check) => health_check,
+            None => return Ok(()),
+        };
+        let subnet_halted = self
+            .registry
+            .get_subnet_record(subnet_id, registry_version)?
+            .is_halted;
+        let replica_exits = self.replica_process.lock().unwrap().exit_count();
+        let health =
+            health_check.evaluate(Instant::now(), cup_height, replica_exits, subnet_halted);
+        match health {
+            UpgradeHealth::Pending => Ok(()),
+            UpgradeHealth::Healthy => {
+                self.confirm_healthy_upgrade().await;
+                Ok(())
+            }
+            UpgradeHealth::Unhealthy(reason) => self.rollback(reason).await,
+        }
+    }
+
+    // Confirms the boot into the current version if it was pending and
+    // forgets about previously rolled back versions.
+    async fn confirm_healthy_upgrade(&mut self) {
+        if self.health_check.take().is_none() {
+            return;
+        }
+        info!(
+            self.logger,
+            "Replica version {} is healthy, confirming the boot", self.replica_version
+        );
+        self.confirm_boot().await;
+        self.metrics.upgrade_health_check_pending.set(0);
+        if let Some(version) = self.rolled_back_version.take() {
+            self.metrics
+                .upgrade_rolled_back_version
+                .with_label_values(&[version.as_ref()])
+                .set(0);
+            let path = rolled_back_version_path(&self.release_content_dir);
+            if let Err(err) = std::fs::remove_file(&path) {
+                warn!(self.logger, "Couldn't delete {:?}: {:?}", path, err);
+            }
+        }
+    }
+
+    // Persists the current version as rolled back and reboots into the
+    // previous image, which has not been overwritten as the boot into the
+    // current version was never confirmed.
+    async fn rollback<T>(&mut self, reason: String) -> OrchestratorResult<T> {
+        error!(
+            self.logger,
+            "Replica version {} failed to become healthy: {}. Rolling back to the previous image.",
+            self.replica_version,
+            reason
+        );
+        let path = rolled_back_version_path(&self.release_content_dir);
+        write_rolled_back_version(&path, &self.replica_version)
+            .map_err(|e| OrchestratorError::file_write_error(&path, e))?;
+        self.metrics
+            .upgrade_rolled_back_version
+            .with_label_values(&[self.replica_version.as_ref()])
+            .set(1);
+        // Best effort, as the rolled back version is published again after
+        // the reboot.
+        self.registration
+            .publish_rolled_back_version(Some(&self.replica_version))
+            .await;
+        if let Err(e) = self.stop_replica() {
+            warn!(self.logger, "Failed to stop replica with error {:?}", e);
+        }
+
+        let mut c = Command::new(self.ic_binary_dir.join("manageboot.sh").into_os_string());
+        let out = c
+            .arg("rollback")
+            .output()
+            .await
+            .map_err(|e| OrchestratorError::file_command_error(e, &c))?;
+        if !out.status.success() {
+            warn!(self.logger, "rollback has failed");
+            Err(OrchestratorError::UpgradeError(
+                "rollback failed".to_string(),
+            ))
+        } else {
+            info!(self.logger, "Rebooting {:?}", out);
+            exit(42);
+        }
+    }
+
+    // Returns false if the current boot is the first boot into a newly
+    // installed image and has not been confirmed yet, i.e., the bootloader
+    // would boot the previous image next. If the boot state cannot be
+    // determined (e.g., outside of a guest OS), the boot counts as confirmed.
+    async fn is_boot_confirmed(&self) -> bool {
+        let script = self.ic_binary_dir.join("manageboot.sh");
+        let mut alternatives = Vec::new();
+        for action in ["current", "next"] {
+            match Command::new(script.clone().into_os_string())
+                .arg(action)
+                .output()
+                .await
+            {
+                Ok(out) if out.status.success() => {
+                    alternatives.push(String::from_utf8_lossy(&out.stdout).trim().to_string())
+                }
+                other => {
+                    warn!(
+                        self.logger,
+                        "Could not determine the boot state: {:?}", other
+                    );
+                    return true;
+                }
+            }
+        }
+        alternatives[0] == alternatives[1]
+    }
+
     // Calls a corresponding script to "confirm" that the base OS could boot
-    // successfully. With a confirmation the image will be reverted on the next
-    // restart.
+    // successfully. Without a confirmation the image will be reverted on the
+    // next restart.
     async fn confirm_boot(&self) {
         if let Err(err) = Command::new(self.ic_binary_dir.join("manageboot.sh").into_os_string())
             .arg("confirm")
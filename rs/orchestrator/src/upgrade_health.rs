@@ -0,0 +1,196 @@
+//! Monitoring of a replica that has just been upgraded, but whose boot has not
+//! been confirmed yet.
+//!
+//! After `manageboot.sh upgrade-commit` and a reboot, the bootloader runs the
+//! new image in a "failsafe" state: unless the boot is confirmed, the next
+//! reboot falls back to the previous image. The orchestrator only confirms the
+//! boot once the new replica is healthy, and triggers a rollback if it does
+//! not become healthy within a configurable window.
+
+use ic_types::{Height, ReplicaVersion};
+use std::convert::TryFrom;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// The name of the file, relative to the release content directory, in which
+/// the version of a rolled back upgrade is persisted across the reboot.
+const ROLLED_BACK_VERSION_FILE: &str = "rolled_back_replica_version.txt";
+
+/// The health of a freshly upgraded replica.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum UpgradeHealth {
+    /// The replica has not shown progress yet, but there is time left.
+    Pending,
+    /// The replica made progress; the boot can be confirmed.
+    Healthy,
+    /// The replica failed to become healthy for the given reason.
+    Unhealthy(String),
+}
+
+/// Tracks the health of a replica after booting into a new image.
+pub(crate) struct UpgradeHealthCheck {
+    deadline: Instant,
+    window: Duration,
+    max_replica_exits: u64,
+    initial_cup_height: Option<Height>,
+    initial_replica_exits: u64,
+}
+
+impl UpgradeHealthCheck {
+    pub(crate) fn new(
+        now: Instant,
+        window: Duration,
+        max_replica_exits: u64,
+        initial_cup_height: Option<Height>,
+        initial_replica_exits: u64,
+    ) -> Self {
+        Self {
+            deadline: now + window,
+            window,
+            max_replica_exits,
+            initial_cup_height,
+            initial_replica_exits,
+        }
+    }
+
+    /// Evaluates the replica's health given the height of the latest local
+    /// CUP, the total number of replica process exits observed so far and
+    /// whether the subnet is currently halted.
+    ///
+    /// The replica is healthy as soon as a CUP higher than the one present at
+    /// boot time has been obtained, i.e., the subnet, including this node,
+    /// made progress. It is unhealthy if its process exited too often or if
+    /// the deadline passed without progress. A halted subnet makes no
+    /// progress by design, e.g., after a recovery upgrade, so the window only
+    /// starts once the subnet is unhalted.
+    pub(crate) fn evaluate(
+        &mut self,
+        now: Instant,
+        cup_height: Height,
+        replica_exits: u64,
+        subnet_halted: bool,
+    ) -> UpgradeHealth {
+        let exits = replica_exits.saturating_sub(self.initial_replica_exits);
+        if exits > self.max_replica_exits {
+            return UpgradeHealth::Unhealthy(format!(
+                "the replica process exited {} times (at most {} tolerated)",
+                exits, self.max_replica_exits
+            ));
+        }
+        if subnet_halted {
+            self.deadline = now + self.window;
+            return UpgradeHealth::Pending;
+        }
+        match self.initial_cup_height {
+            Some(initial) if cup_height <= initial => {
+                if now >= self.deadline {
+                    UpgradeHealth::Unhealthy(format!(
+                        "no catch-up package above height {} was obtained in time",
+                        initial
+                    ))
+                } else {
+                    UpgradeHealth::Pending
+                }
+            }
+            _ => UpgradeHealth::Healthy,
+        }
+    }
+}
+
+/// Returns the path of the file persisting the rolled back replica version.
+pub(crate) fn rolled_back_version_path(release_content_dir: &Path) -> PathBuf {
+    release_content_dir.join(ROLLED_BACK_VERSION_FILE)
+}
+
+/// Reads the version of a rolled back upgrade, if any.
+pub(crate) fn read_rolled_back_version(path: &Path) -> Option<ReplicaVersion> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    ReplicaVersion::try_from(contents.trim()).ok()
+}
+
+/// Persists the version of a rolled back upgrade.
+pub(crate) fn write_rolled_back_version(
+    path: &Path,
+    replica_version: &ReplicaVersion,
+) -> std::io::Result<()> {
+    std::fs::write(path, replica_version.as_ref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW: Duration = Duration::from_secs(60);
+
+    fn health_check(now: Instant) -> UpgradeHealthCheck {
+        UpgradeHealthCheck::new(now, WINDOW, 2, Some(Height::from(100)), 1)
+    }
+
+    #[test]
+    fn healthy_once_cup_height_increases() {
+        let now = Instant::now();
+        let mut check = health_check(now);
+        assert_eq!(
+            check.evaluate(now, Height::from(100), 1, false),
+            UpgradeHealth::Pending
+        );
+        assert_eq!(
+            check.evaluate(now, Height::from(200), 3, false),
+            UpgradeHealth::Healthy
+        );
+    }
+
+    #[test]
+    fn unhealthy_without_progress_after_deadline() {
+        let now = Instant::now();
+        let mut check = health_check(now);
+        assert!(matches!(
+            check.evaluate(now + WINDOW, Height::from(100), 1, false),
+            UpgradeHealth::Unhealthy(_)
+        ));
+    }
+
+    #[test]
+    fn window_starts_once_subnet_is_unhalted() {
+        let now = Instant::now();
+        let mut check = health_check(now);
+        // The subnet stays halted for longer than the window.
+        assert_eq!(
+            check.evaluate(now + WINDOW, Height::from(100), 1, true),
+            UpgradeHealth::Pending
+        );
+        let unhalted = now + 2 * WINDOW;
+        assert_eq!(
+            check.evaluate(unhalted, Height::from(100), 1, true),
+            UpgradeHealth::Pending
+        );
+        assert_eq!(
+            check.evaluate(unhalted + WINDOW / 2, Height::from(100), 1, false),
+            UpgradeHealth::Pending
+        );
+        assert!(matches!(
+            check.evaluate(unhalted + WINDOW, Height::from(100), 1, false),
+            UpgradeHealth::Unhealthy(_)
+        ));
+    }
+
+    #[test]
+    fn unhealthy_if_replica_exits_too_often() {
+        let now = Instant::now();
+        let mut check = health_check(now);
+        assert!(matches!(
+            check.evaluate(now, Height::from(200), 4, false),
+            UpgradeHealth::Unhealthy(_)
+        ));
+    }
+
+    #[test]
+    fn rolled_back_version_is_persisted() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = rolled_back_version_path(dir.path());
+        assert_eq!(read_rolled_back_version(&path), None);
+        let version = ReplicaVersion::try_from("0.1.0").unwrap();
+        write_rolled_back_version(&path, &version).unwrap();
+        assert_eq!(read_rolled_back_version(&path), Some(version));
+    }
+}
@@ -72,4 +72,9 @@ pub struct NodeRecord {
     /// The id of the node operator that added this node.
     #[prost(bytes="vec", tag="15")]
     pub node_operator_id: ::prost::alloc::vec::Vec<u8>,
+    /// The replica version whose upgrade the node rolled back because the
+    /// replica did not become healthy, or empty. Only set by the node itself.
+    #[prost(string, tag="16")]
+    #[serde(default)]
+    pub rolled_back_replica_version_id: ::prost::alloc::string::String,
 }
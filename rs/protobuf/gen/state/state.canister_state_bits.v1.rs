@@ -26,6 +26,8 @@ pub mod call_context {
         pub canister_id: ::core::option::Option<super::super::super::super::types::v1::CanisterId>,
         #[prost(uint64, tag="2")]
         pub callback_id: u64,
+        #[prost(uint64, optional, tag="3")]
+        pub deadline_nanos: ::core::option::Option<u64>,
     }
     #[derive(Clone, PartialEq, ::prost::Message)]
     pub struct Heartbeat {
@@ -74,6 +76,8 @@ pub struct Callback {
     pub originator: ::core::option::Option<super::super::super::types::v1::CanisterId>,
     #[prost(message, optional, tag="7")]
     pub respondent: ::core::option::Option<super::super::super::types::v1::CanisterId>,
+    #[prost(uint64, optional, tag="8")]
+    pub deadline_nanos: ::core::option::Option<u64>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct CallbackEntry {
@@ -83,6 +87,13 @@ pub struct CallbackEntry {
     pub callback: ::core::option::Option<Callback>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TimedOutCallbackEntry {
+    #[prost(uint64, tag="1")]
+    pub callback_id: u64,
+    #[prost(uint64, tag="2")]
+    pub deadline_nanos: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct CallContextManager {
     #[prost(uint64, tag="1")]
     pub next_call_context_id: u64,
@@ -92,6 +103,14 @@ pub struct CallContextManager {
     pub call_contexts: ::prost::alloc::vec::Vec<CallContextEntry>,
     #[prost(message, repeated, tag="4")]
     pub callbacks: ::prost::alloc::vec::Vec<CallbackEntry>,
+    /// Best-effort callbacks that already have a response (or a synthetic reject)
+    /// enqueued.
+    #[prost(uint64, repeated, tag="5")]
+    pub responded_best_effort_callbacks: ::prost::alloc::vec::Vec<u64>,
+    /// Unregistered best-effort callbacks that timed out and may still get a late
+    /// response, along with their deadlines.
+    #[prost(message, repeated, tag="6")]
+    pub timed_out_best_effort_callbacks: ::prost::alloc::vec::Vec<TimedOutCallbackEntry>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct CyclesAccount {
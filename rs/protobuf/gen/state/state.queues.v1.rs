@@ -44,6 +44,8 @@ pub struct Request {
     pub method_payload: ::prost::alloc::vec::Vec<u8>,
     #[prost(message, optional, tag="7")]
     pub cycles_payment: ::core::option::Option<Cycles>,
+    #[prost(uint64, optional, tag="8")]
+    pub deadline_nanos: ::core::option::Option<u64>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct RejectContext {
@@ -64,6 +66,8 @@ pub struct Response {
     pub refund: ::core::option::Option<Funds>,
     #[prost(message, optional, tag="7")]
     pub cycles_refund: ::core::option::Option<Cycles>,
+    #[prost(uint64, optional, tag="8")]
+    pub deadline_nanos: ::core::option::Option<u64>,
     #[prost(oneof="response::ResponsePayload", tags="5, 6")]
     pub response_payload: ::core::option::Option<response::ResponsePayload>,
 }
@@ -248,6 +248,10 @@ fn build_registry_proto(def: &Path, out: &Path) {
         ".registry.unassigned_nodes_config",
         "#[derive(serde::Serialize, serde::Deserialize)]",
     );
+    config.field_attribute(
+        ".registry.node.v1.NodeRecord.rolled_back_replica_version_id",
+        "#[serde(default)]",
+    );
 
     let registry_files = [
         def.join("registry/crypto/v1/crypto.proto"),
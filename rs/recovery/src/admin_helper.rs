@@ -1,4 +1,5 @@
 use ic_base_types::{NodeId, RegistryVersion};
+use ic_registry_routing_table::CanisterIdRange;
 use ic_types::Height;
 use ic_types::{ReplicaVersion, SubnetId};
 
@@ -177,6 +178,84 @@ impl AdminHelper {
         ic_admin
     }
 
+    fn add_canister_id_ranges_args(ic_admin: &mut IcAdmin, canister_id_ranges: &[CanisterIdRange]) {
+        ic_admin.push("--canister-id-ranges".to_string());
+        canister_id_ranges
+            .iter()
+            .for_each(|r| ic_admin.push(format!("{}:{}", r.start, r.end)));
+    }
+
+    /// Return an ic_admin command string to mark the given canister ID ranges
+    /// as being migrated from `source_subnet` to `destination_subnet`.
+    pub fn get_propose_to_prepare_canister_migration_command(
+        &self,
+        canister_id_ranges: &[CanisterIdRange],
+        source_subnet: SubnetId,
+        destination_subnet: SubnetId,
+    ) -> IcAdmin {
+        let mut ic_admin = self.get_ic_admin_cmd_base(&self.neuron_args);
+        ic_admin.push("propose-to-prepare-canister-migration".to_string());
+        AdminHelper::add_canister_id_ranges_args(&mut ic_admin, canister_id_ranges);
+        ic_admin.push("--source-subnet".to_string());
+        ic_admin.push(source_subnet.to_string());
+        ic_admin.push("--destination-subnet".to_string());
+        ic_admin.push(destination_subnet.to_string());
+        ic_admin.push("--summary".to_string());
+        ic_admin.push(format!(
+            "\"Prepare the migration of canisters from subnet {} to subnet {}.\"",
+            source_subnet, destination_subnet
+        ));
+        AdminHelper::add_proposer_args(&mut ic_admin, &self.neuron_args);
+        ic_admin
+    }
+
+    /// Return an ic_admin command string to route the given canister ID ranges
+    /// from `source_subnet` to `destination_subnet`.
+    pub fn get_propose_to_reroute_canister_ranges_command(
+        &self,
+        canister_id_ranges: &[CanisterIdRange],
+        source_subnet: SubnetId,
+        destination_subnet: SubnetId,
+    ) -> IcAdmin {
+        let mut ic_admin = self.get_ic_admin_cmd_base(&self.neuron_args);
+        ic_admin.push("propose-to-reroute-canister-ranges".to_string());
+        AdminHelper::add_canister_id_ranges_args(&mut ic_admin, canister_id_ranges);
+        ic_admin.push("--source-subnet".to_string());
+        ic_admin.push(source_subnet.to_string());
+        ic_admin.push("--destination-subnet".to_string());
+        ic_admin.push(destination_subnet.to_string());
+        ic_admin.push("--summary".to_string());
+        ic_admin.push(format!(
+            "\"Route canisters from subnet {} to subnet {}.\"",
+            source_subnet, destination_subnet
+        ));
+        AdminHelper::add_proposer_args(&mut ic_admin, &self.neuron_args);
+        ic_admin
+    }
+
+    /// Return an ic_admin command string to remove the given canister ID ranges
+    /// from the canister migrations.
+    pub fn get_propose_to_complete_canister_migration_command(
+        &self,
+        canister_id_ranges: &[CanisterIdRange],
+        source_subnet: SubnetId,
+        destination_subnet: SubnetId,
+    ) -> IcAdmin {
+        let mut ic_admin = self.get_ic_admin_cmd_base(&self.neuron_args);
+        ic_admin.push("propose-to-complete-canister-migration".to_string());
+        AdminHelper::add_canister_id_ranges_args(&mut ic_admin, canister_id_ranges);
+        ic_admin.push("--migration-trace".to_string());
+        ic_admin.push(source_subnet.to_string());
+        ic_admin.push(destination_subnet.to_string());
+        ic_admin.push("--summary".to_string());
+        ic_admin.push(format!(
+            "\"Complete the migration of canisters from subnet {} to subnet {}.\"",
+            source_subnet, destination_subnet
+        ));
+        AdminHelper::add_proposer_args(&mut ic_admin, &self.neuron_args);
+        ic_admin
+    }
+
     /// Return an ic_admin command string to create a system subnet with dkg interval of 12
     pub fn get_propose_to_create_test_system_subnet(
         &self,
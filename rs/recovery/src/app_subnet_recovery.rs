@@ -1,17 +1,20 @@
 use crate::recovery_iterator::RecoveryIterator;
+use crate::recovery_state::{read_json, RecoveryState, RECOVERY_STATE_FILE};
 use crate::RecoveryResult;
 use crate::{error::RecoveryError, RecoveryArgs};
 use clap::Parser;
 use ic_base_types::{NodeId, SubnetId};
 use ic_types::ReplicaVersion;
-use slog::Logger;
+use serde::{Deserialize, Serialize};
+use slog::{info, warn, Logger};
 use std::net::IpAddr;
+use std::path::{Path, PathBuf};
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
 use crate::{NeuronArgs, Recovery, Step};
 
-#[derive(Debug, Copy, Clone, EnumIter)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, EnumIter, Serialize, Deserialize)]
 pub enum StepType {
     Halt,
     DownloadState,
@@ -27,7 +30,7 @@ pub enum StepType {
     Cleanup,
 }
 
-#[derive(Parser)]
+#[derive(Clone, Debug, Parser, Serialize, Deserialize)]
 #[clap(version = "1.0")]
 pub struct AppSubnetRecoveryArgs {
     /// Id of the broken subnet
@@ -55,14 +58,57 @@ pub struct AppSubnetRecoveryArgs {
     pub upload_node: Option<IpAddr>,
 }
 
+impl AppSubnetRecoveryArgs {
+    /// Fill the parameters missing in `self` with the ones of `other`.
+    fn or(self, other: Self) -> Self {
+        Self {
+            subnet_id: self.subnet_id,
+            upgrade_version: self.upgrade_version.or(other.upgrade_version),
+            replacement_nodes: self.replacement_nodes.or(other.replacement_nodes),
+            pub_key: self.pub_key.or(other.pub_key),
+            download_node: self.download_node.or(other.download_node),
+            upload_node: self.upload_node.or(other.upload_node),
+        }
+    }
+}
+
+/// A plan to execute an application subnet recovery without any user
+/// interaction, e.g., during automated recovery drills.
+#[derive(Clone, Debug, Deserialize)]
+pub struct AppSubnetRecoveryPlan {
+    /// The parameters of the recovery. Steps depending on a missing parameter
+    /// are skipped.
+    pub params: AppSubnetRecoveryArgs,
+    /// The neuron to submit proposals with. If missing, proposals are
+    /// submitted by the test neuron. The HSM PIN is never read from the plan.
+    #[serde(default)]
+    pub neuron_args: Option<NeuronArgs>,
+    /// Steps that are not executed.
+    #[serde(default)]
+    pub skip_steps: Vec<StepType>,
+}
+
+impl AppSubnetRecoveryPlan {
+    /// Read the plan from the given JSON file.
+    pub fn read(file: &Path) -> RecoveryResult<Self> {
+        read_json(file)
+    }
+}
+
 pub struct AppSubnetRecovery {
     step_iterator: Box<dyn Iterator<Item = StepType>>,
     pub params: AppSubnetRecoveryArgs,
     recovery: Recovery,
+    completed_steps: Vec<StepType>,
+    state_file: PathBuf,
     logger: Logger,
 }
 
 impl AppSubnetRecovery {
+    /// Create a new recovery of the given subnet. If the recovery directory
+    /// holds the state of an interrupted recovery of the same subnet, the
+    /// recovery is resumed: steps that were completed are not repeated, and
+    /// parameters not given in `subnet_args` are taken from the saved state.
     pub fn new(
         logger: Logger,
         recovery_args: RecoveryArgs,
@@ -70,18 +116,132 @@ impl AppSubnetRecovery {
         subnet_args: AppSubnetRecoveryArgs,
     ) -> Self {
         let ssh_confirmation = neuron_args.is_some();
-        Self {
-            step_iterator: Box::new(StepType::iter()),
-            params: subnet_args,
-            recovery: Recovery::new(logger.clone(), recovery_args, neuron_args, ssh_confirmation)
-                .expect("Failed to init recovery"),
+        Self::new_with_skipped_steps(
+            logger,
+            recovery_args,
+            neuron_args,
+            subnet_args,
+            ssh_confirmation,
+            vec![],
+        )
+    }
+
+    /// Create a new recovery executing the given plan. As opposed to
+    /// [AppSubnetRecovery::new], SSH connections never ask for confirmation.
+    pub fn new_from_plan(
+        logger: Logger,
+        recovery_args: RecoveryArgs,
+        plan: AppSubnetRecoveryPlan,
+    ) -> Self {
+        Self::new_with_skipped_steps(
             logger,
+            recovery_args,
+            plan.neuron_args,
+            plan.params,
+            false,
+            plan.skip_steps,
+        )
+    }
+
+    fn new_with_skipped_steps(
+        logger: Logger,
+        recovery_args: RecoveryArgs,
+        neuron_args: Option<NeuronArgs>,
+        subnet_args: AppSubnetRecoveryArgs,
+        ssh_confirmation: bool,
+        skip_steps: Vec<StepType>,
+    ) -> Self {
+        let recovery = Recovery::new(logger.clone(), recovery_args, neuron_args, ssh_confirmation)
+            .expect("Failed to init recovery");
+        let state_file = recovery.recovery_dir.join(RECOVERY_STATE_FILE);
+
+        let (params, completed_steps) = match RecoveryState::read(&state_file) {
+            Ok(Some(RecoveryState::<AppSubnetRecoveryArgs, StepType> {
+                params,
+                completed_steps,
+            })) if params.subnet_id == subnet_args.subnet_id => {
+                info!(
+                    logger,
+                    "Resuming recovery from {:?}, completed steps: {:?}",
+                    state_file,
+                    completed_steps
+                );
+                (subnet_args.or(params), completed_steps)
+            }
+            Ok(Some(state)) => {
+                warn!(
+                    logger,
+                    "Ignoring state of a recovery of subnet {} in {:?}",
+                    state.params.subnet_id,
+                    state_file
+                );
+                (subnet_args, vec![])
+            }
+            Ok(None) => (subnet_args, vec![]),
+            Err(e) => {
+                warn!(logger, "Ignoring unreadable recovery state: {}", e);
+                (subnet_args, vec![])
+            }
+        };
+
+        let done = completed_steps.clone();
+        let step_iterator =
+            StepType::iter().filter(move |step| !done.contains(step) && !skip_steps.contains(step));
+
+        let subnet_recovery = Self {
+            step_iterator: Box::new(step_iterator),
+            params,
+            recovery,
+            completed_steps,
+            state_file,
+            logger,
+        };
+        if let Err(e) = subnet_recovery.save_state() {
+            warn!(
+                subnet_recovery.logger,
+                "Failed to save recovery state: {}", e
+            );
         }
+        subnet_recovery
     }
 
     pub fn get_recovery_api(&self) -> &Recovery {
         &self.recovery
     }
+
+    /// Record the given step as completed, such that it is not repeated if the
+    /// recovery is resumed.
+    pub fn step_completed(&mut self, step_type: StepType) -> RecoveryResult<()> {
+        self.completed_steps.push(step_type);
+        self.save_state()
+    }
+
+    /// Delete the saved state once all steps were offered, so that a later
+    /// recovery of the same subnet in the same directory starts from scratch
+    /// instead of skipping every step, e.g., if the cleanup was skipped.
+    pub fn finish(&self) {
+        if self.state_file.exists() {
+            if let Err(e) = std::fs::remove_file(&self.state_file) {
+                warn!(
+                    self.logger,
+                    "Failed to delete recovery state {:?}: {}", self.state_file, e
+                );
+            }
+        }
+    }
+
+    /// Persist the parameters and completed steps to the recovery directory.
+    /// Does nothing once the recovery directory was cleaned up.
+    pub fn save_state(&self) -> RecoveryResult<()> {
+        if !self.recovery.recovery_dir.exists() {
+            return Ok(());
+        }
+        RecoveryState {
+            params: self.params.clone(),
+            completed_steps: self.completed_steps.clone(),
+        }
+        .save(&self.state_file)
+    }
 }
 
 impl RecoveryIterator<StepType> for AppSubnetRecovery {
@@ -191,3 +351,159 @@ impl RecoveryIterator<StepType> for AppSubnetRecovery {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_base_types::PrincipalId;
+    use url::Url;
+
+    fn logger() -> Logger {
+        Logger::root(slog::Discard, slog::o!())
+    }
+
+    fn recovery_args(dir: &Path) -> RecoveryArgs {
+        RecoveryArgs {
+            dir: dir.to_path_buf(),
+            nns_url: Url::parse("http://localhost:8080").unwrap(),
+            replica_version: None,
+            key_file: None,
+        }
+    }
+
+    fn subnet_args(subnet: u64) -> AppSubnetRecoveryArgs {
+        AppSubnetRecoveryArgs {
+            subnet_id: SubnetId::from(PrincipalId::new_subnet_test_id(subnet)),
+            upgrade_version: None,
+            replacement_nodes: None,
+            pub_key: None,
+            download_node: None,
+            upload_node: None,
+        }
+    }
+
+    fn new_recovery(dir: &Path, subnet_args: AppSubnetRecoveryArgs) -> AppSubnetRecovery {
+        AppSubnetRecovery::new(logger(), recovery_args(dir), None, subnet_args)
+    }
+
+    fn remaining_steps(recovery: &mut AppSubnetRecovery) -> Vec<StepType> {
+        recovery.step_iterator.by_ref().collect()
+    }
+
+    #[test]
+    fn completed_steps_are_saved_and_not_repeated_when_resuming() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut recovery = new_recovery(dir.path(), subnet_args(1));
+        assert!(recovery
+            .recovery
+            .recovery_dir
+            .join(RECOVERY_STATE_FILE)
+            .exists());
+        assert_eq!(
+            remaining_steps(&mut recovery),
+            StepType::iter().collect::<Vec<_>>()
+        );
+
+        recovery.params.download_node = Some("::1".parse().unwrap());
+        recovery.step_completed(StepType::Halt).unwrap();
+        recovery.step_completed(StepType::DownloadState).unwrap();
+
+        let mut resumed = new_recovery(dir.path(), subnet_args(1));
+        assert_eq!(
+            resumed.completed_steps,
+            vec![StepType::Halt, StepType::DownloadState]
+        );
+        assert_eq!(resumed.params.download_node, Some("::1".parse().unwrap()));
+        assert_eq!(
+            remaining_steps(&mut resumed),
+            StepType::iter().skip(2).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn given_parameters_take_precedence_over_saved_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut recovery = new_recovery(dir.path(), subnet_args(1));
+        recovery.params.download_node = Some("::1".parse().unwrap());
+        recovery.save_state().unwrap();
+
+        let args = AppSubnetRecoveryArgs {
+            download_node: Some("::2".parse().unwrap()),
+            ..subnet_args(1)
+        };
+        let resumed = new_recovery(dir.path(), args);
+        assert_eq!(resumed.params.download_node, Some("::2".parse().unwrap()));
+    }
+
+    #[test]
+    fn state_survives_overwriting_the_working_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut recovery = new_recovery(dir.path(), subnet_args(1));
+        recovery.step_completed(StepType::Halt).unwrap();
+
+        // State downloads replace the content of the working directory.
+        std::fs::remove_dir_all(&recovery.recovery.work_dir).unwrap();
+        std::fs::create_dir_all(&recovery.recovery.work_dir).unwrap();
+
+        let resumed = new_recovery(dir.path(), subnet_args(1));
+        assert_eq!(resumed.completed_steps, vec![StepType::Halt]);
+    }
+
+    #[test]
+    fn state_is_deleted_once_all_steps_were_offered() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut recovery = new_recovery(dir.path(), subnet_args(1));
+        // The cleanup step was declined, all other steps were executed.
+        for step in StepType::iter().filter(|step| *step != StepType::Cleanup) {
+            recovery.step_completed(step).unwrap();
+        }
+
+        let mut resumed = new_recovery(dir.path(), subnet_args(1));
+        assert_eq!(remaining_steps(&mut resumed), vec![StepType::Cleanup]);
+        assert!(resumed.next().is_none());
+        assert!(!resumed.state_file.exists());
+
+        let mut restarted = new_recovery(dir.path(), subnet_args(1));
+        assert!(restarted.completed_steps.is_empty());
+        assert_eq!(
+            remaining_steps(&mut restarted),
+            StepType::iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn state_of_another_subnet_is_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut recovery = new_recovery(dir.path(), subnet_args(1));
+        recovery.step_completed(StepType::Halt).unwrap();
+
+        let mut other = new_recovery(dir.path(), subnet_args(2));
+        assert!(other.completed_steps.is_empty());
+        assert_eq!(
+            remaining_steps(&mut other),
+            StepType::iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn skipped_steps_are_not_executed_nor_recorded_as_completed() {
+        let dir = tempfile::tempdir().unwrap();
+        let plan = AppSubnetRecoveryPlan {
+            params: subnet_args(1),
+            neuron_args: None,
+            skip_steps: vec![StepType::BlessVersion, StepType::Cleanup],
+        };
+        let mut recovery =
+            AppSubnetRecovery::new_from_plan(logger(), recovery_args(dir.path()), plan);
+        let steps = remaining_steps(&mut recovery);
+        assert!(!steps.contains(&StepType::BlessVersion));
+        assert!(!steps.contains(&StepType::Cleanup));
+        assert_eq!(steps.len(), StepType::iter().count() - 2);
+
+        recovery.step_completed(StepType::Halt).unwrap();
+        let state = RecoveryState::<AppSubnetRecoveryArgs, StepType>::read(&recovery.state_file)
+            .unwrap()
+            .unwrap();
+        assert_eq!(state.completed_steps, vec![StepType::Halt]);
+    }
+}
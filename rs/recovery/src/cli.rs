@@ -1,11 +1,15 @@
 //! Command line interfaces to various subnet recovery processes.
 //! Calls the recovery library.
-use crate::app_subnet_recovery::{AppSubnetRecovery, AppSubnetRecoveryArgs};
+use crate::app_subnet_recovery::{AppSubnetRecovery, AppSubnetRecoveryArgs, AppSubnetRecoveryPlan};
+use crate::cmd::CheckRecoveryReportArgs;
+use crate::error::{RecoveryError, RecoveryResult};
 use crate::nns_recovery_failover_nodes;
 use crate::nns_recovery_failover_nodes::{NNSRecoveryFailoverNodes, NNSRecoveryFailoverNodesArgs};
 use crate::nns_recovery_same_nodes;
 use crate::nns_recovery_same_nodes::{NNSRecoverySameNodes, NNSRecoverySameNodesArgs};
+use crate::recovery_report::{self, RecoveryReport};
 use crate::steps::Step;
+use crate::subnet_splitting::{self, SubnetSplitting, SubnetSplittingArgs};
 use crate::{app_subnet_recovery, util};
 use crate::{NeuronArgs, Recovery, RecoveryArgs};
 use ic_types::{NodeId, ReplicaVersion, SubnetId};
@@ -13,6 +17,7 @@ use slog::{info, warn, Logger};
 use std::convert::TryFrom;
 use std::io::{stdin, stdout, Write};
 use std::net::IpAddr;
+use std::path::Path;
 
 /// Application subnets are recovered by:
 ///     1. Halting the broken subnet
@@ -27,6 +32,10 @@ use std::net::IpAddr;
 ///     7. Proposing the recovery CUP
 ///     8. Uploading the replayed state to one of the nodes
 ///     9. Unhalting the recovered subnet
+///
+/// The progress is saved in the recovery directory after every successfully
+/// executed step, such that an interrupted recovery is resumed when the tool
+/// is started again. It is deleted once all steps were offered.
 pub fn app_subnet_recovery(
     logger: Logger,
     args: RecoveryArgs,
@@ -54,7 +63,7 @@ pub fn app_subnet_recovery(
 
     while let Some((step_type, step)) = subnet_recovery.next() {
         print_step(&logger, &format!("{:?}", step_type));
-        execute_step_after_consent(&logger, step);
+        let executed = execute_step_after_consent(&logger, step);
 
         // Depending on which step we just executed we might require some user interaction before we can start the next step.
         match step_type {
@@ -95,6 +104,131 @@ pub fn app_subnet_recovery(
             }
             _ => {}
         }
+
+        // Declined or failed steps are not recorded, such that they are
+        // offered again if an interrupted recovery is resumed.
+        let saved = if executed {
+            subnet_recovery.step_completed(step_type)
+        } else {
+            subnet_recovery.save_state()
+        };
+        if let Err(e) = saved {
+            warn!(logger, "Failed to save recovery state: {}", e);
+        }
+    }
+}
+
+/// Executes an application subnet recovery according to the given plan file,
+/// without asking for confirmation or input other than the HSM PIN of the
+/// plan's neuron, which is not stored in the plan. The steps are the same as
+/// in [app_subnet_recovery]; steps depending on a parameter missing in the
+/// plan are skipped.
+///
+/// The first failing step aborts the recovery. As the progress is saved in the
+/// recovery directory, executing the plan again resumes the recovery with the
+/// failed step.
+pub fn app_subnet_recovery_from_plan(
+    logger: Logger,
+    args: RecoveryArgs,
+    plan_file: &Path,
+) -> RecoveryResult<()> {
+    print_step(&logger, "App Subnet Recovery");
+    let mut plan = AppSubnetRecoveryPlan::read(plan_file)?;
+    print_summary(&logger, &args, plan.params.subnet_id);
+    if let Some(neuron_args) = &mut plan.neuron_args {
+        neuron_args.dfx_hsm_pin = read_input(&logger, "Enter DFX HSM PIN: ");
+    }
+
+    let mut subnet_recovery = AppSubnetRecovery::new_from_plan(logger.clone(), args, plan);
+
+    while let Some((step_type, step)) = subnet_recovery.next() {
+        print_step(&logger, &format!("{:?}", step_type));
+        info!(logger, "{}", step.descr());
+        step.exec()?;
+        subnet_recovery.step_completed(step_type)?;
+    }
+    Ok(())
+}
+
+/// Application subnets are split by:
+///     1. Proposing to mark the canister ranges as being migrated
+///     2. Halting the source subnet
+///     3. Downloading the most recent state of the source subnet
+///     4. Updating the config to point to downloaded state
+///     5. Replaying finalized blocks using `ic-replay`
+///     6. Proposing to route the canister ranges to the destination subnet
+///     7. Splitting the replayed state using `state-tool split`
+///     8. Proposing the recovery CUPs and uploading the split states to one
+///        node of each subnet
+///     9. Unhalting both subnets
+///    10. Proposing to remove the canister ranges from the migrations
+///
+/// The destination subnet must already exist and be halted.
+pub fn subnet_splitting(
+    logger: Logger,
+    args: RecoveryArgs,
+    subnet_splitting_args: SubnetSplittingArgs,
+    test: bool,
+) {
+    print_step(&logger, "Subnet Splitting");
+    print_summary(&logger, &args, subnet_splitting_args.source_subnet_id);
+    info!(
+        logger,
+        "Destination subnet: {}", subnet_splitting_args.destination_subnet_id
+    );
+    wait_for_confirmation(&logger);
+
+    let mut neuron_args = None;
+    if !test {
+        neuron_args = Some(read_neuron_args(&logger));
+    }
+
+    let mut subnet_splitting =
+        SubnetSplitting::new(logger.clone(), args, neuron_args, subnet_splitting_args);
+
+    if subnet_splitting.params.pub_key.is_none() {
+        subnet_splitting.params.pub_key = read_optional(
+            &logger,
+            "Enter public key to add readonly SSH access to subnet: ",
+        );
+    }
+
+    while let Some((step_type, step)) = subnet_splitting.next() {
+        print_step(&logger, &format!("{:?}", step_type));
+        execute_step_after_consent(&logger, step);
+
+        match step_type {
+            subnet_splitting::StepType::HaltSourceSubnet => {
+                info!(logger, "Ensure subnet is halted.");
+                wait_for_confirmation(&logger);
+
+                print_height_info(&logger, subnet_splitting.params.source_subnet_id);
+
+                if subnet_splitting.params.download_node.is_none() {
+                    subnet_splitting.params.download_node =
+                        read_optional_ip(&logger, "Enter download IP:");
+                }
+            }
+
+            subnet_splitting::StepType::ProposeSourceCup => {
+                if subnet_splitting.params.source_upload_node.is_none() {
+                    subnet_splitting.params.source_upload_node = read_optional_ip(
+                        &logger,
+                        "Enter IP of source subnet node with admin access: ",
+                    );
+                }
+            }
+
+            subnet_splitting::StepType::ProposeDestinationCup => {
+                if subnet_splitting.params.destination_upload_node.is_none() {
+                    subnet_splitting.params.destination_upload_node = read_optional_ip(
+                        &logger,
+                        "Enter IP of destination subnet node with admin access: ",
+                    );
+                }
+            }
+            _ => {}
+        }
     }
 }
 
@@ -226,12 +360,75 @@ pub fn nns_recovery_failover_nodes(
     }
 }
 
-pub fn execute_step_after_consent(logger: &Logger, step: Box<dyn Step>) {
+/// Checks a recovery report created by another operator by:
+///     1. Verifying the signature of the report, if given
+///     2. Re-deriving the state hash from the replayed state in the recovery
+///        directory and comparing the registry local store to the registry
+///        canister
+///     3. Comparing the resulting report to the given one
+pub fn check_recovery_report(
+    logger: Logger,
+    args: RecoveryArgs,
+    check_args: CheckRecoveryReportArgs,
+) -> RecoveryResult<()> {
+    print_step(&logger, "Check Recovery Report");
+    if let Some(signature) = &check_args.signature {
+        match (&check_args.allowed_signers, &check_args.signer) {
+            (Some(allowed_signers), Some(signer)) => {
+                if let Some(out) = recovery_report::verify_report_signature(
+                    &check_args.report,
+                    signature,
+                    allowed_signers,
+                    signer,
+                )? {
+                    info!(logger, "{}", out);
+                }
+            }
+            _ => {
+                return Err(RecoveryError::invalid_output_error(
+                    "Verifying the signature requires the allowed signers and the signer"
+                        .to_string(),
+                ))
+            }
+        }
+    } else {
+        warn!(
+            logger,
+            "No signature given, only checking the report content."
+        );
+    }
+    let report = RecoveryReport::read(&check_args.report)?;
+
+    let recovery = Recovery::new(logger.clone(), args, None, false)?;
+    let own_report_file = recovery.work_dir.join("check_recovery_report.json");
+    let step = recovery.get_verify_recovery_step_with_report(
+        check_args.subnet_id,
+        own_report_file.clone(),
+        None,
+    );
+    info!(logger, "{}", step.descr());
+    step.exec()?;
+
+    let differences = report.differences(&RecoveryReport::read(&own_report_file)?);
+    if differences.is_empty() {
+        info!(logger, "The recovery report matches the local state.");
+        Ok(())
+    } else {
+        differences.iter().for_each(|d| warn!(logger, "{}", d));
+        Err(RecoveryError::invalid_output_error(
+            "The recovery report does not match the local state.".to_string(),
+        ))
+    }
+}
+
+/// Executes the given step if the user consents, retrying on failure as long
+/// as the user wishes. Returns true if the step was executed successfully.
+pub fn execute_step_after_consent(logger: &Logger, step: Box<dyn Step>) -> bool {
     info!(logger, "{}", step.descr());
     if consent_given(logger, "Execute now?") {
         loop {
             match step.exec() {
-                Ok(()) => break,
+                Ok(()) => return true,
                 Err(e) => {
                     warn!(logger, "Error: {}", e);
                     if !consent_given(logger, "Retry now?") {
@@ -241,6 +438,7 @@ pub fn execute_step_after_consent(logger: &Logger, step: Box<dyn Step>) {
             }
         }
     }
+    false
 }
 
 pub fn print_summary(logger: &Logger, args: &RecoveryArgs, subnet_id: SubnetId) {
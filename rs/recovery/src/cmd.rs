@@ -1,12 +1,12 @@
 use clap::Parser;
-use ic_types::ReplicaVersion;
+use ic_types::{ReplicaVersion, SubnetId};
 use std::path::PathBuf;
 use url::Url;
 
 use crate::{
     app_subnet_recovery::AppSubnetRecoveryArgs,
     nns_recovery_failover_nodes::NNSRecoveryFailoverNodesArgs,
-    nns_recovery_same_nodes::NNSRecoverySameNodesArgs,
+    nns_recovery_same_nodes::NNSRecoverySameNodesArgs, subnet_splitting::SubnetSplittingArgs,
 };
 
 /// Subcommands for recovery procedures (application subnets, NNS with failover nodes, etc...)
@@ -14,10 +14,47 @@ use crate::{
 pub enum SubCommand {
     /// Application subnet recovery on same or failover nodes.
     AppSubnetRecovery(AppSubnetRecoveryArgs),
+    /// Non-interactive application subnet recovery executing a plan file.
+    AppSubnetRecoveryPlan(AppSubnetRecoveryPlanArgs),
     /// NNS recovery on a failover IC.
     NNSRecoveryFailoverNodes(NNSRecoveryFailoverNodesArgs),
     /// NNS recovery on the same nodes.
     NNSRecoverySameNodes(NNSRecoverySameNodesArgs),
+    /// Split an application subnet by moving canister ranges to another subnet.
+    SubnetSplitting(SubnetSplittingArgs),
+    /// Re-create a recovery report from the replayed state in the recovery
+    /// directory and compare it to the given report.
+    CheckRecoveryReport(CheckRecoveryReportArgs),
+}
+
+#[derive(Parser)]
+pub struct CheckRecoveryReportArgs {
+    /// Id of the recovered subnet
+    #[clap(long, parse(try_from_str=crate::util::subnet_id_from_str))]
+    pub subnet_id: SubnetId,
+
+    /// The recovery report to check
+    #[clap(long, parse(from_os_str))]
+    pub report: PathBuf,
+
+    /// The signature of the report. Requires `allowed-signers` and `signer`.
+    #[clap(long, parse(from_os_str))]
+    pub signature: Option<PathBuf>,
+
+    /// File holding the public keys of the signers, see `ssh-keygen -Y verify`
+    #[clap(long, parse(from_os_str))]
+    pub allowed_signers: Option<PathBuf>,
+
+    /// The identity of the signer in the allowed signers file
+    #[clap(long)]
+    pub signer: Option<String>,
+}
+
+#[derive(Parser)]
+pub struct AppSubnetRecoveryPlanArgs {
+    /// JSON file holding the parameters of the recovery and the steps to skip
+    #[clap(long, parse(from_os_str))]
+    pub plan: PathBuf,
 }
 
 #[derive(Parser)]
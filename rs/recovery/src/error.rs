@@ -15,6 +15,7 @@ pub enum RecoveryError {
     IoError(String, io::Error),
     CommandError(Option<i32>, String),
     OutputError(String),
+    ParsingError(String),
     DownloadError(String, FileDownloadError),
     StepSkipped,
 }
@@ -38,6 +39,9 @@ impl RecoveryError {
     pub(crate) fn invalid_output_error(output: String) -> Self {
         RecoveryError::OutputError(format!("Invalid output: {}", output))
     }
+    pub(crate) fn parsing_error(msg: String) -> Self {
+        RecoveryError::ParsingError(msg)
+    }
     pub(crate) fn download_error(url: String, target: &Path, e: FileDownloadError) -> Self {
         RecoveryError::DownloadError(
             format!("Failed to download from {} to {:?}", url, target),
@@ -58,6 +62,9 @@ impl fmt::Display for RecoveryError {
             RecoveryError::OutputError(msg) => {
                 write!(f, "Output error, message: {:?}", msg)
             }
+            RecoveryError::ParsingError(msg) => {
+                write!(f, "Parsing error, message: {:?}", msg)
+            }
             RecoveryError::DownloadError(msg, e) => {
                 write!(f, "Download error, message: {:?}, error: {:?}", msg, e)
             }
@@ -11,6 +11,7 @@ use error::{RecoveryError, RecoveryResult};
 use file_sync_helper::{create_dir, download_binary, read_dir};
 use ic_base_types::{CanisterId, NodeId, PrincipalId};
 use ic_cup_explorer::get_catchup_content;
+use ic_registry_routing_table::CanisterIdRange;
 use ic_replay::cmd::{AddAndBlessReplicaVersionCmd, AddRegistryContentCmd, SubCommand};
 use ic_replay::player::StateParams;
 use ic_types::messages::HttpStatusResponse;
@@ -37,9 +38,12 @@ pub mod file_sync_helper;
 pub mod nns_recovery_failover_nodes;
 pub mod nns_recovery_same_nodes;
 pub mod recovery_iterator;
+pub mod recovery_report;
+pub mod recovery_state;
 pub mod replay_helper;
 pub(crate) mod ssh_helper;
 pub mod steps;
+pub mod subnet_splitting;
 pub(crate) mod util;
 
 pub const IC_DATA_PATH: &str = "/var/lib/ic/data";
@@ -49,13 +53,16 @@ pub const IC_JSON5_PATH: &str = "/run/ic-node/config/ic.json5";
 pub const IC_STATE_EXCLUDES: &[&str] = &["images", "tip", "backups", "fs_tmp", "cups"];
 pub const IC_STATE: &str = "ic_state";
 pub const NEW_IC_STATE: &str = "new_ic_state";
+pub const SPLIT_STATE: &str = "split_state";
 pub const IC_REGISTRY_LOCAL_STORE: &str = "ic_registry_local_store";
 pub const CHECKPOINTS: &str = "checkpoints";
 pub const ADMIN: &str = "admin";
 pub const READONLY: &str = "readonly";
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize)]
 pub struct NeuronArgs {
+    /// Never read from files, only entered interactively.
+    #[serde(skip)]
     dfx_hsm_pin: String,
     slot: String,
     neuron_id: String,
@@ -92,6 +99,7 @@ pub struct Recovery {
 
     pub key_file: Option<PathBuf>,
     ssh_confirmation: bool,
+    replica_version: Option<ReplicaVersion>,
 
     logger: Logger,
 }
@@ -118,6 +126,7 @@ impl Recovery {
             admin_helper: AdminHelper::new(binary_dir.clone(), args.nns_url, neuron_args),
             key_file: args.key_file,
             ssh_confirmation,
+            replica_version: args.replica_version.clone(),
             logger,
         };
 
@@ -493,19 +502,113 @@ impl Recovery {
         }
     }
 
+    /// Return a [VerifyRecoveryStep] re-deriving the state hash of the replayed
+    /// state, comparing the registry local store to the registry canister, and
+    /// writing a recovery report signed with the key file, if any.
+    pub fn get_verify_recovery_step(&self, subnet_id: SubnetId) -> impl Step {
+        self.get_verify_recovery_step_with_report(
+            subnet_id,
+            self.work_dir.join(recovery_report::REPORT_FILE_NAME),
+            self.key_file.clone(),
+        )
+    }
+
+    /// Return a [VerifyRecoveryStep] writing the report to the given file.
+    pub fn get_verify_recovery_step_with_report(
+        &self,
+        subnet_id: SubnetId,
+        report: PathBuf,
+        key_file: Option<PathBuf>,
+    ) -> impl Step {
+        let registry_store_tar = self
+            .work_dir
+            .join(format!("{}.tar.gz", IC_REGISTRY_LOCAL_STORE));
+        VerifyRecoveryStep {
+            logger: self.logger.clone(),
+            subnet_id,
+            work_dir: self.work_dir.clone(),
+            config: self.work_dir.join("ic.json5"),
+            result: self.work_dir.join("verify_recovery.txt"),
+            registry_store_tar: Some(registry_store_tar).filter(|tar| tar.exists()),
+            report,
+            key_file,
+        }
+    }
+
     /// Return an [UploadAndRestartStep] to upload the current recovery state to
     /// a node and restart it.
     pub fn get_upload_and_restart_step(&self, node_ip: IpAddr) -> impl Step {
+        self.get_upload_and_restart_step_with_data_src(node_ip, self.work_dir.join(IC_STATE_DIR))
+    }
+
+    /// Return an [UploadAndRestartStep] to upload the state in `data_src` to a
+    /// node and restart it.
+    pub fn get_upload_and_restart_step_with_data_src(
+        &self,
+        node_ip: IpAddr,
+        data_src: PathBuf,
+    ) -> impl Step {
         UploadAndRestartStep {
             logger: self.logger.clone(),
             node_ip,
             work_dir: self.work_dir.clone(),
-            data_src: self.work_dir.join(IC_STATE_DIR),
+            data_src,
             require_confirmation: self.ssh_confirmation,
             key_file: self.key_file.clone(),
         }
     }
 
+    /// Return the directory holding the split off state of the given subnet.
+    pub fn get_split_state_dir(&self, subnet_id: SubnetId) -> PathBuf {
+        self.work_dir.join(SPLIT_STATE).join(subnet_id.to_string())
+    }
+
+    /// Return a [SplitStateStep] splitting the replayed state into the states
+    /// of `source_subnet_id` and `destination_subnet_id`, by moving the given
+    /// canister ID ranges to the latter. The `state-tool` binary is downloaded
+    /// if it does not exist yet.
+    pub fn get_split_state_step(
+        &self,
+        source_subnet_id: SubnetId,
+        destination_subnet_id: SubnetId,
+        canister_id_ranges: Vec<CanisterIdRange>,
+    ) -> RecoveryResult<impl Step> {
+        let state_tool = self.binary_dir.join("state-tool");
+        if !state_tool.exists() {
+            if let Some(version) = &self.replica_version {
+                block_on(download_binary(
+                    &self.logger,
+                    version.clone(),
+                    String::from("state-tool"),
+                    self.binary_dir.clone(),
+                ))?;
+            } else {
+                return Err(RecoveryError::invalid_output_error(format!(
+                    "No state-tool version provided and {:?} does not exist",
+                    state_tool
+                )));
+            }
+        }
+
+        Ok(SplitStateStep {
+            logger: self.logger.clone(),
+            state_tool,
+            source_subnet_id,
+            destination_subnet_id,
+            canister_id_ranges,
+            work_dir: self.work_dir.clone(),
+            split_dir: self.work_dir.join(SPLIT_STATE),
+        })
+    }
+
+    /// Read the height and hash of the split off state of the given subnet.
+    pub fn get_split_output(&self, subnet_id: SubnetId) -> RecoveryResult<StateParams> {
+        replay_helper::read_output(
+            self.get_split_state_dir(subnet_id)
+                .join(replay_helper::OUTPUT_FILE_NAME),
+        )
+    }
+
     /// Lookup the image [Url] and sha hash of the given [ReplicaVersion]
     pub fn get_img_url_and_sha(version: &ReplicaVersion) -> RecoveryResult<(Url, String)> {
         let mut version_string = version.to_string();
@@ -621,6 +724,66 @@ impl Recovery {
         }
     }
 
+    /// Return an [AdminStep] step marking the given canister ID ranges as being
+    /// migrated from `source_subnet_id` to `destination_subnet_id`.
+    pub fn prepare_canister_migration(
+        &self,
+        canister_id_ranges: &[CanisterIdRange],
+        source_subnet_id: SubnetId,
+        destination_subnet_id: SubnetId,
+    ) -> impl Step {
+        AdminStep {
+            logger: self.logger.clone(),
+            ic_admin_cmd: self
+                .admin_helper
+                .get_propose_to_prepare_canister_migration_command(
+                    canister_id_ranges,
+                    source_subnet_id,
+                    destination_subnet_id,
+                ),
+        }
+    }
+
+    /// Return an [AdminStep] step routing the given canister ID ranges from
+    /// `source_subnet_id` to `destination_subnet_id`.
+    pub fn reroute_canister_ranges(
+        &self,
+        canister_id_ranges: &[CanisterIdRange],
+        source_subnet_id: SubnetId,
+        destination_subnet_id: SubnetId,
+    ) -> impl Step {
+        AdminStep {
+            logger: self.logger.clone(),
+            ic_admin_cmd: self
+                .admin_helper
+                .get_propose_to_reroute_canister_ranges_command(
+                    canister_id_ranges,
+                    source_subnet_id,
+                    destination_subnet_id,
+                ),
+        }
+    }
+
+    /// Return an [AdminStep] step removing the given canister ID ranges from
+    /// the canister migrations.
+    pub fn complete_canister_migration(
+        &self,
+        canister_id_ranges: &[CanisterIdRange],
+        source_subnet_id: SubnetId,
+        destination_subnet_id: SubnetId,
+    ) -> impl Step {
+        AdminStep {
+            logger: self.logger.clone(),
+            ic_admin_cmd: self
+                .admin_helper
+                .get_propose_to_complete_canister_migration_command(
+                    canister_id_ranges,
+                    source_subnet_id,
+                    destination_subnet_id,
+                ),
+        }
+    }
+
     /// Return an [UploadAndRestartStep] to upload the current recovery state to
     /// a node and restart it.
     pub fn get_wait_for_cup_step(&self, node_ip: IpAddr) -> impl Step {
@@ -28,12 +28,26 @@ fn main() {
             subnet_recovery_args,
             args.test,
         ),
+        SubCommand::AppSubnetRecoveryPlan(plan_args) => {
+            cli::app_subnet_recovery_from_plan(logger.clone(), recovery_args, &plan_args.plan)
+                .expect("Recovery failed")
+        }
+        SubCommand::CheckRecoveryReport(check_args) => {
+            cli::check_recovery_report(logger.clone(), recovery_args, check_args)
+                .expect("Recovery report check failed")
+        }
         SubCommand::NNSRecoverySameNodes(nns_recovery_args) => cli::nns_recovery_same_nodes(
             logger.clone(),
             recovery_args,
             nns_recovery_args,
             args.test,
         ),
+        SubCommand::SubnetSplitting(subnet_splitting_args) => cli::subnet_splitting(
+            logger.clone(),
+            recovery_args,
+            subnet_splitting_args,
+            args.test,
+        ),
         SubCommand::NNSRecoveryFailoverNodes(nns_recovery_args) => {
             cli::nns_recovery_failover_nodes(
                 logger.clone(),
@@ -30,6 +30,7 @@ pub enum StepType {
     ValidateReplayOutput,
     UpdateRegistryLocalStore,
     CreateRegistryTar,
+    VerifyRecovery,
     UploadAndHostTar,
     ProposeCUP,
     WaitForCUP,
@@ -189,6 +190,11 @@ impl RecoveryIterator<StepType> for NNSRecoveryFailoverNodes {
 
             StepType::CreateRegistryTar => Ok(Box::new(self.recovery.get_create_tars_step(false))),
 
+            StepType::VerifyRecovery => Ok(Box::new(
+                self.recovery
+                    .get_verify_recovery_step(self.params.subnet_id),
+            )),
+
             StepType::UploadAndHostTar => {
                 if let (Some(aux_user), Some(aux_ip)) =
                     (self.params.aux_user.clone(), self.params.aux_ip)
@@ -20,6 +20,7 @@ pub enum StepType {
     ValidateReplayOutput,
     UpdateRegistryLocalStore,
     CreateTars,
+    VerifyRecovery,
     SetRecoveryCUP,
     UpdateLocalStoreWithCUP,
     ExtractCUPFile,
@@ -143,6 +144,12 @@ impl RecoveryIterator<StepType> for NNSRecoverySameNodes {
             )),
 
             StepType::CreateTars => Ok(Box::new(self.recovery.get_create_tars_step(true))),
+
+            StepType::VerifyRecovery => Ok(Box::new(
+                self.recovery
+                    .get_verify_recovery_step(self.params.subnet_id),
+            )),
+
             StepType::SetRecoveryCUP => Ok(Box::new(
                 self.recovery
                     .get_set_recovery_cup_step(self.params.subnet_id)?,
@@ -1,10 +1,13 @@
 use std::fmt::Debug;
 
-use crate::{app_subnet_recovery, nns_recovery_failover_nodes, nns_recovery_same_nodes};
+use crate::{
+    app_subnet_recovery, nns_recovery_failover_nodes, nns_recovery_same_nodes, subnet_splitting,
+};
 use crate::{
     app_subnet_recovery::AppSubnetRecovery, error::RecoveryError,
     nns_recovery_failover_nodes::NNSRecoveryFailoverNodes,
-    nns_recovery_same_nodes::NNSRecoverySameNodes, steps::Step, RecoveryResult,
+    nns_recovery_same_nodes::NNSRecoverySameNodes, steps::Step, subnet_splitting::SubnetSplitting,
+    RecoveryResult,
 };
 use slog::{info, warn, Logger};
 
@@ -39,7 +42,11 @@ pub trait RecoveryIterator<T: Copy + Debug> {
 impl Iterator for AppSubnetRecovery {
     type Item = (app_subnet_recovery::StepType, Box<dyn Step>);
     fn next(&mut self) -> Option<Self::Item> {
-        self.next_step()
+        let next = self.next_step();
+        if next.is_none() {
+            self.finish();
+        }
+        next
     }
 }
 
@@ -56,3 +63,10 @@ impl Iterator for NNSRecoveryFailoverNodes {
         self.next_step()
     }
 }
+
+impl Iterator for SubnetSplitting {
+    type Item = (subnet_splitting::StepType, Box<dyn Step>);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_step()
+    }
+}
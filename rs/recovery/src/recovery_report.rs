@@ -0,0 +1,289 @@
+//! Recovery reports summarize the verified outcome of a replay before a
+//! recovery CUP is proposed. A report is signed with the SSH key of the
+//! operator who created it, so that other operators can check the signature
+//! and independently re-create the report from their own copy of the state
+//! using `ic-recovery check-recovery-report`.
+use crate::command_helper::{exec_cmd, pipe_all};
+use crate::error::{RecoveryError, RecoveryResult};
+use crate::file_sync_helper::write_file;
+use crate::recovery_state::read_json;
+use ic_types::{Height, RegistryVersion, SubnetId};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub const REPORT_FILE_NAME: &str = "recovery_report.json";
+
+/// The namespace of SSH signatures of recovery reports, see `ssh-keygen -Y`.
+pub const SIGNATURE_NAMESPACE: &str = "ic-recovery-report";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RecoveryReport {
+    /// The recovered subnet.
+    pub subnet_id: String,
+    /// The height of the latest state after the replay.
+    pub replay_height: Height,
+    /// The state hash, as re-derived from the checkpoint on disk.
+    pub state_hash: String,
+    /// The height of the recovery CUP to be proposed.
+    pub recovery_height: Height,
+    /// The latest registry version after the replay.
+    pub registry_version: RegistryVersion,
+    /// The SHA-256 hash of the registry local store tar file, if any.
+    pub registry_store_sha256: Option<String>,
+}
+
+impl RecoveryReport {
+    pub fn new(
+        subnet_id: SubnetId,
+        replay_height: Height,
+        state_hash: String,
+        recovery_height: Height,
+        registry_version: RegistryVersion,
+        registry_store_sha256: Option<String>,
+    ) -> Self {
+        Self {
+            subnet_id: subnet_id.to_string(),
+            replay_height,
+            state_hash,
+            recovery_height,
+            registry_version,
+            registry_store_sha256,
+        }
+    }
+
+    /// Read a report from the given JSON file.
+    pub fn read(file: &Path) -> RecoveryResult<Self> {
+        read_json(file)
+    }
+
+    /// Write the report to the given file in JSON format.
+    pub fn write(&self, file: &Path) -> RecoveryResult<()> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| {
+            RecoveryError::parsing_error(format!("Failed to serialize recovery report: {}", e))
+        })?;
+        write_file(file, json)
+    }
+
+    /// Return a description of every field in which the two reports differ.
+    /// The hashes of the registry store tar files are not compared, as tar
+    /// files are not reproducible. Instead, the hash has to be compared to the
+    /// one in the proposed recovery CUP.
+    pub fn differences(&self, other: &Self) -> Vec<String> {
+        let mut differences = Vec::new();
+        let mut compare = |field: &str, a: String, b: String| {
+            if a != b {
+                differences.push(format!("{}: {} != {}", field, a, b));
+            }
+        };
+        compare("subnet_id", self.subnet_id.clone(), other.subnet_id.clone());
+        compare(
+            "replay_height",
+            self.replay_height.to_string(),
+            other.replay_height.to_string(),
+        );
+        compare(
+            "state_hash",
+            self.state_hash.clone(),
+            other.state_hash.clone(),
+        );
+        compare(
+            "recovery_height",
+            self.recovery_height.to_string(),
+            other.recovery_height.to_string(),
+        );
+        compare(
+            "registry_version",
+            self.registry_version.to_string(),
+            other.registry_version.to_string(),
+        );
+        differences
+    }
+}
+
+/// Return the path of the signature of the given report file, as created by
+/// [sign_report].
+pub fn signature_file(report_file: &Path) -> PathBuf {
+    let mut file = report_file.as_os_str().to_owned();
+    file.push(".sig");
+    PathBuf::from(file)
+}
+
+/// Return the [Command] signing the given report file with the given private
+/// SSH key. The signature is written to [signature_file].
+pub fn get_sign_command(report_file: &Path, key_file: &Path) -> Command {
+    let mut ssh_keygen = Command::new("ssh-keygen");
+    ssh_keygen
+        .args(&["-Y", "sign", "-n", SIGNATURE_NAMESPACE, "-f"])
+        .arg(key_file)
+        .arg(report_file);
+    ssh_keygen
+}
+
+/// Sign the given report file with the given private SSH key and return the
+/// path of the signature.
+pub fn sign_report(report_file: &Path, key_file: &Path) -> RecoveryResult<PathBuf> {
+    let signature = signature_file(report_file);
+    // Remove the signature of a previous version of the report, if any, as
+    // ssh-keygen refuses to overwrite it.
+    if signature.exists() {
+        fs::remove_file(&signature).map_err(|e| RecoveryError::file_error(&signature, e))?;
+    }
+    exec_cmd(&mut get_sign_command(report_file, key_file))?;
+    Ok(signature)
+}
+
+/// Verify that the given signature of the report file was created by the
+/// given signer. The public keys of the signers are taken from the given
+/// `allowed_signers` file, see `ssh-keygen -Y verify`.
+pub fn verify_report_signature(
+    report_file: &Path,
+    signature_file: &Path,
+    allowed_signers: &Path,
+    signer: &str,
+) -> RecoveryResult<Option<String>> {
+    let report = File::open(report_file).map_err(|e| RecoveryError::file_error(report_file, e))?;
+    let mut ssh_keygen = Command::new("ssh-keygen");
+    ssh_keygen
+        .args(&["-Y", "verify", "-n", SIGNATURE_NAMESPACE, "-f"])
+        .arg(allowed_signers)
+        .arg("-I")
+        .arg(signer)
+        .arg("-s")
+        .arg(signature_file)
+        .stdin(report);
+    exec_cmd(&mut ssh_keygen)
+}
+
+/// Return the SHA-256 hash of the given file in hex.
+pub fn get_sha256(file: &Path) -> RecoveryResult<String> {
+    let mut sha256sum = Command::new("sha256sum");
+    sha256sum.arg(file);
+
+    let mut cut = Command::new("cut");
+    cut.arg("-d").arg(" ").arg("-f").arg("1");
+
+    let sha = pipe_all(&mut [sha256sum, cut])?
+        .ok_or_else(|| RecoveryError::invalid_output_error("Empty sha output".to_string()))?;
+    Ok(sha.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_types::PrincipalId;
+
+    fn report(
+        state_hash: &str,
+        registry_version: u64,
+        registry_store_sha256: &str,
+    ) -> RecoveryReport {
+        RecoveryReport::new(
+            SubnetId::from(PrincipalId::new_subnet_test_id(1)),
+            Height::from(100),
+            state_hash.to_string(),
+            Height::from(1100),
+            RegistryVersion::from(registry_version),
+            Some(registry_store_sha256.to_string()),
+        )
+    }
+
+    #[test]
+    fn reports_of_matching_registry_stores_have_no_differences() {
+        let own = report("abcd", 42, "0000");
+        // Tar files of the same registry store are not reproducible.
+        let other = report("abcd", 42, "ffff");
+        assert!(own.differences(&other).is_empty());
+    }
+
+    #[test]
+    fn differences_of_diverging_reports_are_returned() {
+        let own = report("abcd", 42, "0000");
+        let other = report("abcd", 43, "0000");
+        assert_eq!(own.differences(&other), vec!["registry_version: 42 != 43"]);
+
+        let other = report("dcba", 43, "0000");
+        assert_eq!(
+            own.differences(&other),
+            vec!["state_hash: abcd != dcba", "registry_version: 42 != 43"]
+        );
+    }
+
+    #[test]
+    fn written_report_is_read_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join(REPORT_FILE_NAME);
+        let report = report("abcd", 42, "0000");
+        report.write(&file).unwrap();
+        assert_eq!(RecoveryReport::read(&file).unwrap(), report);
+    }
+
+    // Creates an SSH key pair and an allowed signers file for the given
+    // signer. Returns the paths of the private key and the allowed signers.
+    fn create_signer(dir: &Path, signer: &str) -> (PathBuf, PathBuf) {
+        let key_file = dir.join(format!("{}_key", signer));
+        exec_cmd(
+            Command::new("ssh-keygen")
+                .args(&["-q", "-t", "ed25519", "-N", "", "-C", signer, "-f"])
+                .arg(&key_file),
+        )
+        .unwrap();
+        let public_key = fs::read_to_string(key_file.with_extension("pub")).unwrap();
+        let allowed_signers = dir.join(format!("{}_allowed_signers", signer));
+        fs::write(
+            &allowed_signers,
+            format!(
+                "{} namespaces=\"{}\" {}",
+                signer, SIGNATURE_NAMESPACE, public_key
+            ),
+        )
+        .unwrap();
+        (key_file, allowed_signers)
+    }
+
+    #[test]
+    fn signed_report_is_verified() {
+        let dir = tempfile::tempdir().unwrap();
+        let report_file = dir.path().join(REPORT_FILE_NAME);
+        report("abcd", 42, "0000").write(&report_file).unwrap();
+        let (key_file, allowed_signers) = create_signer(dir.path(), "alice");
+
+        let signature = sign_report(&report_file, &key_file).unwrap();
+        assert_eq!(signature, signature_file(&report_file));
+        verify_report_signature(&report_file, &signature, &allowed_signers, "alice").unwrap();
+
+        // Signing again replaces the previous signature.
+        sign_report(&report_file, &key_file).unwrap();
+        verify_report_signature(&report_file, &signature, &allowed_signers, "alice").unwrap();
+    }
+
+    #[test]
+    fn tampered_report_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let report_file = dir.path().join(REPORT_FILE_NAME);
+        report("abcd", 42, "0000").write(&report_file).unwrap();
+        let (key_file, allowed_signers) = create_signer(dir.path(), "alice");
+        let signature = sign_report(&report_file, &key_file).unwrap();
+
+        report("dcba", 42, "0000").write(&report_file).unwrap();
+        assert!(
+            verify_report_signature(&report_file, &signature, &allowed_signers, "alice").is_err()
+        );
+    }
+
+    #[test]
+    fn report_signed_by_another_signer_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let report_file = dir.path().join(REPORT_FILE_NAME);
+        report("abcd", 42, "0000").write(&report_file).unwrap();
+        let (_, alice_allowed_signers) = create_signer(dir.path(), "alice");
+        let (mallory_key, _) = create_signer(dir.path(), "mallory");
+        let signature = sign_report(&report_file, &mallory_key).unwrap();
+
+        assert!(
+            verify_report_signature(&report_file, &signature, &alice_allowed_signers, "alice")
+                .is_err()
+        );
+    }
+}
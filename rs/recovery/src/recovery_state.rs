@@ -0,0 +1,104 @@
+//! Persistence of the progress of a recovery process, so that an interrupted
+//! recovery can be resumed instead of being restarted from the first step.
+use crate::error::{RecoveryError, RecoveryResult};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// The name of the file in the recovery directory holding the recovery state.
+/// It is kept outside of the working directory, as the latter is overwritten
+/// by state downloads.
+pub const RECOVERY_STATE_FILE: &str = "recovery_state.json";
+
+/// The arguments of a recovery process together with the steps that have
+/// already been completed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RecoveryState<P, T> {
+    pub params: P,
+    pub completed_steps: Vec<T>,
+}
+
+impl<P, T> RecoveryState<P, T>
+where
+    P: Serialize + DeserializeOwned,
+    T: Serialize + DeserializeOwned,
+{
+    /// Read the recovery state from the given file. Returns `None` if the file
+    /// does not exist.
+    pub fn read(file: &Path) -> RecoveryResult<Option<Self>> {
+        if !file.exists() {
+            return Ok(None);
+        }
+        read_json(file).map(Some)
+    }
+
+    /// Write the recovery state to the given file. The state is written to a
+    /// temporary file first, so that an interruption never leaves a partially
+    /// written state behind.
+    pub fn save(&self, file: &Path) -> RecoveryResult<()> {
+        let content = serde_json::to_string_pretty(self).map_err(|e| {
+            RecoveryError::parsing_error(format!("Failed to serialize recovery state: {}", e))
+        })?;
+        let tmp_file = file.with_extension("tmp");
+        fs::write(&tmp_file, content).map_err(|e| RecoveryError::file_error(&tmp_file, e))?;
+        fs::rename(&tmp_file, file).map_err(|e| RecoveryError::file_error(file, e))
+    }
+}
+
+/// Read and deserialize the JSON content of the given file.
+pub fn read_json<V: DeserializeOwned>(file: &Path) -> RecoveryResult<V> {
+    let content = fs::read_to_string(file).map_err(|e| RecoveryError::file_error(file, e))?;
+    serde_json::from_str(&content)
+        .map_err(|e| RecoveryError::parsing_error(format!("Failed to parse {:?}: {}", file, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+    struct Params {
+        subnet: String,
+        node: Option<String>,
+    }
+
+    #[test]
+    fn read_returns_none_if_no_state_was_saved() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = RecoveryState::<Params, u32>::read(&dir.path().join(RECOVERY_STATE_FILE));
+        assert_eq!(state.unwrap(), None);
+    }
+
+    #[test]
+    fn saved_state_is_read_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join(RECOVERY_STATE_FILE);
+        let state = RecoveryState {
+            params: Params {
+                subnet: "subnet".to_string(),
+                node: Some("node".to_string()),
+            },
+            completed_steps: vec![1, 2],
+        };
+
+        state.save(&file).unwrap();
+        assert_eq!(RecoveryState::read(&file).unwrap(), Some(state.clone()));
+
+        // Saving again overwrites the state and leaves no temporary file.
+        let state = RecoveryState {
+            completed_steps: vec![1, 2, 3],
+            ..state
+        };
+        state.save(&file).unwrap();
+        assert_eq!(RecoveryState::read(&file).unwrap(), Some(state));
+        assert!(!file.with_extension("tmp").exists());
+    }
+
+    #[test]
+    fn reading_a_corrupted_state_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join(RECOVERY_STATE_FILE);
+        fs::write(&file, "{ \"params\": ").unwrap();
+        assert!(RecoveryState::<Params, u32>::read(&file).is_err());
+    }
+}
@@ -2,6 +2,7 @@ use crate::admin_helper::{AdminHelper, IcAdmin};
 use crate::command_helper::{exec_cmd, pipe_all};
 use crate::error::{RecoveryError, RecoveryResult};
 use crate::file_sync_helper::{remove_dir, rsync, write_file};
+use crate::recovery_report::{self, RecoveryReport};
 use crate::ssh_helper::SshHelper;
 use crate::util::{block_on, parse_hex_str};
 use crate::{replay_helper, ADMIN, CHECKPOINTS, IC_STATE, NEW_IC_STATE, READONLY};
@@ -10,9 +11,11 @@ use crate::{
     IC_STATE_EXCLUDES,
 };
 use ic_base_types::CanisterId;
+use ic_registry_routing_table::CanisterIdRange;
 use ic_replay::cmd::{SetRecoveryCupCmd, SubCommand};
+use ic_replay::player::StateParams;
 use ic_types::{Height, SubnetId};
-use slog::{info, Logger};
+use slog::{info, warn, Logger};
 use std::net::IpAddr;
 use std::path::PathBuf;
 use std::process::Command;
@@ -331,6 +334,80 @@ impl Step for ValidateReplayStep {
     }
 }
 
+pub struct VerifyRecoveryStep {
+    pub logger: Logger,
+    pub subnet_id: SubnetId,
+    pub work_dir: PathBuf,
+    pub config: PathBuf,
+    pub result: PathBuf,
+    pub registry_store_tar: Option<PathBuf>,
+    pub report: PathBuf,
+    pub key_file: Option<PathBuf>,
+}
+
+impl Step for VerifyRecoveryStep {
+    fn descr(&self) -> String {
+        let mut descr = format!(
+            "Re-derive the state hash and compare the registry local store to the registry canister by executing:\nic-replay {:?} --subnet-id {:?} verify-registry-local-store\nCompare the state hash to the replay output and write the recovery report to {:?}.",
+            self.config, self.subnet_id, self.report
+        );
+        if let Some(key_file) = &self.key_file {
+            descr.push_str(&format!(
+                "\nSign the report by executing:\n{:?}",
+                recovery_report::get_sign_command(&self.report, key_file)
+            ));
+        }
+        descr
+    }
+
+    fn exec(&self) -> RecoveryResult<()> {
+        let replayed =
+            replay_helper::read_output(self.work_dir.join(replay_helper::OUTPUT_FILE_NAME))?;
+        let verified = block_on(replay_helper::replay(
+            self.subnet_id,
+            self.config.clone(),
+            None,
+            Some(SubCommand::VerifyRegistryLocalStore),
+            self.result.clone(),
+        ))?;
+
+        info!(self.logger, "Height after replay: {}", replayed.height);
+        info!(self.logger, "Re-derived height: {}", verified.height);
+        info!(self.logger, "State hash after replay: {}", replayed.hash);
+        info!(self.logger, "Re-derived state hash: {}", verified.hash);
+        if verified.height != replayed.height || verified.hash != replayed.hash {
+            return Err(RecoveryError::invalid_output_error(
+                "Re-derived state differs from the replay output.".to_string(),
+            ));
+        }
+
+        let registry_store_sha256 = match &self.registry_store_tar {
+            Some(tar) => Some(recovery_report::get_sha256(tar)?),
+            None => None,
+        };
+        let report = RecoveryReport::new(
+            self.subnet_id,
+            verified.height,
+            verified.hash,
+            Recovery::get_recovery_height(verified.height),
+            verified.registry_version,
+            registry_store_sha256,
+        );
+        report.write(&self.report)?;
+        info!(self.logger, "Recovery report written to {:?}:", self.report);
+        info!(self.logger, "{:#?}", report);
+
+        if let Some(key_file) = &self.key_file {
+            let signature = recovery_report::sign_report(&self.report, key_file)?;
+            info!(self.logger, "Signature written to {:?}", signature);
+        } else {
+            warn!(self.logger, "No key file given, the report is not signed.");
+        }
+
+        Ok(())
+    }
+}
+
 pub struct UploadAndRestartStep {
     pub logger: Logger,
     pub node_ip: IpAddr,
@@ -470,6 +547,127 @@ impl Step for WaitForCUPStep {
     }
 }
 
+/// Splits the replayed state into the states of the source and the destination
+/// subnet using `state-tool split`, and records the height and manifest hash
+/// of both states in `<split_dir>/<subnet_id>/`.
+pub struct SplitStateStep {
+    pub logger: Logger,
+    pub state_tool: PathBuf,
+    pub source_subnet_id: SubnetId,
+    pub destination_subnet_id: SubnetId,
+    pub canister_id_ranges: Vec<CanisterIdRange>,
+    pub work_dir: PathBuf,
+    pub split_dir: PathBuf,
+}
+
+impl SplitStateStep {
+    fn checkpoint_name(height: Height) -> String {
+        format!("{:016x}", height.get())
+    }
+
+    fn get_split_command(&self, height: Height) -> Command {
+        let mut cmd = Command::new(&self.state_tool);
+        cmd.arg("split")
+            .arg("--state")
+            .arg(
+                self.work_dir
+                    .join("data")
+                    .join(IC_CHECKPOINTS_PATH)
+                    .join(Self::checkpoint_name(height)),
+            )
+            .arg("--height")
+            .arg(height.get().to_string())
+            .arg("--new-subnet-id")
+            .arg(self.destination_subnet_id.to_string())
+            .arg("--migrated-ranges");
+        self.canister_id_ranges.iter().for_each(|r| {
+            cmd.arg(format!("{}:{}", r.start, r.end));
+        });
+        cmd.arg("--out").arg(&self.split_dir);
+        cmd
+    }
+
+    fn get_manifest_command(&self, subnet_id: SubnetId, height: Height) -> Command {
+        let mut cmd = Command::new(&self.state_tool);
+        cmd.arg("manifest").arg("--state").arg(
+            self.split_dir
+                .join(subnet_id.to_string())
+                .join(CHECKPOINTS)
+                .join(Self::checkpoint_name(height)),
+        );
+        cmd
+    }
+}
+
+impl Step for SplitStateStep {
+    fn descr(&self) -> String {
+        let height =
+            replay_helper::read_output(self.work_dir.join(replay_helper::OUTPUT_FILE_NAME))
+                .map(|params| params.height)
+                .unwrap_or_else(|_| Height::from(0));
+        format!(
+            "Split the replayed state by moving the canister ranges {:?} from subnet {} to subnet {}:\n{:?}\nThen compute the state hashes of both subnets by executing:\n{:?}\n{:?}",
+            self.canister_id_ranges,
+            self.source_subnet_id,
+            self.destination_subnet_id,
+            self.get_split_command(height),
+            self.get_manifest_command(self.source_subnet_id, height),
+            self.get_manifest_command(self.destination_subnet_id, height),
+        )
+    }
+
+    fn exec(&self) -> RecoveryResult<()> {
+        let replayed =
+            replay_helper::read_output(self.work_dir.join(replay_helper::OUTPUT_FILE_NAME))?;
+        if self.split_dir.exists() {
+            info!(self.logger, "Removing previous split of the state...");
+            remove_dir(&self.split_dir)?;
+        }
+
+        if let Some(res) = exec_cmd(&mut self.get_split_command(replayed.height))? {
+            info!(self.logger, "{}", res);
+        }
+
+        for subnet_id in [self.source_subnet_id, self.destination_subnet_id] {
+            let output = exec_cmd(&mut self.get_manifest_command(subnet_id, replayed.height))?
+                .unwrap_or_default();
+            let hash = output
+                .lines()
+                .find_map(|line| line.strip_prefix("ROOT HASH: "))
+                .ok_or_else(|| {
+                    RecoveryError::invalid_output_error(format!(
+                        "No root hash found in the manifest of subnet {}",
+                        subnet_id
+                    ))
+                })?
+                .trim()
+                .to_string();
+            info!(self.logger, "State hash of subnet {}: {}", subnet_id, hash);
+
+            let state_params = StateParams {
+                height: replayed.height,
+                hash,
+                registry_version: replayed.registry_version,
+            };
+            let json = serde_json::to_string(&state_params).map_err(|e| {
+                RecoveryError::invalid_output_error(format!(
+                    "failed to serialize split output: {}",
+                    e
+                ))
+            })?;
+            write_file(
+                &self
+                    .split_dir
+                    .join(subnet_id.to_string())
+                    .join(replay_helper::OUTPUT_FILE_NAME),
+                json,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
 pub struct CleanupStep {
     pub recovery_dir: PathBuf,
 }
@@ -0,0 +1,226 @@
+use crate::recovery_iterator::RecoveryIterator;
+use crate::{error::RecoveryError, RecoveryArgs};
+use crate::{NeuronArgs, RecoveryResult};
+use clap::Parser;
+use ic_base_types::SubnetId;
+use ic_registry_routing_table::CanisterIdRange;
+use slog::Logger;
+use std::net::IpAddr;
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+
+use crate::{Recovery, Step};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, EnumIter)]
+pub enum StepType {
+    PrepareCanisterMigration,
+    HaltSourceSubnet,
+    DownloadState,
+    UpdateConfig,
+    ICReplay,
+    ValidateReplayOutput,
+    RerouteCanisterRanges,
+    SplitState,
+    ProposeSourceCup,
+    UploadSourceState,
+    ProposeDestinationCup,
+    UploadDestinationState,
+    UnhaltSourceSubnet,
+    UnhaltDestinationSubnet,
+    CompleteCanisterMigration,
+    Cleanup,
+}
+
+#[derive(Parser)]
+#[clap(version = "1.0")]
+pub struct SubnetSplittingArgs {
+    /// Id of the subnet to split
+    #[clap(long, parse(try_from_str=crate::util::subnet_id_from_str))]
+    pub source_subnet_id: SubnetId,
+
+    /// Id of the subnet to move the canisters to. The subnet must already
+    /// exist and be halted.
+    #[clap(long, parse(try_from_str=crate::util::subnet_id_from_str))]
+    pub destination_subnet_id: SubnetId,
+
+    /// Canister ID ranges to move to the destination subnet, as `start:end`
+    #[clap(long, multiple_values(true), required = true)]
+    pub canister_id_ranges: Vec<CanisterIdRange>,
+
+    /// Public ssh key to be deployed to the source subnet for read only access
+    #[clap(long)]
+    pub pub_key: Option<String>,
+
+    /// IP address of the node to download the source subnet state from
+    #[clap(long)]
+    pub download_node: Option<IpAddr>,
+
+    /// IP address of the source subnet node to upload its new state to
+    #[clap(long)]
+    pub source_upload_node: Option<IpAddr>,
+
+    /// IP address of the destination subnet node to upload its new state to
+    #[clap(long)]
+    pub destination_upload_node: Option<IpAddr>,
+}
+
+pub struct SubnetSplitting {
+    step_iterator: Box<dyn Iterator<Item = StepType>>,
+    pub params: SubnetSplittingArgs,
+    recovery: Recovery,
+    logger: Logger,
+}
+
+impl SubnetSplitting {
+    pub fn new(
+        logger: Logger,
+        recovery_args: RecoveryArgs,
+        neuron_args: Option<NeuronArgs>,
+        subnet_args: SubnetSplittingArgs,
+    ) -> Self {
+        let ssh_confirmation = neuron_args.is_some();
+        let recovery = Recovery::new(logger.clone(), recovery_args, neuron_args, ssh_confirmation)
+            .expect("Failed to init recovery");
+        Self {
+            step_iterator: Box::new(StepType::iter()),
+            params: subnet_args,
+            recovery,
+            logger,
+        }
+    }
+
+    pub fn get_recovery_api(&self) -> &Recovery {
+        &self.recovery
+    }
+
+    /// Return a step proposing the recovery CUP of the given subnet, based on
+    /// its split off state.
+    fn propose_cup(&self, subnet_id: SubnetId) -> RecoveryResult<Box<dyn Step>> {
+        let state_params = self.recovery.get_split_output(subnet_id)?;
+        let recovery_height = Recovery::get_recovery_height(state_params.height);
+        Ok(Box::new(self.recovery.update_recovery_cup(
+            subnet_id,
+            recovery_height,
+            state_params.hash,
+            &[],
+            None,
+        )))
+    }
+
+    /// Return a step uploading the split off state of the given subnet to the
+    /// given node, if any.
+    fn upload_state(
+        &self,
+        subnet_id: SubnetId,
+        node_ip: Option<IpAddr>,
+    ) -> RecoveryResult<Box<dyn Step>> {
+        if let Some(node_ip) = node_ip {
+            Ok(Box::new(
+                self.recovery.get_upload_and_restart_step_with_data_src(
+                    node_ip,
+                    self.recovery.get_split_state_dir(subnet_id),
+                ),
+            ))
+        } else {
+            Err(RecoveryError::StepSkipped)
+        }
+    }
+}
+
+impl RecoveryIterator<StepType> for SubnetSplitting {
+    fn get_step_iterator(&mut self) -> &mut Box<dyn Iterator<Item = StepType>> {
+        &mut self.step_iterator
+    }
+
+    fn get_logger(&self) -> &Logger {
+        &self.logger
+    }
+
+    fn get_step_impl(&self, step_type: StepType) -> RecoveryResult<Box<dyn Step>> {
+        let source = self.params.source_subnet_id;
+        let destination = self.params.destination_subnet_id;
+        match step_type {
+            StepType::PrepareCanisterMigration => {
+                Ok(Box::new(self.recovery.prepare_canister_migration(
+                    &self.params.canister_id_ranges,
+                    source,
+                    destination,
+                )))
+            }
+
+            StepType::HaltSourceSubnet => {
+                let keys = if let Some(pub_key) = &self.params.pub_key {
+                    vec![pub_key.clone()]
+                } else {
+                    vec![]
+                };
+                Ok(Box::new(self.recovery.halt_subnet(source, true, &keys)))
+            }
+
+            StepType::DownloadState => {
+                if let Some(node_ip) = self.params.download_node {
+                    Ok(Box::new(self.recovery.get_download_state_step(
+                        node_ip,
+                        self.params.pub_key.is_some(),
+                    )))
+                } else {
+                    Err(RecoveryError::StepSkipped)
+                }
+            }
+
+            StepType::UpdateConfig => Ok(Box::new(self.recovery.get_update_config_step())),
+
+            StepType::ICReplay => Ok(Box::new(self.recovery.get_replay_step(source, None, None))),
+
+            StepType::ValidateReplayOutput => {
+                Ok(Box::new(self.recovery.get_validate_replay_step(source, 0)))
+            }
+
+            StepType::RerouteCanisterRanges => Ok(Box::new(self.recovery.reroute_canister_ranges(
+                &self.params.canister_id_ranges,
+                source,
+                destination,
+            ))),
+
+            StepType::SplitState => Ok(Box::new(self.recovery.get_split_state_step(
+                source,
+                destination,
+                self.params.canister_id_ranges.clone(),
+            )?)),
+
+            StepType::ProposeSourceCup => self.propose_cup(source),
+
+            StepType::UploadSourceState => {
+                self.upload_state(source, self.params.source_upload_node)
+            }
+
+            StepType::ProposeDestinationCup => self.propose_cup(destination),
+
+            StepType::UploadDestinationState => {
+                self.upload_state(destination, self.params.destination_upload_node)
+            }
+
+            StepType::UnhaltSourceSubnet => Ok(Box::new(self.recovery.halt_subnet(
+                source,
+                false,
+                &["".to_string()],
+            ))),
+
+            StepType::UnhaltDestinationSubnet => Ok(Box::new(self.recovery.halt_subnet(
+                destination,
+                false,
+                &["".to_string()],
+            ))),
+
+            StepType::CompleteCanisterMigration => {
+                Ok(Box::new(self.recovery.complete_canister_migration(
+                    &self.params.canister_id_ranges,
+                    source,
+                    destination,
+                )))
+            }
+
+            StepType::Cleanup => Ok(Box::new(self.recovery.get_cleanup_step())),
+        }
+    }
+}
@@ -7,7 +7,10 @@ extern crate chrono;
 use async_trait::async_trait;
 use candid::{CandidType, Decode, Encode};
 use clap::Parser;
-use cycles_minting_canister::SetAuthorizedSubnetworkListArgs;
+use cycles_minting_canister::{
+    ChangeSubnetTypeAssignmentArgs, SetAuthorizedSubnetworkListArgs, SubnetListWithType,
+    UpdateSubnetTypeArgs,
+};
 use ed25519_dalek::Keypair;
 use ic_canister_client::{Agent, Sender};
 use ic_config::subnet_config::SchedulerConfig;
@@ -109,6 +112,7 @@ use registry_canister::mutations::{
     do_bless_replica_version::BlessReplicaVersionPayload, do_create_subnet::CreateSubnetPayload,
     do_recover_subnet::RecoverSubnetPayload,
     do_remove_nodes_from_subnet::RemoveNodesFromSubnetPayload,
+    do_retire_replica_version::RetireReplicaVersionPayload,
     do_update_node_operator_config::UpdateNodeOperatorConfigPayload,
     do_update_subnet::UpdateSubnetPayload,
     do_update_subnet_replica::UpdateSubnetReplicaVersionPayload,
@@ -257,6 +261,10 @@ enum SubCommand {
     /// --propose-to-bless-replica-version instead, which is less flexible, but
     /// easier to use.
     ProposeToBlessReplicaVersionFlexible(ProposeToBlessReplicaVersionFlexibleCmd),
+    /// Submits a proposal to retire replica versions, i.e., to remove them
+    /// from the list of blessed replica versions. The proposal fails if a
+    /// subnet or the unassigned nodes still run one of the versions.
+    ProposeToRetireReplicaVersion(ProposeToRetireReplicaVersionCmd),
     /// Submits a proposal to create a new subnet.
     ProposeToCreateSubnet(ProposeToCreateSubnetCmd),
     /// Submits a proposal to update an existing subnet.
@@ -272,6 +280,12 @@ enum SubCommand {
     /// Submits a proposal to set authorized subnetworks that the cycles minting
     /// canister can use.
     ProposeToSetAuthorizedSubnetworks(ProposeToSetAuthorizedSubnetworksCmd),
+    /// Submits a proposal to add or remove a subnet type in the cycles minting
+    /// canister.
+    ProposeToUpdateSubnetType(ProposeToUpdateSubnetTypeCmd),
+    /// Submits a proposal to assign subnets a subnet type in the cycles minting
+    /// canister, or to remove them from it.
+    ProposeToChangeSubnetTypeAssignment(ProposeToChangeSubnetTypeAssignmentCmd),
     /// Submits a proposal to add a new canister on NNS.
     ProposeToAddNnsCanister(ProposeToAddNnsCanisterCmd),
     /// Convert the integer node ID into Principal Id
@@ -809,6 +823,34 @@ impl ProposalTitleAndPayload<BlessReplicaVersionPayload>
     }
 }
 
+/// Sub-command to submit a proposal to retire replica versions.
+#[derive_common_proposal_fields]
+#[derive(ProposalMetadata, Parser)]
+struct ProposeToRetireReplicaVersionCmd {
+    #[clap(name = "REPLICA_VERSION_ID", multiple_values(true), required = true)]
+    /// The replica versions to retire.
+    pub replica_version_ids: Vec<String>,
+}
+
+#[async_trait]
+impl ProposalTitleAndPayload<RetireReplicaVersionPayload> for ProposeToRetireReplicaVersionCmd {
+    fn title(&self) -> String {
+        match &self.proposal_title {
+            Some(title) => title.clone(),
+            None => format!(
+                "Retire replica versions: {}",
+                self.replica_version_ids.join(", ")
+            ),
+        }
+    }
+
+    async fn payload(&self, _: Url) -> RetireReplicaVersionPayload {
+        RetireReplicaVersionPayload {
+            replica_version_ids: self.replica_version_ids.clone(),
+        }
+    }
+}
+
 /// Sub-command to submit a proposal to create a new subnet.
 #[derive_common_proposal_fields]
 #[derive(ProposalMetadata, Parser)]
@@ -1907,6 +1949,111 @@ impl ProposalTitleAndPayload<SetAuthorizedSubnetworkListArgs>
     }
 }
 
+/// Sub-command to submit a proposal to add or remove a subnet type in the
+/// cycles minting canister.
+#[derive_common_proposal_fields]
+#[derive(ProposalMetadata, Parser)]
+struct ProposeToUpdateSubnetTypeCmd {
+    /// The subnet type to add.
+    #[clap(long)]
+    pub type_to_add: Option<String>,
+
+    /// The subnet type to remove. It must not have any subnets assigned.
+    #[clap(long)]
+    pub type_to_remove: Option<String>,
+}
+
+impl ProposeToUpdateSubnetTypeCmd {
+    fn args(&self) -> UpdateSubnetTypeArgs {
+        match (&self.type_to_add, &self.type_to_remove) {
+            (Some(subnet_type), None) => UpdateSubnetTypeArgs::Add(subnet_type.clone()),
+            (None, Some(subnet_type)) => UpdateSubnetTypeArgs::Remove(subnet_type.clone()),
+            _ => panic!("Must provide exactly one of 'type-to-add' and 'type-to-remove'"),
+        }
+    }
+}
+
+#[async_trait]
+impl ProposalTitleAndPayload<UpdateSubnetTypeArgs> for ProposeToUpdateSubnetTypeCmd {
+    fn title(&self) -> String {
+        match &self.proposal_title {
+            Some(title) => title.clone(),
+            None => match self.args() {
+                UpdateSubnetTypeArgs::Add(subnet_type) => {
+                    format!("Add subnet type: {}", subnet_type)
+                }
+                UpdateSubnetTypeArgs::Remove(subnet_type) => {
+                    format!("Remove subnet type: {}", subnet_type)
+                }
+            },
+        }
+    }
+
+    async fn payload(&self, _: Url) -> UpdateSubnetTypeArgs {
+        self.args()
+    }
+}
+
+/// Sub-command to submit a proposal to assign subnets a subnet type in the
+/// cycles minting canister, or to remove them from it.
+#[derive_common_proposal_fields]
+#[derive(ProposalMetadata, Parser)]
+struct ProposeToChangeSubnetTypeAssignmentCmd {
+    /// The subnets to assign the subnet type.
+    #[clap(long, multiple_values(true))]
+    pub subnets_to_add: Option<Vec<PrincipalId>>,
+
+    /// The subnets to remove from the subnet type.
+    #[clap(long, multiple_values(true))]
+    pub subnets_to_remove: Option<Vec<PrincipalId>>,
+
+    /// The subnet type.
+    #[clap(long)]
+    pub subnet_type: String,
+}
+
+impl ProposeToChangeSubnetTypeAssignmentCmd {
+    fn args(&self) -> ChangeSubnetTypeAssignmentArgs {
+        let subnet_list = |subnets: &Vec<PrincipalId>| SubnetListWithType {
+            subnets: subnets.iter().cloned().map(SubnetId::from).collect(),
+            subnet_type: self.subnet_type.clone(),
+        };
+        match (&self.subnets_to_add, &self.subnets_to_remove) {
+            (Some(subnets), None) => ChangeSubnetTypeAssignmentArgs::Add(subnet_list(subnets)),
+            (None, Some(subnets)) => ChangeSubnetTypeAssignmentArgs::Remove(subnet_list(subnets)),
+            _ => panic!("Must provide exactly one of 'subnets-to-add' and 'subnets-to-remove'"),
+        }
+    }
+}
+
+#[async_trait]
+impl ProposalTitleAndPayload<ChangeSubnetTypeAssignmentArgs>
+    for ProposeToChangeSubnetTypeAssignmentCmd
+{
+    fn title(&self) -> String {
+        match &self.proposal_title {
+            Some(title) => title.clone(),
+            None => match (&self.subnets_to_add, &self.subnets_to_remove) {
+                (Some(subnets), None) => format!(
+                    "Assign subnet type: {} to subnets: {}",
+                    self.subnet_type,
+                    shortened_pids_string(subnets)
+                ),
+                (None, Some(subnets)) => format!(
+                    "Remove subnet type: {} from subnets: {}",
+                    self.subnet_type,
+                    shortened_pids_string(subnets)
+                ),
+                _ => panic!("Must provide exactly one of 'subnets-to-add' and 'subnets-to-remove'"),
+            },
+        }
+    }
+
+    async fn payload(&self, _: Url) -> ChangeSubnetTypeAssignmentArgs {
+        self.args()
+    }
+}
+
 /// Sub-command to get the public key of a subnet from the registry.
 #[derive(Parser)]
 struct SubnetPublicKeyCmd {
@@ -2776,6 +2923,7 @@ async fn main() {
             SubCommand::ProposeToAddNnsCanister(_) => (),
             SubCommand::ProposeToBlessReplicaVersion(_) => (),
             SubCommand::ProposeToBlessReplicaVersionFlexible(_) => (),
+            SubCommand::ProposeToRetireReplicaVersion(_) => (),
             SubCommand::ProposeToUpdateSubnet(_) => (),
             SubCommand::ProposeToClearProvisionalWhitelist(_) => (),
             SubCommand::ProposeToUpdateRecoveryCup(_) => (),
@@ -2785,6 +2933,8 @@ async fn main() {
             SubCommand::ProposeToRemoveFirewallRules(_) => (),
             SubCommand::ProposeToUpdateFirewallRules(_) => (),
             SubCommand::ProposeToSetAuthorizedSubnetworks(_) => (),
+            SubCommand::ProposeToUpdateSubnetType(_) => (),
+            SubCommand::ProposeToChangeSubnetTypeAssignment(_) => (),
             SubCommand::ProposeToAddOrRemoveNodeProvider(_) => (),
             SubCommand::SubmitRootProposalToUpgradeGovernanceCanister(_) => (),
             SubCommand::VoteOnRootProposalToUpgradeGovernanceCanister(_) => (),
@@ -3054,6 +3204,15 @@ async fn main() {
             )
             .await;
         }
+        SubCommand::ProposeToRetireReplicaVersion(cmd) => {
+            propose_external_proposal_from_command(
+                cmd,
+                NnsFunction::RetireReplicaVersion,
+                opts.nns_url,
+                sender,
+            )
+            .await;
+        }
         SubCommand::ProposeToCreateSubnet(mut cmd) => {
             cmd.apply_defaults_for_unset_fields();
             propose_external_proposal_from_command(
@@ -3160,6 +3319,24 @@ async fn main() {
             )
             .await;
         }
+        SubCommand::ProposeToUpdateSubnetType(cmd) => {
+            propose_external_proposal_from_command(
+                cmd,
+                NnsFunction::UpdateSubnetType,
+                opts.nns_url,
+                sender,
+            )
+            .await;
+        }
+        SubCommand::ProposeToChangeSubnetTypeAssignment(cmd) => {
+            propose_external_proposal_from_command(
+                cmd,
+                NnsFunction::ChangeSubnetTypeAssignment,
+                opts.nns_url,
+                sender,
+            )
+            .await;
+        }
         SubCommand::GetProvisionalWhitelist => {
             print_and_get_last_value::<ProvisionalWhitelistProto>(
                 make_provisional_whitelist_record_key().as_bytes().to_vec(),
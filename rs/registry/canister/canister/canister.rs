@@ -41,9 +41,11 @@ use registry_canister::{
         do_delete_subnet::DeleteSubnetPayload,
         do_recover_subnet::RecoverSubnetPayload,
         do_remove_nodes_from_subnet::RemoveNodesFromSubnetPayload,
+        do_retire_replica_version::RetireReplicaVersionPayload,
         do_update_node_directly::UpdateNodeDirectlyPayload,
         do_update_node_operator_config::UpdateNodeOperatorConfigPayload,
         do_update_node_operator_config_directly::UpdateNodeOperatorConfigDirectlyPayload,
+        do_update_node_rolled_back_version_directly::UpdateNodeRolledBackVersionDirectlyPayload,
         do_update_subnet::UpdateSubnetPayload,
         do_update_subnet_replica::UpdateSubnetReplicaVersionPayload,
         do_update_unassigned_nodes_config::UpdateUnassignedNodesConfigPayload,
@@ -389,6 +391,20 @@ fn bless_replica_version_(payload: BlessReplicaVersionPayload) {
     recertify_registry();
 }
 
+#[export_name = "canister_update retire_replica_version"]
+fn retire_replica_version() {
+    check_caller_is_governance_and_log("retire_replica_version");
+    over(candid_one, |payload: RetireReplicaVersionPayload| {
+        retire_replica_version_(payload)
+    });
+}
+
+#[candid_method(update, rename = "retire_replica_version")]
+fn retire_replica_version_(payload: RetireReplicaVersionPayload) {
+    registry_mut().do_retire_replica_version(payload);
+    recertify_registry();
+}
+
 #[export_name = "canister_update update_subnet_replica_version"]
 fn update_subnet_replica_version() {
     check_caller_is_governance_and_log("update_subnet_replica_version");
@@ -796,6 +812,26 @@ fn update_node_directly_(payload: UpdateNodeDirectlyPayload) -> Result<(), Strin
     result
 }
 
+#[export_name = "canister_update update_node_rolled_back_version_directly"]
+fn update_node_rolled_back_version_directly() {
+    // This method can be called by anyone
+    println!(
+        "{}call: update_node_rolled_back_version_directly from: {}",
+        LOG_PREFIX,
+        dfn_core::api::caller()
+    );
+    over_may_reject(candid_one, update_node_rolled_back_version_directly_);
+}
+
+#[candid_method(update, rename = "update_node_rolled_back_version_directly")]
+fn update_node_rolled_back_version_directly_(
+    payload: UpdateNodeRolledBackVersionDirectlyPayload,
+) -> Result<(), String> {
+    let result = registry_mut().do_update_node_rolled_back_version_directly(payload);
+    recertify_registry();
+    result
+}
+
 #[export_name = "canister_update remove_node_directly"]
 fn remove_node_directly() {
     // This method can be called by anyone
@@ -437,6 +437,7 @@ mod tests {
             make_node_record_key(node_id).into_bytes(),
             encode_or_panic::<NodeRecord>(&NodeRecord {
                 node_operator_id: vec![0],
+                rolled_back_replica_version_id: String::new(),
                 xnet: None,
                 http: None,
                 p2p_flow_endpoints: vec![
@@ -482,6 +483,7 @@ mod tests {
             key.clone(),
             encode_or_panic::<NodeRecord>(&NodeRecord {
                 node_operator_id: vec![0],
+                rolled_back_replica_version_id: String::new(),
                 xnet: None,
                 http: None,
                 p2p_flow_endpoints: vec![
@@ -528,6 +530,7 @@ mod tests {
             key,
             encode_or_panic::<NodeRecord>(&NodeRecord {
                 node_operator_id: vec![0],
+                rolled_back_replica_version_id: String::new(),
                 xnet: None,
                 http: None,
                 p2p_flow_endpoints: vec![
@@ -295,6 +295,7 @@ mod tests {
     fn node_record_builder() -> NodeRecord {
         NodeRecord {
             node_operator_id: vec![0],
+            rolled_back_replica_version_id: String::new(),
             xnet: None,
             http: None,
             p2p_flow_endpoints: vec![],
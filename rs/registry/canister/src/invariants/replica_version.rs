@@ -9,14 +9,21 @@ use ic_nns_common::registry::decode_or_panic;
 use ic_protobuf::registry::{
     replica_version::v1::{BlessedReplicaVersions, ReplicaVersionRecord},
     subnet::v1::SubnetRecord,
+    unassigned_nodes_config::v1::UnassignedNodesConfigRecord,
 };
 use ic_registry_keys::{
     make_blessed_replica_version_key, make_replica_version_key, make_subnet_record_key,
+    make_unassigned_nodes_config_record_key,
 };
 
 /// A predicate on the replica version records contained in a registry
 /// snapshot.
 ///
+/// Each replica version that is referred to in an SubnetRecord of a subnet
+/// that is listed in the subnet list, or in the UnassignedNodesConfigRecord,
+/// must be contained in the BlessedReplicaVersions-List. In particular, a
+/// version that is in use cannot be retired.
+///
 /// For each replica version that is either referred to in an SubnetRecord
 /// of a subnet that is listed in the subnet list or that is contained
 /// the BlessedReplicaVersions-List, the following is checked:
@@ -38,6 +45,21 @@ pub(crate) fn check_replica_version_invariants(
             version_list.blessed_version_ids
         })
         .unwrap_or_else(Vec::default);
+
+    // Check that all versions in use are blessed, i.e., have not been retired.
+    let unassigned_version = get_unassigned_nodes_replica_version(snapshot);
+    for version in versions.iter().chain(unassigned_version.iter()) {
+        if !blessed_version_ids.contains(version) {
+            return Err(InvariantCheckError {
+                msg: format!(
+                    "replica version {} is in use, but not blessed (blessed versions: {:?})",
+                    version, blessed_version_ids
+                ),
+                source: None,
+            });
+        }
+    }
+
     versions.extend(blessed_version_ids);
     versions.dedup();
 
@@ -76,6 +98,16 @@ fn get_subnet_record(snapshot: &RegistrySnapshot, subnet_id: SubnetId) -> Subnet
         .unwrap_or_else(|| panic!("Could not get subnet record for subnet: {}", subnet_id))
 }
 
+/// Returns the replica version of the unassigned nodes, if one is set.
+fn get_unassigned_nodes_replica_version(snapshot: &RegistrySnapshot) -> Option<String> {
+    get_value_from_snapshot::<UnassignedNodesConfigRecord>(
+        snapshot,
+        make_unassigned_nodes_config_record_key(),
+    )
+    .map(|config| config.replica_version)
+    .filter(|version| !version.is_empty())
+}
+
 /// Returns the list of replica versions where each version is referred to
 /// by at least one subnet.
 fn get_all_replica_versions_of_subnets(snapshot: &RegistrySnapshot) -> Vec<String> {
@@ -0,0 +1,105 @@
+use crate::{
+    common::LOG_PREFIX,
+    mutations::common::{decode_registry_value, encode_or_panic},
+    registry::Registry,
+};
+
+use candid::{CandidType, Deserialize};
+#[cfg(target_arch = "wasm32")]
+use dfn_core::println;
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+use ic_protobuf::registry::replica_version::v1::BlessedReplicaVersions;
+use ic_registry_keys::{make_blessed_replica_version_key, make_replica_version_key};
+use ic_registry_transport::pb::v1::{registry_mutation, RegistryMutation};
+
+impl Registry {
+    /// Retires replica versions, i.e., removes the versions' IDs from the
+    /// list of blessed replica versions and deletes their ReplicaVersionRecords.
+    ///
+    /// This method is called by the governance canister, after a proposal
+    /// for retiring replica versions has been accepted.
+    ///
+    /// The invariant checks ensure that no subnet and no unassigned nodes
+    /// config still refers to any of the retired versions.
+    pub fn do_retire_replica_version(&mut self, payload: RetireReplicaVersionPayload) {
+        println!("{}do_retire_replica_version: {:?}", LOG_PREFIX, payload);
+
+        assert!(
+            !payload.replica_version_ids.is_empty(),
+            "{}At least one replica version to retire must be specified.",
+            LOG_PREFIX
+        );
+
+        let version = self.latest_version();
+        // Get the current list
+        let blessed_key = make_blessed_replica_version_key();
+        let before_removal = match self.get(blessed_key.as_bytes(), version) {
+            Some(old_blessed_replica_version) => {
+                decode_registry_value::<BlessedReplicaVersions>(
+                    old_blessed_replica_version.value.clone(),
+                )
+                .blessed_version_ids
+            }
+            None => vec![],
+        };
+
+        let to_retire: BTreeSet<&String> = payload.replica_version_ids.iter().collect();
+        for &replica_version_id in &to_retire {
+            assert!(
+                before_removal.contains(replica_version_id),
+                "{}Cannot retire replica version '{}' because it is not blessed.",
+                LOG_PREFIX,
+                replica_version_id
+            );
+        }
+
+        let after_removal: Vec<String> = before_removal
+            .iter()
+            .filter(|v| !to_retire.contains(v))
+            .cloned()
+            .collect();
+        println!(
+            "{}Blessed version before: {:?} and after: {:?}",
+            LOG_PREFIX, before_removal, after_removal
+        );
+
+        let mut mutations: Vec<RegistryMutation> = to_retire
+            .iter()
+            .map(|replica_version_id| {
+                // Delete the version's ReplicaVersionRecord
+                RegistryMutation {
+                    mutation_type: registry_mutation::Type::Delete as i32,
+                    key: make_replica_version_key(replica_version_id)
+                        .as_bytes()
+                        .to_vec(),
+                    value: vec![],
+                }
+            })
+            .collect();
+        // Retire the versions (that is, update the list of blessed versions)
+        mutations.push(RegistryMutation {
+            mutation_type: registry_mutation::Type::Upsert as i32,
+            key: blessed_key.as_bytes().to_vec(),
+            value: encode_or_panic(&BlessedReplicaVersions {
+                blessed_version_ids: after_removal,
+            }),
+        });
+
+        // Check invariants before applying mutations
+        self.maybe_apply_mutation_internal(mutations);
+    }
+}
+
+/// The payload of a proposal to retire replica versions.
+///
+/// Retiring a version removes it from the list of blessed replica versions, so
+/// that it can no longer be deployed to subnets or unassigned nodes. A version
+/// that is still in use by a subnet or by the unassigned nodes config cannot
+/// be retired.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RetireReplicaVersionPayload {
+    /// The IDs of the versions to retire. Each of them must be blessed.
+    pub replica_version_ids: Vec<String>,
+}
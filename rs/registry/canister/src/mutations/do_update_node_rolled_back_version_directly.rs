@@ -0,0 +1,81 @@
+use crate::{
+    common::LOG_PREFIX,
+    mutations::common::{decode_registry_value, encode_or_panic},
+    registry::Registry,
+};
+
+use candid::{CandidType, Deserialize};
+use ic_base_types::NodeId;
+use ic_protobuf::registry::node::v1::NodeRecord;
+use ic_registry_keys::{make_node_record_key, make_replica_version_key};
+use ic_registry_transport::update;
+
+impl Registry {
+    /// Records in the caller's node record that the node rolled back its
+    /// upgrade to a replica version, or clears the rolled back version.
+    ///
+    /// This method is called directly by the node itself.
+    pub fn do_update_node_rolled_back_version_directly(
+        &mut self,
+        payload: UpdateNodeRolledBackVersionDirectlyPayload,
+    ) -> Result<(), String> {
+        println!(
+            "{}do_update_node_rolled_back_version_directly: {:?}",
+            LOG_PREFIX, payload
+        );
+
+        // 1. Check that the caller is a node with a node_id that exists
+        let node_id = NodeId::from(dfn_core::api::caller());
+        let node_key = make_node_record_key(node_id);
+        let node_record_vec = &self
+            .get(node_key.as_bytes(), self.latest_version())
+            .ok_or_else(|| {
+                format!(
+                    "{}do_update_node_rolled_back_version_directly: Node Id {:} not found in the \
+                    registry, aborting node update.",
+                    LOG_PREFIX, node_id
+                )
+            })?
+            .value;
+        let mut node_record = decode_registry_value::<NodeRecord>(node_record_vec.clone());
+
+        // 2. Check that the rolled back version is a known replica version
+        let rolled_back_replica_version_id =
+            payload.rolled_back_replica_version_id.unwrap_or_default();
+        if !rolled_back_replica_version_id.is_empty()
+            && self
+                .get(
+                    make_replica_version_key(&rolled_back_replica_version_id).as_bytes(),
+                    self.latest_version(),
+                )
+                .is_none()
+        {
+            return Err(format!(
+                "Replica version {} not found in the registry",
+                rolled_back_replica_version_id
+            ));
+        }
+
+        if node_record.rolled_back_replica_version_id == rolled_back_replica_version_id {
+            return Ok(());
+        }
+
+        // 3. Create and apply the mutation for the updated record
+        node_record.rolled_back_replica_version_id = rolled_back_replica_version_id;
+        let mutations = vec![update(node_key.as_bytes(), encode_or_panic(&node_record))];
+
+        // Check invariants before applying mutations
+        self.maybe_apply_mutation_internal(mutations);
+
+        Ok(())
+    }
+}
+
+/// The payload of a request of a node to publish the replica version whose
+/// upgrade it rolled back.
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct UpdateNodeRolledBackVersionDirectlyPayload {
+    /// The rolled back replica version. If not set, a previously published
+    /// version is cleared.
+    pub rolled_back_replica_version_id: Option<String>,
+}
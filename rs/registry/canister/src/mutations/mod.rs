@@ -11,11 +11,13 @@ pub mod do_delete_subnet;
 pub mod do_recover_subnet;
 pub mod do_remove_node_operators;
 pub mod do_remove_nodes_from_subnet;
+pub mod do_retire_replica_version;
 pub mod do_set_firewall_config;
 pub mod do_update_node_directly;
 pub mod do_update_node_operator_config;
 pub mod do_update_node_operator_config_directly;
 pub mod do_update_node_rewards_table;
+pub mod do_update_node_rolled_back_version_directly;
 pub mod do_update_subnet;
 pub mod do_update_subnet_replica;
 pub mod do_update_unassigned_nodes_config;
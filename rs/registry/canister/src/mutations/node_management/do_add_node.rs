@@ -59,6 +59,7 @@ impl Registry {
                 .map(|x| flow_endpoint_from_string(x))
                 .collect(),
             node_operator_id: caller.into_vec(),
+            rolled_back_replica_version_id: String::new(),
             prometheus_metrics_http: Some(connection_endpoint_from_string(
                 &payload.prometheus_metrics_endpoint,
             )),
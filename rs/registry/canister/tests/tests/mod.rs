@@ -12,10 +12,12 @@ mod remove_node_operators;
 mod remove_nodes;
 mod remove_nodes_from_subnet;
 mod reroute_canister_ranges;
+mod retire_replica_version;
 mod update_node_directly;
 mod update_node_operator_config;
 mod update_node_operator_config_directly;
 mod update_node_rewards_table;
+mod update_node_rolled_back_version_directly;
 mod update_subnet;
 mod update_subnet_and_bless_replica_version;
 mod update_unassigned_nodes_config;
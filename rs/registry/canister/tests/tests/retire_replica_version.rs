@@ -0,0 +1,245 @@
+use candid::Encode;
+use dfn_candid::candid;
+
+use ic_nns_test_utils::{
+    itest_helpers::{
+        forward_call_via_universal_canister, local_test_on_nns_subnet, set_up_registry_canister,
+        set_up_universal_canister,
+    },
+    registry::{get_value, get_value_or_panic, invariant_compliant_mutation_as_atomic_req},
+};
+use ic_protobuf::registry::replica_version::v1::{BlessedReplicaVersions, ReplicaVersionRecord};
+use ic_registry_keys::{make_blessed_replica_version_key, make_replica_version_key};
+use ic_test_utilities::types::ids::subnet_test_id;
+
+use assert_matches::assert_matches;
+use registry_canister::{
+    init::RegistryCanisterInitPayloadBuilder,
+    mutations::{
+        do_bless_replica_version::BlessReplicaVersionPayload,
+        do_retire_replica_version::RetireReplicaVersionPayload,
+        do_update_subnet_replica::UpdateSubnetReplicaVersionPayload,
+        do_update_unassigned_nodes_config::UpdateUnassignedNodesConfigPayload,
+    },
+};
+
+const MOCK_HASH: &str = "d1bc8d3ba4afc7e109612cb73acbdddac052c93025aa1f82942edabb7deb82a1";
+
+fn bless_payload(replica_version_id: &str) -> BlessReplicaVersionPayload {
+    BlessReplicaVersionPayload {
+        replica_version_id: replica_version_id.to_string(),
+        binary_url: "".into(),
+        sha256_hex: "".into(),
+        node_manager_binary_url: "".into(),
+        node_manager_sha256_hex: "".into(),
+        release_package_url: "http://release_package.tar.gz".into(),
+        release_package_sha256_hex: MOCK_HASH.into(),
+    }
+}
+
+fn retire_payload(replica_version_ids: &[&str]) -> RetireReplicaVersionPayload {
+    RetireReplicaVersionPayload {
+        replica_version_ids: replica_version_ids.iter().map(|v| v.to_string()).collect(),
+    }
+}
+
+#[test]
+fn test_the_anonymous_user_cannot_retire_a_version() {
+    local_test_on_nns_subnet(|runtime| async move {
+        let mut registry = set_up_registry_canister(
+            &runtime,
+            RegistryCanisterInitPayloadBuilder::new()
+                .push_init_mutate_request(invariant_compliant_mutation_as_atomic_req())
+                .build(),
+        )
+        .await;
+
+        // The anonymous end-user tries to retire a version, bypassing the
+        // proposals. This should be rejected.
+        let response: Result<(), String> = registry
+            .update_(
+                "retire_replica_version",
+                candid,
+                (retire_payload(&["version_42"]),),
+            )
+            .await;
+        assert_matches!(response,
+                Err(s) if s.contains("is not authorized to call this method: retire_replica_version"));
+
+        // Go through an upgrade cycle, and verify that it still works the same
+        registry.upgrade_to_self_binary(vec![]).await.unwrap();
+        let response: Result<(), String> = registry
+            .update_(
+                "retire_replica_version",
+                candid,
+                (retire_payload(&["version_42"]),),
+            )
+            .await;
+        assert_matches!(response,
+                Err(s) if s.contains("is not authorized to call this method: retire_replica_version"));
+        assert_eq!(
+            get_value_or_panic::<BlessedReplicaVersions>(
+                &registry,
+                make_blessed_replica_version_key().as_bytes()
+            )
+            .await,
+            BlessedReplicaVersions {
+                blessed_version_ids: vec!["version_42".to_string()]
+            }
+        );
+
+        Ok(())
+    });
+}
+
+#[test]
+fn test_versions_in_use_cannot_be_retired() {
+    local_test_on_nns_subnet(|runtime| async move {
+        let init_payload = RegistryCanisterInitPayloadBuilder::new()
+            .push_init_mutate_request(invariant_compliant_mutation_as_atomic_req())
+            .build();
+        let registry = set_up_registry_canister(&runtime, init_payload).await;
+
+        // Install the universal canister in place of the proposals canister
+        let fake_proposal_canister = set_up_universal_canister(&runtime).await;
+        // Since it takes the id reserved for the proposal canister, it can impersonate
+        // it
+        assert_eq!(
+            fake_proposal_canister.canister_id(),
+            ic_nns_constants::GOVERNANCE_CANISTER_ID
+        );
+
+        for version in &["version_43", "version_44"] {
+            assert!(
+                forward_call_via_universal_canister(
+                    &fake_proposal_canister,
+                    &registry,
+                    "bless_replica_version",
+                    Encode!(&bless_payload(version)).unwrap()
+                )
+                .await
+            );
+        }
+
+        // Subnet 999 runs version 42, so it cannot be retired.
+        assert!(
+            !forward_call_via_universal_canister(
+                &fake_proposal_canister,
+                &registry,
+                "retire_replica_version",
+                Encode!(&retire_payload(&["version_42", "version_43"])).unwrap()
+            )
+            .await
+        );
+
+        // A version that is not blessed cannot be retired.
+        assert!(
+            !forward_call_via_universal_canister(
+                &fake_proposal_canister,
+                &registry,
+                "retire_replica_version",
+                Encode!(&retire_payload(&["unblessed"])).unwrap()
+            )
+            .await
+        );
+
+        // The unassigned nodes run version 44, so it cannot be retired.
+        assert!(
+            forward_call_via_universal_canister(
+                &fake_proposal_canister,
+                &registry,
+                "update_unassigned_nodes_config",
+                Encode!(&UpdateUnassignedNodesConfigPayload {
+                    ssh_readonly_access: None,
+                    replica_version: Some("version_44".to_string()),
+                })
+                .unwrap()
+            )
+            .await
+        );
+        assert!(
+            !forward_call_via_universal_canister(
+                &fake_proposal_canister,
+                &registry,
+                "retire_replica_version",
+                Encode!(&retire_payload(&["version_44"])).unwrap()
+            )
+            .await
+        );
+
+        // Nothing was retired so far.
+        assert_eq!(
+            get_value_or_panic::<BlessedReplicaVersions>(
+                &registry,
+                make_blessed_replica_version_key().as_bytes()
+            )
+            .await,
+            BlessedReplicaVersions {
+                blessed_version_ids: vec![
+                    "version_42".to_string(),
+                    "version_43".to_string(),
+                    "version_44".to_string()
+                ]
+            }
+        );
+
+        // Once subnet 999 runs version 43, version 42 can be retired.
+        assert!(
+            forward_call_via_universal_canister(
+                &fake_proposal_canister,
+                &registry,
+                "update_subnet_replica_version",
+                Encode!(&UpdateSubnetReplicaVersionPayload {
+                    subnet_id: subnet_test_id(999).get(),
+                    replica_version_id: "version_43".to_string(),
+                })
+                .unwrap(),
+            )
+            .await
+        );
+        assert!(
+            forward_call_via_universal_canister(
+                &fake_proposal_canister,
+                &registry,
+                "retire_replica_version",
+                Encode!(&retire_payload(&["version_42"])).unwrap()
+            )
+            .await
+        );
+        assert_eq!(
+            get_value_or_panic::<BlessedReplicaVersions>(
+                &registry,
+                make_blessed_replica_version_key().as_bytes()
+            )
+            .await,
+            BlessedReplicaVersions {
+                blessed_version_ids: vec!["version_43".to_string(), "version_44".to_string()]
+            }
+        );
+        assert_eq!(
+            get_value::<ReplicaVersionRecord>(
+                &registry,
+                make_replica_version_key("version_42").as_bytes()
+            )
+            .await,
+            None
+        );
+
+        // A retired version can no longer be deployed.
+        assert!(
+            !forward_call_via_universal_canister(
+                &fake_proposal_canister,
+                &registry,
+                "update_subnet_replica_version",
+                Encode!(&UpdateSubnetReplicaVersionPayload {
+                    subnet_id: subnet_test_id(999).get(),
+                    replica_version_id: "version_42".to_string(),
+                })
+                .unwrap(),
+            )
+            .await
+        );
+
+        Ok(())
+    });
+}
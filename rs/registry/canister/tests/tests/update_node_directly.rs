@@ -154,7 +154,7 @@ async fn assert_no_idkg_mega_encryption_entry(registry: &Canister<'_>, node_id:
     assert!(pk_record.is_none());
 }
 
-fn init_mutation_for_node_with_id(
+pub(crate) fn init_mutation_for_node_with_id(
     node_id: NodeId,
     payload: &AddNodePayload,
 ) -> RegistryAtomicMutateRequest {
@@ -167,6 +167,7 @@ fn init_mutation_for_node_with_id(
             .map(|x| flow_endpoint_from_string(x))
             .collect(),
         node_operator_id: TEST_USER1_PRINCIPAL.clone().to_vec(),
+        rolled_back_replica_version_id: String::new(),
         prometheus_metrics_http: Some(connection_endpoint_from_string(
             &payload.prometheus_metrics_endpoint,
         )),
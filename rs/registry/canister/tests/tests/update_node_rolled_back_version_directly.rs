@@ -0,0 +1,106 @@
+use dfn_candid::candid;
+use ic_base_types::NodeId;
+use ic_canister_client::Sender;
+use ic_nervous_system_common_test_keys::{
+    TEST_USER1_KEYPAIR, TEST_USER2_KEYPAIR, TEST_USER2_PRINCIPAL,
+};
+use ic_nns_test_utils::{
+    itest_helpers::{local_test_on_nns_subnet, set_up_registry_canister},
+    registry::{
+        get_value_or_panic, invariant_compliant_mutation_as_atomic_req, prepare_add_node_payload,
+    },
+};
+use ic_protobuf::registry::node::v1::NodeRecord;
+use ic_registry_keys::make_node_record_key;
+use registry_canister::{
+    init::RegistryCanisterInitPayloadBuilder,
+    mutations::do_update_node_rolled_back_version_directly::UpdateNodeRolledBackVersionDirectlyPayload,
+};
+
+use super::update_node_directly::init_mutation_for_node_with_id;
+
+#[test]
+fn rolled_back_version_is_published_and_cleared_by_the_node() {
+    local_test_on_nns_subnet(|runtime| async move {
+        let (add_node_payload, _, _) = prepare_add_node_payload();
+        let node_id = NodeId::from(*TEST_USER2_PRINCIPAL);
+
+        let registry = set_up_registry_canister(
+            &runtime,
+            RegistryCanisterInitPayloadBuilder::new()
+                .push_init_mutate_request(invariant_compliant_mutation_as_atomic_req())
+                .push_init_mutate_request(init_mutation_for_node_with_id(
+                    node_id,
+                    &add_node_payload,
+                ))
+                .build(),
+        )
+        .await;
+
+        let node_sender = Sender::from_keypair(&TEST_USER2_KEYPAIR);
+        let other_sender = Sender::from_keypair(&TEST_USER1_KEYPAIR);
+        let publish = |version: Option<&str>| UpdateNodeRolledBackVersionDirectlyPayload {
+            rolled_back_replica_version_id: version.map(String::from),
+        };
+
+        // A caller that is not a node cannot publish a rolled back version.
+        let response: Result<(), String> = registry
+            .update_from_sender(
+                "update_node_rolled_back_version_directly",
+                candid,
+                (publish(Some("version_42")),),
+                &other_sender,
+            )
+            .await;
+        assert!(matches!(response, Err(message) if message.contains("not found in the registry")));
+
+        // Unknown replica versions are rejected.
+        let response: Result<(), String> = registry
+            .update_from_sender(
+                "update_node_rolled_back_version_directly",
+                candid,
+                (publish(Some("unknown_version")),),
+                &node_sender,
+            )
+            .await;
+        assert!(
+            matches!(response, Err(message) if message.contains("Replica version unknown_version not found"))
+        );
+        let node_record =
+            get_value_or_panic::<NodeRecord>(&registry, make_node_record_key(node_id).as_bytes())
+                .await;
+        assert!(node_record.rolled_back_replica_version_id.is_empty());
+
+        // The node publishes a blessed version it rolled back.
+        let response: Result<(), String> = registry
+            .update_from_sender(
+                "update_node_rolled_back_version_directly",
+                candid,
+                (publish(Some("version_42")),),
+                &node_sender,
+            )
+            .await;
+        assert_eq!(response, Ok(()));
+        let node_record =
+            get_value_or_panic::<NodeRecord>(&registry, make_node_record_key(node_id).as_bytes())
+                .await;
+        assert_eq!(node_record.rolled_back_replica_version_id, "version_42");
+
+        // The node clears the version again.
+        let response: Result<(), String> = registry
+            .update_from_sender(
+                "update_node_rolled_back_version_directly",
+                candid,
+                (publish(None),),
+                &node_sender,
+            )
+            .await;
+        assert_eq!(response, Ok(()));
+        let node_record =
+            get_value_or_panic::<NodeRecord>(&registry, make_node_record_key(node_id).as_bytes())
+                .await;
+        assert!(node_record.rolled_back_replica_version_id.is_empty());
+
+        Ok(())
+    });
+}
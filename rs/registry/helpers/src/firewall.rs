@@ -173,6 +173,7 @@ mod tests {
                             protocol: 2,
                         }],
                         node_operator_id: vec![],
+                        rolled_back_replica_version_id: String::new(),
                     },
                 )
             })
@@ -46,6 +46,9 @@ pub enum SubCommand {
     AddRegistryContent(AddRegistryContentCmd),
     /// Update registry local store with data from the registry canister.
     UpdateRegistryLocalStore,
+    /// Compare the registry local store with the content of the registry
+    /// canister and fail if they differ.
+    VerifyRegistryLocalStore,
     /// Remove all nodes from the subnet record that this node belongs to.
     /// Note that this does not remove individual node records.
     RemoveSubnetNodes,
@@ -16,7 +16,7 @@
 
 use crate::cmd::{ReplayToolArgs, SubCommand};
 use crate::ingress::*;
-use crate::player::{Player, ReplayResult};
+use crate::player::{Player, ReplayError, ReplayResult};
 
 use ic_canister_client::{Agent, Sender};
 use ic_config::{Config, ConfigSource};
@@ -149,14 +149,25 @@ pub fn replay(args: ReplayToolArgs) -> ReplayResult {
                 }
             };
             *res_clone.borrow_mut() = match player.replay(extra) {
-                Ok(state_params) => {
-                    if let Some(SubCommand::UpdateRegistryLocalStore) = subcmd {
+                Ok(state_params) => match subcmd {
+                    Some(SubCommand::UpdateRegistryLocalStore) => {
                         player.update_registry_local_store();
                         Ok(player.get_latest_state_params(None))
-                    } else {
-                        Ok(state_params)
                     }
-                }
+                    Some(SubCommand::VerifyRegistryLocalStore) => {
+                        match player.verify_registry_local_store() {
+                            Ok(()) => {
+                                println!("Registry local store matches the registry canister.");
+                                Ok(state_params)
+                            }
+                            Err(differences) => {
+                                differences.iter().for_each(|d| println!("{}", d));
+                                Err(ReplayError::RegistryLocalStoreMismatch(differences))
+                            }
+                        }
+                    }
+                    _ => Ok(state_params),
+                },
                 err => err,
             }
         }
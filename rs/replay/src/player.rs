@@ -34,7 +34,7 @@ use ic_registry_client::client::RegistryClientImpl;
 use ic_registry_client_helpers::deserialize_registry_value;
 use ic_registry_keys::{make_blessed_replica_version_key, make_subnet_record_key};
 use ic_registry_local_store::{
-    Changelog, ChangelogEntry, KeyMutation, LocalStoreImpl, LocalStoreWriter,
+    Changelog, ChangelogEntry, KeyMutation, LocalStoreImpl, LocalStoreReader, LocalStoreWriter,
 };
 use ic_registry_nns_data_provider::registry::registry_deltas_to_registry_transport_records;
 use ic_registry_transport::{
@@ -61,6 +61,7 @@ use ic_types::{
 use serde::{Deserialize, Serialize};
 use slog_async::AsyncGuard;
 use std::{
+    collections::BTreeMap,
     path::{Path, PathBuf},
     sync::Arc,
     time::Duration,
@@ -84,6 +85,8 @@ pub enum ReplayError {
     StateDivergence(Height),
     /// Can't proceed because an upgrade was detected.
     UpgradeDetected(StateParams),
+    /// The registry local store differs from the registry canister.
+    RegistryLocalStoreMismatch(Vec<String>),
 }
 
 pub type ReplayResult = Result<StateParams, ReplayError>;
@@ -491,6 +494,66 @@ impl Player {
         write_records_to_local_store(&local_store_path, latest_version, records)
     }
 
+    /// Compare the records of the registry local store with the records of
+    /// the registry canister. Returns a description of every difference.
+    pub fn verify_registry_local_store(&self) -> Result<(), Vec<String>> {
+        let local_store_path = self.local_store_path.clone().expect(
+           "verify_registry_local_store can only be used with registry configured with local store");
+        println!("RegistryLocalStore path: {:?}", local_store_path);
+        let canister_records: BTreeMap<_, _> = self
+            .get_changes_since(0, current_time() + Duration::from_secs(60))
+            .map_err(|err| vec![format!("Error in get_certified_changes_since: {}", err)])?
+            .into_iter()
+            .map(|r| ((r.version, r.key), r.value))
+            .collect();
+        let local_store_records: BTreeMap<_, _> = LocalStoreImpl::new(&local_store_path)
+            .get_changelog_since_version(RegistryVersion::from(0))
+            .map_err(|err| vec![format!("Failed to read the local store: {}", err)])?
+            .into_iter()
+            .enumerate()
+            .flat_map(|(i, entry)| {
+                let version = RegistryVersion::from(i as u64 + 1);
+                entry
+                    .into_iter()
+                    .map(move |mutation| ((version, mutation.key), mutation.value))
+            })
+            .collect();
+        println!(
+            "Comparing {} records of the registry canister with {} records of the local store",
+            canister_records.len(),
+            local_store_records.len()
+        );
+
+        let mut differences = Vec::new();
+        for ((version, key), value) in canister_records.iter() {
+            match local_store_records.get(&(*version, key.clone())) {
+                None => differences.push(format!(
+                    "Key {} at version {} is missing in the local store",
+                    key, version
+                )),
+                Some(local_value) if local_value != value => differences.push(format!(
+                    "Key {} at version {} has a different value in the local store",
+                    key, version
+                )),
+                _ => {}
+            }
+        }
+        for (version, key) in local_store_records.keys() {
+            if !canister_records.contains_key(&(*version, key.clone())) {
+                differences.push(format!(
+                    "Key {} at version {} is missing in the registry canister",
+                    key, version
+                ));
+            }
+        }
+
+        if differences.is_empty() {
+            Ok(())
+        } else {
+            Err(differences)
+        }
+    }
+
     /// Deliver finalized batches since last expected batch height.
     fn deliver_batches(
         &self,
@@ -7,7 +7,7 @@ use ic_crypto_sha::Sha256;
 use ic_crypto_tls_interfaces::TlsHandshake;
 use ic_interfaces::crypto::IngressSigVerifier;
 use ic_interfaces::registry::{LocalStoreCertifiedTimeReader, RegistryClient};
-use ic_logger::{info, new_replica_logger_from_config};
+use ic_logger::{info, new_replica_logger_from_config, warn};
 use ic_metrics::MetricsRegistry;
 use ic_metrics_exporter::MetricsRuntimeImpl;
 use ic_registry_client_helpers::subnet::SubnetRegistry;
@@ -236,6 +236,10 @@ fn main() -> io::Result<()> {
     context.subnet_id = format!("{}", subnet_id.get());
     let logger = logger.with_new_context(context);
 
+    if let Err(err) = ic_tracing::init(&config.tracing, node_id, subnet_id, logger.clone()) {
+        warn!(logger, "Failed to set up tracing: {}", err);
+    }
+
     info!(logger, "Replica Started");
     info!(logger, "Running in subnetwork {:?}", subnet_id);
     if let Ok((path, hash)) = get_replica_binary_hash() {
@@ -522,19 +522,28 @@ impl CanisterQueues {
         )
         .map_err(|_| ())?;
 
+        self.discard_message_to_self(own_canister_id);
+        Ok(())
+    }
+
+    /// Pops the message at the head of the output queue to `own_canister_id`
+    /// and drops it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the output queue to `own_canister_id` is empty.
+    pub(super) fn discard_message_to_self(&mut self, own_canister_id: CanisterId) {
         let msg = self
             .canister_queues
             .get_mut(&own_canister_id)
-            .expect("Output queue existed above so should not fail.")
+            .expect("No output queue to own canister.")
             .1
             .pop()
-            .expect("Message peeked above so pop should not fail.")
+            .expect("Output queue to own canister is empty.")
             .1;
         let oq_stats_delta = OutputQueuesStats::stats_delta(&msg);
         self.output_queues_stats -= oq_stats_delta;
         self.memory_usage_stats -= MemoryUsageStats::stats_delta(QueueOp::Pop, &msg);
-
-        Ok(())
     }
 
     /// Returns the number of enqueued ingress messages.
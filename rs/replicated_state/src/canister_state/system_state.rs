@@ -1,7 +1,7 @@
 mod call_context_manager;
 
-use super::queues::can_push;
 pub use super::queues::memory_required_to_push_request;
+use super::queues::{can_push, QUEUE_INDEX_NONE};
 pub use crate::canister_state::queues::CanisterOutputQueuesIterator;
 use crate::{CanisterQueues, InputQueueType, StateError};
 pub use call_context_manager::{CallContext, CallContextAction, CallContextManager, CallOrigin};
@@ -15,7 +15,7 @@ use ic_registry_subnet_type::SubnetType;
 use ic_types::{
     messages::{Ingress, Request, RequestOrResponse, Response, StopCanisterContext},
     nominal_cycles::NominalCycles,
-    CanisterId, Cycles, MemoryAllocation, NumBytes, PrincipalId, QueueIndex,
+    CanisterId, Cycles, MemoryAllocation, NumBytes, PrincipalId, QueueIndex, Time,
 };
 use lazy_static::lazy_static;
 use maplit::btreeset;
@@ -448,7 +448,7 @@ impl SystemState {
             msg.receiver()
         );
 
-        match (&msg, &self.status) {
+        match (&msg, &mut self.status) {
             // Requests and responses are both rejected when stopped.
             (_, CanisterStatus::Stopped { .. }) => {
                 Err((StateError::CanisterStopped(self.canister_id()), msg))
@@ -473,10 +473,17 @@ impl SystemState {
                     ..
                 },
             ) => {
+                let mut response_callback_id = None;
                 if let RequestOrResponse::Response(response) = &msg {
+                    // Responses to best-effort calls that already timed out
+                    // (or were already responded to) are dropped silently.
+                    if call_context_manager.drop_late_best_effort_response(response) {
+                        return Ok(());
+                    }
                     call_context_manager
                         .validate_response(response)
                         .map_err(|err| (err, msg.clone()))?;
+                    response_callback_id = Some(response.originator_reply_callback);
                 }
                 push_input(
                     &mut self.queues,
@@ -486,11 +493,45 @@ impl SystemState {
                     subnet_available_memory,
                     own_subnet_type,
                     input_queue_type,
-                )
+                )?;
+                if let Some(callback_id) = response_callback_id {
+                    call_context_manager.on_response_enqueued(callback_id);
+                }
+                Ok(())
             }
         }
     }
 
+    /// Enqueues `SYS_UNKNOWN` reject responses for all best-effort calls whose
+    /// deadline has expired at `current_time` and that did not get a response
+    /// yet. `input_queue_type` determines the input schedule that a respondent
+    /// is added to.
+    ///
+    /// Returns the number of calls that timed out.
+    pub(crate) fn time_out_callbacks<F>(&mut self, current_time: Time, input_queue_type: F) -> usize
+    where
+        F: Fn(&CanisterId) -> InputQueueType,
+    {
+        let expired = match self.call_context_manager_mut() {
+            Some(call_context_manager) => {
+                call_context_manager.expire_best_effort_callbacks(current_time)
+            }
+            None => return 0,
+        };
+        let count = expired.len();
+        for response in expired {
+            let queue_type = input_queue_type(&response.respondent);
+            // The slot for the response was reserved when the request was
+            // enqueued and no response was enqueued since.
+            self.queues
+                .push_input(QUEUE_INDEX_NONE, response.into(), queue_type)
+                .unwrap_or_else(|(err, msg)| {
+                    panic!("Failed to enqueue timeout reject {:?}: {:?}", msg, err)
+                });
+        }
+        count
+    }
+
     /// Pushes an ingress message into the induction pool.
     pub(crate) fn push_ingress(&mut self, msg: Ingress) {
         self.queues.push_ingress(msg)
@@ -607,6 +648,26 @@ impl SystemState {
         let mut memory_usage = self.queues.memory_usage() as i64;
 
         while let Some(msg) = self.queues.peek_output(&self.canister_id) {
+            let response_callback_id = match &*msg {
+                RequestOrResponse::Response(response) => {
+                    // Drop responses to best-effort calls that already timed out.
+                    if self
+                        .call_context_manager_mut()
+                        .map_or(false, |ccm| ccm.drop_late_best_effort_response(response))
+                    {
+                        self.queues.discard_message_to_self(self.canister_id);
+                        available_memory += memory_usage;
+                        *subnet_available_memory += memory_usage;
+                        memory_usage = self.queues.memory_usage() as i64;
+                        available_memory -= memory_usage;
+                        *subnet_available_memory -= memory_usage;
+                        continue;
+                    }
+                    Some(response.originator_reply_callback)
+                }
+                RequestOrResponse::Request(_) => None,
+            };
+
             // Ensure that enough memory is available for inducting `msg`.
             if own_subnet_type != SubnetType::System && can_push(&*msg, available_memory).is_err() {
                 // Bail out if not enough memory available for message.
@@ -621,6 +682,11 @@ impl SystemState {
             {
                 return;
             }
+            if let Some(callback_id) = response_callback_id {
+                if let Some(call_context_manager) = self.call_context_manager_mut() {
+                    call_context_manager.on_response_enqueued(callback_id);
+                }
+            }
 
             // Adjust both `available_memory` and `subnet_available_memory` by
             // `memory_usage_before - memory_usage_after`. Defer the accounting
@@ -2,11 +2,12 @@
 mod tests;
 
 use crate::StateError;
+use ic_error_types::RejectCode;
 use ic_interfaces::{execution_environment::HypervisorError, messages::RequestOrIngress};
 use ic_protobuf::proxy::{try_from_option_field, ProxyDecodeError};
 use ic_protobuf::state::canister_state_bits::v1 as pb;
 use ic_protobuf::types::v1 as pb_types;
-use ic_types::messages::Response;
+use ic_types::messages::{Payload, RejectContext, Response};
 use ic_types::Time;
 use ic_types::{
     ingress::WasmResult,
@@ -15,10 +16,14 @@ use ic_types::{
     user_id_into_protobuf, user_id_try_from_protobuf, CanisterId, Cycles, Funds, UserId,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::convert::{From, TryFrom, TryInto};
 use std::time::Duration;
 
+/// How long after its deadline a timed out best-effort call is remembered, in
+/// order to silently drop a late response to it.
+const TIMED_OUT_CALLBACK_RETENTION: Duration = Duration::from_secs(24 * 60 * 60);
+
 /// Call context contains all context information related to an incoming call.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CallContext {
@@ -210,12 +215,23 @@ pub struct CallContextManager {
     // maps call context to its responded status
     call_contexts: BTreeMap<CallContextId, CallContext>,
     callbacks: BTreeMap<CallbackId, Callback>,
+    // best-effort callbacks that already have a response (or a synthetic
+    // reject) enqueued; any further response for them is dropped
+    #[serde(default)]
+    responded_best_effort_callbacks: BTreeSet<CallbackId>,
+    // deadlines of best-effort callbacks that timed out, so that a late
+    // response is recognized as such after the callback was unregistered,
+    // even if it carries no deadline itself
+    #[serde(default)]
+    timed_out_best_effort_callbacks: BTreeMap<CallbackId, Time>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CallOrigin {
     Ingress(UserId, MessageId),
-    CanisterUpdate(CanisterId, CallbackId),
+    /// A call from a canister, along with the deadline of the call if it is a
+    /// best-effort call.
+    CanisterUpdate(CanisterId, CallbackId, Option<Time>),
     Query(UserId),
     CanisterQuery(CanisterId, CallbackId),
     Heartbeat,
@@ -228,10 +244,11 @@ impl From<&CallOrigin> for pb::call_context::CallOrigin {
                 user_id: Some(user_id_into_protobuf(*user_id)),
                 message_id: message_id.as_bytes().to_vec(),
             }),
-            CallOrigin::CanisterUpdate(canister_id, callback_id) => {
+            CallOrigin::CanisterUpdate(canister_id, callback_id, deadline) => {
                 Self::CanisterUpdate(pb::call_context::CanisterUpdateOrQuery {
                     canister_id: Some(pb_types::CanisterId::from(*canister_id)),
                     callback_id: callback_id.get(),
+                    deadline_nanos: deadline.map(|d| d.as_nanos_since_unix_epoch()),
                 })
             }
             CallOrigin::Query(user_id) => Self::Query(user_id_into_protobuf(*user_id)),
@@ -239,6 +256,7 @@ impl From<&CallOrigin> for pb::call_context::CallOrigin {
                 Self::CanisterQuery(pb::call_context::CanisterUpdateOrQuery {
                     canister_id: Some(pb_types::CanisterId::from(*canister_id)),
                     callback_id: callback_id.get(),
+                    deadline_nanos: None,
                 })
             }
             CallOrigin::Heartbeat => Self::Heartbeat(pb::call_context::Heartbeat {}),
@@ -264,10 +282,12 @@ impl TryFrom<pb::call_context::CallOrigin> for CallOrigin {
                 pb::call_context::CanisterUpdateOrQuery {
                     canister_id,
                     callback_id,
+                    deadline_nanos,
                 },
             ) => Self::CanisterUpdate(
                 try_from_option_field(canister_id, "CallOrigin::CanisterUpdate::canister_id")?,
                 callback_id.into(),
+                deadline_nanos.map(Time::from_nanos_since_unix_epoch),
             ),
             pb::call_context::CallOrigin::Query(user_id) => {
                 Self::Query(user_id_try_from_protobuf(user_id)?)
@@ -276,6 +296,7 @@ impl TryFrom<pb::call_context::CallOrigin> for CallOrigin {
                 pb::call_context::CanisterUpdateOrQuery {
                     canister_id,
                     callback_id,
+                    ..
                 },
             ) => Self::CanisterQuery(
                 try_from_option_field(canister_id, "CallOrigin::CanisterQuery::canister_id")?,
@@ -494,9 +515,101 @@ impl CallContextManager {
     /// If we get a response for one of the outstanding calls, we unregister
     /// the callback and return it.
     pub fn unregister_callback(&mut self, callback_id: CallbackId) -> Option<Callback> {
+        self.responded_best_effort_callbacks.remove(&callback_id);
         self.callbacks.remove(&callback_id)
     }
 
+    /// Returns `true` if `response` belongs to a best-effort call that does
+    /// not expect a response anymore, i.e. its callback is gone or it already
+    /// has a response (or a synthetic reject) enqueued. Such responses are
+    /// silently dropped.
+    ///
+    /// Calls are recognized as best-effort by the deadline recorded for their
+    /// callback, as responses encoded at certification versions before V10
+    /// carry no deadline.
+    pub(crate) fn is_late_best_effort_response(&self, response: &Response) -> bool {
+        let callback_id = &response.originator_reply_callback;
+        match self.callbacks.get(callback_id) {
+            Some(callback) => {
+                callback.deadline.is_some()
+                    && self.responded_best_effort_callbacks.contains(callback_id)
+            }
+            None => {
+                self.timed_out_best_effort_callbacks
+                    .contains_key(callback_id)
+                    || response.is_best_effort()
+            }
+        }
+    }
+
+    /// Drops `response` if it is a late response to a best-effort call (see
+    /// [Self::is_late_best_effort_response]) and forgets about the call timing
+    /// out, as a call gets at most one response. Returns `true` if the
+    /// response was dropped.
+    pub(crate) fn drop_late_best_effort_response(&mut self, response: &Response) -> bool {
+        if !self.is_late_best_effort_response(response) {
+            return false;
+        }
+        self.timed_out_best_effort_callbacks
+            .remove(&response.originator_reply_callback);
+        true
+    }
+
+    /// Records that a response for the given callback was enqueued, so that
+    /// no further responses are accepted for it if it is a best-effort call.
+    pub(crate) fn on_response_enqueued(&mut self, callback_id: CallbackId) {
+        if let Some(Callback {
+            deadline: Some(_), ..
+        }) = self.callbacks.get(&callback_id)
+        {
+            self.responded_best_effort_callbacks.insert(callback_id);
+        }
+    }
+
+    /// Produces `SYS_UNKNOWN` reject responses for all best-effort calls
+    /// whose deadline has expired at `current_time` and that do not have a
+    /// response enqueued yet; and records them as responded and timed out.
+    /// Timed out calls are forgotten `TIMED_OUT_CALLBACK_RETENTION` after
+    /// their deadline.
+    ///
+    /// No cycles are refunded, since the callee may have already accepted
+    /// them.
+    pub(crate) fn expire_best_effort_callbacks(&mut self, current_time: Time) -> Vec<Response> {
+        self.timed_out_best_effort_callbacks
+            .retain(|_, deadline| *deadline + TIMED_OUT_CALLBACK_RETENTION > current_time);
+
+        let responded = &self.responded_best_effort_callbacks;
+        let expired: Vec<Response> = self
+            .callbacks
+            .iter()
+            .filter(|(callback_id, callback)| {
+                matches!(callback.deadline, Some(deadline) if deadline <= current_time)
+                    && !responded.contains(callback_id)
+            })
+            .filter_map(|(callback_id, callback)| {
+                Some(Response {
+                    originator: callback.originator?,
+                    respondent: callback.respondent?,
+                    originator_reply_callback: *callback_id,
+                    refund: Cycles::zero(),
+                    response_payload: Payload::Reject(RejectContext {
+                        code: RejectCode::SysUnknown,
+                        message: "Call deadline has expired.".to_string(),
+                    }),
+                    deadline: callback.deadline,
+                })
+            })
+            .collect();
+        self.responded_best_effort_callbacks
+            .extend(expired.iter().map(|r| r.originator_reply_callback));
+        self.timed_out_best_effort_callbacks.extend(
+            expired
+                .iter()
+                .filter_map(|r| Some((r.originator_reply_callback, r.deadline?))),
+        );
+        expired
+    }
+
     pub fn unregister_call_context(
         &mut self,
         call_context_id: CallContextId,
@@ -563,9 +676,11 @@ impl CallContextManager {
 impl From<&RequestOrIngress> for CallOrigin {
     fn from(msg: &RequestOrIngress) -> Self {
         match msg {
-            RequestOrIngress::Request(request) => {
-                CallOrigin::CanisterUpdate(request.sender, request.sender_reply_callback)
-            }
+            RequestOrIngress::Request(request) => CallOrigin::CanisterUpdate(
+                request.sender,
+                request.sender_reply_callback,
+                request.deadline,
+            ),
             RequestOrIngress::Ingress(ingress) => {
                 CallOrigin::Ingress(ingress.source, ingress.message_id.clone())
             }
@@ -594,6 +709,19 @@ impl From<&CallContextManager> for pb::CallContextManager {
                     callback: Some(callback.into()),
                 })
                 .collect(),
+            responded_best_effort_callbacks: item
+                .responded_best_effort_callbacks
+                .iter()
+                .map(|id| id.get())
+                .collect(),
+            timed_out_best_effort_callbacks: item
+                .timed_out_best_effort_callbacks
+                .iter()
+                .map(|(id, deadline)| pb::TimedOutCallbackEntry {
+                    callback_id: id.get(),
+                    deadline_nanos: deadline.as_nanos_since_unix_epoch(),
+                })
+                .collect(),
         }
     }
 }
@@ -629,6 +757,21 @@ impl TryFrom<pb::CallContextManager> for CallContextManager {
             next_callback_id: value.next_callback_id,
             call_contexts,
             callbacks,
+            responded_best_effort_callbacks: value
+                .responded_best_effort_callbacks
+                .into_iter()
+                .map(CallbackId::from)
+                .collect(),
+            timed_out_best_effort_callbacks: value
+                .timed_out_best_effort_callbacks
+                .into_iter()
+                .map(|entry| {
+                    (
+                        CallbackId::from(entry.callback_id),
+                        Time::from_nanos_since_unix_epoch(entry.deadline_nanos),
+                    )
+                })
+                .collect(),
         })
     }
 }
@@ -8,13 +8,13 @@ fn call_context_origin() {
     let id = canister_test_id(42);
     let cb_id = CallbackId::from(1);
     let cc_id = ccm.new_call_context(
-        CallOrigin::CanisterUpdate(id, cb_id),
+        CallOrigin::CanisterUpdate(id, cb_id, None),
         Cycles::from(10),
         Time::from_nanos_since_unix_epoch(0),
     );
     assert_eq!(
         ccm.call_contexts().get(&cc_id).unwrap().call_origin,
-        CallOrigin::CanisterUpdate(id, cb_id)
+        CallOrigin::CanisterUpdate(id, cb_id, None)
     );
 }
 
@@ -26,18 +26,18 @@ fn call_context_handling() {
 
     // On two incoming calls
     let call_context_id1 = call_context_manager.new_call_context(
-        CallOrigin::CanisterUpdate(canister_test_id(123), CallbackId::from(1)),
+        CallOrigin::CanisterUpdate(canister_test_id(123), CallbackId::from(1), None),
         Cycles::from(0),
         Time::from_nanos_since_unix_epoch(0),
     );
     let call_context_id2 = call_context_manager.new_call_context(
-        CallOrigin::CanisterUpdate(canister_test_id(123), CallbackId::from(2)),
+        CallOrigin::CanisterUpdate(canister_test_id(123), CallbackId::from(2), None),
         Cycles::from(0),
         Time::from_nanos_since_unix_epoch(0),
     );
 
     let call_context_id3 = call_context_manager.new_call_context(
-        CallOrigin::CanisterUpdate(canister_test_id(123), CallbackId::from(3)),
+        CallOrigin::CanisterUpdate(canister_test_id(123), CallbackId::from(3), None),
         Cycles::from(0),
         Time::from_nanos_since_unix_epoch(0),
     );
@@ -76,6 +76,7 @@ fn call_context_handling() {
         WasmClosure::new(0, 1),
         WasmClosure::new(2, 3),
         None,
+        None,
     ));
     let callback_id2 = call_context_manager.register_callback(Callback::new(
         call_context_id1,
@@ -85,6 +86,7 @@ fn call_context_handling() {
         WasmClosure::new(4, 5),
         WasmClosure::new(6, 7),
         None,
+        None,
     ));
 
     // There are 2 ougoing calls
@@ -99,6 +101,7 @@ fn call_context_handling() {
         WasmClosure::new(8, 9),
         WasmClosure::new(10, 11),
         None,
+        None,
     ));
     // There is 1 outgoing call
     assert_eq!(call_context_manager.outstanding_calls(call_context_id2), 1);
@@ -239,7 +242,7 @@ fn withdraw_cycles_fails_when_not_enough_available_cycles() {
     let id = canister_test_id(42);
     let cb_id = CallbackId::from(1);
     let cc_id = ccm.new_call_context(
-        CallOrigin::CanisterUpdate(id, cb_id),
+        CallOrigin::CanisterUpdate(id, cb_id, None),
         Cycles::from(30),
         Time::from_nanos_since_unix_epoch(0),
     );
@@ -261,7 +264,7 @@ fn withdraw_cycles_succeeds_when_enough_available_cycles() {
     let id = canister_test_id(42);
     let cb_id = CallbackId::from(1);
     let cc_id = ccm.new_call_context(
-        CallOrigin::CanisterUpdate(id, cb_id),
+        CallOrigin::CanisterUpdate(id, cb_id, None),
         Cycles::from(30),
         Time::from_nanos_since_unix_epoch(0),
     );
@@ -273,3 +276,127 @@ fn withdraw_cycles_succeeds_when_enough_available_cycles() {
         Ok(())
     );
 }
+
+#[test]
+fn expire_best_effort_callbacks() {
+    let mut ccm = CallContextManager::default();
+    let originator = canister_test_id(1);
+    let respondent = canister_test_id(2);
+    let cc_id = ccm.new_call_context(
+        CallOrigin::CanisterUpdate(canister_test_id(42), CallbackId::from(1), None),
+        Cycles::from(0),
+        Time::from_nanos_since_unix_epoch(0),
+    );
+    let callback = |deadline| {
+        Callback::new(
+            cc_id,
+            Some(originator),
+            Some(respondent),
+            Cycles::from(0),
+            WasmClosure::new(0, 2),
+            WasmClosure::new(0, 2),
+            None,
+            deadline,
+        )
+    };
+    let deadline = Time::from_nanos_since_unix_epoch(10);
+    let guaranteed_cb_id = ccm.register_callback(callback(None));
+    let best_effort_cb_id = ccm.register_callback(callback(Some(deadline)));
+    let responded_cb_id = ccm.register_callback(callback(Some(deadline)));
+    ccm.on_response_enqueued(responded_cb_id);
+
+    // Nothing expires before the deadline.
+    assert!(ccm
+        .expire_best_effort_callbacks(Time::from_nanos_since_unix_epoch(9))
+        .is_empty());
+
+    // Only the best-effort callback without a response expires.
+    let expired = ccm.expire_best_effort_callbacks(deadline);
+    assert_eq!(1, expired.len());
+    let response = &expired[0];
+    assert_eq!(best_effort_cb_id, response.originator_reply_callback);
+    assert_eq!(originator, response.originator);
+    assert_eq!(respondent, response.respondent);
+    assert_eq!(Some(deadline), response.deadline);
+    match &response.response_payload {
+        Payload::Reject(context) => assert_eq!(RejectCode::SysUnknown, context.code),
+        payload => panic!("Unexpected payload: {:?}", payload),
+    }
+
+    // Expired callbacks do not expire again; and late responses are dropped.
+    assert!(ccm
+        .expire_best_effort_callbacks(Time::from_nanos_since_unix_epoch(20))
+        .is_empty());
+    assert!(ccm.is_late_best_effort_response(response));
+    assert!(!ccm.is_late_best_effort_response(&Response {
+        originator_reply_callback: guaranteed_cb_id,
+        deadline: None,
+        ..response.clone()
+    }));
+
+    // Best-effort responses for unknown callbacks are late, too.
+    ccm.unregister_callback(best_effort_cb_id);
+    assert!(ccm.is_late_best_effort_response(response));
+}
+
+#[test]
+fn late_response_without_deadline_is_recognized_by_callback() {
+    let mut ccm = CallContextManager::default();
+    let cc_id = ccm.new_call_context(
+        CallOrigin::CanisterUpdate(canister_test_id(42), CallbackId::from(1), None),
+        Cycles::from(0),
+        Time::from_nanos_since_unix_epoch(0),
+    );
+    let deadline = Time::from_nanos_since_unix_epoch(10);
+    let callback = || {
+        Callback::new(
+            cc_id,
+            Some(canister_test_id(1)),
+            Some(canister_test_id(2)),
+            Cycles::from(0),
+            WasmClosure::new(0, 2),
+            WasmClosure::new(0, 2),
+            None,
+            Some(deadline),
+        )
+    };
+    let cb_id = ccm.register_callback(callback());
+    let other_cb_id = ccm.register_callback(callback());
+
+    let expired = ccm.expire_best_effort_callbacks(deadline);
+    assert_eq!(2, expired.len());
+    // The synthetic rejects are executed, unregistering the callbacks.
+    ccm.unregister_callback(cb_id);
+    ccm.unregister_callback(other_cb_id);
+
+    // Responses encoded before certification version V10 carry no deadline.
+    let late_response = Response {
+        deadline: None,
+        ..expired[0].clone()
+    };
+    assert_eq!(cb_id, late_response.originator_reply_callback);
+    assert!(ccm.is_late_best_effort_response(&late_response));
+    assert!(ccm.drop_late_best_effort_response(&late_response));
+    // A call gets at most one response, so the call is forgotten.
+    assert!(!ccm.is_late_best_effort_response(&late_response));
+
+    // Timed out calls are forgotten after the retention period.
+    let other_late_response = Response {
+        deadline: None,
+        ..expired[1].clone()
+    };
+    ccm.expire_best_effort_callbacks(deadline + TIMED_OUT_CALLBACK_RETENTION);
+    assert!(!ccm.is_late_best_effort_response(&other_late_response));
+
+    // The timed out calls are part of the checkpoint.
+    let mut ccm = CallContextManager::default();
+    let cb_id = ccm.register_callback(callback());
+    ccm.expire_best_effort_callbacks(deadline);
+    ccm.unregister_callback(cb_id);
+    let ccm = CallContextManager::try_from(pb::CallContextManager::from(&ccm)).unwrap();
+    assert!(ccm.is_late_best_effort_response(&Response {
+        originator_reply_callback: cb_id,
+        deadline: None,
+        ..expired[0].clone()
+    }));
+}
@@ -62,7 +62,7 @@ fn canister_state_push_input_response_no_reservation() {
             .call_context_manager_mut()
             .unwrap()
             .new_call_context(
-                CallOrigin::CanisterUpdate(CANISTER_ID, CallbackId::from(1)),
+                CallOrigin::CanisterUpdate(CANISTER_ID, CallbackId::from(1), None),
                 Cycles::zero(),
                 Time::from_nanos_since_unix_epoch(0),
             );
@@ -78,6 +78,7 @@ fn canister_state_push_input_response_no_reservation() {
                 WasmClosure::new(0, 2),
                 WasmClosure::new(0, 2),
                 None,
+                None,
             ));
 
         let response: RequestOrResponse = ResponseBuilder::default()
@@ -120,7 +121,66 @@ fn canister_state_push_input_response_success() {
             .call_context_manager_mut()
             .unwrap()
             .new_call_context(
-                CallOrigin::CanisterUpdate(CANISTER_ID, CallbackId::from(1)),
+                CallOrigin::CanisterUpdate(CANISTER_ID, CallbackId::from(1), None),
+                Cycles::zero(),
+                Time::from_nanos_since_unix_epoch(0),
+            );
+        let callback_id = canister_state
+            .system_state
+            .call_context_manager_mut()
+            .unwrap()
+            .register_callback(Callback::new(
+                call_context_id,
+                Some(CANISTER_ID),
+                Some(OTHER_CANISTER_ID),
+                Cycles::from(0),
+                WasmClosure::new(0, 2),
+                WasmClosure::new(0, 2),
+                None,
+                None,
+            ));
+
+        canister_state
+            .push_input(
+                QueueIndex::from(0),
+                ResponseBuilder::default()
+                    .respondent(OTHER_CANISTER_ID)
+                    .originator(CANISTER_ID)
+                    .originator_reply_callback(callback_id)
+                    .build()
+                    .into(),
+                MAX_CANISTER_MEMORY_SIZE,
+                &mut SUBNET_AVAILABLE_MEMORY.clone(),
+                SubnetType::Application,
+                InputQueueType::RemoteSubnet,
+            )
+            .unwrap();
+    })
+}
+
+#[test]
+fn canister_state_time_out_best_effort_callback_drops_late_response() {
+    canister_state_test(|mut canister_state| {
+        let deadline = Time::from_nanos_since_unix_epoch(10);
+
+        // Make an input queue reservation.
+        canister_state
+            .push_output_request(
+                RequestBuilder::default()
+                    .sender(CANISTER_ID)
+                    .receiver(OTHER_CANISTER_ID)
+                    .deadline(deadline)
+                    .build(),
+            )
+            .unwrap();
+        canister_state.output_into_iter().count();
+
+        let call_context_id = canister_state
+            .system_state
+            .call_context_manager_mut()
+            .unwrap()
+            .new_call_context(
+                CallOrigin::CanisterUpdate(CANISTER_ID, CallbackId::from(1), None),
                 Cycles::zero(),
                 Time::from_nanos_since_unix_epoch(0),
             );
@@ -136,8 +196,25 @@ fn canister_state_push_input_response_success() {
                 WasmClosure::new(0, 2),
                 WasmClosure::new(0, 2),
                 None,
+                Some(deadline),
             ));
 
+        // The deadline expires, a `SYS_UNKNOWN` reject is enqueued.
+        assert_eq!(
+            1,
+            canister_state
+                .system_state
+                .time_out_callbacks(deadline, |_| InputQueueType::RemoteSubnet)
+        );
+        assert_eq!(
+            1,
+            canister_state
+                .system_state
+                .queues()
+                .input_queues_message_count()
+        );
+
+        // The late response is silently dropped.
         canister_state
             .push_input(
                 QueueIndex::from(0),
@@ -145,6 +222,7 @@ fn canister_state_push_input_response_success() {
                     .respondent(OTHER_CANISTER_ID)
                     .originator(CANISTER_ID)
                     .originator_reply_callback(callback_id)
+                    .deadline(deadline)
                     .build()
                     .into(),
                 MAX_CANISTER_MEMORY_SIZE,
@@ -153,6 +231,13 @@ fn canister_state_push_input_response_success() {
                 InputQueueType::RemoteSubnet,
             )
             .unwrap();
+        assert_eq!(
+            1,
+            canister_state
+                .system_state
+                .queues()
+                .input_queues_message_count()
+        );
     })
 }
 
@@ -451,7 +536,7 @@ fn canister_state_push_input_response_memory_limit_test_impl(
             .call_context_manager_mut()
             .unwrap()
             .new_call_context(
-                CallOrigin::CanisterUpdate(CANISTER_ID, CallbackId::from(1)),
+                CallOrigin::CanisterUpdate(CANISTER_ID, CallbackId::from(1), None),
                 Cycles::zero(),
                 Time::from_nanos_since_unix_epoch(0),
             );
@@ -467,6 +552,7 @@ fn canister_state_push_input_response_memory_limit_test_impl(
                 WasmClosure::new(0, 2),
                 WasmClosure::new(0, 2),
                 None,
+                None,
             ));
 
         let response: RequestOrResponse = ResponseBuilder::default()
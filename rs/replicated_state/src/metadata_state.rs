@@ -536,6 +536,37 @@ impl SystemMetadata {
     pub fn streams(&self) -> &Streams {
         &self.streams
     }
+
+    /// Splits the metadata as part of subnet splitting, producing the metadata
+    /// of the half hosted by `subnet_id` (see `ReplicatedState::split()`).
+    ///
+    /// The original subnet retains its streams and subnet call contexts, the
+    /// new subnet starts out without any. Both halves only retain the ingress
+    /// statuses of messages addressed to principals they host according to
+    /// `routing_table`; statuses of messages addressed to principals not known
+    /// to the routing table (e.g. the management canister) are retained by the
+    /// original subnet.
+    pub(crate) fn split(mut self, subnet_id: SubnetId, routing_table: &RoutingTable) -> Self {
+        let is_original_subnet = subnet_id == self.own_subnet_id;
+
+        self.ingress_history.retain(|_, status| match status {
+            IngressStatus::Known { receiver, .. } => match routing_table.route(*receiver) {
+                Some(host) => host == subnet_id,
+                None => is_original_subnet,
+            },
+            IngressStatus::Unknown => is_original_subnet,
+        });
+
+        if !is_original_subnet {
+            self.own_subnet_id = subnet_id;
+            self.streams = Default::default();
+            self.subnet_call_context_manager = Default::default();
+            self.prev_state_hash = None;
+            self.heap_delta_estimate = NumBytes::from(0);
+            self.subnet_metrics = Default::default();
+        }
+        self
+    }
 }
 
 /// Stream is the state of bi-directional communication session with a remote
@@ -1185,6 +1216,23 @@ impl IngressHistoryState {
         self.statuses.is_empty()
     }
 
+    /// Retains only the statuses for which `predicate` returns `true`. The
+    /// pruning times of all other statuses are dropped along with them.
+    pub(crate) fn retain<F>(&mut self, predicate: F)
+    where
+        F: Fn(&MessageId, &IngressStatus) -> bool,
+    {
+        Arc::make_mut(&mut self.statuses)
+            .retain(|message_id, status| predicate(message_id, status));
+
+        let statuses = &self.statuses;
+        Arc::make_mut(&mut self.pruning_times).retain(|_, message_ids| {
+            message_ids.retain(|message_id| statuses.contains_key(message_id));
+            !message_ids.is_empty()
+        });
+        self.memory_usage = Self::compute_memory_usage(&self.statuses);
+    }
+
     /// Removes ingress history entries that are associated with a pruning_time
     /// that's older than the given time.
     pub fn prune(&mut self, time: Time) {
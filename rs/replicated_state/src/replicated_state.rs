@@ -18,7 +18,7 @@ use ic_registry_routing_table::RoutingTable;
 use ic_registry_subnet_features::BitcoinFeatureStatus;
 use ic_registry_subnet_type::SubnetType;
 use ic_types::{
-    ingress::IngressStatus,
+    ingress::{IngressState, IngressStatus},
     messages::{
         is_subnet_message, CallbackId, MessageId, RequestOrResponse, Response, SignedIngressContent,
     },
@@ -663,6 +663,33 @@ impl ReplicatedState {
         }
     }
 
+    /// Enqueues `SYS_UNKNOWN` reject responses for all best-effort calls made
+    /// by local canisters whose deadline has expired at `current_time` and
+    /// that did not get a response yet.
+    ///
+    /// Returns the number of calls that timed out.
+    pub fn time_out_callbacks(&mut self, current_time: Time) -> usize {
+        let own_subnet_id = self.metadata.own_subnet_id;
+        let routing_table = Arc::clone(&self.metadata.network_topology.routing_table);
+        let input_queue_type = |respondent: &CanisterId| {
+            if respondent.get_ref() == own_subnet_id.get_ref()
+                || routing_table.route(respondent.get()) == Some(own_subnet_id)
+            {
+                InputQueueType::LocalSubnet
+            } else {
+                InputQueueType::RemoteSubnet
+            }
+        };
+        self.canister_states
+            .values_mut()
+            .map(|canister| {
+                canister
+                    .system_state
+                    .time_out_callbacks(current_time, &input_queue_type)
+            })
+            .sum()
+    }
+
     /// Pushes an ingress message into the induction pool (canister or subnet
     /// ingress queue).
     pub fn push_ingress(&mut self, msg: SignedIngressContent) -> Result<(), StateError> {
@@ -811,6 +838,102 @@ impl ReplicatedState {
     pub fn put_bitcoin_state(&mut self, bitcoin: BitcoinState) {
         self.bitcoin = bitcoin;
     }
+
+    /// Splits the replicated state as part of subnet splitting, producing the
+    /// state of the half hosted by `subnet_id` according to `routing_table`
+    /// (the routing table after the split). Meant to be called twice on the
+    /// same state: once for the original subnet and once for the new subnet.
+    ///
+    /// Both halves only retain the canisters they host (including their
+    /// queues and call contexts). The original subnet also retains the
+    /// streams, subnet queues, consensus queue and bitcoin state; the new
+    /// subnet starts out with none of these. See `SystemMetadata::split()`
+    /// for how the ingress history is split.
+    ///
+    /// Returns an error if the resulting state violates any of the invariants
+    /// checked by `check_invariants_after_split()`.
+    pub fn split(
+        self,
+        subnet_id: SubnetId,
+        routing_table: &RoutingTable,
+    ) -> Result<Self, StateError> {
+        let ReplicatedState {
+            mut canister_states,
+            metadata,
+            subnet_queues,
+            consensus_queue,
+            root,
+            bitcoin,
+        } = self;
+        let is_original_subnet = subnet_id == metadata.own_subnet_id;
+
+        canister_states
+            .retain(|canister_id, _| routing_table.route(canister_id.get()) == Some(subnet_id));
+        let metadata = metadata.split(subnet_id, routing_table);
+        let (subnet_queues, consensus_queue, bitcoin) = if is_original_subnet {
+            (subnet_queues, consensus_queue, bitcoin)
+        } else {
+            (Default::default(), Vec::new(), Default::default())
+        };
+
+        let state = Self::new_from_checkpoint(
+            canister_states,
+            metadata,
+            subnet_queues,
+            consensus_queue,
+            bitcoin,
+            root,
+        );
+        state.check_invariants_after_split(routing_table)?;
+        Ok(state)
+    }
+
+    /// Checks the invariants that each half of a split state must uphold:
+    ///
+    ///  * all canisters are hosted by this subnet according to `routing_table`;
+    ///  * all ingress messages that may still execute are addressed to
+    ///    canisters hosted by this subnet (or to the subnet itself);
+    ///  * every canister has exactly one reservation or response in its input
+    ///    queues for each of its callbacks.
+    pub fn check_invariants_after_split(
+        &self,
+        routing_table: &RoutingTable,
+    ) -> Result<(), StateError> {
+        let own_subnet_id = self.metadata.own_subnet_id;
+
+        for canister_id in self.canister_states.keys() {
+            let host = routing_table.route(canister_id.get());
+            if host != Some(own_subnet_id) {
+                return Err(StateError::InvariantBroken(format!(
+                    "Canister {} is hosted by {:?}, not by subnet {}",
+                    canister_id, host, own_subnet_id
+                )));
+            }
+        }
+
+        for (message_id, status) in self.metadata.ingress_history.statuses() {
+            if let IngressStatus::Known {
+                receiver,
+                state: IngressState::Received | IngressState::Processing,
+                ..
+            } = status
+            {
+                let host = routing_table.route(*receiver);
+                if host.is_some() && host != Some(own_subnet_id) {
+                    return Err(StateError::InvariantBroken(format!(
+                        "Non-terminal ingress message {} is addressed to {}, hosted by {:?}",
+                        message_id, receiver, host
+                    )));
+                }
+            }
+        }
+
+        for canister in self.canister_states.values() {
+            canister.check_invariants(self.metadata.own_subnet_type, NumBytes::new(u64::MAX))?;
+        }
+
+        Ok(())
+    }
 }
 
 /// A trait exposing `ReplicatedState` functionality for the exclusive use of
@@ -3,6 +3,7 @@ use ic_btc_types_internal::{
     BitcoinAdapterRequestWrapper, BitcoinAdapterResponse, BitcoinAdapterResponseWrapper,
     GetSuccessorsRequest, GetSuccessorsResponse,
 };
+use ic_registry_routing_table::{CanisterIdRange, RoutingTable};
 use ic_registry_subnet_features::SubnetFeatures;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::replicated_state::testing::ReplicatedStateTesting;
@@ -10,19 +11,21 @@ use ic_replicated_state::testing::{CanisterQueuesTesting, SystemStateTesting};
 use ic_replicated_state::{
     replicated_state::PeekableOutputIterator, replicated_state::ReplicatedStateMessageRouting,
     BitcoinStateError, CanisterState, InputQueueType, ReplicatedState, SchedulerState, StateError,
-    SystemState,
+    Stream, SystemState,
 };
 use ic_test_utilities::state::{
     arb_replicated_state_with_queues, assert_next_eq, get_running_canister, register_callback,
 };
 use ic_test_utilities::types::ids::canister_test_id;
 use ic_test_utilities::types::{
-    ids::{subnet_test_id, user_test_id},
+    ids::{message_test_id, subnet_test_id, user_test_id},
     messages::{RequestBuilder, ResponseBuilder},
 };
 use ic_types::{
+    ingress::{IngressState, IngressStatus},
     messages::{CallbackId, RequestOrResponse, MAX_RESPONSE_COUNT_BYTES},
-    CountBytes, Cycles, QueueIndex,
+    xnet::{StreamIndex, StreamIndexedQueue},
+    CountBytes, Cycles, QueueIndex, Time,
 };
 use proptest::prelude::*;
 use std::str::FromStr;
@@ -959,3 +962,90 @@ proptest! {
         }
     }
 }
+
+/// Routing table that assigns `OTHER_CANISTER_ID` to `NEW_SUBNET_ID` and
+/// `CANISTER_ID` to `SUBNET_ID`, as after a subnet split.
+fn split_routing_table(new_subnet_id: SubnetId) -> RoutingTable {
+    let mut routing_table = RoutingTable::new();
+    routing_table
+        .insert(
+            CanisterIdRange {
+                start: CanisterId::from(0),
+                end: CanisterId::from(20),
+            },
+            new_subnet_id,
+        )
+        .unwrap();
+    routing_table
+        .insert(
+            CanisterIdRange {
+                start: CanisterId::from(21),
+                end: CanisterId::from(0xff),
+            },
+            SUBNET_ID,
+        )
+        .unwrap();
+    routing_table
+}
+
+fn ingress_status(receiver: CanisterId, state: IngressState) -> IngressStatus {
+    IngressStatus::Known {
+        receiver: receiver.get(),
+        user_id: user_test_id(1),
+        time: Time::from_nanos_since_unix_epoch(0),
+        state,
+    }
+}
+
+#[test]
+fn split_retains_hosted_canisters_and_ingress_statuses() {
+    replicated_state_test(|mut state| {
+        let new_subnet_id = subnet_test_id(2);
+        state.put_canister_state(get_running_canister(OTHER_CANISTER_ID));
+        state.set_ingress_status(
+            message_test_id(1),
+            ingress_status(CANISTER_ID, IngressState::Received),
+            NumBytes::new(u64::MAX),
+        );
+        state.set_ingress_status(
+            message_test_id(2),
+            ingress_status(OTHER_CANISTER_ID, IngressState::Processing),
+            NumBytes::new(u64::MAX),
+        );
+        state.modify_streams(|streams| {
+            streams.insert(
+                new_subnet_id,
+                Stream::new(StreamIndexedQueue::default(), StreamIndex::new(7)),
+            );
+        });
+        let routing_table = split_routing_table(new_subnet_id);
+
+        // The unsplit state violates the invariants under the new routing table.
+        assert!(matches!(
+            state.check_invariants_after_split(&routing_table),
+            Err(StateError::InvariantBroken(_))
+        ));
+
+        let state_a = state.clone().split(SUBNET_ID, &routing_table).unwrap();
+        assert_eq!(SUBNET_ID, state_a.metadata.own_subnet_id);
+        assert_eq!(
+            vec![&CANISTER_ID],
+            state_a.canister_states.keys().collect::<Vec<_>>()
+        );
+        assert_eq!(1, state_a.metadata.ingress_history.len());
+        assert!(state_a.get_stream(&new_subnet_id).is_some());
+
+        let state_b = state.split(new_subnet_id, &routing_table).unwrap();
+        assert_eq!(new_subnet_id, state_b.metadata.own_subnet_id);
+        assert_eq!(
+            vec![&OTHER_CANISTER_ID],
+            state_b.canister_states.keys().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            ingress_status(OTHER_CANISTER_ID, IngressState::Processing),
+            state_b.get_ingress_status(&message_test_id(2))
+        );
+        assert_eq!(1, state_b.metadata.ingress_history.len());
+        assert!(state_b.streams().is_empty());
+    })
+}
@@ -307,6 +307,8 @@ pub struct Transaction {
     pub created_at: ::core::option::Option<BlockHeight>,
     #[prost(message, optional, tag="6")]
     pub created_at_time: ::core::option::Option<TimeStamp>,
+    #[prost(message, optional, tag="7")]
+    pub icrc1_memo: ::core::option::Option<Icrc1Memo>,
     #[prost(oneof="transaction::Transfer", tags="1, 2, 3")]
     pub transfer: ::core::option::Option<transaction::Transfer>,
 }
@@ -366,6 +368,13 @@ pub struct Memo {
     #[prost(uint64, tag="1")]
     pub memo: u64,
 }
+/// A variable-length memo of at most 32 bytes, only set on transactions
+/// that carry one.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Icrc1Memo {
+    #[prost(bytes="vec", tag="1")]
+    pub memo: ::prost::alloc::vec::Vec<u8>,
+}
 #[derive(Eq, PartialOrd, Ord, Hash, Copy, candid::CandidType, serde::Deserialize, serde::Serialize)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct TimeStamp {
@@ -1,11 +1,11 @@
-use candid::CandidType;
+use candid::{CandidType, Nat};
 use dfn_protobuf::ProtoBuf;
 use ic_base_types::{CanisterId, PrincipalId};
 use ic_crypto_sha::Sha256;
 pub use ic_ledger_core::{
     archive::{ArchiveCanisterWasm, ArchiveOptions},
     balances::{BalanceError, Balances, BalancesStore},
-    block::BlockHeight,
+    block::{BlockHeight, MemoBytes, MAX_MEMO_LENGTH},
     tokens::{Tokens, DECIMAL_PLACES, TOKEN_SUBDIVIDABLE_BY},
 };
 use ic_ledger_core::{
@@ -153,6 +153,12 @@ pub struct Transaction {
 
     /// The time this transaction was created.
     pub created_at_time: TimeStamp,
+
+    /// The variable-length memo of transactions created with
+    /// `icrc1_transfer`. This must remain the last field and is skipped when
+    /// absent so that the hashes of older transactions don't change.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icrc1_memo: Option<MemoBytes>,
 }
 
 impl Transaction {
@@ -174,6 +180,7 @@ impl Transaction {
             operation,
             memo,
             created_at_time,
+            icrc1_memo: None,
         }
     }
 
@@ -205,6 +212,7 @@ impl Block {
             operation,
             memo,
             created_at_time,
+            icrc1_memo: None,
         };
         Ok(Self::new_from_transaction(
             parent_hash,
@@ -459,7 +467,26 @@ impl Ledger {
         payment: Operation,
         created_at_time: Option<TimeStamp>,
     ) -> Result<(BlockHeight, HashOf<EncodedBlock>), PaymentError> {
-        self.add_payment_with_timestamp(memo, payment, created_at_time, dfn_core::api::now().into())
+        self.add_payment_with_icrc1_memo(memo, None, payment, created_at_time)
+    }
+
+    /// Like `add_payment`, but also records a variable-length memo in the
+    /// transaction. The memo is part of the transaction hash, so transactions
+    /// that only differ in their memos are not duplicates of each other.
+    pub fn add_payment_with_icrc1_memo(
+        &mut self,
+        memo: Memo,
+        icrc1_memo: Option<MemoBytes>,
+        payment: Operation,
+        created_at_time: Option<TimeStamp>,
+    ) -> Result<(BlockHeight, HashOf<EncodedBlock>), PaymentError> {
+        self.add_transaction_with_timestamp(
+            memo,
+            icrc1_memo,
+            payment,
+            created_at_time,
+            dfn_core::api::now().into(),
+        )
     }
 
     /// Internal version of `add_payment` that takes a timestamp, for
@@ -470,6 +497,21 @@ impl Ledger {
         payment: Operation,
         created_at_time: Option<TimeStamp>,
         now: TimeStamp,
+    ) -> Result<(BlockHeight, HashOf<EncodedBlock>), PaymentError> {
+        self.add_transaction_with_timestamp(memo, None, payment, created_at_time, now)
+    }
+
+    /// Creates a block for the transaction and adds it to the ledger.
+    /// Every transaction is deduplicated on its hash, which covers the
+    /// operation, both memos and `created_at_time`, for the duration of the
+    /// transaction window.
+    fn add_transaction_with_timestamp(
+        &mut self,
+        memo: Memo,
+        icrc1_memo: Option<MemoBytes>,
+        payment: Operation,
+        created_at_time: Option<TimeStamp>,
+        now: TimeStamp,
     ) -> Result<(BlockHeight, HashOf<EncodedBlock>), PaymentError> {
         let num_pruned = self.purge_old_transactions(now);
 
@@ -497,6 +539,7 @@ impl Ledger {
             operation: payment.clone(),
             memo,
             created_at_time,
+            icrc1_memo,
         };
 
         let transaction_hash = transaction.hash();
@@ -549,6 +592,7 @@ impl Ledger {
                         operation,
                         memo: Memo::default(),
                         created_at_time: now,
+                        icrc1_memo: None,
                     },
                     now,
                 ))
@@ -1650,6 +1694,97 @@ mod tests {
             "Transaction hash must be stable."
         );
     }
+
+    fn icrc1_memo(bytes: &[u8]) -> Option<MemoBytes> {
+        Some(MemoBytes::try_from(bytes.to_vec()).unwrap())
+    }
+
+    /// Check that transactions that only differ in their ICRC-1 memos are
+    /// not duplicates of each other.
+    #[test]
+    fn duplicate_txns_with_icrc1_memo() {
+        let mut state = Ledger::default();
+
+        let transfer = Operation::Mint {
+            to: PrincipalId::new_user_test_id(1).into(),
+            amount: Tokens::from_e8s(1000),
+        };
+
+        let now = dfn_core::api::now().into();
+
+        for (expected_height, memo) in vec![None, icrc1_memo(&[]), icrc1_memo(&[1; 32])]
+            .into_iter()
+            .enumerate()
+        {
+            assert_eq!(
+                state
+                    .add_transaction_with_timestamp(
+                        Memo::default(),
+                        memo,
+                        transfer.clone(),
+                        Some(now),
+                        now
+                    )
+                    .unwrap()
+                    .0,
+                expected_height as u64
+            );
+        }
+
+        let err = state
+            .add_transaction_with_timestamp(
+                Memo::default(),
+                icrc1_memo(&[1; 32]),
+                transfer,
+                Some(now),
+                now,
+            )
+            .unwrap_err();
+        assert_eq!(
+            err,
+            PaymentError::TransferError(TransferError::TxDuplicate { duplicate_of: 2 })
+        );
+
+        let transfer_error = match err {
+            PaymentError::TransferError(transfer_error) => transfer_error,
+            PaymentError::Reject(msg) => panic!("Unexpected rejection: {}", msg),
+        };
+        assert_eq!(
+            Icrc1TransferError::from_transfer_error(transfer_error, now),
+            Icrc1TransferError::Duplicate {
+                duplicate_of: Nat::from(2u64)
+            }
+        );
+    }
+
+    #[test]
+    fn icrc1_memo_encoding() {
+        let mut transaction = Transaction::new(
+            PrincipalId::new_user_test_id(0).into(),
+            PrincipalId::new_user_test_id(1).into(),
+            Tokens::new(1, 0).unwrap(),
+            DEFAULT_TRANSFER_FEE,
+            Memo(123456),
+            TimeStamp::new(1, 0),
+        );
+        let hash_without_memo = transaction.hash();
+        transaction.icrc1_memo = icrc1_memo(b"order #1234");
+        assert_ne!(transaction.hash(), hash_without_memo);
+
+        let block = Block::new_from_transaction(None, transaction, TimeStamp::new(2, 0));
+        assert_eq!(Block::decode(block.clone().encode()).unwrap(), block);
+
+        let candid_block = CandidBlock::from(block);
+        assert_eq!(
+            candid_block.transaction.icrc1_memo,
+            icrc1_memo(b"order #1234")
+        );
+
+        assert!(MemoBytes::try_from(vec![0; MAX_MEMO_LENGTH + 1]).is_err());
+        let too_long =
+            serde_cbor::to_vec(&serde_bytes::ByteBuf::from(vec![0; MAX_MEMO_LENGTH + 1])).unwrap();
+        assert!(serde_cbor::from_slice::<MemoBytes>(&too_long).is_err());
+    }
 }
 
 /// Argument taken by the send endpoint
@@ -1743,6 +1878,68 @@ pub enum PaymentError {
     TransferError(TransferError),
 }
 
+/// An account addressed by the principal that owns it and a subaccount, as
+/// in the ICRC-1 token standard.
+#[derive(Serialize, Deserialize, CandidType, Clone, Copy, Hash, Debug, PartialEq, Eq)]
+pub struct Account {
+    pub owner: PrincipalId,
+    pub subaccount: Option<Subaccount>,
+}
+
+impl From<Account> for AccountIdentifier {
+    fn from(account: Account) -> Self {
+        AccountIdentifier::new(account.owner, account.subaccount)
+    }
+}
+
+/// Argument taken by the icrc1_transfer endpoint
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug, PartialEq, Eq)]
+pub struct Icrc1TransferArgs {
+    pub from_subaccount: Option<Subaccount>,
+    pub to: Account,
+    pub amount: Nat,
+    /// If not set, the ledger charges the current fee.
+    pub fee: Option<Nat>,
+    /// A memo of at most [MAX_MEMO_LENGTH] bytes that is stored in the block.
+    pub memo: Option<MemoBytes>,
+    /// Nanoseconds since the Unix epoch. If not set then now is used.
+    pub created_at_time: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, CandidType, Clone, Debug, PartialEq, Eq)]
+pub enum Icrc1TransferError {
+    BadFee { expected_fee: Nat },
+    BadBurn { min_burn_amount: Nat },
+    InsufficientFunds { balance: Nat },
+    TooOld,
+    CreatedInFuture { ledger_time: u64 },
+    Duplicate { duplicate_of: Nat },
+    TemporarilyUnavailable,
+    GenericError { error_code: Nat, message: String },
+}
+
+impl Icrc1TransferError {
+    /// Converts an error of the `transfer` endpoint. `ledger_time` is the
+    /// time reported to clients whose transactions are created in the future.
+    pub fn from_transfer_error(err: TransferError, ledger_time: TimeStamp) -> Self {
+        match err {
+            TransferError::BadFee { expected_fee } => Self::BadFee {
+                expected_fee: Nat::from(expected_fee.get_e8s()),
+            },
+            TransferError::InsufficientFunds { balance } => Self::InsufficientFunds {
+                balance: Nat::from(balance.get_e8s()),
+            },
+            TransferError::TxTooOld { .. } => Self::TooOld,
+            TransferError::TxCreatedInFuture => Self::CreatedInFuture {
+                ledger_time: ledger_time.as_nanos_since_unix_epoch(),
+            },
+            TransferError::TxDuplicate { duplicate_of } => Self::Duplicate {
+                duplicate_of: Nat::from(duplicate_of),
+            },
+        }
+    }
+}
+
 /// Struct sent by the ledger canister when it notifies a recipient of a payment
 #[derive(Serialize, Deserialize, CandidType, Clone, Hash, Debug, PartialEq, Eq)]
 pub struct TransactionNotification {
@@ -1861,6 +2058,7 @@ impl From<Operation> for CandidOperation {
 pub struct CandidTransaction {
     pub operation: CandidOperation,
     pub memo: Memo,
+    pub icrc1_memo: Option<MemoBytes>,
     pub created_at_time: TimeStamp,
 }
 
@@ -1870,10 +2068,12 @@ impl From<Transaction> for CandidTransaction {
             operation,
             memo,
             created_at_time,
+            icrc1_memo,
         }: Transaction,
     ) -> Self {
         Self {
             memo,
+            icrc1_memo,
             operation: operation.into(),
             created_at_time,
         }
@@ -1,4 +1,4 @@
-use candid::candid_method;
+use candid::{candid_method, Nat};
 use dfn_candid::{candid, candid_one, CandidOne};
 use dfn_core::{
     api::{caller, data_certificate, print, set_certified_data, trap_with},
@@ -14,6 +14,7 @@ use ic_ledger_core::{
 use ledger_canister::*;
 use std::{
     collections::{HashMap, HashSet},
+    convert::TryFrom,
     sync::{Arc, RwLock},
     time::Duration,
 };
@@ -636,6 +637,105 @@ fn transfer() {
     over_async(candid_one, transfer_candid)
 }
 
+fn tokens_from_nat(n: &Nat) -> Option<Tokens> {
+    u64::try_from(&n.0).ok().map(Tokens::from_e8s)
+}
+
+/// Transfers tokens like `transfer`, but takes ICRC-1 style arguments: the
+/// accounts are given as principals and subaccounts, the fee is optional and
+/// the memo is a blob of at most 32 bytes that is stored in the block.
+/// Transfers are deduplicated on the hash of the whole transaction, including
+/// the memo and `created_at_time`.
+#[candid_method(update, rename = "icrc1_transfer")]
+async fn icrc1_transfer(arg: Icrc1TransferArgs) -> Result<Nat, Icrc1TransferError> {
+    let caller_principal_id = caller();
+
+    if !LEDGER.read().unwrap().can_send(&caller_principal_id) {
+        panic!("Sending from {} is not allowed", caller_principal_id);
+    }
+
+    let from = AccountIdentifier::new(caller_principal_id, arg.from_subaccount);
+    let to = AccountIdentifier::from(arg.to);
+    let amount = tokens_from_nat(&arg.amount).unwrap_or_else(|| {
+        trap_with(&format!("Invalid amount: {}", arg.amount));
+        unreachable!()
+    });
+    let (minting_acc, transfer_fee) = {
+        let ledger = LEDGER.read().unwrap();
+        (
+            ledger
+                .minting_account_id
+                .expect("Minting canister id not initialized"),
+            ledger.transfer_fee,
+        )
+    };
+
+    let expected_fee = if from == minting_acc || to == minting_acc {
+        Tokens::ZERO
+    } else {
+        transfer_fee
+    };
+    if let Some(fee) = &arg.fee {
+        if tokens_from_nat(fee) != Some(expected_fee) {
+            return Err(Icrc1TransferError::BadFee {
+                expected_fee: Nat::from(expected_fee.get_e8s()),
+            });
+        }
+    }
+
+    let operation = if from == minting_acc {
+        assert_ne!(
+            to, minting_acc,
+            "It is illegal to mint to a minting_account"
+        );
+        Operation::Mint { to, amount }
+    } else if to == minting_acc {
+        if amount < transfer_fee {
+            return Err(Icrc1TransferError::BadBurn {
+                min_burn_amount: Nat::from(transfer_fee.get_e8s()),
+            });
+        }
+        Operation::Burn { from, amount }
+    } else {
+        Operation::Transfer {
+            from,
+            to,
+            amount,
+            fee: transfer_fee,
+        }
+    };
+
+    let created_at_time = arg
+        .created_at_time
+        .map(TimeStamp::from_nanos_since_unix_epoch);
+    let result = LEDGER.write().unwrap().add_payment_with_icrc1_memo(
+        Memo::default(),
+        arg.memo,
+        operation,
+        created_at_time,
+    );
+    let (height, hash) = match result {
+        Ok((height, hash)) => (height, hash),
+        Err(PaymentError::TransferError(transfer_error)) => {
+            return Err(Icrc1TransferError::from_transfer_error(
+                transfer_error,
+                dfn_core::api::now().into(),
+            ))
+        }
+        Err(PaymentError::Reject(_)) => return Err(Icrc1TransferError::TemporarilyUnavailable),
+    };
+    set_certified_data(&hash.into_bytes());
+
+    // See the comment in `send` about not trapping after this call.
+    archive_blocks().await;
+    Ok(Nat::from(height))
+}
+
+#[export_name = "canister_update icrc1_transfer"]
+fn icrc1_transfer_() {
+    over_async(candid_one, icrc1_transfer)
+}
+
 /// See caveats of use on send_dfx
 #[cfg(feature = "notify-method")]
 #[export_name = "canister_update notify_dfx"]
@@ -2,9 +2,9 @@ use crate::protobuf::transaction::Transfer as PTransfer;
 use crate::{protobuf, TransferFee, TransferFeeArgs};
 use crate::{
     AccountBalanceArgs, AccountIdentifier, Block, BlockArg, BlockRes, CyclesResponse, EncodedBlock,
-    GetBlocksArgs, GetBlocksRes, HashOf, IterBlocksArgs, IterBlocksRes, Memo, NotifyCanisterArgs,
-    Operation, SendArgs, Subaccount, TimeStamp, TipOfChainRes, Tokens, TotalSupplyArgs,
-    Transaction, TransactionNotification, DEFAULT_TRANSFER_FEE,
+    GetBlocksArgs, GetBlocksRes, HashOf, IterBlocksArgs, IterBlocksRes, Memo, MemoBytes,
+    NotifyCanisterArgs, Operation, SendArgs, Subaccount, TimeStamp, TipOfChainRes, Tokens,
+    TotalSupplyArgs, Transaction, TransactionNotification, DEFAULT_TRANSFER_FEE,
 };
 use dfn_protobuf::ToProto;
 use ic_base_types::{CanisterId, CanisterIdError};
@@ -572,6 +572,10 @@ impl ToProto for Transaction {
             .created_at_time
             .map(timestamp_from_proto)
             .unwrap_or_else(|| TimeStamp::new(0, 0));
+        let icrc1_memo = pb
+            .icrc1_memo
+            .map(|m| MemoBytes::try_from(m.memo))
+            .transpose()?;
         let operation = match pb.transfer.ok_or("This block has no transaction")? {
             PTransfer::Burn(protobuf::Burn {
                 from: Some(from),
@@ -607,6 +611,7 @@ impl ToProto for Transaction {
             operation,
             memo,
             created_at_time,
+            icrc1_memo,
         })
     }
 
@@ -615,6 +620,7 @@ impl ToProto for Transaction {
             memo,
             created_at_time,
             operation,
+            icrc1_memo,
         } = self;
         let transfer = match operation {
             Operation::Burn { from, amount } => PTransfer::Burn(protobuf::Burn {
@@ -643,6 +649,7 @@ impl ToProto for Transaction {
             memo: Some(protobuf::Memo { memo: memo.0 }),
             created_at: None,
             created_at_time: Some(timestamp_into_proto(created_at_time)),
+            icrc1_memo: icrc1_memo.map(|m| protobuf::Icrc1Memo { memo: m.into_vec() }),
             transfer: Some(transfer),
         }
     }
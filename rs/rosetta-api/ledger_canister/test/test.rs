@@ -1,4 +1,4 @@
-use candid::CandidType;
+use candid::{CandidType, Nat};
 use canister_test::*;
 use dfn_candid::{candid, candid_one, CandidOne};
 use dfn_protobuf::protobuf;
@@ -10,12 +10,13 @@ use ic_ledger_core::{
     timestamp::TimeStamp,
 };
 use ledger_canister::{
-    tokens_from_proto, AccountBalanceArgs, AccountIdentifier, Archives, BinaryAccountBalanceArgs,
-    Block, BlockArg, BlockHeight, BlockRange, BlockRes, CandidBlock, GetBlocksArgs, GetBlocksError,
-    GetBlocksRes, GetBlocksResult, IterBlocksArgs, IterBlocksRes, LedgerCanisterInitPayload, Memo,
+    tokens_from_proto, Account, AccountBalanceArgs, AccountIdentifier, Archives,
+    BinaryAccountBalanceArgs, Block, BlockArg, BlockHeight, BlockRange, BlockRes, CandidBlock,
+    GetBlocksArgs, GetBlocksError, GetBlocksRes, GetBlocksResult, Icrc1TransferArgs,
+    Icrc1TransferError, IterBlocksArgs, IterBlocksRes, LedgerCanisterInitPayload, Memo, MemoBytes,
     NotifyCanisterArgs, Operation, QueryBlocksResponse, SendArgs, Subaccount, Tokens,
     TotalSupplyArgs, Transaction, TransferArgs, TransferError, TransferFee, TransferFeeArgs,
-    DEFAULT_TRANSFER_FEE,
+    DEFAULT_TRANSFER_FEE, MAX_MEMO_LENGTH,
 };
 use on_wire::IntoWire;
 use serde::Deserialize;
@@ -114,6 +115,28 @@ async fn transfer_candid(
         .expect("transfer call trapped")
 }
 
+/// `Icrc1TransferArgs` without the bound on the memo length.
+#[derive(CandidType)]
+struct UncheckedIcrc1TransferArgs {
+    from_subaccount: Option<Subaccount>,
+    to: Account,
+    amount: Nat,
+    fee: Option<Nat>,
+    memo: Option<serde_bytes::ByteBuf>,
+    created_at_time: Option<u64>,
+}
+
+async fn icrc1_transfer(
+    ledger: &Canister<'_>,
+    from: &Sender,
+    args: Icrc1TransferArgs,
+) -> Result<Nat, Icrc1TransferError> {
+    ledger
+        .update_from_sender("icrc1_transfer", candid_one, args, from)
+        .await
+        .expect("icrc1_transfer call trapped")
+}
+
 async fn get_blocks_pb(
     archive: &Canister<'_>,
     range: std::ops::Range<u64>,
@@ -2047,6 +2070,143 @@ fn test_transfer_u64_overflow() {
         Ok(())
     });
 }
+#[test]
+fn test_icrc1_transfer() {
+    local_test_e(|r| async move {
+        let proj = Project::new(std::env::var("CARGO_MANIFEST_DIR").unwrap());
+
+        let minting_account = create_sender(0);
+        let acc1 = create_sender(1);
+        let acc2 = create_sender(2);
+
+        let acc1_address: AccountIdentifier = acc1.get_principal_id().into();
+        let acc2_address: AccountIdentifier = acc2.get_principal_id().into();
+
+        let mut accounts = HashMap::new();
+        accounts.insert(acc1_address, Tokens::from_e8s(1_000_000_000));
+
+        let ledger = proj
+            .cargo_bin("ledger-canister", &[])
+            .install_(
+                &r,
+                CandidOne(
+                    LedgerCanisterInitPayload::builder()
+                        .minting_account(
+                            CanisterId::try_from(minting_account.get_principal_id())
+                                .unwrap()
+                                .into(),
+                        )
+                        .initial_values(accounts)
+                        .build()
+                        .unwrap(),
+                ),
+            )
+            .await?;
+
+        let timestamp_nanos = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos() as u64;
+
+        let memo = |bytes: &[u8]| Some(MemoBytes::try_from(bytes.to_vec()).unwrap());
+        let args = |memo: Option<MemoBytes>| Icrc1TransferArgs {
+            from_subaccount: None,
+            to: Account {
+                owner: acc2.get_principal_id(),
+                subaccount: None,
+            },
+            amount: Nat::from(10_000_000u64),
+            fee: None,
+            memo,
+            created_at_time: Some(timestamp_nanos),
+        };
+
+        let transfer_block = icrc1_transfer(&ledger, &acc1, args(memo(b"invoice 1")))
+            .await
+            .expect("failed to transfer funds");
+
+        assert_eq!(
+            account_balance_candid(&ledger, &acc1_address).await,
+            Tokens::from_e8s(989_990_000)
+        );
+        assert_eq!(
+            account_balance_candid(&ledger, &acc2_address).await,
+            Tokens::from_e8s(10_000_000)
+        );
+
+        let result: QueryBlocksResponse = ledger
+            .query_(
+                "query_blocks",
+                candid_one,
+                GetBlocksArgs {
+                    start: u64::try_from(transfer_block.0.clone()).unwrap(),
+                    length: 1,
+                },
+            )
+            .await
+            .expect("failed to query blocks");
+        assert_eq!(result.blocks[0].transaction.icrc1_memo, memo(b"invoice 1"));
+
+        assert_eq!(
+            icrc1_transfer(&ledger, &acc1, args(memo(b"invoice 1"))).await,
+            Err(Icrc1TransferError::Duplicate {
+                duplicate_of: transfer_block.clone()
+            })
+        );
+
+        // The memo is part of the transaction hash.
+        let other_block = icrc1_transfer(&ledger, &acc1, args(memo(b"invoice 2")))
+            .await
+            .expect("failed to transfer funds");
+        assert_ne!(other_block, transfer_block);
+
+        assert_eq!(
+            icrc1_transfer(
+                &ledger,
+                &acc1,
+                Icrc1TransferArgs {
+                    fee: Some(Nat::from(10u64)),
+                    ..args(None)
+                }
+            )
+            .await,
+            Err(Icrc1TransferError::BadFee {
+                expected_fee: Nat::from(10_000u64)
+            })
+        );
+
+        let Icrc1TransferArgs {
+            from_subaccount,
+            to,
+            amount,
+            fee,
+            created_at_time,
+            ..
+        } = args(None);
+        let too_long: Result<Result<Nat, Icrc1TransferError>, String> = ledger
+            .update_from_sender(
+                "icrc1_transfer",
+                candid_one,
+                UncheckedIcrc1TransferArgs {
+                    from_subaccount,
+                    to,
+                    amount,
+                    fee,
+                    memo: Some(serde_bytes::ByteBuf::from(vec![0; MAX_MEMO_LENGTH + 1])),
+                    created_at_time,
+                },
+                &acc1,
+            )
+            .await;
+        assert!(
+            too_long.is_err(),
+            "memos longer than 32 bytes must be rejected"
+        );
+
+        Ok(())
+    });
+}
+
 async fn ledger_assert_num_blocks(ledger: &Canister<'_>, num_expected: usize) {
     let IterBlocksRes(blocks) = ledger
         .query_(
@@ -7,7 +7,7 @@ use serde::{
     Deserialize, Serialize, Serializer,
 };
 use serde_bytes::ByteBuf;
-use std::convert::TryInto;
+use std::convert::{TryFrom, TryInto};
 use std::{fmt, marker::PhantomData, str::FromStr};
 
 /// Position of a block in the chain. The first block has position 0.
@@ -172,6 +172,54 @@ impl EncodedBlock {
     }
 }
 
+/// The maximum length of a [MemoBytes] in bytes.
+pub const MAX_MEMO_LENGTH: usize = 32;
+
+/// A variable-length memo of at most [MAX_MEMO_LENGTH] bytes that clients can
+/// attach to transactions.
+///
+/// Transactions only carry a `MemoBytes` if the client set one, and skip it
+/// when it is absent, so the encoding and hash of blocks and transactions
+/// created before memos were introduced don't change.
+#[derive(Serialize, CandidType, Clone, Hash, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(transparent)]
+pub struct MemoBytes(ByteBuf);
+
+impl MemoBytes {
+    pub fn as_slice(&self) -> &[u8] {
+        self.0.as_slice()
+    }
+
+    pub fn into_vec(self) -> Vec<u8> {
+        self.0.into_vec()
+    }
+}
+
+impl TryFrom<Vec<u8>> for MemoBytes {
+    type Error = String;
+
+    fn try_from(bytes: Vec<u8>) -> Result<Self, String> {
+        if bytes.len() > MAX_MEMO_LENGTH {
+            return Err(format!(
+                "Memo must be at most {} bytes long, got {} bytes",
+                MAX_MEMO_LENGTH,
+                bytes.len()
+            ));
+        }
+        Ok(Self(ByteBuf::from(bytes)))
+    }
+}
+
+impl<'de> Deserialize<'de> for MemoBytes {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let bytes = ByteBuf::deserialize(deserializer)?;
+        Self::try_from(bytes.into_vec()).map_err(serde::de::Error::custom)
+    }
+}
+
 pub trait BlockType: Sized {
     fn encode(self) -> EncodedBlock;
 
@@ -4,15 +4,16 @@ use crate::convert::state::State;
 use crate::errors::ApiError;
 use crate::models::amount::{from_amount, ledgeramount_from_amount};
 use crate::models::operation::OperationType;
-use crate::models::{self, operation::Operation, AccountIdentifier, BlockIdentifier};
+use crate::models::{self, operation::Operation, AccountIdentifier, BlockIdentifier, Currency};
 use crate::request::request_result::RequestResult;
 use crate::request::transaction_operation_results::TransactionOperationResults;
 use crate::request::transaction_results::TransactionResults;
 use crate::request::Request;
 use crate::request_types::{
-    DisburseMetadata, FollowMetadata, KeyMetadata, MergeMaturityMetadata, NeuronIdentifierMetadata,
-    NeuronInfoMetadata, PublicKeyOrPrincipal, RequestResultMetadata, SetDissolveTimestampMetadata,
-    SpawnMetadata, Status, STATUS_COMPLETED,
+    DisburseMetadata, FollowMetadata, KeyMetadata, MakeProposalMetadata, MergeMaturityMetadata,
+    MergeMetadata, NeuronIdentifierMetadata, NeuronInfoMetadata, PublicKeyOrPrincipal,
+    RegisterVoteMetadata, RequestResultMetadata, SetDissolveTimestampMetadata, SpawnMetadata,
+    Status, STATUS_COMPLETED,
 };
 use crate::store::HashedBlock;
 use crate::transaction_id::TransactionIdentifier;
@@ -35,7 +36,7 @@ use std::convert::{TryFrom, TryInto};
 
 pub fn block_to_transaction(
     hb: &HashedBlock,
-    token_name: &str,
+    currency: &Currency,
 ) -> Result<models::Transaction, ApiError> {
     let block = Block::decode(hb.block.clone())
         .map_err(|err| ApiError::internal_error(format!("Cannot decode block: {}", err)))?;
@@ -43,7 +44,7 @@ pub fn block_to_transaction(
     let transaction_identifier = TransactionIdentifier::from(&transaction);
     let operation = transaction.operation;
     let operations = {
-        let mut ops = Request::requests_to_operations(&[Request::Transfer(operation)], token_name)?;
+        let mut ops = Request::requests_to_operations(&[Request::Transfer(operation)], currency)?;
         for op in ops.iter_mut() {
             op.status = Some(STATUS_COMPLETED.to_string());
         }
@@ -71,7 +72,7 @@ pub fn block_to_transaction(
 pub fn operations_to_requests(
     ops: &[Operation],
     preprocessing: bool,
-    token_name: &str,
+    currency: &Currency,
 ) -> Result<Vec<Request>, ApiError> {
     let op_error = |op: &Operation, e| {
         let msg = format!("In operation '{:?}': {}", op, e);
@@ -91,7 +92,10 @@ pub fn operations_to_requests(
             .map_err(|e| op_error(o, e))?;
 
         let validate_neuron_management_op = || {
-            if o.amount.is_some() && o._type != OperationType::Disburse {
+            if o.amount.is_some()
+                && o._type != OperationType::Disburse
+                && o._type != OperationType::Split
+            {
                 Err(op_error(
                     o,
                     format!(
@@ -118,7 +122,7 @@ pub fn operations_to_requests(
                     .amount
                     .as_ref()
                     .ok_or_else(|| op_error(o, "Amount must be populated".into()))?;
-                let amount = from_amount(amount, token_name).map_err(|e| op_error(o, e))?;
+                let amount = from_amount(amount, currency).map_err(|e| op_error(o, e))?;
                 state.transaction(account, amount)?;
             }
             OperationType::Fee => {
@@ -126,7 +130,7 @@ pub fn operations_to_requests(
                     .amount
                     .as_ref()
                     .ok_or_else(|| op_error(o, "Amount must be populated".into()))?;
-                let amount = from_amount(amount, token_name).map_err(|e| op_error(o, e))?;
+                let amount = from_amount(amount, currency).map_err(|e| op_error(o, e))?;
                 state.fee(account, Tokens::from_e8s((-amount) as u64))?;
             }
             OperationType::Stake => {
@@ -169,7 +173,7 @@ pub fn operations_to_requests(
                 } = o.metadata.clone().try_into()?;
                 validate_neuron_management_op()?;
                 let amount = if let Some(ref amount) = o.amount {
-                    Some(ledgeramount_from_amount(amount, token_name).map_err(|e| {
+                    Some(ledgeramount_from_amount(amount, currency).map_err(|e| {
                         ApiError::internal_error(format!("Could not convert Amount {:?}", e))
                     })?)
                 } else {
@@ -236,6 +240,68 @@ pub fn operations_to_requests(
                 };
                 state.follow(account, pid, neuron_index, topic, followees)?;
             }
+            OperationType::RegisterVote => {
+                let RegisterVoteMetadata {
+                    proposal_id,
+                    vote,
+                    controller,
+                    neuron_index,
+                } = o.metadata.clone().try_into()?;
+                validate_neuron_management_op()?;
+                let pid = match controller {
+                    None => None,
+                    Some(p) => Some(principal_id_from_public_key_or_principal(p)?),
+                };
+                state.register_vote(account, pid, neuron_index, proposal_id, vote)?;
+            }
+            OperationType::MakeProposal => {
+                let MakeProposalMetadata {
+                    title,
+                    summary,
+                    url,
+                    motion_text,
+                    controller,
+                    neuron_index,
+                } = o.metadata.clone().try_into()?;
+                validate_neuron_management_op()?;
+                let pid = match controller {
+                    None => None,
+                    Some(p) => Some(principal_id_from_public_key_or_principal(p)?),
+                };
+                state.make_proposal(
+                    account,
+                    pid,
+                    neuron_index,
+                    title,
+                    summary,
+                    url,
+                    motion_text,
+                )?;
+            }
+            OperationType::Split => {
+                let NeuronIdentifierMetadata { neuron_index } = o.metadata.clone().try_into()?;
+                validate_neuron_management_op()?;
+                let amount = o
+                    .amount
+                    .as_ref()
+                    .ok_or_else(|| op_error(o, "Amount must be populated".into()))?;
+                let amount = ledgeramount_from_amount(amount, currency).map_err(|e| {
+                    ApiError::internal_error(format!("Could not convert Amount {:?}", e))
+                })?;
+                state.split(account, neuron_index, amount)?;
+            }
+            OperationType::Merge => {
+                let MergeMetadata {
+                    source_neuron_id,
+                    neuron_index,
+                } = o.metadata.clone().try_into()?;
+                validate_neuron_management_op()?;
+                state.merge(account, neuron_index, source_neuron_id)?;
+            }
+            OperationType::ListNeurons => {
+                validate_neuron_management_op()?;
+                state.list_neurons(account)?;
+            }
         }
     }
 
@@ -391,10 +457,10 @@ pub fn make_read_state_from_update(update: &HttpCanisterUpdate) -> HttpReadState
 /// Convert TransactionOperationResults to ApiError.
 pub fn transaction_operation_result_to_api_error(
     e: TransactionOperationResults,
-    token_name: &str,
+    currency: &Currency,
 ) -> ApiError {
-    match from_transaction_operation_results(e, token_name) {
-        Ok(e) => ApiError::OperationsErrors(e, token_name.to_string()),
+    match from_transaction_operation_results(e, currency) {
+        Ok(e) => ApiError::OperationsErrors(e, currency.clone()),
         Err(e) => e,
     }
 }
@@ -402,9 +468,9 @@ pub fn transaction_operation_result_to_api_error(
 /// Convert TransactionOperationResults to TransactionResults.
 pub fn from_transaction_operation_results(
     t: TransactionOperationResults,
-    token_name: &str,
+    currency: &Currency,
 ) -> Result<TransactionResults, ApiError> {
-    let requests = convert::operations_to_requests(&t.operations, false, token_name)?;
+    let requests = convert::operations_to_requests(&t.operations, false, currency)?;
 
     let mut operations = Vec::with_capacity(requests.len());
     let mut op_idx = 0;
@@ -438,7 +504,7 @@ pub fn from_transaction_operation_results(
             response,
         } = RequestResultMetadata::try_from(o.metadata.clone())?;
         let status = response
-            .map(|e| Status::Failed(errors::convert_to_api_error(e, token_name)))
+            .map(|e| Status::Failed(errors::convert_to_api_error(e, currency)))
             .or(status)
             .ok_or_else(|| ApiError::internal_error("Could not decode Status from Operation"))?;
 
@@ -455,8 +521,8 @@ pub fn from_transaction_operation_results(
     Ok(TransactionResults { operations })
 }
 
-pub fn transaction_results_to_api_error(tr: TransactionResults, token_name: &str) -> ApiError {
-    ApiError::OperationsErrors(tr, token_name.to_string())
+pub fn transaction_results_to_api_error(tr: TransactionResults, currency: &Currency) -> ApiError {
+    ApiError::OperationsErrors(tr, currency.clone())
 }
 
 #[cfg(test)]
@@ -2,8 +2,9 @@ use crate::errors::ApiError;
 use crate::models::seconds::Seconds;
 use crate::request::Request;
 use crate::request_types::{
-    AddHotKey, Disburse, Follow, MergeMaturity, NeuronInfo, PublicKeyOrPrincipal, RemoveHotKey,
-    SetDissolveTimestamp, Spawn, Stake, StartDissolve, StopDissolve,
+    AddHotKey, Disburse, Follow, ListNeurons, MakeProposal, Merge, MergeMaturity, NeuronInfo,
+    PublicKeyOrPrincipal, RegisterVote, RemoveHotKey, SetDissolveTimestamp, Spawn, Split, Stake,
+    StartDissolve, StopDissolve,
 };
 use ic_types::PrincipalId;
 use ledger_canister::{Operation, Tokens, DEFAULT_TRANSFER_FEE};
@@ -320,6 +321,89 @@ impl State {
         }));
         Ok(())
     }
+
+    pub fn register_vote(
+        &mut self,
+        account: ledger_canister::AccountIdentifier,
+        controller: Option<PrincipalId>,
+        neuron_index: u64,
+        proposal_id: u64,
+        vote: i32,
+    ) -> Result<(), ApiError> {
+        self.flush()?;
+        self.actions.push(Request::RegisterVote(RegisterVote {
+            account,
+            proposal_id,
+            vote,
+            controller,
+            neuron_index,
+        }));
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn make_proposal(
+        &mut self,
+        account: ledger_canister::AccountIdentifier,
+        controller: Option<PrincipalId>,
+        neuron_index: u64,
+        title: Option<String>,
+        summary: String,
+        url: String,
+        motion_text: String,
+    ) -> Result<(), ApiError> {
+        self.flush()?;
+        self.actions.push(Request::MakeProposal(MakeProposal {
+            account,
+            title,
+            summary,
+            url,
+            motion_text,
+            controller,
+            neuron_index,
+        }));
+        Ok(())
+    }
+
+    pub fn split(
+        &mut self,
+        account: ledger_canister::AccountIdentifier,
+        neuron_index: u64,
+        amount: Tokens,
+    ) -> Result<(), ApiError> {
+        self.flush()?;
+        self.actions.push(Request::Split(Split {
+            account,
+            amount,
+            neuron_index,
+        }));
+        Ok(())
+    }
+
+    pub fn merge(
+        &mut self,
+        account: ledger_canister::AccountIdentifier,
+        neuron_index: u64,
+        source_neuron_id: u64,
+    ) -> Result<(), ApiError> {
+        self.flush()?;
+        self.actions.push(Request::Merge(Merge {
+            account,
+            source_neuron_id,
+            neuron_index,
+        }));
+        Ok(())
+    }
+
+    pub fn list_neurons(
+        &mut self,
+        account: ledger_canister::AccountIdentifier,
+    ) -> Result<(), ApiError> {
+        self.flush()?;
+        self.actions
+            .push(Request::ListNeurons(ListNeurons { account }));
+        Ok(())
+    }
 }
 
 /// Structure for manipulating tokens in relation to account, for example during transfers.
@@ -2,7 +2,6 @@ use super::*;
 use crate::models::amount::signed_amount;
 use crate::models::operation::{OperationIdentifier, OperationType};
 use crate::request_types::Stake;
-use crate::DEFAULT_TOKEN_SYMBOL;
 use ledger_canister::AccountIdentifier;
 use ledger_canister::Operation as LedgerOperation;
 
@@ -30,7 +29,7 @@ impl OperationBuilder {
 
     fn amount(self, amount: i128) -> Self {
         Self(Operation {
-            amount: Some(signed_amount(amount, DEFAULT_TOKEN_SYMBOL)),
+            amount: Some(signed_amount(amount, &Currency::default())),
             ..self.0
         })
     }
@@ -68,7 +67,7 @@ fn test_transfer_requests_to_operations() {
                 amount: Tokens::from_e8s(100),
                 fee: Tokens::from_e8s(10),
             })],
-            DEFAULT_TOKEN_SYMBOL
+            &Currency::default()
         ),
         Ok(vec![
             OperationBuilder::new(0, OperationType::Transaction)
@@ -103,7 +102,7 @@ fn test_transfer_and_stake_requests_to_operations() {
                     neuron_index: 1,
                 })
             ],
-            DEFAULT_TOKEN_SYMBOL
+            &Currency::default()
         ),
         Ok(vec![
             OperationBuilder::new(0, OperationType::Transaction)
@@ -144,7 +143,7 @@ fn test_can_handle_multiple_transfers() {
                     fee: Tokens::from_e8s(20),
                 }),
             ],
-            DEFAULT_TOKEN_SYMBOL
+            &Currency::default()
         ),
         Ok(vec![
             OperationBuilder::new(0, OperationType::Transaction)
@@ -2,7 +2,7 @@ use crate::request::transaction_operation_results::TransactionOperationResults;
 use crate::request::transaction_results::TransactionResults;
 use crate::{
     convert,
-    models::{Error, Object},
+    models::{Currency, Error, Object},
 };
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -37,7 +37,7 @@ pub enum ApiError {
     ICError(ICError),
     TransactionRejected(bool, Details),
     TransactionExpired,
-    OperationsErrors(TransactionResults, String),
+    OperationsErrors(TransactionResults, Currency),
 }
 
 impl ApiError {
@@ -92,6 +92,12 @@ impl ApiError {
     pub fn invalid_account_id<T: Into<Details>>(t: T) -> ApiError {
         ApiError::InvalidAccountId(false, t.into())
     }
+
+    /// The error returned for neuron management on a network whose ledger is
+    /// not paired with a governance canister.
+    pub fn neuron_management_not_supported() -> ApiError {
+        ApiError::invalid_request("Neuron management is not supported on this network")
+    }
 }
 
 pub fn convert_to_error(api_err: &ApiError) -> Error {
@@ -117,8 +123,8 @@ pub fn convert_to_error(api_err: &ApiError) -> Error {
         ApiError::ICError(e) => (740, "Internet Computer error", e.retriable, e.into()),
         ApiError::TransactionRejected(r, d) => (750, "Transaction rejected", *r, d.into()),
         ApiError::TransactionExpired => (760, "Transaction expired", false, Object::default()),
-        ApiError::OperationsErrors(e, token_name) => {
-            match TransactionOperationResults::from_transaction_results(e.clone(), token_name) {
+        ApiError::OperationsErrors(e, currency) => {
+            match TransactionOperationResults::from_transaction_results(e.clone(), currency) {
                 Ok(o) => (770, "Operation failed", e.retriable(), o.into()),
                 Err(_) => (
                     700,
@@ -140,7 +146,7 @@ pub fn convert_to_error(api_err: &ApiError) -> Error {
 }
 
 /// Convert an Error to an ApiError.
-pub fn convert_to_api_error(err: Error, token_name: &str) -> ApiError {
+pub fn convert_to_api_error(err: Error, currency: &Currency) -> ApiError {
     match err {
         Error {
             code: 700,
@@ -229,7 +235,7 @@ pub fn convert_to_api_error(err: Error, token_name: &str) -> ApiError {
         Error {
             code: 770, details, ..
         } => match details.map(TransactionOperationResults::parse) {
-            Some(Ok(e)) => convert::transaction_operation_result_to_api_error(e, token_name),
+            Some(Ok(e)) => convert::transaction_operation_result_to_api_error(e, currency),
             Some(Err(e)) => e,
             None => ApiError::internal_error("OperationsErrors missing details object"),
         },
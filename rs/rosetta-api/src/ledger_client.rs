@@ -3,16 +3,22 @@ mod canister_access;
 mod handle_add_hotkey;
 mod handle_disburse;
 mod handle_follow;
+mod handle_list_neurons;
+mod handle_make_proposal;
+mod handle_merge;
 mod handle_merge_maturity;
 mod handle_neuron_info;
+mod handle_register_vote;
 mod handle_remove_hotkey;
 mod handle_send;
 mod handle_set_dissolve_timestamp;
 mod handle_spawn;
+mod handle_split;
 mod handle_stake;
 mod handle_start_dissolve;
 mod handle_stop_dissolve;
 mod neuron_response;
+mod proposal_response;
 
 use core::ops::Deref;
 use std::convert::TryFrom;
@@ -29,12 +35,16 @@ use tokio::sync::RwLock;
 use dfn_candid::CandidOne;
 use ic_canister_client::HttpClient;
 use ic_ledger_core::block::BlockType;
-use ic_nns_governance::pb::v1::{manage_neuron::NeuronIdOrSubaccount, GovernanceError, NeuronInfo};
+use ic_nns_common::pb::v1::ProposalId;
+use ic_nns_governance::pb::v1::{
+    manage_neuron::NeuronIdOrSubaccount, GovernanceError, NeuronInfo, ProposalInfo,
+};
 use ic_types::messages::{HttpCallContent, MessageId};
 use ic_types::CanisterId;
 use ic_types::{crypto::threshold_sig::ThresholdSigPublicKey, messages::SignedRequestBytes};
 use ledger_canister::{
-    Block, BlockHeight, Symbol, TipOfChainRes, TransferFee, TransferFeeArgs, DEFAULT_TRANSFER_FEE,
+    Block, BlockHeight, Decimals, Symbol, TipOfChainRes, TransferFee, TransferFeeArgs,
+    DEFAULT_TRANSFER_FEE,
 };
 use on_wire::{FromWire, IntoWire};
 
@@ -43,20 +53,24 @@ use crate::convert;
 use crate::errors::{ApiError, Details, ICError};
 use crate::ledger_client::blocks::Blocks;
 use crate::ledger_client::canister_access::CanisterAccess;
-use crate::ledger_client::neuron_response::NeuronResponse;
+use crate::ledger_client::neuron_response::{ListNeuronsResponse, NeuronResponse};
+use crate::ledger_client::proposal_response::ProposalResponse;
 use crate::ledger_client::{
     handle_add_hotkey::handle_add_hotkey, handle_disburse::handle_disburse,
-    handle_follow::handle_follow, handle_merge_maturity::handle_merge_maturity,
-    handle_neuron_info::handle_neuron_info, handle_remove_hotkey::handle_remove_hotkey,
+    handle_follow::handle_follow, handle_list_neurons::handle_list_neurons,
+    handle_make_proposal::handle_make_proposal, handle_merge::handle_merge,
+    handle_merge_maturity::handle_merge_maturity, handle_neuron_info::handle_neuron_info,
+    handle_register_vote::handle_register_vote, handle_remove_hotkey::handle_remove_hotkey,
     handle_send::handle_send, handle_set_dissolve_timestamp::handle_set_dissolve_timestamp,
-    handle_spawn::handle_spawn, handle_stake::handle_stake,
+    handle_spawn::handle_spawn, handle_split::handle_split, handle_stake::handle_stake,
     handle_start_dissolve::handle_start_dissolve, handle_stop_dissolve::handle_stop_dissolve,
 };
-use crate::models::{EnvelopePair, Object, SignedTransaction};
+use crate::models::{Currency, EnvelopePair, Object, SignedTransaction};
 use crate::request::request_result::RequestResult;
 use crate::request::transaction_results::TransactionResults;
 use crate::request::Request;
 use crate::request_types::{RequestType, Status};
+use crate::store::snapshot::StoreSnapshot;
 use crate::store::{BlockStoreError, HashedBlock};
 use crate::transaction_id::TransactionIdentifier;
 
@@ -70,8 +84,10 @@ pub trait LedgerAccess {
     async fn read_blocks<'a>(&'a self) -> Box<dyn Deref<Target = Blocks> + 'a>;
     async fn sync_blocks(&self, stopped: Arc<AtomicBool>) -> Result<(), ApiError>;
     fn ledger_canister_id(&self) -> &CanisterId;
-    fn governance_canister_id(&self) -> &CanisterId;
-    fn token_symbol(&self) -> &str;
+    /// The governance canister used for neuron management, if the ledger
+    /// supports it.
+    fn governance_canister_id(&self) -> Option<&CanisterId>;
+    fn currency(&self) -> &Currency;
     async fn submit(&self, _envelopes: SignedTransaction) -> Result<TransactionResults, ApiError>;
     async fn cleanup(&self);
     async fn neuron_info(
@@ -80,15 +96,17 @@ pub trait LedgerAccess {
         verified: bool,
     ) -> Result<NeuronInfo, ApiError>;
     async fn transfer_fee(&self) -> Result<TransferFee, ApiError>;
+    async fn pending_proposals(&self) -> Result<Vec<ProposalInfo>, ApiError>;
+    async fn proposal_info(&self, proposal_id: u64) -> Result<Option<ProposalInfo>, ApiError>;
 }
 
 pub struct LedgerClient {
     blockchain: RwLock<Blocks>,
     canister_id: CanisterId,
-    governance_canister_id: CanisterId,
+    governance_canister_id: Option<CanisterId>,
     canister_access: Option<Arc<CanisterAccess>>,
     ic_url: Url,
-    token_symbol: String,
+    currency: Currency,
     store_max_blocks: Option<u64>,
     offline: bool,
     root_key: Option<ThresholdSigPublicKey>,
@@ -98,6 +116,8 @@ pub enum OperationOutput {
     BlockIndex(BlockHeight),
     NeuronId(u64),
     NeuronResponse(NeuronResponse),
+    ProposalResponse(ProposalResponse),
+    ListNeuronsResponse(ListNeuronsResponse),
 }
 
 impl LedgerClient {
@@ -105,8 +125,8 @@ impl LedgerClient {
     pub async fn new(
         ic_url: Url,
         canister_id: CanisterId,
-        token_symbol: String,
-        governance_canister_id: CanisterId,
+        currency: Currency,
+        governance_canister_id: Option<CanisterId>,
         store_location: Option<&std::path::Path>,
         store_max_blocks: Option<u64>,
         offline: bool,
@@ -125,60 +145,35 @@ impl LedgerClient {
                 canister_id,
                 http_client,
             ));
-            Self::verify_store(&blocks, &canister_access).await?;
-
-            if root_key.is_some() {
-                // verify if we have the right certificate/we are connecting to the right
-                // canister
-                let TipOfChainRes {
-                    tip_index,
-                    certification,
-                } = canister_access.query_tip().await?;
-
-                let tip_block = canister_access
-                    .query_raw_block(tip_index)
-                    .await?
-                    .expect("Blockchain in the ledger canister is empty");
-
-                verify_block_hash(&certification, tip_block.hash(), &root_key, &canister_id)
-                    .map_err(ApiError::internal_error)?;
-            }
+            Self::verify_store(&blocks, &canister_access, &root_key, canister_id).await?;
 
-            let arg = CandidOne(())
-                .into_bytes()
-                .map_err(|e| ApiError::internal_error(format!("Serialization failed: {:?}", e)))?;
-
-            let symbol_res: Result<Symbol, String> = canister_access
-                .agent
-                .execute_query(&canister_access.canister_id, "symbol", arg)
-                .await
-                .and_then(|bytes| {
-                    CandidOne::from_bytes(
-                        bytes.ok_or_else(|| "symbol reply payload was empty".to_string())?,
-                    )
-                    .map(|c| c.0)
-                });
-
-            match symbol_res {
-                Ok(Symbol { symbol }) => {
-                    if symbol != token_symbol {
+            match Self::query_token_metadata::<Symbol>(&canister_access, "symbol").await? {
+                Some(Symbol { symbol }) => {
+                    if symbol != currency.symbol {
                         return Err(ApiError::internal_error(format!(
                             "The ledger serves a different token ({}) than specified ({})",
-                            symbol, token_symbol
+                            symbol, currency.symbol
                         )));
                     }
                 }
-                Err(e) => {
-                    if e.contains("has no query method") || e.contains("not found") {
-                        log::warn!("Symbol endpoint not present in the ledger canister. Couldn't verify token symbol.");
-                    } else {
+                None => log::warn!(
+                    "Symbol endpoint not present in the ledger canister. Couldn't verify token symbol."
+                ),
+            }
+
+            match Self::query_token_metadata::<Decimals>(&canister_access, "decimals").await? {
+                Some(Decimals { decimals }) => {
+                    if decimals != currency.decimals {
                         return Err(ApiError::internal_error(format!(
-                            "Failed to fetch symbol name from the ledger: {}",
-                            e
+                            "The ledger uses a different number of decimals ({}) than specified ({})",
+                            decimals, currency.decimals
                         )));
                     }
                 }
-            };
+                None => log::warn!(
+                    "Decimals endpoint not present in the ledger canister. Couldn't verify token decimals."
+                ),
+            }
 
             Some(canister_access)
         };
@@ -210,7 +205,7 @@ impl LedgerClient {
         Ok(Self {
             blockchain: RwLock::new(blocks),
             canister_id,
-            token_symbol,
+            currency,
             governance_canister_id,
             canister_access,
             ic_url,
@@ -220,9 +215,90 @@ impl LedgerClient {
         })
     }
 
+    /// Query a token metadata endpoint of the ledger canister (such as
+    /// `symbol` or `decimals`). Returns `None` if the ledger does not have the
+    /// endpoint.
+    async fn query_token_metadata<R>(
+        canister_access: &CanisterAccess,
+        method: &str,
+    ) -> Result<Option<R>, ApiError>
+    where
+        R: candid::CandidType + serde::de::DeserializeOwned,
+    {
+        let arg = CandidOne(())
+            .into_bytes()
+            .map_err(|e| ApiError::internal_error(format!("Serialization failed: {:?}", e)))?;
+
+        let res: Result<R, String> = canister_access
+            .agent
+            .execute_query(&canister_access.canister_id, method, arg)
+            .await
+            .and_then(|bytes| {
+                CandidOne::from_bytes(
+                    bytes.ok_or_else(|| format!("{} reply payload was empty", method))?,
+                )
+                .map(|c| c.0)
+            });
+
+        match res {
+            Ok(r) => Ok(Some(r)),
+            Err(e) if e.contains("has no query method") || e.contains("not found") => Ok(None),
+            Err(e) => Err(ApiError::internal_error(format!(
+                "Failed to fetch {} from the ledger: {}",
+                method, e
+            ))),
+        }
+    }
+
+    /// Check the oldest block of a pruned `snapshot` against the ledger before
+    /// it is imported. The balances at that block cannot be derived from the
+    /// blocks of the snapshot, so they are only as trustworthy as the block.
+    pub async fn verify_snapshot(
+        ic_url: Url,
+        canister_id: CanisterId,
+        snapshot: &StoreSnapshot,
+    ) -> Result<(), ApiError> {
+        if let Some(oldest_block) = snapshot.oldest_kept_block() {
+            let canister_access = CanisterAccess::new(ic_url, canister_id, HttpClient::new());
+            Self::verify_oldest_block(&canister_access, oldest_block).await?;
+        }
+        Ok(())
+    }
+
+    async fn verify_oldest_block(
+        canister_access: &CanisterAccess,
+        oldest_block: &HashedBlock,
+    ) -> Result<(), ApiError> {
+        let queried_block = canister_access.query_raw_block(oldest_block.index).await?;
+        if queried_block.is_none() {
+            let msg = format!(
+                "Oldest block snapshot does not match the block on \
+                the blockchain. Block with this index not found: {}",
+                oldest_block.index
+            );
+            error!("{}", msg);
+            return Err(ApiError::internal_error(msg));
+        }
+        let queried_block = queried_block.unwrap();
+        if oldest_block.hash != queried_block.hash() {
+            let msg = format!(
+                "Oldest block snapshot does not match the block on \
+                the blockchain. Index: {}, snapshot hash: {}, canister hash: {}",
+                oldest_block.index,
+                oldest_block.hash,
+                queried_block.hash()
+            );
+            error!("{}", msg);
+            return Err(ApiError::internal_error(msg));
+        }
+        Ok(())
+    }
+
     async fn verify_store(
         blocks: &Blocks,
         canister_access: &CanisterAccess,
+        root_key: &Option<ThresholdSigPublicKey>,
+        canister_id: CanisterId,
     ) -> Result<(), ApiError> {
         debug!("Verifying store...");
         let first_block = blocks.block_store.first()?;
@@ -259,26 +335,47 @@ impl LedgerClient {
             }
         }
 
-        if first_block.is_some() && first_block.as_ref().unwrap().index > 0 {
-            let first_block = first_block.unwrap();
-            let queried_block = canister_access.query_raw_block(first_block.index).await?;
-            if queried_block.is_none() {
+        if let Some(first_block) = first_block.filter(|hb| hb.index > 0) {
+            Self::verify_oldest_block(canister_access, &first_block).await?;
+        }
+
+        // Verify that we have the right certificate and that we are
+        // connecting to the right canister.
+        let TipOfChainRes {
+            tip_index,
+            certification,
+        } = canister_access.query_tip().await?;
+        let tip_block = canister_access
+            .query_raw_block(tip_index)
+            .await?
+            .expect("Blockchain in the ledger canister is empty");
+        verify_block_hash(&certification, tip_block.hash(), root_key, &canister_id)
+            .map_err(ApiError::internal_error)?;
+
+        if let Some(last_verified) = blocks.block_store.last_verified() {
+            let last_block = blocks.block_store.get_at(last_verified)?;
+            if tip_index < last_verified {
                 let msg = format!(
-                    "Oldest block snapshot does not match the block on \
-                    the blockchain. Block with this index not found: {}",
-                    first_block.index
+                    "The store is ahead of the ledger canister. Last verified \
+                    block in the store: {}, tip of the ledger: {}",
+                    last_verified, tip_index
                 );
                 error!("{}", msg);
                 return Err(ApiError::internal_error(msg));
             }
-            let queried_block = queried_block.unwrap();
-            if first_block.hash != queried_block.hash() {
+            // If the ledger moved on, the last verified block is only
+            // compared with an uncertified query here. The next sync links
+            // it to the certified tip through the parent hashes.
+            let queried_block = if tip_index == last_verified {
+                Some(tip_block)
+            } else {
+                canister_access.query_raw_block(last_verified).await?
+            };
+            if queried_block.map(|b| b.hash()) != Some(last_block.hash) {
                 let msg = format!(
-                    "Oldest block snapshot does not match the block on \
-                    the blockchain. Index: {}, snapshot hash: {}, canister hash: {}",
-                    first_block.index,
-                    first_block.hash,
-                    queried_block.hash()
+                    "The last verified block of the store does not match the \
+                    block on the blockchain. Index: {}, store hash: {}",
+                    last_verified, last_block.hash
                 );
                 error!("{}", msg);
                 return Err(ApiError::internal_error(msg));
@@ -412,12 +509,12 @@ impl LedgerAccess for LedgerClient {
         &self.canister_id
     }
 
-    fn governance_canister_id(&self) -> &CanisterId {
-        &self.governance_canister_id
+    fn governance_canister_id(&self) -> Option<&CanisterId> {
+        self.governance_canister_id.as_ref()
     }
 
-    fn token_symbol(&self) -> &str {
-        &self.token_symbol
+    fn currency(&self) -> &Currency {
+        &self.currency
     }
 
     async fn submit(&self, envelopes: SignedTransaction) -> Result<TransactionResults, ApiError> {
@@ -452,7 +549,7 @@ impl LedgerAccess for LedgerClient {
                 result.status = Status::Failed(e);
                 return Err(convert::transaction_results_to_api_error(
                     results,
-                    &self.token_symbol,
+                    &self.currency,
                 ));
             }
         }
@@ -475,6 +572,7 @@ impl LedgerAccess for LedgerClient {
             return Err(ApiError::NotAvailableOffline(false, Details::default()));
         }
 
+        let governance_canister_id = self.governance()?;
         let agent = &self.canister_access.as_ref().unwrap().agent;
 
         let arg = CandidOne(acc_id)
@@ -490,7 +588,7 @@ impl LedgerAccess for LedgerClient {
             );
             agent
                 .execute_update(
-                    &self.governance_canister_id,
+                    governance_canister_id,
                     "get_neuron_info_by_id_or_subaccount",
                     arg,
                     nonce,
@@ -499,7 +597,7 @@ impl LedgerAccess for LedgerClient {
         } else {
             agent
                 .execute_query(
-                    &self.governance_canister_id,
+                    governance_canister_id,
                     "get_neuron_info_by_id_or_subaccount",
                     arg,
                 )
@@ -570,6 +668,19 @@ impl LedgerAccess for LedgerClient {
             }),
         }
     }
+
+    async fn pending_proposals(&self) -> Result<Vec<ProposalInfo>, ApiError> {
+        let arg = candid::encode_args(())
+            .map_err(|e| ApiError::internal_error(format!("Serialization failed: {:?}", e)))?;
+        self.query_governance("get_pending_proposals", arg).await
+    }
+
+    async fn proposal_info(&self, proposal_id: u64) -> Result<Option<ProposalInfo>, ApiError> {
+        let arg = CandidOne(ProposalId { id: proposal_id })
+            .into_bytes()
+            .map_err(|e| ApiError::internal_error(format!("Serialization failed: {:?}", e)))?;
+        self.query_governance("get_proposal_info", arg).await
+    }
 }
 
 impl LedgerClient {
@@ -579,6 +690,39 @@ impl LedgerClient {
     const POLL_INTERVAL_MULTIPLIER: f32 = 1.3;
     const TIMEOUT: Duration = Duration::from_secs(20);
 
+    fn governance(&self) -> Result<&CanisterId, ApiError> {
+        self.governance_canister_id
+            .as_ref()
+            .ok_or_else(ApiError::neuron_management_not_supported)
+    }
+
+    /// Call a query method of the governance canister with a candid encoded
+    /// argument and decode its single return value.
+    async fn query_governance<R>(&self, method: &str, arg: Vec<u8>) -> Result<R, ApiError>
+    where
+        R: candid::CandidType + serde::de::DeserializeOwned,
+    {
+        if self.offline {
+            return Err(ApiError::NotAvailableOffline(false, Details::default()));
+        }
+
+        let governance_canister_id = self.governance()?;
+        let agent = &self.canister_access.as_ref().unwrap().agent;
+        let bytes = agent
+            .execute_query(governance_canister_id, method, arg)
+            .await
+            .map_err(ApiError::internal_error)?
+            .ok_or_else(|| {
+                ApiError::internal_error(format!("{} reply payload was empty", method))
+            })?;
+        CandidOne::from_bytes(bytes).map(|c| c.0).map_err(|e| {
+            ApiError::internal_error(format!(
+                "Deserialization of {} response failed: {:?}",
+                method, e
+            ))
+        })
+    }
+
     async fn do_request(
         &self,
         http_client: &Client,
@@ -714,6 +858,12 @@ impl LedgerClient {
                     OperationOutput::NeuronResponse(response) => {
                         result.response = Some(Object::from(response));
                     }
+                    OperationOutput::ProposalResponse(response) => {
+                        result.response = Some(Object::from(response));
+                    }
+                    OperationOutput::ListNeuronsResponse(response) => {
+                        result.response = Some(Object::from(response));
+                    }
                 }
                 result.status = Status::Completed;
                 Ok(())
@@ -857,12 +1007,17 @@ impl LedgerClient {
             RequestType::AddHotKey { .. } => handle_add_hotkey(bytes),
             RequestType::Disburse { .. } => handle_disburse(bytes),
             RequestType::Follow { .. } => handle_follow(bytes),
+            RequestType::ListNeurons => handle_list_neurons(bytes),
+            RequestType::MakeProposal { .. } => handle_make_proposal(bytes),
+            RequestType::Merge { .. } => handle_merge(bytes),
             RequestType::MergeMaturity { .. } => handle_merge_maturity(bytes),
             RequestType::NeuronInfo { .. } => handle_neuron_info(bytes),
+            RequestType::RegisterVote { .. } => handle_register_vote(bytes),
             RequestType::RemoveHotKey { .. } => handle_remove_hotkey(bytes),
             RequestType::Send => handle_send(bytes),
             RequestType::SetDissolveTimestamp { .. } => handle_set_dissolve_timestamp(bytes),
             RequestType::Spawn { .. } => handle_spawn(bytes),
+            RequestType::Split { .. } => handle_split(bytes),
             RequestType::Stake { .. } => handle_stake(bytes),
             RequestType::StartDissolve { .. } => handle_start_dissolve(bytes, request_type),
             RequestType::StopDissolve { .. } => handle_stop_dissolve(bytes, request_type),
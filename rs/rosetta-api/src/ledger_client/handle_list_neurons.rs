@@ -0,0 +1,24 @@
+use crate::errors::ApiError;
+use crate::ledger_client::neuron_response::{ListNeuronsResponse, NeuronResponse};
+use crate::ledger_client::OperationOutput;
+use ic_nns_governance::pb::v1::ListNeuronsResponse as GovernanceListNeuronsResponse;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub fn handle_list_neurons(
+    bytes: Vec<u8>,
+) -> Result<Result<Option<OperationOutput>, ApiError>, String> {
+    let response: GovernanceListNeuronsResponse = candid::decode_one(bytes.as_ref())
+        .map_err(|err| format!("Could not decode LIST_NEURONS response: {}", err))?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let neurons = response
+        .full_neurons
+        .iter()
+        .map(|neuron| NeuronResponse::from_neuron(neuron, now))
+        .collect();
+    Ok(Ok(Some(OperationOutput::ListNeuronsResponse(
+        ListNeuronsResponse { neurons },
+    ))))
+}
@@ -0,0 +1,26 @@
+use crate::errors::ApiError;
+use crate::ledger_client::proposal_response::ProposalResponse;
+use crate::ledger_client::OperationOutput;
+use ic_nns_governance::pb::v1::manage_neuron_response::{Command, MakeProposalResponse};
+use ic_nns_governance::pb::v1::ManageNeuronResponse;
+
+pub fn handle_make_proposal(
+    bytes: Vec<u8>,
+) -> Result<Result<Option<OperationOutput>, ApiError>, String> {
+    let response: ManageNeuronResponse = candid::decode_one(bytes.as_ref())
+        .map_err(|err| format!("Could not decode MAKE_PROPOSAL response: {}", err))?;
+    match &response.command {
+        Some(Command::MakeProposal(MakeProposalResponse {
+            proposal_id: Some(proposal_id),
+        })) => Ok(Ok(Some(OperationOutput::ProposalResponse(
+            ProposalResponse {
+                proposal_id: proposal_id.id,
+            },
+        )))),
+        Some(Command::Error(err)) => Ok(Err(ApiError::TransactionRejected(
+            false,
+            format!("Could not make proposal: {}", err).into(),
+        ))),
+        _ => panic!("Unexpected make proposal result: {:?}", response.command),
+    }
+}
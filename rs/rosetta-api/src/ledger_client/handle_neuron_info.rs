@@ -1,8 +1,7 @@
 use crate::errors::ApiError;
 use crate::ledger_client::neuron_response::NeuronResponse;
 use crate::ledger_client::OperationOutput;
-use crate::models;
-use ic_nns_governance::pb::v1::{GovernanceError, Neuron, NeuronState};
+use ic_nns_governance::pb::v1::{GovernanceError, Neuron};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 pub fn handle_neuron_info(
@@ -21,22 +20,7 @@ pub fn handle_neuron_info(
                 .duration_since(UNIX_EPOCH)
                 .unwrap()
                 .as_secs();
-            let state = neuron.state(now);
-            let state = match state {
-                NeuronState::NotDissolving => models::NeuronState::NotDissolving,
-                NeuronState::Dissolving => models::NeuronState::Dissolving,
-                NeuronState::Dissolved => models::NeuronState::Dissolved,
-                NeuronState::Unspecified => models::NeuronState::Dissolved,
-            };
-
-            let output = OperationOutput::NeuronResponse(NeuronResponse {
-                neuron_id: neuron.id.as_ref().unwrap().id,
-                controller: neuron.controller.unwrap(),
-                kyc_verified: neuron.kyc_verified,
-                state,
-                maturity_e8s_equivalent: neuron.maturity_e8s_equivalent,
-                neuron_fees_e8s: neuron.neuron_fees_e8s,
-            });
+            let output = OperationOutput::NeuronResponse(NeuronResponse::from_neuron(&neuron, now));
             return Ok(Ok(Some(output)));
         }
     };
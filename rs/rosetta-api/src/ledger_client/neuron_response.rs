@@ -1,4 +1,5 @@
 use crate::models::{self, Object};
+use ic_nns_governance::pb::v1::{Neuron, NeuronState};
 use ic_types::PrincipalId;
 use serde_json::Value;
 
@@ -12,6 +13,27 @@ pub struct NeuronResponse {
     pub(crate) neuron_fees_e8s: u64,
 }
 
+impl NeuronResponse {
+    /// Builds the response for a full neuron returned by the governance
+    /// canister, with the neuron state evaluated at `now_seconds`.
+    pub(crate) fn from_neuron(neuron: &Neuron, now_seconds: u64) -> Self {
+        let state = match neuron.state(now_seconds) {
+            NeuronState::NotDissolving => models::NeuronState::NotDissolving,
+            NeuronState::Dissolving => models::NeuronState::Dissolving,
+            NeuronState::Dissolved => models::NeuronState::Dissolved,
+            NeuronState::Unspecified => models::NeuronState::Dissolved,
+        };
+        NeuronResponse {
+            neuron_id: neuron.id.as_ref().unwrap().id,
+            controller: neuron.controller.unwrap(),
+            kyc_verified: neuron.kyc_verified,
+            state,
+            maturity_e8s_equivalent: neuron.maturity_e8s_equivalent,
+            neuron_fees_e8s: neuron.neuron_fees_e8s,
+        }
+    }
+}
+
 impl From<NeuronResponse> for Object {
     fn from(r: NeuronResponse) -> Self {
         match serde_json::to_value(r) {
@@ -20,3 +42,17 @@ impl From<NeuronResponse> for Object {
         }
     }
 }
+
+#[derive(serde::Serialize)]
+pub struct ListNeuronsResponse {
+    pub(crate) neurons: Vec<NeuronResponse>,
+}
+
+impl From<ListNeuronsResponse> for Object {
+    fn from(r: ListNeuronsResponse) -> Self {
+        match serde_json::to_value(r) {
+            Ok(Value::Object(o)) => o,
+            _ => Object::default(),
+        }
+    }
+}
@@ -0,0 +1,16 @@
+use crate::models::Object;
+use serde_json::Value;
+
+#[derive(serde::Serialize)]
+pub struct ProposalResponse {
+    pub(crate) proposal_id: u64,
+}
+
+impl From<ProposalResponse> for Object {
+    fn from(r: ProposalResponse) -> Self {
+        match serde_json::to_value(r) {
+            Ok(Value::Object(o)) => o,
+            _ => Object::default(),
+        }
+    }
+}
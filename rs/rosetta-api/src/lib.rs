@@ -14,4 +14,5 @@ pub mod transaction_id;
 pub const API_VERSION: &str = "1.4.10";
 pub const NODE_VERSION: &str = env!("CARGO_PKG_VERSION");
 pub const DEFAULT_TOKEN_SYMBOL: &str = "ICP";
+pub const DEFAULT_TOKEN_DECIMALS: u32 = ledger_canister::DECIMAL_PLACES;
 pub const DEFAULT_BLOCKCHAIN: &str = "Internet Computer";
@@ -1,9 +1,15 @@
 use clap::Parser;
 use ic_crypto_internal_threshold_sig_bls12381 as bls12_381;
 use ic_crypto_utils_threshold_sig::parse_threshold_sig_key;
-use ic_rosetta_api::request_handler::RosettaRequestHandler;
+use ic_rosetta_api::ledger_client::blocks::Blocks;
+use ic_rosetta_api::ledger_client::LedgerAccess;
+use ic_rosetta_api::models::Currency;
+use ic_rosetta_api::request_handler::{NetworkRouter, RosettaRequestHandler};
 use ic_rosetta_api::rosetta_server::{RosettaApiServer, RosettaApiServerOpt};
-use ic_rosetta_api::{ledger_client, DEFAULT_BLOCKCHAIN, DEFAULT_TOKEN_SYMBOL};
+use ic_rosetta_api::store::snapshot::StoreSnapshot;
+use ic_rosetta_api::{
+    ledger_client, DEFAULT_BLOCKCHAIN, DEFAULT_TOKEN_DECIMALS, DEFAULT_TOKEN_SYMBOL,
+};
 use ic_types::crypto::threshold_sig::ThresholdSigPublicKey;
 use ic_types::{CanisterId, PrincipalId};
 use std::{path::Path, path::PathBuf, str::FromStr, sync::Arc};
@@ -20,6 +26,15 @@ struct Opt {
     ic_canister_id: Option<String>,
     #[clap(short = 't', long = "token-sybol")]
     token_symbol: Option<String>,
+    /// The number of decimal places of the token served by the ledger.
+    #[clap(long = "token-decimals")]
+    token_decimals: Option<u32>,
+    /// An additional ledger to serve as a separate network, given as
+    /// CANISTER_ID:SYMBOL:DECIMALS. Can be repeated. Additional ledgers do
+    /// not support neuron management and keep their blocks in a
+    /// subdirectory of the store location named after the canister id.
+    #[clap(long = "additional-ledger", parse(try_from_str = parse_additional_ledger))]
+    additional_ledgers: Vec<AdditionalLedger>,
     /// Id of the governance canister to use for neuron management.
     #[clap(short = 'g', long = "governance-canister-id")]
     governance_canister_id: Option<String>,
@@ -40,6 +55,14 @@ struct Opt {
     store_location: PathBuf,
     #[clap(long = "store-max-blocks")]
     store_max_blocks: Option<u64>,
+    /// Write a snapshot of the verified blocks of the store to this file and
+    /// exit.
+    #[clap(long = "export-snapshot", conflicts_with = "import_snapshot")]
+    export_snapshot: Option<PathBuf>,
+    /// Initialize the empty store from a snapshot written with
+    /// --export-snapshot before syncing with the ledger.
+    #[clap(long = "import-snapshot")]
+    import_snapshot: Option<PathBuf>,
     #[clap(long = "exit-on-sync")]
     exit_on_sync: bool,
     #[clap(long = "offline")]
@@ -55,6 +78,30 @@ struct Opt {
     expose_metrics: bool,
 }
 
+#[derive(Debug)]
+struct AdditionalLedger {
+    canister_id: CanisterId,
+    currency: Currency,
+}
+
+fn parse_additional_ledger(s: &str) -> Result<AdditionalLedger, String> {
+    let parts: Vec<&str> = s.split(':').collect();
+    if parts.len() != 3 {
+        return Err(format!("Expected CANISTER_ID:SYMBOL:DECIMALS, got {}", s));
+    }
+    let principal = PrincipalId::from_str(parts[0])
+        .map_err(|e| format!("Invalid canister id {}: {}", parts[0], e))?;
+    let canister_id = CanisterId::new(principal)
+        .map_err(|e| format!("Invalid canister id {}: {}", parts[0], e))?;
+    let decimals = parts[2]
+        .parse::<u32>()
+        .map_err(|e| format!("Invalid number of decimals {}: {}", parts[2], e))?;
+    Ok(AdditionalLedger {
+        canister_id,
+        currency: Currency::new(parts[1].to_string(), decimals),
+    })
+}
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     let opt = Opt::parse();
@@ -139,7 +186,12 @@ async fn main() -> std::io::Result<()> {
     let token_symbol = opt
         .token_symbol
         .unwrap_or_else(|| DEFAULT_TOKEN_SYMBOL.to_string());
-    log::info!("Token symbol set to {}", token_symbol);
+    let token_decimals = opt.token_decimals.unwrap_or(DEFAULT_TOKEN_DECIMALS);
+    log::info!(
+        "Token symbol set to {} with {} decimals",
+        token_symbol,
+        token_decimals
+    );
 
     let store_location: Option<&Path> = match opt.store_type.as_ref() {
         "sqlite" => Some(&opt.store_location),
@@ -153,6 +205,37 @@ async fn main() -> std::io::Result<()> {
         }
     };
 
+    if let Some(path) = &opt.export_snapshot {
+        let location = store_location.expect("Snapshots require the sqlite store type");
+        let mut blocks = Blocks::new_persistent(location);
+        blocks
+            .load_from_store()
+            .and_then(|_| StoreSnapshot::from_blocks(&blocks))
+            .and_then(|snapshot| snapshot.write_to_file(path))
+            .unwrap_or_else(|e| panic!("Failed to export the store snapshot: {:?}", e));
+        log::info!("Snapshot written to {}", path.display());
+        return Ok(());
+    }
+
+    if let Some(path) = &opt.import_snapshot {
+        let location = store_location.expect("Snapshots require the sqlite store type");
+        let mut blocks = Blocks::new_persistent(location);
+        let snapshot = StoreSnapshot::read_from_file(path)
+            .unwrap_or_else(|e| panic!("Failed to import the store snapshot: {:?}", e));
+        if snapshot.oldest_kept_block().is_some() {
+            if opt.offline {
+                panic!("Cannot import a snapshot of a pruned store offline: its oldest block must be checked against the ledger");
+            }
+            ledger_client::LedgerClient::verify_snapshot(url.clone(), canister_id, &snapshot)
+                .await
+                .unwrap_or_else(|e| panic!("Failed to import the store snapshot: {:?}", e));
+        }
+        snapshot
+            .import_into(&mut blocks)
+            .unwrap_or_else(|e| panic!("Failed to import the store snapshot: {:?}", e));
+        log::info!("Snapshot imported from {}", path.display());
+    }
+
     let Opt {
         store_max_blocks,
         offline,
@@ -161,33 +244,67 @@ async fn main() -> std::io::Result<()> {
         not_whitelisted,
         expose_metrics,
         blockchain,
+        additional_ledgers,
         ..
     } = opt;
-    let client = ledger_client::LedgerClient::new(
-        url,
+
+    // The main ledger keeps using the store location directly so that
+    // existing stores remain valid.
+    let mut networks = vec![(
         canister_id,
-        token_symbol,
-        governance_canister_id,
-        store_location,
-        store_max_blocks,
-        offline,
-        root_key,
-    )
-    .await
-    .map_err(|e| {
-        let msg = if mainnet && !not_whitelisted && e.is_internal_error_403() {
-            ", You may not be whitelisted; please try running the Rosetta server again with the '--not_whitelisted' flag"
-        } else {""};
-        (e, msg)
-    })
-    .unwrap_or_else(|(e, is_403)| panic!("Failed to initialize ledger client{}: {:?}", is_403, e));
+        Currency::new(token_symbol, token_decimals),
+        Some(governance_canister_id),
+        store_location.map(Path::to_path_buf),
+    )];
+    for AdditionalLedger {
+        canister_id,
+        currency,
+    } in additional_ledgers
+    {
+        networks.push((
+            canister_id,
+            currency,
+            None,
+            store_location.map(|loc| loc.join(canister_id.to_string())),
+        ));
+    }
 
-    let ledger = Arc::new(client);
-    let req_handler = RosettaRequestHandler::new(blockchain, ledger.clone());
+    let mut ledgers: Vec<Arc<dyn LedgerAccess + Send + Sync>> = vec![];
+    let mut req_handlers = vec![];
+    for (canister_id, currency, governance_canister_id, store_location) in networks {
+        let client = ledger_client::LedgerClient::new(
+            url.clone(),
+            canister_id,
+            currency,
+            governance_canister_id,
+            store_location.as_deref(),
+            store_max_blocks,
+            offline,
+            root_key,
+        )
+        .await
+        .map_err(|e| {
+            let msg = if mainnet && !not_whitelisted && e.is_internal_error_403() {
+                ", You may not be whitelisted; please try running the Rosetta server again with the '--not_whitelisted' flag"
+            } else {""};
+            (e, msg)
+        })
+        .unwrap_or_else(|(e, is_403)| panic!("Failed to initialize ledger client{}: {:?}", is_403, e));
 
-    log::info!("Network id: {:?}", req_handler.network_id());
-    let serv = RosettaApiServer::new(ledger, req_handler, addr, expose_metrics)
-        .expect("Error creating RosettaApiServer");
+        let ledger = Arc::new(client);
+        let req_handler = RosettaRequestHandler::new(blockchain.clone(), ledger.clone());
+        log::info!("Network id: {:?}", req_handler.network_id());
+        ledgers.push(ledger);
+        req_handlers.push(req_handler);
+    }
+
+    let serv = RosettaApiServer::new_with_networks(
+        ledgers,
+        NetworkRouter::new(req_handlers),
+        addr,
+        expose_metrics,
+    )
+    .expect("Error creating RosettaApiServer");
 
     // actix server catches kill signals. After that we still need to stop our
     // server properly
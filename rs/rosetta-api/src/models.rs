@@ -175,6 +175,11 @@ pub struct Allow {
     /// account at any height in the past should set this to true.
     #[serde(rename = "historical_balance_lookup")]
     pub historical_balance_lookup: bool,
+
+    /// All methods that are supported by the /call endpoint.
+    #[serde(rename = "call_methods")]
+    #[serde(default)]
+    pub call_methods: Vec<String>,
 }
 
 impl Allow {
@@ -183,12 +188,14 @@ impl Allow {
         operation_types: Vec<String>,
         errors: Vec<Error>,
         historical_balance_lookup: bool,
+        call_methods: Vec<String>,
     ) -> Allow {
         Allow {
             operation_statuses,
             operation_types,
             errors,
             historical_balance_lookup,
+            call_methods,
         }
     }
 }
@@ -355,6 +362,64 @@ impl BlockTransactionResponse {
     }
 }
 
+/// CallRequest is the input to the `/call` endpoint.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "conversion", derive(LabelledGeneric))]
+pub struct CallRequest {
+    #[serde(rename = "network_identifier")]
+    pub network_identifier: NetworkIdentifier,
+
+    /// Method is some network-specific procedure call. This method could map
+    /// to a network-specific RPC endpoint, a method in an SDK generated from a
+    /// smart contract, or some hybrid of the two.
+    #[serde(rename = "method")]
+    pub method: String,
+
+    /// Parameters is some network-specific argument for a method. It is up to
+    /// the caller to determine which parameters to provide when invoking
+    /// /call.
+    #[serde(rename = "parameters")]
+    #[serde(default)]
+    pub parameters: Object,
+}
+
+impl CallRequest {
+    pub fn new(
+        network_identifier: NetworkIdentifier,
+        method: String,
+        parameters: Object,
+    ) -> CallRequest {
+        CallRequest {
+            network_identifier,
+            method,
+            parameters,
+        }
+    }
+}
+
+/// CallResponse contains the result of a `/call` invocation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "conversion", derive(LabelledGeneric))]
+pub struct CallResponse {
+    /// Result contains the result of the `/call` invocation. This result will
+    /// not be inspected or interpreted by Rosetta tooling and is left to the
+    /// caller to decode.
+    #[serde(rename = "result")]
+    pub result: Object,
+
+    /// Idempotent indicates that if `/call` is invoked with the same
+    /// CallRequest again, at any point in time, it will return the same
+    /// CallResponse.
+    #[serde(rename = "idempotent")]
+    pub idempotent: bool,
+}
+
+impl CallResponse {
+    pub fn new(result: Object, idempotent: bool) -> CallResponse {
+        CallResponse { result, idempotent }
+    }
+}
+
 /// CoinActions are different state changes that a Coin can undergo. When a Coin
 /// is created, it is coin_created. When a Coin is spent, it is coin_spent. It
 /// is assumed that a single Coin cannot be created or spent more than once.
@@ -1050,6 +1115,16 @@ impl Currency {
     }
 }
 
+/// The currency of the ICP ledger.
+impl Default for Currency {
+    fn default() -> Self {
+        Currency::new(
+            crate::DEFAULT_TOKEN_SYMBOL.to_string(),
+            crate::DEFAULT_TOKEN_DECIMALS,
+        )
+    }
+}
+
 /// CurveType is the type of cryptographic curve associated with a PublicKey.  * secp256k1: SEC compressed - `33 bytes` (https://secg.org/sec1-v2.pdf#subsubsection.2.3.3) * secp256r1: SEC compressed - `33 bytes` (https://secg.org/sec1-v2.pdf#subsubsection.2.3.3) * edwards25519: `y (255-bits) || x-sign-bit (1-bit)` - `32 bytes` (https://ed25519.cr.yp.to/ed25519-20110926.pdf) * tweedle: 1st pk : Fq.t (32 bytes) || 2nd pk : Fq.t (32 bytes) (https://github.com/CodaProtocol/coda/blob/develop/rfcs/0038-rosetta-construction-api.md#marshal-keys)
 /// Enumeration of values.
 /// Since this enum's variants do not hold data, we can easily define them them
@@ -2123,3 +2198,64 @@ pub struct NeuronInfoResponse {
     #[serde(rename = "created_timestamp_seconds")]
     pub created_timestamp_seconds: u64,
 }
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "conversion", derive(LabelledGeneric))]
+pub struct Tally {
+    #[serde(rename = "timestamp_seconds")]
+    pub timestamp_seconds: u64,
+
+    #[serde(rename = "yes")]
+    pub yes: u64,
+
+    #[serde(rename = "no")]
+    pub no: u64,
+
+    #[serde(rename = "total")]
+    pub total: u64,
+}
+
+/// The subset of a governance proposal that is returned by the
+/// `get_proposal_info` and `get_pending_proposals` call methods.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "conversion", derive(LabelledGeneric))]
+pub struct ProposalInfoResponse {
+    #[serde(rename = "proposal_id")]
+    pub proposal_id: Option<u64>,
+
+    /// The id of the neuron that made the proposal.
+    #[serde(rename = "proposer")]
+    pub proposer: Option<u64>,
+
+    #[serde(rename = "title")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    #[serde(rename = "summary")]
+    pub summary: String,
+
+    #[serde(rename = "url")]
+    pub url: String,
+
+    /// The topic, status and reward status codes as defined by the
+    /// governance canister.
+    #[serde(rename = "topic")]
+    pub topic: i32,
+
+    #[serde(rename = "status")]
+    pub status: i32,
+
+    #[serde(rename = "reward_status")]
+    pub reward_status: i32,
+
+    #[serde(rename = "proposal_timestamp_seconds")]
+    pub proposal_timestamp_seconds: u64,
+
+    #[serde(rename = "deadline_timestamp_seconds")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deadline_timestamp_seconds: Option<u64>,
+
+    #[serde(rename = "latest_tally")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latest_tally: Option<Tally>,
+}
@@ -1,6 +1,6 @@
 use crate::errors::ApiError;
 use crate::models::{Currency, Object};
-use ledger_canister::{Tokens, DECIMAL_PLACES};
+use ledger_canister::Tokens;
 use serde::{Deserialize, Serialize};
 use std::convert::TryFrom;
 
@@ -33,31 +33,30 @@ impl Amount {
     }
 }
 
-pub fn tokens_to_amount(tokens: Tokens, token_name: &str) -> Result<Amount, ApiError> {
+pub fn tokens_to_amount(tokens: Tokens, currency: &Currency) -> Result<Amount, ApiError> {
     let amount = tokens.get_e8s();
     Ok(Amount {
         value: format!("{}", amount),
-        currency: Currency::new(token_name.into(), DECIMAL_PLACES),
+        currency: currency.clone(),
         metadata: None,
     })
 }
 
-pub fn signed_amount(amount: i128, token_name: &str) -> Amount {
+pub fn signed_amount(amount: i128, currency: &Currency) -> Amount {
     Amount {
         value: format!("{}", amount),
-        currency: Currency::new(token_name.into(), DECIMAL_PLACES),
+        currency: currency.clone(),
         metadata: None,
     }
 }
 
-pub fn from_amount(amount: &Amount, token_name: &str) -> Result<i128, String> {
-    let cur = Currency::new(token_name.into(), DECIMAL_PLACES);
+pub fn from_amount(amount: &Amount, currency: &Currency) -> Result<i128, String> {
     match amount {
         Amount {
             value,
-            currency,
+            currency: cur,
             metadata: None,
-        } if currency == &cur => {
+        } if cur == currency => {
             let val: i128 = value
                 .parse()
                 .map_err(|e| format!("Parsing amount failed: {}", e))?;
@@ -65,11 +64,11 @@ pub fn from_amount(amount: &Amount, token_name: &str) -> Result<i128, String> {
                 u64::try_from(val.abs()).map_err(|_| "Amount does not fit in u64".to_string())?;
             Ok(val)
         }
-        wrong => Err(format!("This value is not {} {:?}", token_name, wrong)),
+        wrong => Err(format!("This value is not {} {:?}", currency.symbol, wrong)),
     }
 }
 
-pub fn ledgeramount_from_amount(amount: &Amount, token_name: &str) -> Result<Tokens, String> {
-    let inner = from_amount(amount, token_name)?;
+pub fn ledgeramount_from_amount(amount: &Amount, currency: &Currency) -> Result<Tokens, String> {
+    let inner = from_amount(amount, currency)?;
     Ok(Tokens::from_e8s(inner as u64))
 }
@@ -161,4 +161,19 @@ pub enum OperationType {
     #[serde(rename = "FOLLOW")]
     #[strum(serialize = "FOLLOW")]
     Follow,
+    #[serde(rename = "REGISTER_VOTE")]
+    #[strum(serialize = "REGISTER_VOTE")]
+    RegisterVote,
+    #[serde(rename = "MAKE_PROPOSAL")]
+    #[strum(serialize = "MAKE_PROPOSAL")]
+    MakeProposal,
+    #[serde(rename = "SPLIT")]
+    #[strum(serialize = "SPLIT")]
+    Split,
+    #[serde(rename = "MERGE")]
+    #[strum(serialize = "MERGE")]
+    Merge,
+    #[serde(rename = "LIST_NEURONS")]
+    #[strum(serialize = "LIST_NEURONS")]
+    ListNeurons,
 }
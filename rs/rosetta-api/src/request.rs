@@ -5,12 +5,14 @@ use crate::request_types::*;
 use crate::{convert, models};
 use dfn_candid::CandidOne;
 use ic_nns_governance::pb::v1::manage_neuron::{self, configure, Command, Configure};
+use ic_nns_governance::pb::v1::{proposal::Action, Motion};
 use ic_types::PrincipalId;
 use ledger_canister::Tokens;
 use on_wire::FromWire;
 use std::convert::{TryFrom, TryInto};
 
 use crate::models::operation::Operation;
+use crate::models::Currency;
 use serde::{Deserialize, Serialize};
 
 pub mod request_result;
@@ -52,6 +54,16 @@ pub enum Request {
     NeuronInfo(NeuronInfo),
     #[serde(rename = "FOLLOW")]
     Follow(Follow),
+    #[serde(rename = "REGISTER_VOTE")]
+    RegisterVote(RegisterVote),
+    #[serde(rename = "MAKE_PROPOSAL")]
+    MakeProposal(MakeProposal),
+    #[serde(rename = "SPLIT")]
+    Split(Split),
+    #[serde(rename = "MERGE")]
+    Merge(Merge),
+    #[serde(rename = "LIST_NEURONS")]
+    ListNeurons(ListNeurons),
 }
 
 impl Request {
@@ -118,6 +130,29 @@ impl Request {
                 neuron_index: *neuron_index,
                 controller: controller.map(PublicKeyOrPrincipal::Principal),
             }),
+            Request::RegisterVote(RegisterVote {
+                neuron_index,
+                controller,
+                ..
+            }) => Ok(RequestType::RegisterVote {
+                neuron_index: *neuron_index,
+                controller: controller.map(PublicKeyOrPrincipal::Principal),
+            }),
+            Request::MakeProposal(MakeProposal {
+                neuron_index,
+                controller,
+                ..
+            }) => Ok(RequestType::MakeProposal {
+                neuron_index: *neuron_index,
+                controller: controller.map(PublicKeyOrPrincipal::Principal),
+            }),
+            Request::Split(Split { neuron_index, .. }) => Ok(RequestType::Split {
+                neuron_index: *neuron_index,
+            }),
+            Request::Merge(Merge { neuron_index, .. }) => Ok(RequestType::Merge {
+                neuron_index: *neuron_index,
+            }),
+            Request::ListNeurons(_) => Ok(RequestType::ListNeurons),
         }
     }
 
@@ -127,23 +162,28 @@ impl Request {
     /// TODO We should capture the concept of a Transaction in a type.
     pub fn requests_to_operations(
         requests: &[Request],
-        token_name: &str,
+        currency: &Currency,
     ) -> Result<Vec<Operation>, ApiError> {
         let mut builder = TransactionBuilder::default();
         for request in requests {
             match request {
-                Request::Transfer(o) => builder.transfer(o, token_name)?,
+                Request::Transfer(o) => builder.transfer(o, currency)?,
                 Request::Stake(o) => builder.stake(o),
                 Request::SetDissolveTimestamp(o) => builder.set_dissolve_timestamp(o),
                 Request::StartDissolve(o) => builder.start_dissolve(o),
                 Request::StopDissolve(o) => builder.stop_dissolve(o),
-                Request::Disburse(o) => builder.disburse(o, token_name),
+                Request::Disburse(o) => builder.disburse(o, currency),
                 Request::AddHotKey(o) => builder.add_hot_key(o),
                 Request::RemoveHotKey(o) => builder.remove_hotkey(o),
                 Request::Spawn(o) => builder.spawn(o),
                 Request::MergeMaturity(o) => builder.merge_maturity(o),
                 Request::NeuronInfo(o) => builder.neuron_info(o),
                 Request::Follow(o) => builder.follow(o),
+                Request::RegisterVote(o) => builder.register_vote(o),
+                Request::MakeProposal(o) => builder.make_proposal(o),
+                Request::Split(o) => builder.split(o, currency)?,
+                Request::Merge(o) => builder.merge(o),
+                Request::ListNeurons(o) => builder.list_neurons(o),
             };
         }
         Ok(builder.build())
@@ -167,6 +207,11 @@ impl Request {
                 | Request::MergeMaturity(_)
                 | Request::NeuronInfo(_) // not neuron management but we need it signed.
                 | Request::Follow(_)
+                | Request::RegisterVote(_)
+                | Request::MakeProposal(_)
+                | Request::Split(_)
+                | Request::Merge(_)
+                | Request::ListNeurons(_) // not neuron management but we need it signed.
         )
     }
 }
@@ -408,6 +453,86 @@ impl TryFrom<&models::Request> for Request {
                     Err(ApiError::invalid_request("Invalid follow request."))
                 }
             }
+            RequestType::RegisterVote {
+                neuron_index,
+                controller,
+            } => {
+                if let Some(Command::RegisterVote(manage_neuron::RegisterVote {
+                    proposal: Some(proposal),
+                    vote,
+                })) = manage_neuron()?
+                {
+                    let controller = controller
+                        .clone()
+                        .map(principal_id_from_public_key_or_principal)
+                        .transpose()?;
+                    Ok(Request::RegisterVote(RegisterVote {
+                        account,
+                        proposal_id: proposal.id,
+                        vote,
+                        controller,
+                        neuron_index: *neuron_index,
+                    }))
+                } else {
+                    Err(ApiError::invalid_request("Invalid register vote request."))
+                }
+            }
+            RequestType::MakeProposal {
+                neuron_index,
+                controller,
+            } => {
+                if let Some(Command::MakeProposal(proposal)) = manage_neuron()? {
+                    let proposal = *proposal;
+                    if let Some(Action::Motion(Motion { motion_text })) = proposal.action {
+                        let controller = controller
+                            .clone()
+                            .map(principal_id_from_public_key_or_principal)
+                            .transpose()?;
+                        Ok(Request::MakeProposal(MakeProposal {
+                            account,
+                            title: proposal.title,
+                            summary: proposal.summary,
+                            url: proposal.url,
+                            motion_text,
+                            controller,
+                            neuron_index: *neuron_index,
+                        }))
+                    } else {
+                        Err(ApiError::invalid_request(
+                            "Only motion proposals are supported.",
+                        ))
+                    }
+                } else {
+                    Err(ApiError::invalid_request("Invalid make proposal request."))
+                }
+            }
+            RequestType::Split { neuron_index } => {
+                if let Some(Command::Split(manage_neuron::Split { amount_e8s })) = manage_neuron()?
+                {
+                    Ok(Request::Split(Split {
+                        account,
+                        amount: Tokens::from_e8s(amount_e8s),
+                        neuron_index: *neuron_index,
+                    }))
+                } else {
+                    Err(ApiError::invalid_request("Invalid split request."))
+                }
+            }
+            RequestType::Merge { neuron_index } => {
+                if let Some(Command::Merge(manage_neuron::Merge {
+                    source_neuron_id: Some(source_neuron_id),
+                })) = manage_neuron()?
+                {
+                    Ok(Request::Merge(Merge {
+                        account,
+                        source_neuron_id: source_neuron_id.id,
+                        neuron_index: *neuron_index,
+                    }))
+                } else {
+                    Err(ApiError::invalid_request("Invalid merge request."))
+                }
+            }
+            RequestType::ListNeurons => Ok(Request::ListNeurons(ListNeurons { account })),
         }
     }
 }
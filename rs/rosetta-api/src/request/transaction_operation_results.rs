@@ -1,5 +1,5 @@
 use crate::errors::ApiError;
-use crate::models::Object;
+use crate::models::{Currency, Object};
 use crate::request::request_result::{convert_to_request_result_metadata, RequestResult};
 use crate::request::transaction_results::TransactionResults;
 use crate::request::Request;
@@ -27,7 +27,7 @@ impl TransactionOperationResults {
     /// Convert TransactionResults to TransactionOperationResults.
     pub fn from_transaction_results(
         tr: TransactionResults,
-        token_name: &str,
+        currency: &Currency,
     ) -> Result<Self, ApiError> {
         let mut operations = Request::requests_to_operations(
             tr.operations
@@ -35,7 +35,7 @@ impl TransactionOperationResults {
                 .map(|rr| rr._type.clone())
                 .collect::<Vec<_>>()
                 .as_slice(),
-            token_name,
+            currency,
         )?;
 
         let merge_metadata = |o: &mut Operation, rr: &RequestResult| {
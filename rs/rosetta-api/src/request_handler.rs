@@ -26,17 +26,21 @@ use crate::ledger_client::LedgerAccess;
 use crate::models::amount::tokens_to_amount;
 use crate::models::{
     AccountBalanceRequest, AccountBalanceResponse, Allow, BalanceAccountType, BlockIdentifier,
-    BlockResponse, BlockTransaction, BlockTransactionResponse, Error, MempoolResponse,
-    MempoolTransactionResponse, NetworkIdentifier, NetworkListResponse, NetworkOptionsResponse,
-    NetworkStatusResponse, NeuronInfoResponse, NeuronState, NeuronSubaccountComponents,
-    OperationStatus, Operator, PartialBlockIdentifier, SearchTransactionsResponse, SyncStatus,
-    Version,
+    BlockResponse, BlockTransaction, BlockTransactionResponse, CallRequest, CallResponse, Error,
+    MempoolResponse, MempoolTransactionResponse, NetworkIdentifier, NetworkListResponse,
+    NetworkOptionsResponse, NetworkStatusResponse, NeuronInfoResponse, NeuronState,
+    NeuronSubaccountComponents, Object, OperationStatus, Operator, PartialBlockIdentifier,
+    ProposalInfoResponse, SearchTransactionsResponse, SyncStatus, Tally, Version,
 };
 use crate::store::HashedBlock;
 
 /// The maximum amount of blocks to retrieve in a single search.
 const MAX_SEARCH_LIMIT: usize = 10_000;
 
+/// The methods supported by the /call endpoint.
+pub const GET_PENDING_PROPOSALS: &str = "get_pending_proposals";
+pub const GET_PROPOSAL_INFO: &str = "get_proposal_info";
+
 #[derive(Clone)]
 pub struct RosettaRequestHandler {
     blockchain: String,
@@ -64,6 +68,14 @@ impl RosettaRequestHandler {
         NetworkIdentifier::new(self.blockchain.clone(), net_id)
     }
 
+    /// The governance canister of this network, or an error if the network
+    /// does not support neuron management.
+    fn governance_canister_id(&self) -> Result<&CanisterId, ApiError> {
+        self.ledger
+            .governance_canister_id()
+            .ok_or_else(ApiError::neuron_management_not_supported)
+    }
+
     /// Get an Account Balance
     pub async fn account_balance(
         &self,
@@ -87,7 +99,7 @@ impl RosettaRequestHandler {
                 }) = subaccount_components
                 {
                     let addr_from_pk = neuron_account_from_public_key(
-                        self.ledger.governance_canister_id(),
+                        self.governance_canister_id()?,
                         &public_key,
                         neuron_index,
                     )?;
@@ -148,7 +160,7 @@ impl RosettaRequestHandler {
         let block = get_block(&blocks, msg.block_identifier)?;
 
         let tokens = blocks.get_balance(&account_id, block.index)?;
-        let amount = tokens_to_amount(tokens, self.ledger.token_symbol())?;
+        let amount = tokens_to_amount(tokens, self.ledger.currency())?;
         let b = convert::block_id(&block)?;
         Ok(AccountBalanceResponse {
             block_identifier: b,
@@ -168,10 +180,7 @@ impl RosettaRequestHandler {
         let b_id = convert::block_id(&hb)?;
         let parent_id = create_parent_block_id(&blocks, &hb)?;
 
-        let transactions = vec![convert::block_to_transaction(
-            &hb,
-            self.ledger.token_symbol(),
-        )?];
+        let transactions = vec![convert::block_to_transaction(&hb, self.ledger.currency())?];
         let block = Some(models::Block::new(
             b_id,
             parent_id,
@@ -197,7 +206,7 @@ impl RosettaRequestHandler {
             hash: Some(msg.block_identifier.hash),
         });
         let hb = get_block(&blocks, b_id)?;
-        let transaction = convert::block_to_transaction(&hb, self.ledger.token_symbol())?;
+        let transaction = convert::block_to_transaction(&hb, self.ledger.currency())?;
         Ok(BlockTransactionResponse::new(transaction))
     }
 
@@ -248,7 +257,7 @@ impl RosettaRequestHandler {
                     .map(|op| op.to_string())
                     .collect(),
                 {
-                    let token_name = self.ledger.token_symbol();
+                    let currency = self.ledger.currency();
                     let mut errs = vec![
                         Error::new(&ApiError::InternalError(true, Default::default())),
                         Error::new(&ApiError::InvalidRequest(false, Default::default())),
@@ -268,7 +277,7 @@ impl RosettaRequestHandler {
                         Error::new(&ApiError::TransactionRejected(false, Default::default())),
                         Error::new(&ApiError::OperationsErrors(
                             Default::default(),
-                            token_name.to_string(),
+                            currency.clone(),
                         )),
                         Error::new(&ApiError::TransactionExpired),
                     ];
@@ -280,6 +289,10 @@ impl RosettaRequestHandler {
                     errs
                 },
                 true,
+                vec![
+                    GET_PENDING_PROPOSALS.to_string(),
+                    GET_PROPOSAL_INFO.to_string(),
+                ],
             ),
         ))
     }
@@ -381,7 +394,7 @@ impl RosettaRequestHandler {
         for hb in block_range.into_iter().rev() {
             txs.push(BlockTransaction::new(
                 convert::block_id(&hb)?,
-                convert::block_to_transaction(&hb, self.ledger.token_symbol())?,
+                convert::block_to_transaction(&hb, self.ledger.currency())?,
             ));
         }
 
@@ -523,7 +536,7 @@ impl RosettaRequestHandler {
             let hb = blocks.get_verified_at(i)?;
             txs.push(BlockTransaction::new(
                 convert::block_id(&hb)?,
-                convert::block_to_transaction(&hb, self.ledger.token_symbol())?,
+                convert::block_to_transaction(&hb, self.ledger.currency())?,
             ));
         }
 
@@ -564,6 +577,96 @@ impl RosettaRequestHandler {
             created_timestamp_seconds: res.created_timestamp_seconds,
         })
     }
+
+    /// Make a call to a network-specific, read-only method.
+    /// See https://www.rosetta-api.org/docs/CallApi.html#call
+    pub async fn call(&self, msg: CallRequest) -> Result<CallResponse, ApiError> {
+        verify_network_id(self.ledger.ledger_canister_id(), &msg.network_identifier)?;
+
+        let result = match msg.method.as_str() {
+            GET_PENDING_PROPOSALS => {
+                let proposals: Vec<ProposalInfoResponse> = self
+                    .ledger
+                    .pending_proposals()
+                    .await?
+                    .into_iter()
+                    .map(proposal_info_response)
+                    .collect();
+                let mut result = Object::new();
+                result.insert(
+                    "pending_proposals".to_string(),
+                    serde_json::to_value(proposals).map_err(|e| {
+                        ApiError::internal_error(format!("Could not serialize proposals: {}", e))
+                    })?,
+                );
+                result
+            }
+            GET_PROPOSAL_INFO => {
+                let proposal_id = msg
+                    .parameters
+                    .get("proposal_id")
+                    .and_then(|id| id.as_u64())
+                    .ok_or_else(|| {
+                        ApiError::invalid_request(format!(
+                            "Method {} requires a numeric 'proposal_id' parameter",
+                            GET_PROPOSAL_INFO
+                        ))
+                    })?;
+                let info = self
+                    .ledger
+                    .proposal_info(proposal_id)
+                    .await?
+                    .ok_or_else(|| {
+                        ApiError::invalid_request(format!(
+                            "Proposal {} does not exist",
+                            proposal_id
+                        ))
+                    })?;
+                match serde_json::to_value(proposal_info_response(info)) {
+                    Ok(serde_json::Value::Object(o)) => o,
+                    _ => {
+                        return Err(ApiError::internal_error(
+                            "Could not serialize proposal info",
+                        ))
+                    }
+                }
+            }
+            method => {
+                return Err(ApiError::invalid_request(format!(
+                    "Unsupported call method: {}",
+                    method
+                )))
+            }
+        };
+
+        // Proposals change as they are voted on and decided.
+        Ok(CallResponse::new(result, false))
+    }
+}
+
+fn proposal_info_response(info: ic_nns_governance::pb::v1::ProposalInfo) -> ProposalInfoResponse {
+    let (title, summary, url) = match info.proposal {
+        Some(p) => (p.title, p.summary, p.url),
+        None => (None, String::new(), String::new()),
+    };
+    ProposalInfoResponse {
+        proposal_id: info.id.map(|id| id.id),
+        proposer: info.proposer.map(|id| id.id),
+        title,
+        summary,
+        url,
+        topic: info.topic,
+        status: info.status,
+        reward_status: info.reward_status,
+        proposal_timestamp_seconds: info.proposal_timestamp_seconds,
+        deadline_timestamp_seconds: info.deadline_timestamp_seconds,
+        latest_tally: info.latest_tally.map(|t| Tally {
+            timestamp_seconds: t.timestamp_seconds,
+            yes: t.yes,
+            no: t.no,
+            total: t.total,
+        }),
+    }
 }
 
 fn create_parent_block_id(
@@ -632,6 +735,44 @@ fn get_block(
     Ok(block)
 }
 
+/// Dispatches requests to the `RosettaRequestHandler` of the network they are
+/// addressed to. Requests for unknown networks are rejected with an
+/// `InvalidNetworkId` error.
+#[derive(Clone)]
+pub struct NetworkRouter {
+    handlers: Vec<RosettaRequestHandler>,
+}
+
+impl NetworkRouter {
+    pub fn new(handlers: Vec<RosettaRequestHandler>) -> Self {
+        assert!(
+            !handlers.is_empty(),
+            "At least one network must be configured"
+        );
+        Self { handlers }
+    }
+
+    pub fn handler(
+        &self,
+        network_identifier: &NetworkIdentifier,
+    ) -> Result<&RosettaRequestHandler, ApiError> {
+        self.handlers
+            .iter()
+            .find(|h| h.network_id().network == network_identifier.network)
+            .ok_or_else(|| ApiError::InvalidNetworkId(false, "unknown network".into()))
+    }
+
+    /// Get List of Available Networks
+    pub fn network_list(
+        &self,
+        _metadata_request: models::MetadataRequest,
+    ) -> Result<NetworkListResponse, ApiError> {
+        Ok(NetworkListResponse::new(
+            self.handlers.iter().map(|h| h.network_id()).collect(),
+        ))
+    }
+}
+
 fn verify_network_id(canister_id: &CanisterId, net_id: &NetworkIdentifier) -> Result<(), ApiError> {
     verify_network_blockchain(net_id)?;
     let id: CanisterId = net_id.try_into()?;
@@ -19,7 +19,7 @@ impl RosettaRequestHandler {
                 account_type: AccountType::Neuron { neuron_index },
                 ..
             }) => neuron_account_from_public_key(
-                self.ledger.governance_canister_id(),
+                self.governance_canister_id()?,
                 &msg.public_key,
                 neuron_index,
             )?,
@@ -27,7 +27,7 @@ impl RosettaRequestHandler {
                 let transfer_fee = self.ledger.transfer_fee().await?.transfer_fee;
                 Some(vec![tokens_to_amount(
                     transfer_fee,
-                    self.ledger.token_symbol(),
+                    self.ledger.currency(),
                 )?])
             }
         };
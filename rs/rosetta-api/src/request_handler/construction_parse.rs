@@ -3,20 +3,23 @@ use crate::errors::ApiError;
 use crate::models::{ConstructionParseRequest, ConstructionParseResponse, ParsedTransaction};
 use crate::request_handler::{verify_network_id, RosettaRequestHandler};
 use crate::request_types::{
-    AddHotKey, Disburse, Follow, MergeMaturity, NeuronInfo, PublicKeyOrPrincipal, RemoveHotKey,
-    RequestType, SetDissolveTimestamp, Spawn, Stake, StartDissolve, StopDissolve,
+    AddHotKey, Disburse, Follow, ListNeurons, MakeProposal, Merge, MergeMaturity, NeuronInfo,
+    PublicKeyOrPrincipal, RegisterVote, RemoveHotKey, RequestType, SetDissolveTimestamp, Spawn,
+    Split, Stake, StartDissolve, StopDissolve,
 };
 
 use ic_nns_governance::pb::v1::{
     manage_neuron::{self, Command, NeuronIdOrSubaccount},
-    ClaimOrRefreshNeuronFromAccount, ManageNeuron,
+    proposal::Action,
+    ClaimOrRefreshNeuronFromAccount, ListNeurons as ListNeuronsArgs, ManageNeuron, Motion,
+    Proposal,
 };
 
 use crate::models::seconds::Seconds;
 use crate::request::Request;
 use ic_types::messages::{Blob, HttpCallContent, HttpCanisterUpdate};
 use ic_types::PrincipalId;
-use ledger_canister::{AccountIdentifier, Operation, SendArgs};
+use ledger_canister::{AccountIdentifier, Operation, SendArgs, Tokens};
 use std::convert::TryFrom;
 
 impl RosettaRequestHandler {
@@ -88,6 +91,21 @@ impl RosettaRequestHandler {
                     neuron_index,
                     controller,
                 } => follow(&mut requests, arg, from, neuron_index, controller)?,
+                RequestType::RegisterVote {
+                    neuron_index,
+                    controller,
+                } => register_vote(&mut requests, arg, from, neuron_index, controller)?,
+                RequestType::MakeProposal {
+                    neuron_index,
+                    controller,
+                } => make_proposal(&mut requests, arg, from, neuron_index, controller)?,
+                RequestType::Split { neuron_index } => {
+                    split(&mut requests, arg, from, neuron_index)?
+                }
+                RequestType::Merge { neuron_index } => {
+                    merge(&mut requests, arg, from, neuron_index)?
+                }
+                RequestType::ListNeurons => list_neurons(&mut requests, arg, from)?,
             }
         }
 
@@ -96,7 +114,7 @@ impl RosettaRequestHandler {
         let from_ai = from_ai.iter().map(to_model_account_identifier).collect();
 
         Ok(ConstructionParseResponse {
-            operations: Request::requests_to_operations(&requests, self.ledger.token_symbol())?,
+            operations: Request::requests_to_operations(&requests, self.ledger.currency())?,
             signers: None,
             account_identifier_signers: Some(from_ai),
             metadata: None,
@@ -465,3 +483,147 @@ fn follow(
     }
     Ok(())
 }
+
+/// Handle REGISTER_VOTE.
+fn register_vote(
+    requests: &mut Vec<Request>,
+    arg: Blob,
+    from: AccountIdentifier,
+    neuron_index: u64,
+    controller: Option<PublicKeyOrPrincipal>,
+) -> Result<(), ApiError> {
+    let manage: ManageNeuron = candid::decode_one(arg.0.as_ref()).map_err(|e| {
+        ApiError::internal_error(format!("Could not decode ManageNeuron argument: {:?}", e))
+    })?;
+    if let Some(Command::RegisterVote(manage_neuron::RegisterVote {
+        proposal: Some(proposal),
+        vote,
+    })) = manage.command
+    {
+        let controller = controller
+            .map(convert::principal_id_from_public_key_or_principal)
+            .transpose()
+            .map_err(|_| ApiError::invalid_request("Invalid register vote request."))?;
+        requests.push(Request::RegisterVote(RegisterVote {
+            account: from,
+            proposal_id: proposal.id,
+            vote,
+            controller,
+            neuron_index,
+        }));
+    } else {
+        return Err(ApiError::internal_error(
+            "Incompatible manage_neuron command".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Handle MAKE_PROPOSAL.
+fn make_proposal(
+    requests: &mut Vec<Request>,
+    arg: Blob,
+    from: AccountIdentifier,
+    neuron_index: u64,
+    controller: Option<PublicKeyOrPrincipal>,
+) -> Result<(), ApiError> {
+    let manage: ManageNeuron = candid::decode_one(arg.0.as_ref()).map_err(|e| {
+        ApiError::internal_error(format!("Could not decode ManageNeuron argument: {:?}", e))
+    })?;
+    if let Some(Command::MakeProposal(proposal)) = manage.command {
+        if let Proposal {
+            title,
+            summary,
+            url,
+            action: Some(Action::Motion(Motion { motion_text })),
+        } = *proposal
+        {
+            let controller = controller
+                .map(convert::principal_id_from_public_key_or_principal)
+                .transpose()
+                .map_err(|_| ApiError::invalid_request("Invalid make proposal request."))?;
+            requests.push(Request::MakeProposal(MakeProposal {
+                account: from,
+                title,
+                summary,
+                url,
+                motion_text,
+                controller,
+                neuron_index,
+            }));
+        } else {
+            return Err(ApiError::internal_error(
+                "Incompatible manage_neuron command (only motion proposals are supported).",
+            ));
+        }
+    } else {
+        return Err(ApiError::internal_error(
+            "Incompatible manage_neuron command".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Handle SPLIT.
+fn split(
+    requests: &mut Vec<Request>,
+    arg: Blob,
+    from: AccountIdentifier,
+    neuron_index: u64,
+) -> Result<(), ApiError> {
+    let manage: ManageNeuron = candid::decode_one(arg.0.as_ref()).map_err(|e| {
+        ApiError::internal_error(format!("Could not decode ManageNeuron argument: {:?}", e))
+    })?;
+    if let Some(Command::Split(manage_neuron::Split { amount_e8s })) = manage.command {
+        requests.push(Request::Split(Split {
+            account: from,
+            amount: Tokens::from_e8s(amount_e8s),
+            neuron_index,
+        }));
+    } else {
+        return Err(ApiError::internal_error(
+            "Incompatible manage_neuron command".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Handle MERGE.
+fn merge(
+    requests: &mut Vec<Request>,
+    arg: Blob,
+    from: AccountIdentifier,
+    neuron_index: u64,
+) -> Result<(), ApiError> {
+    let manage: ManageNeuron = candid::decode_one(arg.0.as_ref()).map_err(|e| {
+        ApiError::internal_error(format!("Could not decode ManageNeuron argument: {:?}", e))
+    })?;
+    if let Some(Command::Merge(manage_neuron::Merge {
+        source_neuron_id: Some(source_neuron_id),
+    })) = manage.command
+    {
+        requests.push(Request::Merge(Merge {
+            account: from,
+            source_neuron_id: source_neuron_id.id,
+            neuron_index,
+        }));
+    } else {
+        return Err(ApiError::internal_error(
+            "Incompatible manage_neuron command".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Handle LIST_NEURONS.
+fn list_neurons(
+    requests: &mut Vec<Request>,
+    arg: Blob,
+    from: AccountIdentifier,
+) -> Result<(), ApiError> {
+    let _: ListNeuronsArgs = candid::decode_one(arg.0.as_ref()).map_err(|e| {
+        ApiError::internal_error(format!("Could not decode list neurons argument: {:?}", e))
+    })?;
+    requests.push(Request::ListNeurons(ListNeurons { account: from }));
+    Ok(())
+}
@@ -1,5 +1,5 @@
 use dfn_candid::CandidOne;
-use ic_nns_common::pb::v1::NeuronId;
+use ic_nns_common::pb::v1::{NeuronId, ProposalId};
 use ic_types::messages::{Blob, HttpCanisterUpdate, MessageId};
 use ic_types::PrincipalId;
 use ledger_canister::{Memo, Operation, SendArgs, Tokens};
@@ -11,7 +11,8 @@ use std::time::Duration;
 
 use ic_nns_governance::pb::v1::{
     manage_neuron::{self, configure, Command, NeuronIdOrSubaccount},
-    ClaimOrRefreshNeuronFromAccount, ManageNeuron,
+    proposal, ClaimOrRefreshNeuronFromAccount, ListNeurons as ListNeuronsArgs, ManageNeuron,
+    Motion, Proposal,
 };
 
 use crate::convert::{make_read_state_from_update, to_arg, to_model_account_identifier};
@@ -24,8 +25,9 @@ use crate::models::{
 use crate::request::Request;
 use crate::request_handler::{make_sig_data, verify_network_id, RosettaRequestHandler};
 use crate::request_types::{
-    AddHotKey, Disburse, Follow, MergeMaturity, NeuronInfo, PublicKeyOrPrincipal, RemoveHotKey,
-    RequestType, SetDissolveTimestamp, Spawn, Stake, StartDissolve, StopDissolve,
+    AddHotKey, Disburse, Follow, ListNeurons, MakeProposal, Merge, MergeMaturity, NeuronInfo,
+    PublicKeyOrPrincipal, RegisterVote, RemoveHotKey, RequestType, SetDissolveTimestamp, Spawn,
+    Split, Stake, StartDissolve, StopDissolve,
 };
 use crate::{convert, models};
 
@@ -46,8 +48,10 @@ impl RosettaRequestHandler {
         let pks = msg.public_keys.clone().ok_or_else(|| {
             ApiError::internal_error("Expected field 'public_keys' to be populated")
         })?;
-        let transactions =
-            convert::operations_to_requests(&ops, false, self.ledger.token_symbol())?;
+        let transactions = convert::operations_to_requests(&ops, false, self.ledger.currency())?;
+        if transactions.iter().any(Request::is_neuron_management) {
+            self.governance_canister_id()?;
+        }
 
         let interval = ic_constants::MAX_INGRESS_TTL
             - ic_constants::PERMITTED_DRIFT
@@ -187,6 +191,41 @@ impl RosettaRequestHandler {
                     &pks_map,
                     &ingress_expiries,
                 )?,
+                Request::RegisterVote(req) => handle_register_vote(
+                    req,
+                    &mut payloads,
+                    &mut updates,
+                    &pks_map,
+                    &ingress_expiries,
+                )?,
+                Request::MakeProposal(req) => handle_make_proposal(
+                    req,
+                    &mut payloads,
+                    &mut updates,
+                    &pks_map,
+                    &ingress_expiries,
+                )?,
+                Request::Split(req) => handle_split(
+                    req,
+                    &mut payloads,
+                    &mut updates,
+                    &pks_map,
+                    &ingress_expiries,
+                )?,
+                Request::Merge(req) => handle_merge(
+                    req,
+                    &mut payloads,
+                    &mut updates,
+                    &pks_map,
+                    &ingress_expiries,
+                )?,
+                Request::ListNeurons(req) => handle_list_neurons(
+                    req,
+                    &mut payloads,
+                    &mut updates,
+                    &pks_map,
+                    &ingress_expiries,
+                )?,
             }
         }
 
@@ -681,6 +720,173 @@ fn handle_follow(
     Ok(())
 }
 
+/// Handle REGISTER_VOTE.
+fn handle_register_vote(
+    req: RegisterVote,
+    payloads: &mut Vec<SigningPayload>,
+    updates: &mut Vec<(RequestType, HttpCanisterUpdate)>,
+    pks_map: &HashMap<ledger_canister::AccountIdentifier, &PublicKey>,
+    ingress_expiries: &[u64],
+) -> Result<(), ApiError> {
+    let account = req.account;
+    let controller = req.controller;
+    let neuron_index = req.neuron_index;
+    let command = Command::RegisterVote(manage_neuron::RegisterVote {
+        proposal: Some(ProposalId {
+            id: req.proposal_id,
+        }),
+        vote: req.vote,
+    });
+    add_neuron_management_payload(
+        RequestType::RegisterVote {
+            neuron_index,
+            controller: controller.map(PublicKeyOrPrincipal::Principal),
+        },
+        account,
+        controller,
+        neuron_index,
+        command,
+        payloads,
+        updates,
+        pks_map,
+        ingress_expiries,
+    )?;
+    Ok(())
+}
+
+/// Handle MAKE_PROPOSAL.
+fn handle_make_proposal(
+    req: MakeProposal,
+    payloads: &mut Vec<SigningPayload>,
+    updates: &mut Vec<(RequestType, HttpCanisterUpdate)>,
+    pks_map: &HashMap<ledger_canister::AccountIdentifier, &PublicKey>,
+    ingress_expiries: &[u64],
+) -> Result<(), ApiError> {
+    let account = req.account;
+    let controller = req.controller;
+    let neuron_index = req.neuron_index;
+    let command = Command::MakeProposal(Box::new(Proposal {
+        title: req.title,
+        summary: req.summary,
+        url: req.url,
+        action: Some(proposal::Action::Motion(Motion {
+            motion_text: req.motion_text,
+        })),
+    }));
+    add_neuron_management_payload(
+        RequestType::MakeProposal {
+            neuron_index,
+            controller: controller.map(PublicKeyOrPrincipal::Principal),
+        },
+        account,
+        controller,
+        neuron_index,
+        command,
+        payloads,
+        updates,
+        pks_map,
+        ingress_expiries,
+    )?;
+    Ok(())
+}
+
+/// Handle SPLIT.
+fn handle_split(
+    req: Split,
+    payloads: &mut Vec<SigningPayload>,
+    updates: &mut Vec<(RequestType, HttpCanisterUpdate)>,
+    pks_map: &HashMap<ledger_canister::AccountIdentifier, &PublicKey>,
+    ingress_expiries: &[u64],
+) -> Result<(), ApiError> {
+    let account = req.account;
+    let neuron_index = req.neuron_index;
+    let command = Command::Split(manage_neuron::Split {
+        amount_e8s: req.amount.get_e8s(),
+    });
+    add_neuron_management_payload(
+        RequestType::Split { neuron_index },
+        account,
+        None,
+        neuron_index,
+        command,
+        payloads,
+        updates,
+        pks_map,
+        ingress_expiries,
+    )?;
+    Ok(())
+}
+
+/// Handle MERGE.
+fn handle_merge(
+    req: Merge,
+    payloads: &mut Vec<SigningPayload>,
+    updates: &mut Vec<(RequestType, HttpCanisterUpdate)>,
+    pks_map: &HashMap<ledger_canister::AccountIdentifier, &PublicKey>,
+    ingress_expiries: &[u64],
+) -> Result<(), ApiError> {
+    let account = req.account;
+    let neuron_index = req.neuron_index;
+    let command = Command::Merge(manage_neuron::Merge {
+        source_neuron_id: Some(NeuronId {
+            id: req.source_neuron_id,
+        }),
+    });
+    add_neuron_management_payload(
+        RequestType::Merge { neuron_index },
+        account,
+        None,
+        neuron_index,
+        command,
+        payloads,
+        updates,
+        pks_map,
+        ingress_expiries,
+    )?;
+    Ok(())
+}
+
+/// Handle LIST_NEURONS.
+fn handle_list_neurons(
+    req: ListNeurons,
+    payloads: &mut Vec<SigningPayload>,
+    updates: &mut Vec<(RequestType, HttpCanisterUpdate)>,
+    pks_map: &HashMap<ledger_canister::AccountIdentifier, &PublicKey>,
+    ingress_expiries: &[u64],
+) -> Result<(), ApiError> {
+    let account = req.account;
+    let pk = pks_map.get(&account).ok_or_else(|| {
+        ApiError::internal_error(format!(
+            "ListNeurons - Cannot find public key for account {}",
+            account,
+        ))
+    })?;
+    let sender = convert::principal_id_from_public_key(pk)?;
+
+    // The governance canister returns the full neurons that the sender
+    // controls or is a hotkey of.
+    let args = ListNeuronsArgs {
+        neuron_ids: vec![],
+        include_neurons_readable_by_caller: true,
+    };
+    let update = HttpCanisterUpdate {
+        canister_id: Blob(ic_nns_constants::GOVERNANCE_CANISTER_ID.get().to_vec()),
+        method_name: "list_neurons".to_string(),
+        arg: Blob(CandidOne(args).into_bytes().expect("Serialization failed")),
+        nonce: None,
+        sender: Blob(sender.into_vec()),
+        ingress_expiry: 0,
+    };
+    add_payloads(
+        payloads,
+        ingress_expiries,
+        &convert::to_model_account_identifier(&account),
+        &update,
+    );
+    updates.push((RequestType::ListNeurons, update));
+    Ok(())
+}
+
 fn add_neuron_management_payload(
     request_type: RequestType,
     account: ledger_canister::AccountIdentifier,
@@ -7,8 +7,9 @@ use crate::models::{
 use crate::request::Request;
 use crate::request_handler::{verify_network_id, RosettaRequestHandler};
 use crate::request_types::{
-    AddHotKey, Disburse, Follow, MergeMaturity, NeuronInfo, RemoveHotKey, SetDissolveTimestamp,
-    Spawn, Stake, StartDissolve, StopDissolve,
+    AddHotKey, Disburse, Follow, ListNeurons, MakeProposal, Merge, MergeMaturity, NeuronInfo,
+    RegisterVote, RemoveHotKey, SetDissolveTimestamp, Spawn, Split, Stake, StartDissolve,
+    StopDissolve,
 };
 use ledger_canister::Operation;
 use std::collections::HashSet;
@@ -22,7 +23,10 @@ impl RosettaRequestHandler {
     ) -> Result<ConstructionPreprocessResponse, ApiError> {
         verify_network_id(self.ledger.ledger_canister_id(), &msg.network_identifier)?;
         let transfers =
-            convert::operations_to_requests(&msg.operations, true, self.ledger.token_symbol())?;
+            convert::operations_to_requests(&msg.operations, true, self.ledger.currency())?;
+        if transfers.iter().any(Request::is_neuron_management) {
+            self.governance_canister_id()?;
+        }
         let options = Some(ConstructionMetadataRequestOptions {
             request_types: transfers
                 .iter()
@@ -65,6 +69,11 @@ fn required_public_key(request: Request) -> Result<ledger_canister::AccountIdent
         | Request::Spawn(Spawn { account, .. })
         | Request::MergeMaturity(MergeMaturity { account, .. })
         | Request::NeuronInfo(NeuronInfo { account, .. })
-        | Request::Follow(Follow { account, .. }) => Ok(account),
+        | Request::Follow(Follow { account, .. })
+        | Request::RegisterVote(RegisterVote { account, .. })
+        | Request::MakeProposal(MakeProposal { account, .. })
+        | Request::Split(Split { account, .. })
+        | Request::Merge(Merge { account, .. })
+        | Request::ListNeurons(ListNeurons { account }) => Ok(account),
     }
 }
@@ -19,10 +19,8 @@ impl RosettaRequestHandler {
         let envelopes = msg.signed_transaction()?;
         let results = self.ledger.submit(envelopes).await?;
         let transaction_identifier = transaction_identifier(&results);
-        let metadata = TransactionOperationResults::from_transaction_results(
-            results,
-            self.ledger.token_symbol(),
-        )?;
+        let metadata =
+            TransactionOperationResults::from_transaction_results(results, self.ledger.currency())?;
         Ok(ConstructionSubmitResponse {
             transaction_identifier,
             metadata,
@@ -1,6 +1,7 @@
 use crate::models::amount::{signed_amount, tokens_to_amount};
 use crate::models::operation::{OperationIdentifier, OperationType};
 use crate::models::seconds::Seconds;
+use crate::models::Currency;
 use crate::{
     convert::{principal_id_from_public_key, to_model_account_identifier},
     errors::ApiError,
@@ -33,6 +34,11 @@ pub const SPAWN: &str = "SPAWN";
 pub const MERGE_MATURITY: &str = "MERGE_MATURITY";
 pub const NEURON_INFO: &str = "NEURON_INFO";
 pub const FOLLOW: &str = "FOLLOW";
+pub const REGISTER_VOTE: &str = "REGISTER_VOTE";
+pub const MAKE_PROPOSAL: &str = "MAKE_PROPOSAL";
+pub const SPLIT: &str = "SPLIT";
+pub const MERGE: &str = "MERGE";
+pub const LIST_NEURONS: &str = "LIST_NEURONS";
 
 /// `RequestType` contains all supported values of `Operation.type`.
 /// Extra information, such as `neuron_index` should only be included
@@ -82,6 +88,27 @@ pub enum RequestType {
         neuron_index: u64,
         controller: Option<PublicKeyOrPrincipal>,
     },
+    #[serde(rename = "REGISTER_VOTE")]
+    #[serde(alias = "RegisterVote")]
+    RegisterVote {
+        neuron_index: u64,
+        controller: Option<PublicKeyOrPrincipal>,
+    },
+    #[serde(rename = "MAKE_PROPOSAL")]
+    #[serde(alias = "MakeProposal")]
+    MakeProposal {
+        neuron_index: u64,
+        controller: Option<PublicKeyOrPrincipal>,
+    },
+    #[serde(rename = "SPLIT")]
+    #[serde(alias = "Split")]
+    Split { neuron_index: u64 },
+    #[serde(rename = "MERGE")]
+    #[serde(alias = "Merge")]
+    Merge { neuron_index: u64 },
+    #[serde(rename = "LIST_NEURONS")]
+    #[serde(alias = "ListNeurons")]
+    ListNeurons,
 }
 
 impl RequestType {
@@ -99,6 +126,11 @@ impl RequestType {
             RequestType::MergeMaturity { .. } => MERGE_MATURITY,
             RequestType::NeuronInfo { .. } => NEURON_INFO,
             RequestType::Follow { .. } => FOLLOW,
+            RequestType::RegisterVote { .. } => REGISTER_VOTE,
+            RequestType::MakeProposal { .. } => MAKE_PROPOSAL,
+            RequestType::Split { .. } => SPLIT,
+            RequestType::Merge { .. } => MERGE,
+            RequestType::ListNeurons => LIST_NEURONS,
         }
     }
 
@@ -120,6 +152,11 @@ impl RequestType {
                 | RequestType::MergeMaturity { .. }
                 | RequestType::NeuronInfo { .. }
                 | RequestType::Follow { .. }
+                | RequestType::RegisterVote { .. }
+                | RequestType::MakeProposal { .. }
+                | RequestType::Split { .. }
+                | RequestType::Merge { .. }
+                | RequestType::ListNeurons
         )
     }
 }
@@ -288,6 +325,52 @@ pub struct Follow {
     pub neuron_index: u64,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RegisterVote {
+    pub account: ledger_canister::AccountIdentifier,
+    pub proposal_id: u64,
+    pub vote: i32,
+    pub controller: Option<PrincipalId>,
+    #[serde(default)]
+    pub neuron_index: u64,
+}
+
+/// Only motion proposals can be made through Rosetta.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct MakeProposal {
+    pub account: ledger_canister::AccountIdentifier,
+    pub title: Option<String>,
+    pub summary: String,
+    pub url: String,
+    pub motion_text: String,
+    pub controller: Option<PrincipalId>,
+    #[serde(default)]
+    pub neuron_index: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Split {
+    pub account: ledger_canister::AccountIdentifier,
+    pub amount: Tokens,
+    #[serde(default)]
+    pub neuron_index: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Merge {
+    pub account: ledger_canister::AccountIdentifier,
+    /// The id of the neuron that is merged into the neuron identified by
+    /// `neuron_index`. Both neurons must have the same controller.
+    pub source_neuron_id: u64,
+    #[serde(default)]
+    pub neuron_index: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ListNeurons {
+    pub account: ledger_canister::AccountIdentifier,
+}
+
 #[derive(Debug, Clone, Eq, PartialOrd, Ord, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case")]
 // Externally tagged by default.
@@ -673,6 +756,138 @@ impl From<FollowMetadata> for Object {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct RegisterVoteMetadata {
+    pub proposal_id: u64,
+    /// The vote as defined by the governance canister: 1 for yes, 2 for no.
+    pub vote: i32,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub controller: Option<PublicKeyOrPrincipal>,
+    #[serde(default)]
+    pub neuron_index: u64,
+}
+
+impl TryFrom<Option<Object>> for RegisterVoteMetadata {
+    type Error = ApiError;
+    fn try_from(o: Option<Object>) -> Result<Self, Self::Error> {
+        serde_json::from_value(serde_json::Value::Object(o.unwrap_or_default())).map_err(|e| {
+            ApiError::internal_error(format!(
+                "Could not parse a REGISTER_VOTE operation metadata from metadata JSON object: {}",
+                e
+            ))
+        })
+    }
+}
+
+impl From<RegisterVoteMetadata> for Object {
+    fn from(m: RegisterVoteMetadata) -> Self {
+        match serde_json::to_value(m) {
+            Ok(Value::Object(o)) => o,
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct MakeProposalMetadata {
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub summary: String,
+    #[serde(default)]
+    pub url: String,
+    pub motion_text: String,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub controller: Option<PublicKeyOrPrincipal>,
+    #[serde(default)]
+    pub neuron_index: u64,
+}
+
+impl TryFrom<Option<Object>> for MakeProposalMetadata {
+    type Error = ApiError;
+    fn try_from(o: Option<Object>) -> Result<Self, Self::Error> {
+        serde_json::from_value(serde_json::Value::Object(o.unwrap_or_default())).map_err(|e| {
+            ApiError::internal_error(format!(
+                "Could not parse a MAKE_PROPOSAL operation metadata from metadata JSON object: {}",
+                e
+            ))
+        })
+    }
+}
+
+impl From<MakeProposalMetadata> for Object {
+    fn from(m: MakeProposalMetadata) -> Self {
+        match serde_json::to_value(m) {
+            Ok(Value::Object(o)) => o,
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct MergeMetadata {
+    pub source_neuron_id: u64,
+    #[serde(default)]
+    pub neuron_index: u64,
+}
+
+impl TryFrom<Option<Object>> for MergeMetadata {
+    type Error = ApiError;
+    fn try_from(o: Option<Object>) -> Result<Self, Self::Error> {
+        serde_json::from_value(serde_json::Value::Object(o.unwrap_or_default())).map_err(|e| {
+            ApiError::internal_error(format!(
+                "Could not parse MERGE operation metadata from metadata JSON object: {}",
+                e
+            ))
+        })
+    }
+}
+
+impl From<MergeMetadata> for Object {
+    fn from(m: MergeMetadata) -> Self {
+        match serde_json::to_value(m) {
+            Ok(Value::Object(o)) => o,
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[test]
+fn test_parse_register_vote_metadata() {
+    let m: RegisterVoteMetadata =
+        serde_json::from_str(r#"{ "proposal_id": 42, "vote": 1, "neuron_index": 3 }"#).unwrap();
+    assert_eq!(
+        m,
+        RegisterVoteMetadata {
+            proposal_id: 42,
+            vote: 1,
+            controller: None,
+            neuron_index: 3,
+        }
+    );
+    assert!(RegisterVoteMetadata::try_from(Some(Object::default())).is_err());
+}
+
+#[test]
+fn test_parse_make_proposal_metadata() {
+    let m: MakeProposalMetadata =
+        serde_json::from_str(r#"{ "summary": "A summary", "motion_text": "Do the thing" }"#)
+            .unwrap();
+    assert_eq!(
+        m,
+        MakeProposalMetadata {
+            title: None,
+            summary: "A summary".to_string(),
+            url: String::new(),
+            motion_text: "Do the thing".to_string(),
+            controller: None,
+            neuron_index: 0,
+        }
+    );
+}
+
 /// Transaction is a bit of a misnomer, since operations can succeed or fail
 /// independently from a Transaction.
 #[derive(Default)]
@@ -703,7 +918,7 @@ impl TransactionBuilder {
     pub fn transfer(
         &mut self,
         operation: &LedgerOperation,
-        token_name: &str,
+        currency: &Currency,
     ) -> Result<(), ApiError> {
         match operation {
             LedgerOperation::Burn { from, amount } => {
@@ -713,7 +928,7 @@ impl TransactionBuilder {
                     _type: OperationType::Burn,
                     status: None,
                     account: Some(to_model_account_identifier(from)),
-                    amount: Some(signed_amount(-i128::from(amount.get_e8s()), token_name)),
+                    amount: Some(signed_amount(-i128::from(amount.get_e8s()), currency)),
                     related_operations: None,
                     coin_change: None,
                     metadata: None,
@@ -726,7 +941,7 @@ impl TransactionBuilder {
                     _type: OperationType::Mint,
                     status: None,
                     account: Some(to_model_account_identifier(to)),
-                    amount: Some(tokens_to_amount(*amount, token_name)?),
+                    amount: Some(tokens_to_amount(*amount, currency)?),
                     related_operations: None,
                     coin_change: None,
                     metadata: None,
@@ -747,7 +962,7 @@ impl TransactionBuilder {
                     _type: OperationType::Transaction,
                     status: None,
                     account: from_account.clone(),
-                    amount: Some(signed_amount(-amount, token_name)),
+                    amount: Some(signed_amount(-amount, currency)),
                     related_operations: None,
                     coin_change: None,
                     metadata: None,
@@ -758,7 +973,7 @@ impl TransactionBuilder {
                     _type: OperationType::Transaction,
                     status: None,
                     account: Some(to_model_account_identifier(to)),
-                    amount: Some(signed_amount(amount, token_name)),
+                    amount: Some(signed_amount(amount, currency)),
                     related_operations: None,
                     coin_change: None,
                     metadata: None,
@@ -769,7 +984,7 @@ impl TransactionBuilder {
                     _type: OperationType::Fee,
                     status: None,
                     account: from_account,
-                    amount: Some(signed_amount(-(fee.get_e8s() as i128), token_name)),
+                    amount: Some(signed_amount(-(fee.get_e8s() as i128), currency)),
                     related_operations: None,
                     coin_change: None,
                     metadata: None,
@@ -873,7 +1088,7 @@ impl TransactionBuilder {
         });
     }
 
-    pub fn disburse(&mut self, disburse: &Disburse, token_name: &str) {
+    pub fn disburse(&mut self, disburse: &Disburse, currency: &Currency) {
         let Disburse {
             account,
             amount,
@@ -887,7 +1102,7 @@ impl TransactionBuilder {
             status: None,
             account: Some(to_model_account_identifier(account)),
             amount: amount
-                .map(|a| tokens_to_amount(a, token_name).expect("failed to convert amount")),
+                .map(|a| tokens_to_amount(a, currency).expect("failed to convert amount")),
             related_operations: None,
             coin_change: None,
             metadata: Some(
@@ -1054,6 +1269,133 @@ impl TransactionBuilder {
             ),
         });
     }
+
+    pub fn register_vote(&mut self, req: &RegisterVote) {
+        let RegisterVote {
+            account,
+            proposal_id,
+            vote,
+            controller,
+            neuron_index,
+        } = req;
+        let operation_identifier = self.allocate_op_id();
+        self.ops.push(Operation {
+            operation_identifier,
+            _type: OperationType::RegisterVote,
+            status: None,
+            account: Some(to_model_account_identifier(account)),
+            amount: None,
+            related_operations: None,
+            coin_change: None,
+            metadata: Some(
+                RegisterVoteMetadata {
+                    proposal_id: *proposal_id,
+                    vote: *vote,
+                    controller: pkp_from_principal(controller),
+                    neuron_index: *neuron_index,
+                }
+                .into(),
+            ),
+        });
+    }
+
+    pub fn make_proposal(&mut self, proposal: &MakeProposal) {
+        let MakeProposal {
+            account,
+            title,
+            summary,
+            url,
+            motion_text,
+            controller,
+            neuron_index,
+        } = proposal;
+        let operation_identifier = self.allocate_op_id();
+        self.ops.push(Operation {
+            operation_identifier,
+            _type: OperationType::MakeProposal,
+            status: None,
+            account: Some(to_model_account_identifier(account)),
+            amount: None,
+            related_operations: None,
+            coin_change: None,
+            metadata: Some(
+                MakeProposalMetadata {
+                    title: title.clone(),
+                    summary: summary.clone(),
+                    url: url.clone(),
+                    motion_text: motion_text.clone(),
+                    controller: pkp_from_principal(controller),
+                    neuron_index: *neuron_index,
+                }
+                .into(),
+            ),
+        });
+    }
+
+    pub fn split(&mut self, split: &Split, currency: &Currency) -> Result<(), ApiError> {
+        let Split {
+            account,
+            amount,
+            neuron_index,
+        } = split;
+        let operation_identifier = self.allocate_op_id();
+        self.ops.push(Operation {
+            operation_identifier,
+            _type: OperationType::Split,
+            status: None,
+            account: Some(to_model_account_identifier(account)),
+            amount: Some(tokens_to_amount(*amount, currency)?),
+            related_operations: None,
+            coin_change: None,
+            metadata: Some(
+                NeuronIdentifierMetadata {
+                    neuron_index: *neuron_index,
+                }
+                .into(),
+            ),
+        });
+        Ok(())
+    }
+
+    pub fn merge(&mut self, merge: &Merge) {
+        let Merge {
+            account,
+            source_neuron_id,
+            neuron_index,
+        } = merge;
+        let operation_identifier = self.allocate_op_id();
+        self.ops.push(Operation {
+            operation_identifier,
+            _type: OperationType::Merge,
+            status: None,
+            account: Some(to_model_account_identifier(account)),
+            amount: None,
+            related_operations: None,
+            coin_change: None,
+            metadata: Some(
+                MergeMetadata {
+                    source_neuron_id: *source_neuron_id,
+                    neuron_index: *neuron_index,
+                }
+                .into(),
+            ),
+        });
+    }
+
+    pub fn list_neurons(&mut self, req: &ListNeurons) {
+        let ListNeurons { account } = req;
+        let operation_identifier = self.allocate_op_id();
+        self.ops.push(Operation {
+            operation_identifier,
+            _type: OperationType::ListNeurons,
+            status: None,
+            account: Some(to_model_account_identifier(account)),
+            amount: None,
+            related_operations: None,
+            coin_change: None,
+            metadata: None,
+        });
+    }
 }
 
 /// Converts an optional PrincipalId to an optional PublicKeyOrPrincipal.
@@ -8,14 +8,13 @@ use crate::{
     errors::{self, ApiError},
     ledger_client::LedgerAccess,
     models::*,
-    request_handler::RosettaRequestHandler,
+    request_handler::{NetworkRouter, RosettaRequestHandler},
 };
 
 use log::{debug, error, info};
 use prometheus::{
-    register_gauge, register_histogram, register_histogram_vec, register_int_counter,
-    register_int_counter_vec, register_int_gauge, Encoder, Gauge, Histogram, HistogramVec,
-    IntCounter, IntCounterVec, IntGauge,
+    register_gauge_vec, register_histogram_vec, register_int_counter, register_int_counter_vec,
+    register_int_gauge, Encoder, GaugeVec, HistogramVec, IntCounter, IntCounterVec, IntGauge,
 };
 use std::{
     io,
@@ -70,14 +69,16 @@ lazy_static! {
         "Number of times synchronization failed"
     )
     .unwrap();
-    pub static ref OUT_OF_SYNC_TIME: Gauge = register_gauge!(
+    pub static ref OUT_OF_SYNC_TIME: GaugeVec = register_gauge_vec!(
         "ledger_sync_attempt_duration_seconds",
-        "Number of seconds since the last successful sync"
+        "Number of seconds since the last successful sync, by ledger canister id",
+        &["ledger_canister_id"]
     )
     .unwrap();
-    pub static ref OUT_OF_SYNC_TIME_HIST: Histogram = register_histogram!(
+    pub static ref OUT_OF_SYNC_TIME_HIST: HistogramVec = register_histogram_vec!(
         "ledger_sync_attempt_duration_seconds_hist",
-        "Number of seconds since last successful sync",
+        "Number of seconds since last successful sync, by ledger canister id",
+        &["ledger_canister_id"],
         vec![0.5, 1.0, 1.5, 2.0, 2.5, 3.0, 5.0, 10.0, 15.0]
     )
     .unwrap();
@@ -86,168 +87,223 @@ lazy_static! {
 #[post("/account/balance")]
 async fn account_balance(
     msg: web::Json<AccountBalanceRequest>,
-    req_handler: web::Data<RosettaRequestHandler>,
+    router: web::Data<NetworkRouter>,
 ) -> HttpResponse {
     let _timer = ENDPOINTS_METRICS
         .request_duration
         .with_label_values(&["account/balance"])
         .start_timer();
-    let res = req_handler.account_balance(msg.into_inner()).await;
+    let res = match router.handler(&msg.network_identifier) {
+        Ok(handler) => handler.account_balance(msg.into_inner()).await,
+        Err(err) => Err(err),
+    };
     to_rosetta_response(res)
 }
 
 #[post("/block")]
-async fn block(
-    msg: web::Json<BlockRequest>,
-    req_handler: web::Data<RosettaRequestHandler>,
-) -> HttpResponse {
+async fn block(msg: web::Json<BlockRequest>, router: web::Data<NetworkRouter>) -> HttpResponse {
     let _timer = ENDPOINTS_METRICS
         .request_duration
         .with_label_values(&["block"])
         .start_timer();
-    let res = req_handler.block(msg.into_inner()).await;
+    let res = match router.handler(&msg.network_identifier) {
+        Ok(handler) => handler.block(msg.into_inner()).await,
+        Err(err) => Err(err),
+    };
     to_rosetta_response(res)
 }
 
 #[post("/block/transaction")]
 async fn block_transaction(
     msg: web::Json<BlockTransactionRequest>,
-    req_handler: web::Data<RosettaRequestHandler>,
+    router: web::Data<NetworkRouter>,
 ) -> HttpResponse {
-    let res = req_handler.block_transaction(msg.into_inner()).await;
+    let res = match router.handler(&msg.network_identifier) {
+        Ok(handler) => handler.block_transaction(msg.into_inner()).await,
+        Err(err) => Err(err),
+    };
+    to_rosetta_response(res)
+}
+
+#[post("/call")]
+async fn call(msg: web::Json<CallRequest>, router: web::Data<NetworkRouter>) -> HttpResponse {
+    let _timer = ENDPOINTS_METRICS
+        .request_duration
+        .with_label_values(&["call"])
+        .start_timer();
+    let res = match router.handler(&msg.network_identifier) {
+        Ok(handler) => handler.call(msg.into_inner()).await,
+        Err(err) => Err(err),
+    };
     to_rosetta_response(res)
 }
 
 #[post("/construction/combine")]
 async fn construction_combine(
     msg: web::Json<ConstructionCombineRequest>,
-    req_handler: web::Data<RosettaRequestHandler>,
+    router: web::Data<NetworkRouter>,
 ) -> HttpResponse {
-    let res = req_handler.construction_combine(msg.into_inner());
+    let res = match router.handler(&msg.network_identifier) {
+        Ok(handler) => handler.construction_combine(msg.into_inner()),
+        Err(err) => Err(err),
+    };
     to_rosetta_response(res)
 }
 
 #[post("/construction/derive")]
 async fn construction_derive(
     msg: web::Json<ConstructionDeriveRequest>,
-    req_handler: web::Data<RosettaRequestHandler>,
+    router: web::Data<NetworkRouter>,
 ) -> HttpResponse {
-    let res = req_handler.construction_derive(msg.into_inner());
+    let res = match router.handler(&msg.network_identifier) {
+        Ok(handler) => handler.construction_derive(msg.into_inner()),
+        Err(err) => Err(err),
+    };
     to_rosetta_response(res)
 }
 
 #[post("/construction/hash")]
 async fn construction_hash(
     msg: web::Json<ConstructionHashRequest>,
-    req_handler: web::Data<RosettaRequestHandler>,
+    router: web::Data<NetworkRouter>,
 ) -> HttpResponse {
-    let res = req_handler.construction_hash(msg.into_inner());
+    let res = match router.handler(&msg.network_identifier) {
+        Ok(handler) => handler.construction_hash(msg.into_inner()),
+        Err(err) => Err(err),
+    };
     to_rosetta_response(res)
 }
 
 #[post("/construction/metadata")]
 async fn construction_metadata(
     msg: web::Json<ConstructionMetadataRequest>,
-    req_handler: web::Data<RosettaRequestHandler>,
+    router: web::Data<NetworkRouter>,
 ) -> HttpResponse {
-    let res = req_handler.construction_metadata(msg.into_inner()).await;
+    let res = match router.handler(&msg.network_identifier) {
+        Ok(handler) => handler.construction_metadata(msg.into_inner()).await,
+        Err(err) => Err(err),
+    };
     to_rosetta_response(res)
 }
 
 #[post("/construction/parse")]
 async fn construction_parse(
     msg: web::Json<ConstructionParseRequest>,
-    req_handler: web::Data<RosettaRequestHandler>,
+    router: web::Data<NetworkRouter>,
 ) -> HttpResponse {
-    let res = req_handler.construction_parse(msg.into_inner());
+    let res = match router.handler(&msg.network_identifier) {
+        Ok(handler) => handler.construction_parse(msg.into_inner()),
+        Err(err) => Err(err),
+    };
     to_rosetta_response(res)
 }
 
 #[post("/construction/payloads")]
 async fn construction_payloads(
     msg: web::Json<ConstructionPayloadsRequest>,
-    req_handler: web::Data<RosettaRequestHandler>,
+    router: web::Data<NetworkRouter>,
 ) -> HttpResponse {
-    let res = req_handler.construction_payloads(msg.into_inner());
+    let res = match router.handler(&msg.network_identifier) {
+        Ok(handler) => handler.construction_payloads(msg.into_inner()),
+        Err(err) => Err(err),
+    };
     to_rosetta_response(res)
 }
 
 #[post("/construction/preprocess")]
 async fn construction_preprocess(
     msg: web::Json<ConstructionPreprocessRequest>,
-    req_handler: web::Data<RosettaRequestHandler>,
+    router: web::Data<NetworkRouter>,
 ) -> HttpResponse {
-    let res = req_handler.construction_preprocess(msg.into_inner());
+    let res = match router.handler(&msg.network_identifier) {
+        Ok(handler) => handler.construction_preprocess(msg.into_inner()),
+        Err(err) => Err(err),
+    };
     to_rosetta_response(res)
 }
 
 #[post("/construction/submit")]
 async fn construction_submit(
     msg: web::Json<ConstructionSubmitRequest>,
-    req_handler: web::Data<RosettaRequestHandler>,
+    router: web::Data<NetworkRouter>,
 ) -> HttpResponse {
     let _timer = ENDPOINTS_METRICS
         .request_duration
         .with_label_values(&["construction/submit"])
         .start_timer();
-    let res = req_handler.construction_submit(msg.into_inner()).await;
+    let res = match router.handler(&msg.network_identifier) {
+        Ok(handler) => handler.construction_submit(msg.into_inner()).await,
+        Err(err) => Err(err),
+    };
     to_rosetta_response(res)
 }
 
 #[post("/network/list")]
 async fn network_list(
     msg: web::Json<MetadataRequest>,
-    req_handler: web::Data<RosettaRequestHandler>,
+    router: web::Data<NetworkRouter>,
 ) -> HttpResponse {
-    let res = req_handler.network_list(msg.into_inner()).await;
+    let res = router.network_list(msg.into_inner());
     to_rosetta_response(res)
 }
 
 #[post("/network/options")]
 async fn network_options(
     msg: web::Json<NetworkRequest>,
-    req_handler: web::Data<RosettaRequestHandler>,
+    router: web::Data<NetworkRouter>,
 ) -> HttpResponse {
-    let res = req_handler.network_options(msg.into_inner()).await;
+    let res = match router.handler(&msg.network_identifier) {
+        Ok(handler) => handler.network_options(msg.into_inner()).await,
+        Err(err) => Err(err),
+    };
     to_rosetta_response(res)
 }
 
 #[post("/network/status")]
 async fn network_status(
     msg: web::Json<NetworkRequest>,
-    req_handler: web::Data<RosettaRequestHandler>,
+    router: web::Data<NetworkRouter>,
 ) -> HttpResponse {
-    let res = req_handler.network_status(msg.into_inner()).await;
+    let res = match router.handler(&msg.network_identifier) {
+        Ok(handler) => handler.network_status(msg.into_inner()).await,
+        Err(err) => Err(err),
+    };
     to_rosetta_response(res)
 }
 
 #[post("/mempool")]
-async fn mempool(
-    msg: web::Json<NetworkRequest>,
-    req_handler: web::Data<RosettaRequestHandler>,
-) -> HttpResponse {
-    let res = req_handler.mempool(msg.into_inner()).await;
+async fn mempool(msg: web::Json<NetworkRequest>, router: web::Data<NetworkRouter>) -> HttpResponse {
+    let res = match router.handler(&msg.network_identifier) {
+        Ok(handler) => handler.mempool(msg.into_inner()).await,
+        Err(err) => Err(err),
+    };
     to_rosetta_response(res)
 }
 
 #[post("/mempool/transaction")]
 async fn mempool_transaction(
     msg: web::Json<MempoolTransactionRequest>,
-    req_handler: web::Data<RosettaRequestHandler>,
+    router: web::Data<NetworkRouter>,
 ) -> HttpResponse {
-    let res = req_handler.mempool_transaction(msg.into_inner()).await;
+    let res = match router.handler(&msg.network_identifier) {
+        Ok(handler) => handler.mempool_transaction(msg.into_inner()).await,
+        Err(err) => Err(err),
+    };
     to_rosetta_response(res)
 }
 
 #[post("/search/transactions")]
 async fn search_transactions(
     msg: web::Json<SearchTransactionsRequest>,
-    req_handler: web::Data<RosettaRequestHandler>,
+    router: web::Data<NetworkRouter>,
 ) -> HttpResponse {
     let _timer = ENDPOINTS_METRICS
         .request_duration
         .with_label_values(&["search/transactions"]);
-    let res = req_handler.search_transactions(msg.into_inner()).await;
+    let res = match router.handler(&msg.network_identifier) {
+        Ok(handler) => handler.search_transactions(msg.into_inner()).await,
+        Err(err) => Err(err),
+    };
     to_rosetta_response(res)
 }
 
@@ -321,7 +377,7 @@ enum ServerState {
 
 pub struct RosettaApiServer {
     stopped: Arc<AtomicBool>,
-    ledger: Arc<dyn LedgerAccess + Send + Sync>,
+    ledgers: Vec<Arc<dyn LedgerAccess + Send + Sync>>,
     server: Mutex<ServerState>,
     server_handle: ServerHandle,
 }
@@ -332,6 +388,23 @@ impl RosettaApiServer {
         req_handler: RosettaRequestHandler,
         addr: String,
         expose_metrics: bool,
+    ) -> io::Result<Self> {
+        Self::new_with_networks(
+            vec![ledger as Arc<dyn LedgerAccess + Send + Sync>],
+            NetworkRouter::new(vec![req_handler]),
+            addr,
+            expose_metrics,
+        )
+    }
+
+    /// Create a server for several networks at once. Every ledger in `ledgers`
+    /// is kept in sync and requests are dispatched by `router` according to
+    /// their network identifier.
+    pub fn new_with_networks(
+        ledgers: Vec<Arc<dyn LedgerAccess + Send + Sync>>,
+        router: NetworkRouter,
+        addr: String,
+        expose_metrics: bool,
     ) -> io::Result<Self> {
         let stopped = Arc::new(AtomicBool::new(false));
         let server = HttpServer::new(move || {
@@ -347,10 +420,11 @@ impl RosettaApiServer {
                             .into()
                         }),
                 ))
-                .app_data(web::Data::new(req_handler.clone()))
+                .app_data(web::Data::new(router.clone()))
                 .service(account_balance)
                 .service(block)
                 .service(block_transaction)
+                .service(call)
                 .service(construction_combine)
                 .service(construction_derive)
                 .service(construction_hash)
@@ -376,7 +450,7 @@ impl RosettaApiServer {
 
         Ok(Self {
             stopped,
-            ledger,
+            ledgers,
             server_handle: server.handle(),
             server: Mutex::new(ServerState::Unstarted(server)),
         })
@@ -408,44 +482,36 @@ impl RosettaApiServer {
                 ServerState::OfflineStarted
             }
             ServerState::Unstarted(server) => {
-                let ledger = self.ledger.clone();
+                let ledgers = self.ledgers.clone();
                 let stopped = self.stopped.clone();
                 let server_handle = self.server_handle.clone();
-                // Every second start downloading new blocks, when that's done update the index
+                // Every ledger is synced by its own task, so that a slow or
+                // unreachable ledger does not hold up the others.
                 let join_handle = tokio::task::spawn(async move {
-                    let mut interval = interval(Duration::from_secs(1));
-                    let mut synced_at = std::time::Instant::now();
-                    while !stopped.load(Relaxed) {
-                        interval.tick().await;
-
-                        if let Err(err) = ledger.sync_blocks(stopped.clone()).await {
-                            let msg_403 = if mainnet
-                                && !not_whitelisted
-                                && err.is_internal_error_403()
-                            {
-                                ", You may not be whitelisted; please try running the Rosetta server again with the '--not_whitelisted' flag"
-                            } else {
-                                ""
-                            };
-                            error!("Error in syncing blocks{}: {:?}", msg_403, err);
-                            SYNC_ERR_COUNTER.inc();
-                            OUT_OF_SYNC_TIME
-                                .set(Instant::now().duration_since(synced_at).as_secs_f64());
-                        } else {
-                            let t = Instant::now().duration_since(synced_at).as_secs_f64();
-                            OUT_OF_SYNC_TIME.set(t);
-                            OUT_OF_SYNC_TIME_HIST.observe(t);
-                            synced_at = std::time::Instant::now();
+                    let sync_tasks: Vec<_> = ledgers
+                        .iter()
+                        .map(|ledger| {
+                            tokio::task::spawn(sync_ledger(
+                                ledger.clone(),
+                                stopped.clone(),
+                                exit_on_sync,
+                                mainnet && !not_whitelisted,
+                            ))
+                        })
+                        .collect();
+                    for sync_task in sync_tasks {
+                        if let Err(err) = sync_task.await {
+                            error!("Blockchain sync task failed: {}", err);
                         }
+                    }
 
-                        if exit_on_sync {
-                            info!("Blockchain synced, exiting");
-                            server_handle.stop(true).await;
-                            info!("Stopping blockchain sync thread");
-                            break;
-                        }
+                    if exit_on_sync {
+                        info!("Blockchain synced, exiting");
+                        server_handle.stop(true).await;
+                    }
+                    for ledger in ledgers.iter() {
+                        ledger.cleanup().await;
                     }
-                    ledger.cleanup().await;
                     info!("Blockchain sync thread finished");
                 });
 
@@ -473,6 +539,55 @@ impl RosettaApiServer {
     }
 }
 
+/// Every second starts downloading new blocks of `ledger`, until `stopped` is
+/// set. If `exit_on_sync` is set, the blocks are only synced once.
+/// `maybe_not_whitelisted` tells whether a 403 error may be caused by not
+/// being whitelisted.
+async fn sync_ledger(
+    ledger: Arc<dyn LedgerAccess + Send + Sync>,
+    stopped: Arc<AtomicBool>,
+    exit_on_sync: bool,
+    maybe_not_whitelisted: bool,
+) {
+    let ledger_canister_id = ledger.ledger_canister_id().to_string();
+    let out_of_sync_time = OUT_OF_SYNC_TIME.with_label_values(&[&ledger_canister_id]);
+    let out_of_sync_time_hist = OUT_OF_SYNC_TIME_HIST.with_label_values(&[&ledger_canister_id]);
+    let mut interval = interval(Duration::from_secs(1));
+    let mut synced_at = std::time::Instant::now();
+    while !stopped.load(Relaxed) {
+        interval.tick().await;
+
+        if let Err(err) = ledger.sync_blocks(stopped.clone()).await {
+            let msg_403 = if maybe_not_whitelisted && err.is_internal_error_403() {
+                ", You may not be whitelisted; please try running the Rosetta server again with the '--not_whitelisted' flag"
+            } else {
+                ""
+            };
+            error!(
+                "Error in syncing blocks of ledger {}{}: {:?}",
+                ledger.ledger_canister_id(),
+                msg_403,
+                err
+            );
+            SYNC_ERR_COUNTER.inc();
+            out_of_sync_time.set(Instant::now().duration_since(synced_at).as_secs_f64());
+        } else {
+            let t = Instant::now().duration_since(synced_at).as_secs_f64();
+            out_of_sync_time.set(t);
+            out_of_sync_time_hist.observe(t);
+            synced_at = std::time::Instant::now();
+        }
+
+        if exit_on_sync {
+            info!(
+                "Stopping blockchain sync of ledger {}",
+                ledger.ledger_canister_id()
+            );
+            break;
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct RosettaApiServerOpt {
     pub exit_on_sync: bool,
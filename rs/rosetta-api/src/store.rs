@@ -1,3 +1,5 @@
+pub mod snapshot;
+
 use log::debug;
 use std::convert::TryInto;
 use std::path::Path;
@@ -440,6 +442,19 @@ impl SQLiteStore {
         Ok(())
     }
 
+    /// Record `hb` as the oldest block of a store that is being imported and
+    /// was pruned at `hb`. The blocks between the genesis block and `hb` must
+    /// not be in the store.
+    pub(crate) fn import_oldest_block_snapshot(
+        &mut self,
+        hb: &HashedBlock,
+        balances: &BalanceBook,
+    ) -> Result<(), String> {
+        self.write_oldest_block_snapshot(hb, balances)?;
+        self.base_idx = hb.index;
+        Ok(())
+    }
+
     pub fn first_snapshot(&self) -> Option<(HashedBlock, BalanceBook)> {
         self.read_oldest_block_snapshot()
             .expect("Error while retrieving first snapshot.")
@@ -0,0 +1,300 @@
+//! Snapshots of the verified part of a block store.
+//!
+//! A snapshot contains everything needed to recreate a store without
+//! downloading the blockchain from the ledger: the genesis block, the blocks
+//! from the oldest block kept in the store up to the last verified one and the
+//! balances of the accounts. Snapshots of pruned stores also contain the
+//! balances at the oldest block kept, so that the imported store is pruned the
+//! same way. Those balances and the parent hash of the oldest block kept cannot
+//! be derived from the blocks of the snapshot, so they are trusted: pruned
+//! snapshots are only imported after their oldest block was checked against
+//! the ledger.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+use ic_ledger_core::block::BlockType;
+use ledger_canister::{AccountIdentifier, Block, BlockHeight, Tokens};
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::balance_book::BalanceBook;
+use crate::errors::ApiError;
+use crate::ledger_client::blocks::Blocks;
+use crate::store::{HashedBlock, SQLiteStore};
+
+/// The number of blocks read from or written to the store at once.
+const BLOCK_BATCH_LEN: u64 = 10_000;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SnapshotAccount {
+    pub account: AccountIdentifier,
+    pub tokens: Tokens,
+    pub num_pruned_transactions: usize,
+}
+
+/// The balances of all accounts after the block at `block_index`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SnapshotBalances {
+    pub block_index: BlockHeight,
+    pub token_pool: Tokens,
+    pub accounts: Vec<SnapshotAccount>,
+}
+
+impl SnapshotBalances {
+    fn from_balance_book(balance_book: &BalanceBook, block_index: BlockHeight) -> Self {
+        let mut token_pool = Tokens::MAX;
+        let mut accounts = vec![];
+        for (account, hist) in balance_book.store.acc_to_hist.iter() {
+            let tokens = hist.get_at(block_index).unwrap_or(Tokens::ZERO);
+            token_pool = (token_pool - tokens).expect("Token pool underflow");
+            accounts.push(SnapshotAccount {
+                account: *account,
+                tokens,
+                num_pruned_transactions: hist.num_pruned_transactions,
+            });
+        }
+        accounts.sort_by(|a, b| a.account.cmp(&b.account));
+        Self {
+            block_index,
+            token_pool,
+            accounts,
+        }
+    }
+
+    fn to_balance_book(&self) -> BalanceBook {
+        let mut balance_book = BalanceBook::default();
+        balance_book.token_pool = self.token_pool;
+        for acc in &self.accounts {
+            balance_book
+                .store
+                .insert(acc.account, self.block_index, acc.tokens);
+            balance_book
+                .store
+                .acc_to_hist
+                .get_mut(&acc.account)
+                .expect("Expected history for account.")
+                .num_pruned_transactions = acc.num_pruned_transactions;
+        }
+        balance_book
+    }
+
+    /// The non-zero balances, indexed by account.
+    fn non_zero(&self) -> HashMap<AccountIdentifier, Tokens> {
+        self.accounts
+            .iter()
+            .filter(|acc| acc.tokens != Tokens::ZERO)
+            .map(|acc| (acc.account, acc.tokens))
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct StoreSnapshot {
+    /// The genesis block, which is kept even in pruned stores.
+    pub genesis: HashedBlock,
+    /// The balances at the oldest block kept, if the store was pruned.
+    pub oldest_balances: Option<SnapshotBalances>,
+    /// The blocks following the genesis block (or starting with the oldest
+    /// block kept, if the store was pruned) up to the last verified block.
+    pub blocks: Vec<HashedBlock>,
+    /// The balances at the last verified block.
+    pub tip_balances: SnapshotBalances,
+}
+
+impl StoreSnapshot {
+    /// Take a snapshot of the verified blocks of `blocks`.
+    pub fn from_blocks(blocks: &Blocks) -> Result<Self, ApiError> {
+        let store = &blocks.block_store;
+        let last_verified = store
+            .last_verified()
+            .ok_or_else(|| ApiError::internal_error("The store has no verified blocks"))?;
+        let genesis = store.get_at(0)?;
+
+        // A balances snapshot at the genesis block means nothing was pruned.
+        let oldest_balances = store
+            .first_snapshot()
+            .filter(|(hb, _)| hb.index > 0)
+            .map(|(hb, balance_book)| SnapshotBalances::from_balance_book(&balance_book, hb.index));
+
+        let mut next_idx = oldest_balances.as_ref().map(|b| b.block_index).unwrap_or(1);
+        let mut snapshot_blocks = vec![];
+        while next_idx <= last_verified {
+            let end = (next_idx + BLOCK_BATCH_LEN).min(last_verified + 1);
+            let batch = store.get_range(next_idx..end)?;
+            if batch.len() as u64 != end - next_idx {
+                return Err(ApiError::internal_error(format!(
+                    "Blocks missing from the store between {} and {}",
+                    next_idx, end
+                )));
+            }
+            snapshot_blocks.extend(batch);
+            next_idx = end;
+        }
+
+        let tip_balances = SnapshotBalances::from_balance_book(&blocks.balance_book, last_verified);
+
+        Ok(Self {
+            genesis,
+            oldest_balances,
+            blocks: snapshot_blocks,
+            tip_balances,
+        })
+    }
+
+    pub fn write_to_file(&self, path: &Path) -> Result<(), ApiError> {
+        let file = File::create(path).map_err(|e| {
+            ApiError::internal_error(format!("Cannot create {}: {}", path.display(), e))
+        })?;
+        serde_cbor::to_writer(BufWriter::new(file), self).map_err(|e| {
+            ApiError::internal_error(format!(
+                "Cannot write snapshot to {}: {}",
+                path.display(),
+                e
+            ))
+        })
+    }
+
+    pub fn read_from_file(path: &Path) -> Result<Self, ApiError> {
+        let file = File::open(path).map_err(|e| {
+            ApiError::internal_error(format!("Cannot open {}: {}", path.display(), e))
+        })?;
+        serde_cbor::from_reader(BufReader::new(file)).map_err(|e| {
+            ApiError::internal_error(format!(
+                "Cannot read snapshot from {}: {}",
+                path.display(),
+                e
+            ))
+        })
+    }
+
+    /// The last block of the snapshot.
+    pub fn tip(&self) -> &HashedBlock {
+        self.blocks.last().unwrap_or(&self.genesis)
+    }
+
+    /// The oldest block kept in the store the snapshot was taken from, if it
+    /// was pruned.
+    pub fn oldest_kept_block(&self) -> Option<&HashedBlock> {
+        self.oldest_balances.as_ref().and(self.blocks.first())
+    }
+
+    /// Check that the blocks of the snapshot form a hash chain and that the
+    /// balances are consistent. For pruned snapshots, the chain starts at the
+    /// parent hash of the oldest block kept and the balances at that block are
+    /// taken as they are; see [`LedgerClient::verify_snapshot`].
+    ///
+    /// [`LedgerClient::verify_snapshot`]: crate::ledger_client::LedgerClient::verify_snapshot
+    pub fn verify(&self) -> Result<(), ApiError> {
+        let invalid = |msg: String| ApiError::internal_error(format!("Invalid snapshot: {}", msg));
+
+        if self.genesis.index != 0 || self.genesis.parent_hash.is_some() {
+            return Err(invalid(
+                "the first block is not a genesis block".to_string(),
+            ));
+        }
+        verify_block(&self.genesis).map_err(invalid)?;
+
+        let (mut expected_index, mut expected_parent_hash) = match &self.oldest_balances {
+            Some(oldest_balances) => {
+                SQLiteStore::sanity_check(&oldest_balances.to_balance_book()).map_err(invalid)?;
+                let first = self.blocks.first().ok_or_else(|| {
+                    invalid("the oldest block of a pruned store is missing".to_string())
+                })?;
+                if oldest_balances.block_index == 0 {
+                    return Err(invalid("the oldest block is the genesis block".to_string()));
+                }
+                (oldest_balances.block_index, first.parent_hash)
+            }
+            None => (1, Some(self.genesis.hash)),
+        };
+
+        for hb in &self.blocks {
+            if hb.index != expected_index {
+                return Err(invalid(format!(
+                    "expected block {}, got block {}",
+                    expected_index, hb.index
+                )));
+            }
+            if hb.parent_hash != expected_parent_hash {
+                return Err(invalid(format!(
+                    "parent hash mismatch at block {}",
+                    hb.index
+                )));
+            }
+            verify_block(hb).map_err(invalid)?;
+            expected_index += 1;
+            expected_parent_hash = Some(hb.hash);
+        }
+
+        if self.tip_balances.block_index != self.tip().index {
+            return Err(invalid(format!(
+                "the balances are at block {} but the last block is {}",
+                self.tip_balances.block_index,
+                self.tip().index
+            )));
+        }
+        SQLiteStore::sanity_check(&self.tip_balances.to_balance_book()).map_err(invalid)
+    }
+
+    /// Verify the snapshot and write it to the empty store of `blocks`. The
+    /// balances are then recomputed from the blocks and compared to the ones
+    /// of the snapshot.
+    pub fn import_into(&self, blocks: &mut Blocks) -> Result<(), ApiError> {
+        self.verify()?;
+
+        if blocks.block_store.first()?.is_some() {
+            return Err(ApiError::internal_error(
+                "Cannot import a snapshot into a non-empty store",
+            ));
+        }
+
+        info!(
+            "Importing snapshot with {} blocks, tip at {}",
+            self.blocks.len() + 1,
+            self.tip().index
+        );
+
+        blocks.block_store.push(self.genesis.clone())?;
+        for batch in self.blocks.chunks(BLOCK_BATCH_LEN as usize) {
+            blocks.block_store.push_batch(batch.to_vec())?;
+        }
+        if let Some(oldest_balances) = &self.oldest_balances {
+            let oldest_block = self.blocks.first().unwrap();
+            blocks
+                .block_store
+                .import_oldest_block_snapshot(oldest_block, &oldest_balances.to_balance_book())
+                .map_err(ApiError::internal_error)?;
+        }
+        blocks.block_store.mark_last_verified(self.tip().index)?;
+
+        blocks.load_from_store()?;
+
+        let computed = SnapshotBalances::from_balance_book(&blocks.balance_book, self.tip().index);
+        if computed.non_zero() != self.tip_balances.non_zero()
+            || computed.token_pool != self.tip_balances.token_pool
+        {
+            return Err(ApiError::internal_error(
+                "Invalid snapshot: the balances do not match the blocks",
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn verify_block(hb: &HashedBlock) -> Result<(), String> {
+    if hb.block.hash() != hb.hash {
+        return Err(format!("hash mismatch at block {}", hb.index));
+    }
+    let block = Block::decode(hb.block.clone())
+        .map_err(|e| format!("cannot decode block {}: {}", hb.index, e))?;
+    if block.parent_hash != hb.parent_hash {
+        return Err(format!(
+            "the parent hash of block {} does not match its content",
+            hb.index
+        ));
+    }
+    Ok(())
+}
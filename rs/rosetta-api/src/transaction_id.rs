@@ -73,7 +73,12 @@ impl TransactionIdentifier {
             | RequestType::Spawn { .. }
             | RequestType::MergeMaturity { .. }
             | RequestType::NeuronInfo { .. }
-            | RequestType::Follow { .. } => {
+            | RequestType::Follow { .. }
+            | RequestType::RegisterVote { .. }
+            | RequestType::MakeProposal { .. }
+            | RequestType::Split { .. }
+            | RequestType::Merge { .. }
+            | RequestType::ListNeurons => {
                 // Unfortunately, staking operations don't really have a transaction ID
                 Ok(TransactionIdentifier {
                     hash: NEURON_MANAGEMENT_PSEUDO_HASH.to_string(),
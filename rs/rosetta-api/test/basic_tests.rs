@@ -1,20 +1,24 @@
 use super::*;
 
 use ic_ledger_core::block::BlockType;
+use ic_nns_common::pb::v1::{NeuronId, ProposalId};
+use ic_nns_governance::pb::v1::{Proposal, ProposalRewardStatus, Tally, Topic};
 use ic_rosetta_api::convert::{block_id, from_hash, to_hash};
 use ic_rosetta_api::ledger_client::LedgerAccess;
 use ic_rosetta_api::models::amount::{tokens_to_amount, Amount};
-use ic_rosetta_api::request_handler::RosettaRequestHandler;
+use ic_rosetta_api::request_handler::{
+    NetworkRouter, RosettaRequestHandler, GET_PENDING_PROPOSALS, GET_PROPOSAL_INFO,
+};
 use ic_rosetta_api::transaction_id::TransactionIdentifier;
 use ic_rosetta_api::{models, API_VERSION, NODE_VERSION};
 
 use ic_rosetta_api::models::{
-    AccountBalanceResponse, BlockIdentifier, BlockRequest, BlockTransaction,
-    BlockTransactionRequest, ConstructionDeriveRequest, ConstructionDeriveResponse,
-    ConstructionMetadataRequest, ConstructionMetadataResponse, Currency, CurveType,
-    MempoolResponse, MempoolTransactionRequest, MetadataRequest, NetworkListResponse,
-    NetworkRequest, NetworkStatusResponse, SearchTransactionsRequest, SearchTransactionsResponse,
-    SyncStatus,
+    AccountBalanceResponse, AccountType, BlockIdentifier, BlockRequest, BlockTransaction,
+    BlockTransactionRequest, CallRequest, ConstructionDeriveRequest,
+    ConstructionDeriveRequestMetadata, ConstructionDeriveResponse, ConstructionMetadataRequest,
+    ConstructionMetadataResponse, Currency, CurveType, MempoolResponse, MempoolTransactionRequest,
+    MetadataRequest, NetworkListResponse, NetworkRequest, NetworkStatusResponse,
+    SearchTransactionsRequest, SearchTransactionsResponse, SyncStatus,
 };
 use std::sync::Arc;
 
@@ -148,6 +152,22 @@ async fn smoke_test() {
         .contains(&"FEE".to_string()));
     assert!(!network_options.allow.errors.is_empty());
     assert!(network_options.allow.historical_balance_lookup);
+    assert!(network_options
+        .allow
+        .call_methods
+        .contains(&"get_pending_proposals".to_string()));
+    assert!(network_options
+        .allow
+        .operation_types
+        .contains(&"REGISTER_VOTE".to_string()));
+
+    let msg = CallRequest::new(
+        req_handler.network_id(),
+        "not_a_method".to_string(),
+        Default::default(),
+    );
+    let res = req_handler.call(msg).await;
+    assert!(matches!(res, Err(ApiError::InvalidRequest(_, _))));
 
     let msg = NetworkRequest::new(req_handler.network_id());
     let res = req_handler.mempool(msg).await;
@@ -179,7 +199,7 @@ async fn smoke_test() {
             block_id(scribe.blockchain.back().unwrap()).unwrap(),
             vec![tokens_to_amount(
                 *scribe.balance_book.get(&acc_id(0)).unwrap(),
-                DEFAULT_TOKEN_SYMBOL
+                &Currency::default()
             )
             .unwrap()]
         ))
@@ -777,3 +797,190 @@ async fn store_batch_test() {
     blocks.block_store.mark_last_verified(last_idx).unwrap();
     verify_balances(&scribe, &blocks, 0);
 }
+
+#[actix_rt::test]
+async fn multiple_networks_test() {
+    init_test_logger();
+
+    let mut scribe = Scribe::new();
+    scribe.gen_accounts(2, 1_000_000);
+
+    let icp_ledger = Arc::new(TestLedger::new());
+    let token_ledger = Arc::new(TestLedger {
+        canister_id: CanisterId::from_u64(42),
+        governance_canister_id: None,
+        currency: Currency::new("TKN".to_string(), 4),
+        ..Default::default()
+    });
+    for b in &scribe.blockchain {
+        token_ledger.add_block(b.clone()).await.ok();
+    }
+
+    let icp_handler = RosettaRequestHandler::new_with_default_blockchain(icp_ledger);
+    let token_handler = RosettaRequestHandler::new_with_default_blockchain(token_ledger);
+    let router = NetworkRouter::new(vec![icp_handler.clone(), token_handler.clone()]);
+
+    let res = router.network_list(MetadataRequest::new());
+    assert_eq!(
+        res,
+        Ok(NetworkListResponse::new(vec![
+            icp_handler.network_id(),
+            token_handler.network_id()
+        ]))
+    );
+
+    let msg = AccountBalanceRequest::new(
+        token_handler.network_id(),
+        to_model_account_identifier(&acc_id(0)),
+    );
+    let res = router
+        .handler(&msg.network_identifier)
+        .unwrap()
+        .account_balance(msg)
+        .await
+        .unwrap();
+    assert_eq!(
+        res.balances,
+        vec![tokens_to_amount(
+            *scribe.balance_book.get(&acc_id(0)).unwrap(),
+            &Currency::new("TKN".to_string(), 4)
+        )
+        .unwrap()]
+    );
+
+    // Requests for an unknown network are rejected.
+    let mut unknown_network = token_handler.network_id();
+    unknown_network.network = hex::encode(CanisterId::from_u64(43).get().into_vec());
+    assert!(matches!(
+        router.handler(&unknown_network),
+        Err(ApiError::InvalidNetworkId(false, _))
+    ));
+
+    // Neuron management needs a governance canister.
+    let (_acc_id, _ed_kp, pk, _pid) = ic_rosetta_test_utils::make_user(4);
+    let mut msg = ConstructionDeriveRequest::new(token_handler.network_id(), pk);
+    msg.metadata = Some(ConstructionDeriveRequestMetadata {
+        account_type: AccountType::Neuron { neuron_index: 0 },
+    });
+    assert!(icp_handler
+        .construction_derive(ConstructionDeriveRequest {
+            network_identifier: icp_handler.network_id(),
+            ..msg.clone()
+        })
+        .is_ok());
+    assert_eq!(
+        token_handler.construction_derive(msg),
+        Err(ApiError::neuron_management_not_supported())
+    );
+}
+
+#[actix_rt::test]
+async fn call_test() {
+    init_test_logger();
+
+    let proposal = |id: u64, status: ProposalStatus| ProposalInfo {
+        id: Some(ProposalId { id }),
+        proposer: Some(NeuronId { id: 7 }),
+        proposal: Some(Proposal {
+            title: Some(format!("Motion {}", id)),
+            summary: "A motion".to_string(),
+            url: "https://forum.dfinity.org".to_string(),
+            action: None,
+        }),
+        proposal_timestamp_seconds: 1_000 + id,
+        latest_tally: Some(Tally {
+            timestamp_seconds: 2_000 + id,
+            yes: 10,
+            no: 2,
+            total: 20,
+        }),
+        topic: Topic::Governance as i32,
+        status: status as i32,
+        reward_status: ProposalRewardStatus::AcceptVotes as i32,
+        deadline_timestamp_seconds: Some(3_000 + id),
+        ..Default::default()
+    };
+    let ledger = Arc::new(TestLedger {
+        proposals: vec![
+            proposal(1, ProposalStatus::Open),
+            proposal(2, ProposalStatus::Executed),
+        ],
+        ..Default::default()
+    });
+    let req_handler = RosettaRequestHandler::new_with_default_blockchain(ledger);
+
+    let expected_open = serde_json::json!({
+        "proposal_id": 1,
+        "proposer": 7,
+        "title": "Motion 1",
+        "summary": "A motion",
+        "url": "https://forum.dfinity.org",
+        "topic": Topic::Governance as i32,
+        "status": ProposalStatus::Open as i32,
+        "reward_status": ProposalRewardStatus::AcceptVotes as i32,
+        "proposal_timestamp_seconds": 1_001,
+        "deadline_timestamp_seconds": 3_001,
+        "latest_tally": {
+            "timestamp_seconds": 2_001,
+            "yes": 10,
+            "no": 2,
+            "total": 20
+        }
+    });
+
+    let msg = CallRequest::new(
+        req_handler.network_id(),
+        GET_PENDING_PROPOSALS.to_string(),
+        Default::default(),
+    );
+    let res = req_handler.call(msg).await.unwrap();
+    assert!(!res.idempotent);
+    assert_eq!(
+        serde_json::Value::Object(res.result),
+        serde_json::json!({ "pending_proposals": [expected_open.clone()] })
+    );
+
+    let mut parameters = models::Object::new();
+    parameters.insert("proposal_id".to_string(), serde_json::json!(1));
+    let msg = CallRequest::new(
+        req_handler.network_id(),
+        GET_PROPOSAL_INFO.to_string(),
+        parameters,
+    );
+    let res = req_handler.call(msg).await.unwrap();
+    assert_eq!(serde_json::Value::Object(res.result), expected_open);
+
+    // Decided proposals can still be looked up by id.
+    let mut parameters = models::Object::new();
+    parameters.insert("proposal_id".to_string(), serde_json::json!(2));
+    let msg = CallRequest::new(
+        req_handler.network_id(),
+        GET_PROPOSAL_INFO.to_string(),
+        parameters,
+    );
+    let res = req_handler.call(msg).await.unwrap();
+    assert_eq!(
+        res.result.get("status"),
+        Some(&serde_json::json!(ProposalStatus::Executed as i32))
+    );
+
+    let mut parameters = models::Object::new();
+    parameters.insert("proposal_id".to_string(), serde_json::json!(3));
+    let msg = CallRequest::new(
+        req_handler.network_id(),
+        GET_PROPOSAL_INFO.to_string(),
+        parameters,
+    );
+    let res = req_handler.call(msg).await;
+    assert!(matches!(res, Err(ApiError::InvalidRequest(_, _))));
+
+    let mut parameters = models::Object::new();
+    parameters.insert("proposal_id".to_string(), serde_json::json!("1"));
+    let msg = CallRequest::new(
+        req_handler.network_id(),
+        GET_PROPOSAL_INFO.to_string(),
+        parameters,
+    );
+    let res = req_handler.call(msg).await;
+    assert!(matches!(res, Err(ApiError::InvalidRequest(_, _))));
+}
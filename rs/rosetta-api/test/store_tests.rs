@@ -1,4 +1,5 @@
 use super::*;
+use ic_rosetta_api::store::snapshot::StoreSnapshot;
 use ic_rosetta_api::store::{BlockStoreError, SQLiteStore};
 use std::path::Path;
 
@@ -126,6 +127,126 @@ async fn store_prune_and_load_test() {
     verify_balance_snapshot(&scribe, &mut store, 30);
 }
 
+#[actix_rt::test]
+async fn store_snapshot_test() {
+    init_test_logger();
+    let scribe = Scribe::new_with_sample_data(10, 100);
+    let last_idx = scribe.blockchain.back().unwrap().index;
+
+    let mut blocks = Blocks::new_in_memory();
+    for hb in &scribe.blockchain {
+        blocks.add_block(hb.clone()).unwrap();
+    }
+    blocks.block_store.mark_last_verified(last_idx).unwrap();
+
+    let tmpdir = create_tmp_dir();
+    let path = tmpdir.path().join("snapshot.cbor");
+    StoreSnapshot::from_blocks(&blocks)
+        .unwrap()
+        .write_to_file(&path)
+        .unwrap();
+    let snapshot = StoreSnapshot::read_from_file(&path).unwrap();
+    assert_eq!(snapshot, StoreSnapshot::from_blocks(&blocks).unwrap());
+    assert_eq!(snapshot.oldest_kept_block(), None);
+
+    let mut imported = Blocks::new_persistent(&tmpdir.path().join("store"));
+    snapshot.import_into(&mut imported).unwrap();
+    verify_imported(&scribe, &imported, 0);
+
+    // Importing into a non-empty store fails.
+    assert!(snapshot.import_into(&mut imported).is_err());
+
+    // The imported store survives a restart.
+    drop(imported);
+    let mut reloaded = Blocks::new_persistent(&tmpdir.path().join("store"));
+    reloaded.load_from_store().unwrap();
+    verify_imported(&scribe, &reloaded, 0);
+}
+
+#[actix_rt::test]
+async fn store_pruned_snapshot_test() {
+    init_test_logger();
+    let scribe = Scribe::new_with_sample_data(10, 100);
+    let last_idx = scribe.blockchain.back().unwrap().index;
+
+    let mut blocks = Blocks::new_in_memory();
+    for hb in &scribe.blockchain {
+        blocks.add_block(hb.clone()).unwrap();
+    }
+    blocks.block_store.mark_last_verified(last_idx).unwrap();
+    blocks.try_prune(&Some(last_idx - 20), 0).unwrap();
+
+    let snapshot = StoreSnapshot::from_blocks(&blocks).unwrap();
+    assert_eq!(
+        snapshot.oldest_balances.as_ref().map(|b| b.block_index),
+        Some(20)
+    );
+    // The oldest block kept is the one to check against the ledger.
+    assert_eq!(snapshot.oldest_kept_block(), scribe.blockchain.get(20));
+
+    let mut imported = Blocks::new_in_memory();
+    snapshot.import_into(&mut imported).unwrap();
+    assert_eq!(imported.block_store.first().unwrap().unwrap().index, 20);
+    assert_eq!(
+        imported.block_store.get_at(19).unwrap_err(),
+        BlockStoreError::NotAvailable(19)
+    );
+    verify_imported(&scribe, &imported, 20);
+}
+
+#[actix_rt::test]
+async fn store_corrupted_snapshot_test() {
+    init_test_logger();
+    let scribe = Scribe::new_with_sample_data(10, 100);
+    let last_idx = scribe.blockchain.back().unwrap().index;
+
+    let mut blocks = Blocks::new_in_memory();
+    for hb in &scribe.blockchain {
+        blocks.add_block(hb.clone()).unwrap();
+    }
+    blocks.block_store.mark_last_verified(last_idx).unwrap();
+    let snapshot = StoreSnapshot::from_blocks(&blocks).unwrap();
+    assert!(snapshot.verify().is_ok());
+
+    let mut missing_block = snapshot.clone();
+    missing_block.blocks.remove(10);
+    assert!(missing_block.verify().is_err());
+
+    let mut wrong_hash = snapshot.clone();
+    wrong_hash.blocks[10].hash = wrong_hash.blocks[11].hash;
+    assert!(wrong_hash.verify().is_err());
+
+    let mut wrong_balance = snapshot.clone();
+    wrong_balance.tip_balances.accounts[0].tokens += Tokens::from_e8s(1);
+    assert!(wrong_balance.verify().is_err());
+
+    // Consistent balances that do not match the blocks are only detected
+    // when the blocks are replayed.
+    let mut wrong_balance = snapshot;
+    let funded = wrong_balance
+        .tip_balances
+        .accounts
+        .iter()
+        .position(|acc| acc.tokens != Tokens::ZERO)
+        .unwrap();
+    let other = if funded == 0 { 1 } else { 0 };
+    wrong_balance.tip_balances.accounts[funded].tokens -= Tokens::from_e8s(1);
+    wrong_balance.tip_balances.accounts[other].tokens += Tokens::from_e8s(1);
+    assert!(wrong_balance.verify().is_ok());
+    assert!(wrong_balance
+        .import_into(&mut Blocks::new_in_memory())
+        .is_err());
+}
+
+fn verify_imported(scribe: &Scribe, blocks: &Blocks, first_idx: usize) {
+    for hb in scribe.blockchain.iter().skip(first_idx) {
+        assert_eq!(*hb, blocks.get_verified_at(hb.index).unwrap());
+        for (account, amount) in scribe.balance_history.get(hb.index as usize).unwrap() {
+            assert_eq!(blocks.get_balance(account, hb.index).unwrap(), *amount);
+        }
+    }
+}
+
 fn prune(scribe: &Scribe, store: &mut SQLiteStore, prune_at: u64) {
     let oldest_idx = prune_at;
     let oldest_block = scribe.blockchain.get(oldest_idx as usize).unwrap();
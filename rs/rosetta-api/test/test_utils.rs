@@ -5,7 +5,7 @@ mod store_tests;
 use ic_ledger_core::block::BlockType;
 use ic_rosetta_api::errors::ApiError;
 use ic_rosetta_api::models::{
-    AccountBalanceRequest, EnvelopePair, PartialBlockIdentifier, SignedTransaction,
+    AccountBalanceRequest, Currency, EnvelopePair, PartialBlockIdentifier, SignedTransaction,
 };
 use ic_rosetta_api::request_types::{RequestType, Status};
 use ledger_canister::{
@@ -23,7 +23,7 @@ use ic_rosetta_api::ledger_client::blocks::Blocks;
 use ic_rosetta_api::ledger_client::LedgerAccess;
 use ic_rosetta_api::request_handler::RosettaRequestHandler;
 use ic_rosetta_api::rosetta_server::RosettaApiServer;
-use ic_rosetta_api::{store::HashedBlock, DEFAULT_TOKEN_SYMBOL};
+use ic_rosetta_api::store::HashedBlock;
 use ic_types::{
     messages::{HttpCallContent, HttpCanisterUpdate},
     PrincipalId,
@@ -37,6 +37,7 @@ use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 
 use ic_nns_governance::pb::v1::manage_neuron::NeuronIdOrSubaccount;
+use ic_nns_governance::pb::v1::{ProposalInfo, ProposalStatus};
 use ic_rosetta_api::request::request_result::RequestResult;
 use ic_rosetta_api::request::transaction_results::TransactionResults;
 use ic_rosetta_api::request::Request;
@@ -61,9 +62,11 @@ fn create_tmp_dir() -> tempfile::TempDir {
 pub struct TestLedger {
     pub blockchain: RwLock<Blocks>,
     pub canister_id: CanisterId,
-    pub governance_canister_id: CanisterId,
+    pub governance_canister_id: Option<CanisterId>,
+    pub currency: Currency,
     pub submit_queue: RwLock<Vec<HashedBlock>>,
     pub transfer_fee: Tokens,
+    pub proposals: Vec<ProposalInfo>,
 }
 
 impl TestLedger {
@@ -74,9 +77,11 @@ impl TestLedger {
                 PrincipalId::from_str("5v3p4-iyaaa-aaaaa-qaaaa-cai").unwrap(),
             )
             .unwrap(),
-            governance_canister_id: ic_nns_constants::GOVERNANCE_CANISTER_ID,
+            governance_canister_id: Some(ic_nns_constants::GOVERNANCE_CANISTER_ID),
+            currency: Currency::default(),
             submit_queue: RwLock::new(Vec::new()),
             transfer_fee: DEFAULT_TRANSFER_FEE,
+            proposals: Vec::new(),
         }
     }
 
@@ -115,8 +120,8 @@ impl LedgerAccess for TestLedger {
 
     async fn cleanup(&self) {}
 
-    fn token_symbol(&self) -> &str {
-        DEFAULT_TOKEN_SYMBOL
+    fn currency(&self) -> &Currency {
+        &self.currency
     }
 
     async fn sync_blocks(&self, _stopped: Arc<AtomicBool>) -> Result<(), ApiError> {
@@ -139,8 +144,8 @@ impl LedgerAccess for TestLedger {
         &self.canister_id
     }
 
-    fn governance_canister_id(&self) -> &CanisterId {
-        &self.governance_canister_id
+    fn governance_canister_id(&self) -> Option<&CanisterId> {
+        self.governance_canister_id.as_ref()
     }
 
     async fn submit(&self, envelopes: SignedTransaction) -> Result<TransactionResults, ApiError> {
@@ -223,6 +228,23 @@ impl LedgerAccess for TestLedger {
             transfer_fee: self.transfer_fee,
         })
     }
+
+    async fn pending_proposals(&self) -> Result<Vec<ProposalInfo>, ApiError> {
+        Ok(self
+            .proposals
+            .iter()
+            .filter(|p| p.status() == ProposalStatus::Open)
+            .cloned()
+            .collect())
+    }
+
+    async fn proposal_info(&self, proposal_id: u64) -> Result<Option<ProposalInfo>, ApiError> {
+        Ok(self
+            .proposals
+            .iter()
+            .find(|p| p.id.as_ref().map(|id| id.id) == Some(proposal_id))
+            .cloned())
+    }
 }
 
 pub(crate) fn to_balances(
@@ -5,15 +5,16 @@ use ic_rosetta_api::convert::{
 
 use ic_rosetta_api::models::{
     ConstructionCombineResponse, ConstructionParseResponse, ConstructionPayloadsRequestMetadata,
-    ConstructionPayloadsResponse, CurveType, PublicKey, Signature, SignatureType,
+    ConstructionPayloadsResponse, Currency, CurveType, PublicKey, Signature, SignatureType,
 };
 use ic_rosetta_api::models::{ConstructionSubmitResponse, Error as RosettaError};
 use ic_rosetta_api::request_types::{
-    AddHotKey, Disburse, Follow, MergeMaturity, NeuronInfo, RemoveHotKey, SetDissolveTimestamp,
-    Spawn, Stake, StartDissolve, StopDissolve,
+    AddHotKey, Disburse, Follow, ListNeurons, MakeProposal, Merge, MergeMaturity, NeuronInfo,
+    RegisterVote, RemoveHotKey, SetDissolveTimestamp, Spawn, Split, Stake, StartDissolve,
+    StopDissolve,
 };
 use ic_rosetta_api::transaction_id::TransactionIdentifier;
-use ic_rosetta_api::{convert, errors, errors::ApiError, DEFAULT_TOKEN_SYMBOL};
+use ic_rosetta_api::{convert, errors, errors::ApiError};
 use ic_types::{messages::Blob, time, PrincipalId};
 
 use ledger_canister::{AccountIdentifier, BlockHeight, Operation, Tokens};
@@ -91,12 +92,12 @@ pub async fn prepare_multiple_txn(
     let mut all_sender_account_ids = Vec::new();
     let mut all_sender_pks = Vec::new();
     let mut trans_fee_amount = None;
-    let token_name = DEFAULT_TOKEN_SYMBOL;
+    let currency = &Currency::default();
 
     for request in requests {
         // first ask for the fee
         let mut fee_found = false;
-        for o in Request::requests_to_operations(&[request.request.clone()], token_name).unwrap() {
+        for o in Request::requests_to_operations(&[request.request.clone()], currency).unwrap() {
             if o._type == OperationType::Fee {
                 fee_found = true;
             } else {
@@ -107,7 +108,7 @@ pub async fn prepare_multiple_txn(
 
         match request.request.clone() {
             Request::Transfer(Operation::Transfer { from, fee, .. }) => {
-                trans_fee_amount = Some(tokens_to_amount(fee, token_name).unwrap());
+                trans_fee_amount = Some(tokens_to_amount(fee, currency).unwrap());
                 all_sender_account_ids.push(to_model_account_identifier(&from));
 
                 // just a sanity check
@@ -123,7 +124,12 @@ pub async fn prepare_multiple_txn(
             | Request::Spawn(Spawn { account, .. })
             | Request::MergeMaturity(MergeMaturity { account, .. })
             | Request::NeuronInfo(NeuronInfo { account, .. })
-            | Request::Follow(Follow { account, .. }) => {
+            | Request::Follow(Follow { account, .. })
+            | Request::RegisterVote(RegisterVote { account, .. })
+            | Request::MakeProposal(MakeProposal { account, .. })
+            | Request::Split(Split { account, .. })
+            | Request::Merge(Merge { account, .. })
+            | Request::ListNeurons(ListNeurons { account }) => {
                 all_sender_account_ids.push(to_model_account_identifier(&account));
             }
             Request::Transfer(Operation::Burn { .. }) => {
@@ -162,7 +168,7 @@ pub async fn prepare_multiple_txn(
     let fee_icpts = Tokens::from_e8s(
         dry_run_suggested_fee
             .clone()
-            .unwrap_or_else(|| tokens_to_amount(Tokens::default(), token_name).unwrap())
+            .unwrap_or_else(|| tokens_to_amount(Tokens::default(), currency).unwrap())
             .value
             .parse()
             .unwrap(),
@@ -171,7 +177,7 @@ pub async fn prepare_multiple_txn(
     if accept_suggested_fee {
         for o in &mut all_ops {
             if o._type == OperationType::Fee {
-                o.amount = Some(signed_amount(-(fee_icpts.get_e8s() as i128), token_name));
+                o.amount = Some(signed_amount(-(fee_icpts.get_e8s() as i128), currency));
             }
         }
     } else {
@@ -346,7 +352,7 @@ pub async fn do_multiple_txn(
         Ok((submit_res, charged_fee)) => {
             let results = convert::from_transaction_operation_results(
                 submit_res.metadata,
-                DEFAULT_TOKEN_SYMBOL,
+                &Currency::default(),
             )
             .expect("Couldn't convert metadata to TransactionResults");
             if let Some(RequestResult {
@@ -429,7 +435,7 @@ async fn do_multiple_txn_submit(
     // Verify consistency between requests and construction parse response.
     fn verify_operations(requests: &[RequestInfo], parse_response: ConstructionParseResponse) {
         let rs1: Vec<_> = requests.iter().map(|r| r.request.clone()).collect();
-        let rs2 = operations_to_requests(&parse_response.operations, false, DEFAULT_TOKEN_SYMBOL)
+        let rs2 = operations_to_requests(&parse_response.operations, false, &Currency::default())
             .unwrap();
         assert_eq!(rs1, rs2, "Requests differs: {:?} vs {:?}", rs1, rs2);
     }
@@ -559,7 +565,7 @@ pub async fn send_icpts_with_window(
                 ))
             } else {
                 Err(errors::convert_to_error(
-                    &convert::transaction_results_to_api_error(results, DEFAULT_TOKEN_SYMBOL),
+                    &convert::transaction_results_to_api_error(results, &Currency::default()),
                 ))
             }
         })
@@ -567,7 +573,7 @@ pub async fn send_icpts_with_window(
 
 pub fn assert_ic_error(err: &RosettaError, code: u32, ic_http_status: u64, text: &str) {
     let err = if let ApiError::OperationsErrors(results, _) =
-        errors::convert_to_api_error(err.clone(), DEFAULT_TOKEN_SYMBOL)
+        errors::convert_to_api_error(err.clone(), &Currency::default())
     {
         errors::convert_to_error(&results.error().unwrap().clone())
     } else {
@@ -595,7 +601,7 @@ pub fn assert_ic_error(err: &RosettaError, code: u32, ic_http_status: u64, text:
 
 pub fn assert_canister_error(err: &RosettaError, code: u32, text: &str) {
     let err = if let ApiError::OperationsErrors(results, _) =
-        errors::convert_to_api_error(err.clone(), DEFAULT_TOKEN_SYMBOL)
+        errors::convert_to_api_error(err.clone(), &Currency::default())
     {
         errors::convert_to_error(&results.error().unwrap().clone())
     } else {
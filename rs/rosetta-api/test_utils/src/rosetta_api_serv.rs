@@ -384,6 +384,21 @@ impl RosettaApiHandle {
         )
     }
 
+    pub async fn call(
+        &self,
+        method: &str,
+        parameters: Object,
+    ) -> Result<Result<CallResponse, RosettaError>, String> {
+        let req = CallRequest::new(self.network_id(), method.to_string(), parameters);
+        to_rosetta_response(
+            self.post_json_request(
+                &format!("http://{}/call", self.api_url),
+                serde_json::to_vec(&req).unwrap(),
+            )
+            .await,
+        )
+    }
+
     pub async fn account_balance_neuron(
         &self,
         acc: AccountIdentifier,
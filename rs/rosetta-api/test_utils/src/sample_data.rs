@@ -116,6 +116,7 @@ impl Scribe {
             operation: Operation::Mint { to: uid, amount },
             memo: self.next_message(),
             created_at_time: self.time().into(),
+            icrc1_memo: None,
         };
         self.balance_history.push_back(self.balance_book.clone());
         self.add_block(transaction);
@@ -129,6 +130,7 @@ impl Scribe {
             operation: Operation::Burn { from: uid, amount },
             memo: self.next_message(),
             created_at_time: self.time().into(),
+            icrc1_memo: None,
         };
         self.balance_history.push_back(self.balance_book.clone());
         self.add_block(transaction);
@@ -150,6 +152,7 @@ impl Scribe {
             },
             memo: self.next_message(),
             created_at_time: self.time().into(),
+            icrc1_memo: None,
         };
         self.balance_history.push_back(self.balance_book.clone());
         self.add_block(transaction);
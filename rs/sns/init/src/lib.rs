@@ -1,5 +1,7 @@
+use candid::CandidType;
 use ic_base_types::{CanisterId, PrincipalId};
 use ic_nervous_system_common::ledger::compute_neuron_staking_subaccount;
+use ic_nns_constants::{GOVERNANCE_CANISTER_ID, LEDGER_CANISTER_ID};
 use ic_sns_governance::init::GovernanceCanisterInitPayloadBuilder;
 use ic_sns_governance::pb::v1::neuron::DissolveState;
 use ic_sns_governance::pb::v1::{
@@ -7,6 +9,7 @@ use ic_sns_governance::pb::v1::{
     NeuronPermissionType,
 };
 use ic_sns_root::pb::v1::SnsRootCanister;
+use ic_sns_sale::pb::v1::Init as SaleInit;
 use ledger_canister::{
     AccountIdentifier, ArchiveOptions, LedgerCanisterInitPayload, Subaccount, Tokens,
 };
@@ -22,6 +25,16 @@ const MAX_TOKEN_SYMBOL_LENGTH: usize = 10;
 /// The maximum number of characters allowed for token name.
 const MAX_TOKEN_NAME_LENGTH: usize = 255;
 
+/// The minimum number of buyers of a token sale if none is given.
+const DEFAULT_MIN_PARTICIPANTS: u32 = 1;
+
+/// The minimum amount of ICP a buyer must contribute to a token sale if none
+/// is given (1 ICP).
+const DEFAULT_MIN_PARTICIPANT_ICP_E8S: u64 = 100_000_000;
+
+/// How long a token sale lasts if no end is given (one week).
+const DEFAULT_SALE_DURATION_SECONDS: u64 = 7 * 24 * 60 * 60;
+
 /// The canister IDs of all SNS canisters
 #[derive(Debug, Clone)]
 pub struct SnsCanisterIds {
@@ -103,13 +116,39 @@ impl SnsInitPayloadBuilder {
         self
     }
 
+    pub fn with_min_participants(&mut self, min_participants: Option<u32>) -> &mut Self {
+        self.sns_init_payload.min_participants = min_participants;
+        self
+    }
+
+    pub fn with_min_participant_icp_e8s(
+        &mut self,
+        min_participant_icp_e8s: Option<u64>,
+    ) -> &mut Self {
+        self.sns_init_payload.min_participant_icp_e8s = min_participant_icp_e8s;
+        self
+    }
+
+    pub fn with_target_icp_e8s(&mut self, target_icp_e8s: Option<u64>) -> &mut Self {
+        self.sns_init_payload.target_icp_e8s = target_icp_e8s;
+        self
+    }
+
+    pub fn with_token_sale_timestamp_seconds(
+        &mut self,
+        token_sale_timestamp_seconds: Option<u64>,
+    ) -> &mut Self {
+        self.sns_init_payload.token_sale_timestamp_seconds = token_sale_timestamp_seconds;
+        self
+    }
+
     pub fn build(&mut self) -> Result<SnsInitPayload, String> {
         self.sns_init_payload.validate()?;
         Ok(self.sns_init_payload.clone())
     }
 }
 
-#[derive(Clone, Default)]
+#[derive(CandidType, Clone, Debug, Default, Deserialize)]
 pub struct SnsInitPayload {
     /// The transaction fee that must be paid for ledger transactions (except
     /// minting and burning governance tokens), denominated in e8s (1 token = 100,000,000 e8s).
@@ -136,6 +175,22 @@ pub struct SnsInitPayload {
     /// The initial neurons that the SNS will be initialized with specified in the
     /// NeuronBlueprint form.
     pub initial_neurons: Vec<NeuronBlueprint>,
+
+    /// The minimum number of buyers that must participate in the token sale.
+    /// Defaults to 1.
+    pub min_participants: Option<u32>,
+
+    /// The minimum number of ICP e8s that each buyer must contribute to
+    /// participate in the token sale. Defaults to 1 ICP.
+    pub min_participant_icp_e8s: Option<u64>,
+
+    /// The number of ICP e8s targeted by the token sale. Must be at least
+    /// `min_participants * min_participant_icp_e8s`, which is also the default.
+    pub target_icp_e8s: Option<u64>,
+
+    /// The time (in seconds since the Unix epoch) when the token sale ends.
+    /// Defaults to one week after the SNS canister payloads are built.
+    pub token_sale_timestamp_seconds: Option<u64>,
 }
 
 impl SnsInitPayload {
@@ -144,17 +199,63 @@ impl SnsInitPayload {
     pub fn build_canister_payloads(
         &self,
         sns_canister_ids: &SnsCanisterIds,
+    ) -> Result<SnsCanisterInitPayloads, String> {
+        let now_seconds = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        self.build_canister_payloads_at(sns_canister_ids, now_seconds)
+    }
+
+    /// Like `build_canister_payloads`, but with the current time given in seconds since
+    /// the Unix epoch. This is for use in canisters, which can't access the system time.
+    pub fn build_canister_payloads_at(
+        &self,
+        sns_canister_ids: &SnsCanisterIds,
+        now_seconds: u64,
     ) -> Result<SnsCanisterInitPayloads, String> {
         self.validate()?;
         Ok(SnsCanisterInitPayloads {
-            governance: self.governance_init_args(sns_canister_ids),
+            governance: self.governance_init_args(sns_canister_ids, now_seconds),
             ledger: self.ledger_init_args(sns_canister_ids),
             root: self.root_init_args(sns_canister_ids),
         })
     }
 
+    /// Construct the params used to initialize a SNS Sale canister, with the current
+    /// time given in seconds since the Unix epoch.
+    pub fn sale_init_args(
+        &self,
+        sns_canister_ids: &SnsCanisterIds,
+        now_seconds: u64,
+    ) -> Result<SaleInit, String> {
+        self.validate()?;
+        let min_participants = self.min_participants.unwrap_or(DEFAULT_MIN_PARTICIPANTS);
+        let min_participant_icp_e8s = self
+            .min_participant_icp_e8s
+            .unwrap_or(DEFAULT_MIN_PARTICIPANT_ICP_E8S);
+        Ok(SaleInit {
+            nns_governance_canister_id: GOVERNANCE_CANISTER_ID.to_string(),
+            sns_governance_canister_id: sns_canister_ids.governance.to_string(),
+            sns_ledger_canister_id: sns_canister_ids.ledger.to_string(),
+            icp_ledger_canister_id: LEDGER_CANISTER_ID.to_string(),
+            target_icp_e8s: self
+                .target_icp_e8s
+                .unwrap_or_else(|| min_participants as u64 * min_participant_icp_e8s),
+            token_sale_timestamp_seconds: self
+                .token_sale_timestamp_seconds
+                .unwrap_or(now_seconds + DEFAULT_SALE_DURATION_SECONDS),
+            min_participants,
+            min_participant_icp_e8s,
+        })
+    }
+
     /// Construct the params used to initialize a SNS Governance canister.
-    fn governance_init_args(&self, sns_canister_ids: &SnsCanisterIds) -> Governance {
+    fn governance_init_args(
+        &self,
+        sns_canister_ids: &SnsCanisterIds,
+        now_seconds: u64,
+    ) -> Governance {
         let mut governance = GovernanceCanisterInitPayloadBuilder::new().build();
         governance.ledger_canister_id = Some(sns_canister_ids.ledger);
         governance.root_canister_id = Some(sns_canister_ids.root);
@@ -178,7 +279,7 @@ impl SnsInitPayload {
             parameters.reject_cost_e8s = Some(proposal_reject_cost_e8s);
         }
 
-        governance.neurons = self.get_initial_neurons(parameters);
+        governance.neurons = self.get_initial_neurons(parameters, now_seconds);
 
         governance
     }
@@ -252,11 +353,12 @@ impl SnsInitPayload {
     fn get_initial_neurons(
         &self,
         parameters: &NervousSystemParameters,
+        now_seconds: u64,
     ) -> BTreeMap<String, Neuron> {
         self.initial_neurons
             .iter()
             .map(|neuron_blueprint| {
-                let neuron = neuron_blueprint.as_neuron(parameters);
+                let neuron = neuron_blueprint.as_neuron(parameters, now_seconds);
                 (neuron.id.as_ref().unwrap().to_string(), neuron)
             })
             .collect()
@@ -282,12 +384,49 @@ impl SnsInitPayload {
             ));
         }
 
+        if self.min_participants == Some(0) {
+            return Err("Error: min-participants must be greater than zero".to_string());
+        }
+
+        if self.min_participant_icp_e8s == Some(0) {
+            return Err("Error: min-participant-icp-e8s must be greater than zero".to_string());
+        }
+
+        if let Some(target_icp_e8s) = self.target_icp_e8s {
+            let min_target_icp_e8s =
+                (self.min_participants.unwrap_or(DEFAULT_MIN_PARTICIPANTS) as u64).saturating_mul(
+                    self.min_participant_icp_e8s
+                        .unwrap_or(DEFAULT_MIN_PARTICIPANT_ICP_E8S),
+                );
+            if target_icp_e8s < min_target_icp_e8s {
+                return Err(format!(
+                    "Error: target-icp-e8s must be at least min-participants * \
+                    min-participant-icp-e8s ({}), given: {}",
+                    min_target_icp_e8s, target_icp_e8s
+                ));
+            }
+        }
+
+        // The initial neurons are validated against the parameters that
+        // governance will be initialized with, so that building the payloads
+        // does not fail afterwards.
+        let parameters = NervousSystemParameters::with_default_values();
+        let neuron_minimum_stake_e8s = self
+            .neuron_minimum_stake_e8s
+            .or(parameters.neuron_minimum_stake_e8s);
+        for neuron_blueprint in &self.initial_neurons {
+            neuron_blueprint.validate(
+                neuron_minimum_stake_e8s,
+                parameters.max_dissolve_delay_seconds,
+            )?;
+        }
+
         Ok(())
     }
 }
 
 /// Specifies the necessary info from which to create a Neuron
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(CandidType, Serialize, Deserialize, Debug, Clone)]
 pub struct NeuronBlueprint {
     controller: String,
     nonce: Option<u64>,
@@ -297,8 +436,45 @@ pub struct NeuronBlueprint {
 }
 
 impl NeuronBlueprint {
-    /// Build a `Neuron` from the blueprint
-    pub fn as_neuron(&self, parameters: &NervousSystemParameters) -> Neuron {
+    /// Validates that the blueprint can be turned into a neuron with the given parameters,
+    /// i.e. that `as_neuron` does not panic.
+    fn validate(
+        &self,
+        neuron_minimum_stake_e8s: Option<u64>,
+        max_dissolve_delay_seconds: Option<u64>,
+    ) -> Result<(), String> {
+        if PrincipalId::from_str(&self.controller).is_err() {
+            return Err(format!(
+                "Error: could not parse initial neuron controller {} as a PrincipalId",
+                self.controller
+            ));
+        }
+
+        if let Some(neuron_minimum_stake_e8s) = neuron_minimum_stake_e8s {
+            if self.stake_e8s < neuron_minimum_stake_e8s {
+                return Err(format!(
+                    "Error: an initial neuron has stake {}, which is less than \
+                    neuron-minimum-stake-e8s ({})",
+                    self.stake_e8s, neuron_minimum_stake_e8s
+                ));
+            }
+        }
+
+        if let Some(max_dissolve_delay_seconds) = max_dissolve_delay_seconds {
+            if self.dissolve_delay_seconds > max_dissolve_delay_seconds {
+                return Err(format!(
+                    "Error: an initial neuron has dissolve-delay-seconds {}, which is more \
+                    than the maximum dissolve delay ({})",
+                    self.dissolve_delay_seconds, max_dissolve_delay_seconds
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Build a `Neuron` from the blueprint, created at `now` (in seconds since the Unix epoch)
+    pub fn as_neuron(&self, parameters: &NervousSystemParameters, now: u64) -> Neuron {
         if let Some(neuron_minimum_stake_e8s) = parameters.neuron_minimum_stake_e8s {
             if self.stake_e8s < neuron_minimum_stake_e8s {
                 panic!(
@@ -329,11 +505,6 @@ impl NeuronBlueprint {
                 .clone(),
         };
 
-        let now = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-
         Neuron {
             id: Some(self.subaccount().into()),
             permissions: vec![permission],
@@ -374,8 +545,8 @@ impl NeuronBlueprint {
 #[cfg(test)]
 mod test {
     use crate::{
-        SnsCanisterIds, SnsInitPayload, SnsInitPayloadBuilder, MAX_TOKEN_NAME_LENGTH,
-        MAX_TOKEN_SYMBOL_LENGTH,
+        NeuronBlueprint, SnsCanisterIds, SnsInitPayload, SnsInitPayloadBuilder,
+        MAX_TOKEN_NAME_LENGTH, MAX_TOKEN_SYMBOL_LENGTH,
     };
     use ic_base_types::{CanisterId, PrincipalId};
     use ic_sns_governance::governance::ValidGovernanceProto;
@@ -404,6 +575,61 @@ mod test {
         assert!(sns_init_payload.validate().is_err());
         sns_init_payload.token_name = "SNS Core Developer Team Governance Token".to_string();
         assert!(sns_init_payload.validate().is_ok());
+
+        sns_init_payload.min_participants = Some(0);
+        assert!(sns_init_payload.validate().is_err());
+        sns_init_payload.min_participants = Some(10);
+        assert!(sns_init_payload.validate().is_ok());
+
+        sns_init_payload.min_participant_icp_e8s = Some(0);
+        assert!(sns_init_payload.validate().is_err());
+        sns_init_payload.min_participant_icp_e8s = Some(100_000_000);
+        assert!(sns_init_payload.validate().is_ok());
+
+        sns_init_payload.target_icp_e8s = Some(10 * 100_000_000 - 1);
+        assert!(sns_init_payload.validate().is_err());
+        sns_init_payload.target_icp_e8s = Some(10 * 100_000_000);
+        assert!(sns_init_payload.validate().is_ok());
+    }
+
+    #[test]
+    fn test_sns_init_payload_validate_initial_neurons() {
+        let mut sns_init_payload = SnsInitPayloadBuilder::new().build().unwrap();
+        sns_init_payload.neuron_minimum_stake_e8s = Some(100);
+        let neuron_blueprint = NeuronBlueprint {
+            controller: PrincipalId::new_user_test_id(1).to_string(),
+            nonce: None,
+            stake_e8s: 100,
+            age_seconds: None,
+            dissolve_delay_seconds: 0,
+        };
+        sns_init_payload.initial_neurons = vec![neuron_blueprint.clone()];
+        assert!(sns_init_payload.validate().is_ok());
+        assert!(sns_init_payload
+            .build_canister_payloads(&create_canister_ids())
+            .is_ok());
+
+        let invalid_neuron_blueprints = vec![
+            NeuronBlueprint {
+                controller: "not a principal".to_string(),
+                ..neuron_blueprint.clone()
+            },
+            NeuronBlueprint {
+                stake_e8s: 99,
+                ..neuron_blueprint.clone()
+            },
+            NeuronBlueprint {
+                dissolve_delay_seconds: u64::MAX,
+                ..neuron_blueprint
+            },
+        ];
+        for invalid_neuron_blueprint in invalid_neuron_blueprints {
+            sns_init_payload.initial_neurons = vec![invalid_neuron_blueprint];
+            assert!(sns_init_payload.validate().is_err());
+            assert!(sns_init_payload
+                .build_canister_payloads(&create_canister_ids())
+                .is_err());
+        }
     }
 
     #[test]
@@ -436,6 +662,10 @@ mod test {
             proposal_reject_cost_e8s: Some(10_000),
             neuron_minimum_stake_e8s: Some(100_000_000),
             initial_neurons: vec![],
+            min_participants: None,
+            min_participant_icp_e8s: None,
+            target_icp_e8s: None,
+            token_sale_timestamp_seconds: None,
         };
 
         // Create valid CanisterIds
@@ -577,4 +807,34 @@ mod test {
         );
         assert_eq!(ledger.transfer_fee, Some(Tokens::from_e8s(transaction_fee)));
     }
+
+    #[test]
+    fn test_sale_init_args_is_valid() {
+        let now_seconds = 1_700_000_000;
+        let sns_init_payload = SnsInitPayloadBuilder::new()
+            .with_min_participants(Some(5))
+            .build()
+            .expect("Expected SnsInitPayloadBuilder to produce a valid payload");
+
+        // Create valid CanisterIds
+        let sns_canister_ids = create_canister_ids();
+
+        let sale = sns_init_payload
+            .sale_init_args(&sns_canister_ids, now_seconds)
+            .expect("Expected SnsInitPayload to be a valid payload");
+
+        // Assert that the Sale canister would accept this init payload
+        assert!(sale.is_valid());
+        assert_eq!(
+            sale.sns_governance(),
+            CanisterId::new(sns_canister_ids.governance).unwrap()
+        );
+        assert_eq!(
+            sale.sns_ledger(),
+            CanisterId::new(sns_canister_ids.ledger).unwrap()
+        );
+        assert_eq!(sale.min_participants, 5);
+        assert_eq!(sale.target_icp_e8s, 5 * sale.min_participant_icp_e8s);
+        assert!(sale.token_sale_timestamp_seconds > now_seconds);
+    }
 }
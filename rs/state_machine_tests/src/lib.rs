@@ -1,61 +1,108 @@
+use bitcoin::hashes::Hash;
+use ic_btc_types_internal::{
+    BitcoinAdapterRequestWrapper, BitcoinAdapterResponse, BitcoinAdapterResponseWrapper,
+    GetSuccessorsResponse,
+};
 use ic_config::subnet_config::{SubnetConfig, SubnetConfigs};
+use ic_crypto::utils::ni_dkg::initial_ni_dkg_transcript_record_from_transcript;
+use ic_crypto_internal_threshold_sig_bls12381::api::{
+    combine_signatures, combined_public_key, keygen, public_key_to_der, sign_message,
+};
+use ic_crypto_internal_threshold_sig_bls12381::types::SecretKeyBytes;
+use ic_crypto_internal_threshold_sig_ecdsa::{
+    DerivationPath, EccCurveType, EccPoint, EccScalar, Seed, ThresholdEcdsaResult,
+};
+use ic_crypto_internal_types::sign::threshold_sig::ni_dkg::{
+    ni_dkg_groth20_bls12_381::{PublicCoefficientsBytes, Transcript},
+    CspNiDkgTranscript,
+};
+use ic_crypto_sha::Sha256;
+use ic_crypto_tree_hash::{flatmap, Label, LabeledTree, MixedHashTree};
 use ic_cycles_account_manager::CyclesAccountManager;
 pub use ic_error_types::{ErrorCode, UserError};
 use ic_execution_environment::ExecutionServices;
-use ic_ic00_types::{self as ic00, CanisterIdRecord, InstallCodeArgs, Method, Payload};
-pub use ic_ic00_types::{CanisterInstallMode, CanisterSettingsArgs};
+use ic_ic00_types::{
+    self as ic00, CanisterIdRecord, InstallCodeArgs, Method, Payload, SignWithECDSAReply,
+};
+pub use ic_ic00_types::{CanisterInstallMode, CanisterSettingsArgs, EcdsaKeyId};
 use ic_interfaces::{
     certification::{Verifier, VerifierError},
+    certified_stream_store::CertifiedStreamStore,
+    crypto::Signable,
     execution_environment::{IngressHistoryReader, QueryHandler},
-    messaging::MessageRouting,
+    messaging::{MessageRouting, XNetPayloadBuilder},
     registry::RegistryClient,
     validation::ValidationResult,
 };
-use ic_interfaces_state_manager::{CertificationScope, StateHashError, StateManager, StateReader};
+use ic_interfaces_state_manager::{
+    CertificationScope, CertifiedStateReader, StateHashError, StateManager, StateReader,
+};
 use ic_logger::ReplicaLogger;
 use ic_messaging::MessageRoutingImpl;
 use ic_metrics::MetricsRegistry;
 use ic_protobuf::registry::{
     provisional_whitelist::v1::ProvisionalWhitelist as PbProvisionalWhitelist,
     routing_table::v1::RoutingTable as PbRoutingTable,
+    subnet::v1::{CatchUpPackageContents, EcdsaConfig, SubnetListRecord},
 };
 use ic_protobuf::types::v1::PrincipalId as PrincipalIdIdProto;
 use ic_protobuf::types::v1::SubnetId as SubnetIdProto;
 use ic_registry_client_fake::FakeRegistryClient;
 use ic_registry_keys::{
-    make_provisional_whitelist_record_key, make_routing_table_record_key, ROOT_SUBNET_ID_KEY,
+    make_catch_up_package_contents_key, make_provisional_whitelist_record_key,
+    make_routing_table_record_key, make_subnet_list_record_key, make_subnet_record_key,
+    ROOT_SUBNET_ID_KEY,
 };
 use ic_registry_proto_data_provider::ProtoRegistryDataProvider;
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
 use ic_registry_routing_table::{
     routing_table_insert_subnet, CanisterIdRange, CanisterIdRanges, RoutingTable,
 };
+use ic_registry_subnet_features::DEFAULT_ECDSA_MAX_QUEUE_SIZE;
+pub use ic_registry_subnet_features::{BitcoinFeature, BitcoinFeatureStatus, SubnetFeatures};
 use ic_registry_subnet_type::SubnetType;
+pub use ic_replicated_state::metadata_state::subnet_call_context_manager::CanisterHttpRequestContext;
 use ic_replicated_state::ReplicatedState;
 use ic_state_manager::StateManagerImpl;
-use ic_test_utilities_registry::{
-    add_subnet_record, insert_initial_dkg_transcript, SubnetRecordBuilder,
-};
+use ic_test_utilities_registry::SubnetRecordBuilder;
 use ic_types::{
-    batch::{Batch, BatchPayload, IngressPayload},
-    consensus::certification::Certification,
+    batch::{
+        Batch, BatchPayload, IngressPayload, SelfValidatingPayload, ValidationContext, XNetPayload,
+    },
+    consensus::certification::CertificationContent,
+    crypto::{
+        canister_threshold_sig::{ExtendedDerivationPath, MasterEcdsaPublicKey},
+        threshold_sig::ni_dkg::{NiDkgId, NiDkgTag, NiDkgTargetSubnet, NiDkgTranscript},
+        AlgorithmId, CombinedThresholdSig, CombinedThresholdSigOf, Signed,
+    },
     messages::{
-        Blob, HttpCallContent, HttpCanisterUpdate, HttpRequestEnvelope, SignedIngress, UserQuery,
+        Blob, Certificate, HttpCallContent, HttpCanisterUpdate, HttpRequestEnvelope, Response,
+        SignedIngress, UserQuery,
     },
+    signature::ThresholdSignature,
     time::{current_time_and_expiry_time, UNIX_EPOCH},
-    Height, NodeId, Randomness, RegistryVersion,
+    Cycles, Height, NodeId, NumBytes, NumberOfNodes, Randomness, RegistryVersion,
 };
 pub use ic_types::{
+    canister_http::{CanisterHttpReject, CanisterHttpResponseContent},
+    consensus::certification::Certification,
     ingress::{IngressState, IngressStatus, WasmResult},
-    messages::MessageId,
+    messages::{CallbackId, MessageId},
     time::Time,
     CanisterId, CryptoHashOfState, PrincipalId, SubnetId, UserId,
 };
+use ic_xnet_payload_builder::{
+    certified_slice_pool::CertifiedSlicePool, RefillTaskHandle, XNetPayloadBuilderImpl,
+    XNetPayloadBuilderMetrics, POOL_SLICE_BYTE_SIZE_MAX,
+};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
 use std::convert::TryFrom;
 use std::fmt;
 use std::path::Path;
+use std::rc::{Rc, Weak};
 use std::string::ToString;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant, SystemTime};
 use tempfile::TempDir;
 use tokio::runtime::Runtime;
@@ -72,20 +119,29 @@ impl Verifier for FakeVerifier {
     }
 }
 
-/// Constructs the initial version of the registry containing a subnet with the
-/// specified SUBNET_ID, with the node with the specified NODE_ID assigned to
-/// it.
-fn make_single_node_registry(
-    subnet_id: SubnetId,
-    subnet_type: SubnetType,
-    node_id: NodeId,
+/// The maximum size of the XNet payload of a single block.
+const MAX_XNET_PAYLOAD_BYTES: u64 = 4 << 20;
+
+/// Constructs the initial version of the registry containing the specified
+/// subnets, each with a single node. The first subnet is the root subnet and
+/// every subnet is assigned a range of canister IDs in the routing table.
+///
+/// All subnets have the specified `features` and hold the specified threshold
+/// ECDSA keys.
+/// The initial DKG transcripts of every subnet record the public key that
+/// the state machine of that subnet uses to certify its states.
+fn make_registry(
+    subnets: &[(SubnetId, SubnetType, NodeId)],
+    features: SubnetFeatures,
+    ecdsa_keys: &[EcdsaKeyId],
 ) -> (Arc<ProtoRegistryDataProvider>, Arc<FakeRegistryClient>) {
     let registry_version = RegistryVersion::from(1);
     let data_provider = Arc::new(ProtoRegistryDataProvider::new());
 
+    let (root_subnet_id, _, _) = subnets[0];
     let root_subnet_id_proto = SubnetIdProto {
         principal_id: Some(PrincipalIdIdProto {
-            raw: subnet_id.get_ref().to_vec(),
+            raw: root_subnet_id.get_ref().to_vec(),
         }),
     };
     data_provider
@@ -97,7 +153,9 @@ fn make_single_node_registry(
         .unwrap();
 
     let mut routing_table = RoutingTable::new();
-    routing_table_insert_subnet(&mut routing_table, subnet_id).unwrap();
+    for (subnet_id, _, _) in subnets {
+        routing_table_insert_subnet(&mut routing_table, *subnet_id).unwrap();
+    }
     let pb_routing_table = PbRoutingTable::from(routing_table);
     data_provider
         .add(
@@ -115,28 +173,433 @@ fn make_single_node_registry(
         )
         .unwrap();
 
-    // Set subnetwork list(needed for filling network_topology.nns_subnet_id)
-    let record = SubnetRecordBuilder::from(&[node_id])
-        .with_subnet_type(subnet_type)
-        .build();
+    for (subnet_id, subnet_type, node_id) in subnets {
+        let mut record_builder = SubnetRecordBuilder::from(&[*node_id])
+            .with_subnet_type(*subnet_type)
+            .with_features(features.into());
+        if !ecdsa_keys.is_empty() {
+            record_builder = record_builder.with_ecdsa_config(EcdsaConfig {
+                quadruples_to_create_in_advance: 1,
+                key_ids: ecdsa_keys.iter().map(Into::into).collect(),
+                max_queue_size: DEFAULT_ECDSA_MAX_QUEUE_SIZE,
+            });
+        }
+        let record = record_builder.build();
+
+        let (public_coefficients, _) = subnet_threshold_key(*subnet_id);
+        let initial_transcript_record = |dkg_tag| {
+            let mut transcript = NiDkgTranscript::dummy_transcript_for_tests_with_params(
+                vec![*node_id],
+                dkg_tag,
+                1,
+                registry_version.get(),
+            );
+            transcript.internal_csp_transcript =
+                CspNiDkgTranscript::Groth20_Bls12_381(Transcript {
+                    public_coefficients: public_coefficients.clone(),
+                    receiver_data: BTreeMap::new(),
+                });
+            initial_ni_dkg_transcript_record_from_transcript(transcript)
+        };
+        let cup_contents = CatchUpPackageContents {
+            initial_ni_dkg_transcript_high_threshold: Some(initial_transcript_record(
+                NiDkgTag::HighThreshold,
+            )),
+            initial_ni_dkg_transcript_low_threshold: Some(initial_transcript_record(
+                NiDkgTag::LowThreshold,
+            )),
+            ..Default::default()
+        };
+        data_provider
+            .add(
+                &make_catch_up_package_contents_key(*subnet_id),
+                registry_version,
+                Some(cup_contents),
+            )
+            .unwrap();
+        data_provider
+            .add(
+                &make_subnet_record_key(*subnet_id),
+                registry_version,
+                Some(record),
+            )
+            .unwrap();
+    }
 
-    insert_initial_dkg_transcript(registry_version.get(), subnet_id, &record, &data_provider);
-    add_subnet_record(&data_provider, registry_version.get(), subnet_id, record);
+    // Set subnetwork list(needed for filling network_topology.nns_subnet_id)
+    let subnet_list_record = SubnetListRecord {
+        subnets: subnets
+            .iter()
+            .map(|(subnet_id, _, _)| subnet_id.get().into_vec())
+            .collect(),
+    };
+    data_provider
+        .add(
+            make_subnet_list_record_key().as_str(),
+            registry_version,
+            Some(subnet_list_record),
+        )
+        .unwrap();
 
     let registry_client = Arc::new(FakeRegistryClient::new(Arc::clone(&data_provider) as _));
     registry_client.update_to_latest_version();
     (data_provider, registry_client)
 }
 
+/// Generates the threshold BLS key of the single-node subnet with the specified
+/// ID. The key is derived from the subnet ID, so that it survives node
+/// restarts and matches the public key recorded in the registry.
+fn subnet_threshold_key(subnet_id: SubnetId) -> (PublicCoefficientsBytes, SecretKeyBytes) {
+    let seed = Sha256::hash(subnet_id.get_ref().as_slice());
+    let (public_coefficients, secret_keys) =
+        keygen(Randomness::from(seed), NumberOfNodes::new(1), &[true])
+            .expect("failed to generate the test subnet key");
+    let secret_key = secret_keys[0].expect("missing the secret key of the only node");
+    (public_coefficients, secret_key)
+}
+
+/// Signs `message_hash` with the key derived from `master_secret_key` along
+/// `derivation_path`, the same way threshold ECDSA does. Returns the signature
+/// as the concatenation of `r` and the normalized `s`.
+fn sign_with_derived_key(
+    master_secret_key: &EccScalar,
+    master_public_key: &MasterEcdsaPublicKey,
+    derivation_path: &ExtendedDerivationPath,
+    message_hash: &[u8],
+    nonce_seed: &[u8],
+) -> ThresholdEcdsaResult<Vec<u8>> {
+    let curve = EccCurveType::K256;
+    let master_public_key = EccPoint::deserialize(curve, &master_public_key.public_key)?;
+    let (key_tweak, _chain_key) =
+        DerivationPath::from(derivation_path).derive_tweak(&master_public_key)?;
+    let secret_key = master_secret_key.add(&key_tweak)?;
+
+    let k = EccScalar::from_seed(curve, Seed::from_bytes(nonce_seed))?;
+    let r = EccScalar::from_bytes_wide(curve, &EccPoint::mul_by_g(&k)?.affine_x()?.as_bytes())?;
+    let z = EccScalar::from_bytes_wide(curve, message_hash)?;
+    let s = k.invert()?.mul(&z.add(&r.mul(&secret_key)?)?)?;
+    let s = if s.is_high() { s.negate() } else { s };
+
+    let mut signature = r.serialize();
+    signature.extend_from_slice(&s.serialize());
+    Ok(signature)
+}
+
+/// Serializes `value` to CBOR, prefixed with the "self describing" tag.
+pub fn into_cbor<T: serde::Serialize>(value: &T) -> Vec<u8> {
+    let mut serializer = serde_cbor::Serializer::new(Vec::new());
+    serializer
+        .self_describe()
+        .expect("failed to write the self describing tag");
+    value
+        .serialize(&mut serializer)
+        .expect("failed to serialize to CBOR");
+    serializer.into_inner()
+}
+
+/// Converts a block of the `bitcoin` crate to the representation used by the
+/// Bitcoin adapter.
+fn from_btc_block(block: &bitcoin::Block) -> ic_btc_types_internal::Block {
+    use ic_btc_types_internal::{BlockHeader, OutPoint, Transaction, TxIn, TxOut};
+
+    ic_btc_types_internal::Block {
+        header: BlockHeader {
+            version: block.header.version,
+            prev_blockhash: block.header.prev_blockhash.to_vec(),
+            merkle_root: block.header.merkle_root.to_vec(),
+            time: block.header.time,
+            bits: block.header.bits,
+            nonce: block.header.nonce,
+        },
+        txdata: block
+            .txdata
+            .iter()
+            .map(|tx| Transaction {
+                version: tx.version,
+                lock_time: tx.lock_time,
+                input: tx
+                    .input
+                    .iter()
+                    .map(|x| TxIn {
+                        previous_output: OutPoint {
+                            txid: x.previous_output.txid.into_inner(),
+                            vout: x.previous_output.vout,
+                        },
+                        script_sig: x.script_sig.to_bytes(),
+                        sequence: x.sequence,
+                        witness: x
+                            .witness
+                            .to_vec()
+                            .into_iter()
+                            .map(serde_bytes::ByteBuf::from)
+                            .collect(),
+                    })
+                    .collect(),
+                output: tx
+                    .output
+                    .iter()
+                    .map(|x| TxOut {
+                        value: x.value,
+                        script_pubkey: x.script_pubkey.to_bytes(),
+                    })
+                    .collect(),
+            })
+            .collect(),
+    }
+}
+
+/// Configures and builds [StateMachine] instances.
+pub struct StateMachineBuilder {
+    state_dir: Option<TempDir>,
+    nonce: u64,
+    time: Time,
+    config: Option<SubnetConfig>,
+    subnet_type: SubnetType,
+    checkpoints_enabled: bool,
+    features: SubnetFeatures,
+    ecdsa_keys: Vec<EcdsaKeyId>,
+}
+
+impl Default for StateMachineBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StateMachineBuilder {
+    pub fn new() -> Self {
+        Self {
+            state_dir: None,
+            nonce: 0,
+            time: UNIX_EPOCH,
+            config: None,
+            subnet_type: SubnetType::System,
+            // Note: state machine tests are commonly used for testing
+            // canisters, such tests usually don't rely on any persistence.
+            checkpoints_enabled: false,
+            features: SubnetFeatures::default(),
+            ecdsa_keys: vec![],
+        }
+    }
+
+    fn with_state_dir(self, state_dir: TempDir) -> Self {
+        Self {
+            state_dir: Some(state_dir),
+            ..self
+        }
+    }
+
+    fn with_nonce(self, nonce: u64) -> Self {
+        Self { nonce, ..self }
+    }
+
+    /// Sets the time that the state machine starts with.
+    pub fn with_current_time(self, time: Time) -> Self {
+        Self { time, ..self }
+    }
+
+    /// Sets the configuration of the subnet(s).
+    pub fn with_config(self, config: Option<SubnetConfig>) -> Self {
+        Self { config, ..self }
+    }
+
+    /// Sets the type of the subnet(s), `System` by default.
+    pub fn with_subnet_type(self, subnet_type: SubnetType) -> Self {
+        Self {
+            subnet_type,
+            ..self
+        }
+    }
+
+    /// See [StateMachine::set_checkpoints_enabled].
+    pub fn with_checkpoints_enabled(self, checkpoints_enabled: bool) -> Self {
+        Self {
+            checkpoints_enabled,
+            ..self
+        }
+    }
+
+    /// Sets the features of the subnet(s), e.g. to enable canister HTTP
+    /// requests or the Bitcoin integration. All features are disabled by
+    /// default.
+    pub fn with_features(self, features: SubnetFeatures) -> Self {
+        Self { features, ..self }
+    }
+
+    /// Makes the subnet(s) hold the threshold ECDSA key with the specified ID
+    /// and enables the `ecdsa_signatures` feature. See
+    /// [StateMachine::ecdsa_subnet_public_key].
+    pub fn with_ecdsa_key(mut self, key_id: EcdsaKeyId) -> Self {
+        self.ecdsa_keys.push(key_id);
+        self.features.ecdsa_signatures = true;
+        self
+    }
+
+    /// Builds a state machine simulating a single subnet.
+    pub fn build(self) -> StateMachine {
+        let subnet_id = SubnetId::from(PrincipalId::new_subnet_test_id(1));
+        let node_id = NodeId::from(PrincipalId::new_node_test_id(1));
+        let (registry_data_provider, registry_client) = make_registry(
+            &[(subnet_id, self.subnet_type, node_id)],
+            self.features,
+            &self.ecdsa_keys,
+        );
+        StateMachine::setup(self, subnet_id, registry_data_provider, registry_client)
+    }
+
+    /// Builds `count` state machines simulating subnets that share a registry
+    /// (and hence a routing table). Each subnet inducts the certified streams
+    /// of the other subnets when it executes a round. See [Subnets].
+    pub fn build_subnets(self, count: usize) -> Subnets {
+        assert!(count > 0, "at least one subnet is required");
+        let specs: Vec<_> = (1..=count as u64)
+            .map(|i| {
+                (
+                    SubnetId::from(PrincipalId::new_subnet_test_id(i)),
+                    self.subnet_type,
+                    NodeId::from(PrincipalId::new_node_test_id(i)),
+                )
+            })
+            .collect();
+        let (registry_data_provider, registry_client) =
+            make_registry(&specs, self.features, &self.ecdsa_keys);
+
+        let subnets: BTreeMap<_, _> = specs
+            .iter()
+            .map(|(subnet_id, _, _)| {
+                let builder = StateMachineBuilder {
+                    state_dir: None,
+                    config: self.config.clone(),
+                    ecdsa_keys: self.ecdsa_keys.clone(),
+                    ..self
+                };
+                let sm = StateMachine::setup(
+                    builder,
+                    *subnet_id,
+                    Arc::clone(&registry_data_provider),
+                    Arc::clone(&registry_client),
+                );
+                (*subnet_id, Rc::new(sm))
+            })
+            .collect();
+
+        for sm in subnets.values() {
+            *sm.xnet_peers.borrow_mut() = subnets
+                .iter()
+                .filter(|(subnet_id, _)| **subnet_id != sm.subnet_id)
+                .map(|(subnet_id, peer)| (*subnet_id, Rc::downgrade(peer)))
+                .collect();
+        }
+
+        Subnets { subnets }
+    }
+}
+
+/// A set of subnets simulated by [StateMachine]s that share a registry. Each
+/// subnet inducts the certified streams of the other subnets when it executes
+/// a round, using the production `XNetPayloadBuilder` and message routing.
+///
+/// Message delivery between subnets only happens when the subnets are ticked,
+/// so the order in which subnets are ticked determines the order in which
+/// messages are delivered. Delivery from a given subnet can be delayed with
+/// [StateMachine::pause_xnet_from].
+pub struct Subnets {
+    subnets: BTreeMap<SubnetId, Rc<StateMachine>>,
+}
+
+impl Subnets {
+    /// Returns the IDs of all subnets, in ascending order. The first subnet
+    /// is the root subnet.
+    pub fn subnet_ids(&self) -> Vec<SubnetId> {
+        self.subnets.keys().cloned().collect()
+    }
+
+    /// Returns the state machine of the subnet with the specified ID.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if there is no such subnet.
+    pub fn subnet(&self, subnet_id: SubnetId) -> &StateMachine {
+        self.subnets
+            .get(&subnet_id)
+            .unwrap_or_else(|| panic!("no such subnet: {}", subnet_id))
+    }
+
+    /// Returns an iterator over the state machines of all subnets, in
+    /// ascending subnet ID order.
+    pub fn iter(&self) -> impl Iterator<Item = &StateMachine> {
+        self.subnets.values().map(|sm| sm.as_ref())
+    }
+
+    /// Executes one round on each subnet, in ascending subnet ID order.
+    pub fn tick_all(&self) {
+        self.iter().for_each(StateMachine::tick)
+    }
+
+    /// Executes one round on each of the specified subnets, in the specified
+    /// order.
+    pub fn tick_in_order(&self, order: &[SubnetId]) {
+        for subnet_id in order {
+            self.subnet(*subnet_id).tick()
+        }
+    }
+
+    /// Same as [StateMachine::await_ingress] for an ingress message sent to the
+    /// subnet with the specified ID, but executes a round on all subnets (see
+    /// [tick_all]) while waiting, so that the message can make inter-canister
+    /// calls to other subnets.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the result doesn't become available after the
+    /// specified number of rounds.
+    pub fn await_ingress(
+        &self,
+        subnet_id: SubnetId,
+        msg_id: MessageId,
+        max_ticks: usize,
+    ) -> Result<WasmResult, UserError> {
+        let sm = self.subnet(subnet_id);
+        for _tick in 0..max_ticks {
+            match sm.ingress_status(&msg_id) {
+                IngressStatus::Known {
+                    state: IngressState::Completed(result),
+                    ..
+                } => return Ok(result),
+                IngressStatus::Known {
+                    state: IngressState::Failed(error),
+                    ..
+                } => return Err(error),
+                _ => self.tick_all(),
+            }
+        }
+        panic!(
+            "Did not get answer to ingress {} on subnet {} after {} rounds",
+            msg_id, subnet_id, max_ticks
+        )
+    }
+}
+
 /// Represents a replicated state machine detached from the network layer that
 /// can be used to test this part of the stack in isolation.
 pub struct StateMachine {
+    subnet_id: SubnetId,
     registry_data_provider: Arc<ProtoRegistryDataProvider>,
     registry_client: Arc<FakeRegistryClient>,
     state_manager: Arc<StateManagerImpl>,
     message_routing: MessageRoutingImpl,
     ingress_history_reader: Box<dyn IngressHistoryReader>,
     query_handler: Arc<dyn QueryHandler<State = ReplicatedState>>,
+    xnet_payload_builder: XNetPayloadBuilderImpl,
+    xnet_slice_pool: Arc<Mutex<CertifiedSlicePool>>,
+    xnet_peers: RefCell<BTreeMap<SubnetId, Weak<StateMachine>>>,
+    xnet_paused: RefCell<BTreeSet<SubnetId>>,
+    secret_key: SecretKeyBytes,
+    public_key_der: Vec<u8>,
+    ecdsa_secret_key: EccScalar,
+    ecdsa_subnet_public_key: Option<MasterEcdsaPublicKey>,
+    canister_http_responses: RefCell<Vec<Response>>,
+    bitcoin_blocks: RefCell<Vec<ic_btc_types_internal::Block>>,
     _runtime: Runtime,
     state_dir: TempDir,
     checkpoints_enabled: std::cell::Cell<bool>,
@@ -153,6 +616,7 @@ impl Default for StateMachine {
 impl fmt::Debug for StateMachine {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("StateMachine")
+            .field("subnet_id", &self.subnet_id)
             .field("state_dir", &self.state_dir.path().display())
             .field("nonce", &self.nonce.get())
             .finish()
@@ -163,31 +627,21 @@ impl StateMachine {
     /// Constructs a new environment that uses a temporary directory for storing
     /// states.
     pub fn new() -> Self {
-        Self::setup_from_dir(
-            TempDir::new().expect("failed to create a temporary directory"),
-            0,
-            UNIX_EPOCH,
-            None,
-        )
+        StateMachineBuilder::new().build()
     }
 
     /// Constructs a new environment with the specified configuration.
     pub fn new_with_config(config: SubnetConfig) -> Self {
-        Self::setup_from_dir(
-            TempDir::new().expect("failed to create a temporary directory"),
-            0,
-            UNIX_EPOCH,
-            Some(config),
-        )
+        StateMachineBuilder::new().with_config(Some(config)).build()
     }
 
-    /// Constructs and initializes a new state machine that uses the specified
-    /// directory for storing states.
-    fn setup_from_dir(
-        state_dir: TempDir,
-        nonce: u64,
-        time: Time,
-        subnet_config: Option<SubnetConfig>,
+    /// Constructs and initializes a new state machine for the subnet with the
+    /// specified ID, as configured by `builder`.
+    fn setup(
+        builder: StateMachineBuilder,
+        subnet_id: SubnetId,
+        registry_data_provider: Arc<ProtoRegistryDataProvider>,
+        registry_client: Arc<FakeRegistryClient>,
     ) -> Self {
         use slog::Drain;
 
@@ -196,18 +650,18 @@ impl StateMachine {
         let logger = slog::Logger::root(drain, slog::o!());
         let replica_logger: ReplicaLogger = logger.into();
 
-        let subnet_id = SubnetId::from(PrincipalId::new_subnet_test_id(1));
-        let node_id = NodeId::from(PrincipalId::new_node_test_id(1));
+        let state_dir = match builder.state_dir {
+            Some(state_dir) => state_dir,
+            None => TempDir::new().expect("failed to create a temporary directory"),
+        };
         let metrics_registry = MetricsRegistry::new();
-        let subnet_type = SubnetType::System;
-        let subnet_config = match subnet_config {
+        let subnet_type = builder.subnet_type;
+        let ecdsa_signatures_enabled = builder.features.ecdsa_signatures;
+        let subnet_config = match builder.config {
             Some(subnet_config) => subnet_config,
             None => SubnetConfigs::default().own_subnet_config(subnet_type),
         };
 
-        let (registry_data_provider, registry_client) =
-            make_single_node_registry(subnet_id, subnet_type, node_id);
-
         let sm_config = ic_config::state_manager::Config::new(state_dir.path().to_path_buf());
         let hypervisor_config = ic_config::execution_environment::Config {
             canister_sandboxing_flag: ic_config::flag_status::FlagStatus::Disabled,
@@ -265,41 +719,100 @@ impl StateMachine {
             cycles_account_manager,
             subnet_id,
             &metrics_registry,
-            replica_logger,
+            replica_logger.clone(),
             Arc::clone(&registry_client) as _,
         );
 
+        // The slice pool is filled synchronously from the certified streams of
+        // the other subnets (see `refill_xnet_slice_pool()`), so there is no
+        // refill task listening on the other end of the channel.
+        let xnet_slice_pool = Arc::new(Mutex::new(CertifiedSlicePool::new(&metrics_registry)));
+        let (refill_trigger, _) = tokio::sync::mpsc::channel(1);
+        let xnet_payload_builder = XNetPayloadBuilderImpl::new_from_components(
+            Arc::clone(&state_manager) as _,
+            Arc::clone(&state_manager) as _,
+            Arc::clone(&registry_client) as _,
+            Arc::clone(&xnet_slice_pool),
+            RefillTaskHandle(Mutex::new(refill_trigger)),
+            Arc::new(XNetPayloadBuilderMetrics::new(&metrics_registry)),
+            replica_logger,
+        );
+
+        let (public_coefficients, secret_key) = subnet_threshold_key(subnet_id);
+        let public_key_der = combined_public_key(&public_coefficients)
+            .and_then(public_key_to_der)
+            .expect("failed to encode the test subnet public key");
+
+        // The test ECDSA key is derived from the subnet ID, so that it survives
+        // node restarts.
+        let ecdsa_secret_key = EccScalar::from_seed(
+            EccCurveType::K256,
+            Seed::from_bytes(subnet_id.get_ref().as_slice()),
+        )
+        .expect("failed to generate the test ECDSA key");
+        let ecdsa_subnet_public_key = if ecdsa_signatures_enabled {
+            Some(MasterEcdsaPublicKey {
+                algorithm_id: AlgorithmId::EcdsaSecp256k1,
+                public_key: EccPoint::mul_by_g(&ecdsa_secret_key)
+                    .expect("failed to compute the test ECDSA public key")
+                    .serialize(),
+            })
+        } else {
+            None
+        };
+
         Self {
+            subnet_id,
             registry_data_provider,
             registry_client,
             state_manager,
             ingress_history_reader: execution_services.ingress_history_reader,
             message_routing,
             query_handler: execution_services.sync_query_handler,
+            xnet_payload_builder,
+            xnet_slice_pool,
+            xnet_peers: RefCell::new(BTreeMap::new()),
+            xnet_paused: RefCell::new(BTreeSet::new()),
+            secret_key,
+            public_key_der,
+            ecdsa_secret_key,
+            ecdsa_subnet_public_key,
+            canister_http_responses: RefCell::new(vec![]),
+            bitcoin_blocks: RefCell::new(vec![]),
             _runtime: runtime,
             state_dir,
-            // Note: state machine tests are commonly used for testing
-            // canisters, such tests usually don't rely on any persistence.
-            checkpoints_enabled: std::cell::Cell::new(false),
-            nonce: std::cell::Cell::new(nonce),
-            time: std::cell::Cell::new(time),
+            checkpoints_enabled: std::cell::Cell::new(builder.checkpoints_enabled),
+            nonce: std::cell::Cell::new(builder.nonce),
+            time: std::cell::Cell::new(builder.time),
         }
     }
 
     /// Emulates a node restart, including checkpoint recovery.
+    ///
+    /// Only supported for state machines built with
+    /// [StateMachineBuilder::build].
     pub fn restart_node(self) -> Self {
-        Self::setup_from_dir(self.state_dir, self.nonce.get(), self.time.get(), None)
+        StateMachineBuilder::new()
+            .with_state_dir(self.state_dir)
+            .with_nonce(self.nonce.get())
+            .with_current_time(self.time.get())
+            .build()
     }
 
     /// Same as [restart_node], but the subnet will have the specified `config`
     /// after the restart.
     pub fn restart_node_with_config(self, config: SubnetConfig) -> Self {
-        Self::setup_from_dir(
-            self.state_dir,
-            self.nonce.get(),
-            self.time.get(),
-            Some(config),
-        )
+        StateMachineBuilder::new()
+            .with_state_dir(self.state_dir)
+            .with_nonce(self.nonce.get())
+            .with_current_time(self.time.get())
+            .with_config(Some(config))
+            .build()
+    }
+
+    /// Returns the ID of the subnet simulated by this state machine.
+    pub fn get_subnet_id(&self) -> SubnetId {
+        self.subnet_id
     }
 
     /// If the argument is true, the state machine will create an on-disk
@@ -312,14 +825,121 @@ impl StateMachine {
         self.checkpoints_enabled.set(enabled)
     }
 
+    /// Stops inducting messages and signals from the specified subnet, until
+    /// [resume_xnet_from] is called. Messages from that subnet are not lost,
+    /// they remain in its outgoing stream.
+    pub fn pause_xnet_from(&self, remote_subnet: SubnetId) {
+        self.xnet_paused.borrow_mut().insert(remote_subnet);
+    }
+
+    /// Resumes inducting messages and signals from the specified subnet.
+    pub fn resume_xnet_from(&self, remote_subnet: SubnetId) {
+        self.xnet_paused.borrow_mut().remove(&remote_subnet);
+    }
+
+    /// Returns the public key of the test threshold ECDSA key of this subnet,
+    /// if it holds one (see [StateMachineBuilder::with_ecdsa_key]).
+    ///
+    /// Requests to `sign_with_ecdsa` made in a round are answered in the next
+    /// round with a signature that verifies under the key derived from this
+    /// key, same as `ecdsa_public_key` computes it. Note that all key IDs are
+    /// backed by the same key.
+    pub fn ecdsa_subnet_public_key(&self) -> Option<MasterEcdsaPublicKey> {
+        self.ecdsa_subnet_public_key.clone()
+    }
+
+    /// Returns the canister HTTP requests that are waiting for a response,
+    /// keyed by the callback ID to respond to. See
+    /// [send_canister_http_response].
+    pub fn canister_http_request_contexts(
+        &self,
+    ) -> BTreeMap<CallbackId, CanisterHttpRequestContext> {
+        self.state_manager
+            .get_latest_state()
+            .take()
+            .metadata
+            .subnet_call_context_manager
+            .canister_http_request_contexts
+            .clone()
+    }
+
+    /// Responds to the pending canister HTTP request with the specified
+    /// callback ID. The response is delivered to the calling canister in the
+    /// next round.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if there is no such pending request.
+    pub fn send_canister_http_response(
+        &self,
+        callback_id: CallbackId,
+        content: CanisterHttpResponseContent,
+    ) {
+        let context = self
+            .canister_http_request_contexts()
+            .remove(&callback_id)
+            .unwrap_or_else(|| panic!("no pending canister HTTP request {}", callback_id));
+        let response_payload = match content {
+            CanisterHttpResponseContent::Success(data) => ic_types::messages::Payload::Data(data),
+            CanisterHttpResponseContent::Reject(reject) => {
+                ic_types::messages::Payload::Reject((&reject).into())
+            }
+        };
+        self.canister_http_responses.borrow_mut().push(Response {
+            originator: context.request.sender,
+            respondent: context.request.sender,
+            originator_reply_callback: callback_id,
+            refund: Cycles::from(0),
+            response_payload,
+            deadline: None,
+        });
+    }
+
+    /// Appends the specified blocks to the simulated Bitcoin network. The
+    /// blocks are delivered to the `BitcoinState` of this subnet in response
+    /// to the next request of the Bitcoin canister for successor blocks,
+    /// which requires the Bitcoin feature to be enabled (see
+    /// [StateMachineBuilder::with_features]).
+    ///
+    /// The blocks must extend the blocks pushed so far, or the genesis block
+    /// of the configured network.
+    pub fn push_bitcoin_blocks(&self, blocks: &[bitcoin::Block]) {
+        self.bitcoin_blocks
+            .borrow_mut()
+            .extend(blocks.iter().map(from_btc_block));
+    }
+
+    /// Returns the DER-encoded threshold public key of the subnet. For a
+    /// standalone state machine, this is the root key that verifies the
+    /// certificates returned by [StateMachine::read_certified_state].
+    pub fn public_key_der(&self) -> Vec<u8> {
+        self.public_key_der.clone()
+    }
+
+    /// Reads the parts of the latest certified state specified by `paths`, see
+    /// [CertifiedStateReader::read_certified_state] for details.
+    ///
+    /// Returns `None` if no state was certified yet.
+    pub fn read_certified_state(
+        &self,
+        paths: &LabeledTree<()>,
+    ) -> Option<(Arc<ReplicatedState>, MixedHashTree, Certification)> {
+        self.state_manager.read_certified_state(paths)
+    }
+
     /// Creates a new batch containing a single ingress message and sends it for
     /// processing to the replicated state machine.
-    fn send_signed_ingress(&self, msg: SignedIngress) {
+    ///
+    /// The signature of the message is not verified.
+    pub fn send_signed_ingress(&self, msg: SignedIngress) {
         self.execute_block_with_ingress_payload(IngressPayload::from(vec![msg]))
     }
 
     /// Triggers a single round of execution without any new inputs.  The state
     /// machine will invoke hearbeats and make progress on pending async calls.
+    ///
+    /// On a state machine that is part of [Subnets], the round also inducts
+    /// the messages and signals in the certified streams of the other subnets.
     pub fn tick(&self) {
         self.execute_block_with_ingress_payload(IngressPayload::default())
     }
@@ -331,18 +951,184 @@ impl StateMachine {
             requires_full_state_hash: self.checkpoints_enabled.get(),
             payload: BatchPayload {
                 ingress,
+                xnet: self.build_xnet_payload(),
+                self_validating: self.build_self_validating_payload(),
                 ..BatchPayload::default()
             },
             randomness: Randomness::from([0; 32]),
-            ecdsa_subnet_public_key: None,
+            ecdsa_subnet_public_key: self.ecdsa_subnet_public_key.clone(),
             registry_version: self.registry_client.get_latest_version(),
             time: self.time.get(),
-            consensus_responses: vec![],
+            consensus_responses: self.build_consensus_responses(),
         };
         self.message_routing
             .deliver_batch(batch)
             .expect("MR queue overflow");
         self.await_height(batch_number);
+        self.certify_latest_state();
+    }
+
+    /// Builds an `XNetPayload` from the certified streams of the other subnets
+    /// using the production `XNetPayloadBuilder`. Returns an empty payload for
+    /// a standalone subnet.
+    fn build_xnet_payload(&self) -> XNetPayload {
+        if self.xnet_peers.borrow().is_empty() {
+            return XNetPayload::default();
+        }
+
+        let validation_context = ValidationContext {
+            registry_version: self.registry_client.get_latest_version(),
+            certified_height: self.state_manager.latest_certified_height(),
+            time: self.time.get(),
+        };
+        let byte_limit = NumBytes::new(MAX_XNET_PAYLOAD_BYTES);
+
+        // The first call updates the stream positions of the slice pool, which
+        // determine where the pulled slices begin.
+        self.xnet_payload_builder
+            .get_xnet_payload(&validation_context, &[], byte_limit);
+        self.refill_xnet_slice_pool();
+        self.xnet_payload_builder
+            .get_xnet_payload(&validation_context, &[], byte_limit)
+    }
+
+    /// Pulls a certified slice of the stream to this subnet from each peer
+    /// subnet that is not paused, beginning at the stream position expected by
+    /// this subnet, and places it into the slice pool.
+    fn refill_xnet_slice_pool(&self) {
+        let peers = self.xnet_peers.borrow();
+        let paused = self.xnet_paused.borrow();
+        let mut pool = self.xnet_slice_pool.lock().unwrap();
+
+        let stream_positions: Vec<_> = pool
+            .peers()
+            .filter(|subnet_id| !paused.contains(subnet_id))
+            .filter_map(|subnet_id| {
+                pool.slice_stats(*subnet_id)
+                    .0
+                    .map(|stream_position| (*subnet_id, stream_position))
+            })
+            .collect();
+
+        for (subnet_id, stream_position) in stream_positions {
+            let remote = match peers.get(&subnet_id).and_then(Weak::upgrade) {
+                Some(remote) => remote,
+                // Own subnet (the loopback stream is inducted separately).
+                None => continue,
+            };
+            // Fails if there is no stream to this subnet (yet).
+            if let Ok(slice) = remote.state_manager.encode_certified_stream_slice(
+                self.subnet_id,
+                Some(stream_position.message_index),
+                Some(stream_position.message_index),
+                None,
+                Some(POOL_SLICE_BYTE_SIZE_MAX),
+            ) {
+                pool.put(subnet_id, slice)
+                    .expect("failed to pool a certified stream slice");
+            }
+        }
+    }
+
+    /// Builds the responses that consensus would deliver to the management
+    /// canister: signatures for all pending `sign_with_ecdsa` requests and the
+    /// canister HTTP responses supplied by the test.
+    fn build_consensus_responses(&self) -> Vec<Response> {
+        let mut responses = self.canister_http_responses.take();
+
+        if let Some(master_public_key) = &self.ecdsa_subnet_public_key {
+            let state = self.state_manager.get_latest_state().take();
+            let contexts = &state
+                .metadata
+                .subnet_call_context_manager
+                .sign_with_ecdsa_contexts;
+            for (callback_id, context) in contexts.iter() {
+                let signature = sign_with_derived_key(
+                    &self.ecdsa_secret_key,
+                    master_public_key,
+                    &ExtendedDerivationPath {
+                        caller: context.request.sender.get(),
+                        derivation_path: context.derivation_path.clone(),
+                    },
+                    &context.message_hash,
+                    &context.pseudo_random_id,
+                )
+                .expect("failed to compute a test ECDSA signature");
+                responses.push(Response {
+                    originator: context.request.sender,
+                    respondent: CanisterId::ic_00(),
+                    originator_reply_callback: *callback_id,
+                    refund: context.request.payment,
+                    response_payload: ic_types::messages::Payload::Data(
+                        SignWithECDSAReply { signature }.encode(),
+                    ),
+                    deadline: None,
+                });
+            }
+        }
+
+        responses
+    }
+
+    /// Builds the responses that the Bitcoin adapter would deliver: all blocks
+    /// pushed since the last response, in response to the pending request for
+    /// successor blocks, if any.
+    fn build_self_validating_payload(&self) -> SelfValidatingPayload {
+        let mut blocks = self.bitcoin_blocks.borrow_mut();
+        if blocks.is_empty() {
+            return SelfValidatingPayload::default();
+        }
+
+        let state = self.state_manager.get_latest_state().take();
+        let callback_id = state.bitcoin().adapter_requests_iter().find_map(
+            |(callback_id, request)| match request.request {
+                BitcoinAdapterRequestWrapper::GetSuccessorsRequest(_) => Some(*callback_id),
+                BitcoinAdapterRequestWrapper::SendTransactionRequest(_) => None,
+            },
+        );
+        match callback_id {
+            Some(callback_id) => SelfValidatingPayload::new(vec![BitcoinAdapterResponse {
+                response: BitcoinAdapterResponseWrapper::GetSuccessorsResponse(
+                    GetSuccessorsResponse {
+                        blocks: blocks.drain(..).collect(),
+                        next: vec![],
+                    },
+                ),
+                callback_id,
+            }]),
+            None => SelfValidatingPayload::default(),
+        }
+    }
+
+    /// Certifies all states awaiting certification by signing them with the
+    /// subnet key. This makes the streams of the latest state available to
+    /// other subnets and the certified state available to `read_state`.
+    fn certify_latest_state(&self) {
+        for (height, hash) in self.state_manager.list_state_hashes_to_certify() {
+            let content = CertificationContent::new(hash);
+            let signature = sign_message(&content.as_signed_bytes(), &self.secret_key)
+                .expect("failed to sign the state certification");
+            let signature = combine_signatures(&[Some(signature)], NumberOfNodes::new(1))
+                .expect("failed to combine the certification signature");
+            self.state_manager
+                .deliver_state_certification(Certification {
+                    height,
+                    signed: Signed {
+                        content,
+                        signature: ThresholdSignature {
+                            signer: NiDkgId {
+                                start_block_height: Height::from(0),
+                                dealer_subnet: self.subnet_id,
+                                dkg_tag: NiDkgTag::LowThreshold,
+                                target_subnet: NiDkgTargetSubnet::Local,
+                            },
+                            signature: CombinedThresholdSigOf::new(CombinedThresholdSig(
+                                signature.0.to_vec(),
+                            )),
+                        },
+                    },
+                });
+        }
     }
 
     fn await_height(&self, h: Height) {
@@ -643,17 +1429,55 @@ impl StateMachine {
         method: impl ToString,
         method_payload: Vec<u8>,
     ) -> Result<WasmResult, UserError> {
+        self.query_as(
+            PrincipalId::new_anonymous(),
+            receiver,
+            method,
+            method_payload,
+        )
+    }
+
+    /// Queries the canister with the specified ID on behalf of `sender`.
+    ///
+    /// The query is executed on the latest certified state, if any, and the
+    /// canister can access a certificate for its certified data.
+    pub fn query_as(
+        &self,
+        sender: PrincipalId,
+        receiver: CanisterId,
+        method: impl ToString,
+        method_payload: Vec<u8>,
+    ) -> Result<WasmResult, UserError> {
+        let path = LabeledTree::SubTree(flatmap! {
+            Label::from("canister") => LabeledTree::SubTree(flatmap! {
+                Label::from(receiver.get_ref()) => LabeledTree::SubTree(flatmap! {
+                    Label::from("certified_data") => LabeledTree::Leaf(())
+                })
+            }),
+            Label::from("time") => LabeledTree::Leaf(())
+        });
+        let (state, data_certificate) = match self.read_certified_state(&path) {
+            Some((state, tree, certification)) => (
+                state,
+                into_cbor(&Certificate {
+                    tree,
+                    signature: Blob(certification.signed.signature.signature.get().0),
+                    delegation: None,
+                }),
+            ),
+            None => (self.state_manager.get_latest_state().take(), Vec::new()),
+        };
         self.query_handler.query(
             UserQuery {
                 receiver,
-                source: UserId::from(PrincipalId::new_anonymous()),
+                source: UserId::from(sender),
                 method_name: method.to_string(),
                 method_payload,
                 ingress_expiry: 0,
                 nonce: None,
             },
-            self.state_manager.get_latest_state().take(),
-            Vec::new(),
+            state,
+            data_certificate,
         )
     }
 
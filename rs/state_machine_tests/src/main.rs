@@ -0,0 +1,638 @@
+//! Serves a [StateMachine] over the public HTTP interface of the Internet
+//! Computer, so that agents can talk to it as if it were a replica.
+//!
+//! Besides `/api/v2/status` and `/api/v2/canister/<id>/{call,query,read_state}`,
+//! the server provides the following endpoints for controlling the state
+//! machine:
+//!
+//! * `POST /state_machine/tick` executes a round.
+//! * `POST /state_machine/set_time` sets the time used for the next rounds.
+//!   The body holds the number of nanoseconds since the Unix epoch, as text.
+//! * `POST /state_machine/checkpoint` executes a round that checkpoints the
+//!   state and responds with the hex-encoded hash of that state.
+//!
+//! Usage:
+//!
+//! ic-test-state-machine --listen-addr 127.0.0.1:8080
+//!
+//! Certificates are signed with the key of the test subnet, which is reported
+//! as the root key by `/api/v2/status`. Request signatures are not verified,
+//! but requests are rejected if they are not addressed to the canister in the
+//! URL (or, for calls and queries, to the management canister).
+
+use clap::Parser;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{header, Body, HeaderMap, Method, Request, Response, Server, StatusCode};
+use ic_crypto_tree_hash::{sparse_labeled_tree_from_paths, Label, Path};
+use ic_error_types::RejectCode;
+use ic_state_machine_tests::{
+    into_cbor, CanisterId, IngressState, IngressStatus, StateMachine, StateMachineBuilder,
+    WasmResult,
+};
+use ic_types::messages::{
+    Blob, Certificate, HttpQueryContent, HttpQueryResponse, HttpQueryResponseReply,
+    HttpReadStateContent, HttpReadStateResponse, HttpRequest, HttpRequestEnvelope,
+    HttpStatusResponse, ReadState, ReplicaHealthStatus, SignedIngress, SignedRequestBytes,
+    UserQuery,
+};
+use serde::Serialize;
+use std::convert::{Infallible, TryFrom};
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+use tokio::sync::{mpsc, oneshot};
+
+/// The IC API version reported on status requests.
+const IC_API_VERSION: &str = "0.18.0";
+
+/// The maximum number of rounds executed on a call before responding, so that
+/// agents polling for the result do not need to drive the state machine.
+const MAX_TICKS_PER_CALL: usize = 100;
+
+/// A request for the thread that owns the state machine.
+type Job = Box<dyn FnOnce(&StateMachine, &Opts) + Send>;
+
+#[derive(Clone, Parser)]
+#[clap(version = "0.1.0", author = "DFINITY team <team@dfinity.org>")]
+struct Opts {
+    #[clap(
+        long,
+        help = "The address to serve the HTTP interface on.",
+        default_value = "127.0.0.1:8080"
+    )]
+    listen_addr: SocketAddr,
+
+    #[clap(long, help = "Whether every round checkpoints the state.")]
+    checkpoints_enabled: bool,
+}
+
+fn main() {
+    let opts: Opts = Opts::parse();
+
+    // The state machine is not thread-safe and blocks on its own runtime, so
+    // it lives on a dedicated thread that executes the requests one by one.
+    let (jobs_tx, mut jobs_rx) = mpsc::unbounded_channel::<Job>();
+    let state_machine_opts = opts.clone();
+    std::thread::spawn(move || {
+        let state_machine = StateMachineBuilder::new()
+            .with_checkpoints_enabled(state_machine_opts.checkpoints_enabled)
+            .build();
+        state_machine.set_time(SystemTime::now());
+        while let Some(job) = jobs_rx.blocking_recv() {
+            job(&state_machine, &state_machine_opts);
+        }
+    });
+
+    let runtime = tokio::runtime::Runtime::new().expect("failed to create a tokio runtime");
+    runtime.block_on(async move {
+        let make_service = make_service_fn(move |_| {
+            let jobs_tx = jobs_tx.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    handle_request(request, jobs_tx.clone())
+                }))
+            }
+        });
+        println!("Serving the state machine on http://{}", opts.listen_addr);
+        Server::bind(&opts.listen_addr)
+            .serve(make_service)
+            .await
+            .expect("failed to serve the HTTP interface");
+    });
+}
+
+/// Reads the body of `request` and routes it to the state machine thread.
+async fn handle_request(
+    request: Request<Body>,
+    jobs_tx: mpsc::UnboundedSender<Job>,
+) -> Result<Response<Body>, Infallible> {
+    let (parts, body) = request.into_parts();
+    let body = match hyper::body::to_bytes(body).await {
+        Ok(body) => body.to_vec(),
+        Err(err) => {
+            return Ok(make_plaintext_response(
+                StatusCode::BAD_REQUEST,
+                format!("Failed to read the request body: {}", err),
+            ))
+        }
+    };
+    let (response_tx, response_rx) = oneshot::channel();
+    jobs_tx
+        .send(Box::new(move |state_machine, opts| {
+            let response = route(state_machine, opts, &parts.method, parts.uri.path(), body);
+            let _ = response_tx.send(response);
+        }))
+        .expect("the state machine thread terminated");
+    Ok(response_rx
+        .await
+        .expect("the state machine thread dropped the request"))
+}
+
+fn route(
+    state_machine: &StateMachine,
+    opts: &Opts,
+    method: &Method,
+    path: &str,
+    body: Vec<u8>,
+) -> Response<Body> {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match (method, segments.as_slice()) {
+        (&Method::GET, ["api", "v2", "status"]) => status(state_machine),
+        (&Method::POST, ["api", "v2", "canister", canister_id, endpoint])
+            if matches!(*endpoint, "call" | "query" | "read_state") =>
+        {
+            let effective_canister_id = match CanisterId::from_str(canister_id) {
+                Ok(canister_id) => canister_id,
+                Err(err) => {
+                    return make_plaintext_response(
+                        StatusCode::BAD_REQUEST,
+                        format!("Invalid canister id {} in URL: {}", canister_id, err),
+                    )
+                }
+            };
+            match *endpoint {
+                "call" => call(state_machine, effective_canister_id, body),
+                "query" => query(state_machine, effective_canister_id, body),
+                _ => read_state(state_machine, effective_canister_id, body),
+            }
+        }
+        (&Method::POST, ["state_machine", "tick"]) => {
+            state_machine.tick();
+            make_plaintext_response(StatusCode::OK, String::new())
+        }
+        (&Method::POST, ["state_machine", "set_time"]) => set_time(state_machine, body),
+        (&Method::POST, ["state_machine", "checkpoint"]) => {
+            state_machine.set_checkpoints_enabled(true);
+            state_machine.tick();
+            let hash = state_machine.await_state_hash();
+            state_machine.set_checkpoints_enabled(opts.checkpoints_enabled);
+            make_plaintext_response(StatusCode::OK, hex::encode(hash.get().0))
+        }
+        _ => make_plaintext_response(
+            StatusCode::NOT_FOUND,
+            format!("No endpoint {} {}", method, path),
+        ),
+    }
+}
+
+fn status(state_machine: &StateMachine) -> Response<Body> {
+    cbor_response(&HttpStatusResponse {
+        ic_api_version: IC_API_VERSION.to_string(),
+        root_key: Some(Blob(state_machine.public_key_der())),
+        impl_version: None,
+        impl_hash: None,
+        replica_health_status: Some(ReplicaHealthStatus::Healthy),
+    })
+}
+
+/// Checks that a message to `receiver` may be sent to the URL of
+/// `effective_canister_id`. Messages to the management canister may be sent to
+/// the URL of any canister.
+fn check_receiver(
+    receiver: CanisterId,
+    effective_canister_id: CanisterId,
+) -> Result<(), Response<Body>> {
+    if receiver == effective_canister_id || receiver == CanisterId::ic_00() {
+        Ok(())
+    } else {
+        Err(make_plaintext_response(
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Specified CanisterId {} does not match effective canister id in URL {}",
+                receiver, effective_canister_id
+            ),
+        ))
+    }
+}
+
+/// Executes the ingress message in `body` and keeps executing rounds until
+/// its result is known, or the maximum number of rounds is reached.
+fn call(
+    state_machine: &StateMachine,
+    effective_canister_id: CanisterId,
+    body: Vec<u8>,
+) -> Response<Body> {
+    let msg = match SignedIngress::try_from(SignedRequestBytes::from(body)) {
+        Ok(msg) => msg,
+        Err(err) => {
+            return make_plaintext_response(
+                StatusCode::BAD_REQUEST,
+                format!("Could not parse body as call message: {}", err),
+            )
+        }
+    };
+    if let Err(response) = check_receiver(msg.canister_id(), effective_canister_id) {
+        return response;
+    }
+    let msg_id = msg.id();
+    state_machine.send_signed_ingress(msg);
+    for _ in 0..MAX_TICKS_PER_CALL {
+        match state_machine.ingress_status(&msg_id) {
+            IngressStatus::Known {
+                state: IngressState::Completed(_) | IngressState::Failed(_) | IngressState::Done,
+                ..
+            } => break,
+            _ => state_machine.tick(),
+        }
+    }
+    make_plaintext_response(StatusCode::ACCEPTED, String::new())
+}
+
+fn query(
+    state_machine: &StateMachine,
+    effective_canister_id: CanisterId,
+    body: Vec<u8>,
+) -> Response<Body> {
+    let query =
+        match <HttpRequestEnvelope<HttpQueryContent>>::try_from(&SignedRequestBytes::from(body))
+            .map_err(|err| format!("Could not parse body as read request: {}", err))
+            .and_then(|request| {
+                HttpRequest::<UserQuery>::try_from(request)
+                    .map_err(|err| format!("Malformed request: {:?}", err))
+            }) {
+            Ok(request) => request.take_content(),
+            Err(message) => return make_plaintext_response(StatusCode::BAD_REQUEST, message),
+        };
+    if let Err(response) = check_receiver(query.receiver, effective_canister_id) {
+        return response;
+    }
+    let response = match state_machine.query_as(
+        query.source.get(),
+        query.receiver,
+        query.method_name,
+        query.method_payload,
+    ) {
+        Ok(WasmResult::Reply(arg)) => HttpQueryResponse::Replied {
+            reply: HttpQueryResponseReply { arg: Blob(arg) },
+        },
+        Ok(WasmResult::Reject(message)) => HttpQueryResponse::Rejected {
+            reject_code: RejectCode::CanisterReject as u64,
+            reject_message: message,
+        },
+        Err(user_error) => HttpQueryResponse::Rejected {
+            reject_code: user_error.reject_code() as u64,
+            reject_message: user_error.to_string(),
+        },
+    };
+    cbor_response(&response)
+}
+
+/// Responds with a certificate for the paths in the read request in `body`.
+/// Paths below `/canister` may only refer to `effective_canister_id`.
+fn read_state(
+    state_machine: &StateMachine,
+    effective_canister_id: CanisterId,
+    body: Vec<u8>,
+) -> Response<Body> {
+    let read_state = match <HttpRequestEnvelope<HttpReadStateContent>>::try_from(
+        &SignedRequestBytes::from(body),
+    )
+    .map_err(|err| format!("Could not parse body as read request: {}", err))
+    .and_then(|request| {
+        HttpRequest::<ReadState>::try_from(request)
+            .map_err(|err| format!("Malformed request: {:?}", err))
+    }) {
+        Ok(request) => request.take_content(),
+        Err(message) => return make_plaintext_response(StatusCode::BAD_REQUEST, message),
+    };
+
+    let canister_label = Label::from("canister");
+    for path in read_state.paths.iter() {
+        if let [label, canister_id, ..] = path.as_slice() {
+            if *label == canister_label
+                && CanisterId::try_from(canister_id.as_bytes()) != Ok(effective_canister_id)
+            {
+                return make_plaintext_response(
+                    StatusCode::BAD_REQUEST,
+                    format!(
+                        "Effective canister id in URL {} does not match requested path {}",
+                        effective_canister_id, path
+                    ),
+                );
+            }
+        }
+    }
+
+    // Always add "time" to the paths even if not explicitly requested.
+    let mut paths = read_state.paths;
+    paths.push(Path::from(Label::from("time")));
+    let labeled_tree = sparse_labeled_tree_from_paths(&mut paths);
+
+    match state_machine.read_certified_state(&labeled_tree) {
+        Some((_state, tree, certification)) => cbor_response(&HttpReadStateResponse {
+            certificate: Blob(into_cbor(&Certificate {
+                tree,
+                signature: Blob(certification.signed.signature.signature.get().0),
+                delegation: None,
+            })),
+        }),
+        None => make_plaintext_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Certified state is not available yet. Please try again...".to_string(),
+        ),
+    }
+}
+
+fn set_time(state_machine: &StateMachine, body: Vec<u8>) -> Response<Body> {
+    let nanos = match String::from_utf8(body)
+        .map_err(|err| err.to_string())
+        .and_then(|body| body.trim().parse::<u64>().map_err(|err| err.to_string()))
+    {
+        Ok(nanos) => nanos,
+        Err(err) => {
+            return make_plaintext_response(
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "Could not parse body as nanoseconds since the epoch: {}",
+                    err
+                ),
+            )
+        }
+    };
+    state_machine.set_time(SystemTime::UNIX_EPOCH + Duration::from_nanos(nanos));
+    make_plaintext_response(StatusCode::OK, String::new())
+}
+
+/// Adds CORS headers allowing any origin to call the endpoints.
+fn get_cors_headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::ACCESS_CONTROL_ALLOW_METHODS,
+        header::HeaderValue::from_static("POST, GET"),
+    );
+    headers.insert(
+        header::ACCESS_CONTROL_ALLOW_ORIGIN,
+        header::HeaderValue::from_static("*"),
+    );
+    headers.insert(
+        header::ACCESS_CONTROL_ALLOW_HEADERS,
+        header::HeaderValue::from_static("Accept, Authorization, Content-Type"),
+    );
+    headers
+}
+
+fn make_plaintext_response(status: StatusCode, message: String) -> Response<Body> {
+    let mut response = Response::new(Body::from(message));
+    *response.status_mut() = status;
+    *response.headers_mut() = get_cors_headers();
+    response
+}
+
+/// Writes the "self describing" CBOR tag and serializes the response.
+fn cbor_response<R: Serialize>(r: &R) -> Response<Body> {
+    let mut response = Response::new(Body::from(into_cbor(r)));
+    *response.status_mut() = StatusCode::OK;
+    *response.headers_mut() = get_cors_headers();
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        header::HeaderValue::from_static("application/cbor"),
+    );
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_state_machine_tests::PrincipalId;
+    use ic_types::messages::{
+        HttpCallContent, HttpCanisterUpdate, HttpReadState, HttpUserQuery, MessageId,
+    };
+
+    /// A canister that replies "hi" to its update "hi" and its query
+    /// "hi_query".
+    const HI_CANISTER: &str = r#"
+        (module
+          (import "ic0" "msg_reply" (func $msg_reply))
+          (import "ic0" "msg_reply_data_append" (func $msg_reply_data_append (param i32 i32)))
+          (func $hi
+            (call $msg_reply_data_append (i32.const 0) (i32.const 2))
+            (call $msg_reply))
+          (memory 1)
+          (data (i32.const 0) "hi")
+          (export "canister_update hi" (func $hi))
+          (export "canister_query hi_query" (func $hi)))"#;
+
+    fn opts() -> Opts {
+        Opts {
+            listen_addr: "127.0.0.1:8080".parse().unwrap(),
+            checkpoints_enabled: false,
+        }
+    }
+
+    fn envelope<C>(content: C) -> HttpRequestEnvelope<C> {
+        HttpRequestEnvelope {
+            content,
+            sender_pubkey: None,
+            sender_sig: None,
+            sender_delegation: None,
+        }
+    }
+
+    fn call_body(canister_id: CanisterId, method: &str) -> (MessageId, Vec<u8>) {
+        let envelope = envelope(HttpCallContent::Call {
+            update: HttpCanisterUpdate {
+                canister_id: Blob(canister_id.get().into_vec()),
+                method_name: method.to_string(),
+                arg: Blob(vec![]),
+                sender: Blob(PrincipalId::new_anonymous().into_vec()),
+                ingress_expiry: u64::MAX,
+                nonce: None,
+            },
+        });
+        let HttpCallContent::Call { update } = &envelope.content;
+        (update.id(), into_cbor(&envelope))
+    }
+
+    fn query_body(canister_id: CanisterId, method: &str) -> Vec<u8> {
+        into_cbor(&envelope(HttpQueryContent::Query {
+            query: HttpUserQuery {
+                canister_id: Blob(canister_id.get().into_vec()),
+                method_name: method.to_string(),
+                arg: Blob(vec![]),
+                sender: Blob(PrincipalId::new_anonymous().into_vec()),
+                ingress_expiry: u64::MAX,
+                nonce: None,
+            },
+        }))
+    }
+
+    fn read_state_body(paths: Vec<Path>) -> Vec<u8> {
+        into_cbor(&envelope(HttpReadStateContent::ReadState {
+            read_state: HttpReadState {
+                sender: Blob(PrincipalId::new_anonymous().into_vec()),
+                paths,
+                nonce: None,
+                ingress_expiry: u64::MAX,
+            },
+        }))
+    }
+
+    fn post(state_machine: &StateMachine, path: &str, body: Vec<u8>) -> Response<Body> {
+        route(state_machine, &opts(), &Method::POST, path, body)
+    }
+
+    fn body_bytes(response: Response<Body>) -> Vec<u8> {
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(hyper::body::to_bytes(response.into_body()))
+            .unwrap()
+            .to_vec()
+    }
+
+    fn body_text(response: Response<Body>) -> String {
+        String::from_utf8(body_bytes(response)).unwrap()
+    }
+
+    #[test]
+    fn test_status_reports_the_root_key() {
+        let state_machine = StateMachine::new();
+        let response = route(
+            &state_machine,
+            &opts(),
+            &Method::GET,
+            "/api/v2/status",
+            vec![],
+        );
+        assert_eq!(response.status(), StatusCode::OK);
+        let status: HttpStatusResponse = serde_cbor::from_slice(&body_bytes(response)).unwrap();
+        assert_eq!(status.root_key, Some(Blob(state_machine.public_key_der())));
+    }
+
+    #[test]
+    fn test_call_is_executed_on_the_canister_in_the_url() {
+        let state_machine = StateMachine::new();
+        let canister_id = state_machine.install_canister_wat(HI_CANISTER, vec![], None);
+
+        let (msg_id, body) = call_body(canister_id, "hi");
+        let response = post(
+            &state_machine,
+            &format!("/api/v2/canister/{}/call", canister_id),
+            body,
+        );
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        assert_eq!(
+            state_machine.await_ingress(msg_id, 1),
+            Ok(WasmResult::Reply(b"hi".to_vec()))
+        );
+    }
+
+    #[test]
+    fn test_call_to_another_canister_is_rejected() {
+        let state_machine = StateMachine::new();
+        let canister_id = state_machine.install_canister_wat(HI_CANISTER, vec![], None);
+        let other_canister_id = state_machine.install_canister_wat(HI_CANISTER, vec![], None);
+
+        let (msg_id, body) = call_body(canister_id, "hi");
+        let response = post(
+            &state_machine,
+            &format!("/api/v2/canister/{}/call", other_canister_id),
+            body,
+        );
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(body_text(response).contains("does not match effective canister id"));
+        assert_eq!(
+            state_machine.ingress_status(&msg_id),
+            IngressStatus::Unknown
+        );
+
+        let (_, body) = call_body(canister_id, "hi");
+        let response = post(&state_machine, "/api/v2/canister/not-an-id/call", body);
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(body_text(response).starts_with("Invalid canister id not-an-id in URL"));
+    }
+
+    #[test]
+    fn test_query_is_answered_by_the_canister_in_the_url() {
+        let state_machine = StateMachine::new();
+        let canister_id = state_machine.install_canister_wat(HI_CANISTER, vec![], None);
+        let other_canister_id = state_machine.install_canister_wat(HI_CANISTER, vec![], None);
+        let url = format!("/api/v2/canister/{}/query", canister_id);
+
+        let response = post(&state_machine, &url, query_body(canister_id, "hi_query"));
+        assert_eq!(response.status(), StatusCode::OK);
+        let reply: HttpQueryResponse = serde_cbor::from_slice(&body_bytes(response)).unwrap();
+        assert_eq!(
+            reply,
+            HttpQueryResponse::Replied {
+                reply: HttpQueryResponseReply {
+                    arg: Blob(b"hi".to_vec())
+                }
+            }
+        );
+
+        let response = post(&state_machine, &url, query_body(canister_id, "unknown"));
+        assert_eq!(response.status(), StatusCode::OK);
+        let reply: HttpQueryResponse = serde_cbor::from_slice(&body_bytes(response)).unwrap();
+        assert!(matches!(reply, HttpQueryResponse::Rejected { .. }));
+
+        let response = post(
+            &state_machine,
+            &url,
+            query_body(other_canister_id, "hi_query"),
+        );
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = post(&state_machine, &url, b"not cbor".to_vec());
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_read_state_only_covers_the_canister_in_the_url() {
+        let state_machine = StateMachine::new();
+        let canister_id = state_machine.install_canister_wat(HI_CANISTER, vec![], None);
+        let other_canister_id = state_machine.install_canister_wat(HI_CANISTER, vec![], None);
+        let url = format!("/api/v2/canister/{}/read_state", canister_id);
+        let module_hash_path = |canister_id: CanisterId| {
+            Path::new(vec![
+                Label::from("canister"),
+                Label::from(canister_id.get().into_vec()),
+                Label::from("module_hash"),
+            ])
+        };
+
+        let response = post(
+            &state_machine,
+            &url,
+            read_state_body(vec![module_hash_path(canister_id)]),
+        );
+        assert_eq!(response.status(), StatusCode::OK);
+        let response: HttpReadStateResponse =
+            serde_cbor::from_slice(&body_bytes(response)).unwrap();
+        let certificate: Certificate = serde_cbor::from_slice(&response.certificate.0).unwrap();
+        assert!(!certificate.signature.0.is_empty());
+
+        let response = post(
+            &state_machine,
+            &url,
+            read_state_body(vec![
+                module_hash_path(canister_id),
+                module_hash_path(other_canister_id),
+            ]),
+        );
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(body_text(response).starts_with("Effective canister id in URL"));
+    }
+
+    #[test]
+    fn test_unknown_endpoints_and_malformed_control_requests() {
+        let state_machine = StateMachine::new();
+        let canister_id = state_machine.install_canister_wat(HI_CANISTER, vec![], None);
+
+        let response = post(
+            &state_machine,
+            &format!("/api/v2/canister/{}/unknown", canister_id),
+            vec![],
+        );
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = post(&state_machine, "/state_machine/set_time", b"soon".to_vec());
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = post(
+            &state_machine,
+            "/state_machine/set_time",
+            b"1000000000".to_vec(),
+        );
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
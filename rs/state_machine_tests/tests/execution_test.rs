@@ -1,9 +1,22 @@
+use bitcoin::{blockdata::constants::genesis_block, Network};
+use ic_btc_test_utils::{random_p2pkh_address, BlockBuilder, TransactionBuilder};
+use ic_certified_vars::verify_certificate;
 use ic_config::subnet_config::{CyclesAccountManagerConfig, SubnetConfigs};
+use ic_crypto_internal_basic_sig_ecdsa_secp256k1::{api::verify, types as ecdsa_types};
+use ic_crypto_utils_threshold_sig::parse_threshold_sig_key_from_der;
+use ic_ic00_types::{
+    BitcoinGetBalanceArgs, BitcoinNetwork, CanisterHttpRequestArgs, CanisterHttpResponsePayload,
+    ECDSAPublicKeyArgs, ECDSAPublicKeyResponse, EcdsaCurve, HttpMethod, Payload, SignWithECDSAArgs,
+    SignWithECDSAReply,
+};
 use ic_registry_subnet_type::SubnetType;
 use ic_state_machine_tests::{
-    CanisterSettingsArgs, ErrorCode, PrincipalId, StateMachine, SubnetId,
+    BitcoinFeature, BitcoinFeatureStatus, CanisterHttpResponseContent, CanisterSettingsArgs,
+    EcdsaKeyId, ErrorCode, IngressState, IngressStatus, PrincipalId, StateMachine,
+    StateMachineBuilder, SubnetFeatures, SubnetId, WasmResult,
 };
 use ic_types::Cycles;
+use std::convert::TryFrom;
 
 /// This is a canister that keeps a counter on the heap and exposes various test
 /// methods. Exposed methods:
@@ -413,3 +426,393 @@ fn compressed_canisters_support() {
     let val = env.query(canister_id, "read", vec![]).unwrap().bytes();
     assert_eq!(to_int(val), 1);
 }
+
+/// A canister that replies "pong" to calls of its "pong" method.
+const PONG_CANISTER: &str = r#"
+            (module
+              (import "ic0" "msg_reply" (func $msg_reply))
+              (import "ic0" "msg_reply_data_append"
+                (func $msg_reply_data_append (param i32 i32)))
+
+              (func $pong
+                (call $msg_reply_data_append (i32.const 0) (i32.const 4))
+                (call $msg_reply))
+
+              (memory $memory 1)
+              (data (i32.const 0) "pong")
+              (export "canister_update pong" (func $pong)))"#;
+
+/// A canister that calls the "pong" method of the canister whose ID is passed
+/// as the argument of its "ping" method, and replies with the response.
+const PING_CANISTER: &str = r#"
+            (module
+              (import "ic0" "msg_arg_data_size" (func $msg_arg_data_size (result i32)))
+              (import "ic0" "msg_arg_data_copy"
+                (func $msg_arg_data_copy (param $dst i32) (param $offset i32) (param $size i32)))
+              (import "ic0" "msg_reply" (func $msg_reply))
+              (import "ic0" "msg_reply_data_append"
+                (func $msg_reply_data_append (param i32 i32)))
+              (import "ic0" "call_new"
+                (func $call_new
+                  (param $callee_src i32) (param $callee_size i32)
+                  (param $name_src i32) (param $name_size i32)
+                  (param $reply_fun i32) (param $reply_env i32)
+                  (param $reject_fun i32) (param $reject_env i32)))
+              (import "ic0" "call_perform" (func $call_perform (result i32)))
+
+              (func $ping
+                (call $msg_arg_data_copy (i32.const 100) (i32.const 0) (call $msg_arg_data_size))
+                (call $call_new
+                  (i32.const 100) (call $msg_arg_data_size) ;; callee canister ID
+                  (i32.const 0) (i32.const 4)               ;; refers to "pong" on the heap
+                  (i32.const 0) (i32.const 0)               ;; on_reply closure
+                  (i32.const 1) (i32.const 0))              ;; on_reject closure
+                (drop (call $call_perform)))
+
+              (func $on_reply (param $env i32)
+                (call $msg_arg_data_copy (i32.const 200) (i32.const 0) (call $msg_arg_data_size))
+                (call $msg_reply_data_append (i32.const 200) (call $msg_arg_data_size))
+                (call $msg_reply))
+
+              (func $on_reject (param $env i32)
+                (call $msg_reply))
+
+              (table funcref (elem $on_reply $on_reject))
+              (memory $memory 1)
+              (data (i32.const 0) "pong")
+              (export "canister_update ping" (func $ping)))"#;
+
+/// Checks that an inter-canister call to a canister on another subnet is
+/// delivered through the certified streams of both subnets.
+#[test]
+fn test_xnet_call() {
+    let subnets = StateMachineBuilder::new().build_subnets(2);
+    let subnet_ids = subnets.subnet_ids();
+    let (caller_subnet, callee_subnet) = (subnet_ids[0], subnet_ids[1]);
+
+    let caller = subnets
+        .subnet(caller_subnet)
+        .install_canister_wat(PING_CANISTER, vec![], None);
+    let callee = subnets
+        .subnet(callee_subnet)
+        .install_canister_wat(PONG_CANISTER, vec![], None);
+
+    let msg_id = subnets.subnet(caller_subnet).send_ingress(
+        PrincipalId::new_anonymous(),
+        caller,
+        "ping",
+        callee.get().to_vec(),
+    );
+    assert_eq!(
+        subnets.await_ingress(caller_subnet, msg_id, 20),
+        Ok(WasmResult::Reply(b"pong".to_vec()))
+    );
+}
+
+/// Checks that pausing the stream from a subnet delays the delivery of its
+/// messages until the stream is resumed.
+#[test]
+fn test_xnet_call_paused() {
+    let subnets = StateMachineBuilder::new().build_subnets(2);
+    let subnet_ids = subnets.subnet_ids();
+    let (caller_subnet, callee_subnet) = (subnet_ids[0], subnet_ids[1]);
+
+    let caller = subnets
+        .subnet(caller_subnet)
+        .install_canister_wat(PING_CANISTER, vec![], None);
+    let callee = subnets
+        .subnet(callee_subnet)
+        .install_canister_wat(PONG_CANISTER, vec![], None);
+
+    subnets.subnet(caller_subnet).pause_xnet_from(callee_subnet);
+    let msg_id = subnets.subnet(caller_subnet).send_ingress(
+        PrincipalId::new_anonymous(),
+        caller,
+        "ping",
+        callee.get().to_vec(),
+    );
+    for _ in 0..10 {
+        subnets.tick_all();
+    }
+    assert!(matches!(
+        subnets.subnet(caller_subnet).ingress_status(&msg_id),
+        IngressStatus::Known {
+            state: IngressState::Processing,
+            ..
+        }
+    ));
+
+    subnets
+        .subnet(caller_subnet)
+        .resume_xnet_from(callee_subnet);
+    assert_eq!(
+        subnets.await_ingress(caller_subnet, msg_id, 20),
+        Ok(WasmResult::Reply(b"pong".to_vec()))
+    );
+}
+
+/// A canister that forwards the argument of its update methods to the
+/// management canister method of the same name, and replies with the response
+/// (or rejects with the reject message).
+const MANAGEMENT_PROXY_CANISTER: &str = r#"
+            (module
+              (import "ic0" "msg_arg_data_size" (func $msg_arg_data_size (result i32)))
+              (import "ic0" "msg_arg_data_copy"
+                (func $msg_arg_data_copy (param $dst i32) (param $offset i32) (param $size i32)))
+              (import "ic0" "msg_reply" (func $msg_reply))
+              (import "ic0" "msg_reply_data_append"
+                (func $msg_reply_data_append (param i32 i32)))
+              (import "ic0" "msg_reject" (func $msg_reject (param i32 i32)))
+              (import "ic0" "msg_reject_msg_size" (func $msg_reject_msg_size (result i32)))
+              (import "ic0" "msg_reject_msg_copy"
+                (func $msg_reject_msg_copy (param $dst i32) (param $offset i32) (param $size i32)))
+              (import "ic0" "call_new"
+                (func $call_new
+                  (param $callee_src i32) (param $callee_size i32)
+                  (param $name_src i32) (param $name_size i32)
+                  (param $reply_fun i32) (param $reply_env i32)
+                  (param $reject_fun i32) (param $reject_env i32)))
+              (import "ic0" "call_data_append" (func $call_data_append (param i32 i32)))
+              (import "ic0" "call_perform" (func $call_perform (result i32)))
+
+              (func $forward (param $name_src i32) (param $name_size i32)
+                (call $msg_arg_data_copy (i32.const 1024) (i32.const 0) (call $msg_arg_data_size))
+                (call $call_new
+                  (i32.const 0) (i32.const 0)                   ;; the management canister ID is empty
+                  (local.get $name_src) (local.get $name_size)  ;; method name
+                  (i32.const 0) (i32.const 0)                   ;; on_reply closure
+                  (i32.const 1) (i32.const 0))                  ;; on_reject closure
+                (call $call_data_append (i32.const 1024) (call $msg_arg_data_size))
+                (drop (call $call_perform)))
+
+              (func $sign_with_ecdsa (call $forward (i32.const 0) (i32.const 15)))
+              (func $ecdsa_public_key (call $forward (i32.const 16) (i32.const 16)))
+              (func $http_request (call $forward (i32.const 32) (i32.const 12)))
+              (func $bitcoin_get_balance (call $forward (i32.const 48) (i32.const 19)))
+
+              (func $on_reply (param $env i32)
+                (call $msg_arg_data_copy (i32.const 1024) (i32.const 0) (call $msg_arg_data_size))
+                (call $msg_reply_data_append (i32.const 1024) (call $msg_arg_data_size))
+                (call $msg_reply))
+
+              (func $on_reject (param $env i32)
+                (call $msg_reject_msg_copy (i32.const 1024) (i32.const 0) (call $msg_reject_msg_size))
+                (call $msg_reject (i32.const 1024) (call $msg_reject_msg_size)))
+
+              (table funcref (elem $on_reply $on_reject))
+              (memory $memory 1)
+              (data (i32.const 0) "sign_with_ecdsa")
+              (data (i32.const 16) "ecdsa_public_key")
+              (data (i32.const 32) "http_request")
+              (data (i32.const 48) "bitcoin_get_balance")
+              (export "canister_update sign_with_ecdsa" (func $sign_with_ecdsa))
+              (export "canister_update ecdsa_public_key" (func $ecdsa_public_key))
+              (export "canister_update http_request" (func $http_request))
+              (export "canister_update bitcoin_get_balance" (func $bitcoin_get_balance)))"#;
+
+fn reply_data(result: WasmResult) -> Vec<u8> {
+    match result {
+        WasmResult::Reply(data) => data,
+        WasmResult::Reject(msg) => panic!("Unexpected reject: {}", msg),
+    }
+}
+
+/// Checks that `sign_with_ecdsa` returns a signature that verifies under the
+/// public key returned by `ecdsa_public_key`.
+#[test]
+fn test_sign_with_ecdsa() {
+    let key_id = EcdsaKeyId {
+        curve: EcdsaCurve::Secp256k1,
+        name: "test_key".to_string(),
+    };
+    let env = StateMachineBuilder::new()
+        .with_ecdsa_key(key_id.clone())
+        .build();
+    let canister_id = env.install_canister_wat(MANAGEMENT_PROXY_CANISTER, vec![], None);
+    let derivation_path = vec![b"wallet".to_vec(), vec![0, 0, 0, 1]];
+    let message_hash = [0xab; 32];
+
+    let public_key = ECDSAPublicKeyResponse::decode(&reply_data(
+        env.execute_ingress(
+            canister_id,
+            "ecdsa_public_key",
+            ECDSAPublicKeyArgs {
+                canister_id: None,
+                derivation_path: derivation_path.clone(),
+                key_id: key_id.clone(),
+            }
+            .encode(),
+        )
+        .unwrap(),
+    ))
+    .unwrap()
+    .public_key;
+
+    let signature = SignWithECDSAReply::decode(&reply_data(
+        env.execute_ingress(
+            canister_id,
+            "sign_with_ecdsa",
+            SignWithECDSAArgs {
+                message_hash: message_hash.to_vec(),
+                derivation_path,
+                key_id,
+            }
+            .encode(),
+        )
+        .unwrap(),
+    ))
+    .unwrap()
+    .signature;
+
+    verify(
+        &ecdsa_types::SignatureBytes(<[u8; 64]>::try_from(signature.as_slice()).unwrap()),
+        &message_hash,
+        &ecdsa_types::PublicKeyBytes(public_key),
+    )
+    .expect("invalid ECDSA signature");
+}
+
+/// Checks that a canister HTTP request can be inspected and responded to.
+#[test]
+fn test_canister_http_request() {
+    let env = StateMachineBuilder::new()
+        .with_features(SubnetFeatures {
+            http_requests: true,
+            ..SubnetFeatures::default()
+        })
+        .build();
+    let canister_id = env.install_canister_wat(MANAGEMENT_PROXY_CANISTER, vec![], None);
+
+    let msg_id = env.send_ingress(
+        PrincipalId::new_anonymous(),
+        canister_id,
+        "http_request",
+        CanisterHttpRequestArgs {
+            url: "https://example.com".to_string(),
+            headers: vec![],
+            body: None,
+            http_method: HttpMethod::GET,
+            transform_method_name: None,
+        }
+        .encode(),
+    );
+    for _ in 0..2 {
+        env.tick();
+    }
+
+    let contexts = env.canister_http_request_contexts();
+    assert_eq!(contexts.len(), 1);
+    let (callback_id, context) = contexts.into_iter().next().unwrap();
+    assert_eq!(context.url, "https://example.com");
+    assert_eq!(context.request.sender, canister_id);
+
+    let response = CanisterHttpResponsePayload {
+        status: 200,
+        headers: vec![],
+        body: b"hello".to_vec(),
+    };
+    env.send_canister_http_response(
+        callback_id,
+        CanisterHttpResponseContent::Success(response.encode()),
+    );
+    let result = env.await_ingress(msg_id, 10).unwrap();
+    assert_eq!(
+        CanisterHttpResponsePayload::decode(&reply_data(result)).unwrap(),
+        response
+    );
+    assert!(env.canister_http_request_contexts().is_empty());
+}
+
+/// Checks that Bitcoin blocks pushed to the state machine are delivered to
+/// the Bitcoin state.
+#[test]
+fn test_bitcoin_blocks() {
+    let env = StateMachineBuilder::new()
+        .with_features(SubnetFeatures {
+            bitcoin: Some(BitcoinFeature {
+                network: BitcoinNetwork::Testnet,
+                status: BitcoinFeatureStatus::Enabled,
+            }),
+            ..SubnetFeatures::default()
+        })
+        .build();
+    let canister_id = env.install_canister_wat(MANAGEMENT_PROXY_CANISTER, vec![], None);
+
+    let address = random_p2pkh_address(Network::Testnet);
+    let block = BlockBuilder::with_prev_header(genesis_block(Network::Testnet).header)
+        .with_transaction(
+            TransactionBuilder::coinbase()
+                .with_output(&address, 1_000)
+                .build(),
+        )
+        .build();
+    env.push_bitcoin_blocks(&[block]);
+    for _ in 0..3 {
+        env.tick();
+    }
+
+    let result = env
+        .execute_ingress(
+            canister_id,
+            "bitcoin_get_balance",
+            BitcoinGetBalanceArgs {
+                address: address.to_string(),
+                network: BitcoinNetwork::Testnet,
+                min_confirmations: None,
+            }
+            .encode(),
+        )
+        .unwrap();
+    assert_eq!(candid::Decode!(&reply_data(result), u64).unwrap(), 1_000);
+}
+
+/// A canister that sets its certified data to the argument of its "set"
+/// method, and replies to its "get" query with its data certificate.
+const CERTIFIED_DATA_CANISTER: &str = r#"
+            (module
+              (import "ic0" "msg_arg_data_size" (func $msg_arg_data_size (result i32)))
+              (import "ic0" "msg_arg_data_copy"
+                (func $msg_arg_data_copy (param $dst i32) (param $offset i32) (param $size i32)))
+              (import "ic0" "msg_reply" (func $msg_reply))
+              (import "ic0" "msg_reply_data_append"
+                (func $msg_reply_data_append (param i32 i32)))
+              (import "ic0" "certified_data_set"
+                (func $certified_data_set (param $src i32) (param $size i32)))
+              (import "ic0" "data_certificate_present"
+                (func $data_certificate_present (result i32)))
+              (import "ic0" "data_certificate_size" (func $data_certificate_size (result i32)))
+              (import "ic0" "data_certificate_copy"
+                (func $data_certificate_copy (param $dst i32) (param $offset i32) (param $size i32)))
+
+              (func $set
+                (call $msg_arg_data_copy (i32.const 0) (i32.const 0) (call $msg_arg_data_size))
+                (call $certified_data_set (i32.const 0) (call $msg_arg_data_size))
+                (call $msg_reply))
+
+              (func $get
+                (if (call $data_certificate_present)
+                  (then
+                    (call $data_certificate_copy
+                      (i32.const 0) (i32.const 0) (call $data_certificate_size))
+                    (call $msg_reply_data_append (i32.const 0) (call $data_certificate_size))))
+                (call $msg_reply))
+
+              (memory $memory 1)
+              (export "canister_update set" (func $set))
+              (export "canister_query get" (func $get)))"#;
+
+/// Checks that the certificates of the state machine verify against its
+/// public key.
+#[test]
+fn test_data_certificate() {
+    let env = StateMachine::new();
+    let canister_id = env.install_canister_wat(CERTIFIED_DATA_CANISTER, vec![], None);
+    let certified_data = vec![42; 32];
+
+    env.execute_ingress(canister_id, "set", certified_data.clone())
+        .unwrap();
+    let certificate = reply_data(env.query(canister_id, "get", vec![]).unwrap());
+
+    let root_key = parse_threshold_sig_key_from_der(&env.public_key_der()).unwrap();
+    verify_certificate(&certificate, &canister_id, &root_key, &certified_data).unwrap();
+}
@@ -5,4 +5,5 @@ pub mod decode;
 pub mod import_state;
 pub mod list;
 pub mod manifest;
+pub mod split;
 mod utils;
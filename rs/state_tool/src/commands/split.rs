@@ -0,0 +1,121 @@
+//! Splits a checkpoint into the states of two subnets, by canister ID range.
+
+use ic_logger::replica_logger::no_op_logger;
+use ic_metrics::MetricsRegistry;
+use ic_registry_routing_table::{CanisterIdRange, CanisterIdRanges};
+use ic_registry_subnet_type::SubnetType;
+use ic_replicated_state::ReplicatedState;
+use ic_state_layout::{CompleteCheckpointLayout, StateLayout};
+use ic_state_manager::{
+    checkpoint::{load_checkpoint, make_checkpoint},
+    CheckpointMetrics, NUMBER_OF_CHECKPOINT_THREADS,
+};
+use ic_types::{Height, SubnetId};
+use std::convert::TryFrom;
+use std::path::{Path, PathBuf};
+
+/// Splits the checkpoint at `path` (of height `height`) into two states: one
+/// for the original subnet, retaining all canisters outside of
+/// `migrated_ranges`; and one for `new_subnet_id`, hosting the canisters in
+/// `migrated_ranges`. Both states are validated and written as checkpoints of
+/// the same height into `<out_dir>/<subnet_id>`.
+pub fn do_split(
+    path: PathBuf,
+    height: u64,
+    new_subnet_id: SubnetId,
+    migrated_ranges: Vec<CanisterIdRange>,
+    out_dir: PathBuf,
+) -> Result<(), String> {
+    let height = Height::new(height);
+    let cp_layout = CompleteCheckpointLayout::new(path.clone(), height)
+        .map_err(|e| format!("failed to create checkpoint layout: {}", e))?;
+
+    let metrics_registry = MetricsRegistry::new();
+    let metrics = CheckpointMetrics::new(&metrics_registry);
+    let mut thread_pool = scoped_threadpool::Pool::new(NUMBER_OF_CHECKPOINT_THREADS);
+
+    let state = load_checkpoint(
+        &cp_layout,
+        SubnetType::Application,
+        &metrics,
+        Some(&mut thread_pool),
+    )
+    .map_err(|e| format!("failed to load checkpoint at {}: {}", path.display(), e))?;
+    let own_subnet_id = state.metadata.own_subnet_id;
+    if own_subnet_id == new_subnet_id {
+        return Err(format!(
+            "the new subnet must differ from the original subnet {}",
+            own_subnet_id
+        ));
+    }
+
+    let migrated_ranges = CanisterIdRanges::try_from(migrated_ranges)
+        .map_err(|e| format!("invalid canister ID ranges: {:?}", e))?;
+    let mut routing_table = state.routing_table().as_ref().clone();
+    routing_table
+        .assign_ranges(migrated_ranges, new_subnet_id)
+        .map_err(|e| format!("failed to reroute canister ID ranges: {:?}", e))?;
+
+    for subnet_id in [own_subnet_id, new_subnet_id] {
+        let split_state = state
+            .clone()
+            .split(subnet_id, &routing_table)
+            .map_err(|e| {
+                format!(
+                    "failed to split off the state of subnet {}: {}",
+                    subnet_id, e
+                )
+            })?;
+        write_checkpoint(
+            &split_state,
+            height,
+            &out_dir.join(subnet_id.to_string()),
+            &metrics,
+            &mut thread_pool,
+        )?;
+        println!(
+            "Subnet {}: {} canisters, {} ingress history entries",
+            subnet_id,
+            split_state.num_canisters(),
+            split_state.metadata.ingress_history.len()
+        );
+    }
+
+    println!(
+        "Successfully split checkpoint {} into {}",
+        height,
+        out_dir.display()
+    );
+
+    Ok(())
+}
+
+/// Writes `state` as the checkpoint at `height` into the state root at `root`.
+fn write_checkpoint(
+    state: &ReplicatedState,
+    height: Height,
+    root: &Path,
+    metrics: &CheckpointMetrics,
+    thread_pool: &mut scoped_threadpool::Pool,
+) -> Result<(), String> {
+    let state_layout = StateLayout::new(no_op_logger(), root.to_path_buf());
+    if let Ok(cp_layout) = state_layout.checkpoint(height) {
+        return Err(format!(
+            "Checkpoint {} already exists at {}",
+            height,
+            cp_layout.raw_path().display()
+        ));
+    }
+
+    make_checkpoint(
+        state,
+        height,
+        &state_layout,
+        &no_op_logger(),
+        metrics,
+        thread_pool,
+    )
+    .map_err(|e| format!("failed to write checkpoint to {}: {}", root.display(), e))?;
+
+    Ok(())
+}
@@ -2,10 +2,13 @@
 //!
 //! A command-line tool to manage Internet Computer replicated states (decode
 //! persisted state files, diff checkpoints, compute partial state hashes and
-//! checkpoint manifests, import state trees).
+//! checkpoint manifests, import state trees, split states).
 
 use clap::Parser;
+use ic_registry_routing_table::CanisterIdRange;
+use ic_types::{PrincipalId, SubnetId};
 use std::path::PathBuf;
+use std::str::FromStr;
 
 mod commands;
 
@@ -64,6 +67,37 @@ enum Opt {
         #[clap(long = "file")]
         file: PathBuf,
     },
+
+    /// Splits a checkpoint into the states of two subnets, by canister ID
+    /// range.
+    #[clap(name = "split")]
+    Split {
+        /// Path to the checkpoint to split.
+        #[clap(long = "state")]
+        path: PathBuf,
+
+        /// Height of the checkpoint to split.
+        #[clap(long = "height", short = 'h')]
+        height: u64,
+
+        /// ID of the subnet to migrate the canisters to.
+        #[clap(long = "new-subnet-id", parse(try_from_str = subnet_id_from_str))]
+        new_subnet_id: SubnetId,
+
+        /// Canister ID ranges to migrate to the new subnet, as `start:end`.
+        #[clap(long = "migrated-ranges", multiple_values(true), required(true))]
+        migrated_ranges: Vec<CanisterIdRange>,
+
+        /// Directory to write the states of both subnets to.
+        #[clap(long = "out")]
+        out_dir: PathBuf,
+    },
+}
+
+fn subnet_id_from_str(s: &str) -> Result<SubnetId, String> {
+    PrincipalId::from_str(s)
+        .map(SubnetId::from)
+        .map_err(|e| format!("Unable to parse subnet ID {:?}: {}", s, e))
 }
 
 fn main() {
@@ -79,6 +113,13 @@ fn main() {
         Opt::Manifest { path } => commands::manifest::do_compute_manifest(path),
         Opt::ListStates { config } => commands::list::do_list(config),
         Opt::Decode { file } => commands::decode::do_decode(file),
+        Opt::Split {
+            path,
+            height,
+            new_subnet_id,
+            migrated_ranges,
+            out_dir,
+        } => commands::split::do_split(path, height, new_subnet_id, migrated_ranges, out_dir),
     };
 
     if let Err(e) = result {
@@ -22,7 +22,10 @@ use ic_replicated_state::{
 use ic_sys::PageBytes;
 use ic_types::{
     ingress::WasmResult,
-    messages::{CallContextId, RejectContext, Request, MAX_INTER_CANISTER_PAYLOAD_IN_BYTES},
+    messages::{
+        CallContextId, RejectContext, Request, MAX_CALL_TIMEOUT_SECONDS,
+        MAX_INTER_CANISTER_PAYLOAD_IN_BYTES,
+    },
     methods::{Callback, WasmClosure},
     CanisterId, Cycles, NumBytes, NumInstructions, PrincipalId, SubnetId, Time,
 };
@@ -34,11 +37,12 @@ use stable_memory::StableMemory;
 use std::{
     convert::{From, TryFrom},
     sync::Arc,
+    time::Duration,
 };
 
 const MULTIPLIER_MAX_SIZE_LOCAL_SUBNET: u64 = 5;
 const MAX_NON_REPLICATED_QUERY_REPLY_SIZE: NumBytes = NumBytes::new(3 << 20);
-const CERTIFIED_DATA_MAX_LENGTH: u32 = 32;
+const CERTIFIED_DATA_MAX_LENGTH: u64 = 32;
 
 // Enables tracing of system calls for local debugging.
 const TRACE_SYSCALLS: bool = false;
@@ -69,10 +73,10 @@ macro_rules! trace_syscall {
 
 // This helper is used in system calls for displaying a summary hash of a heap region.
 #[inline]
-fn summarize(heap: &[u8], start: u32, size: u32) -> u64 {
+fn summarize(heap: &[u8], start: u64, size: u64) -> u64 {
     if TRACE_SYSCALLS {
         let start = (start as usize).min(heap.len());
-        let end = ((start as usize) + (size as usize)).min(heap.len());
+        let end = start.saturating_add(size as usize).min(heap.len());
         // The actual hash function doesn't matter much as long as it is
         // cheap to compute and maps the input to u64 reasonably well.
         let mut sum = 0;
@@ -148,6 +152,8 @@ pub enum ApiType {
         incoming_cycles: Cycles,
         caller: PrincipalId,
         call_context_id: CallContextId,
+        /// The deadline of the incoming call, if it is a best-effort call.
+        deadline: Option<Time>,
         /// Begins as empty and used to accumulate data for sending replies.
         #[serde(with = "serde_bytes")]
         response_data: Vec<u8>,
@@ -169,6 +175,8 @@ pub enum ApiType {
         response_status: ResponseStatus,
         data_certificate: Option<Vec<u8>>,
         max_reply_size: NumBytes,
+        /// The deadline of the incoming call, if it is a best-effort call.
+        deadline: Option<Time>,
     },
 
     NonReplicatedQuery {
@@ -193,6 +201,9 @@ pub enum ApiType {
         incoming_payload: Vec<u8>,
         incoming_cycles: Cycles,
         call_context_id: CallContextId,
+        /// The deadline of the call that this is a response to, if it is a
+        /// best-effort call.
+        deadline: Option<Time>,
         // Begins as empty and used to accumulate data for sending replies.
         #[serde(with = "serde_bytes")]
         response_data: Vec<u8>,
@@ -209,6 +220,9 @@ pub enum ApiType {
         reject_context: RejectContext,
         incoming_cycles: Cycles,
         call_context_id: CallContextId,
+        /// The deadline of the call that this is a response to, if it is a
+        /// best-effort call.
+        deadline: Option<Time>,
         // Begins as empty and used to accumulate data for sending replies.
         #[serde(with = "serde_bytes")]
         response_data: Vec<u8>,
@@ -284,6 +298,7 @@ impl ApiType {
         incoming_cycles: Cycles,
         caller: PrincipalId,
         call_context_id: CallContextId,
+        deadline: Option<Time>,
     ) -> Self {
         Self::Update {
             time,
@@ -291,6 +306,7 @@ impl ApiType {
             incoming_cycles,
             caller,
             call_context_id,
+            deadline,
             response_data: vec![],
             response_status: ResponseStatus::NotRepliedYet,
             outgoing_request: None,
@@ -303,6 +319,7 @@ impl ApiType {
         incoming_payload: Vec<u8>,
         caller: PrincipalId,
         data_certificate: Option<Vec<u8>>,
+        deadline: Option<Time>,
     ) -> Self {
         Self::ReplicatedQuery {
             time,
@@ -312,6 +329,7 @@ impl ApiType {
             response_status: ResponseStatus::NotRepliedYet,
             data_certificate,
             max_reply_size: MAX_INTER_CANISTER_PAYLOAD_IN_BYTES,
+            deadline,
         }
     }
 
@@ -343,6 +361,7 @@ impl ApiType {
         incoming_payload: Vec<u8>,
         incoming_cycles: Cycles,
         call_context_id: CallContextId,
+        deadline: Option<Time>,
         replied: bool,
     ) -> Self {
         Self::ReplyCallback {
@@ -350,6 +369,7 @@ impl ApiType {
             incoming_payload,
             incoming_cycles,
             call_context_id,
+            deadline,
             response_data: vec![],
             response_status: if replied {
                 ResponseStatus::AlreadyReplied
@@ -367,6 +387,7 @@ impl ApiType {
         reject_context: RejectContext,
         incoming_cycles: Cycles,
         call_context_id: CallContextId,
+        deadline: Option<Time>,
         replied: bool,
     ) -> Self {
         Self::RejectCallback {
@@ -374,6 +395,7 @@ impl ApiType {
             reject_context,
             incoming_cycles,
             call_context_id,
+            deadline,
             response_data: vec![],
             response_status: if replied {
                 ResponseStatus::AlreadyReplied
@@ -1117,9 +1139,9 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_msg_caller_copy(
         &self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: u64,
+        offset: u64,
+        size: u64,
         heap: &mut [u8],
     ) -> HypervisorResult<()> {
         let result = match self.get_msg_caller_id("ic0_msg_caller_copy") {
@@ -1177,9 +1199,9 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_msg_arg_data_copy(
         &self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: u64,
+        offset: u64,
+        size: u64,
         heap: &mut [u8],
     ) -> HypervisorResult<()> {
         let result = match &self.api_type {
@@ -1250,9 +1272,9 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_msg_method_name_copy(
         &self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: u64,
+        offset: u64,
+        size: u64,
         heap: &mut [u8],
     ) -> HypervisorResult<()> {
         let result = match &self.api_type {
@@ -1341,8 +1363,8 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_msg_reply_data_append(
         &mut self,
-        src: u32,
-        size: u32,
+        src: u64,
+        size: u64,
         heap: &[u8],
     ) -> HypervisorResult<()> {
         let result = match self.get_response_info() {
@@ -1379,12 +1401,12 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
-    fn ic0_msg_reject(&mut self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()> {
+    fn ic0_msg_reject(&mut self, src: u64, size: u64, heap: &[u8]) -> HypervisorResult<()> {
         let result = match self.get_response_info() {
             None => Err(self.error_for("ic0_msg_reject")),
             Some((_, max_reply_size, response_status)) => match response_status {
                 ResponseStatus::NotRepliedYet => {
-                    if size as u64 > max_reply_size.get() {
+                    if size > max_reply_size.get() {
                         let string = format!(
                         "ic0.msg_reject: application payload size ({}) cannot be larger than {}",
                         size, max_reply_size
@@ -1417,6 +1439,26 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
+    fn ic0_msg_deadline(&self) -> HypervisorResult<u64> {
+        let result = match &self.api_type {
+            ApiType::Start { .. }
+            | ApiType::Init { .. }
+            | ApiType::Heartbeat { .. }
+            | ApiType::Cleanup { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::InspectMessage { .. } => Err(self.error_for("ic0_msg_deadline")),
+            ApiType::NonReplicatedQuery { .. } => Ok(0),
+            ApiType::Update { deadline, .. }
+            | ApiType::ReplicatedQuery { deadline, .. }
+            | ApiType::ReplyCallback { deadline, .. }
+            | ApiType::RejectCallback { deadline, .. } => {
+                Ok(deadline.map_or(0, |deadline| deadline.as_nanos_since_unix_epoch()))
+            }
+        };
+        trace_syscall!(self, ic0_msg_deadline, result);
+        result
+    }
+
     fn ic0_msg_reject_code(&self) -> HypervisorResult<i32> {
         let result = self
             .get_reject_code()
@@ -1436,9 +1478,9 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_msg_reject_msg_copy(
         &self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: u64,
+        offset: u64,
+        size: u64,
         heap: &mut [u8],
     ) -> HypervisorResult<()> {
         let result = {
@@ -1492,9 +1534,9 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_canister_self_copy(
         &mut self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: u64,
+        offset: u64,
+        size: u64,
         heap: &mut [u8],
     ) -> HypervisorResult<()> {
         let result = match &self.api_type {
@@ -1552,9 +1594,9 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_controller_copy(
         &mut self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: u64,
+        offset: u64,
+        size: u64,
         heap: &mut [u8],
     ) -> HypervisorResult<()> {
         let result = match &self.api_type {
@@ -1584,16 +1626,16 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_call_simple(
         &mut self,
-        callee_src: u32,
-        callee_size: u32,
-        method_name_src: u32,
-        method_name_len: u32,
+        callee_src: u64,
+        callee_size: u64,
+        method_name_src: u64,
+        method_name_len: u64,
         reply_fun: u32,
         reply_env: u32,
         reject_fun: u32,
         reject_env: u32,
-        data_src: u32,
-        data_len: u32,
+        data_src: u64,
+        data_len: u64,
         heap: &[u8],
     ) -> HypervisorResult<i32> {
         let result = match &mut self.api_type {
@@ -1626,7 +1668,7 @@ impl SystemApi for SystemApiImpl {
             | ApiType::RejectCallback {
                 call_context_id, ..
             } => {
-                if data_len as u64 > MAX_INTER_CANISTER_PAYLOAD_IN_BYTES.get() {
+                if data_len > MAX_INTER_CANISTER_PAYLOAD_IN_BYTES.get() {
                     return Ok(RejectCode::SysFatal as i32);
                 }
 
@@ -1663,6 +1705,7 @@ impl SystemApi for SystemApiImpl {
                             on_reply,
                             on_reject,
                             None,
+                            None,
                         ))?;
 
                 let msg = Request {
@@ -1672,6 +1715,7 @@ impl SystemApi for SystemApiImpl {
                     method_payload: payload,
                     sender_reply_callback: callback_id,
                     payment: Cycles::zero(),
+                    deadline: None,
                 };
                 self.push_output_request(msg)
             }
@@ -1698,10 +1742,10 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_call_new(
         &mut self,
-        callee_src: u32,
-        callee_size: u32,
-        name_src: u32,
-        name_len: u32,
+        callee_src: u64,
+        callee_size: u64,
+        name_src: u64,
+        name_len: u64,
         reply_fun: u32,
         reply_env: u32,
         reject_fun: u32,
@@ -1776,7 +1820,7 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
-    fn ic0_call_data_append(&mut self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()> {
+    fn ic0_call_data_append(&mut self, src: u64, size: u64, heap: &[u8]) -> HypervisorResult<()> {
         let result = match &mut self.api_type {
             ApiType::Start { .. }
             | ApiType::Init { .. }
@@ -1864,6 +1908,62 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
+    fn ic0_call_with_best_effort_response(&mut self, timeout_seconds: u32) -> HypervisorResult<()> {
+        let result = match &mut self.api_type {
+            ApiType::Start { .. }
+            | ApiType::Init { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::NonReplicatedQuery {
+                query_kind: NonReplicatedQueryKind::Pure,
+                ..
+            }
+            | ApiType::Cleanup { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::InspectMessage { .. } => {
+                Err(self.error_for("ic0_call_with_best_effort_response"))
+            }
+            ApiType::Update {
+                time,
+                outgoing_request,
+                ..
+            }
+            | ApiType::NonReplicatedQuery {
+                time,
+                query_kind:
+                    NonReplicatedQueryKind::Stateful {
+                        outgoing_request, ..
+                    },
+                ..
+            }
+            | ApiType::Heartbeat {
+                time,
+                outgoing_request,
+                ..
+            }
+            | ApiType::ReplyCallback {
+                time,
+                outgoing_request,
+                ..
+            }
+            | ApiType::RejectCallback {
+                time,
+                outgoing_request,
+                ..
+            } => match outgoing_request {
+                None => Err(HypervisorError::ContractViolation(
+                    "ic0.call_with_best_effort_response called when no call is under construction."
+                        .to_string(),
+                )),
+                Some(request) => {
+                    let timeout = timeout_seconds.min(MAX_CALL_TIMEOUT_SECONDS);
+                    request.set_deadline(*time + Duration::from_secs(timeout as u64))
+                }
+            },
+        };
+        trace_syscall!(self, ic0_call_with_best_effort_response, timeout_seconds);
+        result
+    }
+
     fn ic0_call_cycles_add(&mut self, amount: u64) -> HypervisorResult<()> {
         let result = self.ic0_call_cycles_add_helper("ic0_call_cycles_add", Cycles::from(amount));
         trace_syscall!(self, ic0_call_cycles_add, result, amount);
@@ -2039,7 +2139,7 @@ impl SystemApi for SystemApiImpl {
             dst,
             offset,
             size,
-            summarize(heap, dst, size)
+            summarize(heap, dst as u64, size as u64)
         );
         result
     }
@@ -2077,7 +2177,7 @@ impl SystemApi for SystemApiImpl {
             offset,
             src,
             size,
-            summarize(heap, src, size)
+            summarize(heap, src as u64, size as u64)
         );
         result
     }
@@ -2161,7 +2261,7 @@ impl SystemApi for SystemApiImpl {
             dst,
             offset,
             size,
-            summarize(heap, dst as u32, size as u32)
+            summarize(heap, dst, size)
         );
         result
     }
@@ -2199,7 +2299,7 @@ impl SystemApi for SystemApiImpl {
             offset,
             src,
             size,
-            summarize(heap, src as u32, size as u32)
+            summarize(heap, src, size)
         );
         result
     }
@@ -2248,9 +2348,9 @@ impl SystemApi for SystemApiImpl {
 
     fn update_available_memory(
         &mut self,
-        native_memory_grow_res: i32,
-        additional_pages: u32,
-    ) -> HypervisorResult<i32> {
+        native_memory_grow_res: i64,
+        additional_pages: u64,
+    ) -> HypervisorResult<i64> {
         let result = {
             if native_memory_grow_res == -1 {
                 return Ok(-1);
@@ -2284,7 +2384,7 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
-    fn ic0_canister_cycles_balance128(&self, dst: u32, heap: &mut [u8]) -> HypervisorResult<()> {
+    fn ic0_canister_cycles_balance128(&self, dst: u64, heap: &mut [u8]) -> HypervisorResult<()> {
         let result = {
             let method_name = "ic0_canister_cycles_balance128";
             let cycles = self.ic0_canister_cycles_balance_helper(method_name)?;
@@ -2314,7 +2414,7 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
-    fn ic0_msg_cycles_available128(&self, dst: u32, heap: &mut [u8]) -> HypervisorResult<()> {
+    fn ic0_msg_cycles_available128(&self, dst: u64, heap: &mut [u8]) -> HypervisorResult<()> {
         let result = {
             let method_name = "ic0_msg_cycles_available128";
             let cycles = self.ic0_msg_cycles_available_helper(method_name)?;
@@ -2339,7 +2439,7 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
-    fn ic0_msg_cycles_refunded128(&self, dst: u32, heap: &mut [u8]) -> HypervisorResult<()> {
+    fn ic0_msg_cycles_refunded128(&self, dst: u64, heap: &mut [u8]) -> HypervisorResult<()> {
         let result = {
             let method_name = "ic0_msg_cycles_refunded128";
             let cycles = self.ic0_msg_cycles_refunded_helper(method_name)?;
@@ -2378,7 +2478,7 @@ impl SystemApi for SystemApiImpl {
     fn ic0_msg_cycles_accept128(
         &mut self,
         max_amount: Cycles,
-        dst: u32,
+        dst: u64,
         heap: &mut [u8],
     ) -> HypervisorResult<()> {
         let result = {
@@ -2443,9 +2543,9 @@ impl SystemApi for SystemApiImpl {
 
     fn ic0_data_certificate_copy(
         &self,
-        dst: u32,
-        offset: u32,
-        size: u32,
+        dst: u64,
+        offset: u64,
+        size: u64,
         heap: &mut [u8],
     ) -> HypervisorResult<()> {
         let result = match &self.api_type {
@@ -2512,7 +2612,7 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
-    fn ic0_certified_data_set(&mut self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()> {
+    fn ic0_certified_data_set(&mut self, src: u64, size: u64, heap: &[u8]) -> HypervisorResult<()> {
         let result = match &mut self.api_type {
             ApiType::Start { .. }
             | ApiType::ReplicatedQuery { .. }
@@ -2608,8 +2708,8 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
-    fn ic0_debug_print(&self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()> {
-        const MAX_DEBUG_MESSAGE_SIZE: u32 = 32 * 1024;
+    fn ic0_debug_print(&self, src: u64, size: u64, heap: &[u8]) -> HypervisorResult<()> {
+        const MAX_DEBUG_MESSAGE_SIZE: u64 = 32 * 1024;
         let size = size.min(MAX_DEBUG_MESSAGE_SIZE);
         let msg = match valid_subslice("ic0.debug_print", src, size, heap) {
             Ok(bytes) => String::from_utf8_lossy(bytes).to_string(),
@@ -2628,8 +2728,8 @@ impl SystemApi for SystemApiImpl {
         Ok(())
     }
 
-    fn ic0_trap(&self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()> {
-        const MAX_ERROR_MESSAGE_SIZE: u32 = 16 * 1024;
+    fn ic0_trap(&self, src: u64, size: u64, heap: &[u8]) -> HypervisorResult<()> {
+        const MAX_ERROR_MESSAGE_SIZE: u64 = 16 * 1024;
         let size = size.min(MAX_ERROR_MESSAGE_SIZE);
         let result = {
             let msg = valid_subslice("trap", src, size, heap)
@@ -2657,7 +2757,7 @@ impl OutOfInstructionsHandler for DefaultOutOfInstructionsHandler {
 
 pub(crate) fn copy_cycles_to_heap(
     cycles: Cycles,
-    dst: u32,
+    dst: u64,
     heap: &mut [u8],
     method_name: &str,
 ) -> HypervisorResult<()> {
@@ -2684,13 +2784,15 @@ pub(crate) fn copy_cycles_to_heap(
 
 pub(crate) fn valid_subslice<'a>(
     ctx: &str,
-    src: u32,
-    len: u32,
+    src: u64,
+    len: u64,
     slice: &'a [u8],
 ) -> HypervisorResult<&'a [u8]> {
     let len = len as usize;
     let src = src as usize;
-    if slice.len() < src + len {
+    // Pointers into a 64-bit Wasm memory may be arbitrary `u64` values, so
+    // the end of the range must not overflow.
+    if src.checked_add(len).map_or(true, |end| slice.len() < end) {
         return Err(ContractViolation(format!(
             "{}: src={} + length={} exceeds the slice size={}",
             ctx,
@@ -2724,5 +2826,9 @@ mod test {
         assert!(valid_subslice("", 3, 2, &[1, 2, 3, 4]).is_err());
         assert!(valid_subslice("", 0, 5, &[1, 2, 3, 4]).is_err());
         assert!(valid_subslice("", 4, 1, &[1, 2, 3, 4]).is_err());
+
+        // ranges whose end overflows the address space
+        assert!(valid_subslice("", u64::MAX, 2, &[1, 2, 3, 4]).is_err());
+        assert!(valid_subslice("", 1, u64::MAX, &[1, 2, 3, 4]).is_err());
     }
 }
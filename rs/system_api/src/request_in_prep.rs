@@ -4,7 +4,7 @@ use ic_logger::ReplicaLogger;
 use ic_types::{
     messages::{CallContextId, Request},
     methods::{Callback, WasmClosure},
-    CanisterId, Cycles, NumBytes, PrincipalId,
+    CanisterId, Cycles, NumBytes, PrincipalId, Time,
 };
 use serde::{Deserialize, Serialize};
 use std::convert::TryFrom;
@@ -47,16 +47,20 @@ pub struct RequestInPrep {
     /// them up creating tricky bugs. Storing this an integer means that the two
     /// limits are stored as different types and are more difficult to mix up.
     multiplier_max_size_local_subnet: u64,
+    /// The deadline of a best-effort call; `None` for calls with a guaranteed
+    /// response.
+    #[serde(default)]
+    deadline: Option<Time>,
 }
 
 impl RequestInPrep {
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         sender: CanisterId,
-        callee_src: u32,
-        callee_size: u32,
-        method_name_src: u32,
-        method_name_len: u32,
+        callee_src: u64,
+        callee_size: u64,
+        method_name_src: u64,
+        method_name_len: u64,
         heap: &[u8],
         on_reply: WasmClosure,
         on_reject: WasmClosure,
@@ -65,7 +69,7 @@ impl RequestInPrep {
     ) -> HypervisorResult<Self> {
         let method_name = {
             let max_size_local_subnet = max_size_remote_subnet * multiplier_max_size_local_subnet;
-            if method_name_len as u64 > max_size_local_subnet.get() {
+            if method_name_len > max_size_local_subnet.get() {
                 return Err(HypervisorError::ContractViolation(format!(
                     "RequestInPrep: size of method_name {} exceeded the allowed limit local-subnet {} remote-subnet {}",
                     callee_size, max_size_local_subnet, max_size_remote_subnet
@@ -96,9 +100,22 @@ impl RequestInPrep {
             method_payload: Vec::new(),
             max_size_remote_subnet,
             multiplier_max_size_local_subnet,
+            deadline: None,
         })
     }
 
+    pub(crate) fn set_deadline(&mut self, deadline: Time) -> HypervisorResult<()> {
+        if self.deadline.is_some() {
+            Err(HypervisorError::ContractViolation(
+                "ic0.call_with_best_effort_response can be called at most once between `ic0.call_new` and `ic0.call_perform`"
+                    .to_string(),
+            ))
+        } else {
+            self.deadline = Some(deadline);
+            Ok(())
+        }
+    }
+
     pub(crate) fn set_on_cleanup(&mut self, on_cleanup: WasmClosure) -> HypervisorResult<()> {
         if self.on_cleanup.is_some() {
             Err(HypervisorError::ContractViolation(
@@ -117,14 +134,14 @@ impl RequestInPrep {
 
     pub(crate) fn extend_method_payload(
         &mut self,
-        src: u32,
-        size: u32,
+        src: u64,
+        size: u64,
         heap: &[u8],
     ) -> HypervisorResult<()> {
         let current_size = self.method_name.len() + self.method_payload.len();
         let max_size_local_subnet =
             self.max_size_remote_subnet * self.multiplier_max_size_local_subnet;
-        if size as u64 > max_size_local_subnet.get() - current_size as u64 {
+        if size > max_size_local_subnet.get() - current_size as u64 {
             Err(HypervisorError::ContractViolation(format!(
                 "RequestInPrep: current_size {} exceeded the allowed limit local-subnet {} remote-subnet {}",
                 current_size, max_size_local_subnet, self.max_size_remote_subnet
@@ -155,6 +172,7 @@ pub(crate) fn into_request(
         method_payload,
         max_size_remote_subnet,
         multiplier_max_size_local_subnet,
+        deadline,
     }: RequestInPrep,
     call_context_id: CallContextId,
     sandbox_safe_system_state: &mut SandboxSafeSystemState,
@@ -182,6 +200,7 @@ pub(crate) fn into_request(
         on_reply,
         on_reject,
         on_cleanup,
+        deadline,
     ))?;
 
     let req = Request {
@@ -191,6 +210,7 @@ pub(crate) fn into_request(
         method_payload,
         sender_reply_callback: callback_id,
         payment: cycles,
+        deadline,
     };
     // We cannot call `Request::payload_size_bytes()` before constructing the
     // request, so ensure our separate calculation matches the actual size.
@@ -72,6 +72,7 @@ impl ApiTypeBuilder {
             Cycles::from(0),
             user_test_id(1).get(),
             CallContextId::from(1),
+            None,
         )
     }
 
@@ -85,6 +86,7 @@ impl ApiTypeBuilder {
             vec![],
             incoming_cycles,
             CallContextId::new(1),
+            None,
             false,
         )
     }
@@ -95,6 +97,7 @@ impl ApiTypeBuilder {
             reject_context,
             Cycles::from(0),
             call_context_test_id(1),
+            None,
             false,
         )
     }
@@ -124,7 +127,7 @@ pub fn get_system_state() -> SystemState {
         .call_context_manager_mut()
         .unwrap()
         .new_call_context(
-            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5)),
+            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5), None),
             Cycles::from(50),
             Time::from_nanos_since_unix_epoch(0),
         );
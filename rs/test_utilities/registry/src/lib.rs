@@ -4,7 +4,9 @@ use ic_interfaces::registry::{
     RegistryClientVersionedResult,
 };
 use ic_interfaces::time_source::TimeSource;
-use ic_protobuf::registry::subnet::v1::{CatchUpPackageContents, SubnetListRecord, SubnetRecord};
+use ic_protobuf::registry::subnet::v1::{
+    CatchUpPackageContents, EcdsaConfig, SubnetFeatures, SubnetListRecord, SubnetRecord,
+};
 use ic_registry_client_fake::FakeRegistryClient;
 use ic_registry_keys::{
     make_catch_up_package_contents_key, make_subnet_list_record_key, make_subnet_record_key,
@@ -250,6 +252,16 @@ impl SubnetRecordBuilder {
         self
     }
 
+    pub fn with_features(mut self, features: SubnetFeatures) -> Self {
+        self.record.features = Some(features);
+        self
+    }
+
+    pub fn with_ecdsa_config(mut self, ecdsa_config: EcdsaConfig) -> Self {
+        self.record.ecdsa_config = Some(ecdsa_config);
+        self
+    }
+
     pub fn build(self) -> SubnetRecord {
         self.record
     }
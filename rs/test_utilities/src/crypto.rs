@@ -13,10 +13,10 @@ use ic_crypto_test_utils_canister_threshold_sigs::{
     create_params_for_dealers, mock_transcript, mock_unmasked_transcript_type, set_of_nodes,
 };
 use ic_interfaces::crypto::{
-    BasicSigVerifier, BasicSigVerifierByPublicKey, BasicSigner, CanisterSigVerifier, IDkgProtocol,
-    KeyManager, LoadTranscriptResult, NiDkgAlgorithm, PublicKeyRegistrationStatus,
-    ThresholdEcdsaSigVerifier, ThresholdEcdsaSigner, ThresholdSigVerifier,
-    ThresholdSigVerifierByPublicKey, ThresholdSigner,
+    BasicSigBatchVerifierByPublicKey, BasicSigVerifier, BasicSigVerifierByPublicKey, BasicSigner,
+    CanisterSigVerifier, IDkgProtocol, KeyManager, LoadTranscriptResult, NiDkgAlgorithm,
+    PublicKeyRegistrationStatus, ThresholdEcdsaSigVerifier, ThresholdEcdsaSigner,
+    ThresholdSigVerifier, ThresholdSigVerifierByPublicKey, ThresholdSigner,
 };
 use ic_interfaces::crypto::{MultiSigVerifier, MultiSigner, Signable};
 use ic_interfaces::registry::RegistryClient;
@@ -374,6 +374,15 @@ impl<T: Signable> BasicSigVerifierByPublicKey<T> for CryptoReturningOk {
     }
 }
 
+impl<T: Signable> BasicSigBatchVerifierByPublicKey<T> for CryptoReturningOk {
+    fn verify_basic_sig_batch_by_public_key(
+        &self,
+        _signatures: &[(&BasicSigOf<T>, &T, &UserPublicKey)],
+    ) -> CryptoResult<()> {
+        Ok(())
+    }
+}
+
 impl<T: Signable> MultiSigner<T> for CryptoReturningOk {
     fn sign_multi(
         &self,
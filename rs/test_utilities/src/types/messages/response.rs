@@ -1,7 +1,7 @@
 use crate::types::ids::canister_test_id;
 use ic_types::{
     messages::{CallbackId, Payload, Response},
-    CanisterId, Cycles,
+    CanisterId, Cycles, Time,
 };
 
 pub struct ResponseBuilder {
@@ -19,6 +19,7 @@ impl Default for ResponseBuilder {
                 originator_reply_callback: CallbackId::from(0),
                 refund: Cycles::zero(),
                 response_payload: rpb.build(),
+                deadline: None,
             },
         }
     }
@@ -59,6 +60,12 @@ impl ResponseBuilder {
         self
     }
 
+    /// Sets the deadline field, making this a best-effort response.
+    pub fn deadline(mut self, deadline: Time) -> Self {
+        self.response.deadline = Some(deadline);
+        self
+    }
+
     pub fn build(&self) -> Response {
         self.response.clone()
     }
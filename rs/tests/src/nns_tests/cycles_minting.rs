@@ -1033,6 +1033,8 @@ impl UserHandle {
         let notify_arg = NotifyCreateCanister {
             block_index: block,
             controller: *controller_id,
+            subnet_selection: None,
+            settings: None,
         };
 
         let result: Result<CanisterId, NotifyError> = self
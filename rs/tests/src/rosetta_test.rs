@@ -32,20 +32,22 @@ use ic_canister_client::Sender;
 use ic_fondue::ic_manager::IcHandle;
 use ic_nervous_system_common::ledger;
 use ic_nns_constants::{GOVERNANCE_CANISTER_ID, LEDGER_CANISTER_ID, REGISTRY_CANISTER_ID};
-use ic_nns_governance::pb::v1::{Governance, NetworkEconomics, Neuron};
+use ic_nns_governance::pb::v1::{Governance, NetworkEconomics, Neuron, Vote};
 use ic_nns_test_utils::itest_helpers::{set_up_governance_canister, set_up_ledger_canister};
 use ic_registry_subnet_type::SubnetType;
 use ic_rosetta_api::convert::{
     from_hex, from_model_account_identifier, neuron_account_from_public_key,
     neuron_subaccount_bytes_from_public_key, to_hex, to_model_account_identifier,
 };
+use ic_rosetta_api::models::operation::OperationType;
 use ic_rosetta_api::models::seconds::Seconds;
 use ic_rosetta_api::request::request_result::RequestResult;
 use ic_rosetta_api::request::Request;
+use ic_rosetta_api::request_handler::{GET_PENDING_PROPOSALS, GET_PROPOSAL_INFO};
 use ic_rosetta_api::request_types::{
-    AddHotKey, Disburse, Follow, MergeMaturity, NeuronInfo as NeuronInfoRequest,
-    PublicKeyOrPrincipal, RemoveHotKey, SetDissolveTimestamp, Spawn, Stake, StartDissolve, Status,
-    StopDissolve,
+    AddHotKey, Disburse, Follow, ListNeurons, MakeProposal, Merge, MergeMaturity,
+    NeuronInfo as NeuronInfoRequest, PublicKeyOrPrincipal, RegisterVote, RemoveHotKey,
+    SetDissolveTimestamp, Spawn, Split, Stake, StartDissolve, Status, StopDissolve,
 };
 use ic_rosetta_test_utils::{
     acc_id, assert_canister_error, assert_ic_error, do_multiple_txn, do_multiple_txn_external,
@@ -360,6 +362,40 @@ pub fn test_everything(handle: IcHandle, ctx: &ic_fondue::pot::Context) {
         },
     );
 
+    neuron_tests.add(
+        &mut ledger_balances,
+        "Test make proposal",
+        rand::random(),
+        |neuron| {
+            neuron.dissolve_state =
+                Some(DissolveState::DissolveDelaySeconds(2 * 365 * 24 * 60 * 60));
+        },
+    );
+
+    neuron_tests.add(
+        &mut ledger_balances,
+        "Test register vote",
+        rand::random(),
+        |neuron| {
+            neuron.dissolve_state =
+                Some(DissolveState::DissolveDelaySeconds(2 * 365 * 24 * 60 * 60));
+        },
+    );
+
+    neuron_tests.add(
+        &mut ledger_balances,
+        "Test split and merge neuron",
+        rand::random(),
+        |_| {},
+    );
+
+    neuron_tests.add(
+        &mut ledger_balances,
+        "Test list neurons",
+        rand::random(),
+        |_| {},
+    );
+
     let archive_options = ArchiveOptions {
         trigger_threshold: 8,
         num_blocks_to_archive: 4,
@@ -659,6 +695,18 @@ pub fn test_everything(handle: IcHandle, ctx: &ic_fondue::pot::Context) {
         let neuron_info = neuron_tests.get_neuron_for_test("Test follow too many");
         test_follow_too_many(&rosetta_api_serv, &ledger_for_governance, neuron_info).await;
 
+        // Proposals and voting.
+        let proposer_info = neuron_tests.get_neuron_for_test("Test make proposal");
+        let voter_info = neuron_tests.get_neuron_for_test("Test register vote");
+        test_make_proposal_and_vote(&rosetta_api_serv, proposer_info, voter_info).await;
+
+        // Split and merge.
+        let neuron_info = neuron_tests.get_neuron_for_test("Test split and merge neuron");
+        test_split_and_merge(&rosetta_api_serv, &ledger_for_governance, neuron_info).await;
+
+        let neuron_info = neuron_tests.get_neuron_for_test("Test list neurons");
+        test_list_neurons(&rosetta_api_serv, neuron_info).await;
+
         info!(&ctx.logger, "Test staking");
         let _ = test_staking(&rosetta_api_serv, acc_b, Arc::clone(&kp_b)).await;
         info!(&ctx.logger, "Test staking (raw JSON)");
@@ -3477,6 +3525,295 @@ async fn test_follow_too_many(
     assert_eq!(res.message, "Operation failed");
 }
 
+async fn test_make_proposal_and_vote(
+    ros: &RosettaApiHandle,
+    proposer_info: NeuronInfo,
+    voter_info: NeuronInfo,
+) {
+    let proposer_id = proposer_info.neuron.id.as_ref().unwrap().id;
+    let proposer_key_pair: Arc<EdKeypair> = proposer_info.key_pair.into();
+    let res = do_multiple_txn_external(
+        ros,
+        &[RequestInfo {
+            request: Request::MakeProposal(MakeProposal {
+                account: proposer_info.account_id,
+                title: Some("Rosetta motion".to_string()),
+                summary: "A motion proposal made through Rosetta.".to_string(),
+                url: "https://forum.dfinity.org".to_string(),
+                motion_text: "Make motion proposals through Rosetta.".to_string(),
+                controller: None,
+                neuron_index: proposer_info.neuron_subaccount_identifier,
+            }),
+            sender_keypair: Arc::clone(&proposer_key_pair),
+        }],
+        false,
+        Some(one_day_from_now_nanos()),
+        None,
+    )
+    .await
+    .map(|(tx_id, results, _)| {
+        assert!(!tx_id.is_transfer());
+        results
+    })
+    .expect("Failed to make a proposal");
+
+    assert_eq!(1, res.operations.len());
+    let operation = res.operations.first().unwrap();
+    assert_eq!(operation._type, OperationType::MakeProposal);
+    assert_eq!(operation.status.as_deref(), Some("COMPLETED"));
+    let proposal_id = operation
+        .metadata
+        .as_ref()
+        .and_then(|metadata| metadata.get("proposal_id"))
+        .and_then(|id| id.as_u64())
+        .expect("Proposal id expected");
+
+    // The new proposal is open.
+    let pending = ros
+        .call(GET_PENDING_PROPOSALS, Object::new())
+        .await
+        .unwrap()
+        .expect("Failed to get pending proposals");
+    let pending = pending
+        .result
+        .get("pending_proposals")
+        .and_then(|proposals| proposals.as_array())
+        .expect("Pending proposals expected");
+    assert!(pending
+        .iter()
+        .any(|p| p.get("proposal_id").and_then(|id| id.as_u64()) == Some(proposal_id)));
+
+    let info = get_proposal_info(ros, proposal_id).await;
+    assert_eq!(
+        Some(proposer_id),
+        info.get("proposer").and_then(|id| id.as_u64())
+    );
+    assert_eq!(Some(&json!("Rosetta motion")), info.get("title"));
+    assert_eq!(Some(&json!("https://forum.dfinity.org")), info.get("url"));
+    // The proposer votes yes on its own proposal.
+    let yes_before = tally_yes(&info);
+    assert!(yes_before > 0, "Expected the proposer's vote in the tally.");
+
+    let voter_key_pair: Arc<EdKeypair> = voter_info.key_pair.into();
+    let vote = Request::RegisterVote(RegisterVote {
+        account: voter_info.account_id,
+        proposal_id,
+        vote: Vote::Yes as i32,
+        controller: None,
+        neuron_index: voter_info.neuron_subaccount_identifier,
+    });
+    do_multiple_txn(
+        ros,
+        &[RequestInfo {
+            request: vote.clone(),
+            sender_keypair: Arc::clone(&voter_key_pair),
+        }],
+        false,
+        Some(one_day_from_now_nanos()),
+        None,
+    )
+    .await
+    .map(|(tx_id, results, _)| {
+        assert!(!tx_id.is_transfer());
+        assert!(matches!(
+            results.operations.first().unwrap(),
+            RequestResult {
+                _type: Request::RegisterVote(_),
+                status: Status::Completed,
+                ..
+            }
+        ));
+    })
+    .expect("Failed to register vote");
+
+    let info = get_proposal_info(ros, proposal_id).await;
+    assert!(
+        tally_yes(&info) > yes_before,
+        "Expected the vote to be counted in the tally."
+    );
+
+    // A neuron can only vote once on a proposal.
+    let res = do_multiple_txn(
+        ros,
+        &[RequestInfo {
+            request: vote,
+            sender_keypair: Arc::clone(&voter_key_pair),
+        }],
+        false,
+        Some(one_day_from_now_nanos()),
+        None,
+    )
+    .await;
+    assert!(
+        res.is_err(),
+        "Expecting an error while voting twice on the same proposal."
+    );
+
+    // The proposal id is a required parameter.
+    let res = ros.call(GET_PROPOSAL_INFO, Object::new()).await.unwrap();
+    assert!(
+        res.is_err(),
+        "Expecting an error while getting proposal info without a proposal id."
+    );
+}
+
+async fn get_proposal_info(ros: &RosettaApiHandle, proposal_id: u64) -> Object {
+    let mut parameters = Object::new();
+    parameters.insert("proposal_id".to_string(), json!(proposal_id));
+    let res = ros
+        .call(GET_PROPOSAL_INFO, parameters)
+        .await
+        .unwrap()
+        .expect("Failed to get proposal info");
+    assert_eq!(
+        Some(proposal_id),
+        res.result.get("proposal_id").and_then(|id| id.as_u64())
+    );
+    res.result
+}
+
+fn tally_yes(proposal_info: &Object) -> u64 {
+    proposal_info
+        .get("latest_tally")
+        .and_then(|tally| tally.get("yes"))
+        .and_then(|yes| yes.as_u64())
+        .expect("Tally expected")
+}
+
+async fn test_split_and_merge(
+    ros: &RosettaApiHandle,
+    ledger: &Canister<'_>,
+    neuron_info: NeuronInfo,
+) {
+    let acc = neuron_info.account_id;
+    let neuron_index = neuron_info.neuron_subaccount_identifier;
+    let key_pair: Arc<EdKeypair> = neuron_info.key_pair.into();
+    let neuron_acc = neuron_info.neuron_account;
+    let balance_before = get_balance(ledger, neuron_acc).await;
+
+    let split_amount = Tokens::new(5, 0).unwrap();
+    let res = do_multiple_txn_external(
+        ros,
+        &[RequestInfo {
+            request: Request::Split(Split {
+                account: acc,
+                amount: split_amount,
+                neuron_index,
+            }),
+            sender_keypair: Arc::clone(&key_pair),
+        }],
+        false,
+        Some(one_day_from_now_nanos()),
+        None,
+    )
+    .await
+    .map(|(tx_id, results, _)| {
+        assert!(!tx_id.is_transfer());
+        results
+    })
+    .expect("Failed to split neuron");
+
+    assert_eq!(1, res.operations.len());
+    let operation = res.operations.first().unwrap();
+    assert_eq!(operation._type, OperationType::Split);
+    assert_eq!(operation.status.as_deref(), Some("COMPLETED"));
+    // The neuron id in the result is the one of the split off neuron.
+    let split_neuron_id = operation
+        .metadata
+        .as_ref()
+        .and_then(|metadata| metadata.get("neuron_id"))
+        .and_then(|id| id.as_u64())
+        .expect("Neuron id expected");
+    assert_ne!(neuron_info.neuron.id.as_ref().unwrap().id, split_neuron_id);
+
+    let balance_after_split = get_balance(ledger, neuron_acc).await;
+    assert_eq!(
+        balance_before.get_e8s() - split_amount.get_e8s(),
+        balance_after_split.get_e8s(),
+        "Expected the split amount to leave the neuron."
+    );
+
+    // Merge the split off neuron back.
+    do_multiple_txn(
+        ros,
+        &[RequestInfo {
+            request: Request::Merge(Merge {
+                account: acc,
+                source_neuron_id: split_neuron_id,
+                neuron_index,
+            }),
+            sender_keypair: Arc::clone(&key_pair),
+        }],
+        false,
+        Some(one_day_from_now_nanos()),
+        None,
+    )
+    .await
+    .map(|(tx_id, results, _)| {
+        assert!(!tx_id.is_transfer());
+        assert!(matches!(
+            results.operations.first().unwrap(),
+            RequestResult {
+                _type: Request::Merge(_),
+                status: Status::Completed,
+                ..
+            }
+        ));
+    })
+    .expect("Failed to merge neurons");
+
+    // Both the split and the merge paid one transfer fee.
+    let expected_balance =
+        Tokens::from_e8s(balance_before.get_e8s() - 2 * DEFAULT_TRANSFER_FEE.get_e8s());
+    assert_eq!(expected_balance, get_balance(ledger, neuron_acc).await);
+
+    let (_, tip_idx) = get_tip(ledger).await;
+    ros.wait_for_tip_sync(tip_idx).await.unwrap();
+    check_balance(ros, ledger, &neuron_acc, expected_balance).await;
+}
+
+async fn test_list_neurons(ros: &RosettaApiHandle, neuron_info: NeuronInfo) {
+    let key_pair: Arc<EdKeypair> = neuron_info.key_pair.into();
+    let res = do_multiple_txn_external(
+        ros,
+        &[RequestInfo {
+            request: Request::ListNeurons(ListNeurons {
+                account: neuron_info.account_id,
+            }),
+            sender_keypair: Arc::clone(&key_pair),
+        }],
+        false,
+        Some(one_day_from_now_nanos()),
+        None,
+    )
+    .await
+    .map(|(tx_id, results, _)| {
+        assert!(!tx_id.is_transfer());
+        results
+    })
+    .expect("Failed to list neurons");
+
+    assert_eq!(1, res.operations.len());
+    let operation = res.operations.first().unwrap();
+    assert_eq!(operation._type, OperationType::ListNeurons);
+    assert_eq!(operation.status.as_deref(), Some("COMPLETED"));
+    let neurons = operation
+        .metadata
+        .as_ref()
+        .and_then(|metadata| metadata.get("neurons"))
+        .and_then(|neurons| neurons.as_array())
+        .expect("Neurons expected");
+
+    // The controller of the test neuron has no other neurons.
+    assert_eq!(1, neurons.len());
+    let neuron = &neurons[0];
+    assert_eq!(
+        Some(neuron_info.neuron.id.as_ref().unwrap().id),
+        neuron.get("neuron_id").and_then(|id| id.as_u64())
+    );
+    assert_eq!(Some(&json!("DISSOLVED")), neuron.get("state"));
+}
+
 // Create neurons to follow.
 fn create_neuron(id: u64) -> Neuron {
     let (_, _, pk, pid) = make_user(10_000 + id);
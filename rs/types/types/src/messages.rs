@@ -61,6 +61,11 @@ pub const MAX_INTER_CANISTER_PAYLOAD_IN_BYTES: NumBytes =
 pub const MAX_XNET_PAYLOAD_IN_BYTES: NumBytes =
     NumBytes::new(MAX_INTER_CANISTER_PAYLOAD_IN_BYTES_U64 * 21 / 20); // 2.1 MiB
 
+/// The maximum timeout of a best-effort inter-canister call, in seconds.
+/// Longer timeouts requested via `ic0.call_with_best_effort_response` are
+/// capped to this value.
+pub const MAX_CALL_TIMEOUT_SECONDS: u32 = 300;
+
 /// Maximum byte size of a valid inter-canister `Response`.
 pub const MAX_RESPONSE_COUNT_BYTES: usize =
     size_of::<RequestOrResponse>() + MAX_INTER_CANISTER_PAYLOAD_IN_BYTES_U64 as usize;
@@ -1,4 +1,4 @@
-use crate::{ingress::WasmResult, CanisterId, CountBytes, Cycles, Funds, NumBytes};
+use crate::{ingress::WasmResult, CanisterId, CountBytes, Cycles, Funds, NumBytes, Time};
 use ic_error_types::{RejectCode, TryFromError, UserError};
 use ic_protobuf::{
     proxy::{try_from_option_field, ProxyDecodeError},
@@ -32,6 +32,10 @@ pub struct Request {
     pub method_name: String,
     #[serde(with = "serde_bytes")]
     pub method_payload: Vec<u8>,
+    /// The deadline of a best-effort call; `None` for calls with a guaranteed
+    /// response.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<Time>,
 }
 
 impl Request {
@@ -56,6 +60,13 @@ impl Request {
         let bytes = self.method_name.len() + self.method_payload.len();
         NumBytes::from(bytes as u64)
     }
+
+    /// Returns `true` if this `Request` belongs to a best-effort call, i.e.
+    /// one that may be dropped and whose caller may be rejected with
+    /// `SYS_UNKNOWN` once the deadline has expired.
+    pub fn is_best_effort(&self) -> bool {
+        self.deadline.is_some()
+    }
 }
 
 impl std::fmt::Debug for Request {
@@ -68,6 +79,9 @@ impl std::fmt::Debug for Request {
             self.sender_reply_callback
         )?;
         write!(f, "payment: {:?}, ", self.payment)?;
+        if let Some(deadline) = self.deadline {
+            write!(f, "deadline: {:?}, ", deadline)?;
+        }
         if self.method_name.len() <= 103 {
             write!(f, "method_name: {:?}, ", self.method_name)?;
         } else {
@@ -199,6 +213,10 @@ pub struct Response {
     pub originator_reply_callback: CallbackId,
     pub refund: Cycles,
     pub response_payload: Payload,
+    /// The deadline of the best-effort call that this `Response` belongs to;
+    /// `None` for calls with a guaranteed response.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<Time>,
 }
 
 impl Response {
@@ -206,6 +224,11 @@ impl Response {
     pub fn payload_size_bytes(&self) -> NumBytes {
         self.response_payload.size_bytes()
     }
+
+    /// Returns `true` if this `Response` belongs to a best-effort call.
+    pub fn is_best_effort(&self) -> bool {
+        self.deadline.is_some()
+    }
 }
 
 /// Canister-to-canister message.
@@ -249,6 +272,15 @@ impl RequestOrResponse {
             RequestOrResponse::Response(resp) => resp.refund,
         }
     }
+
+    /// Returns the deadline of the best-effort call that this message belongs
+    /// to; `None` for calls with a guaranteed response.
+    pub fn deadline(&self) -> Option<Time> {
+        match self {
+            RequestOrResponse::Request(req) => req.deadline,
+            RequestOrResponse::Response(resp) => resp.deadline,
+        }
+    }
 }
 
 /// Convenience `CountBytes` implementation that returns the same value as
@@ -304,6 +336,7 @@ impl From<&Request> for pb_queues::Request {
             method_name: req.method_name.clone(),
             method_payload: req.method_payload.clone(),
             cycles_payment: Some((req.payment).into()),
+            deadline_nanos: req.deadline.map(|d| d.as_nanos_since_unix_epoch()),
         }
     }
 }
@@ -327,6 +360,7 @@ impl TryFrom<pb_queues::Request> for Request {
             payment,
             method_name: req.method_name,
             method_payload: req.method_payload,
+            deadline: req.deadline_nanos.map(Time::from_nanos_since_unix_epoch),
         })
     }
 }
@@ -369,6 +403,7 @@ impl From<&Response> for pb_queues::Response {
             refund: Some((&Funds::new(rep.refund)).into()),
             response_payload: Some(p),
             cycles_refund: Some((rep.refund).into()),
+            deadline_nanos: rep.deadline.map(|d| d.as_nanos_since_unix_epoch()),
         }
     }
 }
@@ -399,6 +434,7 @@ impl TryFrom<pb_queues::Response> for Response {
             originator_reply_callback: rep.originator_reply_callback.into(),
             refund,
             response_payload,
+            deadline: rep.deadline_nanos.map(Time::from_nanos_since_unix_epoch),
         })
     }
 }
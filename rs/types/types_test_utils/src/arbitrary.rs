@@ -132,6 +132,7 @@ prop_compose! {
             payment: Cycles::from(cycles_payment),
             method_name,
             method_payload,
+            deadline: None,
         }
     }
 }
@@ -165,7 +166,8 @@ prop_compose! {
             respondent,
             originator_reply_callback: CallbackId::from(callback),
             refund: Cycles::from(cycles_refund),
-            response_payload
+            response_payload,
+            deadline: None,
         }
     }
 }
@@ -178,6 +180,19 @@ pub fn request_or_response() -> impl Strategy<Value = RequestOrResponse> {
     ]
 }
 
+/// Produces an arbitrary best-effort [`RequestOrResponse`], i.e. one with a
+/// deadline.
+pub fn best_effort_request_or_response() -> impl Strategy<Value = RequestOrResponse> {
+    (request_or_response(), any::<u64>()).prop_map(|(mut msg, deadline)| {
+        let deadline = Some(Time::from_nanos_since_unix_epoch(deadline));
+        match &mut msg {
+            RequestOrResponse::Request(req) => req.deadline = deadline,
+            RequestOrResponse::Response(rep) => rep.deadline = deadline,
+        }
+        msg
+    })
+}
+
 prop_compose! {
     /// Returns an arbitrary [`StreamIndex`] in the `[0, max)` range.
     pub fn stream_index(max: u64) (
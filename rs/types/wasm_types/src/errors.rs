@@ -42,6 +42,10 @@ pub enum WasmValidationError {
     InvalidDataSection(String),
     /// Module contains an invalid custom section
     InvalidCustomSection(String),
+    /// Module contains an invalid global section
+    InvalidGlobalSection(String),
+    /// Module contains an invalid memory section
+    InvalidMemorySection(String),
     /// Module contains too many globals.
     TooManyGlobals { defined: usize, allowed: usize },
     /// Module contains too many functions.
@@ -52,6 +56,8 @@ pub enum WasmValidationError {
     InvalidFunctionIndex { index: usize, import_count: usize },
     /// A function was too complex.
     FunctionComplexityTooHigh,
+    /// Module contains an instruction whose result is not deterministic.
+    NonDeterministicInstruction(String),
 }
 
 impl std::fmt::Display for WasmValidationError {
@@ -81,6 +87,12 @@ impl std::fmt::Display for WasmValidationError {
             Self::InvalidCustomSection(err) => {
                 write!(f, "Wasm module has an invalid custom section. {}", err)
             }
+            Self::InvalidGlobalSection(err) => {
+                write!(f, "Wasm module has an invalid global section. {}", err)
+            }
+            Self::InvalidMemorySection(err) => {
+                write!(f, "Wasm module has an invalid memory section. {}", err)
+            }
             Self::TooManyGlobals { defined, allowed } => write!(
                 f,
                 "Wasm module defined {} globals which exceeds the maximum number allowed {}.",
@@ -105,6 +117,9 @@ impl std::fmt::Display for WasmValidationError {
                 index, import_count
             ),
             Self::FunctionComplexityTooHigh => write!(f, "Wasm module contains a function that is too complex"),
+            Self::NonDeterministicInstruction(err) => {
+                write!(f, "Wasm module contains a non-deterministic instruction: {}", err)
+            }
         }
     }
 }
@@ -4,10 +4,12 @@ mod errors;
 
 pub use errors::{ParityWasmError, WasmEngineError, WasmInstrumentationError, WasmValidationError};
 use ic_utils::byte_slice_fmt::truncate_and_format;
+use serde::{Deserialize, Serialize};
 use std::{
     fmt,
     path::{Path, PathBuf},
     sync::Arc,
+    time::Duration,
 };
 
 /// A newtype for
@@ -31,6 +33,26 @@ impl BinaryEncodedWasm {
     }
 }
 
+/// A Wasm module compiled and serialized by Wasmtime, as stored in the
+/// on-disk compilation cache. Sandbox processes return the modules they
+/// compiled to the replica in this form.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SerializedModule {
+    #[serde(with = "serde_bytes")]
+    pub bytes: Vec<u8>,
+    /// The time it took to compile the module.
+    pub compilation_time: Duration,
+}
+
+impl fmt::Debug for SerializedModule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SerializedModule")
+            .field("len", &self.bytes.len())
+            .field("compilation_time", &self.compilation_time)
+            .finish()
+    }
+}
+
 /// Canister module stored by the replica.
 /// Currently, we support two kinds of modules:
 ///   * Raw Wasm modules (magic number \0asm)
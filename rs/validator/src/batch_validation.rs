@@ -0,0 +1,333 @@
+use crate::ingress_validation::{validate_request, AuthenticationError, RequestValidationError};
+use ic_crypto::VerifiedDelegationCache;
+use ic_interfaces::crypto::{
+    BasicSigBatchVerifierByPublicKey, BasicSigVerifierByPublicKey, CanisterSigVerifier,
+    IngressSigVerifier,
+};
+use ic_types::{
+    crypto::{AlgorithmId, BasicSigOf, CanisterSigOf, CryptoResult, UserPublicKey},
+    malicious_flags::MaliciousFlags,
+    messages::{
+        Delegation, HasCanisterId, HttpRequest, HttpRequestContent, MessageId, WebAuthnEnvelope,
+    },
+    RegistryVersion, Time,
+};
+use std::cell::{Cell, RefCell};
+
+/// Validates a batch of requests like [`validate_request`], returning the
+/// result for each of the `requests` in the same order.
+///
+/// This is considerably faster than validating the requests one by one:
+/// * Ed25519 request signatures are verified in a single batch. Only if the
+///   batch fails are they verified one by one, to find the invalid ones.
+/// * Delegations whose verification succeeded before according to
+///   `delegation_cache` are not verified again.
+pub fn validate_requests<C: HttpRequestContent + HasCanisterId>(
+    requests: &[&HttpRequest<C>],
+    ingress_signature_verifier: &dyn IngressSigVerifier,
+    delegation_cache: &VerifiedDelegationCache,
+    current_time: Time,
+    registry_version: RegistryVersion,
+    malicious_flags: &MaliciousFlags,
+) -> Vec<Result<(), RequestValidationError>> {
+    let verifier = DeferringVerifier {
+        inner: ingress_signature_verifier,
+        delegation_cache,
+        current_request: Cell::new(0),
+        deferred: RefCell::new(Vec::new()),
+    };
+    let mut results: Vec<_> = requests
+        .iter()
+        .enumerate()
+        .map(|(index, request)| {
+            verifier.current_request.set(index);
+            validate_request(
+                request,
+                &verifier,
+                current_time,
+                registry_version,
+                malicious_flags,
+            )
+        })
+        .collect();
+
+    let deferred = verifier.deferred.into_inner();
+    let batch: Vec<_> = deferred
+        .iter()
+        .map(|sig| (&sig.signature, &sig.message_id, &sig.public_key))
+        .collect();
+    if batch.is_empty()
+        || ingress_signature_verifier
+            .verify_basic_sig_batch_by_public_key(&batch)
+            .is_ok()
+    {
+        return results;
+    }
+
+    for sig in deferred.iter() {
+        if let Err(err) = ingress_signature_verifier.verify_basic_sig_by_public_key(
+            &sig.signature,
+            &sig.message_id,
+            &sig.public_key,
+        ) {
+            // The request signature is the last thing verified, and only the
+            // delegation targets are checked after it.
+            let result = &mut results[sig.request_index];
+            if matches!(
+                result,
+                Ok(()) | Err(RequestValidationError::CanisterNotInDelegationTargets(_))
+            ) {
+                *result = Err(RequestValidationError::InvalidSignature(
+                    AuthenticationError::InvalidBasicSignature(err),
+                ));
+            }
+        }
+    }
+    results
+}
+
+/// An Ed25519 request signature whose verification is deferred to a batch.
+struct DeferredSignature {
+    request_index: usize,
+    signature: BasicSigOf<MessageId>,
+    message_id: MessageId,
+    public_key: UserPublicKey,
+}
+
+/// Verifies signatures with `inner`, except for Ed25519 request signatures,
+/// which are recorded for batch verification and reported as valid, and
+/// delegations found in `delegation_cache`.
+struct DeferringVerifier<'a> {
+    inner: &'a dyn IngressSigVerifier,
+    delegation_cache: &'a VerifiedDelegationCache,
+    current_request: Cell<usize>,
+    deferred: RefCell<Vec<DeferredSignature>>,
+}
+
+impl<'a> BasicSigVerifierByPublicKey<MessageId> for DeferringVerifier<'a> {
+    fn verify_basic_sig_by_public_key(
+        &self,
+        signature: &BasicSigOf<MessageId>,
+        signed_bytes: &MessageId,
+        public_key: &UserPublicKey,
+    ) -> CryptoResult<()> {
+        if public_key.algorithm_id != AlgorithmId::Ed25519 {
+            return self
+                .inner
+                .verify_basic_sig_by_public_key(signature, signed_bytes, public_key);
+        }
+        self.deferred.borrow_mut().push(DeferredSignature {
+            request_index: self.current_request.get(),
+            signature: signature.clone(),
+            message_id: signed_bytes.clone(),
+            public_key: public_key.clone(),
+        });
+        Ok(())
+    }
+}
+
+impl<'a> BasicSigVerifierByPublicKey<Delegation> for DeferringVerifier<'a> {
+    fn verify_basic_sig_by_public_key(
+        &self,
+        signature: &BasicSigOf<Delegation>,
+        signed_bytes: &Delegation,
+        public_key: &UserPublicKey,
+    ) -> CryptoResult<()> {
+        // The verification of a basic signature does not depend on the
+        // registry version.
+        self.delegation_cache.get_or_verify(
+            signed_bytes,
+            &signature.get_ref().0,
+            public_key,
+            RegistryVersion::from(0),
+            || {
+                self.inner
+                    .verify_basic_sig_by_public_key(signature, signed_bytes, public_key)
+            },
+        )
+    }
+}
+
+impl<'a> BasicSigVerifierByPublicKey<WebAuthnEnvelope> for DeferringVerifier<'a> {
+    fn verify_basic_sig_by_public_key(
+        &self,
+        signature: &BasicSigOf<WebAuthnEnvelope>,
+        signed_bytes: &WebAuthnEnvelope,
+        public_key: &UserPublicKey,
+    ) -> CryptoResult<()> {
+        self.inner
+            .verify_basic_sig_by_public_key(signature, signed_bytes, public_key)
+    }
+}
+
+impl<'a> BasicSigBatchVerifierByPublicKey<MessageId> for DeferringVerifier<'a> {
+    fn verify_basic_sig_batch_by_public_key(
+        &self,
+        signatures: &[(&BasicSigOf<MessageId>, &MessageId, &UserPublicKey)],
+    ) -> CryptoResult<()> {
+        self.inner.verify_basic_sig_batch_by_public_key(signatures)
+    }
+}
+
+impl<'a> CanisterSigVerifier<Delegation> for DeferringVerifier<'a> {
+    fn verify_canister_sig(
+        &self,
+        signature: &CanisterSigOf<Delegation>,
+        signed_bytes: &Delegation,
+        public_key: &UserPublicKey,
+        registry_version: RegistryVersion,
+    ) -> CryptoResult<()> {
+        self.delegation_cache.get_or_verify(
+            signed_bytes,
+            &signature.get_ref().0,
+            public_key,
+            registry_version,
+            || {
+                self.inner.verify_canister_sig(
+                    signature,
+                    signed_bytes,
+                    public_key,
+                    registry_version,
+                )
+            },
+        )
+    }
+}
+
+impl<'a> CanisterSigVerifier<MessageId> for DeferringVerifier<'a> {
+    fn verify_canister_sig(
+        &self,
+        signature: &CanisterSigOf<MessageId>,
+        signed_bytes: &MessageId,
+        public_key: &UserPublicKey,
+        registry_version: RegistryVersion,
+    ) -> CryptoResult<()> {
+        self.inner
+            .verify_canister_sig(signature, signed_bytes, public_key, registry_version)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+    use ic_test_utilities::{
+        crypto::temp_crypto_component_with_fake_registry,
+        types::{
+            ids::{canister_test_id, node_test_id},
+            messages::SignedIngressBuilder,
+        },
+    };
+    use ic_types::{messages::SignedIngress, time::current_time};
+
+    fn validate(requests: &[SignedIngress]) -> Vec<Result<(), RequestValidationError>> {
+        let requests: Vec<_> = requests.iter().map(|request| request.as_ref()).collect();
+        validate_requests(
+            &requests,
+            &temp_crypto_component_with_fake_registry(node_test_id(0)),
+            &VerifiedDelegationCache::default(),
+            current_time(),
+            RegistryVersion::from(0),
+            &MaliciousFlags::default(),
+        )
+    }
+
+    fn signed_request(nonce: u64) -> SignedIngress {
+        SignedIngressBuilder::new()
+            .canister_id(canister_test_id(1))
+            .nonce(nonce)
+            .sign_for_randomly_generated_sender()
+            .build()
+    }
+
+    /// A request whose signature does not match its content.
+    fn request_with_invalid_signature(nonce: u64) -> SignedIngress {
+        SignedIngressBuilder::new()
+            .canister_id(canister_test_id(1))
+            .nonce(nonce)
+            .sign_for_randomly_generated_sender()
+            .nonce(nonce + 1)
+            .build()
+    }
+
+    #[test]
+    fn should_validate_empty_batch() {
+        assert!(validate(&[]).is_empty());
+    }
+
+    #[test]
+    fn should_validate_batch_of_signed_and_anonymous_requests() {
+        let requests = vec![
+            signed_request(1),
+            SignedIngressBuilder::new().nonce(2).build(),
+            signed_request(3),
+        ];
+        let results = validate(&requests);
+        assert_eq!(results.len(), 3);
+        for result in results {
+            assert_matches!(result, Ok(()));
+        }
+    }
+
+    #[test]
+    fn should_pinpoint_invalid_signatures_in_batch() {
+        let requests = vec![
+            signed_request(1),
+            request_with_invalid_signature(2),
+            signed_request(4),
+            request_with_invalid_signature(5),
+        ];
+        let results = validate(&requests);
+        assert_matches!(results[0], Ok(()));
+        assert_matches!(
+            results[1],
+            Err(RequestValidationError::InvalidSignature(
+                AuthenticationError::InvalidBasicSignature(_)
+            ))
+        );
+        assert_matches!(results[2], Ok(()));
+        assert_matches!(
+            results[3],
+            Err(RequestValidationError::InvalidSignature(
+                AuthenticationError::InvalidBasicSignature(_)
+            ))
+        );
+    }
+
+    #[test]
+    fn should_agree_with_individual_validation() {
+        let requests = vec![
+            signed_request(1),
+            request_with_invalid_signature(2),
+            SignedIngressBuilder::new()
+                .expiry_time(current_time())
+                .sign_for_randomly_generated_sender()
+                .build(),
+        ];
+        let verifier = temp_crypto_component_with_fake_registry(node_test_id(0));
+        let time = current_time();
+        let batch_results = validate_requests(
+            &requests.iter().map(|r| r.as_ref()).collect::<Vec<_>>(),
+            &verifier,
+            &VerifiedDelegationCache::default(),
+            time,
+            RegistryVersion::from(0),
+            &MaliciousFlags::default(),
+        );
+        for (request, batch_result) in requests.iter().zip(batch_results) {
+            let result = validate_request(
+                request.as_ref(),
+                &verifier,
+                time,
+                RegistryVersion::from(0),
+                &MaliciousFlags::default(),
+            );
+            assert_eq!(
+                format!("{:?}", result),
+                format!("{:?}", batch_result),
+                "Batch validation disagrees for request {}",
+                request.id()
+            );
+        }
+    }
+}
@@ -4,9 +4,14 @@
 //!  * The request hasn't expired relative to `current_time`.
 //!  * The delegations haven't expired relative to `current_time`.
 //!  * The signatures are corrrect.
+//!
+//! Batches of requests can be validated more efficiently with
+//! [`validate_requests`].
+mod batch_validation;
 mod ingress_validation;
 mod webauthn;
 
+pub use batch_validation::validate_requests;
 pub use ingress_validation::{
     get_authorized_canisters, validate_request, AuthenticationError, CanisterIdSet,
     RequestValidationError,
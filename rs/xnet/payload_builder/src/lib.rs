@@ -311,6 +311,30 @@ impl XNetPayloadBuilderImpl {
         }
     }
 
+    /// Creates an `XNetPayloadBuilderImpl` from an externally managed slice
+    /// pool, e.g. for deterministic tests that fill the pool synchronously
+    /// instead of relying on a `PoolRefillTask` querying `XNetEndpoints`.
+    pub fn new_from_components(
+        state_manager: Arc<dyn StateManager<State = ReplicatedState>>,
+        certified_stream_store: Arc<dyn CertifiedStreamStore>,
+        registry: Arc<dyn RegistryClient>,
+        slice_pool: Arc<Mutex<CertifiedSlicePool>>,
+        refill_task_handle: RefillTaskHandle,
+        metrics: Arc<XNetPayloadBuilderMetrics>,
+        log: ReplicaLogger,
+    ) -> XNetPayloadBuilderImpl {
+        Self {
+            state_manager,
+            certified_stream_store,
+            registry,
+            slice_pool,
+            refill_task_handle,
+            count_bytes_fn: certified_slice_count_bytes,
+            metrics,
+            log,
+        }
+    }
+
     /// Testing only: replaces the function to be used for calculating
     /// `CertifiedStreamSlice` byte sizes with the provided one.
     #[doc(hidden)]
@@ -1222,7 +1246,7 @@ impl PoolRefillTask {
 
 /// A handle for a `PoolRefillTask`to be used for triggering pool refills and
 /// terminating the task (by dropping the handle).
-pub struct RefillTaskHandle(Mutex<mpsc::Sender<()>>);
+pub struct RefillTaskHandle(pub Mutex<mpsc::Sender<()>>);
 
 impl RefillTaskHandle {
     /// Triggers a slice pool refill.